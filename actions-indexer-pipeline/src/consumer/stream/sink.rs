@@ -73,7 +73,10 @@ impl SubstreamsStreamProvider {
         let raw_actions = actions
             .actions
             .iter()
-            .map(|action| ActionRaw::try_from(action))
+            .enumerate()
+            .map(|(index, action)| {
+                ActionRaw::try_from(action).map(|raw| ActionRaw { action_index: index as u32, ..raw })
+            })
             .collect::<Result<Vec<ActionRaw>, ConsumerError>>()?;
 
         Ok(raw_actions)
@@ -376,6 +379,8 @@ impl TryFrom<&Action> for ActionRaw {
             tx_hash: action.tx_hash.parse()
                 .map_err(|e| ConsumerError::InvalidTxHash(format!("tx_hash: {}", e)))?,
             object_type: 0,
+            // Only known from the action's position in its block
+            action_index: 0,
         })
     }
 }
\ No newline at end of file
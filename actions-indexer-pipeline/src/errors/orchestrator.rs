@@ -5,6 +5,7 @@ use crate::errors::consumer::ConsumerError;
 use actions_indexer_repository::errors::ActionsRepositoryError;
 use actions_indexer_repository::errors::CursorRepositoryError;
 use crate::errors::loader::LoaderError;
+use crate::errors::processor::ProcessorError;
 
 /// Represents errors that can occur within the action orchestrator.
 ///
@@ -20,4 +21,6 @@ pub enum OrchestratorError {
     CursorRepository(#[from] CursorRepositoryError),
     #[error("Loader error: {0}")]
     Loader(#[from] LoaderError),
+    #[error("Processor error: {0}")]
+    Processor(#[from] ProcessorError),
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
 //! Error types for the processor module of the Actions Indexer Pipeline.
 //! Defines specific errors that can occur during the processing of action events.
 use thiserror::Error;
+use actions_indexer_repository::errors::ActionsRepositoryError;
 
 /// Represents errors that can occur within the action processor.
 ///
@@ -10,4 +11,6 @@ use thiserror::Error;
 pub enum ProcessorError {
     #[error("Invalid vote")]
     InvalidVote,
+    #[error("Actions repository error: {0}")]
+    ActionsRepository(#[from] ActionsRepositoryError),
 }
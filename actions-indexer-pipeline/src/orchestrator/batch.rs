@@ -0,0 +1,272 @@
+//! Batching of blocks in the orchestrator.
+//!
+//! The orchestrator can accumulate the actions of several blocks and persist
+//! them as one changeset, which amortizes the transaction overhead of
+//! `persist_changeset` when actions arrive in bursts.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use actions_indexer_shared::types::{Action, UserActivity, Vote};
+use tokio::time::Instant;
+
+use crate::processor::filters::FilteredActions;
+
+/// When the orchestrator persists the blocks it has accumulated.
+///
+/// Without a window every block with actions is persisted on its own, as
+/// soon as it arrives, which is the default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Batching {
+    /// Persists a batch once it holds at least this many actions.
+    pub max_actions: usize,
+    /// Persists a batch once this long has passed since its first action
+    /// arrived, even if it holds fewer than `max_actions` actions.
+    pub window: Option<Duration>,
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Self {
+            max_actions: 1,
+            window: None,
+        }
+    }
+}
+
+/// The blocks accumulated since the last persisted batch.
+///
+/// A batch tracks the cursor of the last block it accumulated, including
+/// blocks without actions, so the cursor is only saved once the actions of
+/// the blocks before it are persisted.
+#[derive(Debug, Default)]
+pub(crate) struct Batch {
+    pub(crate) actions: Vec<Action>,
+    pub(crate) votes: Vec<Vote>,
+    pub(crate) user_activity: Vec<UserActivity>,
+    pub(crate) blocks: u64,
+    pub(crate) cursor: Option<(String, i64)>,
+    started: Option<Instant>,
+}
+
+impl Batch {
+    /// Adds a block's filtered actions and activity feed entries. Blocks
+    /// without a cursor keep the cursor of the block before them.
+    pub(crate) fn push_block(
+        &mut self,
+        filtered: FilteredActions,
+        user_activity: Vec<UserActivity>,
+        cursor: String,
+        block_number: i64,
+    ) {
+        if self.started.is_none() && !filtered.actions.is_empty() {
+            self.started = Some(Instant::now());
+        }
+        self.actions.extend(filtered.actions);
+        self.votes.extend(filtered.votes);
+        self.user_activity.extend(user_activity);
+        self.blocks += 1;
+        if !cursor.is_empty() {
+            self.cursor = Some((cursor, block_number));
+        }
+    }
+
+    /// When the batch has to be persisted by, if it holds any actions and
+    /// batching has a window.
+    pub(crate) fn deadline(&self, batching: &Batching) -> Option<Instant> {
+        Some(self.started? + batching.window?)
+    }
+
+    /// Whether the batch should be persisted now. Batches without actions
+    /// only carry a cursor, which is saved right away.
+    pub(crate) fn is_ready(&self, batching: &Batching) -> bool {
+        batching.window.is_none()
+            || self.actions.is_empty()
+            || self.actions.len() >= batching.max_actions
+            || self.deadline(batching).is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// How long the batch's first action has waited to be persisted.
+    pub(crate) fn waited(&self) -> Duration {
+        self.started.map(|started| started.elapsed()).unwrap_or_default()
+    }
+}
+
+/// Counters of the batches the orchestrator persisted.
+///
+/// The counters are shared with the orchestrator while it runs, so they can be
+/// read from another task, through [`BatchMetrics::snapshot`].
+#[derive(Debug, Default)]
+pub struct BatchMetrics {
+    batches: AtomicU64,
+    blocks: AtomicU64,
+    actions: AtomicU64,
+    failed_batches: AtomicU64,
+    persist_millis: AtomicU64,
+    last_batch_actions: AtomicU64,
+}
+
+/// The values of the [`BatchMetrics`] counters at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BatchMetricsSnapshot {
+    /// The number of batches persisted.
+    pub batches: u64,
+    /// The number of blocks in the persisted batches.
+    pub blocks: u64,
+    /// The number of actions in the persisted batches.
+    pub actions: u64,
+    /// The number of batches that failed to persist.
+    pub failed_batches: u64,
+    /// The total time spent persisting batches, in milliseconds.
+    pub persist_millis: u64,
+    /// The number of actions in the last persisted batch.
+    pub last_batch_actions: u64,
+}
+
+impl BatchMetrics {
+    pub(crate) fn record_persisted(&self, blocks: u64, actions: u64, took: Duration) {
+        self.batches.fetch_add(1, Ordering::Relaxed);
+        self.blocks.fetch_add(blocks, Ordering::Relaxed);
+        self.actions.fetch_add(actions, Ordering::Relaxed);
+        self.persist_millis.fetch_add(took.as_millis() as u64, Ordering::Relaxed);
+        self.last_batch_actions.store(actions, Ordering::Relaxed);
+    }
+
+    pub(crate) fn record_failed(&self) {
+        self.failed_batches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> BatchMetricsSnapshot {
+        BatchMetricsSnapshot {
+            batches: self.batches.load(Ordering::Relaxed),
+            blocks: self.blocks.load(Ordering::Relaxed),
+            actions: self.actions.load(Ordering::Relaxed),
+            failed_batches: self.failed_batches.load(Ordering::Relaxed),
+            persist_millis: self.persist_millis.load(Ordering::Relaxed),
+            last_batch_actions: self.last_batch_actions.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actions_indexer_shared::types::{ActionRaw, VoteValue};
+    use alloy::hex::FromHex;
+    use alloy::primitives::{Address, TxHash};
+    use uuid::uuid;
+
+    fn make_action() -> Action {
+        Action::Vote(Vote {
+            raw: ActionRaw {
+                action_type: 0,
+                action_version: 1,
+                sender: Address::from_hex("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap(),
+                entity: uuid!("a7ef0016-a2f4-44fb-82ca-a4f5c61d2cf5"),
+                group_id: None,
+                space_pov: uuid!("e50fe85c-108a-4d4a-97b9-376a1e5d318b"),
+                metadata: None,
+                block_number: 1,
+                block_timestamp: 1,
+                tx_hash: TxHash::from_hex(
+                    "0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4",
+                )
+                .unwrap(),
+                object_type: 0,
+                action_index: 0,
+            },
+            vote: VoteValue::Up,
+        })
+    }
+
+    fn accepted(count: usize) -> FilteredActions {
+        let actions: Vec<Action> = (0..count).map(|_| make_action()).collect();
+        let votes = actions
+            .iter()
+            .map(|action| match action {
+                Action::Vote(vote) => vote.clone(),
+            })
+            .collect();
+        FilteredActions { actions, votes }
+    }
+
+    fn windowed(max_actions: usize) -> Batching {
+        Batching {
+            max_actions,
+            window: Some(Duration::from_secs(1)),
+        }
+    }
+
+    #[test]
+    fn test_every_block_is_ready_without_a_window() {
+        let mut batch = Batch::default();
+        batch.push_block(accepted(1), Vec::new(), "a".to_string(), 1);
+
+        assert!(batch.is_ready(&Batching::default()));
+        assert_eq!(batch.deadline(&Batching::default()), None);
+    }
+
+    #[test]
+    fn test_batch_is_ready_once_full() {
+        let batching = windowed(3);
+        let mut batch = Batch::default();
+
+        batch.push_block(accepted(2), Vec::new(), "a".to_string(), 1);
+        assert!(!batch.is_ready(&batching));
+
+        batch.push_block(FilteredActions::default(), Vec::new(), "b".to_string(), 2);
+        assert!(!batch.is_ready(&batching));
+
+        batch.push_block(accepted(1), Vec::new(), "c".to_string(), 3);
+        assert!(batch.is_ready(&batching));
+        assert_eq!(batch.blocks, 3);
+        assert_eq!(batch.cursor, Some(("c".to_string(), 3)));
+
+        batch.push_block(FilteredActions::default(), Vec::new(), String::new(), 4);
+        assert_eq!(batch.cursor, Some(("c".to_string(), 3)));
+    }
+
+    #[test]
+    fn test_batch_without_actions_is_ready() {
+        let mut batch = Batch::default();
+        batch.push_block(FilteredActions::default(), Vec::new(), "a".to_string(), 1);
+
+        assert!(batch.is_ready(&windowed(100)));
+        assert_eq!(batch.deadline(&windowed(100)), None);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_batch_is_ready_once_its_window_passes() {
+        let batching = windowed(100);
+        let mut batch = Batch::default();
+        batch.push_block(FilteredActions::default(), Vec::new(), "a".to_string(), 1);
+        let started = Instant::now();
+        batch.push_block(accepted(1), Vec::new(), "b".to_string(), 2);
+
+        // The window starts with the first action, not the first block
+        assert_eq!(batch.deadline(&batching), Some(started + Duration::from_secs(1)));
+        assert!(!batch.is_ready(&batching));
+
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert!(batch.is_ready(&batching));
+    }
+
+    #[test]
+    fn test_metrics_snapshot() {
+        let metrics = BatchMetrics::default();
+        metrics.record_persisted(2, 10, Duration::from_millis(5));
+        metrics.record_persisted(1, 4, Duration::from_millis(3));
+        metrics.record_failed();
+
+        assert_eq!(
+            metrics.snapshot(),
+            BatchMetricsSnapshot {
+                batches: 2,
+                blocks: 3,
+                actions: 14,
+                failed_batches: 1,
+                persist_millis: 8,
+                last_batch_actions: 4,
+            }
+        );
+    }
+}
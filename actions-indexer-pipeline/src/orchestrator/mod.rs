@@ -4,13 +4,25 @@
 //! flow of action events from ingestion to persistence.
 use crate::errors::OrchestratorError;
 use crate::consumer::{ActionsConsumer, StreamMessage};
-use crate::processor::{ActionsProcessor, ProcessActions};
+use crate::processor::{ActionFilters, ActionsProcessor, ActivityProcessor, ProcessActions};
+use crate::processor::filters::FilterMetrics;
 use crate::loader::ActionsLoader;
-use actions_indexer_shared::types::{Action, Changeset, UserVote, Vote, VoteCriteria, VoteCountCriteria, VoteValue, VotesCount};
+use actions_indexer_shared::types::{Changeset, UserVote, Vote, VoteCriteria, VoteCountCriteria, VoteValue, VotesCount};
 use tokio::sync::mpsc;
 use std::collections::HashMap;
+use std::sync::Arc;
 use actions_indexer_repository::{ActionsRepository, CursorRepository};
 
+mod batch;
+mod vote_cache;
+
+/// The id the orchestrator saves its cursor under, unless set otherwise.
+pub const DEFAULT_CURSOR_ID: &str = "actions_indexer";
+
+use batch::Batch;
+pub use batch::{BatchMetrics, BatchMetricsSnapshot, Batching};
+pub use vote_cache::VoteCache;
+
 /// `Orchestrator` is responsible for coordinating the consumption, processing,
 /// and loading of actions.
 ///
@@ -20,6 +32,11 @@ pub struct Orchestrator {
     pub actions_consumer: Box<ActionsConsumer>,
     pub actions_processor: Box<ActionsProcessor>,
     pub actions_loader: Box<ActionsLoader>,
+    batching: Batching,
+    metrics: Arc<BatchMetrics>,
+    filters: ActionFilters,
+    vote_cache: Arc<VoteCache>,
+    cursor_id: String,
 }
 
 impl Orchestrator {
@@ -43,9 +60,99 @@ impl Orchestrator {
             actions_consumer,
             actions_processor,
             actions_loader,
+            batching: Batching::default(),
+            metrics: Arc::new(BatchMetrics::default()),
+            filters: ActionFilters::default(),
+            vote_cache: Arc::new(VoteCache::default()),
+            cursor_id: DEFAULT_CURSOR_ID.to_string(),
         }
     }
 
+    /// Sets when the orchestrator persists the blocks it has accumulated.
+    ///
+    /// By default every block with actions is persisted as soon as it
+    /// arrives.
+    ///
+    /// # Arguments
+    ///
+    /// * `batching` - The batch size and window to accumulate blocks for
+    ///
+    /// # Returns
+    ///
+    /// The `Orchestrator` with the batching set.
+    pub fn with_batching(mut self, batching: Batching) -> Self {
+        self.batching = batching;
+        self
+    }
+
+    /// Returns the counters of the batches the orchestrator persists, which
+    /// keep updating while it runs.
+    pub fn metrics(&self) -> Arc<BatchMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Sets the filters that flag or drop suspicious actions before their
+    /// votes are counted.
+    ///
+    /// By default every action is accepted.
+    ///
+    /// # Arguments
+    ///
+    /// * `filters` - The filters to apply to the actions of every block
+    ///
+    /// # Returns
+    ///
+    /// The `Orchestrator` with the filters set.
+    pub fn with_filters(mut self, filters: ActionFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
+    /// Returns the counters of the actions the filters checked, which keep
+    /// updating while the orchestrator runs.
+    pub fn filter_metrics(&self) -> Arc<FilterMetrics> {
+        self.filters.metrics()
+    }
+
+    /// Sets the cache of the stored votes new votes are counted against.
+    ///
+    /// By default every batch reads them from the actions repository.
+    ///
+    /// # Arguments
+    ///
+    /// * `vote_cache` - The cache to read stored votes through
+    ///
+    /// # Returns
+    ///
+    /// The `Orchestrator` with the vote cache set.
+    pub fn with_vote_cache(mut self, vote_cache: VoteCache) -> Self {
+        self.vote_cache = Arc::new(vote_cache);
+        self
+    }
+
+    /// Returns the vote cache, whose hit and miss counters keep updating
+    /// while the orchestrator runs.
+    pub fn vote_cache(&self) -> Arc<VoteCache> {
+        self.vote_cache.clone()
+    }
+
+    /// Sets the id the orchestrator saves and resumes its cursor under.
+    ///
+    /// Backfills use their own id, so they don't move the cursor of the
+    /// indexer following the chain.
+    ///
+    /// # Arguments
+    ///
+    /// * `cursor_id` - The id of the cursor
+    ///
+    /// # Returns
+    ///
+    /// The `Orchestrator` with the cursor id set.
+    pub fn with_cursor_id(mut self, cursor_id: impl Into<String>) -> Self {
+        self.cursor_id = cursor_id.into();
+        self
+    }
+
     /// Runs the orchestrator, initiating the action processing pipeline.
     ///
     /// This method is the main entry point for starting the continuous flow of
@@ -62,6 +169,12 @@ impl Orchestrator {
         let consumer = self.actions_consumer;
         let processor = self.actions_processor;
         let loader = self.actions_loader;
+        let batching = self.batching;
+        let metrics = self.metrics;
+        let filters = self.filters;
+        let vote_cache = self.vote_cache;
+        let cursor_id = self.cursor_id;
+        let activity_processor = ActivityProcessor::new();
 
         // Wait until the tables are created
         loop {
@@ -73,54 +186,54 @@ impl Orchestrator {
         }
 
         // Get the cursor from the database
-        let cursor = loader.cursor_repository.get_cursor("actions_indexer").await.map_err(OrchestratorError::from)?;
+        let cursor = loader.cursor_repository.get_cursor(&cursor_id).await.map_err(OrchestratorError::from)?;
         
         tokio::spawn(async move {
             if let Err(e) = consumer.run(consumer_tx, cursor).await {
                 eprintln!("Consumer error: {:?}", e);
             }
         });
-        
-        while let Some(message) = rx.recv().await {
+
+        let mut batch = Batch::default();
+
+        loop {
+            // Wait for the next block only until the pending batch's window
+            // closes, so a quiet stream doesn't hold back its actions
+            let message = match batch.deadline(&batching) {
+                Some(deadline) => match tokio::time::timeout_at(deadline, rx.recv()).await {
+                    Ok(message) => message,
+                    Err(_) => {
+                        persist_batch(std::mem::take(&mut batch), &loader, &metrics, &vote_cache, &cursor_id).await?;
+                        continue;
+                    }
+                },
+                None => rx.recv().await,
+            };
+
+            let Some(message) = message else {
+                break;
+            };
+
             match message {
                 StreamMessage::BlockData(block_data) => {
-                    let actions = block_data.actions;
-                    let cursor = block_data.cursor;
-                    let block_number = block_data.block_number;
-
-                    if actions.len() > 0 {
-                        let now = chrono::Utc::now();
-                        println!("{} - Processing {} actions", now.to_rfc3339(), actions.len());
-                        
-                        let actions = processor.process(&actions);
-                        
-                        let mut votes: Vec<Vote> = Vec::new();
-                        for action in actions.clone() {
-                            match action {
-                                Action::Vote(vote) => votes.push(vote),
-                            }
-                        }
-                        
-                        let user_votes = get_latest_user_votes(&votes);
-                        let votes_count = update_vote_counts(&user_votes, loader.actions_repository.as_ref()).await?;
-
-                        let changeset = Changeset { 
-                            actions: &actions,  
-                            user_votes: &user_votes,
-                            votes_count: &votes_count,
-                        };
-
-                        if let Err(e) = loader.persist_changeset(&changeset).await {
-                            eprintln!("Failed to persist changeset: {:?}", e);
-                        } else {
-                            save_cursor(&cursor, &block_number, loader.cursor_repository.as_ref()).await?;
-                        }
+                    let actions = if block_data.actions.is_empty() {
+                        Vec::new()
                     } else {
-                        if !cursor.is_empty() {
-                            save_cursor(&cursor, &block_number, loader.cursor_repository.as_ref()).await?;
-                        }
-                    }
+                        let now = chrono::Utc::now();
+                        println!("{} - Processing {} actions", now.to_rfc3339(), block_data.actions.len());
+                        processor.process(&block_data.actions)
+                    };
+
+                    let filtered = filters.apply(actions).await?;
+
+                    // Activity is numbered per block, so it's processed before
+                    // the block joins the batch
+                    let user_activity = activity_processor.process(&filtered.actions);
+                    batch.push_block(filtered, user_activity, block_data.cursor, block_data.block_number);
 
+                    if batch.is_ready(&batching) {
+                        persist_batch(std::mem::take(&mut batch), &loader, &metrics, &vote_cache, &cursor_id).await?;
+                    }
                 }
                 StreamMessage::UndoSignal(undo_signal) => {
                     println!("UndoSignal: {:?}", undo_signal);
@@ -129,14 +242,89 @@ impl Orchestrator {
                     println!("Error: {:?}", error);
                 }
                 StreamMessage::StreamEnd => {
+                    // Streams only end at their stop block
                     println!("StreamEnd");
+                    break;
                 }
             }   
         }
+
+        if batch.blocks > 0 {
+            persist_batch(batch, &loader, &metrics, &vote_cache, &cursor_id).await?;
+        }
         Ok(())
     }
 }
 
+/// Persists the actions of a batch as one changeset, and then saves the
+/// cursor of its last block.
+///
+/// As with single blocks, a batch that fails to persist is logged and
+/// skipped without saving its cursor.
+///
+/// # Arguments
+///
+/// * `batch` - The `Batch` to persist
+/// * `loader` - The `ActionsLoader` to persist it with
+/// * `metrics` - The `BatchMetrics` to record it in
+/// * `vote_cache` - The `VoteCache` to read stored votes through
+/// * `cursor_id` - The id to save the cursor under
+///
+/// # Returns
+///
+/// A `Result` indicating success or an `OrchestratorError` if the vote counts
+/// couldn't be read or the cursor couldn't be saved.
+async fn persist_batch(batch: Batch, loader: &ActionsLoader, metrics: &BatchMetrics, vote_cache: &VoteCache, cursor_id: &str) -> Result<(), OrchestratorError> {
+    if batch.actions.is_empty() {
+        if let Some((cursor, block_number)) = &batch.cursor {
+            save_cursor(cursor_id, cursor, block_number, loader.cursor_repository.as_ref()).await?;
+        }
+        return Ok(());
+    }
+
+    let waited = batch.waited();
+    let started = std::time::Instant::now();
+
+    let user_votes = get_latest_user_votes(&batch.votes);
+    let votes_count = update_vote_counts(&user_votes, vote_cache, loader.actions_repository.as_ref()).await?;
+
+    let changeset = Changeset { 
+        actions: &batch.actions,  
+        user_votes: &user_votes,
+        votes_count: &votes_count,
+        user_activity: &batch.user_activity,
+    };
+
+    if let Err(e) = loader.persist_changeset(&changeset).await {
+        metrics.record_failed();
+        vote_cache.invalidate(&user_votes);
+        eprintln!("Failed to persist changeset: {:?}", e);
+        return Ok(());
+    }
+
+    vote_cache.write(&user_votes, &votes_count);
+
+    if let Some((cursor, block_number)) = &batch.cursor {
+        save_cursor(cursor_id, cursor, block_number, loader.cursor_repository.as_ref()).await?;
+    }
+
+    let took = started.elapsed();
+    metrics.record_persisted(batch.blocks, batch.actions.len() as u64, took);
+
+    if batch.blocks > 1 {
+        println!(
+            "{} - Persisted {} actions from {} blocks in {}ms, after waiting {}ms",
+            chrono::Utc::now().to_rfc3339(),
+            batch.actions.len(),
+            batch.blocks,
+            took.as_millis(),
+            waited.as_millis(),
+        );
+    }
+
+    Ok(())
+}
+
 #[derive(Debug)]
 struct VotesDelta {
     upvotes: i32,
@@ -187,13 +375,14 @@ fn get_latest_user_votes(votes: &[Vote]) -> Vec<UserVote> {
 /// # Arguments
 ///
 /// * `user_votes` - A slice of `UserVote`s to process
+/// * `vote_cache` - A reference to the `VoteCache` to read stored votes through
 /// * `actions_repository` - A reference to the `ActionsRepository` to use
 ///
 /// # Returns
 ///
 /// A vector of `VotesCount`s with the updated vote counts for each entity/space combination.
 ///
-async fn update_vote_counts(user_votes: &[UserVote], actions_repository: &dyn ActionsRepository) -> Result<Vec<VotesCount>, OrchestratorError> {
+async fn update_vote_counts(user_votes: &[UserVote], vote_cache: &VoteCache, actions_repository: &dyn ActionsRepository) -> Result<Vec<VotesCount>, OrchestratorError> {
     if user_votes.is_empty() {
         return Ok(Vec::new());
     }
@@ -207,8 +396,8 @@ async fn update_vote_counts(user_votes: &[UserVote], actions_repository: &dyn Ac
         .collect();
 
     let (stored_user_votes, stored_vote_counts) = tokio::try_join!(
-        actions_repository.get_user_votes(&vote_criteria),
-        actions_repository.get_vote_counts(&vote_count_criteria)
+        vote_cache.get_user_votes(&vote_criteria, actions_repository),
+        vote_cache.get_vote_counts(&vote_count_criteria, actions_repository)
     )?;
 
     let stored_user_votes_map: HashMap<VoteCriteria, UserVote> = stored_user_votes
@@ -243,6 +432,12 @@ async fn update_vote_counts(user_votes: &[UserVote], actions_repository: &dyn Ac
 }
 
 fn compute_vote_delta(saved_vote: &Option<&UserVote>, new_vote: &UserVote) -> VotesDelta {
+    // Votes older than the saved vote come from blocks processed again, and
+    // are already counted
+    if saved_vote.is_some_and(|vote| vote.voted_at > new_vote.voted_at) {
+        return VotesDelta { upvotes: 0, downvotes: 0 };
+    }
+
     let saved_vote_value = saved_vote.map(|vote| vote.vote_type.clone());
     let new_vote_value = new_vote.vote_type.clone();
 
@@ -261,8 +456,8 @@ fn compute_vote_delta(saved_vote: &Option<&UserVote>, new_vote: &UserVote) -> Vo
     VotesDelta { upvotes, downvotes }
 }
 
-async fn save_cursor(cursor: &str, block_number: &i64, cursor_repository: &dyn CursorRepository) -> Result<(), OrchestratorError> {
-    if let Err(e) = cursor_repository.save_cursor("actions_indexer", cursor, block_number).await {
+async fn save_cursor(id: &str, cursor: &str, block_number: &i64, cursor_repository: &dyn CursorRepository) -> Result<(), OrchestratorError> {
+    if let Err(e) = cursor_repository.save_cursor(id, cursor, block_number).await {
         eprintln!("Failed to save cursor to database: {:?}", e);
         return Err(OrchestratorError::from(e));
     }
@@ -303,6 +498,27 @@ mod tests {
         assert_eq!(votes_changes.downvotes, 1);
     }
 
+    #[tokio::test]
+    async fn test_calculate_votes_changes_older_vote() {
+        let prev_vote = UserVote {
+            user_id: dead_address(),
+            entity_id: uuid!("a7ef0016-a2f4-44fb-82ca-a4f5c61d2cf5"),
+            space_id: uuid!("e50fe85c-108a-4d4a-97b9-376a1e5d318b"),
+            vote_type: VoteValue::Up,
+            voted_at: 1713859200,
+        };
+
+        let old_vote = UserVote {
+            vote_type: VoteValue::Down,
+            voted_at: 1713859100,
+            ..prev_vote.clone()
+        };
+
+        let votes_changes = compute_vote_delta(&Some(&prev_vote), &old_vote);
+        assert_eq!(votes_changes.upvotes, 0);
+        assert_eq!(votes_changes.downvotes, 0);
+    }
+
     #[tokio::test]
     async fn test_calculate_votes_changes_upvote_remove() {
         let prev_vote = UserVote {
@@ -393,6 +609,7 @@ mod tests {
             block_timestamp: 1713859200,
             tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
             object_type: 0,
+            action_index: 0,
         };
 
         let vote = Vote {
@@ -435,6 +652,7 @@ mod tests {
             block_timestamp: 1713859200,
             tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
             object_type: 0,
+            action_index: 0,
         };
 
         // First vote (older)
@@ -490,6 +708,7 @@ mod tests {
                 block_timestamp: 1713859200,
                 tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Up,
         };
@@ -507,6 +726,7 @@ mod tests {
                 block_timestamp: 1713859300,
                 tx_hash: TxHash::from_hex("0x6538dbff9d04388e9ac36264cf493b8c96e05421e59ead18b6e6547bc3d72fc5").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Down,
         };
@@ -550,6 +770,7 @@ mod tests {
                 block_timestamp: 1713859200,
                 tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Up,
         };
@@ -567,6 +788,7 @@ mod tests {
                 block_timestamp: 1713859300,
                 tx_hash: TxHash::from_hex("0x6538dbff9d04388e9ac36264cf493b8c96e05421e59ead18b6e6547bc3d72fc5").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Remove,
         };
@@ -611,6 +833,7 @@ mod tests {
                 block_timestamp: 1713859200,
                 tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Up,
         };
@@ -628,6 +851,7 @@ mod tests {
                 block_timestamp: 1713859300,
                 tx_hash: TxHash::from_hex("0x6538dbff9d04388e9ac36264cf493b8c96e05421e59ead18b6e6547bc3d72fc5").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Down,
         };
@@ -645,6 +869,7 @@ mod tests {
                 block_timestamp: 1713859400,
                 tx_hash: TxHash::from_hex("0x7649ec009e05499f9bd47274ef4e73a6f7b24126f79ead19c6e6648cd4e83af6").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Remove,
         };
@@ -688,6 +913,7 @@ mod tests {
                 block_timestamp: 1713859200,
                 tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Up,
         };
@@ -705,6 +931,7 @@ mod tests {
                 block_timestamp: 1713859300,
                 tx_hash: TxHash::from_hex("0x6538dbff9d04388e9ac36264cf493b8c96e05421e59ead18b6e6547bc3d72fc5").unwrap(),
                 object_type: 0,
+                action_index: 0,
             },
             vote: VoteValue::Down,
         };
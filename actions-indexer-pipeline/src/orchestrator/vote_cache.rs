@@ -0,0 +1,430 @@
+//! Caching of the stored votes the orchestrator counts new votes against.
+//!
+//! Every batch reads the stored vote of each user it has a vote of and the
+//! stored counts of each entity it has votes for. During bursts of votes on
+//! the same content those are the same few rows, so the orchestrator keeps
+//! the rows it read and wrote in memory instead of reading them again.
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use actions_indexer_repository::{ActionsRepository, ActionsRepositoryError};
+use actions_indexer_shared::types::{UserVote, VoteCountCriteria, VoteCriteria, VotesCount};
+use lru::LruCache;
+
+/// `VoteCache` keeps the most recently used user votes and vote counts.
+///
+/// The cache assumes the orchestrator is the only writer of the votes, so
+/// entries are never refreshed from storage. They're replaced with what the
+/// orchestrator persists, and dropped if persisting fails. Rows that aren't
+/// stored are cached as missing, so new voters aren't read again either.
+///
+/// A cache without capacity, which is the default, caches nothing and reads
+/// every vote from the repository.
+#[derive(Debug, Default)]
+pub struct VoteCache {
+    entries: Option<Mutex<Entries>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug)]
+struct Entries {
+    user_votes: LruCache<VoteCriteria, Option<UserVote>>,
+    vote_counts: LruCache<VoteCountCriteria, Option<VotesCount>>,
+}
+
+impl VoteCache {
+    /// Creates a new `VoteCache` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `capacity` - How many user votes, and separately how many vote
+    ///   counts, to keep. Nothing is cached if it's 0.
+    ///
+    /// # Returns
+    ///
+    /// A new `VoteCache` instance.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: NonZeroUsize::new(capacity).map(|capacity| {
+                Mutex::new(Entries {
+                    user_votes: LruCache::new(capacity),
+                    vote_counts: LruCache::new(capacity),
+                })
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// The number of user votes and vote counts read from the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of user votes and vote counts read from the repository.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Returns the stored votes matching the criteria, reading the ones that
+    /// aren't cached from the repository.
+    ///
+    /// # Arguments
+    ///
+    /// * `vote_criteria` - The user, entity and space of the votes to read
+    /// * `actions_repository` - The repository to read uncached votes from
+    ///
+    /// # Returns
+    ///
+    /// A `Result` with the stored votes, or an `ActionsRepositoryError` if
+    /// the uncached votes couldn't be read.
+    pub async fn get_user_votes(
+        &self,
+        vote_criteria: &[VoteCriteria],
+        actions_repository: &dyn ActionsRepository,
+    ) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+        let Some(entries) = &self.entries else {
+            return actions_repository.get_user_votes(vote_criteria).await;
+        };
+
+        let (mut user_votes, missing) = {
+            let mut entries = entries.lock().unwrap();
+            partition(vote_criteria, |criteria| entries.user_votes.get(criteria).cloned())
+        };
+        self.record(vote_criteria.len(), missing.len());
+        if missing.is_empty() {
+            return Ok(user_votes);
+        }
+
+        let stored = actions_repository.get_user_votes(&missing).await?;
+
+        let mut entries = entries.lock().unwrap();
+        for criteria in missing {
+            entries.user_votes.put(criteria, None);
+        }
+        for vote in stored {
+            entries
+                .user_votes
+                .put((vote.user_id, vote.entity_id, vote.space_id), Some(vote.clone()));
+            user_votes.push(vote);
+        }
+
+        Ok(user_votes)
+    }
+
+    /// Returns the stored vote counts matching the criteria, reading the ones
+    /// that aren't cached from the repository.
+    ///
+    /// # Arguments
+    ///
+    /// * `vote_criteria` - The entity and space of the vote counts to read
+    /// * `actions_repository` - The repository to read uncached counts from
+    ///
+    /// # Returns
+    ///
+    /// A `Result` with the stored vote counts, or an `ActionsRepositoryError`
+    /// if the uncached counts couldn't be read.
+    pub async fn get_vote_counts(
+        &self,
+        vote_criteria: &[VoteCountCriteria],
+        actions_repository: &dyn ActionsRepository,
+    ) -> Result<Vec<VotesCount>, ActionsRepositoryError> {
+        let Some(entries) = &self.entries else {
+            return actions_repository.get_vote_counts(vote_criteria).await;
+        };
+
+        let (mut vote_counts, missing) = {
+            let mut entries = entries.lock().unwrap();
+            partition(vote_criteria, |criteria| entries.vote_counts.get(criteria).cloned())
+        };
+        self.record(vote_criteria.len(), missing.len());
+        if missing.is_empty() {
+            return Ok(vote_counts);
+        }
+
+        let stored = actions_repository.get_vote_counts(&missing).await?;
+
+        let mut entries = entries.lock().unwrap();
+        for criteria in missing {
+            entries.vote_counts.put(criteria, None);
+        }
+        for count in stored {
+            entries
+                .vote_counts
+                .put((count.entity_id, count.space_id), Some(count.clone()));
+            vote_counts.push(count);
+        }
+
+        Ok(vote_counts)
+    }
+
+    /// Updates the cache with the votes and counts the orchestrator persisted.
+    ///
+    /// As in storage, a cached vote is only replaced by a vote that isn't
+    /// older. Votes that aren't cached are left to be read from storage.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_votes` - The user votes that were persisted
+    /// * `vote_counts` - The vote counts that were persisted
+    pub fn write(&self, user_votes: &[UserVote], vote_counts: &[VotesCount]) {
+        let Some(entries) = &self.entries else {
+            return;
+        };
+        let mut entries = entries.lock().unwrap();
+
+        for vote in user_votes {
+            if let Some(cached) = entries.user_votes.get_mut(&(vote.user_id, vote.entity_id, vote.space_id)) {
+                if cached.as_ref().is_none_or(|cached| cached.voted_at <= vote.voted_at) {
+                    *cached = Some(vote.clone());
+                }
+            }
+        }
+        for count in vote_counts {
+            entries.vote_counts.put((count.entity_id, count.space_id), Some(count.clone()));
+        }
+    }
+
+    /// Drops the cached votes and counts of votes that failed to persist, so
+    /// they're read from storage again.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_votes` - The user votes that failed to persist
+    pub fn invalidate(&self, user_votes: &[UserVote]) {
+        let Some(entries) = &self.entries else {
+            return;
+        };
+        let mut entries = entries.lock().unwrap();
+
+        for vote in user_votes {
+            entries.user_votes.pop(&(vote.user_id, vote.entity_id, vote.space_id));
+            entries.vote_counts.pop(&(vote.entity_id, vote.space_id));
+        }
+    }
+
+    fn record(&self, read: usize, missing: usize) {
+        self.hits.fetch_add((read - missing) as u64, Ordering::Relaxed);
+        self.misses.fetch_add(missing as u64, Ordering::Relaxed);
+    }
+}
+
+/// Splits criteria into the stored rows cached for them and the criteria
+/// that aren't cached. Criteria cached as missing are in neither.
+fn partition<K: Clone, V>(
+    criteria: &[K],
+    mut cached: impl FnMut(&K) -> Option<Option<V>>,
+) -> (Vec<V>, Vec<K>) {
+    let mut found = Vec::new();
+    let mut missing = Vec::new();
+
+    for criteria in criteria {
+        match cached(criteria) {
+            Some(Some(row)) => found.push(row),
+            Some(None) => {}
+            None => missing.push(criteria.clone()),
+        }
+    }
+
+    (found, missing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actions_indexer_shared::types::{
+        Action, ActivityCursor, Changeset, EntityId, SpaceId, UserActivity, UserAddress, VoteValue,
+    };
+    use alloy::hex::FromHex;
+    use alloy::primitives::Address;
+    use std::collections::HashMap;
+    use uuid::uuid;
+
+    /// Stores a fixed set of votes and counts how many rows it was asked
+    /// about.
+    #[derive(Default)]
+    struct CountingRepository {
+        user_votes: Vec<UserVote>,
+        vote_counts: Vec<VotesCount>,
+        asked: Mutex<usize>,
+    }
+
+    impl CountingRepository {
+        fn asked(&self) -> usize {
+            *self.asked.lock().unwrap()
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl ActionsRepository for CountingRepository {
+        async fn insert_actions(&self, _: &[Action]) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn update_user_votes(&self, _: &[UserVote]) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn update_votes_counts(&self, _: &[VotesCount]) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn persist_changeset(&self, _: &Changeset<'_>) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_user_votes(&self, vote_criteria: &[VoteCriteria]) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+            *self.asked.lock().unwrap() += vote_criteria.len();
+            Ok(self
+                .user_votes
+                .iter()
+                .filter(|vote| vote_criteria.contains(&(vote.user_id, vote.entity_id, vote.space_id)))
+                .cloned()
+                .collect())
+        }
+        async fn get_vote_counts(&self, vote_criteria: &[VoteCountCriteria]) -> Result<Vec<VotesCount>, ActionsRepositoryError> {
+            *self.asked.lock().unwrap() += vote_criteria.len();
+            Ok(self
+                .vote_counts
+                .iter()
+                .filter(|count| vote_criteria.contains(&(count.entity_id, count.space_id)))
+                .cloned()
+                .collect())
+        }
+        async fn get_votes_by_entity(&self, _: EntityId, _: Option<SpaceId>, _: i64, _: i64) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_votes_by_user(&self, _: UserAddress, _: Option<SpaceId>, _: i64, _: i64) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_top_voted_entities(&self, _: SpaceId, _: i64) -> Result<Vec<VotesCount>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_user_activity(&self, _: UserAddress, _: Option<ActivityCursor>, _: i64) -> Result<Vec<UserActivity>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_first_activity(&self, _: &[UserAddress]) -> Result<HashMap<UserAddress, u64>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn check_tables_created(&self) -> Result<bool, ActionsRepositoryError> {
+            unimplemented!()
+        }
+    }
+
+    fn make_user_vote(user: &str, vote_type: VoteValue, voted_at: u64) -> UserVote {
+        UserVote {
+            user_id: Address::from_hex(user).unwrap(),
+            entity_id: uuid!("a7ef0016-a2f4-44fb-82ca-a4f5c61d2cf5"),
+            space_id: uuid!("e50fe85c-108a-4d4a-97b9-376a1e5d318b"),
+            vote_type,
+            voted_at,
+        }
+    }
+
+    fn make_votes_count(upvotes: i64) -> VotesCount {
+        VotesCount {
+            entity_id: uuid!("a7ef0016-a2f4-44fb-82ca-a4f5c61d2cf5"),
+            space_id: uuid!("e50fe85c-108a-4d4a-97b9-376a1e5d318b"),
+            upvotes,
+            downvotes: 0,
+        }
+    }
+
+    fn criteria(vote: &UserVote) -> VoteCriteria {
+        (vote.user_id, vote.entity_id, vote.space_id)
+    }
+
+    const ALICE: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+    const BOB: &str = "0x1234567890123456789012345678901234567890";
+
+    #[tokio::test]
+    async fn test_votes_are_read_once() {
+        let alice_vote = make_user_vote(ALICE, VoteValue::Up, 100);
+        let bob_vote = make_user_vote(BOB, VoteValue::Up, 100);
+        let repository = CountingRepository {
+            user_votes: vec![alice_vote.clone()],
+            vote_counts: vec![make_votes_count(1)],
+            ..Default::default()
+        };
+        let cache = VoteCache::new(10);
+        let vote_criteria = [criteria(&alice_vote), criteria(&bob_vote)];
+        let count_criteria = [(alice_vote.entity_id, alice_vote.space_id)];
+
+        for _ in 0..2 {
+            // Bob has no stored vote, which is cached too
+            let user_votes = cache.get_user_votes(&vote_criteria, &repository).await.unwrap();
+            assert_eq!(user_votes, vec![alice_vote.clone()]);
+
+            let vote_counts = cache.get_vote_counts(&count_criteria, &repository).await.unwrap();
+            assert_eq!(vote_counts, vec![make_votes_count(1)]);
+        }
+
+        assert_eq!(repository.asked(), 3);
+        assert_eq!((cache.hits(), cache.misses()), (3, 3));
+    }
+
+    #[tokio::test]
+    async fn test_write_updates_cached_votes() {
+        let alice_vote = make_user_vote(ALICE, VoteValue::Up, 100);
+        let repository = CountingRepository {
+            user_votes: vec![alice_vote.clone()],
+            ..Default::default()
+        };
+        let cache = VoteCache::new(10);
+        let count_criteria = [(alice_vote.entity_id, alice_vote.space_id)];
+
+        cache.get_user_votes(&[criteria(&alice_vote)], &repository).await.unwrap();
+
+        let newer_vote = make_user_vote(ALICE, VoteValue::Down, 200);
+        cache.write(&[newer_vote.clone()], &[make_votes_count(0)]);
+        // Older votes don't replace the stored vote
+        cache.write(&[make_user_vote(ALICE, VoteValue::Remove, 150)], &[]);
+
+        let user_votes = cache.get_user_votes(&[criteria(&alice_vote)], &repository).await.unwrap();
+        assert_eq!(user_votes, vec![newer_vote]);
+        let vote_counts = cache.get_vote_counts(&count_criteria, &repository).await.unwrap();
+        assert_eq!(vote_counts, vec![make_votes_count(0)]);
+
+        // Votes that weren't cached are read from storage
+        let bob_vote = make_user_vote(BOB, VoteValue::Up, 100);
+        cache.write(&[bob_vote.clone()], &[]);
+        cache.get_user_votes(&[criteria(&bob_vote)], &repository).await.unwrap();
+        assert_eq!(repository.asked(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_invalidate_drops_cached_votes() {
+        let alice_vote = make_user_vote(ALICE, VoteValue::Up, 100);
+        let repository = CountingRepository {
+            user_votes: vec![alice_vote.clone()],
+            vote_counts: vec![make_votes_count(1)],
+            ..Default::default()
+        };
+        let cache = VoteCache::new(10);
+        let count_criteria = [(alice_vote.entity_id, alice_vote.space_id)];
+
+        cache.get_user_votes(&[criteria(&alice_vote)], &repository).await.unwrap();
+        cache.get_vote_counts(&count_criteria, &repository).await.unwrap();
+        cache.invalidate(&[make_user_vote(ALICE, VoteValue::Down, 200)]);
+
+        let user_votes = cache.get_user_votes(&[criteria(&alice_vote)], &repository).await.unwrap();
+        assert_eq!(user_votes, vec![alice_vote]);
+        let vote_counts = cache.get_vote_counts(&count_criteria, &repository).await.unwrap();
+        assert_eq!(vote_counts, vec![make_votes_count(1)]);
+        assert_eq!(repository.asked(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_cache_without_capacity_reads_every_vote() {
+        let alice_vote = make_user_vote(ALICE, VoteValue::Up, 100);
+        let repository = CountingRepository {
+            user_votes: vec![alice_vote.clone()],
+            ..Default::default()
+        };
+        let cache = VoteCache::default();
+
+        cache.get_user_votes(&[criteria(&alice_vote)], &repository).await.unwrap();
+        cache.write(&[alice_vote.clone()], &[]);
+        cache.get_user_votes(&[criteria(&alice_vote)], &repository).await.unwrap();
+
+        assert_eq!(repository.asked(), 2);
+    }
+}
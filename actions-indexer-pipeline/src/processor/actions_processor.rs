@@ -5,6 +5,10 @@ use actions_indexer_shared::types::{Action, ActionRaw, ActionType, ActionVersion
 
 /// `ActionsProcessor` is responsible for processing raw `ActionEvent` data into structured `Action` data.
 /// It manages a registry of handlers for different action versions and kinds.
+///
+/// Actions are routed to the handler registered for their exact version, so a
+/// new payload format gets its own handler while actions emitted with older
+/// versions keep being decoded by the handlers they were written for.
 pub struct ActionsProcessor {
     handler_registry: HashMap<(ActionVersion, ActionType, ObjectType), Arc<dyn HandleAction>>,
 }
@@ -29,6 +33,45 @@ impl ActionsProcessor {
     pub fn register_handler(&mut self, version: ActionVersion, kind: ActionType, object_type: ObjectType, handler: Arc<dyn HandleAction>) {
         self.handler_registry.insert((version, kind, object_type), handler);
     }
+
+    /// Registers the same handler for several versions of an action, for
+    /// versions that didn't change its payload format.
+    ///
+    /// # Arguments
+    ///
+    /// * `versions` - The versions of the action to register the handler for.
+    /// * `kind` - The kind of the action to register the handler for.
+    /// * `object_type` - The type of object the action applies to.
+    /// * `handler` - The handler decoding the action's payload.
+    pub fn register_versions(
+        &mut self,
+        versions: impl IntoIterator<Item = ActionVersion>,
+        kind: ActionType,
+        object_type: ObjectType,
+        handler: Arc<dyn HandleAction>,
+    ) {
+        for version in versions {
+            self.register_handler(version, kind, object_type, handler.clone());
+        }
+    }
+
+    /// Returns the versions of an action that have a handler, in ascending
+    /// order.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of the action.
+    /// * `object_type` - The type of object the action applies to.
+    pub fn supported_versions(&self, kind: ActionType, object_type: ObjectType) -> Vec<ActionVersion> {
+        let mut versions: Vec<ActionVersion> = self
+            .handler_registry
+            .keys()
+            .filter(|(_, k, o)| *k == kind && *o == object_type)
+            .map(|(version, _, _)| *version)
+            .collect();
+        versions.sort_unstable();
+        versions
+    }
 }
 
 impl ProcessActions for ActionsProcessor {
@@ -55,7 +98,15 @@ impl ProcessActions for ActionsProcessor {
                     println!("Error processing action: {:?}", action);
                 }
             } else {
-                println!("No handler found for action: {:?}", action);
+                let supported = self.supported_versions(action.action_type, action.object_type);
+                if supported.is_empty() {
+                    println!("No handler found for action: {:?}", action);
+                } else {
+                    println!(
+                        "Unsupported version {} of action type {}, supported versions are {:?}: {:?}",
+                        action.action_version, action.action_type, supported, action
+                    );
+                }
             }
         }
         results
@@ -105,6 +156,7 @@ mod tests {
             )
             .unwrap(),
             object_type: 0,
+            action_index: 0,
         }
     }
 
@@ -205,8 +257,71 @@ mod tests {
             )
             .unwrap(),
             object_type: 0,
+            action_index: 0,
         };
         let result = processor.process(&[action_event.clone()]);
         assert!(result.len() == 0); // no actions were processed
     }
+
+    /// Decodes a version 2 vote, whose payload has a leading flags byte.
+    struct MockHandlerV2;
+
+    impl HandleAction for MockHandlerV2 {
+        fn handle(&self, action: &ActionRaw) -> Result<Action, ProcessorError> {
+            Ok(Action::Vote(Vote {
+                raw: action.clone(),
+                vote: match action.metadata.as_ref().unwrap()[1] {
+                    0 => VoteValue::Up,
+                    1 => VoteValue::Down,
+                    2 => VoteValue::Remove,
+                    _ => return Err(ProcessorError::InvalidVote),
+                },
+            }))
+        }
+    }
+
+    #[test]
+    fn test_process_routes_actions_by_version() {
+        let mut processor = mocked_processor();
+        processor.register_handler(2, 0, 0, Arc::new(MockHandlerV2));
+
+        let v1 = make_action_event(1);
+        let v2 = ActionRaw {
+            action_version: 2,
+            metadata: Some(Bytes::from(vec![0xff, 0])),
+            ..make_action_event(0)
+        };
+
+        let result = processor.process(&[v1.clone(), v2.clone()]);
+        assert_eq!(result.len(), 2);
+        assert_is_vote_action(&result[0], &v1, Vote {
+            raw: v1.clone(),
+            vote: VoteValue::Down,
+        });
+        assert_is_vote_action(&result[1], &v2, Vote {
+            raw: v2.clone(),
+            vote: VoteValue::Up,
+        });
+    }
+
+    #[test]
+    fn test_process_unsupported_version() {
+        let processor = mocked_processor();
+        let action_event = ActionRaw {
+            action_version: 3,
+            ..make_action_event(0)
+        };
+        assert!(processor.process(&[action_event]).is_empty());
+    }
+
+    #[test]
+    fn test_supported_versions() {
+        let mut processor = mocked_processor();
+        processor.register_versions([3, 2], 0, 0, Arc::new(MockHandlerV2));
+        processor.register_handler(1, 1, 0, Arc::new(MockHandler));
+
+        assert_eq!(processor.supported_versions(0, 0), vec![1, 2, 3]);
+        assert_eq!(processor.supported_versions(1, 0), vec![1]);
+        assert!(processor.supported_versions(0, 1).is_empty());
+    }
 }
@@ -0,0 +1,105 @@
+use actions_indexer_shared::types::{Action, ActivityKind, UserActivity};
+
+/// `ActivityProcessor` derives the entries of users' activity feeds from the
+/// processed actions of a block.
+///
+/// Every action becomes an entry in the feed of the user who sent it. Feeds
+/// are written next to the actions, so profile pages can read a user's
+/// activity without scanning `raw_actions`.
+#[derive(Default)]
+pub struct ActivityProcessor;
+
+impl ActivityProcessor {
+    /// Creates a new `ActivityProcessor` instance.
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Maps the processed actions of a block to activity feed entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `actions` - The block's processed actions. Entries are keyed by the
+    ///   index of their action among the actions emitted in the block, so the
+    ///   same block always yields the same keys, even if some of its actions
+    ///   weren't processed or were dropped.
+    ///
+    /// # Returns
+    ///
+    /// A `Vec<UserActivity>` with an entry per action.
+    pub fn process(&self, actions: &[Action]) -> Vec<UserActivity> {
+        actions
+            .iter()
+            .map(|action| match action {
+                Action::Vote(vote) => UserActivity {
+                    user_id: vote.raw.sender,
+                    block_number: vote.raw.block_number,
+                    action_index: vote.raw.action_index,
+                    kind: ActivityKind::Vote(vote.vote.clone()),
+                    entity_id: vote.raw.entity,
+                    space_id: vote.raw.space_pov,
+                    tx_hash: vote.raw.tx_hash,
+                    occurred_at: vote.raw.block_timestamp,
+                },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actions_indexer_shared::types::{ActionRaw, Vote, VoteValue};
+    use alloy::hex::FromHex;
+    use alloy::primitives::{Address, TxHash};
+    use uuid::uuid;
+
+    fn make_vote(sender: &str, vote: VoteValue, action_index: u32) -> Action {
+        Action::Vote(Vote {
+            raw: ActionRaw {
+                action_type: 0,
+                action_version: 1,
+                sender: Address::from_hex(sender).unwrap(),
+                entity: uuid!("a7ef0016-a2f4-44fb-82ca-a4f5c61d2cf5"),
+                group_id: None,
+                space_pov: uuid!("e50fe85c-108a-4d4a-97b9-376a1e5d318b"),
+                metadata: None,
+                block_number: 7,
+                block_timestamp: 1755182913,
+                tx_hash: TxHash::from_hex(
+                    "0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4",
+                )
+                .unwrap(),
+                object_type: 0,
+                action_index,
+            },
+            vote,
+        })
+    }
+
+    #[test]
+    fn test_process_votes_into_their_senders_feeds() {
+        let actions = vec![
+            make_vote("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", VoteValue::Up, 0),
+            // The action emitted between them wasn't processed
+            make_vote("0x1234567890123456789012345678901234567890", VoteValue::Remove, 2),
+        ];
+
+        let activity = ActivityProcessor::new().process(&actions);
+
+        assert_eq!(activity.len(), 2);
+        for (entry, action) in activity.iter().zip(&actions) {
+            let Action::Vote(vote) = action;
+            assert_eq!(entry.user_id, vote.raw.sender);
+            assert_eq!(entry.kind, ActivityKind::Vote(vote.vote.clone()));
+            assert_eq!(entry.entity_id, vote.raw.entity);
+            assert_eq!(entry.space_id, vote.raw.space_pov);
+            assert_eq!((entry.block_number, entry.action_index), (7, vote.raw.action_index));
+        }
+    }
+
+    #[test]
+    fn test_process_no_actions() {
+        assert!(ActivityProcessor::new().process(&[]).is_empty());
+    }
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use actions_indexer_repository::ActionsRepository;
+use actions_indexer_shared::types::{Action, UserAddress};
+
+use super::{sender, sent_at, FilterActions, Verdict};
+use crate::errors::ProcessorError;
+
+/// `AccountAgeFilter` flags the actions of senders who sent their first
+/// action less than a minimum age before.
+///
+/// A sender's first action is read from their activity feed the first time
+/// the filter sees them, and kept in memory from then on. Senders without any
+/// activity are first seen with the action being checked, so their age starts
+/// from their first action, even if it's flagged.
+pub struct AccountAgeFilter {
+    actions_repository: Arc<dyn ActionsRepository>,
+    min_age: u64,
+    first_seen: Mutex<HashMap<UserAddress, u64>>,
+}
+
+impl AccountAgeFilter {
+    /// Creates a new `AccountAgeFilter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `actions_repository` - The repository to read senders' first actions from.
+    /// * `min_age` - How long before an action its sender must have sent their
+    ///   first action for it to count.
+    pub fn new(actions_repository: Arc<dyn ActionsRepository>, min_age: Duration) -> Self {
+        Self {
+            actions_repository,
+            min_age: min_age.as_secs(),
+            first_seen: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl FilterActions for AccountAgeFilter {
+    fn name(&self) -> &'static str {
+        "account_age"
+    }
+
+    async fn check(&self, actions: &[Action]) -> Result<Vec<Verdict>, ProcessorError> {
+        let mut unknown: Vec<UserAddress> = {
+            let first_seen = self.first_seen.lock().unwrap();
+            actions
+                .iter()
+                .map(sender)
+                .filter(|sender| !first_seen.contains_key(sender))
+                .collect()
+        };
+        unknown.sort();
+        unknown.dedup();
+
+        let known = self.actions_repository.get_first_activity(&unknown).await?;
+
+        let mut first_seen = self.first_seen.lock().unwrap();
+        first_seen.extend(known);
+
+        Ok(actions
+            .iter()
+            .map(|action| {
+                let at = sent_at(action);
+                let first = *first_seen.entry(sender(action)).or_insert(at);
+                if at.saturating_sub(first) < self.min_age {
+                    Verdict::Flag
+                } else {
+                    Verdict::Accept
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::filters::tests::make_vote;
+    use actions_indexer_repository::ActionsRepositoryError;
+    use actions_indexer_shared::types::{
+        ActivityCursor, Changeset, EntityId, SpaceId, UserActivity, UserVote, VoteCountCriteria,
+        VoteCriteria, VotesCount,
+    };
+
+    const ALICE: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+    const BOB: &str = "0x1234567890123456789012345678901234567890";
+
+    /// Knows the first action of a fixed set of senders, and counts how many
+    /// senders it was asked about.
+    struct FirstActivityRepository {
+        first_activity: HashMap<UserAddress, u64>,
+        asked: Mutex<usize>,
+    }
+
+    #[async_trait::async_trait]
+    impl ActionsRepository for FirstActivityRepository {
+        async fn insert_actions(&self, _: &[Action]) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn update_user_votes(&self, _: &[UserVote]) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn update_votes_counts(&self, _: &[VotesCount]) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn persist_changeset(&self, _: &Changeset<'_>) -> Result<(), ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_user_votes(&self, _: &[VoteCriteria]) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_vote_counts(&self, _: &[VoteCountCriteria]) -> Result<Vec<VotesCount>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_votes_by_entity(&self, _: EntityId, _: Option<SpaceId>, _: i64, _: i64) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_votes_by_user(&self, _: UserAddress, _: Option<SpaceId>, _: i64, _: i64) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_top_voted_entities(&self, _: SpaceId, _: i64) -> Result<Vec<VotesCount>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_user_activity(&self, _: UserAddress, _: Option<ActivityCursor>, _: i64) -> Result<Vec<UserActivity>, ActionsRepositoryError> {
+            unimplemented!()
+        }
+        async fn get_first_activity(&self, user_ids: &[UserAddress]) -> Result<HashMap<UserAddress, u64>, ActionsRepositoryError> {
+            *self.asked.lock().unwrap() += user_ids.len();
+            Ok(user_ids
+                .iter()
+                .filter_map(|user_id| Some((*user_id, *self.first_activity.get(user_id)?)))
+                .collect())
+        }
+        async fn check_tables_created(&self) -> Result<bool, ActionsRepositoryError> {
+            unimplemented!()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_young_senders_are_flagged() {
+        let alice = sender(&make_vote(ALICE, 0));
+        let repository = Arc::new(FirstActivityRepository {
+            first_activity: HashMap::from([(alice, 1_000)]),
+            asked: Mutex::new(0),
+        });
+        let filter = AccountAgeFilter::new(repository.clone(), Duration::from_secs(86_400));
+
+        // Bob has no activity, so Bob is first seen with this vote
+        let verdicts = filter
+            .check(&[make_vote(ALICE, 90_000), make_vote(BOB, 90_000), make_vote(ALICE, 90_100)])
+            .await
+            .unwrap();
+        assert_eq!(verdicts, vec![Verdict::Accept, Verdict::Flag, Verdict::Accept]);
+
+        let verdicts = filter
+            .check(&[make_vote(BOB, 176_399), make_vote(BOB, 176_400)])
+            .await
+            .unwrap();
+        assert_eq!(verdicts, vec![Verdict::Flag, Verdict::Accept]);
+
+        // Both senders were only looked up once
+        assert_eq!(*repository.asked.lock().unwrap(), 2);
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashSet;
+
+use actions_indexer_shared::types::{Action, UserAddress};
+
+use super::{sender, FilterActions, Verdict};
+use crate::errors::ProcessorError;
+
+/// `DenylistFilter` drops every action sent by a denied address.
+pub struct DenylistFilter {
+    senders: HashSet<UserAddress>,
+}
+
+impl DenylistFilter {
+    /// Creates a new `DenylistFilter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `senders` - The addresses whose actions are dropped.
+    pub fn new(senders: impl IntoIterator<Item = UserAddress>) -> Self {
+        Self {
+            senders: senders.into_iter().collect(),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl FilterActions for DenylistFilter {
+    fn name(&self) -> &'static str {
+        "denylist"
+    }
+
+    async fn check(&self, actions: &[Action]) -> Result<Vec<Verdict>, ProcessorError> {
+        Ok(actions
+            .iter()
+            .map(|action| {
+                if self.senders.contains(&sender(action)) {
+                    Verdict::Drop
+                } else {
+                    Verdict::Accept
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::filters::tests::make_vote;
+
+    #[tokio::test]
+    async fn test_denied_senders_are_dropped() {
+        let denied = make_vote("0x1234567890123456789012345678901234567890", 1);
+        let filter = DenylistFilter::new([sender(&denied)]);
+
+        let verdicts = filter
+            .check(&[make_vote("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045", 1), denied])
+            .await
+            .unwrap();
+
+        assert_eq!(verdicts, vec![Verdict::Accept, Verdict::Drop]);
+    }
+}
@@ -0,0 +1,318 @@
+//! Filters that hold back suspicious actions before they affect vote counts.
+//!
+//! Every filter returns a [`Verdict`] for each action of a block, and an
+//! action gets the strictest verdict of all filters. Flagged actions are
+//! still recorded in `raw_actions` and their senders' activity feeds, but
+//! their votes don't count towards `user_votes` and `votes_count`. Dropped
+//! actions aren't recorded at all.
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use actions_indexer_shared::types::{Action, UserAddress, Vote};
+
+use crate::errors::ProcessorError;
+
+mod account_age;
+mod denylist;
+mod rate_limit;
+
+pub use account_age::AccountAgeFilter;
+pub use denylist::DenylistFilter;
+pub use rate_limit::RateLimitFilter;
+
+/// What a filter decided about an action, from the least to the most strict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verdict {
+    /// The action is recorded and its vote counts.
+    Accept,
+    /// The action is recorded, but its vote doesn't count.
+    Flag,
+    /// The action is neither recorded nor counted.
+    Drop,
+}
+
+/// Defines the interface for deciding whether actions look like spam.
+///
+/// Filters see every action of a block in order, including the actions other
+/// filters flag or drop, so filters keeping track of senders see all of their
+/// activity.
+#[async_trait::async_trait]
+pub trait FilterActions: Send + Sync {
+    /// The name the filter's verdicts are counted under in the metrics.
+    fn name(&self) -> &'static str;
+
+    /// Decides what happens to each action of a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `actions` - The processed actions of a block.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing a `Verdict` per action, in the same order, or a
+    /// `ProcessorError` if the filter couldn't decide.
+    async fn check(&self, actions: &[Action]) -> Result<Vec<Verdict>, ProcessorError>;
+}
+
+/// The actions of a block that passed the filters.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FilteredActions {
+    /// The accepted and flagged actions, which are recorded.
+    pub actions: Vec<Action>,
+    /// The votes of the accepted actions, which count.
+    pub votes: Vec<Vote>,
+}
+
+/// The filters the orchestrator applies to the actions of every block.
+///
+/// Without filters every action is accepted, which is the default.
+#[derive(Default)]
+pub struct ActionFilters {
+    filters: Vec<Arc<dyn FilterActions>>,
+    metrics: Arc<FilterMetrics>,
+}
+
+impl ActionFilters {
+    /// Creates an `ActionFilters` instance without filters.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a filter, applied after the filters added before it.
+    ///
+    /// # Arguments
+    ///
+    /// * `filter` - The filter to add.
+    ///
+    /// # Returns
+    ///
+    /// The `ActionFilters` with the filter added.
+    pub fn with_filter(mut self, filter: Arc<dyn FilterActions>) -> Self {
+        self.filters.push(filter);
+        self
+    }
+
+    /// Returns the counters of the actions the filters checked, which keep
+    /// updating while they're applied.
+    pub fn metrics(&self) -> Arc<FilterMetrics> {
+        self.metrics.clone()
+    }
+
+    /// Applies every filter to the processed actions of a block.
+    ///
+    /// # Arguments
+    ///
+    /// * `actions` - The processed actions of a block.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `FilteredActions` of the block, or a
+    /// `ProcessorError` if a filter failed.
+    pub async fn apply(&self, actions: Vec<Action>) -> Result<FilteredActions, ProcessorError> {
+        let mut verdicts = vec![Verdict::Accept; actions.len()];
+        for filter in &self.filters {
+            let filter_verdicts = filter.check(&actions).await?;
+            for (verdict, filter_verdict) in verdicts.iter_mut().zip(filter_verdicts) {
+                if filter_verdict != Verdict::Accept {
+                    self.metrics.record_verdict(filter.name(), filter_verdict);
+                }
+                *verdict = (*verdict).max(filter_verdict);
+            }
+        }
+
+        let mut filtered = FilteredActions::default();
+        for (action, verdict) in actions.into_iter().zip(verdicts) {
+            self.metrics.record_checked(verdict);
+            match verdict {
+                Verdict::Accept => {
+                    match &action {
+                        Action::Vote(vote) => filtered.votes.push(vote.clone()),
+                    }
+                    filtered.actions.push(action);
+                }
+                Verdict::Flag => filtered.actions.push(action),
+                Verdict::Drop => {}
+            }
+        }
+        Ok(filtered)
+    }
+}
+
+/// Returns the address that sent an action.
+fn sender(action: &Action) -> UserAddress {
+    match action {
+        Action::Vote(vote) => vote.raw.sender,
+    }
+}
+
+/// Returns the timestamp of the block an action was sent in.
+fn sent_at(action: &Action) -> u64 {
+    match action {
+        Action::Vote(vote) => vote.raw.block_timestamp,
+    }
+}
+
+/// Counters of the actions the filters checked.
+///
+/// The counters are shared with the orchestrator while it runs, so they can be
+/// read from another task, through [`FilterMetrics::snapshot`].
+#[derive(Debug, Default)]
+pub struct FilterMetrics {
+    checked: AtomicU64,
+    flagged: AtomicU64,
+    dropped: AtomicU64,
+    by_filter: Mutex<HashMap<&'static str, FilterCounts>>,
+}
+
+/// The number of actions flagged and dropped, in total or by one filter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FilterCounts {
+    pub flagged: u64,
+    pub dropped: u64,
+}
+
+/// The values of the [`FilterMetrics`] counters at one point in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterMetricsSnapshot {
+    /// The number of actions checked.
+    pub checked: u64,
+    /// The number of actions flagged or dropped, by their strictest verdict.
+    pub filtered: FilterCounts,
+    /// The number of actions each filter flagged or dropped. An action
+    /// filtered by several filters is counted for each of them.
+    pub by_filter: BTreeMap<String, FilterCounts>,
+}
+
+impl FilterMetrics {
+    fn record_checked(&self, verdict: Verdict) {
+        self.checked.fetch_add(1, Ordering::Relaxed);
+        match verdict {
+            Verdict::Accept => {}
+            Verdict::Flag => {
+                self.flagged.fetch_add(1, Ordering::Relaxed);
+            }
+            Verdict::Drop => {
+                self.dropped.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    fn record_verdict(&self, filter: &'static str, verdict: Verdict) {
+        let mut by_filter = self.by_filter.lock().unwrap();
+        let counts = by_filter.entry(filter).or_default();
+        match verdict {
+            Verdict::Accept => {}
+            Verdict::Flag => counts.flagged += 1,
+            Verdict::Drop => counts.dropped += 1,
+        }
+    }
+
+    pub fn snapshot(&self) -> FilterMetricsSnapshot {
+        FilterMetricsSnapshot {
+            checked: self.checked.load(Ordering::Relaxed),
+            filtered: FilterCounts {
+                flagged: self.flagged.load(Ordering::Relaxed),
+                dropped: self.dropped.load(Ordering::Relaxed),
+            },
+            by_filter: self
+                .by_filter
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(filter, counts)| (filter.to_string(), *counts))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use actions_indexer_shared::types::{ActionRaw, VoteValue};
+    use alloy::hex::FromHex;
+    use alloy::primitives::{Address, TxHash};
+    use uuid::uuid;
+
+    pub(crate) fn make_vote(sender: &str, block_timestamp: u64) -> Action {
+        Action::Vote(Vote {
+            raw: ActionRaw {
+                action_type: 0,
+                action_version: 1,
+                sender: Address::from_hex(sender).unwrap(),
+                entity: uuid!("a7ef0016-a2f4-44fb-82ca-a4f5c61d2cf5"),
+                group_id: None,
+                space_pov: uuid!("e50fe85c-108a-4d4a-97b9-376a1e5d318b"),
+                metadata: None,
+                block_number: 1,
+                block_timestamp,
+                tx_hash: TxHash::from_hex(
+                    "0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4",
+                )
+                .unwrap(),
+                object_type: 0,
+                action_index: 0,
+            },
+            vote: VoteValue::Up,
+        })
+    }
+
+    /// Returns the same verdict for every action.
+    struct FixedFilter(&'static str, Verdict);
+
+    #[async_trait::async_trait]
+    impl FilterActions for FixedFilter {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        async fn check(&self, actions: &[Action]) -> Result<Vec<Verdict>, ProcessorError> {
+            Ok(vec![self.1; actions.len()])
+        }
+    }
+
+    const ALICE: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+
+    #[tokio::test]
+    async fn test_apply_without_filters_accepts_everything() {
+        let actions = vec![make_vote(ALICE, 1), make_vote(ALICE, 2)];
+
+        let filtered = ActionFilters::new().apply(actions.clone()).await.unwrap();
+
+        assert_eq!(filtered.actions, actions);
+        assert_eq!(filtered.votes.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_apply_keeps_flagged_actions_without_their_votes() {
+        let filters = ActionFilters::new().with_filter(Arc::new(FixedFilter("flag", Verdict::Flag)));
+
+        let filtered = filters.apply(vec![make_vote(ALICE, 1)]).await.unwrap();
+
+        assert_eq!(filtered.actions.len(), 1);
+        assert!(filtered.votes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_apply_takes_the_strictest_verdict() {
+        let filters = ActionFilters::new()
+            .with_filter(Arc::new(FixedFilter("drop", Verdict::Drop)))
+            .with_filter(Arc::new(FixedFilter("flag", Verdict::Flag)))
+            .with_filter(Arc::new(FixedFilter("accept", Verdict::Accept)));
+
+        let filtered = filters.apply(vec![make_vote(ALICE, 1), make_vote(ALICE, 2)]).await.unwrap();
+
+        assert_eq!(filtered, FilteredActions::default());
+        assert_eq!(
+            filters.metrics().snapshot(),
+            FilterMetricsSnapshot {
+                checked: 2,
+                filtered: FilterCounts { flagged: 0, dropped: 2 },
+                by_filter: BTreeMap::from([
+                    ("drop".to_string(), FilterCounts { flagged: 0, dropped: 2 }),
+                    ("flag".to_string(), FilterCounts { flagged: 2, dropped: 0 }),
+                ]),
+            }
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use actions_indexer_shared::types::{Action, UserAddress};
+
+use super::{sender, sent_at, FilterActions, Verdict};
+use crate::errors::ProcessorError;
+
+/// `RateLimitFilter` flags the actions a sender sends over a limit within a
+/// sliding window.
+///
+/// Windows are measured in block time, so replaying blocks flags the same
+/// actions. Flagged actions count towards the limit, so a sender stays flagged
+/// for as long as they keep sending over it. The actions sent before the
+/// indexer started aren't counted.
+pub struct RateLimitFilter {
+    max_actions: usize,
+    window: u64,
+    sent: Mutex<HashMap<UserAddress, VecDeque<u64>>>,
+}
+
+impl RateLimitFilter {
+    /// Creates a new `RateLimitFilter` instance.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_actions` - The most actions a sender can send within the window.
+    /// * `window` - The length of the window.
+    pub fn new(max_actions: usize, window: Duration) -> Self {
+        Self {
+            max_actions,
+            window: window.as_secs(),
+            sent: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl FilterActions for RateLimitFilter {
+    fn name(&self) -> &'static str {
+        "rate_limit"
+    }
+
+    async fn check(&self, actions: &[Action]) -> Result<Vec<Verdict>, ProcessorError> {
+        let mut sent = self.sent.lock().unwrap();
+        let mut latest = 0;
+
+        let verdicts = actions
+            .iter()
+            .map(|action| {
+                let at = sent_at(action);
+                latest = latest.max(at);
+
+                let times = sent.entry(sender(action)).or_default();
+                while times.front().is_some_and(|&time| time + self.window <= at) {
+                    times.pop_front();
+                }
+                let verdict = if times.len() >= self.max_actions {
+                    Verdict::Flag
+                } else {
+                    Verdict::Accept
+                };
+                times.push_back(at);
+                verdict
+            })
+            .collect();
+
+        // Forget the senders whose actions have all left the window
+        sent.retain(|_, times| times.back().is_some_and(|&time| time + self.window > latest));
+
+        Ok(verdicts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processor::filters::tests::make_vote;
+
+    const ALICE: &str = "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045";
+    const BOB: &str = "0x1234567890123456789012345678901234567890";
+
+    #[tokio::test]
+    async fn test_actions_over_the_limit_are_flagged() {
+        let filter = RateLimitFilter::new(2, Duration::from_secs(3600));
+
+        let verdicts = filter
+            .check(&[make_vote(ALICE, 100), make_vote(BOB, 100), make_vote(ALICE, 200)])
+            .await
+            .unwrap();
+        assert_eq!(verdicts, vec![Verdict::Accept; 3]);
+
+        // The limit carries over to the next block
+        let verdicts = filter
+            .check(&[make_vote(ALICE, 300), make_vote(BOB, 300)])
+            .await
+            .unwrap();
+        assert_eq!(verdicts, vec![Verdict::Flag, Verdict::Accept]);
+    }
+
+    #[tokio::test]
+    async fn test_actions_leave_the_window() {
+        let filter = RateLimitFilter::new(1, Duration::from_secs(3600));
+
+        assert_eq!(filter.check(&[make_vote(ALICE, 100)]).await.unwrap(), vec![Verdict::Accept]);
+        assert_eq!(filter.check(&[make_vote(ALICE, 3699)]).await.unwrap(), vec![Verdict::Flag]);
+        // The flagged action at 3699 is still in the window
+        assert_eq!(filter.check(&[make_vote(ALICE, 3700)]).await.unwrap(), vec![Verdict::Flag]);
+        assert_eq!(filter.check(&[make_vote(ALICE, 7400)]).await.unwrap(), vec![Verdict::Accept]);
+        assert_eq!(filter.sent.lock().unwrap().len(), 1);
+    }
+}
@@ -4,8 +4,12 @@ use crate::errors::ProcessorError;
 use actions_indexer_shared::types::{Action, ActionRaw};
 
 mod actions_processor;
+mod activity_processor;
+pub mod filters;
 
 pub use actions_processor::ActionsProcessor;
+pub use activity_processor::ActivityProcessor;
+pub use filters::{ActionFilters, FilterActions, Verdict};
 
 /// Defines the interface for processing raw `ActionEvent` data into structured `Action` data.
 ///
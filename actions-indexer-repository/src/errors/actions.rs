@@ -19,4 +19,10 @@ pub enum ActionsRepositoryError {
 
     #[error("Invalid vote type: {0}")]
     InvalidVoteType(i16),
+
+    #[error("Invalid transaction hash: {0}")]
+    InvalidTxHash(String),
+
+    #[error("Invalid activity type: {0}")]
+    InvalidActivityType(String),
 }
\ No newline at end of file
@@ -7,12 +7,14 @@
 //! - **Actions**: Processed blockchain actions (e.g., voting actions)
 //! - **User Votes**: Individual user voting records with timestamps
 //! - **Vote Counts**: Aggregated vote tallies per entity and space
+//! - **User Activity**: Per-user activity feeds derived from the actions
 //! - **Changesets**: Atomic batches of related data modifications
 //!
 //! The trait is designed to support transactional operations and efficient batch processing,
 //! making it suitable for high-throughput blockchain data indexing scenarios.
-use actions_indexer_shared::types::{Action, UserVote, VotesCount, Changeset, VoteCriteria, VoteCountCriteria};
+use actions_indexer_shared::types::{Action, UserVote, VotesCount, Changeset, VoteCriteria, VoteCountCriteria, EntityId, SpaceId, UserAddress, UserActivity, ActivityCursor};
 use crate::errors::ActionsRepositoryError;
+use std::collections::HashMap;
 
 /// Repository interface for managing actions indexer data storage operations.
 ///
@@ -121,6 +123,7 @@ pub trait ActionsRepository: Send + Sync {
     ///   - `actions`: New actions to be inserted
     ///   - `user_votes`: User vote records to be updated/inserted
     ///   - `votes_count`: Aggregated vote counts to be updated
+    ///   - `user_activity`: Activity feed entries to be inserted
     ///
     /// # Returns
     ///
@@ -196,6 +199,125 @@ pub trait ActionsRepository: Send + Sync {
         vote_criteria: &[VoteCountCriteria],
     ) -> Result<Vec<VotesCount>, ActionsRepositoryError>;
 
+    /// Retrieves the votes cast on an entity, most recent first.
+    ///
+    /// Removed votes are left out, as they no longer count towards the entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The UUID of the entity that was voted on
+    /// * `space_id` - The space to restrict the votes to, or `None` for the
+    ///   entity's votes in every space
+    /// * `limit` - The most votes to return
+    /// * `offset` - The number of votes to skip, for paging through the votes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserVote>)` - The entity's votes. Returns an empty vector if
+    ///   the entity has no votes.
+    /// * `Err(ActionsRepositoryError)` - If the query fails due to database errors
+    ///   or connection issues
+    async fn get_votes_by_entity(
+        &self,
+        entity_id: EntityId,
+        space_id: Option<SpaceId>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<UserVote>, ActionsRepositoryError>;
+
+    /// Retrieves the votes cast by a user, most recent first.
+    ///
+    /// Removed votes are left out, as they no longer count towards their entity.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The blockchain address of the voting user
+    /// * `space_id` - The space to restrict the votes to, or `None` for the
+    ///   user's votes in every space
+    /// * `limit` - The most votes to return
+    /// * `offset` - The number of votes to skip, for paging through the votes
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserVote>)` - The user's votes. Returns an empty vector if the
+    ///   user hasn't voted.
+    /// * `Err(ActionsRepositoryError)` - If the query fails due to database errors
+    ///   or connection issues
+    async fn get_votes_by_user(
+        &self,
+        user_id: UserAddress,
+        space_id: Option<SpaceId>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<UserVote>, ActionsRepositoryError>;
+
+    /// Retrieves the vote counts of the top voted entities in a space.
+    ///
+    /// Entities are ranked by their upvotes minus their downvotes, and ties are
+    /// broken by their upvotes, then by their id, so the order is stable.
+    ///
+    /// # Arguments
+    ///
+    /// * `space_id` - The UUID of the space to rank the entities of
+    /// * `limit` - The most entities to return
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<VotesCount>)` - The vote counts of the top voted entities, best
+    ///   first. Returns an empty vector if no entity in the space was voted on.
+    /// * `Err(ActionsRepositoryError)` - If the query fails due to database errors
+    ///   or connection issues
+    async fn get_top_voted_entities(
+        &self,
+        space_id: SpaceId,
+        limit: i64,
+    ) -> Result<Vec<VotesCount>, ActionsRepositoryError>;
+
+    /// Retrieves a page of a user's activity feed, most recent first.
+    ///
+    /// Pages are keyed rather than offset, so entries written while a client
+    /// pages through the feed don't shift the pages it hasn't read yet.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The blockchain address of the user
+    /// * `before` - The key of the last entry of the previous page, or `None`
+    ///   for the first page. Only entries older than it are returned.
+    /// * `limit` - The most entries to return
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserActivity>)` - The page of the feed. Returns an empty vector
+    ///   once the feed is read to the end.
+    /// * `Err(ActionsRepositoryError)` - If the query fails due to database errors
+    ///   or connection issues
+    async fn get_user_activity(
+        &self,
+        user_id: UserAddress,
+        before: Option<ActivityCursor>,
+        limit: i64,
+    ) -> Result<Vec<UserActivity>, ActionsRepositoryError>;
+
+    /// Retrieves when each of the given users sent their first action.
+    ///
+    /// Users are only known once an action they sent is persisted, so the
+    /// result leaves out users whose first action is still being processed.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_ids` - The blockchain addresses of the users
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HashMap<UserAddress, u64>)` - The block timestamp of each known
+    ///   user's first action, in seconds
+    /// * `Err(ActionsRepositoryError)` - If the query fails due to database errors
+    ///   or connection issues
+    async fn get_first_activity(
+        &self,
+        user_ids: &[UserAddress],
+    ) -> Result<HashMap<UserAddress, u64>, ActionsRepositoryError>;
+
     /// Checks if the tables are created in the database.
     ///
     /// This method checks if the tables are created in the database.
@@ -16,12 +16,14 @@
 //! - `raw_actions`: Processed blockchain actions
 //! - `user_votes`: Individual voting records with upsert support
 //! - `votes_count`: Aggregated vote tallies per entity/space
+//! - `user_activity`: Per-user activity feeds keyed for paging
 use async_trait::async_trait;
-use actions_indexer_shared::types::{Action, Changeset, UserVote, VotesCount, EntityId, VoteCriteria, VoteCountCriteria, VoteValue};
+use actions_indexer_shared::types::{Action, ActivityCursor, ActivityKind, Changeset, UserActivity, UserVote, VotesCount, EntityId, SpaceId, UserAddress, VoteCriteria, VoteCountCriteria, VoteValue};
 use crate::{ActionsRepository, ActionsRepositoryError};
 use hex;
+use std::collections::HashMap;
 use time::OffsetDateTime;
-use alloy::{primitives::Address, hex::FromHex};
+use alloy::{primitives::{Address, TxHash}, hex::FromHex};
 use uuid::Uuid;
 
 /// PostgreSQL implementation of the actions indexer repository.
@@ -59,6 +61,8 @@ impl PostgresActionsRepository {
     ///
     /// Uses `QueryBuilder` for efficient multi-row INSERT into `raw_actions` table.
     /// Handles blockchain addresses as hex-encoded strings and timestamps as PostgreSQL timestamps.
+    /// Actions are keyed by (block_number, action_index), and actions that already
+    /// exist are left alone, so blocks processed again don't duplicate them.
     ///
     /// # Arguments
     ///
@@ -75,7 +79,7 @@ impl PostgresActionsRepository {
         }
 
         let mut query_builder = sqlx::QueryBuilder::new(
-            "INSERT INTO raw_actions (action_type, action_version, sender, entity, group_id, space_pov, metadata, block_number, block_timestamp, tx_hash) "
+            "INSERT INTO raw_actions (action_type, action_version, sender, entity, group_id, space_pov, metadata, block_number, block_timestamp, tx_hash, action_index) "
         );
 
         query_builder.push_values(actions, |mut b, action| {
@@ -93,10 +97,12 @@ impl PostgresActionsRepository {
                      .push_bind(vote_action.raw.metadata.as_ref().map(|b| b.as_ref().to_vec()))
                      .push_bind(vote_action.raw.block_number as i64)
                      .push_bind(voted_at)
-                     .push_bind(format!("0x{}", hex::encode(vote_action.raw.tx_hash.as_slice())));
+                     .push_bind(format!("0x{}", hex::encode(vote_action.raw.tx_hash.as_slice())))
+                     .push_bind(vote_action.raw.action_index as i32);
                 }
             }
         });
+        query_builder.push(" ON CONFLICT (block_number, action_index) DO NOTHING");
 
         query_builder.build().execute(&mut **tx).await?;
         Ok(())
@@ -106,6 +112,8 @@ impl PostgresActionsRepository {
     ///
     /// Uses `ON CONFLICT DO UPDATE` for each vote record targeting the `user_votes` table
     /// with composite key (user_id, entity_id, space_id). Addresses are hex-encoded.
+    /// Votes older than the stored vote are ignored, so processing earlier blocks
+    /// again doesn't roll a vote back.
     ///
     /// # Arguments
     ///
@@ -130,15 +138,12 @@ impl PostgresActionsRepository {
                 DO UPDATE SET
                     vote_type = EXCLUDED.vote_type,
                     voted_at = EXCLUDED.voted_at
+                WHERE user_votes.voted_at <= EXCLUDED.voted_at
                 "#,
                 format!("0x{}", hex::encode(vote.user_id.as_slice())),
                 vote.entity_id.clone(),
                 vote.space_id.clone(),
-                match vote.vote_type {
-                    VoteValue::Up => 0,
-                    VoteValue::Down => 1,
-                    VoteValue::Remove => 2,
-                } as i16,
+                vote_type_code(&vote.vote_type),
                 OffsetDateTime::from_unix_timestamp(vote.voted_at as i64)
                     .unwrap_or(OffsetDateTime::now_utc())
             )
@@ -151,7 +156,9 @@ impl PostgresActionsRepository {
     /// Updates vote count aggregations within an active transaction.
     ///
     /// Uses upsert operations on `votes_count` table with composite key (entity_id, space_id).
-    /// Replaces existing totals with new values to maintain accurate statistics.
+    /// Replaces existing totals with new values to maintain accurate statistics. The totals
+    /// are computed from counts read before the transaction, so they're only accurate while
+    /// the writer holds the [`WriterLock`](super::WriterLock).
     ///
     /// # Arguments
     ///
@@ -187,6 +194,92 @@ impl PostgresActionsRepository {
         }
         Ok(())
     }
+
+    /// Inserts activity feed entries within an active transaction.
+    ///
+    /// Entries are keyed by (user_id, block_number, action_index), and entries
+    /// that already exist are left alone, so blocks processed again don't
+    /// duplicate them.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_activity` - Feed entries to insert (empty slices are no-ops)
+    /// * `tx` - Active transaction context
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - All entries inserted successfully
+    /// * `Err(ActionsRepositoryError)` - Database or encoding error
+    async fn insert_user_activity_tx(&self, user_activity: &[UserActivity], tx: &mut sqlx::Transaction<'_, sqlx::Postgres>) -> Result<(), ActionsRepositoryError> {
+        if user_activity.is_empty() {
+            return Ok(());
+        }
+
+        let mut query_builder = sqlx::QueryBuilder::new(
+            "INSERT INTO user_activity (user_id, block_number, action_index, activity_type, vote_type, entity_id, space_id, tx_hash, occurred_at) "
+        );
+
+        query_builder.push_values(user_activity, |mut b, entry| {
+            let (activity_type, vote_type) = match &entry.kind {
+                ActivityKind::Vote(vote) => ("vote", Some(vote_type_code(vote))),
+            };
+            b.push_bind(format!("0x{}", hex::encode(entry.user_id.as_slice())))
+             .push_bind(entry.block_number as i64)
+             .push_bind(entry.action_index as i32)
+             .push_bind(activity_type)
+             .push_bind(vote_type)
+             .push_bind(entry.entity_id)
+             .push_bind(entry.space_id)
+             .push_bind(format!("0x{}", hex::encode(entry.tx_hash.as_slice())))
+             .push_bind(OffsetDateTime::from_unix_timestamp(entry.occurred_at as i64)
+                 .unwrap_or(OffsetDateTime::now_utc()));
+        });
+        query_builder.push(" ON CONFLICT (user_id, block_number, action_index) DO NOTHING");
+
+        query_builder.build().execute(&mut **tx).await?;
+        Ok(())
+    }
+}
+
+/// The `vote_type` a vote is stored as.
+fn vote_type_code(vote: &VoteValue) -> i16 {
+    match vote {
+        VoteValue::Up => 0,
+        VoteValue::Down => 1,
+        VoteValue::Remove => 2,
+    }
+}
+
+/// Maps a stored `vote_type` back to a vote.
+fn vote_value_from_code(vote_type: i16) -> Result<VoteValue, ActionsRepositoryError> {
+    match vote_type {
+        0 => Ok(VoteValue::Up),
+        1 => Ok(VoteValue::Down),
+        2 => Ok(VoteValue::Remove),
+        _ => Err(ActionsRepositoryError::InvalidVoteType(vote_type)),
+    }
+}
+
+/// Maps a `user_votes` row back to a `UserVote`.
+///
+/// # Returns
+///
+/// * `Ok(UserVote)` - The vote of the row
+/// * `Err(ActionsRepositoryError)` - If the row holds an invalid address or vote type
+fn user_vote_from_row(
+    user_id: String,
+    entity_id: EntityId,
+    space_id: SpaceId,
+    vote_type: i16,
+    voted_at: OffsetDateTime,
+) -> Result<UserVote, ActionsRepositoryError> {
+    Ok(UserVote {
+        user_id: Address::from_hex(&user_id).map_err(|_| ActionsRepositoryError::InvalidAddress(user_id))?,
+        entity_id,
+        space_id,
+        vote_type: vote_value_from_code(vote_type)?,
+        voted_at: voted_at.unix_timestamp() as u64,
+    })
 }
 
 #[async_trait]
@@ -262,7 +355,8 @@ impl ActionsRepository for PostgresActionsRepository {
 
     /// Atomically persists a complete changeset in a single transaction.
     ///
-    /// Bundles actions, user votes, and vote counts together for atomic persistence.
+    /// Bundles actions, user votes, vote counts and activity feed entries together
+    /// for atomic persistence.
     /// Either all changes succeed or all are rolled back on failure.
     ///
     /// # Arguments
@@ -281,6 +375,7 @@ impl ActionsRepository for PostgresActionsRepository {
         self.insert_actions_tx(changeset.actions, &mut tx).await?;
         self.update_user_votes_tx(changeset.user_votes, &mut tx).await?;
         self.update_votes_counts_tx(changeset.votes_count, &mut tx).await?;
+        self.insert_user_activity_tx(changeset.user_activity, &mut tx).await?;
         tx.commit().await.map_err(|e| ActionsRepositoryError::DatabaseError(e))?;
         Ok(())
     }
@@ -320,23 +415,10 @@ impl ActionsRepository for PostgresActionsRepository {
         .fetch_all(&self.pool)
         .await?;
 
-        let mut result_votes = Vec::with_capacity(votes.len());
-        for v in votes {
-            result_votes.push(UserVote {
-                user_id: Address::from_hex(&v.user_id).map_err(|_| ActionsRepositoryError::InvalidAddress(v.user_id))?,
-                entity_id: v.entity_id,
-                space_id: v.space_id,
-                vote_type: match v.vote_type {
-                    0 => VoteValue::Up,
-                    1 => VoteValue::Down,
-                    2 => VoteValue::Remove,
-                    _ => return Err(ActionsRepositoryError::InvalidVoteType(v.vote_type)),
-                },
-                voted_at: v.voted_at.unix_timestamp() as u64,
-            });
-        }
-
-        Ok(result_votes)
+        votes
+            .into_iter()
+            .map(|v| user_vote_from_row(v.user_id, v.entity_id, v.space_id, v.vote_type, v.voted_at))
+            .collect()
     }
 
     /// Retrieves aggregated vote counts for entities and spaces.
@@ -385,6 +467,245 @@ impl ActionsRepository for PostgresActionsRepository {
         Ok(result_counts)
     }
 
+    /// Retrieves the votes cast on an entity, most recent first.
+    ///
+    /// Served by the `(entity_id, space_id)` index on `user_votes`. Removed
+    /// votes are filtered out.
+    ///
+    /// # Arguments
+    ///
+    /// * `entity_id` - The entity to get the votes of
+    /// * `space_id` - The space to restrict the votes to, if any
+    /// * `limit` - The most votes to return
+    /// * `offset` - The number of votes to skip
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserVote>)` - The entity's votes (empty if none found)
+    /// * `Err(ActionsRepositoryError)` - Database query failure
+    async fn get_votes_by_entity(
+        &self,
+        entity_id: EntityId,
+        space_id: Option<SpaceId>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+        let votes = sqlx::query!(
+            r#"
+            SELECT user_id, entity_id, space_id, vote_type, voted_at
+            FROM user_votes
+            WHERE entity_id = $1
+                AND ($2::uuid IS NULL OR space_id = $2)
+                AND vote_type <> 2
+            ORDER BY voted_at DESC, user_id
+            LIMIT $3 OFFSET $4
+            "#,
+            entity_id,
+            space_id,
+            limit,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        votes
+            .into_iter()
+            .map(|v| user_vote_from_row(v.user_id, v.entity_id, v.space_id, v.vote_type, v.voted_at))
+            .collect()
+    }
+
+    /// Retrieves the votes cast by a user, most recent first.
+    ///
+    /// Addresses are stored as lowercase hex, so the user's address is encoded
+    /// the same way to match them. Removed votes are filtered out.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The address of the user to get the votes of
+    /// * `space_id` - The space to restrict the votes to, if any
+    /// * `limit` - The most votes to return
+    /// * `offset` - The number of votes to skip
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserVote>)` - The user's votes (empty if none found)
+    /// * `Err(ActionsRepositoryError)` - Database query failure
+    async fn get_votes_by_user(
+        &self,
+        user_id: UserAddress,
+        space_id: Option<SpaceId>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<UserVote>, ActionsRepositoryError> {
+        let votes = sqlx::query!(
+            r#"
+            SELECT user_id, entity_id, space_id, vote_type, voted_at
+            FROM user_votes
+            WHERE user_id = $1
+                AND ($2::uuid IS NULL OR space_id = $2)
+                AND vote_type <> 2
+            ORDER BY voted_at DESC, entity_id
+            LIMIT $3 OFFSET $4
+            "#,
+            format!("0x{}", hex::encode(user_id.as_slice())),
+            space_id,
+            limit,
+            offset,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        votes
+            .into_iter()
+            .map(|v| user_vote_from_row(v.user_id, v.entity_id, v.space_id, v.vote_type, v.voted_at))
+            .collect()
+    }
+
+    /// Retrieves the vote counts of the top voted entities in a space.
+    ///
+    /// Ranks the space's `votes_count` rows by upvotes minus downvotes, then by
+    /// upvotes and entity id.
+    ///
+    /// # Arguments
+    ///
+    /// * `space_id` - The space to rank the entities of
+    /// * `limit` - The most entities to return
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<VotesCount>)` - The top vote counts, best first (empty if none found)
+    /// * `Err(ActionsRepositoryError)` - Database query failure
+    async fn get_top_voted_entities(
+        &self,
+        space_id: SpaceId,
+        limit: i64,
+    ) -> Result<Vec<VotesCount>, ActionsRepositoryError> {
+        let counts = sqlx::query_as!(
+            VotesCount,
+            r#"
+            SELECT entity_id, space_id, upvotes, downvotes
+            FROM votes_count
+            WHERE space_id = $1
+            ORDER BY upvotes - downvotes DESC, upvotes DESC, entity_id
+            LIMIT $2
+            "#,
+            space_id,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    /// Retrieves a page of a user's activity feed, most recent first.
+    ///
+    /// Pages by comparing the (block_number, action_index) key with the last
+    /// key read, which the `user_activity` primary key serves directly.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_id` - The address of the user to get the feed of
+    /// * `before` - The key to read the entries before, if any
+    /// * `limit` - The most entries to return
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<UserActivity>)` - The page of the feed (empty if none found)
+    /// * `Err(ActionsRepositoryError)` - Database query failure
+    async fn get_user_activity(
+        &self,
+        user_id: UserAddress,
+        before: Option<ActivityCursor>,
+        limit: i64,
+    ) -> Result<Vec<UserActivity>, ActionsRepositoryError> {
+        let (before_block, before_index) = match before {
+            Some((block_number, action_index)) => (Some(block_number as i64), Some(action_index as i32)),
+            None => (None, None),
+        };
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id, block_number, action_index, activity_type, vote_type, entity_id, space_id, tx_hash, occurred_at
+            FROM user_activity
+            WHERE user_id = $1
+                AND ($2::bigint IS NULL OR (block_number, action_index) < ($2, $3::integer))
+            ORDER BY block_number DESC, action_index DESC
+            LIMIT $4
+            "#,
+            format!("0x{}", hex::encode(user_id.as_slice())),
+            before_block,
+            before_index,
+            limit,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let kind = match (row.activity_type.as_str(), row.vote_type) {
+                    ("vote", Some(vote_type)) => ActivityKind::Vote(vote_value_from_code(vote_type)?),
+                    _ => return Err(ActionsRepositoryError::InvalidActivityType(row.activity_type)),
+                };
+
+                Ok(UserActivity {
+                    user_id: Address::from_hex(&row.user_id).map_err(|_| ActionsRepositoryError::InvalidAddress(row.user_id))?,
+                    block_number: row.block_number as u64,
+                    action_index: row.action_index as u32,
+                    kind,
+                    entity_id: row.entity_id,
+                    space_id: row.space_id,
+                    tx_hash: TxHash::from_hex(&row.tx_hash).map_err(|_| ActionsRepositoryError::InvalidTxHash(row.tx_hash))?,
+                    occurred_at: row.occurred_at.unix_timestamp() as u64,
+                })
+            })
+            .collect()
+    }
+
+    /// Retrieves when each of the given users sent their first action.
+    ///
+    /// Reads the oldest entry of each user's activity feed, which is keyed by
+    /// user, rather than scanning `raw_actions`.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_ids` - The addresses of the users
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(HashMap<UserAddress, u64>)` - The timestamp of each known user's
+    ///   first action (users without activity are left out)
+    /// * `Err(ActionsRepositoryError)` - Database query failure
+    async fn get_first_activity(
+        &self,
+        user_ids: &[UserAddress],
+    ) -> Result<HashMap<UserAddress, u64>, ActionsRepositoryError> {
+        if user_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let user_ids: Vec<String> = user_ids.iter().map(|u| format!("0x{}", hex::encode(u.as_slice()))).collect();
+
+        let rows = sqlx::query!(
+            r#"
+            SELECT user_id, MIN(occurred_at) AS "first_activity_at!"
+            FROM user_activity
+            WHERE user_id = ANY($1::text[])
+            GROUP BY user_id
+            "#,
+            &user_ids,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let user_id = Address::from_hex(&row.user_id).map_err(|_| ActionsRepositoryError::InvalidAddress(row.user_id))?;
+                Ok((user_id, row.first_activity_at.unix_timestamp() as u64))
+            })
+            .collect()
+    }
+
     /// Checks if the tables are created in the database.
     ///
     /// This method checks if the tables are created in the database.
@@ -393,7 +714,7 @@ impl ActionsRepository for PostgresActionsRepository {
     ///
     /// * `Ok(true)` - If the tables are created
     async fn check_tables_created(&self) -> Result<bool, ActionsRepositoryError> {
-        let tables = vec!["raw_actions", "user_votes", "votes_count"];
+        let tables = vec!["raw_actions", "user_votes", "votes_count", "user_activity"];
         for table in tables {
             let table_exists: bool = sqlx::query_scalar!(
                 r#"
@@ -0,0 +1 @@
+CREATE INDEX idx_user_votes_entity_space ON user_votes(entity_id, space_id);
@@ -0,0 +1,12 @@
+CREATE TABLE user_activity (
+    user_id VARCHAR(42) NOT NULL,
+    block_number BIGINT NOT NULL,
+    action_index INTEGER NOT NULL,
+    activity_type TEXT NOT NULL,
+    vote_type SMALLINT,
+    entity_id UUID NOT NULL,
+    space_id UUID NOT NULL,
+    tx_hash VARCHAR(66) NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (user_id, block_number, action_index)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE raw_actions ADD COLUMN action_index INTEGER NOT NULL;
+ALTER TABLE raw_actions ADD CONSTRAINT raw_actions_block_number_action_index_unique UNIQUE (block_number, action_index);
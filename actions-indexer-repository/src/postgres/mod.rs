@@ -16,7 +16,24 @@
 //! - `raw_actions`: Processed blockchain actions
 //! - `user_votes`: Individual voting records with upsert support
 //! - `votes_count`: Aggregated vote tallies per entity/space
+//!
+//! ## Writers
+//!
+//! Only one actions indexer may write votes to a database at a time, which
+//! [`WriterLock`] enforces.
+//!
+//! ## Migrations
+//!
+//! [`MIGRATOR`] embeds the schema migrations of the API project, which also
+//! create the knowledge graph tables, so the actions indexer and the
+//! knowledge graph indexer can share one database and migration history.
 mod actions_repository;
 mod cursor_repository;
+mod writer_lock;
 pub use actions_repository::PostgresActionsRepository;
-pub use cursor_repository::PostgresCursorRepository;
\ No newline at end of file
+pub use cursor_repository::PostgresCursorRepository;
+pub use writer_lock::WriterLock;
+
+/// The drizzle migrations of the API project, applied by the actions indexer
+/// on startup when migrations are enabled.
+pub static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("../api/drizzle");
@@ -0,0 +1,50 @@
+//! Exclusive ownership of the vote tables of an actions database.
+
+use sqlx::{Connection, PgConnection, PgPool};
+
+/// The key of the advisory lock held by the actions indexer writing votes.
+const WRITER_LOCK_KEY: &str = "actions_indexer_writer";
+
+/// Keeps a second actions indexer, live or backfilling, from writing votes
+/// to the same database.
+///
+/// Vote counts are computed from the stored votes and counts read before a
+/// batch is written, so two writers counting votes of the same user or
+/// entity at once would each count them from the same totals.
+///
+/// The lock is a session-level Postgres advisory lock held on a dedicated
+/// connection. It is released when the lock is dropped or its connection is
+/// lost.
+pub struct WriterLock {
+    connection: PgConnection,
+}
+
+impl WriterLock {
+    /// Acquires the lock.
+    ///
+    /// # Arguments
+    ///
+    /// * `pool` - Pool of the actions database to take a connection from
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Some(WriterLock))` - The lock, held until it's dropped
+    /// * `Ok(None)` - Another actions indexer holds the lock
+    /// * `Err(sqlx::Error)` - Database failure
+    pub async fn try_acquire(pool: &PgPool) -> Result<Option<Self>, sqlx::Error> {
+        // Detached so the pool doesn't recycle the connection holding the lock
+        let mut connection = pool.acquire().await?.detach();
+
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock(hashtext($1))")
+            .bind(WRITER_LOCK_KEY)
+            .fetch_one(&mut connection)
+            .await?;
+
+        Ok(acquired.then_some(WriterLock { connection }))
+    }
+
+    /// Releases the lock by closing its connection.
+    pub async fn release(self) -> Result<(), sqlx::Error> {
+        self.connection.close().await
+    }
+}
@@ -6,11 +6,12 @@
 //! Run with: `cargo test --test postgres_actions`
 
 use actions_indexer_repository::{ActionsRepository, PostgresActionsRepository};
-use actions_indexer_shared::types::{Action, ActionRaw, Vote, UserVote, VotesCount, VoteCriteria, VoteValue};
+use actions_indexer_shared::types::{Action, ActionRaw, ActivityKind, Changeset, Vote, UserActivity, UserVote, VotesCount, VoteCriteria, VoteValue};
 use alloy::primitives::{Address, TxHash};
 use alloy::hex::FromHex;
 use uuid::{Uuid, uuid};
 use sqlx::Row;
+use std::collections::HashMap;
 
 /// Creates a test action raw data with default values.
 fn make_raw_action() -> ActionRaw {
@@ -26,6 +27,7 @@ fn make_raw_action() -> ActionRaw {
         block_timestamp: 1755182913,
         tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
         object_type: 0,
+        action_index: 0,
     }
 }
 
@@ -84,11 +86,11 @@ async fn test_insert_multiple_raw_actions(pool: sqlx::PgPool) {
             vote: VoteValue::Up,
         }),
         Action::Vote(Vote {
-            raw: raw_action.clone(),
+            raw: ActionRaw { action_index: 1, ..raw_action.clone() },
             vote: VoteValue::Down,
         }),
         Action::Vote(Vote {
-            raw: raw_action.clone(),
+            raw: ActionRaw { action_index: 2, ..raw_action.clone() },
             vote: VoteValue::Remove,
         }),
     ];
@@ -101,6 +103,30 @@ async fn test_insert_multiple_raw_actions(pool: sqlx::PgPool) {
     assert_eq!(actions.len(), 3);
 }
 
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_insert_raw_actions_twice(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+    let raw_action = make_raw_action();
+    let actions = vec![
+        Action::Vote(Vote {
+            raw: raw_action.clone(),
+            vote: VoteValue::Up,
+        }),
+        Action::Vote(Vote {
+            raw: ActionRaw { action_index: 1, ..raw_action.clone() },
+            vote: VoteValue::Down,
+        }),
+    ];
+
+    repository.insert_actions(&actions).await.unwrap();
+    repository.insert_actions(&actions).await.unwrap();
+
+    let actions = sqlx::query("SELECT * FROM raw_actions")
+        .fetch_all(&pool).await.unwrap();
+
+    assert_eq!(actions.len(), 2);
+}
+
 #[sqlx::test(migrations = "src/postgres/migrations")]
 async fn test_insert_empty_actions(pool: sqlx::PgPool) {
     let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
@@ -187,6 +213,34 @@ async fn test_update_user_vote(pool: sqlx::PgPool) {
     assert_eq!(updated_votes_in_db.voted_at.unix_timestamp() as u64, updated_user_vote.voted_at);
 }
 
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_update_user_vote_with_older_vote(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let user_vote = make_user_vote();
+    let older_user_vote = UserVote {
+        vote_type: VoteValue::Down,
+        voted_at: user_vote.voted_at - 1,
+        ..user_vote.clone()
+    };
+
+    repository.update_user_votes(&[user_vote.clone()]).await.unwrap();
+    repository.update_user_votes(&[older_user_vote]).await.unwrap();
+
+    let votes_in_db = sqlx::query!(
+        "SELECT vote_type, voted_at FROM user_votes WHERE user_id = $1 AND entity_id = $2 AND space_id = $3",
+        format!("0x{}", hex::encode(user_vote.user_id.as_slice())),
+        user_vote.entity_id,
+        user_vote.space_id,
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+
+    assert_eq!(votes_in_db.vote_type, 0);
+    assert_eq!(votes_in_db.voted_at.unix_timestamp() as u64, user_vote.voted_at);
+}
+
 #[sqlx::test(migrations = "src/postgres/migrations")]
 async fn test_update_multiple_user_votes(pool: sqlx::PgPool) {
     let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
@@ -413,4 +467,192 @@ async fn test_get_user_votes_nonexistent_data(pool: sqlx::PgPool) {
     
     let found_votes = repository.get_user_votes(&vote_criteria).await.unwrap();
     assert!(found_votes.is_empty());
-}
\ No newline at end of file
+}
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_get_votes_by_entity(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let older_vote = make_user_vote();
+    let newer_vote = UserVote {
+        user_id: Address::from_hex("0x1234567890123456789012345678901234567890").unwrap(),
+        vote_type: VoteValue::Down,
+        voted_at: older_vote.voted_at + 1,
+        ..older_vote.clone()
+    };
+    let removed_vote = UserVote {
+        user_id: Address::from_hex("0x1111111111111111111111111111111111111111").unwrap(),
+        vote_type: VoteValue::Remove,
+        ..older_vote.clone()
+    };
+    let other_space_vote = UserVote {
+        user_id: Address::from_hex("0x3333333333333333333333333333333333333333").unwrap(),
+        space_id: Uuid::new_v4(),
+        ..older_vote.clone()
+    };
+
+    repository
+        .update_user_votes(&[older_vote.clone(), newer_vote.clone(), removed_vote, other_space_vote.clone(), make_user_vote()])
+        .await
+        .unwrap();
+
+    let votes = repository.get_votes_by_entity(older_vote.entity_id, Some(older_vote.space_id), 10, 0).await.unwrap();
+    assert_eq!(votes, vec![newer_vote.clone(), older_vote.clone()]);
+
+    let votes = repository.get_votes_by_entity(older_vote.entity_id, None, 10, 0).await.unwrap();
+    assert_eq!(votes.len(), 3);
+    assert!(votes.contains(&other_space_vote));
+
+    let page = repository.get_votes_by_entity(older_vote.entity_id, Some(older_vote.space_id), 1, 1).await.unwrap();
+    assert_eq!(page, vec![older_vote]);
+}
+
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_get_votes_by_user(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let first_vote = make_user_vote();
+    let second_vote = UserVote {
+        entity_id: Uuid::new_v4(),
+        space_id: Uuid::new_v4(),
+        voted_at: first_vote.voted_at + 1,
+        ..first_vote.clone()
+    };
+    let removed_vote = UserVote {
+        entity_id: Uuid::new_v4(),
+        vote_type: VoteValue::Remove,
+        ..first_vote.clone()
+    };
+    let other_user_vote = UserVote {
+        user_id: Address::from_hex("0x1234567890123456789012345678901234567890").unwrap(),
+        ..first_vote.clone()
+    };
+
+    repository
+        .update_user_votes(&[first_vote.clone(), second_vote.clone(), removed_vote, other_user_vote])
+        .await
+        .unwrap();
+
+    let votes = repository.get_votes_by_user(first_vote.user_id, None, 10, 0).await.unwrap();
+    assert_eq!(votes, vec![second_vote.clone(), first_vote.clone()]);
+
+    let votes = repository.get_votes_by_user(first_vote.user_id, Some(second_vote.space_id), 10, 0).await.unwrap();
+    assert_eq!(votes, vec![second_vote]);
+}
+
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_get_top_voted_entities(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let controversial = VotesCount { upvotes: 10, downvotes: 9, ..make_votes_count() };
+    let liked = VotesCount { upvotes: 5, downvotes: 0, ..make_votes_count() };
+    let disliked = VotesCount { upvotes: 0, downvotes: 3, ..make_votes_count() };
+    let other_space = VotesCount { space_id: Uuid::new_v4(), upvotes: 100, ..make_votes_count() };
+
+    repository
+        .update_votes_counts(&[controversial.clone(), liked.clone(), disliked.clone(), other_space])
+        .await
+        .unwrap();
+
+    let top = repository.get_top_voted_entities(liked.space_id, 10).await.unwrap();
+    let entities: Vec<Uuid> = top.iter().map(|count| count.entity_id).collect();
+    assert_eq!(entities, vec![liked.entity_id, controversial.entity_id, disliked.entity_id]);
+
+    let top = repository.get_top_voted_entities(liked.space_id, 1).await.unwrap();
+    assert_eq!(top.len(), 1);
+    assert_eq!(top[0].entity_id, liked.entity_id);
+}
+
+// ============================================================================
+// User Activity Tests
+// ============================================================================
+
+/// Creates a test activity feed entry at the given position.
+fn make_user_activity(block_number: u64, action_index: u32) -> UserActivity {
+    UserActivity {
+        user_id: Address::from_hex("0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045").unwrap(),
+        block_number,
+        action_index,
+        kind: ActivityKind::Vote(VoteValue::Up),
+        entity_id: Uuid::new_v4(),
+        space_id: uuid!("f5d2fe0c-fb9d-4027-b227-54f59af20f19"),
+        tx_hash: TxHash::from_hex("0x5427daee8d03277f8a30ea881692c04861e692ce5f305b7a689b76248cae63c4").unwrap(),
+        occurred_at: 1755182913,
+    }
+}
+
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_persist_changeset_with_user_activity(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let user_activity = vec![
+        make_user_activity(1, 0),
+        UserActivity { kind: ActivityKind::Vote(VoteValue::Remove), ..make_user_activity(1, 1) },
+    ];
+    let changeset = Changeset {
+        actions: &[],
+        user_votes: &[],
+        votes_count: &[],
+        user_activity: &user_activity,
+    };
+
+    // Blocks processed again don't duplicate their entries
+    repository.persist_changeset(&changeset).await.unwrap();
+    repository.persist_changeset(&changeset).await.unwrap();
+
+    let feed = repository.get_user_activity(user_activity[0].user_id, None, 10).await.unwrap();
+    assert_eq!(feed, vec![user_activity[1].clone(), user_activity[0].clone()]);
+}
+
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_get_user_activity_pages(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let user_activity = vec![
+        make_user_activity(1, 0),
+        make_user_activity(1, 3),
+        make_user_activity(2, 0),
+        UserActivity {
+            user_id: Address::from_hex("0x1234567890123456789012345678901234567890").unwrap(),
+            ..make_user_activity(3, 0)
+        },
+    ];
+    repository
+        .persist_changeset(&Changeset { actions: &[], user_votes: &[], votes_count: &[], user_activity: &user_activity })
+        .await
+        .unwrap();
+
+    let user_id = user_activity[0].user_id;
+    let first_page = repository.get_user_activity(user_id, None, 2).await.unwrap();
+    assert_eq!(first_page, vec![user_activity[2].clone(), user_activity[1].clone()]);
+
+    let last = first_page.last().unwrap();
+    let second_page = repository
+        .get_user_activity(user_id, Some((last.block_number, last.action_index)), 2)
+        .await
+        .unwrap();
+    assert_eq!(second_page, vec![user_activity[0].clone()]);
+
+    let end = repository.get_user_activity(user_id, Some((1, 0)), 2).await.unwrap();
+    assert!(end.is_empty());
+}
+
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_get_first_activity(pool: sqlx::PgPool) {
+    let repository = PostgresActionsRepository::new(pool.clone()).await.unwrap();
+
+    let user_activity = vec![
+        UserActivity { occurred_at: 1755182000, ..make_user_activity(1, 0) },
+        make_user_activity(2, 0),
+    ];
+    repository
+        .persist_changeset(&Changeset { actions: &[], user_votes: &[], votes_count: &[], user_activity: &user_activity })
+        .await
+        .unwrap();
+
+    let user_id = user_activity[0].user_id;
+    let unknown = Address::from_hex("0x1234567890123456789012345678901234567890").unwrap();
+    let first_activity = repository.get_first_activity(&[user_id, unknown]).await.unwrap();
+    assert_eq!(first_activity, HashMap::from([(user_id, 1755182000)]));
+
+    assert!(repository.get_first_activity(&[]).await.unwrap().is_empty());
+}
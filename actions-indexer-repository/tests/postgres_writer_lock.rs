@@ -0,0 +1,20 @@
+//! Integration tests for the PostgreSQL writer lock.
+//!
+//! These tests require a real PostgreSQL database and use SQLx test macros
+//! to ensure proper test isolation and cleanup.
+//!
+//! Run with: `cargo test --test postgres_writer_lock`
+
+use actions_indexer_repository::postgres::WriterLock;
+
+#[sqlx::test(migrations = "src/postgres/migrations")]
+async fn test_writer_lock_is_exclusive(pool: sqlx::PgPool) {
+    let lock = WriterLock::try_acquire(&pool).await.unwrap();
+    assert!(lock.is_some());
+
+    // A second writer is turned away while the lock is held
+    assert!(WriterLock::try_acquire(&pool).await.unwrap().is_none());
+
+    lock.unwrap().release().await.unwrap();
+    assert!(WriterLock::try_acquire(&pool).await.unwrap().is_some());
+}
@@ -19,4 +19,8 @@ pub struct ActionRaw {
     pub block_timestamp: BlockTimestamp,
     pub tx_hash: TxHash,
     pub object_type: ObjectType,
+    /// The index of the action among the actions emitted in its block. With
+    /// the block number, it identifies the action, so blocks processed again
+    /// don't record it twice.
+    pub action_index: u32,
 }
\ No newline at end of file
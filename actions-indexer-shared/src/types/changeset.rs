@@ -1,11 +1,12 @@
-use crate::types::{Action, UserVote, VotesCount};
+use crate::types::{Action, UserActivity, UserVote, VotesCount};
 
 /// Represents a collection of changes to be persisted in the actions repository.
 ///
-/// A `Changeset` bundles new actions, updated user votes, and updated vote counts
-/// together for atomic persistence operations.
+/// A `Changeset` bundles new actions, updated user votes, updated vote counts
+/// and new activity feed entries together for atomic persistence operations.
 pub struct Changeset<'a> {
 	pub actions: &'a [Action],
 	pub user_votes: &'a [UserVote],
-	pub votes_count: &'a [VotesCount]
+	pub votes_count: &'a [VotesCount],
+	pub user_activity: &'a [UserActivity],
 }
\ No newline at end of file
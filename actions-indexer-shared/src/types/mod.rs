@@ -1,5 +1,5 @@
 //! This module defines the core data structures and types used across the actions indexer.
-//! It re-exports specific types like `Action`, `UserVote`, `VotesCount`, `Changeset`, `ActionRaw`, `Vote`, and `VoteValue`.
+//! It re-exports specific types like `Action`, `UserVote`, `VotesCount`, `Changeset`, `ActionRaw`, `Vote`, `VoteValue` and `UserActivity`.
 use alloy::primitives::Address;
 use uuid::Uuid;
 
@@ -9,6 +9,7 @@ mod votes_count;
 mod changeset;
 mod action_raw;
 mod action_vote;
+mod user_activity;
 
 pub use action::Action;
 pub use user_vote::UserVote;
@@ -16,6 +17,7 @@ pub use votes_count::VotesCount;
 pub use changeset::Changeset;
 pub use action_raw::ActionRaw;
 pub use action_vote::{Vote, VoteValue};
+pub use user_activity::{ActivityKind, UserActivity};
 
 pub type EntityId = Uuid;
 pub type GroupId = Uuid;
@@ -23,6 +25,9 @@ pub type SpaceId = Uuid;
 pub type UserAddress = Address;
 pub type VoteCriteria = (UserAddress, EntityId, SpaceId);
 pub type VoteCountCriteria = (EntityId, SpaceId);
+/// The key of an activity feed entry within its user's feed: the block number
+/// and the index of its action in the block.
+pub type ActivityCursor = (u64, u32);
 pub type ActionVersion = u64;
 pub type ActionType = u64;
 pub type ObjectType = u64;
\ No newline at end of file
@@ -0,0 +1,28 @@
+use alloy::primitives::{BlockNumber, TxHash};
+use serde::{Deserialize, Serialize};
+use crate::types::{EntityId, SpaceId, UserAddress, VoteValue};
+
+/// Represents what a user did in an entry of their activity feed.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ActivityKind {
+    /// The user voted on the entity, or removed their vote.
+    Vote(VoteValue),
+}
+
+/// Represents an entry of a user's activity feed.
+///
+/// Entries are keyed by the user and the position of the action they come
+/// from: its block and its index among the actions emitted in the block. The
+/// key only grows, so a feed can be paged through from the most recent entry
+/// with the key of the last entry read.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct UserActivity {
+    pub user_id: UserAddress,
+    pub block_number: BlockNumber,
+    pub action_index: u32,
+    pub kind: ActivityKind,
+    pub entity_id: EntityId,
+    pub space_id: SpaceId,
+    pub tx_hash: TxHash,
+    pub occurred_at: u64,
+}
@@ -5,7 +5,7 @@ use crate::types::{EntityId, SpaceId};
 ///
 /// This struct is intended to store the total number of upvotes and 
 /// downvotes for a particular entity and space.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct VotesCount {
     pub entity_id: EntityId,
     pub space_id: SpaceId,
@@ -1,10 +1,16 @@
 use actions_indexer_pipeline::consumer::ActionsConsumer;
 use actions_indexer_pipeline::loader::ActionsLoader;
+use actions_indexer_pipeline::orchestrator::{Batching, VoteCache};
 use actions_indexer_pipeline::processor::ActionsProcessor;
+use actions_indexer_pipeline::processor::filters::{AccountAgeFilter, ActionFilters, DenylistFilter, RateLimitFilter};
 use actions_indexer_pipeline::consumer::stream::sink::SubstreamsStreamProvider;
-use actions_indexer_repository::{PostgresActionsRepository, PostgresCursorRepository};
+use actions_indexer_repository::{ActionsRepository, PostgresActionsRepository, PostgresCursorRepository};
+use actions_indexer_shared::types::UserAddress;
+use actions_indexer_repository::postgres::{MIGRATOR, WriterLock};
+use indexer_utils::config::Config;
 use std::sync::Arc;
-use crate::config::handlers::VoteHandler;
+use std::time::Duration;
+use crate::config::handlers::register_handlers;
 use crate::errors::IndexingError;
 
 // Use CARGO_MANIFEST_DIR to get path relative to the crate
@@ -14,11 +20,19 @@ const MODULE_NAME: &str = "map_actions";
 /// `Dependencies` struct holds the necessary components for the action indexer.
 ///
 /// It includes a consumer for ingesting actions, a processor for handling
-/// business logic, and a loader for persisting processed data.
+/// business logic, and a loader for persisting processed data, along with
+/// how the orchestrator batches blocks before persisting them, the filters
+/// it holds back spam with and the cache it reads stored votes through. It
+/// holds the database's writer lock, so only one actions indexer writes
+/// votes at a time.
 pub struct Dependencies {
     pub consumer: Box<ActionsConsumer>,
     pub processor: Box<ActionsProcessor>,
     pub loader: Box<ActionsLoader>,
+    pub batching: Batching,
+    pub filters: ActionFilters,
+    pub vote_cache: VoteCache,
+    pub writer_lock: WriterLock,
 }
 
 impl Dependencies {
@@ -30,15 +44,40 @@ impl Dependencies {
     /// # Returns
     ///
     /// A `Result` which is `Ok(Self)` on successful initialization or an
-    /// `IndexingError` if any dependency fails to initialize.
+    /// `IndexingError` if the configuration is missing or invalid, another
+    /// actions indexer is writing to the database, or any dependency fails
+    /// to initialize.
     pub async fn new() -> Result<Self, IndexingError> {
-        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let substreams_endpoint = std::env::var("SUBSTREAMS_ENDPOINT").expect("SUBSTREAMS_ENDPOINT must be set");
-        let substreams_api_token = std::env::var("SUBSTREAMS_API_TOKEN").expect("SUBSTREAMS_API_TOKEN must be set");
+        Self::with_block_range(None).await
+    }
+
+    /// Creates a new `Dependencies` instance whose consumer only streams a
+    /// range of blocks, for backfills.
+    ///
+    /// # Arguments
+    ///
+    /// * `start_block` - The first block to stream
+    /// * `stop_block` - The last block to stream
+    ///
+    /// # Returns
+    ///
+    /// A `Result` which is `Ok(Self)` on successful initialization or an
+    /// `IndexingError` if the configuration is missing or invalid, another
+    /// actions indexer is writing to the database, or any dependency fails
+    /// to initialize.
+    pub async fn for_backfill(start_block: u64, stop_block: u64) -> Result<Self, IndexingError> {
+        // Substreams stop before the stop block
+        Self::with_block_range(Some(format!("{}:{}", start_block, stop_block + 1))).await
+    }
+
+    async fn with_block_range(block_range: Option<String>) -> Result<Self, IndexingError> {
+        let config = Config::load()?;
+        let database_url = config.database_url()?;
+        let substreams_endpoint = config.substreams_endpoint()?.to_string();
+        let substreams_api_token = config.substreams_api_token()?.to_string();
 
         let package_file = PKG_FILE.to_string();
         let module_name = MODULE_NAME.to_string();
-        let block_range = None;
         let params = vec![];
 
         let substreams_stream_provider = SubstreamsStreamProvider::new(
@@ -52,18 +91,56 @@ impl Dependencies {
 
         let actions_consumer = ActionsConsumer::new(Box::new(substreams_stream_provider));
         let mut actions_processor = ActionsProcessor::new();
-        actions_processor.register_handler(1, 0, 0, Arc::new(VoteHandler));
+        register_handlers(&mut actions_processor);
 
-        let pool = sqlx::PgPool::connect(&database_url).await.map_err(|e| IndexingError::Database(e.into()))?;
+        let pool = config
+            .database
+            .connect(database_url)
+            .await
+            .map_err(|e| IndexingError::Database(e.into()))?;
+
+        if config.database.run_migrations {
+            MIGRATOR.run(&pool).await.map_err(|e| IndexingError::Database(e.into()))?;
+        }
 
+        let writer_lock = WriterLock::try_acquire(&pool)
+            .await?
+            .ok_or(IndexingError::WriterLocked)?;
+
+        let actions_repository: Arc<dyn ActionsRepository> =
+            Arc::new(PostgresActionsRepository::new(pool.clone()).await.map_err(|e| IndexingError::ActionsRepository(e))?);
         let actions_loader = ActionsLoader::new(
-            Arc::new(PostgresActionsRepository::new(pool.clone()).await.map_err(|e| IndexingError::ActionsRepository(e))?), 
+            actions_repository.clone(), 
             Arc::new(PostgresCursorRepository::new(pool).await.map_err(|e| IndexingError::CursorRepository(e))?));
 
+        let mut filters = ActionFilters::new();
+        if !config.actions.denied_senders.is_empty() {
+            // Addresses are checked when the config is validated
+            let senders = config
+                .actions
+                .denied_senders
+                .iter()
+                .filter_map(|sender| sender.parse::<UserAddress>().ok());
+            filters = filters.with_filter(Arc::new(DenylistFilter::new(senders)));
+        }
+        if let Some(max_votes) = config.actions.max_votes_per_hour {
+            filters = filters.with_filter(Arc::new(RateLimitFilter::new(max_votes, Duration::from_secs(3600))));
+        }
+        if let Some(min_age) = config.actions.min_sender_age() {
+            filters = filters.with_filter(Arc::new(AccountAgeFilter::new(actions_repository, min_age)));
+        }
+
         Ok(Dependencies {
             consumer: Box::new(actions_consumer),
             processor: Box::new(actions_processor),
             loader: Box::new(actions_loader),
+            batching: Batching {
+                max_actions: config.actions.batch_max_actions,
+                window: config.actions.batch_window(),
+            },
+            filters,
+            vote_cache: VoteCache::new(config.actions.vote_cache_size),
+            writer_lock,
         })
     }
 }
@@ -71,6 +148,8 @@ impl Dependencies {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::config::handlers::VoteHandler;
+    use indexer_utils::config::ConfigError;
     use std::env;
     use serial_test::serial;
     use tempfile::NamedTempFile;
@@ -105,7 +184,6 @@ mod tests {
 
     #[tokio::test]
     #[serial]
-    #[should_panic(expected = "DATABASE_URL must be set")]
     async fn test_dependencies_new_missing_database_url() {
         clear_env_vars();
         unsafe {
@@ -113,12 +191,15 @@ mod tests {
             env::set_var("SUBSTREAMS_API_TOKEN", "test-token");
         }
 
-        let _ = Dependencies::new().await;
+        let result = Dependencies::new().await;
+        assert!(matches!(
+            result,
+            Err(IndexingError::Config(ConfigError::Missing { env: "DATABASE_URL", .. }))
+        ));
     }
 
     #[tokio::test]
     #[serial]
-    #[should_panic(expected = "SUBSTREAMS_ENDPOINT must be set")]
     async fn test_dependencies_new_missing_substreams_endpoint() {
         clear_env_vars();
         unsafe {
@@ -126,12 +207,15 @@ mod tests {
             env::set_var("SUBSTREAMS_API_TOKEN", "test-token");
         }
 
-        let _ = Dependencies::new().await;
+        let result = Dependencies::new().await;
+        assert!(matches!(
+            result,
+            Err(IndexingError::Config(ConfigError::Missing { env: "SUBSTREAMS_ENDPOINT", .. }))
+        ));
     }
 
     #[tokio::test]
     #[serial]
-    #[should_panic(expected = "SUBSTREAMS_API_TOKEN must be set")]
     async fn test_dependencies_new_missing_api_token() {
         clear_env_vars();
         unsafe {
@@ -139,8 +223,11 @@ mod tests {
             env::set_var("SUBSTREAMS_ENDPOINT", "https://test-endpoint.com");
         }
 
-        let _ = Dependencies::new().await;
-
+        let result = Dependencies::new().await;
+        assert!(matches!(
+            result,
+            Err(IndexingError::Config(ConfigError::Missing { env: "SUBSTREAMS_API_TOKEN", .. }))
+        ));
     }
 
     #[tokio::test]
@@ -156,10 +243,10 @@ mod tests {
         let result = Dependencies::new().await;
         assert!(result.is_err());
         
-        if let Err(IndexingError::Database(_)) = result {
+        if let Err(IndexingError::Config(ConfigError::Invalid { env: "DATABASE_URL", .. })) = result {
             // Expected error type - test passes
         } else {
-            panic!("Expected Database error");
+            panic!("Expected Config error");
         }
     }
 
@@ -210,6 +297,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_register_handlers() {
+        let mut processor = ActionsProcessor::new();
+        register_handlers(&mut processor);
+
+        assert_eq!(processor.supported_versions(0, 0), vec![1]);
+    }
+
     #[test]
     fn test_vote_handler_registration() {
         // Test that VoteHandler can be created and used in processor registration
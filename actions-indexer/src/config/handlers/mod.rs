@@ -1,3 +1,21 @@
 mod vote;
 
-pub use vote::VoteHandler;
\ No newline at end of file
+use std::sync::Arc;
+
+use actions_indexer_pipeline::processor::ActionsProcessor;
+
+pub use vote::VoteHandler;
+
+/// Registers the handler of every action version the indexer decodes.
+///
+/// A new payload format is introduced with a handler for its version, next
+/// to the handlers of the versions before it, so actions already emitted with
+/// those versions keep being processed on replays and backfills.
+///
+/// # Arguments
+///
+/// * `processor` - The `ActionsProcessor` to register the handlers with
+pub fn register_handlers(processor: &mut ActionsProcessor) {
+    // Votes on entities, with the vote as the payload's only byte
+    processor.register_handler(1, 0, 0, Arc::new(VoteHandler));
+}
@@ -11,4 +11,8 @@ pub enum IndexingError {
     ActionsRepository(#[from] actions_indexer_repository::ActionsRepositoryError),
     #[error("Cursor repository error: {0}")]
     CursorRepository(#[from] actions_indexer_repository::CursorRepositoryError),
+    #[error("Configuration error: {0}")]
+    Config(#[from] indexer_utils::config::ConfigError),
+    #[error("Another actions indexer is writing votes to this database")]
+    WriterLocked,
 }
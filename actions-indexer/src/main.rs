@@ -1,7 +1,110 @@
+//! Indexes actions into the actions database.
+//!
+//! ```text
+//! actions-indexer
+//! actions-indexer backfill --start <block> --stop <block>
+//! ```
+//!
+//! Without a command, the indexer follows the chain from its cursor.
+//!
+//! `backfill` consumes the actions of the blocks from `--start` to `--stop`,
+//! both included, and exits once the stop block is indexed. Actions that are
+//! already stored are skipped, so a range can be backfilled again after an
+//! interruption, which resumes from the backfill's own cursor.
+//!
+//! Only one actions indexer writes to a database at a time, as vote counts
+//! are computed from the totals read before each batch is written. A backfill
+//! started while the live indexer runs exits with an error, as does the live
+//! indexer started during a backfill.
+
+use std::{env, process::ExitCode};
+
 use actions_indexer::{Dependencies, IndexingError};
 use dotenv::dotenv;
 use actions_indexer_pipeline::orchestrator::Orchestrator;
 
+const USAGE: &str = "Usage:
+  actions-indexer
+  actions-indexer backfill --start <block> --stop <block>";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Run,
+    Backfill { start_block: u64, stop_block: u64 },
+}
+
+/// Parses the value of a block number option.
+fn block_number(option: &str, value: Option<&String>) -> Result<u64, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| format!("{} expects a block number", option))
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, options)) = args.split_first() else {
+        return Ok(Command::Run);
+    };
+
+    match command.as_str() {
+        "backfill" => {
+            let (mut start_block, mut stop_block) = (None, None);
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--start" => start_block = Some(block_number(option, options.next())?),
+                    "--stop" => stop_block = Some(block_number(option, options.next())?),
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            let (Some(start_block), Some(stop_block)) = (start_block, stop_block) else {
+                return Err("backfill expects a --start and a --stop block".to_string());
+            };
+            if start_block > stop_block {
+                return Err("--start must not be after --stop".to_string());
+            }
+
+            Ok(Command::Backfill {
+                start_block,
+                stop_block,
+            })
+        }
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+async fn run(command: Command) -> Result<(), IndexingError> {
+    let (dependencies, cursor_id) = match command {
+        Command::Run => (Dependencies::new().await?, None),
+        Command::Backfill {
+            start_block,
+            stop_block,
+        } => (
+            Dependencies::for_backfill(start_block, stop_block).await?,
+            Some(format!("actions_indexer_backfill_{}_{}", start_block, stop_block)),
+        ),
+    };
+
+    // Held until the orchestrator stops
+    let writer_lock = dependencies.writer_lock;
+
+    let mut orchestrator = Orchestrator::new(
+        dependencies.consumer,
+        dependencies.processor,
+        dependencies.loader,
+    )
+    .with_batching(dependencies.batching)
+    .with_filters(dependencies.filters)
+    .with_vote_cache(dependencies.vote_cache);
+    if let Some(cursor_id) = cursor_id {
+        orchestrator = orchestrator.with_cursor_id(cursor_id);
+    }
+    orchestrator.run().await?;
+    writer_lock.release().await?;
+    Ok(())
+}
+
 /// Main entry point for the Actions Indexer application.
 ///
 /// Initializes dotenv, sets up application dependencies, and starts the
@@ -9,19 +112,60 @@ use actions_indexer_pipeline::orchestrator::Orchestrator;
 ///
 /// # Returns
 ///
-/// A `Result` indicating success or an `IndexingError` if an
-/// error occurs during initialization or execution.
+/// `ExitCode::SUCCESS` once the orchestrator stops, or exit status 2 if the
+/// arguments are invalid or an error occurs during initialization or
+/// execution.
 #[tokio::main]
-async fn main() -> Result<(), IndexingError> {
+async fn main() -> ExitCode {
     dotenv().ok();
 
-    let dependencies = Dependencies::new().await?;
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
 
-    let orchestrator = Orchestrator::new(
-        dependencies.consumer,
-        dependencies.processor,
-        dependencies.loader,
-    );
-    orchestrator.run().await?;
-    Ok(())
+    match run(command).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(&args(&[])), Ok(Command::Run));
+        assert_eq!(
+            parse_args(&args(&["backfill", "--start", "100", "--stop", "200"])),
+            Ok(Command::Backfill {
+                start_block: 100,
+                stop_block: 200
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["backfill", "--stop", "100", "--start", "100"])),
+            Ok(Command::Backfill {
+                start_block: 100,
+                stop_block: 100
+            })
+        );
+        assert!(parse_args(&args(&["backfill", "--start", "100"])).is_err());
+        assert!(parse_args(&args(&["backfill", "--start", "-1", "--stop", "5"])).is_err());
+        assert!(parse_args(&args(&["backfill", "--start", "200", "--stop", "100"])).is_err());
+        assert!(parse_args(&args(&["backfill", "--start", "1", "--stop", "2", "--fix"])).is_err());
+        assert!(parse_args(&args(&["replay"])).is_err());
+    }
 }
@@ -0,0 +1,10 @@
+CREATE TABLE "edits" (
+	"space_id" uuid NOT NULL,
+	"content_hash" text NOT NULL,
+	"cid" text NOT NULL,
+	"created_at" text NOT NULL,
+	"created_at_block" text NOT NULL,
+	CONSTRAINT "edits_space_id_content_hash_pk" PRIMARY KEY("space_id","content_hash")
+);
+--> statement-breakpoint
+ALTER TABLE "ipfs_cache" ADD COLUMN "content_hash" text;
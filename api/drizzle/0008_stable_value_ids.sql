@@ -0,0 +1,10 @@
+-- Value ids were derived with Rust's DefaultHasher, which is not stable across
+-- Rust releases. Recompute them as the MD5 of
+-- `value:{entity_id}:{property_id}:{space_id}` with the UUID version and
+-- variant bits set, matching `indexer_utils::id::derive_value_id`.
+UPDATE "values" SET "id" = (
+	SELECT substr(h, 1, 8) || '-' || substr(h, 9, 4) || '-4' || substr(h, 14, 3) || '-'
+		|| substr('89ab', (('x' || substr(h, 17, 1))::bit(4)::int & 3) + 1, 1) || substr(h, 18, 3) || '-'
+		|| substr(h, 21, 12)
+	FROM md5('value:' || "entity_id" || ':' || "property_id" || ':' || "space_id") AS h
+);
@@ -0,0 +1,2 @@
+ALTER TABLE "spaces" ADD COLUMN "network" text DEFAULT 'KJjKetFsGVSbw9qFpRzRSy' NOT NULL;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "chain_id" bigint;
@@ -0,0 +1,19 @@
+ALTER TABLE "properties" ADD COLUMN "name" text;--> statement-breakpoint
+ALTER TABLE "properties" ADD COLUMN "description" text;--> statement-breakpoint
+ALTER TABLE "properties" ADD COLUMN "renderable_type" uuid;--> statement-breakpoint
+UPDATE "properties" p SET
+	"name" = (
+		SELECT v."string" FROM "values" v
+		WHERE v."entity_id" = p."id" AND v."property_id" = 'a126ca53-0c8e-48d5-b888-82c734c38935'
+		ORDER BY v."space_id" LIMIT 1
+	),
+	"description" = (
+		SELECT v."string" FROM "values" v
+		WHERE v."entity_id" = p."id" AND v."property_id" = '9b1f76ff-9711-404c-861e-59dc3fa7d037'
+		ORDER BY v."space_id" LIMIT 1
+	),
+	"renderable_type" = (
+		SELECT r."to_entity_id" FROM "relations" r
+		WHERE r."from_entity_id" = p."id" AND r."type_id" = '2316bbe1-c76f-4635-83f2-3e03b4f1fe46'
+		ORDER BY r."space_id", r."position" NULLS LAST, r."id" LIMIT 1
+	);
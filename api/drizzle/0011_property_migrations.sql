@@ -0,0 +1,14 @@
+CREATE TABLE "property_migrations" (
+	"id" serial PRIMARY KEY NOT NULL,
+	"property_id" uuid NOT NULL,
+	"space_id" uuid NOT NULL,
+	"from_type" "dataTypes" NOT NULL,
+	"to_type" "dataTypes" NOT NULL,
+	"applied" boolean NOT NULL,
+	"reason" text,
+	"converted_values" integer NOT NULL,
+	"created_at" text NOT NULL,
+	"created_at_block" text NOT NULL
+);
+--> statement-breakpoint
+CREATE INDEX "property_migrations_property_id_idx" ON "property_migrations" USING btree ("property_id");
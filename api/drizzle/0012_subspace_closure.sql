@@ -0,0 +1,21 @@
+CREATE TABLE "subspace_closure" (
+	"ancestor_space_id" uuid NOT NULL,
+	"descendant_space_id" uuid NOT NULL,
+	"depth" integer NOT NULL,
+	CONSTRAINT "subspace_closure_ancestor_space_id_descendant_space_id_pk" PRIMARY KEY("ancestor_space_id","descendant_space_id")
+);
+--> statement-breakpoint
+CREATE INDEX "subspace_closure_descendant_space_id_idx" ON "subspace_closure" USING btree ("descendant_space_id");--> statement-breakpoint
+WITH RECURSIVE ancestors(descendant_space_id, ancestor_space_id, depth, path) AS (
+	SELECT s.child_space_id, s.parent_space_id, 1, ARRAY[s.child_space_id, s.parent_space_id]
+	FROM subspaces s
+	UNION ALL
+	SELECT a.descendant_space_id, s.parent_space_id, a.depth + 1, a.path || s.parent_space_id
+	FROM ancestors a
+	JOIN subspaces s ON s.child_space_id = a.ancestor_space_id
+	WHERE NOT s.parent_space_id = ANY(a.path)
+)
+INSERT INTO "subspace_closure" ("ancestor_space_id", "descendant_space_id", "depth")
+SELECT ancestor_space_id, descendant_space_id, MIN(depth)
+FROM ancestors
+GROUP BY ancestor_space_id, descendant_space_id;
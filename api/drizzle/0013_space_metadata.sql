@@ -0,0 +1,35 @@
+ALTER TABLE "spaces" ADD COLUMN "entity_id" uuid;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "name" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "description" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "cover" text;--> statement-breakpoint
+UPDATE "spaces" s SET
+	"entity_id" = e.id,
+	"name" = (
+		SELECT v.string FROM "values" v
+		WHERE v.entity_id = e.id AND v.property_id = 'a126ca53-0c8e-48d5-b888-82c734c38935' AND v.space_id = s.id -- SystemIds.NAME_PROPERTY
+	),
+	"description" = (
+		SELECT v.string FROM "values" v
+		WHERE v.entity_id = e.id AND v.property_id = '9b1f76ff-9711-404c-861e-59dc3fa7d037' AND v.space_id = s.id -- SystemIds.DESCRIPTION_PROPERTY
+	),
+	"cover" = (
+		SELECT v.string FROM relations r
+		JOIN "values" v ON v.entity_id = r.to_entity_id
+			AND v.space_id = COALESCE(r.to_space_id, r.space_id)
+			AND v.property_id = '8a743832-c094-4a62-b665-0c3cc2f9c7bc' -- SystemIds.IMAGE_URL_PROPERTY
+		WHERE r.from_entity_id = e.id AND r.type_id = '34f53507-2e6b-42c5-a844-43981a77cfa2' AND r.space_id = s.id -- SystemIds.COVER_PROPERTY
+		ORDER BY r.position NULLS LAST, r.id
+		LIMIT 1
+	)
+FROM (
+	SELECT sp.id AS space_id, (
+		SELECT r.from_entity_id FROM relations r
+		WHERE r.space_id = sp.id
+			AND r.type_id = '8f151ba4-de20-4e3c-9cb4-99ddf96f48f1' -- SystemIds.TYPES_PROPERTY
+			AND r.to_entity_id = '362c1dbd-dc64-44bb-a3c4-652f38a642d7' -- SystemIds.SPACE_TYPE
+		ORDER BY r.position NULLS LAST, r.id
+		LIMIT 1
+	) AS id
+	FROM "spaces" sp
+) e
+WHERE e.space_id = s.id;
@@ -0,0 +1,22 @@
+CREATE TABLE "webhooks" (
+	"id" uuid PRIMARY KEY NOT NULL,
+	"url" text NOT NULL,
+	"secret" text NOT NULL,
+	"space_id" uuid,
+	"event_types" text[] DEFAULT '{}' NOT NULL,
+	"created_at" timestamp with time zone DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE TABLE "webhook_dead_letters" (
+	"id" serial PRIMARY KEY NOT NULL,
+	"webhook_id" uuid NOT NULL,
+	"notification_id" uuid NOT NULL,
+	"event_type" text NOT NULL,
+	"payload" jsonb NOT NULL,
+	"error" text NOT NULL,
+	"attempts" integer NOT NULL,
+	"block_number" bigint NOT NULL,
+	"failed_at" timestamp with time zone DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE INDEX "webhook_dead_letters_webhook_id_idx" ON "webhook_dead_letters" USING btree ("webhook_id");
@@ -0,0 +1,38 @@
+CREATE TABLE "space_activity_rollups" (
+	"space_id" uuid NOT NULL,
+	"period" text NOT NULL,
+	"period_start" date NOT NULL,
+	"edits" integer DEFAULT 0 NOT NULL,
+	"active_editors" integer DEFAULT 0 NOT NULL,
+	"proposals_created" integer DEFAULT 0 NOT NULL,
+	"proposals_executed" integer DEFAULT 0 NOT NULL,
+	"new_entities" integer DEFAULT 0 NOT NULL,
+	CONSTRAINT "space_activity_rollups_space_id_period_period_start_pk" PRIMARY KEY("space_id","period","period_start")
+);
+--> statement-breakpoint
+CREATE TABLE "space_active_editors" (
+	"space_id" uuid NOT NULL,
+	"period" text NOT NULL,
+	"period_start" date NOT NULL,
+	"editor_id" uuid NOT NULL,
+	CONSTRAINT "space_active_editors_space_id_period_period_start_editor_id_pk" PRIMARY KEY("space_id","period","period_start","editor_id")
+);
+--> statement-breakpoint
+CREATE TABLE "counted_proposals" (
+	"space_id" uuid NOT NULL,
+	"proposal_id" text NOT NULL,
+	"event" text NOT NULL,
+	CONSTRAINT "counted_proposals_space_id_proposal_id_event_pk" PRIMARY KEY("space_id","proposal_id","event")
+);
+--> statement-breakpoint
+CREATE INDEX "space_activity_rollups_period_period_start_idx" ON "space_activity_rollups" USING btree ("period","period_start");--> statement-breakpoint
+INSERT INTO "space_activity_rollups" ("space_id", "period", "period_start", "edits")
+SELECT e.space_id, p.period, p.period_start, COUNT(*)
+FROM "edits" e
+CROSS JOIN LATERAL (
+	VALUES
+		('day', (to_timestamp(e.created_at::bigint) AT TIME ZONE 'UTC')::date),
+		('week', date_trunc('week', to_timestamp(e.created_at::bigint) AT TIME ZONE 'UTC')::date)
+) AS p(period, period_start)
+WHERE e.created_at ~ '^[0-9]+$'
+GROUP BY e.space_id, p.period, p.period_start;
@@ -0,0 +1,9 @@
+CREATE TABLE "unverified_edits" (
+	"space_id" uuid NOT NULL,
+	"cid" text NOT NULL,
+	"sender" text,
+	"rejected" boolean NOT NULL,
+	"created_at" text NOT NULL,
+	"created_at_block" text NOT NULL,
+	CONSTRAINT "unverified_edits_space_id_cid_pk" PRIMARY KEY("space_id","cid")
+);
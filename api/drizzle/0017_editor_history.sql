@@ -0,0 +1,21 @@
+CREATE TABLE "editor_history" (
+	"space_id" uuid NOT NULL,
+	"address" text NOT NULL,
+	"added_at_block" bigint NOT NULL,
+	"removed_at_block" bigint,
+	CONSTRAINT "editor_history_space_id_address_added_at_block_pk" PRIMARY KEY("space_id","address","added_at_block")
+);
+--> statement-breakpoint
+CREATE TABLE "unauthorized_edits" (
+	"space_id" uuid NOT NULL,
+	"cid" text NOT NULL,
+	"sender" text,
+	"created_at" text NOT NULL,
+	"created_at_block" text NOT NULL,
+	CONSTRAINT "unauthorized_edits_space_id_cid_pk" PRIMARY KEY("space_id","cid")
+);
+--> statement-breakpoint
+CREATE INDEX "editor_history_space_id_address_idx" ON "editor_history" USING btree ("space_id","address");--> statement-breakpoint
+INSERT INTO "editor_history" ("space_id", "address", "added_at_block")
+SELECT "space_id", "address", 0
+FROM "editors";
@@ -0,0 +1,13 @@
+CREATE TABLE "processed_blocks" (
+	"indexer_id" text NOT NULL,
+	"block_number" bigint NOT NULL,
+	"cursor" text NOT NULL,
+	"processed_at" timestamp with time zone DEFAULT now() NOT NULL,
+	CONSTRAINT "processed_blocks_indexer_id_block_number_pk" PRIMARY KEY("indexer_id","block_number")
+);
+--> statement-breakpoint
+CREATE TABLE "counted_blocks" (
+	"space_id" uuid NOT NULL,
+	"block_number" bigint NOT NULL,
+	CONSTRAINT "counted_blocks_space_id_block_number_pk" PRIMARY KEY("space_id","block_number")
+);
@@ -0,0 +1,5 @@
+ALTER TABLE "ipfs_cache" ADD COLUMN "content_length" integer;--> statement-breakpoint
+ALTER TABLE "ipfs_cache" ADD COLUMN "compression" text;--> statement-breakpoint
+ALTER TABLE "ipfs_cache" ADD COLUMN "encoding" text;--> statement-breakpoint
+ALTER TABLE "ipfs_cache" ADD COLUMN "fetch_duration_ms" integer;--> statement-breakpoint
+ALTER TABLE "ipfs_cache" ADD COLUMN "gateway" text;
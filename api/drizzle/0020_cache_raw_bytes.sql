@@ -0,0 +1 @@
+ALTER TABLE "ipfs_cache" ADD COLUMN "raw_bytes" bytea;
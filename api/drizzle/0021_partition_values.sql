@@ -0,0 +1,59 @@
+-- Partitions "values" by a hash of space_id, so the indexes of each partition
+-- stay small and partitions are vacuumed on their own. Queries that filter by
+-- space_id only scan the partition of that space. The indexer can change the
+-- number of partitions with `database.value_partitions`.
+--
+-- The primary key has to include space_id. Value ids are derived from their
+-- space, so they stay unique on their own. The foreign keys have been NOT
+-- VALID with their triggers disabled since 0003, and partitioned tables can't
+-- have NOT VALID foreign keys, so they aren't added back.
+CREATE TABLE "values_partitioned" (
+	"id" text NOT NULL,
+	"property_id" uuid NOT NULL,
+	"entity_id" uuid NOT NULL,
+	"space_id" uuid NOT NULL,
+	"string" text,
+	"language" text,
+	"unit" text,
+	"boolean" boolean,
+	"number" numeric,
+	"point" text,
+	"time" text
+) PARTITION BY HASH ("space_id");
+--> statement-breakpoint
+DO $$
+BEGIN
+	FOR remainder IN 0..15 LOOP
+		EXECUTE format(
+			'CREATE TABLE %I PARTITION OF "values_partitioned" FOR VALUES WITH (MODULUS 16, REMAINDER %s)',
+			'values_16_' || remainder,
+			remainder
+		);
+	END LOOP;
+END $$;
+--> statement-breakpoint
+INSERT INTO "values_partitioned" ("id", "property_id", "entity_id", "space_id", "string", "language", "unit", "boolean", "number", "point", "time")
+SELECT "id", "property_id", "entity_id", "space_id", "string", "language", "unit", "boolean", "number", "point", "time"
+FROM "values";
+--> statement-breakpoint
+DROP TABLE "values";
+--> statement-breakpoint
+ALTER TABLE "values_partitioned" RENAME TO "values";
+--> statement-breakpoint
+ALTER TABLE "values" ADD CONSTRAINT "values_id_space_id_pk" PRIMARY KEY("id","space_id");--> statement-breakpoint
+CREATE INDEX "values_property_id_idx" ON "values" USING btree ("property_id");--> statement-breakpoint
+CREATE INDEX "values_entity_id_idx" ON "values" USING btree ("entity_id");--> statement-breakpoint
+CREATE INDEX "values_space_id_idx" ON "values" USING btree ("space_id");--> statement-breakpoint
+CREATE INDEX "values_text_idx" ON "values" USING btree ("string");--> statement-breakpoint
+CREATE INDEX "values_number_idx" ON "values" USING btree ("number");--> statement-breakpoint
+CREATE INDEX "values_point_idx" ON "values" USING btree ("point");--> statement-breakpoint
+CREATE INDEX "values_boolean_idx" ON "values" USING btree ("boolean");--> statement-breakpoint
+CREATE INDEX "values_time_idx" ON "values" USING btree ("time");--> statement-breakpoint
+CREATE INDEX "values_entity_property_idx" ON "values" USING btree ("entity_id","property_id");--> statement-breakpoint
+CREATE INDEX "values_entity_space_idx" ON "values" USING btree ("entity_id","space_id");--> statement-breakpoint
+CREATE INDEX "values_property_space_idx" ON "values" USING btree ("property_id","space_id");--> statement-breakpoint
+CREATE INDEX "values_entity_property_space_idx" ON "values" USING btree ("entity_id","property_id","space_id");--> statement-breakpoint
+CREATE INDEX "values_space_text_idx" ON "values" USING btree ("space_id","string");--> statement-breakpoint
+CREATE INDEX "values_language_idx" ON "values" USING btree ("language");--> statement-breakpoint
+CREATE INDEX "values_unit_idx" ON "values" USING btree ("unit");--> statement-breakpoint
+CREATE INDEX "values_text_gin_trgm_idx" ON "values" USING GIN ("string" gin_trgm_ops);
@@ -0,0 +1 @@
+ALTER TABLE "values" ADD COLUMN "options" jsonb;
@@ -0,0 +1,2 @@
+CREATE INDEX "relations_space_type_page_idx" ON "relations" USING btree ("space_id","type_id","id");--> statement-breakpoint
+CREATE INDEX "relations_from_entity_page_idx" ON "relations" USING btree ("from_entity_id","id");
@@ -0,0 +1,15 @@
+CREATE TABLE "proposal_previews" (
+	"space_id" uuid NOT NULL,
+	"proposal_id" text NOT NULL,
+	"content_uri" text NOT NULL,
+	"entity_ids" uuid[] NOT NULL,
+	"property_ids" uuid[] NOT NULL,
+	"values_set" integer NOT NULL,
+	"values_unset" integer NOT NULL,
+	"relations_created" integer NOT NULL,
+	"relations_updated" integer NOT NULL,
+	"relations_deleted" integer NOT NULL,
+	"entities_deleted" integer NOT NULL,
+	"created_at_block" text NOT NULL,
+	CONSTRAINT "proposal_previews_space_id_proposal_id_pk" PRIMARY KEY("space_id","proposal_id")
+);
@@ -0,0 +1,4 @@
+ALTER TABLE "values" ADD COLUMN "edit_id" uuid;--> statement-breakpoint
+ALTER TABLE "relations" ADD COLUMN "edit_id" uuid;--> statement-breakpoint
+ALTER TABLE "edits" ADD COLUMN "edit_id" uuid;--> statement-breakpoint
+CREATE INDEX "edits_edit_id_idx" ON "edits" USING btree ("edit_id");
@@ -0,0 +1,13 @@
+CREATE TABLE "ops_journal" (
+	"sequence" bigserial PRIMARY KEY NOT NULL,
+	"block_number" bigint NOT NULL,
+	"space_id" uuid NOT NULL,
+	"edit_id" uuid,
+	"content_hash" text NOT NULL,
+	"op_index" integer NOT NULL,
+	"op_kind" text NOT NULL,
+	"target_id" uuid NOT NULL,
+	CONSTRAINT "ops_journal_space_id_content_hash_op_index_unique" UNIQUE("space_id","content_hash","op_index")
+);
+--> statement-breakpoint
+CREATE INDEX "ops_journal_space_id_sequence_idx" ON "ops_journal" USING btree ("space_id","sequence");
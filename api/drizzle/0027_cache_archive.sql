@@ -0,0 +1,18 @@
+CREATE TABLE "ipfs_cache_archive" (
+	"uri" text PRIMARY KEY NOT NULL,
+	"json" jsonb,
+	"is_errored" boolean DEFAULT false NOT NULL,
+	"block" text NOT NULL,
+	"space" uuid NOT NULL,
+	"content_hash" text,
+	"content_length" integer,
+	"compression" text,
+	"encoding" text,
+	"fetch_duration_ms" integer,
+	"gateway" text,
+	"raw_bytes" bytea,
+	"archived_at" timestamp with time zone DEFAULT now() NOT NULL
+);
+--> statement-breakpoint
+CREATE INDEX "edits_cid_idx" ON "edits" USING btree ("cid");--> statement-breakpoint
+CREATE INDEX "edits_content_hash_idx" ON "edits" USING btree ("content_hash");
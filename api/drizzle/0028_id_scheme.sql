@@ -0,0 +1,14 @@
+CREATE TABLE "id_scheme" (
+	"id" boolean PRIMARY KEY DEFAULT true NOT NULL,
+	"scheme" text NOT NULL,
+	"selected_at" timestamp with time zone DEFAULT now() NOT NULL,
+	CONSTRAINT "id_scheme_singleton" CHECK ("id_scheme"."id")
+);
+--> statement-breakpoint
+CREATE TABLE "id_mappings" (
+	"kind" text NOT NULL,
+	"scheme" text NOT NULL,
+	"old_id" text NOT NULL,
+	"new_id" uuid NOT NULL,
+	CONSTRAINT "id_mappings_kind_scheme_old_id_pk" PRIMARY KEY("kind","scheme","old_id")
+);
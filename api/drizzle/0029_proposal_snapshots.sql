@@ -0,0 +1,9 @@
+CREATE TABLE "proposal_snapshots" (
+	"space_id" uuid NOT NULL,
+	"proposal_id" text NOT NULL,
+	"role" text NOT NULL,
+	"address" text NOT NULL,
+	"created_at_block" text NOT NULL,
+	CONSTRAINT "proposal_snapshots_space_id_proposal_id_role_address_pk" PRIMARY KEY("space_id","proposal_id","role","address"),
+	CONSTRAINT "proposal_snapshots_role_check" CHECK ("proposal_snapshots"."role" IN ('editor', 'member'))
+);
@@ -0,0 +1,16 @@
+CREATE TABLE "proposals" (
+	"plugin_address" text NOT NULL,
+	"proposal_id" text NOT NULL,
+	"space_id" uuid NOT NULL,
+	"proposal_type" text NOT NULL,
+	"creator" text NOT NULL,
+	"status" text DEFAULT 'created' NOT NULL,
+	"created_at_block" text NOT NULL,
+	"executed_at_block" text,
+	"canceled_by" text,
+	"canceled_at_block" text,
+	CONSTRAINT "proposals_plugin_address_proposal_id_pk" PRIMARY KEY("plugin_address","proposal_id"),
+	CONSTRAINT "proposals_status_check" CHECK ("proposals"."status" IN ('created', 'executed', 'canceled'))
+);
+--> statement-breakpoint
+CREATE INDEX "proposals_space_id_idx" ON "proposals" USING btree ("space_id");
\ No newline at end of file
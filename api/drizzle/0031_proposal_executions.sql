@@ -0,0 +1,2 @@
+ALTER TABLE "proposals" ADD COLUMN "executed_at" text;--> statement-breakpoint
+ALTER TABLE "proposals" ADD COLUMN "executed_tx_hash" text;
\ No newline at end of file
@@ -0,0 +1,5 @@
+ALTER TABLE "spaces" ADD COLUMN "member_count" integer DEFAULT 0 NOT NULL;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "editor_count" integer DEFAULT 0 NOT NULL;--> statement-breakpoint
+UPDATE "spaces" SET
+	"member_count" = (SELECT count(*) FROM "members" WHERE "members"."space_id" = "spaces"."id"),
+	"editor_count" = (SELECT count(*) FROM "editors" WHERE "editors"."space_id" = "spaces"."id");
\ No newline at end of file
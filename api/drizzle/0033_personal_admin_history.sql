@@ -0,0 +1,13 @@
+CREATE TABLE "personal_admin_history" (
+	"space_id" uuid NOT NULL,
+	"personal_address" text NOT NULL,
+	"admin_address" text,
+	"added_at_block" bigint NOT NULL,
+	"replaced_at_block" bigint,
+	CONSTRAINT "personal_admin_history_pk" PRIMARY KEY("space_id","personal_address","added_at_block")
+);
+--> statement-breakpoint
+INSERT INTO "personal_admin_history" ("space_id", "personal_address", "added_at_block")
+SELECT "id", "personal_address", 0
+FROM "spaces"
+WHERE "type" = 'Personal' AND "personal_address" IS NOT NULL;
\ No newline at end of file
@@ -0,0 +1,20 @@
+CREATE TABLE "space_plugins_history" (
+	"space_id" uuid NOT NULL,
+	"plugin_type" text NOT NULL,
+	"plugin_address" text NOT NULL,
+	"added_at_block" bigint NOT NULL,
+	"replaced_at_block" bigint,
+	CONSTRAINT "space_plugins_history_pk" PRIMARY KEY("space_id","plugin_type","plugin_address","added_at_block"),
+	CONSTRAINT "space_plugins_history_plugin_type_check" CHECK ("space_plugins_history"."plugin_type" IN ('voting', 'membership'))
+);
+--> statement-breakpoint
+CREATE INDEX "space_plugins_history_plugin_address_idx" ON "space_plugins_history" USING btree ("plugin_address");--> statement-breakpoint
+CREATE INDEX "personal_admin_history_personal_address_idx" ON "personal_admin_history" USING btree ("personal_address");--> statement-breakpoint
+INSERT INTO "space_plugins_history" ("space_id", "plugin_type", "plugin_address", "added_at_block")
+SELECT "id", 'voting', "main_voting_address", 0
+FROM "spaces"
+WHERE "type" = 'Public' AND "main_voting_address" IS NOT NULL
+UNION ALL
+SELECT "id", 'membership', "membership_address", 0
+FROM "spaces"
+WHERE "type" = 'Public' AND "membership_address" IS NOT NULL;
\ No newline at end of file
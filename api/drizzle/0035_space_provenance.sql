@@ -0,0 +1,4 @@
+ALTER TABLE "spaces" ADD COLUMN "creator" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "created_tx_hash" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "created_at" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "created_at_block" text;
\ No newline at end of file
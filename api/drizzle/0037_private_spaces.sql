@@ -0,0 +1,72 @@
+ALTER TABLE "spaces" ADD COLUMN "is_private" boolean DEFAULT false NOT NULL;--> statement-breakpoint
+DO $$
+BEGIN
+	IF NOT EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'gaia_reader') THEN
+		CREATE ROLE gaia_reader NOLOGIN;
+	END IF;
+END
+$$;--> statement-breakpoint
+GRANT gaia_reader TO CURRENT_USER;--> statement-breakpoint
+GRANT USAGE ON SCHEMA public TO gaia_reader;--> statement-breakpoint
+GRANT SELECT ON ALL TABLES IN SCHEMA public TO gaia_reader;--> statement-breakpoint
+ALTER DEFAULT PRIVILEGES IN SCHEMA public GRANT SELECT ON TABLES TO gaia_reader;--> statement-breakpoint
+REVOKE SELECT ON "api_keys", "api_key_usage" FROM gaia_reader;--> statement-breakpoint
+-- Partitions don't inherit their table's policies, so they're only read
+-- through the table
+DO $$
+DECLARE
+	partition regclass;
+BEGIN
+	FOR partition IN SELECT inhrelid::regclass FROM pg_inherits WHERE inhparent = 'public.values'::regclass LOOP
+		EXECUTE format('REVOKE SELECT ON %s FROM gaia_reader', partition);
+	END LOOP;
+END
+$$;--> statement-breakpoint
+-- The private spaces the viewer of the request isn't a member or editor of.
+-- The viewer is the verified address the API sets in `gaia.viewer`. Runs as
+-- its owner, so it sees the spaces the policies below hide.
+CREATE OR REPLACE FUNCTION public.unreadable_space_ids() RETURNS uuid[]
+LANGUAGE sql STABLE SECURITY DEFINER SET search_path = public AS $$
+	SELECT coalesce(array_agg(s.id), '{}')
+	FROM spaces s
+	WHERE s.is_private
+		AND NOT EXISTS (
+			SELECT 1 FROM members m
+			WHERE m.space_id = s.id AND m.address = current_setting('gaia.viewer', true)
+		)
+		AND NOT EXISTS (
+			SELECT 1 FROM editors e
+			WHERE e.space_id = s.id AND e.address = current_setting('gaia.viewer', true)
+		)
+$$;--> statement-breakpoint
+COMMENT ON FUNCTION public.unreadable_space_ids() IS E'@omit';--> statement-breakpoint
+-- The API queries as gaia_reader, which only sees the content of private
+-- spaces its viewer belongs to. Every other role is unaffected.
+ALTER TABLE "spaces" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "spaces_private_read" ON "spaces" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "values" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "values_private_read" ON "values" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "relations" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "relations_private_read" ON "relations" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "edits" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "edits_private_read" ON "edits" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "proposals" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "proposals_private_read" ON "proposals" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "proposal_previews" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "proposal_previews_private_read" ON "proposal_previews" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "ops_journal" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "ops_journal_private_read" ON "ops_journal" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);
\ No newline at end of file
@@ -0,0 +1,35 @@
+CREATE INDEX "idx_user_votes_entity_space" ON "user_votes" USING btree ("entity_id","space_id");--> statement-breakpoint
+-- Votes on entities, most recent first. Removed votes (vote_type 2) no longer
+-- count towards their entity, so they're left out.
+CREATE OR REPLACE FUNCTION public.votes_by_entity(entity_id uuid, space_id uuid DEFAULT NULL) RETURNS SETOF public.user_votes AS $$
+  SELECT v.*
+  FROM user_votes v
+  WHERE v.entity_id = votes_by_entity.entity_id
+    AND (votes_by_entity.space_id IS NULL OR v.space_id = votes_by_entity.space_id)
+    AND v.vote_type <> 2
+  ORDER BY v.voted_at DESC, v.user_id;
+$$ LANGUAGE sql STABLE;--> statement-breakpoint
+-- Voters are stored as lowercase hex, so any casing of the address matches.
+CREATE OR REPLACE FUNCTION public.votes_by_user(user_id text, space_id uuid DEFAULT NULL) RETURNS SETOF public.user_votes AS $$
+  SELECT v.*
+  FROM user_votes v
+  WHERE v.user_id = lower(votes_by_user.user_id)
+    AND (votes_by_user.space_id IS NULL OR v.space_id = votes_by_user.space_id)
+    AND v.vote_type <> 2
+  ORDER BY v.voted_at DESC, v.entity_id;
+$$ LANGUAGE sql STABLE;--> statement-breakpoint
+CREATE OR REPLACE FUNCTION public.top_voted_entities(space_id uuid) RETURNS SETOF public.votes_count AS $$
+  SELECT c.*
+  FROM votes_count c
+  WHERE c.space_id = top_voted_entities.space_id
+  ORDER BY c.upvotes - c.downvotes DESC, c.upvotes DESC, c.entity_id;
+$$ LANGUAGE sql STABLE;--> statement-breakpoint
+-- Votes in private spaces are as private as the spaces' content
+ALTER TABLE "user_votes" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "user_votes_private_read" ON "user_votes" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "votes_count" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "votes_count_private_read" ON "votes_count" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);
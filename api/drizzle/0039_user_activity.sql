@@ -0,0 +1,17 @@
+CREATE TABLE "user_activity" (
+	"user_id" varchar(42) NOT NULL,
+	"block_number" bigint NOT NULL,
+	"action_index" integer NOT NULL,
+	"activity_type" text NOT NULL,
+	"vote_type" smallint,
+	"entity_id" uuid NOT NULL,
+	"space_id" uuid NOT NULL,
+	"tx_hash" varchar(66) NOT NULL,
+	"occurred_at" timestamp with time zone NOT NULL,
+	CONSTRAINT "user_activity_user_id_block_number_action_index_pk" PRIMARY KEY("user_id","block_number","action_index")
+);
+--> statement-breakpoint
+ALTER TABLE "user_activity" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "user_activity_private_read" ON "user_activity" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);
@@ -0,0 +1,24 @@
+-- Actions are keyed by their block and their index among the actions emitted
+-- in the block, so blocks processed again don't record them twice. Copies of
+-- an action recorded by earlier replays are removed, and the remaining rows
+-- are numbered in the order they were recorded.
+DELETE FROM "raw_actions" a USING "raw_actions" b
+WHERE a."id" > b."id"
+  AND a."block_number" = b."block_number"
+  AND a."tx_hash" = b."tx_hash"
+  AND a."sender" = b."sender"
+  AND a."action_type" = b."action_type"
+  AND a."action_version" = b."action_version"
+  AND a."entity" = b."entity"
+  AND a."space_pov" = b."space_pov"
+  AND a."group_id" IS NOT DISTINCT FROM b."group_id"
+  AND a."metadata" IS NOT DISTINCT FROM b."metadata";--> statement-breakpoint
+ALTER TABLE "raw_actions" ADD COLUMN "action_index" integer;--> statement-breakpoint
+UPDATE "raw_actions" r SET "action_index" = n."action_index"
+FROM (
+  SELECT "id", (row_number() OVER (PARTITION BY "block_number" ORDER BY "id") - 1)::integer AS "action_index"
+  FROM "raw_actions"
+) n
+WHERE r."id" = n."id";--> statement-breakpoint
+ALTER TABLE "raw_actions" ALTER COLUMN "action_index" SET NOT NULL;--> statement-breakpoint
+ALTER TABLE "raw_actions" ADD CONSTRAINT "raw_actions_block_number_action_index_unique" UNIQUE("block_number","action_index");
@@ -0,0 +1,9 @@
+-- When an entity's values or outgoing relations were last changed, bubbled to
+-- the types of the entity and the space the edit was applied to. Rows edited
+-- before these columns existed have no timestamp until they're edited again.
+ALTER TABLE "entities" ADD COLUMN "last_edited_at" text;--> statement-breakpoint
+ALTER TABLE "entities" ADD COLUMN "last_edited_block" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "last_edited_at" text;--> statement-breakpoint
+ALTER TABLE "spaces" ADD COLUMN "last_edited_block" text;--> statement-breakpoint
+CREATE INDEX "entities_last_edited_at_id_idx" ON "entities" USING btree ("last_edited_at","id");--> statement-breakpoint
+CREATE INDEX "spaces_last_edited_at_id_idx" ON "spaces" USING btree ("last_edited_at","id");
@@ -0,0 +1,5 @@
+-- Webhooks hold the secrets their deliveries are signed with, so they're
+-- neither exposed by the API nor readable by its role
+COMMENT ON TABLE "webhooks" IS E'@omit';--> statement-breakpoint
+COMMENT ON TABLE "webhook_dead_letters" IS E'@omit';--> statement-breakpoint
+REVOKE SELECT ON "webhooks", "webhook_dead_letters" FROM gaia_reader;
@@ -0,0 +1,66 @@
+-- Every table keyed by space is either hidden from the API's role or only
+-- shows it the spaces it can read, like the tables in 0037. Tables created
+-- later are readable by the role by default, so they need the same.
+REVOKE SELECT ON "counted_blocks", "counted_proposals", "ipfs_cache", "ipfs_cache_archive" FROM gaia_reader;--> statement-breakpoint
+COMMENT ON TABLE "counted_blocks" IS E'@omit';--> statement-breakpoint
+COMMENT ON TABLE "counted_proposals" IS E'@omit';--> statement-breakpoint
+COMMENT ON TABLE "ipfs_cache_archive" IS E'@omit';--> statement-breakpoint
+ALTER TABLE "unverified_edits" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "unverified_edits_private_read" ON "unverified_edits" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "unauthorized_edits" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "unauthorized_edits_private_read" ON "unauthorized_edits" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "property_migrations" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "property_migrations_private_read" ON "property_migrations" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "space_activity_rollups" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "space_activity_rollups_private_read" ON "space_activity_rollups" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "space_active_editors" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "space_active_editors_private_read" ON "space_active_editors" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "proposal_snapshots" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "proposal_snapshots_private_read" ON "proposal_snapshots" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "members" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "members_private_read" ON "members" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "editors" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "editors_private_read" ON "editors" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "editor_history" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "editor_history_private_read" ON "editor_history" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "space_plugins_history" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "space_plugins_history_private_read" ON "space_plugins_history" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "personal_admin_history" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "personal_admin_history_private_read" ON "personal_admin_history" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "raw_actions" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "raw_actions_private_read" ON "raw_actions" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR "space_pov" <> ALL ((SELECT unreadable_space_ids())::uuid[]))
+	WITH CHECK (true);--> statement-breakpoint
+-- Subspace links are only shown when both of their spaces are readable
+ALTER TABLE "subspaces" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "subspaces_private_read" ON "subspaces" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR ("parent_space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[])
+		AND "child_space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[])))
+	WITH CHECK (true);--> statement-breakpoint
+ALTER TABLE "subspace_closure" ENABLE ROW LEVEL SECURITY;--> statement-breakpoint
+CREATE POLICY "subspace_closure_private_read" ON "subspace_closure" FOR ALL TO PUBLIC
+	USING (current_user <> 'gaia_reader' OR ("ancestor_space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[])
+		AND "descendant_space_id" <> ALL ((SELECT unreadable_space_ids())::uuid[])))
+	WITH CHECK (true);
@@ -4,12 +4,14 @@ import {Hono} from "hono"
 import {compress} from "hono/compress"
 import {cors} from "hono/cors"
 import {openAPISpecs} from "hono-openapi"
+import {gateway} from "./src/gateway"
 import {health} from "./src/health"
 import {graphqlServer} from "./src/kg/postgraphile"
 import {Environment, EnvironmentLive, make as makeEnvironment} from "./src/services/environment"
 import {uploadEdit, uploadFile} from "./src/services/ipfs"
 import {make as makeStorage, Storage} from "./src/services/storage/storage"
 import {getPublishEditCalldata} from "./src/utils/calldata"
+import {readViewer} from "./src/utils/viewer"
 
 /**
  * Currently hand-rolling a compression polyfill until Bun implements
@@ -38,8 +40,19 @@ app.route("/health", health)
 
 app.get("/", swaggerUI({url: "/openapi"}))
 
+// The query API can be exposed publicly behind API keys and rate limits
+if (EnvironmentLive.gatewayEnabled) {
+	app.use("/graphql", gateway)
+}
+
 app.use("/graphql", async (c) => {
-	return graphqlServer.fetch(c.req.raw)
+	const viewer = await readViewer(c.req.raw.headers, new Date())
+
+	if (viewer._tag === "Invalid") {
+		return c.json({error: "Invalid viewer signature", reason: viewer.reason}, 401)
+	}
+
+	return graphqlServer.fetch(c.req.raw, {viewer: viewer._tag === "Signed" ? viewer.address : null})
 })
 
 app.post("/ipfs/upload-edit", async (c) => {
@@ -440,6 +453,19 @@ app.post("/space/:spaceId/edit/calldata", async (c) => {
 	}
 
 	const cid = parsedRequestJsonResult.right.cid
+	const viewer = await readViewer(c.req.raw.headers, new Date())
+
+	if (viewer._tag === "Invalid") {
+		return new Response(
+			JSON.stringify({
+				error: "Invalid viewer signature",
+				reason: viewer.reason,
+			}),
+			{
+				status: 401,
+			},
+		)
+	}
 
 	if (!cid || !cid.startsWith("ipfs://")) {
 		console.error(`[SPACE][calldata] Invalid CID ${cid}`)
@@ -455,7 +481,7 @@ app.post("/space/:spaceId/edit/calldata", async (c) => {
 	}
 
 	const getCalldata = Effect.gen(function* () {
-		return yield* getPublishEditCalldata(spaceId, cid as string)
+		return yield* getPublishEditCalldata(spaceId, cid as string, viewer._tag === "Signed" ? viewer.address : null)
 	})
 
 	const calldata = await Effect.runPromise(Effect.either(getCalldata.pipe(provideDeps)))
@@ -0,0 +1,48 @@
+#!/usr/bin/env bun
+
+import {Pool} from "pg"
+import {generateApiKey, hashApiKey} from "../src/utils/api-keys"
+
+/**
+ * Creates a key for the API gateway and prints it. Usage:
+ *
+ *   bun run scripts/create-api-key.ts <name> [requests per minute]
+ */
+async function createApiKey() {
+	const databaseUrl = process.env.DATABASE_URL
+	const [name, requestsPerMinute = "60"] = process.argv.slice(2)
+
+	if (!databaseUrl) {
+		console.error("❌ DATABASE_URL environment variable is not set")
+		process.exit(1)
+	}
+
+	if (!name || !/^[1-9]\d*$/.test(requestsPerMinute)) {
+		console.error("Usage: bun run scripts/create-api-key.ts <name> [requests per minute]")
+		process.exit(1)
+	}
+
+	const key = generateApiKey()
+	const pool = new Pool({connectionString: databaseUrl})
+
+	try {
+		const result = await pool.query(
+			"INSERT INTO api_keys (name, key_hash, requests_per_minute) VALUES ($1, $2, $3) RETURNING id",
+			[name, hashApiKey(key), Number(requestsPerMinute)],
+		)
+
+		console.log(`✅ Created API key ${result.rows[0].id} for ${name}, limited to ${requestsPerMinute} requests per minute`)
+		console.log(`🔑 ${key}`)
+		console.log("The key is only shown once. Revoke it by setting revoked_at on its row.")
+	} catch (error) {
+		console.error("❌ Failed to create API key:")
+		console.error(error)
+		process.exit(1)
+	} finally {
+		await pool.end()
+	}
+}
+
+if (import.meta.main) {
+	createApiKey()
+}
@@ -0,0 +1,50 @@
+#!/usr/bin/env bun
+
+import {Pool} from "pg"
+
+/**
+ * Marks a space private, so only its members and editors can read it
+ * through the API, or public again. Usage:
+ *
+ *   bun run scripts/set-space-private.ts <space id> [true|false]
+ */
+async function setSpacePrivate() {
+	const databaseUrl = process.env.DATABASE_URL
+	const [spaceId, isPrivate = "true"] = process.argv.slice(2)
+
+	if (!databaseUrl) {
+		console.error("❌ DATABASE_URL environment variable is not set")
+		process.exit(1)
+	}
+
+	if (!spaceId || (isPrivate !== "true" && isPrivate !== "false")) {
+		console.error("Usage: bun run scripts/set-space-private.ts <space id> [true|false]")
+		process.exit(1)
+	}
+
+	const pool = new Pool({connectionString: databaseUrl})
+
+	try {
+		const result = await pool.query("UPDATE spaces SET is_private = $2 WHERE id = $1", [
+			spaceId,
+			isPrivate === "true",
+		])
+
+		if (result.rowCount === 0) {
+			console.error(`❌ Could not find space with id ${spaceId}`)
+			process.exit(1)
+		}
+
+		console.log(`✅ Space ${spaceId} is now ${isPrivate === "true" ? "private" : "public"}`)
+	} catch (error) {
+		console.error("❌ Failed to update space:")
+		console.error(error)
+		process.exit(1)
+	} finally {
+		await pool.end()
+	}
+}
+
+if (import.meta.main) {
+	setSpacePrivate()
+}
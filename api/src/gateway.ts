@@ -0,0 +1,101 @@
+import {and, eq, isNull, sql} from "drizzle-orm"
+import {Effect, Either, Layer} from "effect"
+import {createMiddleware} from "hono/factory"
+import {Environment, make as makeEnvironment} from "./services/environment"
+import {apiKeyUsage, apiKeys} from "./services/storage/schema"
+import {make as makeStorage, Storage} from "./services/storage/storage"
+import {hashApiKey, readApiKey, secondsUntilNextWindow, windowStart} from "./utils/api-keys"
+
+const EnvironmentLayer = Layer.effect(Environment, makeEnvironment)
+const StorageLayer = Layer.effect(Storage, makeStorage).pipe(Layer.provide(EnvironmentLayer))
+const layers = Layer.mergeAll(EnvironmentLayer, StorageLayer)
+const provideDeps = Effect.provide(layers)
+
+/**
+ * Counts a request against its key's current window. Returns null if the
+ * key doesn't exist or was revoked, and otherwise the key's limit and the
+ * requests made in the window, including this one.
+ */
+function meterRequest(key: string, now: Date) {
+	return Effect.gen(function* () {
+		const storage = yield* Storage
+
+		return yield* storage.use(async (client) => {
+			const [apiKey] = await client
+				.select({id: apiKeys.id, requestsPerMinute: apiKeys.requestsPerMinute})
+				.from(apiKeys)
+				.where(and(eq(apiKeys.keyHash, hashApiKey(key)), isNull(apiKeys.revokedAt)))
+
+			if (!apiKey) {
+				return null
+			}
+
+			// A single upsert, so concurrent requests of a key are all counted
+			const [usage] = await client
+				.insert(apiKeyUsage)
+				.values({keyId: apiKey.id, windowStart: windowStart(now), requests: 1})
+				.onConflictDoUpdate({
+					target: [apiKeyUsage.keyId, apiKeyUsage.windowStart],
+					set: {requests: sql`${apiKeyUsage.requests} + 1`},
+				})
+				.returning({requests: apiKeyUsage.requests})
+
+			return {limit: apiKey.requestsPerMinute, requests: usage.requests}
+		})
+	})
+}
+
+/**
+ * Requires requests to carry a valid API key, and limits every key to its
+ * requests per minute. Responses carry the key's limit and remaining requests
+ * in `X-RateLimit-Limit` and `X-RateLimit-Remaining`.
+ */
+export const gateway = createMiddleware(async (c, next) => {
+	const key = readApiKey(c.req.raw.headers)
+
+	if (key === null) {
+		return c.json(
+			{
+				error: "Missing API key",
+				reason: "Pass an API key in the Authorization header as a bearer token, or in the X-API-Key header.",
+			},
+			401,
+		)
+	}
+
+	const now = new Date()
+	const result = await Effect.runPromise(Effect.either(meterRequest(key, now).pipe(provideDeps)))
+
+	if (Either.isLeft(result)) {
+		console.error(`[GATEWAY] Failed to meter request. message: ${result.left.message}`)
+		return c.json({error: "Failed to check API key"}, 500)
+	}
+
+	const usage = result.right
+
+	if (usage === null) {
+		return c.json({error: "Invalid API key", reason: "The API key doesn't exist or was revoked."}, 401)
+	}
+
+	const remaining = Math.max(usage.limit - usage.requests, 0)
+
+	if (usage.requests > usage.limit) {
+		c.header("X-RateLimit-Limit", String(usage.limit))
+		c.header("X-RateLimit-Remaining", "0")
+		c.header("Retry-After", String(secondsUntilNextWindow(now)))
+		return c.json(
+			{
+				error: "Rate limit exceeded",
+				reason: `The API key is limited to ${usage.limit} requests per minute.`,
+			},
+			429,
+		)
+	}
+
+	await next()
+
+	// Set once the response exists, as the routes behind the gateway return
+	// their own responses
+	c.header("X-RateLimit-Limit", String(usage.limit))
+	c.header("X-RateLimit-Remaining", String(remaining))
+})
@@ -164,7 +164,7 @@ describe("entities_ordered_by_property integration tests", () => {
 			await pool.query(`
 				INSERT INTO values (${columns.join(', ')})
 				VALUES (${placeholders.join(', ')})
-				ON CONFLICT (id) DO NOTHING
+				ON CONFLICT (id, space_id) DO NOTHING
 			`, values)
 		}
 	}
@@ -356,7 +356,7 @@ describe("entities_ordered_by_property integration tests", () => {
 			await pool.query(`
 				INSERT INTO values (id, property_id, entity_id, space_id, string)
 				VALUES ('null-val', $1, $2, $3, NULL)
-				ON CONFLICT (id) DO NOTHING
+				ON CONFLICT (id, space_id) DO NOTHING
 			`, [testPropertyIds.string, nullEntityId, testSpaceId])
 
 			const result = await pool.query(`
@@ -384,7 +384,7 @@ describe("entities_ordered_by_property integration tests", () => {
 			await pool.query(`
 				INSERT INTO values (id, property_id, entity_id, space_id, string)
 				VALUES ('empty-val', $1, $2, $3, '')
-				ON CONFLICT (id) DO NOTHING
+				ON CONFLICT (id, space_id) DO NOTHING
 			`, [testPropertyIds.string, emptyEntityId, testSpaceId])
 
 			const result = await pool.query(`
@@ -0,0 +1,73 @@
+import {afterAll, beforeAll, describe, expect, it} from "vitest"
+import {Pool, type PoolClient} from "pg"
+
+const SPACE_ID = "00000000-0000-0000-0000-0000000a11ce"
+const MEMBER = "0x000000000000000000000000000000000000bEEF"
+
+describe("private spaces", () => {
+	let pool: Pool
+
+	beforeAll(async () => {
+		pool = new Pool({
+			connectionString: process.env.DATABASE_URL,
+		})
+	})
+
+	afterAll(async () => {
+		await pool?.end()
+	})
+
+	/**
+	 * Runs queries as the API does, as `gaia_reader` with the viewer set, in a
+	 * transaction that's rolled back.
+	 */
+	async function asViewer<T>(viewer: string, query: (client: PoolClient) => Promise<T>): Promise<T> {
+		const client = await pool.connect()
+
+		try {
+			await client.query("BEGIN")
+			await client.query(
+				`INSERT INTO spaces (id, type, dao_address, space_address, is_private)
+				VALUES ($1, 'Personal', '0x0000000000000000000000000000000000000a11', '0x0000000000000000000000000000000000000a12', true)`,
+				[SPACE_ID],
+			)
+			await client.query("INSERT INTO members (address, space_id) VALUES ($1, $2)", [MEMBER, SPACE_ID])
+			await client.query("SELECT set_config('role', 'gaia_reader', true), set_config('gaia.viewer', $1, true)", [
+				viewer,
+			])
+
+			return await query(client)
+		} finally {
+			await client.query("ROLLBACK")
+			client.release()
+		}
+	}
+
+	it("should hide private spaces from anonymous viewers", async () => {
+		const rows = await asViewer("", (client) => client.query("SELECT id FROM spaces WHERE id = $1", [SPACE_ID]))
+
+		expect(rows.rowCount).toBe(0)
+	})
+
+	it("should show private spaces to their members", async () => {
+		const rows = await asViewer(MEMBER, (client) => client.query("SELECT id FROM spaces WHERE id = $1", [SPACE_ID]))
+
+		expect(rows.rowCount).toBe(1)
+	})
+
+	it("should still show public spaces to anonymous viewers", async () => {
+		const rows = await asViewer("", (client) => client.query("SELECT count(*)::int AS count FROM spaces"))
+		const all = await pool.query("SELECT count(*)::int AS count FROM spaces WHERE NOT is_private")
+
+		expect(rows.rows[0].count).toBe(all.rows[0].count)
+	})
+
+	it("should not let the API read API keys", async () => {
+		await expect(asViewer("", (client) => client.query("SELECT * FROM api_keys"))).rejects.toThrow()
+	})
+
+	it("should not let the API read webhooks or their secrets", async () => {
+		await expect(asViewer("", (client) => client.query("SELECT secret FROM webhooks"))).rejects.toThrow()
+		await expect(asViewer("", (client) => client.query("SELECT * FROM webhook_dead_letters"))).rejects.toThrow()
+	})
+})
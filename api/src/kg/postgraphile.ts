@@ -40,7 +40,12 @@ const postgraphileOptions = {
 // Create PostGraphile schema
 const postgraphileSchema = await createPostGraphileSchema(pgPool, ["public"], postgraphileOptions)
 
-export const graphqlServer = createYoga({
+/**
+ * Queries run as `gaia_reader`, whose row level security policies only let
+ * the verified viewer of a request read the private spaces they're a member
+ * or editor of.
+ */
+export const graphqlServer = createYoga<{viewer: string | null}>({
 	schema: postgraphileSchema,
 	graphiql: {
 		title: "Geo API",
@@ -48,16 +53,23 @@ export const graphqlServer = createYoga({
 	plugins: [
 		useExecutionCancellation(),
 		useResponseCache({
-			session: () => null,
+			// Viewers see different private spaces. The address header is only
+			// trusted here because requests with an invalid signature are
+			// rejected before they reach the server.
+			session: (request) => request.headers.get("x-viewer-address")?.toLowerCase() ?? null,
 			ttl: 10_000, // 10 seconds
 		}),
 	],
-	context: async ({request}) => {
+	context: async ({request, viewer}) => {
 		// Create a promise that will resolve with the PostGraphile context
 		const contextPromise = new Promise((resolve) => {
 			withPostGraphileContext(
 				{
 					pgPool,
+					pgSettings: {
+						role: "gaia_reader",
+						"gaia.viewer": viewer ?? "",
+					},
 				},
 				async (postgraphileContext) => {
 					resolve({
@@ -8,6 +8,7 @@ export type IEnvironment = Readonly<{
 	ipfsGatewayWrite: string
 	rpcEndpoint: string
 	chainId: "80451" | "19411"
+	gatewayEnabled: boolean
 }>
 
 export const make = Effect.gen(function* (_) {
@@ -22,6 +23,8 @@ export const make = Effect.gen(function* (_) {
 		throw new Error(`Invalid configuration for chain id. Expected 19411 or 80451. Got ${chainId}`)
 	}
 
+	const gatewayEnabled = yield* Config.boolean("GATEWAY_ENABLED").pipe(Config.withDefault(false))
+
 	const maybeTelemetryToken = yield* Config.option(Config.redacted("TELEMETRY_TOKEN"))
 
 	const telemetryToken = Option.match(maybeTelemetryToken, {
@@ -41,6 +44,7 @@ export const make = Effect.gen(function* (_) {
 		ipfsKey: ipfsKey,
 		ipfsGatewayWrite: ipfsGatewayWrite,
 		rpcEndpoint: rpcEndpoint,
+		gatewayEnabled,
 	} as const
 })
 
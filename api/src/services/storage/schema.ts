@@ -1,13 +1,18 @@
 import {
 	relations as drizzleRelations,
 	type InferSelectModel,
+	sql,
 } from "drizzle-orm";
 import {
 	bigint,
+	bigserial,
 	boolean,
+	check,
 	customType,
+	date,
 	decimal,
 	index,
+	integer,
 	jsonb,
 	pgEnum,
 	pgTable,
@@ -25,6 +30,21 @@ import {
 // This comment signals that we want the trigram extension available
 // The actual extension creation is handled in migrations
 
+/**
+ * bytea
+ *
+ * This is a custom type for the bytea data type.
+ * It is used to store binary data in the database.
+ */
+export const bytea = customType<{
+	data: Buffer;
+	default: false;
+}>({
+	dataType() {
+		return "bytea";
+	},
+});
+
 export const ipfsCache = pgTable("ipfs_cache", {
 	id: serial(),
 	json: jsonb(),
@@ -38,8 +58,81 @@ export const ipfsCache = pgTable("ipfs_cache", {
 	isErrored: boolean().notNull().default(false),
 	block: text().notNull(),
 	space: uuid().notNull(),
+	/**
+	 * Hash of the edit's canonical protobuf encoding. The same content
+	 * published under different CIDs shares a hash. Null for errored items.
+	 */
+	contentHash: text(),
+	/**
+	 * How the content was fetched, for cache health dashboards. Null for
+	 * items cached before these were recorded, and the length, compression
+	 * and encoding are null when the fetch failed before they were known.
+	 */
+	contentLength: integer(),
+	compression: text(),
+	encoding: text(),
+	fetchDurationMs: integer(),
+	gateway: text(),
+	/**
+	 * The content as fetched, before it's decompressed, so it can be decoded
+	 * again without fetching it. Null if the fetch failed.
+	 */
+	rawBytes: bytea("raw_bytes"),
+});
+
+/**
+ * Cache items that no edit or proposal refers to anymore, moved here by the
+ * cache garbage collector. A replay from before their block reads them, so
+ * they can be moved back into the cache for it.
+ */
+export const ipfsCacheArchive = pgTable("ipfs_cache_archive", {
+	uri: text().primaryKey(),
+	json: jsonb(),
+	isErrored: boolean().notNull().default(false),
+	block: text().notNull(),
+	space: uuid().notNull(),
+	contentHash: text(),
+	contentLength: integer(),
+	compression: text(),
+	encoding: text(),
+	fetchDurationMs: integer(),
+	gateway: text(),
+	rawBytes: bytea("raw_bytes"),
+	archivedAt: timestamp({ withTimezone: true }).notNull().defaultNow(),
 });
 
+/**
+ * The scheme the deployment derives space and value ids with, recorded when
+ * the indexer first starts so it refuses to run with another one.
+ */
+export const idScheme = pgTable(
+	"id_scheme",
+	{
+		id: boolean().primaryKey().default(true),
+		scheme: text().notNull(),
+		selectedAt: timestamp({ withTimezone: true }).notNull().defaultNow(),
+	},
+	(table) => [check("id_scheme_singleton", sql`${table.id}`)],
+);
+
+/**
+ * The ids spaces and values would have with another id scheme, written by
+ * `gaia map-ids` so references to the old ids can be rewritten after the
+ * deployment is reindexed with the new scheme.
+ */
+export const idMappings = pgTable(
+	"id_mappings",
+	{
+		kind: text().notNull(),
+		scheme: text().notNull(),
+		oldId: text().notNull(),
+		newId: uuid().notNull(),
+	},
+	(table) => [
+		primaryKey({ columns: [table.kind, table.scheme, table.oldId] }),
+	],
+);
+
 /**
  * Cursors store the latest indexed block log. Indexers store their latest
  * block log after they have completed indexing a block, and read the latest
@@ -60,17 +153,78 @@ export const meta = pgTable("meta", {
 	blockNumber: text().notNull(),
 });
 
+/**
+ * The blocks each indexer has written, keyed by the same id as its cursor.
+ * A block in the ledger is skipped if it's streamed again, such as after a
+ * restart from an older cursor.
+ */
+export const processedBlocks = pgTable(
+	"processed_blocks",
+	{
+		indexerId: text().notNull(),
+		blockNumber: bigint({ mode: "number" }).notNull(),
+		cursor: text().notNull(),
+		processedAt: timestamp({ withTimezone: true }).notNull().defaultNow(),
+	},
+	(table) => [primaryKey({ columns: [table.indexerId, table.blockNumber] })],
+);
+
 export const spaceTypesEnum = pgEnum("spaceTypes", ["Personal", "Public"]);
 
-export const spaces = pgTable("spaces", {
-	id: uuid().primaryKey(),
-	type: spaceTypesEnum().notNull(),
-	daoAddress: text().notNull(),
-	spaceAddress: text().notNull(),
-	mainVotingAddress: text(),
-	membershipAddress: text(),
-	personalAddress: text(),
-});
+export const spaces = pgTable(
+	"spaces",
+	{
+		id: uuid().primaryKey(),
+		type: spaceTypesEnum().notNull(),
+		daoAddress: text().notNull(),
+		spaceAddress: text().notNull(),
+		mainVotingAddress: text(),
+		membershipAddress: text(),
+		personalAddress: text(),
+		/**
+		 * The network the space's DAO lives on. The space id is derived from
+		 * the network and DAO address, so spaces of several networks can be
+		 * indexed side by side.
+		 */
+		network: text().notNull().default("KJjKetFsGVSbw9qFpRzRSy"),
+		chainId: bigint({ mode: "number" }),
+		/**
+		 * The space's own entity and its display metadata, maintained by the
+		 * indexer from the entity's values in the space.
+		 */
+		entityId: uuid(),
+		name: text(),
+		description: text(),
+		cover: text(),
+		/**
+		 * The number of members and editors of the space, kept up to date by the
+		 * indexer in the transaction that changes them.
+		 */
+		memberCount: integer().notNull().default(0),
+		editorCount: integer().notNull().default(0),
+		/**
+		 * The address that sent the transaction creating the space's DAO, the
+		 * transaction's hash, and the timestamp and number of its block. Empty
+		 * for spaces indexed before they were recorded.
+		 */
+		creator: text(),
+		createdTxHash: text(),
+		createdAt: text(),
+		createdAtBlock: text(),
+		/**
+		 * Whether the space's content is only readable by its members and
+		 * editors. The API reads as `gaia_reader`, whose row level security
+		 * policies hide private spaces and their content from other viewers.
+		 */
+		isPrivate: boolean().notNull().default(false),
+		/**
+		 * When an edit last changed an entity in the space.
+		 */
+		lastEditedAt: text(),
+		lastEditedBlock: text(),
+	},
+	(table) => [index("spaces_last_edited_at_id_idx").on(table.lastEditedAt, table.id)],
+);
 
 export const entities = pgTable(
 	"entities",
@@ -80,12 +234,20 @@ export const entities = pgTable(
 		createdAtBlock: text().notNull(),
 		updatedAt: text().notNull(),
 		updatedAtBlock: text().notNull(),
+		/**
+		 * When the entity's values or outgoing relations last changed, or
+		 * those of an entity of this type. Unlike `updatedAt`, referencing
+		 * the entity doesn't change it.
+		 */
+		lastEditedAt: text(),
+		lastEditedBlock: text(),
 	},
 	(table) => [
 		// Index for ordering queries
 		index("entities_updated_at_idx").on(table.updatedAt),
 		// Composite index for ordering with id for stable pagination
 		index("entities_updated_at_id_idx").on(table.updatedAt, table.id),
+		index("entities_last_edited_at_id_idx").on(table.lastEditedAt, table.id),
 	],
 );
 
@@ -103,6 +265,10 @@ export const properties = pgTable(
 	{
 		id: uuid().primaryKey(),
 		type: dataTypesEnum().notNull(),
+		// Copied by the indexer from the property entity's values and relations
+		name: text(),
+		description: text(),
+		renderableType: uuid(),
 	},
 	(table) => [
 		// Index for filtering by data type
@@ -110,19 +276,18 @@ export const properties = pgTable(
 	],
 );
 
+/**
+ * Hash partitioned by space id, see the 0021 migration. Partitioned tables
+ * can't have the NOT VALID foreign keys the other tables use, so values have
+ * none, and the primary key includes the partition key.
+ */
 export const values = pgTable(
 	"values",
 	{
-		id: text().primaryKey(),
-		propertyId: uuid()
-			.notNull()
-			.references(() => properties.id),
-		entityId: uuid()
-			.notNull()
-			.references(() => entities.id),
-		spaceId: uuid()
-			.notNull()
-			.references(() => spaces.id),
+		id: text().notNull(),
+		propertyId: uuid().notNull(),
+		entityId: uuid().notNull(),
+		spaceId: uuid().notNull(),
 		string: text(),
 		boolean: boolean(),
 		number: decimal(),
@@ -130,8 +295,13 @@ export const values = pgTable(
 		time: text(),
 		language: text(),
 		unit: text(),
+		options: jsonb(),
+		// The edit that last set the value
+		editId: uuid(),
 	},
 	(table) => [
+		primaryKey({ columns: [table.id, table.spaceId] }),
+
 		// Foreign key indexes for join performance
 		index("values_property_id_idx").on(table.propertyId),
 		index("values_entity_id_idx").on(table.entityId),
@@ -189,6 +359,8 @@ export const relations = pgTable(
 			.notNull()
 			.references(() => spaces.id),
 		verified: boolean(),
+		// The edit that created the relation
+		editId: uuid(),
 	},
 	(table) => [
 		// Foreign key indexes for join performance
@@ -222,6 +394,14 @@ export const relations = pgTable(
 			table.fromEntityId,
 			table.toEntityId,
 		),
+
+		// Keyset pagination of relations by type and by source entity
+		index("relations_space_type_page_idx").on(
+			table.spaceId,
+			table.typeId,
+			table.id,
+		),
+		index("relations_from_entity_page_idx").on(table.fromEntityId, table.id),
 	],
 );
 
@@ -253,6 +433,90 @@ export const editors = pgTable(
 	],
 );
 
+/**
+ * Every period an address was an editor of a space. An editor added and
+ * removed in the same block was an editor at that block. Editors from
+ * before the history was kept are recorded as added at block 0.
+ */
+export const editorHistory = pgTable(
+	"editor_history",
+	{
+		spaceId: uuid().notNull(),
+		address: text().notNull(),
+		addedAtBlock: bigint({ mode: "number" }).notNull(),
+		removedAtBlock: bigint({ mode: "number" }),
+	},
+	(table) => [
+		primaryKey({
+			columns: [table.spaceId, table.address, table.addedAtBlock],
+		}),
+		index("editor_history_space_id_address_idx").on(
+			table.spaceId,
+			table.address,
+		),
+	],
+);
+
+/**
+ * Every admin plugin installed in a personal space, and the admin it was
+ * installed for. The space's current plugin is the one that wasn't replaced,
+ * and is also the space's `personalAddress`. Plugins from before the history
+ * was kept are recorded as added at block 0, without their admin.
+ */
+export const personalAdminHistory = pgTable(
+	"personal_admin_history",
+	{
+		spaceId: uuid().notNull(),
+		personalAddress: text().notNull(),
+		adminAddress: text(),
+		addedAtBlock: bigint({ mode: "number" }).notNull(),
+		replacedAtBlock: bigint({ mode: "number" }),
+	},
+	(table) => [
+		primaryKey({
+			name: "personal_admin_history_pk",
+			columns: [table.spaceId, table.personalAddress, table.addedAtBlock],
+		}),
+		index("personal_admin_history_personal_address_idx").on(
+			table.personalAddress,
+		),
+	],
+);
+
+/**
+ * Every voting and membership plugin installed in a public space. The
+ * space's current plugins are the ones that weren't replaced, and are also
+ * its `mainVotingAddress` and `membershipAddress`. Proposals of replaced
+ * plugins are resolved to their space through this table. Plugins from
+ * before the history was kept are recorded as added at block 0.
+ */
+export const spacePluginsHistory = pgTable(
+	"space_plugins_history",
+	{
+		spaceId: uuid().notNull(),
+		pluginType: text().notNull(),
+		pluginAddress: text().notNull(),
+		addedAtBlock: bigint({ mode: "number" }).notNull(),
+		replacedAtBlock: bigint({ mode: "number" }),
+	},
+	(table) => [
+		primaryKey({
+			name: "space_plugins_history_pk",
+			columns: [
+				table.spaceId,
+				table.pluginType,
+				table.pluginAddress,
+				table.addedAtBlock,
+			],
+		}),
+		index("space_plugins_history_plugin_address_idx").on(table.pluginAddress),
+		check(
+			"space_plugins_history_plugin_type_check",
+			sql`${table.pluginType} IN ('voting', 'membership')`,
+		),
+	],
+);
+
 export const subspaces = pgTable(
 	"subspaces",
 	{
@@ -270,6 +534,350 @@ export const subspaces = pgTable(
 	],
 );
 
+/**
+ * Transitive closure of the subspace hierarchy, maintained by the indexer
+ * whenever subspaces are added or removed. Every ancestor of a space has a
+ * row at the length of the shortest path between them.
+ */
+export const subspaceClosure = pgTable(
+	"subspace_closure",
+	{
+		ancestorSpaceId: uuid().notNull(),
+		descendantSpaceId: uuid().notNull(),
+		depth: integer().notNull(),
+	},
+	(table) => [
+		primaryKey({ columns: [table.ancestorSpaceId, table.descendantSpaceId] }),
+		index("subspace_closure_descendant_space_id_idx").on(table.descendantSpaceId),
+	],
+);
+
+/**
+ * Edits applied to each space keyed by their content hash. Used by the
+ * indexer to skip edits whose content was already applied to a space
+ * under a different CID.
+ */
+export const edits = pgTable(
+	"edits",
+	{
+		spaceId: uuid().notNull(),
+		contentHash: text().notNull(),
+		cid: text().notNull(),
+		// The id in the edit's content, which values and relations reference
+		editId: uuid(),
+		createdAt: text().notNull(),
+		createdAtBlock: text().notNull(),
+	},
+	(table) => [
+		primaryKey({ columns: [table.spaceId, table.contentHash] }),
+		index("edits_edit_id_idx").on(table.editId),
+		index("edits_cid_idx").on(table.cid),
+		index("edits_content_hash_idx").on(table.contentHash),
+	],
+);
+
+/**
+ * Edits whose publishing transaction wasn't sent by one of their authors or
+ * an editor of their space. Rejected edits weren't applied.
+ */
+export const unverifiedEdits = pgTable(
+	"unverified_edits",
+	{
+		spaceId: uuid().notNull(),
+		cid: text().notNull(),
+		sender: text(),
+		rejected: boolean().notNull(),
+		createdAt: text().notNull(),
+		createdAtBlock: text().notNull(),
+	},
+	(table) => [primaryKey({ columns: [table.spaceId, table.cid] })],
+);
+
+/**
+ * Edits to public spaces whose publishing transaction wasn't sent by an
+ * editor of the space at that block. They're recorded instead of applied
+ * when editor permissions are enforced.
+ */
+export const unauthorizedEdits = pgTable(
+	"unauthorized_edits",
+	{
+		spaceId: uuid().notNull(),
+		cid: text().notNull(),
+		sender: text(),
+		createdAt: text().notNull(),
+		createdAtBlock: text().notNull(),
+	},
+	(table) => [primaryKey({ columns: [table.spaceId, table.cid] })],
+);
+
+/**
+ * Every request to change the data type of a property, whether or not it
+ * was applied. Rejected requests record the reason.
+ */
+export const propertyMigrations = pgTable(
+	"property_migrations",
+	{
+		id: serial().primaryKey(),
+		propertyId: uuid().notNull(),
+		spaceId: uuid().notNull(),
+		fromType: dataTypesEnum().notNull(),
+		toType: dataTypesEnum().notNull(),
+		applied: boolean().notNull(),
+		reason: text(),
+		convertedValues: integer().notNull(),
+		createdAt: text().notNull(),
+		createdAtBlock: text().notNull(),
+	},
+	(table) => [index("property_migrations_property_id_idx").on(table.propertyId)],
+);
+
+/**
+ * Webhooks registered by operators. The indexer posts each matching event
+ * to the URL after the block is committed, signed with the secret. An unset
+ * space or an empty list of event types matches everything.
+ */
+export const webhooks = pgTable("webhooks", {
+	id: uuid().primaryKey(),
+	url: text().notNull(),
+	secret: text().notNull(),
+	spaceId: uuid(),
+	eventTypes: text().array().notNull().default([]),
+	createdAt: timestamp({ withTimezone: true }).notNull().defaultNow(),
+});
+
+/**
+ * Notifications that couldn't be delivered to a webhook after every retry.
+ */
+export const webhookDeadLetters = pgTable(
+	"webhook_dead_letters",
+	{
+		id: serial().primaryKey(),
+		webhookId: uuid().notNull(),
+		notificationId: uuid().notNull(),
+		eventType: text().notNull(),
+		payload: jsonb().notNull(),
+		error: text().notNull(),
+		attempts: integer().notNull(),
+		blockNumber: bigint({ mode: "number" }).notNull(),
+		failedAt: timestamp({ withTimezone: true }).notNull().defaultNow(),
+	},
+	(table) => [index("webhook_dead_letters_webhook_id_idx").on(table.webhookId)],
+);
+
+/**
+ * Daily and weekly activity per space, maintained by the indexer as blocks
+ * are indexed. `period` is either "day" or "week", with weeks starting on
+ * Monday (UTC).
+ */
+export const spaceActivityRollups = pgTable(
+	"space_activity_rollups",
+	{
+		spaceId: uuid().notNull(),
+		period: text().notNull(),
+		periodStart: date().notNull(),
+		edits: integer().notNull().default(0),
+		activeEditors: integer().notNull().default(0),
+		proposalsCreated: integer().notNull().default(0),
+		proposalsExecuted: integer().notNull().default(0),
+		newEntities: integer().notNull().default(0),
+	},
+	(table) => [
+		primaryKey({ columns: [table.spaceId, table.period, table.periodStart] }),
+		index("space_activity_rollups_period_period_start_idx").on(
+			table.period,
+			table.periodStart,
+		),
+	],
+);
+
+/**
+ * The editors counted as active in each rollup period, so an editor is
+ * only counted once per period.
+ */
+export const spaceActiveEditors = pgTable(
+	"space_active_editors",
+	{
+		spaceId: uuid().notNull(),
+		period: text().notNull(),
+		periodStart: date().notNull(),
+		editorId: uuid().notNull(),
+	},
+	(table) => [
+		primaryKey({
+			columns: [table.spaceId, table.period, table.periodStart, table.editorId],
+		}),
+	],
+);
+
+/**
+ * Proposals already counted in the rollups, as every indexer shard sees
+ * executed proposals.
+ */
+export const countedProposals = pgTable(
+	"counted_proposals",
+	{
+		spaceId: uuid().notNull(),
+		proposalId: text().notNull(),
+		event: text().notNull(),
+	},
+	(table) => [
+		primaryKey({ columns: [table.spaceId, table.proposalId, table.event] }),
+	],
+);
+
+/**
+ * Blocks whose edits and new entities were already counted in a space's
+ * rollups, so replaying a block doesn't count them again.
+ */
+export const countedBlocks = pgTable(
+	"counted_blocks",
+	{
+		spaceId: uuid().notNull(),
+		blockNumber: bigint({ mode: "number" }).notNull(),
+	},
+	(table) => [primaryKey({ columns: [table.spaceId, table.blockNumber] })],
+);
+
+/**
+ * Every op of the applied edits in the order they were applied, for external
+ * systems to tail with the sequence as their cursor.
+ */
+export const opsJournal = pgTable(
+	"ops_journal",
+	{
+		sequence: bigserial({ mode: "number" }).primaryKey(),
+		blockNumber: bigint({ mode: "number" }).notNull(),
+		spaceId: uuid().notNull(),
+		editId: uuid(),
+		contentHash: text().notNull(),
+		opIndex: integer().notNull(),
+		opKind: text().notNull(),
+		targetId: uuid().notNull(),
+	},
+	(table) => [
+		unique("ops_journal_space_id_content_hash_op_index_unique").on(
+			table.spaceId,
+			table.contentHash,
+			table.opIndex,
+		),
+		index("ops_journal_space_id_sequence_idx").on(
+			table.spaceId,
+			table.sequence,
+		),
+	],
+);
+
+/**
+ * A summary of what each proposal to publish an edit changes, computed from
+ * the proposed edit when the proposal is created.
+ */
+export const proposalPreviews = pgTable(
+	"proposal_previews",
+	{
+		spaceId: uuid().notNull(),
+		proposalId: text().notNull(),
+		contentUri: text().notNull(),
+		entityIds: uuid().array().notNull(),
+		propertyIds: uuid().array().notNull(),
+		valuesSet: integer().notNull(),
+		valuesUnset: integer().notNull(),
+		relationsCreated: integer().notNull(),
+		relationsUpdated: integer().notNull(),
+		relationsDeleted: integer().notNull(),
+		entitiesDeleted: integer().notNull(),
+		createdAtBlock: text().notNull(),
+	},
+	(table) => [primaryKey({ columns: [table.spaceId, table.proposalId] })],
+);
+
+/**
+ * The editors and members of a space when each of its proposals was created,
+ * as of the end of the proposal's block, so votes on the proposal are
+ * counted against the electorate it was created with.
+ */
+export const proposalSnapshots = pgTable(
+	"proposal_snapshots",
+	{
+		spaceId: uuid().notNull(),
+		proposalId: text().notNull(),
+		role: text().notNull(),
+		address: text().notNull(),
+		createdAtBlock: text().notNull(),
+	},
+	(table) => [
+		primaryKey({
+			columns: [table.spaceId, table.proposalId, table.role, table.address],
+		}),
+		check(
+			"proposal_snapshots_role_check",
+			sql`${table.role} IN ('editor', 'member')`,
+		),
+	],
+);
+
+/**
+ * Every proposal created in a space, keyed by the plugin it was created in,
+ * and its status. A proposal is `created` until it's executed, or until it's
+ * withdrawn by its creator or vetoed by an editor, when it's `canceled`.
+ * Executed proposals keep the timestamp, block and transaction hash they
+ * were executed in.
+ */
+export const proposals = pgTable(
+	"proposals",
+	{
+		pluginAddress: text().notNull(),
+		proposalId: text().notNull(),
+		spaceId: uuid().notNull(),
+		proposalType: text().notNull(),
+		creator: text().notNull(),
+		status: text().notNull().default("created"),
+		createdAtBlock: text().notNull(),
+		executedAt: text(),
+		executedAtBlock: text(),
+		executedTxHash: text(),
+		canceledBy: text(),
+		canceledAtBlock: text(),
+	},
+	(table) => [
+		primaryKey({ columns: [table.pluginAddress, table.proposalId] }),
+		index("proposals_space_id_idx").on(table.spaceId),
+		check(
+			"proposals_status_check",
+			sql`${table.status} IN ('created', 'executed', 'canceled')`,
+		),
+	],
+);
+
+/**
+ * The keys of the API gateway, which is off unless `GATEWAY_ENABLED` is set.
+ * Only the SHA-256 hash of a key is stored. A key may make up to
+ * `requestsPerMinute` requests a minute, and stops working once revoked.
+ */
+export const apiKeys = pgTable("api_keys", {
+	id: uuid().primaryKey().defaultRandom(),
+	name: text().notNull(),
+	keyHash: text().notNull().unique(),
+	requestsPerMinute: integer().notNull().default(60),
+	createdAt: timestamp({ withTimezone: true }).notNull().defaultNow(),
+	revokedAt: timestamp({ withTimezone: true }),
+});
+
+/**
+ * The requests made with each API key, counted by the minute they were made
+ * in. Requests turned away for going over the key's limit are counted too.
+ */
+export const apiKeyUsage = pgTable(
+	"api_key_usage",
+	{
+		keyId: uuid()
+			.notNull()
+			.references(() => apiKeys.id),
+		windowStart: timestamp({ withTimezone: true }).notNull(),
+		requests: integer().notNull().default(0),
+	},
+	(table) => [primaryKey({ columns: [table.keyId, table.windowStart] })],
+);
+
 export const entityForeignValues = drizzleRelations(
 	entities,
 	({ many, one }) => ({
@@ -389,21 +997,6 @@ export type DbEditor = InferSelectModel<typeof editors>;
 
 /** Actions Schema definitions */
 
-/**
- * bytea
- *
- * This is a custom type for the bytea data type.
- * It is used to store binary data in the database.
- */
-export const bytea = customType<{
-	data: Buffer;
-	default: false;
-}>({
-	dataType() {
-		return "bytea";
-	},
-});
-
 /**
  * raw_actions
  */
@@ -424,8 +1017,17 @@ export const rawActions = pgTable(
 			mode: "date",
 		}).notNull(),
 		txHash: varchar("tx_hash", { length: 66 }).notNull(),
+		actionIndex: integer("action_index").notNull(),
+	},
+	(table) => {
+		return {
+			// Blocks processed again don't record their actions twice
+			uqBlockAction: unique("raw_actions_block_number_action_index_unique").on(
+				table.blockNumber,
+				table.actionIndex,
+			),
+		};
 	},
-	// no explicit indexes/uniques defined in SQL for this table
 );
 
 /**
@@ -458,6 +1060,11 @@ export const userVotes = pgTable(
 				table.entityId,
 				table.spaceId,
 			),
+			// CREATE INDEX idx_user_votes_entity_space ON user_votes(entity_id, space_id)
+			idxEntitySpace: index("idx_user_votes_entity_space").on(
+				table.entityId,
+				table.spaceId,
+			),
 		};
 	},
 );
@@ -491,3 +1098,33 @@ export const votesCount = pgTable(
 		};
 	},
 );
+
+/**
+ * user_activity
+ *
+ * Each user's activity feed, written by the actions indexer with the actions
+ * it comes from. Entries are keyed by the user, block and the action's index
+ * in the block, so feeds are paged through by the key of the last entry read.
+ */
+export const userActivity = pgTable(
+	"user_activity",
+	{
+		userId: varchar("user_id", { length: 42 }).notNull(),
+		blockNumber: bigint("block_number", { mode: "number" }).notNull(),
+		actionIndex: integer("action_index").notNull(),
+		activityType: text("activity_type").notNull(),
+		voteType: smallint("vote_type"),
+		entityId: uuid("entity_id").notNull(),
+		spaceId: uuid("space_id").notNull(),
+		txHash: varchar("tx_hash", { length: 66 }).notNull(),
+		occurredAt: timestamp("occurred_at", {
+			withTimezone: true,
+			mode: "date",
+		}).notNull(),
+	},
+	(table) => [
+		primaryKey({
+			columns: [table.userId, table.blockNumber, table.actionIndex],
+		}),
+	],
+);
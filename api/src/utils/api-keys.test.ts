@@ -0,0 +1,35 @@
+import {describe, expect, it} from "vitest"
+import {generateApiKey, hashApiKey, readApiKey, secondsUntilNextWindow, windowStart} from "./api-keys"
+
+describe("API keys", () => {
+	it("should generate distinct keys", () => {
+		const key = generateApiKey()
+
+		expect(key).toMatch(/^gaia_[0-9a-f]{48}$/)
+		expect(generateApiKey()).not.toBe(key)
+	})
+
+	it("should hash keys to the same hex digest", () => {
+		expect(hashApiKey("gaia_key")).toBe(hashApiKey("gaia_key"))
+		expect(hashApiKey("gaia_key")).toMatch(/^[0-9a-f]{64}$/)
+		expect(hashApiKey("gaia_other")).not.toBe(hashApiKey("gaia_key"))
+	})
+
+	it("should read keys from bearer tokens and the X-API-Key header", () => {
+		expect(readApiKey(new Headers({authorization: "Bearer gaia_key"}))).toBe("gaia_key")
+		expect(readApiKey(new Headers({authorization: "bearer gaia_key "}))).toBe("gaia_key")
+		expect(readApiKey(new Headers({"x-api-key": "gaia_key"}))).toBe("gaia_key")
+		expect(readApiKey(new Headers({authorization: "Basic dXNlcg=="}))).toBe(null)
+		expect(readApiKey(new Headers())).toBe(null)
+	})
+})
+
+describe("rate limit windows", () => {
+	it("should count requests by the minute", () => {
+		const now = new Date("2025-01-01T12:34:56.789Z")
+
+		expect(windowStart(now)).toEqual(new Date("2025-01-01T12:34:00.000Z"))
+		expect(secondsUntilNextWindow(now)).toBe(4)
+		expect(secondsUntilNextWindow(new Date("2025-01-01T12:34:00.000Z"))).toBe(60)
+	})
+})
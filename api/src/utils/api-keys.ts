@@ -0,0 +1,39 @@
+import {createHash, randomBytes} from "node:crypto"
+
+const WINDOW_MILLIS = 60_000
+
+/**
+ * Generates a new API key. Keys are only shown when they're created, as
+ * only their hash is stored.
+ */
+export function generateApiKey(): string {
+	return `gaia_${randomBytes(24).toString("hex")}`
+}
+
+export function hashApiKey(key: string): string {
+	return createHash("sha256").update(key).digest("hex")
+}
+
+/**
+ * Reads the API key of a request from an `Authorization: Bearer` header, or
+ * from an `X-API-Key` header.
+ */
+export function readApiKey(headers: Headers): string | null {
+	const authorization = headers.get("authorization")
+	if (authorization?.toLowerCase().startsWith("bearer ")) {
+		return authorization.slice("bearer ".length).trim() || null
+	}
+
+	return headers.get("x-api-key")?.trim() || null
+}
+
+/**
+ * The start of the minute a request is counted in.
+ */
+export function windowStart(now: Date): Date {
+	return new Date(Math.floor(now.getTime() / WINDOW_MILLIS) * WINDOW_MILLIS)
+}
+
+export function secondsUntilNextWindow(now: Date): number {
+	return Math.ceil((windowStart(now).getTime() + WINDOW_MILLIS - now.getTime()) / 1000)
+}
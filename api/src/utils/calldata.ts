@@ -3,7 +3,11 @@ import {Effect} from "effect"
 import {encodeFunctionData, stringToHex} from "viem"
 import {Storage} from "../services/storage/storage"
 
-export function getPublishEditCalldata(spaceId: string, cid: string) {
+/**
+ * Returns null if the space doesn't exist, or if it's private and the viewer
+ * isn't one of its members or editors.
+ */
+export function getPublishEditCalldata(spaceId: string, cid: string, viewer: string | null) {
 	return Effect.gen(function* () {
 		const db = yield* Storage
 
@@ -16,6 +20,25 @@ export function getPublishEditCalldata(spaceId: string, cid: string) {
 				return null
 			}
 
+			if (maybeSpace.isPrivate) {
+				if (viewer === null) {
+					return null
+				}
+
+				const [member, editor] = await Promise.all([
+					client.query.members.findFirst({
+						where: (members, {and, eq}) => and(eq(members.spaceId, spaceId), eq(members.address, viewer)),
+					}),
+					client.query.editors.findFirst({
+						where: (editors, {and, eq}) => and(eq(editors.spaceId, spaceId), eq(editors.address, viewer)),
+					}),
+				])
+
+				if (!member && !editor) {
+					return null
+				}
+			}
+
 			if (maybeSpace.type === "Personal") {
 				const calldata = encodeFunctionData({
 					functionName: "submitEdits",
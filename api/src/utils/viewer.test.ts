@@ -0,0 +1,49 @@
+import {generatePrivateKey, privateKeyToAccount} from "viem/accounts"
+import {describe, expect, it} from "vitest"
+import {readViewer, viewerMessage} from "./viewer"
+
+const account = privateKeyToAccount(generatePrivateKey())
+const now = new Date("2025-01-01T12:00:00Z")
+const timestamp = now.getTime() / 1000
+
+async function signedHeaders(signedAt: number) {
+	const signature = await account.signMessage({message: viewerMessage(account.address, signedAt)})
+
+	return new Headers({
+		"x-viewer-address": account.address.toLowerCase(),
+		"x-viewer-timestamp": String(signedAt),
+		"x-viewer-signature": signature,
+	})
+}
+
+describe("viewers", () => {
+	it("should treat requests without viewer headers as anonymous", async () => {
+		expect(await readViewer(new Headers(), now)).toEqual({_tag: "Anonymous"})
+	})
+
+	it("should read the checksummed address of signed requests", async () => {
+		expect(await readViewer(await signedHeaders(timestamp), now)).toEqual({
+			_tag: "Signed",
+			address: account.address,
+		})
+	})
+
+	it("should reject signatures of other addresses", async () => {
+		const headers = await signedHeaders(timestamp)
+		headers.set("x-viewer-address", privateKeyToAccount(generatePrivateKey()).address)
+
+		expect((await readViewer(headers, now))._tag).toBe("Invalid")
+	})
+
+	it("should reject expired signatures", async () => {
+		expect((await readViewer(await signedHeaders(timestamp - 301), now))._tag).toBe("Invalid")
+		expect((await readViewer(await signedHeaders(timestamp - 300), now))._tag).toBe("Signed")
+	})
+
+	it("should reject requests with only some of the headers", async () => {
+		const headers = await signedHeaders(timestamp)
+		headers.delete("x-viewer-signature")
+
+		expect((await readViewer(headers, now))._tag).toBe("Invalid")
+	})
+})
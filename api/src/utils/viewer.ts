@@ -0,0 +1,72 @@
+import {getAddress, isAddress, isHex, verifyMessage} from "viem"
+
+const MAX_SIGNATURE_AGE_SECONDS = 300
+
+export type Viewer =
+	| {_tag: "Anonymous"}
+	| {_tag: "Signed"; address: `0x${string}`}
+	| {_tag: "Invalid"; reason: string}
+
+/**
+ * The message a viewer signs to read the private spaces they're a member or
+ * editor of.
+ */
+export function viewerMessage(address: string, timestamp: number): string {
+	return `Read private spaces on the Geo API\nAddress: ${getAddress(address)}\nTimestamp: ${timestamp}`
+}
+
+/**
+ * Reads the viewer of a request from its `X-Viewer-Address`,
+ * `X-Viewer-Timestamp` and `X-Viewer-Signature` headers. The signature is
+ * of the viewer message for the address and timestamp, in unix seconds, and
+ * is accepted for five minutes either side of the timestamp.
+ *
+ * Requests without any of the headers are anonymous. Requests with only some
+ * of them, or with a signature that doesn't match, are invalid rather than
+ * anonymous, so clients find out their signature was rejected.
+ */
+export async function readViewer(headers: Headers, now: Date): Promise<Viewer> {
+	const address = headers.get("x-viewer-address")?.trim()
+	const timestamp = headers.get("x-viewer-timestamp")?.trim()
+	const signature = headers.get("x-viewer-signature")?.trim()
+
+	if (!address && !timestamp && !signature) {
+		return {_tag: "Anonymous"}
+	}
+
+	if (!address || !timestamp || !signature) {
+		return {
+			_tag: "Invalid",
+			reason: "Pass the X-Viewer-Address, X-Viewer-Timestamp and X-Viewer-Signature headers together.",
+		}
+	}
+
+	if (!isAddress(address, {strict: false})) {
+		return {_tag: "Invalid", reason: `${address} isn't an address.`}
+	}
+
+	if (!/^\d+$/.test(timestamp)) {
+		return {_tag: "Invalid", reason: "The timestamp must be in unix seconds."}
+	}
+
+	if (Math.abs(now.getTime() / 1000 - Number(timestamp)) > MAX_SIGNATURE_AGE_SECONDS) {
+		return {_tag: "Invalid", reason: "The signature expired. Sign a message with the current timestamp."}
+	}
+
+	if (!isHex(signature)) {
+		return {_tag: "Invalid", reason: "The signature must be hex encoded."}
+	}
+
+	const isValid = await verifyMessage({
+		address,
+		message: viewerMessage(address, Number(timestamp)),
+		signature,
+	}).catch(() => false)
+
+	if (!isValid) {
+		return {_tag: "Invalid", reason: `The signature wasn't made by ${address}.`}
+	}
+
+	// Members and editors are indexed with checksummed addresses
+	return {_tag: "Signed", address: getAddress(address)}
+}
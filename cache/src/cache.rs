@@ -1,6 +1,6 @@
-use std::env;
-
-use sqlx::{postgres::PgPoolOptions, Postgres};
+use indexer_utils::config::DatabaseConfig;
+use ipfs::FetchMetadata;
+use sqlx::Postgres;
 use uuid::Uuid;
 
 use thiserror::Error;
@@ -22,15 +22,8 @@ pub struct Storage {
 // @TODO: How do we abstract to handle arbitrary storage mechanisms for the cache?
 // e.g. we may want in-memory or a different db
 impl Storage {
-    pub async fn new() -> Result<Self, CacheError> {
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-
-        let database_url_static = database_url.as_str();
-
-        let connection = PgPoolOptions::new()
-            .max_connections(20)
-            .connect(database_url_static)
-            .await?;
+    pub async fn new(database_url: &str, database: &DatabaseConfig) -> Result<Self, CacheError> {
+        let connection = database.connect(database_url).await?;
 
         return Ok(Storage { connection });
     }
@@ -39,13 +32,20 @@ impl Storage {
         let json_string = serde_json::to_value(&item.json)?;
 
         sqlx::query(
-            "INSERT INTO ipfs_cache (uri, json, block, space, is_errored) VALUES ($1, $2, $3, $4, $5)"
+            "INSERT INTO ipfs_cache (uri, json, block, space, is_errored, content_hash, content_length, compression, encoding, fetch_duration_ms, gateway, raw_bytes) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)"
         )
         .bind(&item.uri)
         .bind(&json_string)
         .bind(&item.block)
         .bind(&item.space)
         .bind(&item.is_errored)
+        .bind(&item.content_hash)
+        .bind(item.content_length)
+        .bind(&item.compression)
+        .bind(&item.encoding)
+        .bind(item.fetch_duration_ms)
+        .bind(&item.gateway)
+        .bind(&item.raw_bytes)
         .execute(&self.connection)
         .await?;
 
@@ -63,30 +63,8 @@ impl Storage {
         Ok(maybe_exists.exists.unwrap_or(false))
     }
 
-    pub async fn load_cursor(&self, id: &str) -> Result<Option<String>, CacheError> {
-        let result = sqlx::query!("SELECT cursor FROM meta WHERE id = $1", id)
-            .fetch_optional(&self.connection)
-            .await?;
-
-        Ok(result.map(|row| row.cursor))
-    }
-
-    pub async fn persist_cursor(
-        &self,
-        id: &str,
-        cursor: &str,
-        block: &u64,
-    ) -> Result<(), CacheError> {
-        sqlx::query!(
-            "INSERT INTO meta (id, cursor, block_number) VALUES ($1, $2, $3) ON CONFLICT (id) DO UPDATE SET cursor = $2, block_number = $3",
-            id,
-            cursor,
-            block.to_string()
-        )
-        .execute(&self.connection)
-        .await?;
-
-        Ok(())
+    pub fn pool(&self) -> &sqlx::Pool<Postgres> {
+        &self.connection
     }
 }
 
@@ -100,6 +78,39 @@ pub struct CacheItem {
     pub block: String,
     pub space: Uuid,
     pub is_errored: bool,
+    /// Hash of the edit's canonical encoding, see `wire::hash::edit_hash`.
+    pub content_hash: Option<String>,
+    /// The size of the content as fetched, before decompressing it.
+    pub content_length: Option<i32>,
+    /// The detected compression, like `zstd` or `none`.
+    pub compression: Option<String>,
+    /// The detected encoding, `protobuf` or `json` for legacy edits.
+    pub encoding: Option<String>,
+    pub fetch_duration_ms: Option<i32>,
+    /// The gateway the content was fetched from.
+    pub gateway: Option<String>,
+    /// The content as fetched, so it can be decoded again without fetching
+    /// it, unset if the fetch failed.
+    pub raw_bytes: Option<Vec<u8>>,
+}
+
+impl CacheItem {
+    /// Sets the metadata columns from how the item's content was fetched.
+    pub fn with_fetch_metadata(mut self, metadata: &FetchMetadata) -> Self {
+        self.content_length = metadata
+            .content_length
+            .map(|length| i32::try_from(length).unwrap_or(i32::MAX));
+        self.compression = metadata
+            .format
+            .map(|format| format.codec.as_str().to_string());
+        self.encoding = metadata
+            .format
+            .map(|format| format.encoding.as_str().to_string());
+        self.fetch_duration_ms =
+            Some(i32::try_from(metadata.duration.as_millis()).unwrap_or(i32::MAX));
+        self.gateway = Some(metadata.gateway.clone());
+        self
+    }
 }
 
 impl Cache {
@@ -117,17 +128,4 @@ impl Cache {
         let result = self.storage.has(uri).await?;
         Ok(result)
     }
-
-    pub async fn load_cursor(&self, id: &str) -> Result<Option<String>, CacheError> {
-        self.storage.load_cursor(id).await
-    }
-
-    pub async fn persist_cursor(
-        &self,
-        id: &str,
-        cursor: &str,
-        block: &u64,
-    ) -> Result<(), CacheError> {
-        self.storage.persist_cursor(id, cursor, block).await
-    }
 }
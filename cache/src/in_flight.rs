@@ -0,0 +1,57 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+/// The URIs being fetched and written, so an edit published more than once
+/// while it's being cached, like twice in one block, is only fetched once.
+/// Once it's written, the cache's `has()` check skips it.
+#[derive(Clone, Default)]
+pub struct InFlight {
+    uris: Arc<Mutex<HashSet<String>>>,
+}
+
+impl InFlight {
+    /// Claims the URI, or returns `None` if it's already in flight. The
+    /// claim is released when the guard is dropped, whether or not the entry
+    /// was written.
+    pub fn claim(&self, uri: &str) -> Option<InFlightGuard> {
+        let mut uris = self.uris.lock().unwrap();
+        if !uris.insert(uri.to_string()) {
+            return None;
+        }
+
+        Some(InFlightGuard {
+            uris: self.uris.clone(),
+            uri: uri.to_string(),
+        })
+    }
+}
+
+pub struct InFlightGuard {
+    uris: Arc<Mutex<HashSet<String>>>,
+    uri: String,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.uris.lock().unwrap().remove(&self.uri);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_claims_each_uri_once() {
+        let in_flight = InFlight::default();
+
+        let guard = in_flight.claim("ipfs://a").unwrap();
+        assert!(in_flight.claim("ipfs://a").is_none());
+        assert!(in_flight.claim("ipfs://b").is_some());
+
+        drop(guard);
+        assert!(in_flight.claim("ipfs://a").is_some());
+    }
+}
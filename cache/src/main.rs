@@ -1,25 +1,39 @@
+use indexer_utils::config::{Config, ConfigError};
+use indexer_utils::decoded::decode_block;
 use indexer_utils::get_blocklist;
-use indexer_utils::id::derive_space_id;
-use indexer_utils::network_ids::GEO;
+use indexer_utils::id;
+use indexer_utils::network_ids::Network;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 use std::{env, io::Error};
+use stream::backfill::BackfillConfig;
+use stream::cursor::{CursorStore, PostgresCursorStore};
 use stream::utils::BlockMetadata;
 use thiserror::Error;
 use tokio::task;
-use wire::pb::chain::{EditPublished, GeoOutput};
+use tokio_retry::{
+    strategy::{jitter, ExponentialBackoff},
+    Retry,
+};
+use wire::hash::edit_hash;
+use wire::pb::chain::EditPublished;
 
 use dotenv::dotenv;
-use prost::Message;
 use stream::Sink;
 use tokio::sync::{Mutex, Semaphore};
+use tracing::{error, info, warn};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 const PKG_FILE: &str = "geo_substream.spkg";
 const MODULE_NAME: &str = "geo_out";
-const START_BLOCK: i64 = 67162;
 
 mod cache;
+mod in_flight;
+mod progress;
 use cache::{Cache, CacheItem};
-use ipfs::IpfsClient;
+use in_flight::InFlight;
+use ipfs::{FetchedEdit, IpfsClient};
+use progress::BlockProgress;
 
 type CacheIndexerError = Error;
 
@@ -32,14 +46,55 @@ struct CacheIndexer {
     semaphore: Arc<Semaphore>,
     cache: Arc<Mutex<Cache>>,
     ipfs: Arc<IpfsClient>,
+    cursor_store: PostgresCursorStore,
+    network: Arc<Network>,
+    api_token: Option<String>,
+    concurrency: usize,
+    retry: RetryPolicy,
+    in_flight: InFlight,
+    progress: BlockProgress,
+}
+
+/// How the cache retries fetches that fail with a transient error.
+#[derive(Clone, Copy)]
+struct RetryPolicy {
+    max_retries: usize,
+    max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// The same exponential backoff with jitter the indexer uses to read
+    /// from the cache.
+    fn strategy(&self) -> impl Iterator<Item = Duration> {
+        ExponentialBackoff::from_millis(10)
+            .factor(2)
+            .max_delay(self.max_delay)
+            .map(jitter)
+            .take(self.max_retries)
+    }
 }
 
 impl CacheIndexer {
-    pub fn new(cache: Cache, ipfs: IpfsClient) -> Self {
+    pub fn new(
+        cache: Cache,
+        ipfs: IpfsClient,
+        cursor_store: PostgresCursorStore,
+        config: &Config,
+    ) -> Self {
         CacheIndexer {
             cache: Arc::new(Mutex::new(cache)),
             ipfs: Arc::new(ipfs),
-            semaphore: Arc::new(Semaphore::new(20)),
+            semaphore: Arc::new(Semaphore::new(config.ipfs.concurrency)),
+            concurrency: config.ipfs.concurrency,
+            cursor_store,
+            network: Arc::new(config.network()),
+            api_token: config.substreams.api_token.clone(),
+            retry: RetryPolicy {
+                max_retries: config.ipfs.max_retries,
+                max_delay: config.ipfs.retry_max_delay(),
+            },
+            in_flight: InFlight::default(),
+            progress: BlockProgress::default(),
         }
     }
 }
@@ -53,44 +108,44 @@ enum IndexerError {
 impl Sink<EventData> for CacheIndexer {
     type Error = CacheIndexerError;
 
+    fn api_token(&self) -> Option<String> {
+        self.api_token.clone()
+    }
+
     async fn load_persisted_cursor(&self) -> Result<Option<String>, Self::Error> {
-        self.cache
-            .lock()
-            .await
-            .load_cursor("ipfs_indexer")
+        self.cursor_store
+            .load(&self.network.cursor_id("ipfs_indexer"))
             .await
             .map_err(|e| Error::new(std::io::ErrorKind::Other, e))
     }
 
     async fn persist_cursor(&self, cursor: String, block: u64) -> Result<(), Self::Error> {
-        self.cache
-            .lock()
-            .await
-            .persist_cursor("ipfs_indexer", &cursor, &block)
-            .await
-            .map_err(|e| Error::new(std::io::ErrorKind::Other, e))
+        // Fetches may still be running, so the cursor persisted is the
+        // latest one whose block and the blocks before it are fully cached
+        let completed = self
+            .progress
+            .finish_block(block, cursor)
+            .map_err(Error::other)?;
+
+        match completed {
+            Some((cursor, block)) => self
+                .cursor_store
+                .persist(&self.network.cursor_id("ipfs_indexer"), &cursor, block)
+                .await
+                .map_err(|e| Error::new(std::io::ErrorKind::Other, e)),
+            None => Ok(()),
+        }
     }
 
     async fn process_block_scoped_data(
         &self,
         block_data: &stream::pb::sf::substreams::rpc::v2::BlockScopedData,
     ) -> Result<(), Self::Error> {
-        let output = stream::utils::output(block_data);
-
-        // @TODO: Parsing and decoding of event data should happen in a separate module.
-        // This makes it so we can generate test data using these decoders and pass them
-        // to any arbitrary handler. This gives us testing and prototyping by mocking the
-        // events coming via the stream.
-
-        // We should take the code to get the output and decode it into
-        // a "GeoOutput" into it's own module that any Sink trait impl
-        // can consume to get the decoded data from the substream.
-
         // We want to enable extensible governance actions. This means we should probably
         // distinguish between KG messages and governance messages.
-        let geo = GeoOutput::decode(output.value.as_slice())?;
-
-        let block_metadata = stream::utils::block_metadata(block_data);
+        let decoded =
+            decode_block(block_data).map_err(|e| Error::new(std::io::ErrorKind::InvalidData, e))?;
+        let block_metadata = decoded.block;
 
         let block_timestamp_seconds: i64 = block_metadata.timestamp.parse().unwrap_or(0);
         let block_datetime = chrono::DateTime::from_timestamp(block_timestamp_seconds, 0)
@@ -98,42 +153,112 @@ impl Sink<EventData> for CacheIndexer {
             .with_timezone(&chrono::Local);
         let drift_str = stream::utils::format_drift(&block_metadata);
 
-        println!(
-            "Processing Block #{} [{}] - Payload {} ({} bytes) - Drift {} – Edits Published {}",
-            block_metadata.block_number,
-            block_datetime.format("%Y-%m-%d %H:%M:%S"),
-            output.type_url.replace("type.googleapis.com/", ""),
-            output.value.len(),
-            drift_str,
-            geo.edits_published.len()
+        // The content of proposals to publish an edit is cached as well, so
+        // the indexer can preview what a proposal changes before it executes
+        let proposed_edits: Vec<EditPublished> = decoded
+            .output
+            .edits
+            .iter()
+            .filter(|proposal| {
+                !proposal.content_uri.is_empty()
+                    && !get_blocklist()
+                        .dao_addresses
+                        .contains(&proposal.dao_address.as_str())
+            })
+            .map(|proposal| EditPublished {
+                content_uri: proposal.content_uri.clone(),
+                plugin_address: proposal.plugin_address.clone(),
+                dao_address: proposal.dao_address.clone(),
+                sender: proposal.creator.clone(),
+            })
+            .collect();
+
+        info!(
+            block_number = block_metadata.block_number,
+            block_time = %block_datetime.format("%Y-%m-%d %H:%M:%S"),
+            payload_bytes = decoded.payload_bytes,
+            drift = %drift_str,
+            drift_secs = stream::utils::drift(&block_metadata).num_seconds(),
+            edits_published = decoded.output.edits_published.len(),
+            blocklisted_edits = decoded.blocklisted_edits,
+            proposed_edits = proposed_edits.len(),
+            "Processing block"
         );
 
-        for edit in geo.edits_published {
-            if get_blocklist()
-                .dao_addresses
-                .contains(&edit.dao_address.as_str())
-            {
+        let mut saturated_fetches = 0;
+        let mut saturated_wait = Duration::ZERO;
+
+        for edit in decoded
+            .output
+            .edits_published
+            .into_iter()
+            .chain(proposed_edits)
+        {
+            let Some(claim) = self.in_flight.claim(&edit.content_uri) else {
+                info!(
+                    uri = %edit.content_uri,
+                    block_number = block_metadata.block_number,
+                    "Cache entry is already being processed, skipping"
+                );
                 continue;
-            }
+            };
 
+            // Fetches waiting here mean the concurrency limit is saturated
+            let waiting_since = Instant::now();
+            let saturated = self.semaphore.available_permits() == 0;
             let permit = self.semaphore.clone().acquire_owned().await.unwrap();
+            if saturated {
+                saturated_fetches += 1;
+                saturated_wait += waiting_since.elapsed();
+            }
+
             let cache = self.cache.clone();
             let ipfs = self.ipfs.clone();
+            let network = self.network.clone();
+            let retry = self.retry;
 
-            println!(
-                "Processing cache entry for uri {} in block {}",
-                edit.content_uri, block_metadata.block_number
+            info!(
+                uri = %edit.content_uri,
+                block_number = block_metadata.block_number,
+                "Processing cache entry"
             );
 
-            let block_metadata = stream::utils::block_metadata(block_data);
+            let block_metadata = block_metadata.clone();
+            let tracked = self.progress.track(block_metadata.block_number);
 
             task::spawn(async move {
-                process_edit_event(edit, &cache, &ipfs, &block_metadata).await?;
+                let uri = edit.content_uri.clone();
+                let result =
+                    process_edit_event(edit, &cache, &ipfs, &network, &block_metadata, retry).await;
                 drop(permit);
-                Ok::<(), IndexerError>(())
+                drop(claim);
+
+                match result {
+                    Ok(()) => tracked.complete(),
+                    Err(err) => {
+                        // Dropping the task without completing it holds the
+                        // cursor back, so the edit is fetched again on restart
+                        error!(
+                            error = %err,
+                            uri = %uri,
+                            block_number = block_metadata.block_number,
+                            "Error caching edit"
+                        );
+                    }
+                }
             });
         }
 
+        if saturated_fetches > 0 {
+            warn!(
+                block_number = block_metadata.block_number,
+                saturated_fetches = saturated_fetches,
+                saturated_wait_ms = saturated_wait.as_millis() as u64,
+                concurrency = self.concurrency,
+                "Fetch concurrency saturated, fetches waited for a free slot"
+            );
+        }
+
         Ok(())
     }
 }
@@ -142,7 +267,9 @@ async fn process_edit_event(
     edit: EditPublished,
     cache: &Arc<Mutex<Cache>>,
     ipfs: &Arc<IpfsClient>,
+    network: &Network,
     block: &BlockMetadata,
+    retry: RetryPolicy,
 ) -> Result<(), IndexerError> {
     {
         let mut cache_instance = cache.lock().await;
@@ -152,38 +279,45 @@ async fn process_edit_event(
         }
     }
 
-    let data = ipfs.get(&edit.content_uri).await;
+    let FetchedEdit {
+        edit: data,
+        bytes,
+        metadata,
+    } = fetch_with_retry(ipfs, &edit.content_uri, retry).await;
 
     match data {
         Ok(result) => {
             let item = CacheItem {
                 uri: edit.content_uri.clone(),
                 block: block.timestamp.clone(),
+                content_hash: Some(edit_hash(&result)),
                 json: Some(result),
-                space: derive_space_id(GEO, &edit.dao_address),
+                space: network.space_id(&edit.dao_address),
                 is_errored: false,
-            };
+                content_length: None,
+                compression: None,
+                encoding: None,
+                fetch_duration_ms: None,
+                gateway: None,
+                raw_bytes: bytes,
+            }
+            .with_fetch_metadata(&metadata);
 
             let mut cache_instance = cache.lock().await;
-            let res = cache_instance.put(&item).await;
-
-            match res {
-                Ok(_) => {
-                    println!(
-                        "Successfully wrote cid to cache {} for block {}",
-                        edit.content_uri.clone(),
-                        block.block_number,
-                    );
-                }
-                Err(err) => {
-                    println!("Err {:?}", err)
-                }
-            }
+            cache_instance.put(&item).await?;
+
+            info!(
+                uri = %edit.content_uri,
+                block_number = block.block_number,
+                "Successfully wrote cid to cache"
+            );
         }
         Err(error) => {
-            println!(
-                "Error writing decoded edit event to cache for uri {} in block {} {}",
-                edit.content_uri, block.block_number, error
+            warn!(
+                error = %error,
+                uri = %edit.content_uri,
+                block_number = block.block_number,
+                "Error fetching edit from ipfs, writing errored cache entry"
             );
 
             // We may receive events where the format of the ipfs contents is
@@ -195,9 +329,17 @@ async fn process_edit_event(
                 uri: edit.content_uri,
                 block: block.timestamp.clone(),
                 json: None,
-                space: derive_space_id(GEO, &edit.dao_address),
+                space: network.space_id(&edit.dao_address),
                 is_errored: true,
-            };
+                content_hash: None,
+                content_length: None,
+                compression: None,
+                encoding: None,
+                fetch_duration_ms: None,
+                gateway: None,
+                raw_bytes: bytes,
+            }
+            .with_fetch_metadata(&metadata);
 
             let mut cache_instance = cache.lock().await;
             cache_instance.put(&item).await?;
@@ -207,30 +349,149 @@ async fn process_edit_event(
     Ok(())
 }
 
+/// Fetches an edit, retrying with backoff while the fetch fails with a
+/// transient error so a gateway hiccup doesn't cache the edit as errored.
+/// Returns the last attempt once the retries run out.
+async fn fetch_with_retry(ipfs: &IpfsClient, uri: &str, retry: RetryPolicy) -> FetchedEdit {
+    let attempt = async || {
+        let fetched = ipfs.fetch(uri).await;
+        match &fetched.edit {
+            Err(error) if error.is_transient() => {
+                warn!(
+                    error = %error,
+                    uri = %uri,
+                    "Transient error fetching edit from ipfs"
+                );
+                Err(fetched)
+            }
+            _ => Ok(fetched),
+        }
+    };
+
+    match Retry::spawn(retry.strategy(), attempt).await {
+        Ok(fetched) | Err(fetched) => fetched,
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     dotenv().ok();
 
-    let ipfs_gateway = env::var("IPFS_GATEWAY").expect("IPFS_GATEWAY not set");
-    let ipfs = IpfsClient::new(&ipfs_gateway);
-    let storage = cache::Storage::new().await;
+    init_tracing();
+
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(err) => {
+            error!(error = %err, "Invalid configuration");
+            std::process::exit(1);
+        }
+    };
+    id::select_scheme(config.network.id_scheme);
+    let start_block = config.substreams.start_block;
+
+    let mut ipfs = IpfsClient::new(config.ipfs_gateway().unwrap());
+    if let Some(per_sec) = config.ipfs.rate_limit_per_sec {
+        ipfs = ipfs.with_rate_limit(per_sec);
+    }
+    let storage = cache::Storage::new(config.database_url().unwrap(), &config.database).await;
 
     match storage {
         Ok(result) => {
+            let cursor_store = PostgresCursorStore::new(result.pool().clone());
             let kv = cache::Cache::new(result);
-            let indexer = CacheIndexer::new(kv, ipfs);
-
-            let endpoint_url =
-                env::var("SUBSTREAMS_ENDPOINT").expect("SUBSTREAMS_ENDPOINT not set");
+            let indexer = CacheIndexer::new(kv, ipfs, cursor_store, &config);
+            info!(network = %indexer.network.id, chain_id = ?indexer.network.chain_id, "Caching network");
+
+            let endpoint_url = config.substreams_endpoint().unwrap();
+
+            if let Some(backfill) = BackfillConfig::from_env() {
+                if let Err(err) = indexer
+                    .backfill(endpoint_url, PKG_FILE, MODULE_NAME, start_block, backfill)
+                    .await
+                {
+                    error!(error = %err, "Backfill failed");
+                    std::process::exit(1);
+                }
+            }
 
-            let _result = indexer
-                .run(&endpoint_url, PKG_FILE, MODULE_NAME, START_BLOCK, 0)
-                .await;
+            if let Err(err) = indexer
+                .run(endpoint_url, PKG_FILE, MODULE_NAME, start_block, 0)
+                .await
+            {
+                error!(error = %err, "Stream terminated");
+                std::process::exit(1);
+            }
         }
         Err(err) => {
-            println!("Error initializing stream {}", err);
+            error!(error = %err, "Error initializing stream");
         }
     }
 
     Ok(())
 }
+
+/// Loads the config and checks every setting the cache needs is set, so a
+/// misconfigured cache exits before connecting to anything.
+fn load_config() -> Result<Config, ConfigError> {
+    let config = Config::load()?;
+    config.database_url()?;
+    config.substreams_endpoint()?;
+    config.ipfs_gateway()?;
+
+    Ok(config)
+}
+
+/// Sets up the global tracing subscriber. Levels are controlled through
+/// `RUST_LOG` and `LOG_FORMAT=json` switches the console output to JSON.
+fn init_tracing() {
+    let registry = tracing_subscriber::registry().with(
+        tracing_subscriber::EnvFilter::try_from_default_env()
+            .unwrap_or_else(|_| "cache=info,stream=info".into()),
+    );
+
+    let use_json = env::var("LOG_FORMAT")
+        .map(|format| format.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+
+    if use_json {
+        registry
+            .with(tracing_subscriber::fmt::layer().with_target(true).json())
+            .init();
+    } else {
+        registry
+            .with(tracing_subscriber::fmt::layer().with_target(true))
+            .init();
+    }
+
+    info!(
+        service_name = "gaia.cache",
+        service_version = env!("CARGO_PKG_VERSION"),
+        json = use_json,
+        "Tracing initialized"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use ipfs::IpfsFaults;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_retries_transient_fetch_errors() {
+        let ipfs = IpfsClient::new("http://127.0.0.1:9/ipfs/").with_faults(IpfsFaults {
+            timeout_rate: 1.0,
+            ..IpfsFaults::default()
+        });
+        let retry = RetryPolicy {
+            max_retries: 3,
+            max_delay: Duration::from_millis(1),
+        };
+
+        let fetched = fetch_with_retry(&ipfs, "ipfs://cid", retry).await;
+
+        assert!(fetched.edit.unwrap_err().is_transient());
+        // The first attempt and every retry timed out
+        assert_eq!(ipfs.injected_faults(), 4);
+    }
+}
@@ -0,0 +1,142 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ProgressError {
+    #[error("Caching an edit of block {0} failed, the cursor can't advance past it")]
+    TaskFailed(u64),
+}
+
+/// Tracks the fetches spawned for each block, so the cursor is only
+/// persisted once every fetch of the block and the blocks before it has
+/// written its cache entry, decoded or errored. Otherwise a crash while
+/// fetches are running would resume after edits that were never cached.
+#[derive(Clone, Default)]
+pub struct BlockProgress {
+    state: Arc<Mutex<State>>,
+}
+
+#[derive(Default)]
+struct State {
+    blocks: BTreeMap<u64, PendingBlock>,
+    failed: Option<u64>,
+}
+
+#[derive(Default)]
+struct PendingBlock {
+    outstanding: usize,
+    /// Set once all of the block's fetches have been spawned.
+    cursor: Option<String>,
+}
+
+impl BlockProgress {
+    /// Tracks a fetch of the block. The fetch counts as failed if the task
+    /// is dropped before it's completed.
+    pub fn track(&self, block: u64) -> TrackedTask {
+        let mut state = self.state.lock().unwrap();
+        state.blocks.entry(block).or_default().outstanding += 1;
+
+        TrackedTask {
+            state: self.state.clone(),
+            block,
+            completed: false,
+        }
+    }
+
+    /// Records that all of the block's fetches have been spawned, and
+    /// returns the cursor and number of the latest block that can be
+    /// persisted, if it advanced.
+    pub fn finish_block(
+        &self,
+        block: u64,
+        cursor: String,
+    ) -> Result<Option<(String, u64)>, ProgressError> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(failed) = state.failed {
+            return Err(ProgressError::TaskFailed(failed));
+        }
+        state.blocks.entry(block).or_default().cursor = Some(cursor);
+
+        let mut completed = None;
+        while let Some(entry) = state.blocks.first_entry() {
+            if entry.get().outstanding > 0 || entry.get().cursor.is_none() {
+                break;
+            }
+
+            let block = *entry.key();
+            completed = entry.remove().cursor.map(|cursor| (cursor, block));
+        }
+
+        Ok(completed)
+    }
+}
+
+pub struct TrackedTask {
+    state: Arc<Mutex<State>>,
+    block: u64,
+    completed: bool,
+}
+
+impl TrackedTask {
+    pub fn complete(mut self) {
+        self.completed = true;
+    }
+}
+
+impl Drop for TrackedTask {
+    fn drop(&mut self) {
+        let mut state = self.state.lock().unwrap();
+        if !self.completed {
+            state.failed = Some(state.failed.map_or(self.block, |f| f.min(self.block)));
+        }
+        if let Some(pending) = state.blocks.get_mut(&self.block) {
+            pending.outstanding -= 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_waits_for_outstanding_fetches() {
+        let progress = BlockProgress::default();
+
+        let first = progress.track(1);
+        assert_eq!(progress.finish_block(1, "c1".to_string()), Ok(None));
+
+        // A later block finishing first can't be persisted
+        let second = progress.track(2);
+        second.complete();
+        assert_eq!(progress.finish_block(2, "c2".to_string()), Ok(None));
+        assert_eq!(progress.finish_block(3, "c3".to_string()), Ok(None));
+
+        first.complete();
+        assert_eq!(
+            progress.finish_block(4, "c4".to_string()),
+            Ok(Some(("c4".to_string(), 4)))
+        );
+        assert!(progress.state.lock().unwrap().blocks.is_empty());
+    }
+
+    #[test]
+    fn test_failed_fetch_holds_back_cursor() {
+        let progress = BlockProgress::default();
+
+        assert_eq!(
+            progress.finish_block(1, "c1".to_string()),
+            Ok(Some(("c1".to_string(), 1)))
+        );
+
+        drop(progress.track(2));
+        assert_eq!(
+            progress.finish_block(2, "c2".to_string()),
+            Err(ProgressError::TaskFailed(2))
+        );
+    }
+}
@@ -2,7 +2,9 @@ pub mod helpers;
 
 mod pb;
 
-use member_access_plugin::events::AddMemberProposalCreated as AddMemberProposalCreatedEvent;
+use member_access_plugin::events::{
+    AddMemberProposalCreated as AddMemberProposalCreatedEvent, Rejected as RejectedEvent,
+};
 use pb::schema::{
     AddEditorProposalCreated, AddEditorProposalsCreated, AddMemberProposalCreated,
     AddMemberProposalsCreated, AddSubspaceProposalCreated, AddSubspaceProposalsCreated,
@@ -10,7 +12,8 @@ use pb::schema::{
     GeoGovernancePluginCreated, GeoGovernancePluginsCreated, GeoOutput,
     GeoPersonalSpaceAdminPluginCreated, GeoPersonalSpaceAdminPluginsCreated, GeoSpaceCreated,
     GeoSpacesCreated, InitialEditorAdded, InitialEditorsAdded, MemberAdded, MemberRemoved,
-    MembersAdded, MembersRemoved, ProposalExecuted, ProposalsExecuted, PublishEditProposalCreated,
+    MembersAdded, MembersRemoved, ProposalCanceled, ProposalExecuted, ProposalsCanceled,
+    ProposalsExecuted, PublishEditProposalCreated,
     PublishEditsProposalsCreated, RemoveEditorProposalCreated, RemoveEditorProposalsCreated,
     RemoveMemberProposalCreated, RemoveMemberProposalsCreated, RemoveSubspaceProposalCreated,
     RemoveSubspaceProposalsCreated, SubspaceAdded, SubspaceRemoved, SubspacesAdded,
@@ -39,7 +42,7 @@ use main_voting_plugin::events::{
     AddEditorProposalCreated as AddEditorProposalCreatedEvent, EditorAdded as EditorAddedEvent,
     EditorRemoved as EditorRemovedEvent, EditorsAdded as EditorsAddedEvent,
     MemberAdded as MemberAddedEvent, MemberRemoved as MemberRemovedEvent,
-    ProposalExecuted as ProposalExecutedEvent,
+    ProposalCanceled as ProposalCanceledEvent, ProposalExecuted as ProposalExecutedEvent,
     PublishEditsProposalCreated as PublishEditsProposalCreatedEvent,
     RemoveEditorProposalCreated as RemoveEditorProposalCreatedEvent,
     RemoveMemberProposalCreated as RemoveMemberProposalCreatedEvent,
@@ -106,6 +109,8 @@ fn map_spaces_created(
                 return Some(GeoSpaceCreated {
                     dao_address: format_hex(&space_created.dao),
                     space_address: format_hex(&space_created.plugin),
+                    creator: format_hex(&log.receipt.transaction.from),
+                    tx_hash: format_hex(&log.receipt.transaction.hash),
                 });
             }
 
@@ -419,6 +424,7 @@ fn map_proposals_executed(
                 return Some(ProposalExecuted {
                     plugin_address: format_hex(&log.address()),
                     proposal_id: proposal_created.proposal_id.to_string(),
+                    tx_hash: format_hex(&log.receipt.transaction.hash),
                 });
             }
 
@@ -429,6 +435,43 @@ fn map_proposals_executed(
     Ok(ProposalsExecuted { executed_proposals })
 }
 
+/**
+ * Proposals in the main voting plugin can be canceled by their creator. The
+ * event doesn't name who canceled it, so it's the sender of the transaction.
+ *
+ * Membership requests in the member access plugin are vetoed once an editor
+ * rejects them.
+*/
+#[substreams::handlers::map]
+fn map_proposals_canceled(
+    block: eth::v2::Block,
+) -> Result<ProposalsCanceled, substreams::errors::Error> {
+    let canceled_proposals: Vec<ProposalCanceled> = block
+        .logs()
+        .filter_map(|log| {
+            if let Some(proposal_canceled) = ProposalCanceledEvent::match_and_decode(log) {
+                return Some(ProposalCanceled {
+                    plugin_address: format_hex(&log.address()),
+                    proposal_id: proposal_canceled.proposal_id.to_string(),
+                    canceled_by: format_hex(&log.receipt.transaction.from),
+                });
+            }
+
+            if let Some(rejected) = RejectedEvent::match_and_decode(log) {
+                return Some(ProposalCanceled {
+                    plugin_address: format_hex(&log.address()),
+                    proposal_id: rejected.proposal_id.to_string(),
+                    canceled_by: format_hex(&rejected.editor),
+                });
+            }
+
+            return None;
+        })
+        .collect();
+
+    Ok(ProposalsCanceled { canceled_proposals })
+}
+
 /**
  * Processed Proposals represent content that has been approved by a DAO
  * and executed onchain.
@@ -447,6 +490,7 @@ fn map_edits_published(block: eth::v2::Block) -> Result<EditsPublished, substrea
                     content_uri: edit_published.edits_content_uri,
                     dao_address: format_hex(&edit_published.dao),
                     plugin_address: format_hex(&log.address()),
+                    sender: format_hex(&log.receipt.transaction.from),
                 });
             }
 
@@ -704,6 +748,7 @@ fn geo_out(
     proposed_removed_editors: RemoveEditorProposalsCreated,
     proposed_added_subspaces: AddSubspaceProposalsCreated,
     proposed_removed_subspaces: RemoveSubspaceProposalsCreated,
+    proposals_canceled: ProposalsCanceled,
 ) -> Result<GeoOutput, substreams::errors::Error> {
     let spaces_created = spaces_created.spaces;
     let governance_plugins_created = governance_plugins_created.plugins;
@@ -743,5 +788,6 @@ fn geo_out(
         proposed_removed_editors: proposed_removed_editors.proposed_editors,
         proposed_added_subspaces: proposed_added_subspaces.proposed_subspaces,
         proposed_removed_subspaces: proposed_removed_subspaces.proposed_subspaces,
+        canceled_proposals: proposals_canceled.canceled_proposals,
     })
 }
@@ -35,6 +35,10 @@ pub struct GeoSpaceCreated {
     pub dao_address: ::prost::alloc::string::String,
     #[prost(string, tag="2")]
     pub space_address: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub creator: ::prost::alloc::string::String,
+    #[prost(string, tag="4")]
+    pub tx_hash: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -131,6 +135,8 @@ pub struct ProposalExecuted {
     pub proposal_id: ::prost::alloc::string::String,
     #[prost(string, tag="2")]
     pub plugin_address: ::prost::alloc::string::String,
+    #[prost(string, tag="3")]
+    pub tx_hash: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -138,6 +144,26 @@ pub struct ProposalsExecuted {
     #[prost(message, repeated, tag="1")]
     pub executed_proposals: ::prost::alloc::vec::Vec<ProposalExecuted>,
 }
+/// Canceled proposals were withdrawn by their creator in a space's main
+/// voting plugin, or vetoed by an editor in its member access plugin. Like
+/// executed proposals they only name the plugin, not the DAO.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProposalCanceled {
+    #[prost(string, tag="1")]
+    pub proposal_id: ::prost::alloc::string::String,
+    #[prost(string, tag="2")]
+    pub plugin_address: ::prost::alloc::string::String,
+    /// The creator who withdrew the proposal, or the editor who vetoed it
+    #[prost(string, tag="3")]
+    pub canceled_by: ::prost::alloc::string::String,
+}
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProposalsCanceled {
+    #[prost(message, repeated, tag="1")]
+    pub canceled_proposals: ::prost::alloc::vec::Vec<ProposalCanceled>,
+}
 /// *
 /// Processed Proposals represent content that has been approved by a DAO
 /// and executed onchain.
@@ -154,6 +180,9 @@ pub struct EditPublished {
     pub plugin_address: ::prost::alloc::string::String,
     #[prost(string, tag="3")]
     pub dao_address: ::prost::alloc::string::String,
+    /// The sender of the transaction that published the edit
+    #[prost(string, tag="4")]
+    pub sender: ::prost::alloc::string::String,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -523,5 +552,7 @@ pub struct GeoOutput {
     pub proposed_added_subspaces: ::prost::alloc::vec::Vec<AddSubspaceProposalCreated>,
     #[prost(message, repeated, tag="21")]
     pub proposed_removed_subspaces: ::prost::alloc::vec::Vec<RemoveSubspaceProposalCreated>,
+    #[prost(message, repeated, tag="22")]
+    pub canceled_proposals: ::prost::alloc::vec::Vec<ProposalCanceled>,
 }
 // @@protoc_insertion_point(module)
@@ -0,0 +1,114 @@
+//! Throughput of `root_handler` on blocks with a single synthetic edit of
+//! 1k, 10k and 100k ops.
+//!
+//! Writes go to a `RecordingStorage`, so what's measured is op squashing,
+//! validation and the models rather than Postgres. The handlers still begin
+//! and commit their transactions on a pool, so `DATABASE_URL` has to point
+//! at a database, though it needs no tables.
+
+use std::{env, sync::Arc};
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use dotenv::dotenv;
+use indexer::{
+    block_handler::root_handler,
+    cache::{properties_cache::PropertiesCache, PreprocessedEdit},
+    test_utils::{KgDataBuilder, RecordingStorage},
+    KgData,
+};
+use uuid::Uuid;
+use wire::{
+    builder::{relation, value, EditBuilder},
+    pb::grc20::DataType,
+};
+
+const SIZES: [usize; 3] = [1_000, 10_000, 100_000];
+
+const NAME_PROPERTY: Uuid = Uuid::from_u128(1);
+const COUNT_PROPERTY: Uuid = Uuid::from_u128(2);
+const RELATION_TYPE: Uuid = Uuid::from_u128(3);
+
+/// A block with an edit of `ops` ops: mostly entity updates, with relations
+/// between them and some unset values. Entities are updated a few times
+/// each, so squashing has work to do.
+fn block(ops: usize, space_id: Uuid) -> KgData {
+    let entity = |i: usize| Uuid::from_u128(1_000_000 + (i / 4) as u128);
+
+    let builder = EditBuilder::new(Uuid::from_u128(ops as u128))
+        .create_property(NAME_PROPERTY, DataType::Text)
+        .create_property(COUNT_PROPERTY, DataType::Number)
+        .create_property(RELATION_TYPE, DataType::Relation);
+    let edit = (3..ops)
+        .fold(builder, |builder, i| match i % 8 {
+            0..=4 => builder.update_entity(
+                entity(i),
+                [
+                    value(NAME_PROPERTY, format!("Entity {}", i)),
+                    value(COUNT_PROPERTY, i.to_string()),
+                ],
+            ),
+            5 | 6 => builder.create_relation(relation(
+                Uuid::from_u128(2_000_000 + i as u128),
+                Uuid::from_u128(3_000_000 + i as u128),
+                RELATION_TYPE,
+                entity(i),
+                entity(i / 2),
+            )),
+            _ => builder.unset_entity_values(entity(i), [COUNT_PROPERTY]),
+        })
+        .build();
+
+    KgDataBuilder::new(1)
+        .edit(PreprocessedEdit {
+            edit: Some(edit),
+            is_errored: false,
+            space_id,
+            cid: format!("ipfs://bench-{}", ops),
+            sender: None,
+            event_index: 0,
+        })
+        .build()
+}
+
+fn benchmark_root_handler(c: &mut Criterion) {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let pool = runtime
+        .block_on(sqlx::PgPool::connect(&database_url))
+        .unwrap();
+    let space_id = Uuid::from_u128(42);
+
+    let mut group = c.benchmark_group("root_handler");
+    group.sample_size(10);
+    for ops in SIZES {
+        let data = block(ops, space_id);
+        group.throughput(Throughput::Elements(ops as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(ops), &data, |b, data| {
+            // The storage and cache are fresh for every run, since an edit
+            // whose content was recorded before is skipped
+            b.iter_batched(
+                || {
+                    (
+                        Arc::new(RecordingStorage::new(pool.clone()).with_spaces([space_id])),
+                        Arc::new(PropertiesCache::new()),
+                    )
+                },
+                |(storage, properties_cache)| {
+                    runtime.block_on(async {
+                        root_handler::run(data, &data.block, &storage, &properties_cache)
+                            .await
+                            .unwrap();
+                    });
+                    // Returned so dropping the recorded calls isn't timed
+                    storage
+                },
+                BatchSize::PerIteration,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, benchmark_root_handler);
+criterion_main!(benches);
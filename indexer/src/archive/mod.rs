@@ -0,0 +1,128 @@
+//! Archives the raw output of every indexed block in object storage, so the
+//! database can be rebuilt from the archive instead of streaming every block
+//! from a substreams provider again.
+//!
+//! Each block is stored as its encoded `BlockScopedData`, which carries the
+//! raw `GeoOutput` bytes with the block's clock and cursor, compressed with
+//! zstd. Blocks are named like the blocks written by
+//! [`stream::recorder::BlockRecorder`], so a recording made with
+//! `BLOCK_ARCHIVE_DIR` can be replayed as an archive too.
+
+use std::sync::Arc;
+
+use indexer_utils::config::ArchiveConfig;
+use prost::Message;
+use stream::pb::sf::substreams::rpc::v2::BlockScopedData;
+
+use crate::error::IndexingError;
+
+use stream::archive::parse_block_key;
+pub use stream::archive::{block_key, file, s3, ArchiveError, ArchiveStore};
+
+pub struct BlockArchive {
+    store: Arc<dyn ArchiveStore>,
+    compression_level: i32,
+}
+
+impl BlockArchive {
+    pub fn new(store: Arc<dyn ArchiveStore>, compression_level: i32) -> Self {
+        BlockArchive {
+            store,
+            compression_level,
+        }
+    }
+
+    /// Opens the configured archive, or returns `None` if no archive URL is
+    /// set. URLs starting with `s3://` name a bucket and an optional key
+    /// prefix, anything else a directory.
+    pub fn from_config(config: &ArchiveConfig) -> Result<Option<Self>, IndexingError> {
+        let Some(url) = &config.url else {
+            return Ok(None);
+        };
+
+        let store: Arc<dyn ArchiveStore> = match url.strip_prefix("s3://") {
+            Some(location) => {
+                let (bucket, prefix) = location.split_once('/').unwrap_or((location, ""));
+                let (access_key_id, secret_access_key) = config.credentials()?;
+                Arc::new(s3::S3Store::new(
+                    config.endpoint.as_deref(),
+                    &config.region,
+                    bucket,
+                    prefix,
+                    access_key_id,
+                    secret_access_key,
+                )?)
+            }
+            None => Arc::new(file::DirectoryStore::new(
+                url.strip_prefix("file://").unwrap_or(url),
+            )?),
+        };
+
+        Ok(Some(BlockArchive::new(store, config.compression_level)))
+    }
+
+    /// Stores a block, replacing it if it was already archived.
+    pub async fn archive(&self, block: &BlockScopedData) -> Result<(), ArchiveError> {
+        let block_number = block.clock.as_ref().map(|clock| clock.number).unwrap_or(0);
+        let compressed =
+            zstd::encode_all(block.encode_to_vec().as_slice(), self.compression_level)?;
+
+        self.store.put(&block_key(block_number), compressed).await
+    }
+
+    pub async fn load(&self, block_number: u64) -> Result<BlockScopedData, ArchiveError> {
+        let compressed = self
+            .store
+            .get(&block_key(block_number))
+            .await?
+            .ok_or(ArchiveError::NotFound(block_number))?;
+        let bytes = zstd::decode_all(compressed.as_slice())?;
+
+        Ok(BlockScopedData::decode(bytes.as_slice())?)
+    }
+
+    /// The numbers of the archived blocks after `block`, or of every
+    /// archived block if it's `None`, in order.
+    pub async fn blocks_after(&self, block: Option<u64>) -> Result<Vec<u64>, ArchiveError> {
+        let mut blocks: Vec<u64> = self
+            .store
+            .list()
+            .await?
+            .iter()
+            .filter_map(|key| parse_block_key(key))
+            .filter(|number| block.is_none_or(|block| *number > block))
+            .collect();
+        blocks.sort_unstable();
+
+        Ok(blocks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stream::test_source::fixture_block;
+
+    #[tokio::test]
+    async fn test_archive_and_load_blocks() {
+        let dir = std::env::temp_dir().join(format!("gaia-archive-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let archive = BlockArchive::new(Arc::new(file::DirectoryStore::new(&dir).unwrap()), 3);
+
+        let first = fixture_block(10, 1_700_000_010, "geo_out", "type", vec![7; 1024]);
+        let second = fixture_block(20, 1_700_000_020, "geo_out", "type", vec![9; 1024]);
+        archive.archive(&second).await.unwrap();
+        archive.archive(&first).await.unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a block").unwrap();
+
+        assert_eq!(archive.blocks_after(None).await.unwrap(), vec![10, 20]);
+        assert_eq!(archive.blocks_after(Some(10)).await.unwrap(), vec![20]);
+        assert_eq!(archive.load(10).await.unwrap(), first);
+        assert!(matches!(
+            archive.load(30).await,
+            Err(ArchiveError::NotFound(30))
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,383 @@
+//! Backups of the knowledge graph tables, to roll the indexer back to a
+//! known-good state.
+//!
+//! A backup is a directory with one file per table, in the text format of
+//! Postgres' `COPY`, and a `manifest.json` describing it. Backups are taken
+//! in a single snapshot, so the tables match the cursors saved with them.
+//! Restoring a full backup also restores the cursors and the ledger of
+//! processed blocks, so the indexer resumes from the backup's block.
+//!
+//! A backup can also be limited to some spaces. Entities, properties and the
+//! indexer's cursors aren't backed up then, as they're shared by every space,
+//! and restoring it only replaces the rows of those spaces.
+
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::comparison::columns;
+
+/// The version of the backup format, bumped when backups written by older
+/// versions can't be restored anymore.
+pub const BACKUP_VERSION: u32 = 1;
+
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// Cursor ids of the knowledge graph indexer start with this, whatever the
+/// network or shard.
+const KG_INDEXER_ID: &str = "kg_indexer";
+
+#[derive(Error, Debug)]
+pub enum BackupError {
+    #[error("Backup error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Backup error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Backup error: {0}")]
+    Manifest(#[from] serde_json::Error),
+
+    #[error("Backup version {0} isn't supported")]
+    UnsupportedVersion(u32),
+
+    #[error("'{0}' isn't a backed up table")]
+    UnknownTable(String),
+
+    #[error("{0} already has a backup")]
+    AlreadyExists(PathBuf),
+}
+
+/// Which rows of a table belong to a backup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Scope {
+    /// Rows shared by every space, only in full backups.
+    Global,
+    /// Rows of the space in the given column.
+    Space(&'static str),
+    /// Rows of the knowledge graph indexer, by the cursor id in the given
+    /// column, only in full backups.
+    Indexer(&'static str),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BackedUpTable {
+    name: &'static str,
+    scope: Scope,
+    /// The column filled by a sequence, if any.
+    serial: Option<&'static str>,
+}
+
+const fn table(name: &'static str, scope: Scope) -> BackedUpTable {
+    BackedUpTable {
+        name,
+        scope,
+        serial: None,
+    }
+}
+
+/// The tables written by the knowledge graph indexer. The IPFS cache has
+/// its own cursor and isn't backed up.
+const BACKED_UP_TABLES: [BackedUpTable; 24] = [
+    table("spaces", Scope::Space("id")),
+    table("entities", Scope::Global),
+    table("properties", Scope::Global),
+    table("values", Scope::Space("space_id")),
+    table("relations", Scope::Space("space_id")),
+    table("members", Scope::Space("space_id")),
+    table("editors", Scope::Space("space_id")),
+    table("editor_history", Scope::Space("space_id")),
+    table("personal_admin_history", Scope::Space("space_id")),
+    table("space_plugins_history", Scope::Space("space_id")),
+    table("subspaces", Scope::Space("parent_space_id")),
+    table("subspace_closure", Scope::Space("descendant_space_id")),
+    table("edits", Scope::Space("space_id")),
+    table("unverified_edits", Scope::Space("space_id")),
+    table("unauthorized_edits", Scope::Space("space_id")),
+    BackedUpTable {
+        name: "property_migrations",
+        scope: Scope::Space("space_id"),
+        serial: Some("id"),
+    },
+    table("space_activity_rollups", Scope::Space("space_id")),
+    table("space_active_editors", Scope::Space("space_id")),
+    table("counted_proposals", Scope::Space("space_id")),
+    table("counted_blocks", Scope::Space("space_id")),
+    table("proposal_snapshots", Scope::Space("space_id")),
+    table("proposals", Scope::Space("space_id")),
+    table("processed_blocks", Scope::Indexer("indexer_id")),
+    table("meta", Scope::Indexer("id")),
+];
+
+impl BackedUpTable {
+    fn find(name: &str) -> Option<BackedUpTable> {
+        BACKED_UP_TABLES
+            .iter()
+            .find(|table| table.name == name)
+            .copied()
+    }
+
+    /// The condition selecting the table's rows in a backup of the given
+    /// spaces, or of everything. `None` if the table isn't in the backup.
+    fn condition(&self, space_ids: Option<&[Uuid]>) -> Option<String> {
+        match (self.scope, space_ids) {
+            (Scope::Global, None) | (Scope::Space(_), None) => Some("TRUE".to_string()),
+            (Scope::Indexer(column), None) => {
+                Some(format!("\"{}\" LIKE '{}%'", column, KG_INDEXER_ID))
+            }
+            (Scope::Space(column), Some(space_ids)) => {
+                // Uuids can't hold quotes, and COPY doesn't take parameters
+                let space_ids: Vec<String> =
+                    space_ids.iter().map(|id| format!("'{}'", id)).collect();
+                Some(format!(
+                    "\"{}\" = ANY(ARRAY[{}]::uuid[])",
+                    column,
+                    space_ids.join(", ")
+                ))
+            }
+            (Scope::Global, Some(_)) | (Scope::Indexer(_), Some(_)) => None,
+        }
+    }
+
+    fn path(&self, directory: &Path) -> PathBuf {
+        directory.join(format!("{}.copy", self.name))
+    }
+}
+
+/// A cursor of the knowledge graph indexer when the backup was taken.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BackupCursor {
+    pub id: String,
+    pub cursor: String,
+    pub block_number: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupTable {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub version: u32,
+    pub created_at: String,
+    /// The spaces in the backup, or `None` if everything is.
+    pub space_ids: Option<Vec<Uuid>>,
+    pub cursors: Vec<BackupCursor>,
+    /// The lowest block of the cursors, which every part of the indexer had
+    /// reached when the backup was taken.
+    pub block_number: Option<u64>,
+    pub tables: Vec<BackupTable>,
+}
+
+impl BackupManifest {
+    pub fn read(directory: &Path) -> Result<Self, BackupError> {
+        let file = File::open(directory.join(MANIFEST_FILE))?;
+        Ok(serde_json::from_reader(file)?)
+    }
+}
+
+/// Writes a backup of the knowledge graph tables, or of the rows of the
+/// given spaces, to an empty or missing directory.
+pub async fn backup(
+    pool: &PgPool,
+    directory: &Path,
+    space_ids: Option<&[Uuid]>,
+) -> Result<BackupManifest, BackupError> {
+    if directory.join(MANIFEST_FILE).exists() {
+        return Err(BackupError::AlreadyExists(directory.to_path_buf()));
+    }
+    fs::create_dir_all(directory)?;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+        .execute(&mut *tx)
+        .await?;
+
+    let cursors: Vec<BackupCursor> = sqlx::query_as(
+        "SELECT id, cursor, block_number FROM meta WHERE id LIKE $1 || '%' ORDER BY id",
+    )
+    .bind(KG_INDEXER_ID)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let mut manifest = BackupManifest {
+        version: BACKUP_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        space_ids: space_ids.map(<[Uuid]>::to_vec),
+        block_number: cursors
+            .iter()
+            .filter_map(|cursor| cursor.block_number.parse().ok())
+            .min(),
+        cursors,
+        tables: Vec::new(),
+    };
+
+    for table in BACKED_UP_TABLES {
+        let Some(condition) = table.condition(space_ids) else {
+            continue;
+        };
+        let columns = columns(pool, table.name).await?;
+        let statement = format!(
+            "COPY (SELECT {} FROM \"{}\" WHERE {}) TO STDOUT",
+            quoted(&columns),
+            table.name,
+            condition
+        );
+
+        let mut writer = BufWriter::new(File::create(table.path(directory))?);
+        let mut stream = tx.copy_out_raw(&statement).await?;
+        let mut rows = 0;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            // Newlines in values are escaped, so each one ends a row
+            rows += chunk.iter().filter(|byte| **byte == b'\n').count() as u64;
+            writer.write_all(&chunk)?;
+        }
+        drop(stream);
+        writer.flush()?;
+
+        info!(table = table.name, rows = rows, "Backed up table");
+        manifest.tables.push(BackupTable {
+            name: table.name.to_string(),
+            columns,
+            rows,
+        });
+    }
+
+    tx.commit().await?;
+
+    // The manifest is written last, so an interrupted backup has none
+    let file = File::create(directory.join(MANIFEST_FILE))?;
+    serde_json::to_writer_pretty(file, &manifest)?;
+
+    Ok(manifest)
+}
+
+/// Replaces the rows in a backup with the backed up rows, in a single
+/// transaction. The indexer should be stopped while the backup is restored.
+pub async fn restore(pool: &PgPool, directory: &Path) -> Result<BackupManifest, BackupError> {
+    let manifest = BackupManifest::read(directory)?;
+    if manifest.version != BACKUP_VERSION {
+        return Err(BackupError::UnsupportedVersion(manifest.version));
+    }
+
+    let tables = manifest
+        .tables
+        .iter()
+        .map(|backup| {
+            BackedUpTable::find(&backup.name)
+                .map(|table| (table, backup))
+                .ok_or_else(|| BackupError::UnknownTable(backup.name.clone()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let space_ids = manifest.space_ids.as_deref();
+
+    let mut tx = pool.begin().await?;
+
+    // Every table referencing the truncated tables is truncated with them
+    let truncated: Vec<String> = tables
+        .iter()
+        .filter(|(table, _)| space_ids.is_none() && !matches!(table.scope, Scope::Indexer(_)))
+        .map(|(table, _)| format!("\"{}\"", table.name))
+        .collect();
+    if !truncated.is_empty() {
+        sqlx::query(&format!("TRUNCATE {}", truncated.join(", ")))
+            .execute(&mut *tx)
+            .await?;
+    }
+
+    for (table, backup) in &tables {
+        if space_ids.is_some() || matches!(table.scope, Scope::Indexer(_)) {
+            let condition = table
+                .condition(space_ids)
+                .ok_or_else(|| BackupError::UnknownTable(backup.name.clone()))?;
+            sqlx::query(&format!(
+                "DELETE FROM \"{}\" WHERE {}",
+                table.name, condition
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let statement = format!(
+            "COPY \"{}\" ({}) FROM STDIN",
+            table.name,
+            quoted(&backup.columns)
+        );
+        let mut file = File::open(table.path(directory))?;
+        let mut buffer = vec![0; 1 << 20];
+        let mut copy = tx.copy_in_raw(&statement).await?;
+        loop {
+            let read = file.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            copy.send(&buffer[..read]).await?;
+        }
+        let rows = copy.finish().await?;
+
+        if let Some(column) = table.serial {
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence('\"{}\"', '{}'), COALESCE(MAX(\"{}\"), 0) + 1, false) FROM \"{}\"",
+                table.name, column, column, table.name
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        info!(table = table.name, rows = rows, "Restored table");
+    }
+
+    tx.commit().await?;
+
+    Ok(manifest)
+}
+
+fn quoted(columns: &[String]) -> String {
+    columns
+        .iter()
+        .map(|column| format!("\"{}\"", column))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table_conditions() {
+        let space_id = Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap();
+        let values = BackedUpTable::find("values").unwrap();
+        let meta = BackedUpTable::find("meta").unwrap();
+        let entities = BackedUpTable::find("entities").unwrap();
+
+        assert_eq!(values.condition(None), Some("TRUE".to_string()));
+        assert_eq!(
+            values.condition(Some(&[space_id])),
+            Some(
+                "\"space_id\" = ANY(ARRAY['550e8400-e29b-41d4-a716-446655440000']::uuid[])"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            meta.condition(None),
+            Some("\"id\" LIKE 'kg_indexer%'".to_string())
+        );
+        assert_eq!(meta.condition(Some(&[space_id])), None);
+        assert_eq!(entities.condition(Some(&[space_id])), None);
+        assert_eq!(BackedUpTable::find("ipfs_cache"), None);
+    }
+}
@@ -0,0 +1,591 @@
+//! Maintenance commands for a knowledge graph database.
+//!
+//! ```text
+//! gaia verify [--fix] [--sample <n>]
+//! gaia compare <left-url> <right-url> [--table <name>]... [--sample <n>] [--batch <n>]
+//! gaia backup <directory> [--space <id>]...
+//! gaia restore <directory>
+//! gaia copy-cache <source-url> <target-url> [--since <timestamp>] [--until <timestamp>]
+//!     [--cid <cid>]... [--space <id>]... [--batch <n>]
+//! gaia redecode-cache [--all] [--batch <n>]
+//! gaia gc-cache [--dry-run | --delete] [--batch <n>]
+//! gaia map-ids --to <scheme> [--batch <n>]
+//! ```
+//!
+//! `verify` checks the tables for rows that reference missing rows and
+//! prints a JSON report to stdout. With `--fix`, fixable rows are deleted.
+//! It exits with status 1 if any issue is left.
+//!
+//! `compare` compares the knowledge graph tables of two databases, or only
+//! the given tables, and prints a JSON report of the rows that diverge. It
+//! exits with status 1 if any row diverges.
+//!
+//! `backup` writes the knowledge graph tables, or the rows of the given
+//! spaces, to a new directory with the indexer's cursors, and `restore`
+//! replaces the rows in a backup with the backed up rows. Both print the
+//! backup's manifest.
+//!
+//! `copy-cache` copies the IPFS cache entries of the source database that
+//! the target doesn't have, optionally only those of edits published within
+//! a range of block timestamps, with the given CIDs or in the given spaces.
+//! It prints a JSON report of the copied entries.
+//!
+//! `redecode-cache` decodes the errored IPFS cache entries again from the
+//! raw bytes they were fetched with, or every entry with `--all`, and
+//! updates the entries whose decoded edit changed. It prints a JSON report
+//! and exits with status 1 if any entry still doesn't decode.
+//!
+//! `gc-cache` moves the IPFS cache entries of blocklisted DAOs, and those
+//! of indexed blocks that no edit or proposal refers to, to the cache
+//! archive. With `--delete` they're deleted instead, and with `--dry-run`
+//! they're only counted. It prints a JSON report of the collected entries.
+//!
+//! `map-ids` writes the id every space and value would have with another id
+//! scheme to `id_mappings`, for migrating references to the old ids once the
+//! deployment is reindexed with the new scheme. It prints a JSON report of
+//! the mapped ids.
+
+use std::{env, path::PathBuf, process::ExitCode};
+
+use dotenv::dotenv;
+use indexer::{
+    backup,
+    cache::{
+        copy::{self, CacheCopy, CacheSelection},
+        gc::{CacheCollector, CollectMode},
+        redecode::CacheRedecoder,
+    },
+    comparison::{ComparedTable, DatabaseComparison, COMPARED_TABLES, DEFAULT_BATCH_SIZE},
+    consistency::{ConsistencyChecker, DEFAULT_SAMPLE_SIZE},
+    error::IndexingError,
+    id_mapping::IdMapper,
+};
+use indexer_utils::{
+    config::Config,
+    get_blocklist,
+    id::{self, IdScheme},
+};
+use uuid::Uuid;
+
+const USAGE: &str = "Usage:
+  gaia verify [--fix] [--sample <n>]
+  gaia compare <left-url> <right-url> [--table <name>]... [--sample <n>] [--batch <n>]
+  gaia backup <directory> [--space <id>]...
+  gaia restore <directory>
+  gaia copy-cache <source-url> <target-url> [--since <timestamp>] [--until <timestamp>]
+      [--cid <cid>]... [--space <id>]... [--batch <n>]
+  gaia redecode-cache [--all] [--batch <n>]
+  gaia gc-cache [--dry-run | --delete] [--batch <n>]
+  gaia map-ids --to <scheme> [--batch <n>]";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Verify {
+        fix: bool,
+        sample_size: i64,
+    },
+    Compare {
+        left_url: String,
+        right_url: String,
+        tables: Vec<ComparedTable>,
+        sample_size: i64,
+        batch_size: i64,
+    },
+    Backup {
+        directory: PathBuf,
+        space_ids: Vec<Uuid>,
+    },
+    Restore {
+        directory: PathBuf,
+    },
+    CopyCache {
+        source_url: String,
+        target_url: String,
+        selection: CacheSelection,
+        batch_size: i64,
+    },
+    RedecodeCache {
+        all: bool,
+        batch_size: i64,
+    },
+    GcCache {
+        mode: CollectMode,
+        batch_size: i64,
+    },
+    MapIds {
+        scheme: IdScheme,
+        batch_size: i64,
+    },
+}
+
+/// Parses the value of a numeric option.
+fn number(option: &str, value: Option<&String>, min: i64) -> Result<i64, String> {
+    value
+        .and_then(|value| value.parse().ok())
+        .filter(|number| *number >= min)
+        .ok_or_else(|| format!("{} expects a number of at least {}", option, min))
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let Some((command, options)) = args.split_first() else {
+        return Err("no command given".to_string());
+    };
+
+    match command.as_str() {
+        "verify" => {
+            let (mut fix, mut sample_size) = (false, DEFAULT_SAMPLE_SIZE);
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--fix" => fix = true,
+                    "--sample" => sample_size = number(option, options.next(), 0)?,
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            Ok(Command::Verify { fix, sample_size })
+        }
+        "compare" => {
+            let (Some(left_url), Some(right_url)) = (options.first(), options.get(1)) else {
+                return Err("compare expects the URLs of two databases".to_string());
+            };
+            let (mut tables, mut sample_size, mut batch_size) =
+                (Vec::new(), DEFAULT_SAMPLE_SIZE, DEFAULT_BATCH_SIZE);
+            let mut options = options[2..].iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--table" => {
+                        let name = options.next().ok_or("--table expects a table name")?;
+                        let table = ComparedTable::find(name)
+                            .ok_or_else(|| format!("'{}' isn't a compared table", name))?;
+                        tables.push(table);
+                    }
+                    "--sample" => sample_size = number(option, options.next(), 0)?,
+                    "--batch" => batch_size = number(option, options.next(), 1)?,
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            if tables.is_empty() {
+                tables = COMPARED_TABLES.to_vec();
+            }
+
+            Ok(Command::Compare {
+                left_url: left_url.clone(),
+                right_url: right_url.clone(),
+                tables,
+                sample_size,
+                batch_size,
+            })
+        }
+        "backup" => {
+            let Some((directory, options)) = options.split_first() else {
+                return Err("backup expects a directory".to_string());
+            };
+            let mut space_ids = Vec::new();
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--space" => {
+                        let space_id = options
+                            .next()
+                            .and_then(|id| Uuid::parse_str(id).ok())
+                            .ok_or("--space expects a space id")?;
+                        space_ids.push(space_id);
+                    }
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            Ok(Command::Backup {
+                directory: PathBuf::from(directory),
+                space_ids,
+            })
+        }
+        "restore" => match options {
+            [directory] => Ok(Command::Restore {
+                directory: PathBuf::from(directory),
+            }),
+            _ => Err("restore expects a directory".to_string()),
+        },
+        "copy-cache" => {
+            let (Some(source_url), Some(target_url)) = (options.first(), options.get(1)) else {
+                return Err("copy-cache expects the URLs of two databases".to_string());
+            };
+            let (mut selection, mut batch_size) =
+                (CacheSelection::default(), copy::DEFAULT_BATCH_SIZE);
+            let mut options = options[2..].iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--since" => selection.since = Some(number(option, options.next(), 0)?),
+                    "--until" => selection.until = Some(number(option, options.next(), 0)?),
+                    "--cid" => {
+                        let cid = options.next().ok_or("--cid expects a CID")?;
+                        selection.uris.push(copy::cache_uri(cid));
+                    }
+                    "--space" => {
+                        let space_id = options
+                            .next()
+                            .and_then(|id| Uuid::parse_str(id).ok())
+                            .ok_or("--space expects a space id")?;
+                        selection.space_ids.push(space_id);
+                    }
+                    "--batch" => batch_size = number(option, options.next(), 1)?,
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            Ok(Command::CopyCache {
+                source_url: source_url.clone(),
+                target_url: target_url.clone(),
+                selection,
+                batch_size,
+            })
+        }
+        "redecode-cache" => {
+            let (mut all, mut batch_size) = (false, copy::DEFAULT_BATCH_SIZE);
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--all" => all = true,
+                    "--batch" => batch_size = number(option, options.next(), 1)?,
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            Ok(Command::RedecodeCache { all, batch_size })
+        }
+        "gc-cache" => {
+            let (mut mode, mut batch_size) = (CollectMode::Archive, copy::DEFAULT_BATCH_SIZE);
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                let collect = match option.as_str() {
+                    "--dry-run" => CollectMode::DryRun,
+                    "--delete" => CollectMode::Delete,
+                    "--batch" => {
+                        batch_size = number(option, options.next(), 1)?;
+                        continue;
+                    }
+                    _ => return Err(format!("unknown option '{}'", option)),
+                };
+                if mode != CollectMode::Archive && mode != collect {
+                    return Err("--dry-run and --delete can't be combined".to_string());
+                }
+                mode = collect;
+            }
+
+            Ok(Command::GcCache { mode, batch_size })
+        }
+        "map-ids" => {
+            let (mut scheme, mut batch_size) = (None, copy::DEFAULT_BATCH_SIZE);
+            let mut options = options.iter();
+
+            while let Some(option) = options.next() {
+                match option.as_str() {
+                    "--to" => {
+                        let value = options.next().ok_or("--to expects a scheme")?;
+                        scheme = Some(value.parse().map_err(|e| format!("--to {}", e))?);
+                    }
+                    "--batch" => batch_size = number(option, options.next(), 1)?,
+                    _ => return Err(format!("unknown option '{}'", option)),
+                }
+            }
+
+            let scheme = scheme.ok_or("map-ids expects --to")?;
+            Ok(Command::MapIds { scheme, batch_size })
+        }
+        _ => Err(format!("unknown command '{}'", command)),
+    }
+}
+
+async fn run(command: Command) -> Result<bool, IndexingError> {
+    let config = Config::load()?;
+    id::select_scheme(config.network.id_scheme);
+
+    match command {
+        Command::Verify { fix, sample_size } => {
+            let pool = config.database.connect(config.database_url()?).await?;
+            let report = ConsistencyChecker::new(pool, sample_size).run(fix).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("reports serialize to JSON")
+            );
+
+            Ok(!report.has_issues())
+        }
+        Command::Compare {
+            left_url,
+            right_url,
+            tables,
+            sample_size,
+            batch_size,
+        } => {
+            let left = config.database.connect(&left_url).await?;
+            let right = config.database.connect(&right_url).await?;
+            let report = DatabaseComparison::new(left, right, batch_size, sample_size as usize)
+                .run(&tables)
+                .await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("reports serialize to JSON")
+            );
+
+            Ok(report.is_identical())
+        }
+        Command::Backup {
+            directory,
+            space_ids,
+        } => {
+            let pool = config.database.connect(config.database_url()?).await?;
+            let space_ids = (!space_ids.is_empty()).then_some(space_ids.as_slice());
+            let manifest = backup::backup(&pool, &directory, space_ids).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&manifest).expect("manifests serialize to JSON")
+            );
+
+            Ok(true)
+        }
+        Command::Restore { directory } => {
+            let pool = config.database.connect(config.database_url()?).await?;
+            let manifest = backup::restore(&pool, &directory).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&manifest).expect("manifests serialize to JSON")
+            );
+
+            Ok(true)
+        }
+        Command::CopyCache {
+            source_url,
+            target_url,
+            selection,
+            batch_size,
+        } => {
+            let source = config.database.connect(&source_url).await?;
+            let target = config.database.connect(&target_url).await?;
+            let report = CacheCopy::new(source, target, batch_size)
+                .run(&selection)
+                .await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("reports serialize to JSON")
+            );
+
+            Ok(true)
+        }
+        Command::RedecodeCache { all, batch_size } => {
+            let pool = config.database.connect(config.database_url()?).await?;
+            let report = CacheRedecoder::new(pool, batch_size).run(all).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("reports serialize to JSON")
+            );
+
+            Ok(report.failed == 0)
+        }
+        Command::GcCache { mode, batch_size } => {
+            let pool = config.database.connect(config.database_url()?).await?;
+            let network = config.network();
+            let blocked_space_ids: Vec<Uuid> = get_blocklist()
+                .dao_addresses
+                .iter()
+                .map(|dao_address| network.space_id(dao_address))
+                .collect();
+            let report = CacheCollector::new(pool, batch_size)
+                .run(mode, &blocked_space_ids)
+                .await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("reports serialize to JSON")
+            );
+
+            Ok(true)
+        }
+        Command::MapIds { scheme, batch_size } => {
+            let pool = config.database.connect(config.database_url()?).await?;
+            let report = IdMapper::new(pool, batch_size).run(scheme).await?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&report).expect("reports serialize to JSON")
+            );
+
+            Ok(true)
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    dotenv().ok();
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env()
+                .unwrap_or_else(|_| "indexer=info".into()),
+        )
+        .init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(command).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(2)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            parse_args(&args(&["verify"])),
+            Ok(Command::Verify {
+                fix: false,
+                sample_size: DEFAULT_SAMPLE_SIZE
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["verify", "--sample", "5", "--fix"])),
+            Ok(Command::Verify {
+                fix: true,
+                sample_size: 5
+            })
+        );
+        assert!(parse_args(&args(&["verify", "--sample"])).is_err());
+        assert!(parse_args(&args(&["verify", "--force"])).is_err());
+        assert!(parse_args(&args(&["repair"])).is_err());
+
+        assert_eq!(
+            parse_args(&args(&[
+                "compare",
+                "postgres://old",
+                "postgres://new",
+                "--table",
+                "members",
+                "--batch",
+                "10"
+            ])),
+            Ok(Command::Compare {
+                left_url: "postgres://old".to_string(),
+                right_url: "postgres://new".to_string(),
+                tables: vec![ComparedTable::find("members").unwrap()],
+                sample_size: DEFAULT_SAMPLE_SIZE,
+                batch_size: 10,
+            })
+        );
+        assert!(matches!(
+            parse_args(&args(&["compare", "postgres://old", "postgres://new"])),
+            Ok(Command::Compare { tables, .. }) if tables == COMPARED_TABLES.to_vec()
+        ));
+        assert!(parse_args(&args(&["compare", "postgres://old"])).is_err());
+        assert!(parse_args(&args(&["compare", "a", "b", "--table", "meta"])).is_err());
+        assert!(parse_args(&args(&["compare", "a", "b", "--batch", "0"])).is_err());
+
+        let space_id = Uuid::new_v4();
+        assert_eq!(
+            parse_args(&args(&[
+                "backup",
+                "backups/1",
+                "--space",
+                &space_id.to_string()
+            ])),
+            Ok(Command::Backup {
+                directory: PathBuf::from("backups/1"),
+                space_ids: vec![space_id],
+            })
+        );
+        assert!(parse_args(&args(&["backup"])).is_err());
+        assert!(parse_args(&args(&["backup", "backups/1", "--space", "space"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["restore", "backups/1"])),
+            Ok(Command::Restore {
+                directory: PathBuf::from("backups/1"),
+            })
+        );
+        assert!(parse_args(&args(&["restore", "backups/1", "backups/2"])).is_err());
+
+        assert_eq!(
+            parse_args(&args(&[
+                "copy-cache",
+                "postgres://prod",
+                "postgres://dev",
+                "--since",
+                "1700000000",
+                "--cid",
+                "bafkreib",
+                "--space",
+                &space_id.to_string()
+            ])),
+            Ok(Command::CopyCache {
+                source_url: "postgres://prod".to_string(),
+                target_url: "postgres://dev".to_string(),
+                selection: CacheSelection {
+                    since: Some(1_700_000_000),
+                    until: None,
+                    uris: vec!["ipfs://bafkreib".to_string()],
+                    space_ids: vec![space_id],
+                },
+                batch_size: copy::DEFAULT_BATCH_SIZE,
+            })
+        );
+        assert!(parse_args(&args(&["copy-cache", "postgres://prod"])).is_err());
+        assert!(parse_args(&args(&["copy-cache", "a", "b", "--until", "soon"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["redecode-cache", "--all", "--batch", "50"])),
+            Ok(Command::RedecodeCache {
+                all: true,
+                batch_size: 50,
+            })
+        );
+        assert!(parse_args(&args(&["redecode-cache", "--batch", "0"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["gc-cache"])),
+            Ok(Command::GcCache {
+                mode: CollectMode::Archive,
+                batch_size: copy::DEFAULT_BATCH_SIZE,
+            })
+        );
+        assert_eq!(
+            parse_args(&args(&["gc-cache", "--delete", "--batch", "10"])),
+            Ok(Command::GcCache {
+                mode: CollectMode::Delete,
+                batch_size: 10,
+            })
+        );
+        assert!(parse_args(&args(&["gc-cache", "--dry-run", "--delete"])).is_err());
+        assert_eq!(
+            parse_args(&args(&["map-ids", "--to", "v2", "--batch", "500"])),
+            Ok(Command::MapIds {
+                scheme: IdScheme::V2,
+                batch_size: 500,
+            })
+        );
+        assert!(parse_args(&args(&["map-ids"])).is_err());
+        assert!(parse_args(&args(&["map-ids", "--to", "v3"])).is_err());
+        assert!(parse_args(&[]).is_err());
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use stream::utils::BlockMetadata;
+
+use crate::{
+    error::IndexingError, models::analytics::AnalyticsModel, storage::StorageBackend, KgData,
+};
+
+/// Adds the block's activity to the daily and weekly rollups of each space.
+pub async fn run<S>(
+    output: &KgData,
+    block_metadata: &BlockMetadata,
+    storage: &Arc<S>,
+) -> Result<(), IndexingError>
+where
+    S: StorageBackend + Send + Sync + 'static,
+{
+    let activity = AnalyticsModel::map_block_activity(output);
+
+    // Edits are counted from the edits recorded in the block
+    if activity.is_empty() && output.edits.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = storage.get_pool().begin().await?;
+    storage
+        .record_activity(&activity, block_metadata, &mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
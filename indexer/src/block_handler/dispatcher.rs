@@ -0,0 +1,199 @@
+use std::future::Future;
+
+use futures::{future::BoxFuture, FutureExt};
+use tokio::task::JoinSet;
+use tracing::error;
+use uuid::Uuid;
+
+/// A task queued for a space, with a label to report it by if it panics.
+struct Task {
+    label: String,
+    future: BoxFuture<'static, ()>,
+}
+
+enum Stage {
+    /// Queues of tasks by space, in the order the spaces were first queued.
+    Parallel(Vec<(Uuid, Vec<Task>)>),
+    Exclusive(Uuid, Task),
+}
+
+/// Runs a block's writes in per-space queues. The tasks of a space run one
+/// at a time, in the order they were queued, while the queues of different
+/// spaces run in parallel.
+///
+/// Exclusive tasks, for writes other spaces' tasks depend on, run on their
+/// own: once every task queued before them is done, and before any task
+/// queued after them starts.
+///
+/// Every task runs in a task of its own, so a task that panics is reported
+/// and the rest of its space's queue still runs.
+#[derive(Default)]
+pub struct SpaceQueues {
+    stages: Vec<Stage>,
+}
+
+impl SpaceQueues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues a task behind the space's earlier tasks.
+    pub fn push<F>(&mut self, space_id: Uuid, label: impl Into<String>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let task = Task {
+            label: label.into(),
+            future: future.boxed(),
+        };
+
+        match self.stages.last_mut() {
+            Some(Stage::Parallel(queues)) => {
+                match queues.iter_mut().find(|(id, _)| *id == space_id) {
+                    Some((_, queue)) => queue.push(task),
+                    None => queues.push((space_id, vec![task])),
+                }
+            }
+            _ => self
+                .stages
+                .push(Stage::Parallel(vec![(space_id, vec![task])])),
+        }
+    }
+
+    /// Queues a task that runs on its own, after every task queued so far.
+    pub fn push_exclusive<F>(&mut self, space_id: Uuid, label: impl Into<String>, future: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        self.stages.push(Stage::Exclusive(
+            space_id,
+            Task {
+                label: label.into(),
+                future: future.boxed(),
+            },
+        ));
+    }
+
+    /// Runs the queued tasks, and returns once they're all done.
+    pub async fn run(self) {
+        for stage in self.stages {
+            match stage {
+                Stage::Parallel(queues) => {
+                    let mut workers = JoinSet::new();
+                    for (space_id, queue) in queues {
+                        workers.spawn(async move {
+                            for task in queue {
+                                run_task(space_id, task).await;
+                            }
+                        });
+                    }
+                    while let Some(result) = workers.join_next().await {
+                        if let Err(error) = result {
+                            error!(error = %error, "Error executing space queue");
+                        }
+                    }
+                }
+                Stage::Exclusive(space_id, task) => run_task(space_id, task).await,
+            }
+        }
+    }
+}
+
+async fn run_task(space_id: Uuid, task: Task) {
+    if let Err(error) = tokio::spawn(task.future).await {
+        error!(
+            error = %error,
+            space_id = %space_id,
+            task = %task.label,
+            "Error executing task for space"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::sync::Barrier;
+
+    use super::*;
+
+    fn recorder() -> (Arc<Mutex<Vec<&'static str>>>, impl Fn(&'static str) + Clone) {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let record = {
+            let log = log.clone();
+            move |entry| log.lock().unwrap().push(entry)
+        };
+        (log, record)
+    }
+
+    #[tokio::test]
+    async fn test_runs_a_space_in_order() {
+        let (log, record) = recorder();
+        let space_id = Uuid::new_v4();
+        let mut queues = SpaceQueues::new();
+
+        for (entry, delay) in [("first", 30), ("second", 0), ("third", 10)] {
+            let record = record.clone();
+            queues.push(space_id, entry, async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                record(entry);
+            });
+        }
+        queues.run().await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["first", "second", "third"]);
+    }
+
+    #[tokio::test]
+    async fn test_runs_spaces_in_parallel() {
+        // Neither space's task finishes unless the other is running too
+        let barrier = Arc::new(Barrier::new(2));
+        let mut queues = SpaceQueues::new();
+        for _ in 0..2 {
+            let barrier = barrier.clone();
+            queues.push(Uuid::new_v4(), "waits", async move {
+                barrier.wait().await;
+            });
+        }
+
+        tokio::time::timeout(Duration::from_secs(5), queues.run())
+            .await
+            .expect("spaces should run in parallel");
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_tasks_run_alone() {
+        let (log, record) = recorder();
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut queues = SpaceQueues::new();
+
+        let slow = record.clone();
+        queues.push(first, "before", async move {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            slow("before");
+        });
+        let exclusive = record.clone();
+        queues.push_exclusive(second, "exclusive", async move { exclusive("exclusive") });
+        queues.push(first, "after", async move { record("after") });
+        queues.run().await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["before", "exclusive", "after"]);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_doesnt_stop_its_queue() {
+        let (log, record) = recorder();
+        let space_id = Uuid::new_v4();
+        let mut queues = SpaceQueues::new();
+
+        queues.push(space_id, "panics", async { panic!("task failed") });
+        queues.push(space_id, "runs", async move { record("runs") });
+        queues.run().await;
+
+        assert_eq!(*log.lock().unwrap(), vec!["runs"]);
+    }
+}
@@ -1,11 +1,16 @@
 use std::sync::Arc;
 
+use indexer_utils::id;
 use stream::utils::BlockMetadata;
 use tracing::{debug, error, instrument, warn, Instrument};
+use uuid::Uuid;
+use wire::hash::edit_hash;
 
+use crate::block_handler::dispatcher::SpaceQueues;
 use crate::cache::properties_cache::ImmutableCache;
-use crate::models::properties::PropertiesModel;
+use crate::models::properties::{PropertiesModel, PropertyItem};
 use crate::models::relations::RelationsModel;
+use crate::models::spaces::SpacesModel;
 use crate::models::{
     entities::EntitiesModel,
     values::{ValueOp, ValuesModel},
@@ -71,12 +76,44 @@ where
     validated
 }
 
+/// Writes the properties created by a block's edits in a transaction of
+/// their own, committed before the edits' transactions begin.
+async fn write_properties<S>(properties: &Vec<PropertyItem>, storage: &Arc<S>)
+where
+    S: StorageBackend + Send + Sync + 'static,
+{
+    if properties.is_empty() {
+        return;
+    }
+
+    let mut tx = match storage.get_pool().begin().await {
+        Ok(transaction) => transaction,
+        Err(error) => {
+            error!(error = %error, "Error starting transaction for properties");
+            return;
+        }
+    };
+
+    if let Err(error) = storage.insert_properties(properties, &mut tx).await {
+        error!(
+            error = %error,
+            property_count = properties.len(),
+            "Error writing properties"
+        );
+    }
+
+    if let Err(error) = tx.commit().await {
+        error!(error = %error, "Error committing transaction for properties");
+    }
+}
+
 #[instrument(skip_all, fields(
     edit_count = output.len(),
     block_number = block_metadata.block_number
 ))]
 pub async fn run<S, C>(
-    output: &Vec<PreprocessedEdit>,
+    output: &[PreprocessedEdit],
+    unowned_properties: &[PropertyItem],
     block_metadata: &BlockMetadata,
     storage: &Arc<S>,
     properties_cache: &Arc<C>,
@@ -88,15 +125,54 @@ where
     // Ensure block context is available to all child operations
     let current_span = tracing::Span::current();
     current_span.record("block_number", block_metadata.block_number);
+
+    // Edits are applied in the order their events were emitted, so a replay
+    // of the block converges to the same state however the edits were
+    // fetched. The sort is stable, so edits with the same index keep their
+    // order.
+    let mut output: Vec<&PreprocessedEdit> = output.iter().collect();
+    output.sort_by_key(|preprocessed_edit| preprocessed_edit.event_index);
+
+    // For now we write properties to an in-memory cache that we reference
+    // when validating values in the edit. There's a weird mismatch between
+    // where properties data lives. We store properties on disk in order
+    // to be able to query properties. We need to do this in "real-time" as
+    // our external API depends on being able to query for properties when
+    // querying for values.
+    //
+    // This does mean we write properties in two places, one for the cache,
+    // and one for the queryable store. Eventually I think we want to move
+    // to in-memory for _all_ data stores with a disk-based commit log, but
+    // for now we'll write properties twice.
+    //
+    // The block's properties are cached and written before any of its edits
+    // run, so values validate the same way whichever space's queue gets to
+    // them first.
+    let properties = PropertiesModel::map_edits_to_properties(
+        output
+            .iter()
+            .filter(|preprocessed_edit| !preprocessed_edit.is_errored)
+            .filter_map(|preprocessed_edit| preprocessed_edit.edit.as_ref()),
+    );
+    properties_cache.insert_many(&properties).await;
+    write_properties(&properties, storage).await;
+
+    // Properties created in other shards' spaces are written by those shards.
+    properties_cache.insert_many(unowned_properties).await;
+
+    let mut queues = SpaceQueues::new();
     for preprocessed_edit in output {
         let storage = storage.clone();
         let block = block_metadata.clone();
 
-        let _block_number = block_metadata.block_number;
-        let _space_id = preprocessed_edit.space_id;
-        let _cid = preprocessed_edit.cid.clone();
-        
-        let handle = tokio::spawn({
+        // Data type migrations change how values validate in every space, so
+        // edits that migrate properties run on their own.
+        let migrates_properties = preprocessed_edit
+            .edit
+            .as_ref()
+            .is_some_and(|edit| !PropertiesModel::map_edit_to_property_updates(edit).is_empty());
+
+        let task = {
             let preprocessed_edit = preprocessed_edit.clone();
             let storage = storage.clone();
             let cache = properties_cache.clone();
@@ -132,39 +208,129 @@ where
                     let edit = preprocessed_edit.edit.unwrap();
                     let space_id = preprocessed_edit.space_id;
 
-                    // We write properties first to update the cache with any properties
-                    // created within the edit. This makes it simpler to do validation
-                    // later in the edit handler as the properties cache will already
-                    // be up-to-date.
-                    let properties = PropertiesModel::map_edit_to_properties(&edit);
-
-                    // For now we write properties to an in-memory cache that we reference
-                    // when validating values in the edit. There's a weird mismatch between
-                    // where properties data lives. We store properties on disk in order
-                    // to be able to query properties. We need to do this in "real-time" as
-                    // our external API depends on being able to query for properties when
-                    // querying for values.
-                    //
-                    // This does mean we write properties in two places, one for the cache,
-                    // and one for the queryable store. Eventually I think we want to move
-                    // to in-memory for _all_ data stores with a disk-based commit log, but
-                    // for now we'll write properties twice.
-                    for property in &properties {
-                        cache.insert(&property.id, property.data_type.clone()).await;
+                    // The same edit content can be published under several CIDs.
+                    // Only the first one applied to a space is indexed.
+                    let content_hash = edit_hash(&edit);
+                    let edit_id = id::transform_id_bytes(edit.id.clone())
+                        .ok()
+                        .map(Uuid::from_bytes);
+                    let record_result = storage
+                        .record_edit(
+                            &space_id,
+                            &content_hash,
+                            &preprocessed_edit.cid,
+                            edit_id,
+                            &block,
+                            &mut tx,
+                        )
+                        .await;
+
+                    match record_result {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            debug!(
+                                content_hash = %content_hash,
+                                "Edit content already applied to space, skipping indexing"
+                            );
+                            return;
+                        }
+                        // The failed statement aborts the transaction, so the
+                        // edit can't be written and is rolled back.
+                        Err(error) => {
+                            error!(
+                                error = %error,
+                                content_hash = %content_hash,
+                                "Error recording edit, skipping indexing"
+                            );
+                            return;
+                        }
                     }
 
-                    if let Err(error) = storage.insert_properties(&properties, &mut tx).await {
-                        tracing::error!("Error writing properties: {}", error);
+                    // Data type changes are applied before values are validated, so
+                    // the rest of the edit can already use the new data type.
+                    for property in PropertiesModel::map_edit_to_property_updates(&edit) {
+                        match storage
+                            .migrate_property(&property, &space_id, &block, &mut tx)
+                            .await
+                        {
+                            Ok(Some(migration)) => match &migration.rejection {
+                                None => cache.migrate(&property.id, property.data_type).await,
+                                Some(rejection) => warn!(
+                                    property_id = %property.id,
+                                    from_type = %migration.from_type,
+                                    to_type = %migration.to_type,
+                                    reason = %rejection,
+                                    "Rejected property data type migration"
+                                ),
+                            },
+                            Ok(None) => {}
+                            Err(error) => error!(
+                                error = %error,
+                                property_id = %property.id,
+                                "Error migrating property"
+                            ),
+                        }
                     }
 
                     let edit = edit.clone();
                     let block = block.clone();
                     let storage = storage.clone();
 
+                    // Deleted entities are cleared before the rest of the edit is
+                    // written, so ops after a delete in the same edit still apply.
+                    // Ops before it are dropped by the models.
+                    let deleted_entities = EntitiesModel::map_edit_to_deleted_entities(&edit);
+
+                    if let Err(error) = storage
+                        .delete_entities(&deleted_entities, &space_id, &mut tx)
+                        .await
+                    {
+                        error!(
+                            error = %error,
+                            entity_count = deleted_entities.len(),
+                            "Error deleting entities"
+                        );
+                    }
+
                     let entities = EntitiesModel::map_edit_to_entities(&edit, &block);
 
+                    // Edited entities are read before the edit's relations are
+                    // written, while its updated and deleted relations exist.
+                    let edited_entities = EntitiesModel::map_edit_to_edited_entities(&edit);
+
+                    // Every entity row the edit writes is locked up front, so
+                    // edits of other spaces sharing them wait instead of
+                    // deadlocking.
+                    let entity_ids: Vec<Uuid> = entities.iter().map(|entity| entity.id).collect();
+
+                    if let Err(error) = storage
+                        .lock_entities(&entity_ids, &edited_entities, &space_id, &mut tx)
+                        .await
+                    {
+                        error!(
+                            error = %error,
+                            entity_count = entity_ids.len(),
+                            "Error locking entities"
+                        );
+                    }
+
                     if let Err(error) = storage.insert_entities(&entities, &mut tx).await {
-                        tracing::error!("Error writing entities: {}", error);
+                        error!(
+                            error = %error,
+                            entity_count = entities.len(),
+                            "Error writing entities"
+                        );
+                    }
+
+                    if let Err(error) = storage
+                        .record_edited_entities(&edited_entities, &space_id, &block, &mut tx)
+                        .await
+                    {
+                        error!(
+                            error = %error,
+                            entity_count = edited_entities.entity_ids.len(),
+                            "Error recording edited entities"
+                        );
                     }
 
                     let (created_values, deleted_values) =
@@ -179,7 +345,11 @@ where
                         .await;
 
                     if let Err(error) = write_values_result {
-                        tracing::error!("Error writing set values: {}", error);
+                        error!(
+                            error = %error,
+                            value_count = validated_created_values.len(),
+                            "Error writing set values"
+                        );
                     }
 
                     let write_values_result = storage
@@ -187,7 +357,11 @@ where
                         .await;
 
                     if let Err(error) = write_values_result {
-                        tracing::error!("Error writing delete values: {}", error);
+                        error!(
+                            error = %error,
+                            value_count = deleted_values.len(),
+                            "Error writing delete values"
+                        );
                     }
 
                     let (
@@ -197,18 +371,53 @@ where
                         deleted_relation_ids,
                     ) = RelationsModel::map_edit_to_relations(&edit, &space_id);
 
+                    // Relations may point into spaces that this instance hasn't
+                    // indexed yet, e.g. spaces created in the same block or owned
+                    // by another shard, so unknown spaces are reported but the
+                    // relations are still written.
+                    let referenced_spaces =
+                        RelationsModel::referenced_spaces(&created_relations, &updated_relations);
+
+                    match storage
+                        .find_unknown_spaces(&referenced_spaces, &mut tx)
+                        .await
+                    {
+                        Ok(unknown_spaces) if !unknown_spaces.is_empty() => {
+                            warn!(
+                                unknown_spaces = ?unknown_spaces,
+                                "Relations reference spaces that aren't indexed"
+                            );
+                        }
+                        Ok(_) => {}
+                        Err(error) => {
+                            error!(
+                                error = %error,
+                                space_count = referenced_spaces.len(),
+                                "Error verifying referenced spaces"
+                            );
+                        }
+                    }
+
                     let write_relations_result =
                         storage.insert_relations(&created_relations, &mut tx).await;
 
                     if let Err(write_error) = write_relations_result {
-                        tracing::error!("Error writing relations: {}", write_error);
+                        error!(
+                            error = %write_error,
+                            relation_count = created_relations.len(),
+                            "Error writing relations"
+                        );
                     }
 
                     let update_relations_result =
                         storage.update_relations(&updated_relations, &mut tx).await;
 
                     if let Err(write_error) = update_relations_result {
-                        tracing::error!("Error updating relations: {}", write_error);
+                        error!(
+                            error = %write_error,
+                            relation_count = updated_relations.len(),
+                            "Error updating relations"
+                        );
                     }
 
                     let unset_relations_result = storage
@@ -216,7 +425,11 @@ where
                         .await;
 
                     if let Err(write_error) = unset_relations_result {
-                        tracing::error!("Error unsetting relation fields: {}", write_error);
+                        error!(
+                            error = %write_error,
+                            relation_count = unset_relations.len(),
+                            "Error unsetting relation fields"
+                        );
                     }
 
                     let delete_relations_result = storage
@@ -224,7 +437,37 @@ where
                         .await;
 
                     if let Err(write_error) = delete_relations_result {
-                        tracing::error!("Error deleting relations: {}", write_error);
+                        error!(
+                            error = %write_error,
+                            relation_count = deleted_relation_ids.len(),
+                            "Error deleting relations"
+                        );
+                    }
+
+                    // Property names, descriptions and renderable types arrive as
+                    // regular values and relations on the property entity. They're
+                    // copied onto the properties once the edit's values and
+                    // relations are written.
+                    let property_ids = PropertiesModel::map_edit_to_property_metadata(&edit);
+
+                    if let Err(error) = storage
+                        .update_property_metadata(&property_ids, &space_id, &mut tx)
+                        .await
+                    {
+                        error!(
+                            error = %error,
+                            property_count = property_ids.len(),
+                            "Error writing property metadata"
+                        );
+                    }
+
+                    // The space's name, description and cover are read from the
+                    // space entity once the edit is written.
+                    if SpacesModel::edit_changes_metadata(&edit) {
+                        if let Err(error) = storage.update_space_metadata(&space_id, &mut tx).await
+                        {
+                            error!(error = %error, "Error writing space metadata");
+                        }
                     }
                 } else {
                     warn!(
@@ -242,22 +485,21 @@ where
                         "Error committing transaction for edit"
                     );
                 }
-            }.instrument(edit_span)
-        })
-        .await;
-
-        match handle {
-            Ok(_) => {
-                //
             }
-            Err(error) => error!(
-                error = %error,
-                cid = %preprocessed_edit.cid,
-                space_id = %preprocessed_edit.space_id,
-                "[Root handler] Error executing task for edit"
-            ),
+            .instrument(edit_span)
+        };
+
+        if migrates_properties {
+            queues.push_exclusive(
+                preprocessed_edit.space_id,
+                preprocessed_edit.cid.clone(),
+                task,
+            );
+        } else {
+            queues.push(preprocessed_edit.space_id, preprocessed_edit.cid.clone(), task);
         }
     }
+    queues.run().await;
 
     Ok(())
 }
@@ -290,6 +532,8 @@ mod tests {
 
             language: None,
             unit: None,
+            options: None,
+            edit_id: None,
             string: None,
             number: Some(123.45),
             boolean: None,
@@ -322,6 +566,8 @@ mod tests {
 
             language: None,
             unit: None,
+            options: None,
+            edit_id: None,
             string: None,
             number: Some(123.45), // Properly populated number field
             boolean: None,
@@ -354,6 +600,8 @@ mod tests {
 
             language: None,
             unit: None,
+            options: None,
+            edit_id: None,
             string: None,
             number: None,
             boolean: None,
@@ -382,6 +630,8 @@ mod tests {
 
             language: None,
             unit: None,
+            options: None,
+            edit_id: None,
             string: Some("some text".to_string()),
             number: None,
             boolean: None,
@@ -420,6 +670,8 @@ mod tests {
 
                 language: None,
                 unit: None,
+                options: None,
+                edit_id: None,
                 string: Some("Hello World".to_string()), // String field populated
                 number: None,
                 boolean: None,
@@ -435,6 +687,8 @@ mod tests {
 
                 language: None,
                 unit: None,
+                options: None,
+                edit_id: None,
                 string: None,
                 number: None,
                 boolean: Some(true), // Boolean field populated
@@ -450,6 +704,8 @@ mod tests {
 
                 language: None,
                 unit: None,
+                options: None,
+                edit_id: None,
                 string: None,
                 number: None,
                 boolean: None, // Not populated due to validation failure
@@ -465,6 +721,8 @@ mod tests {
 
                 language: None,
                 unit: None,
+                options: None,
+                edit_id: None,
                 string: None,
                 number: None,
                 boolean: None,
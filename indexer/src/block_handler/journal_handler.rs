@@ -0,0 +1,32 @@
+use std::sync::Arc;
+
+use stream::utils::BlockMetadata;
+
+use crate::{
+    error::IndexingError, models::journal::JournalModel, storage::StorageBackend, KgData,
+};
+
+/// Appends the ops of the block's applied edits to the ops journal. The
+/// journal is written in one transaction once every edit of the block is
+/// committed, so its sequence follows the order blocks are indexed in.
+pub async fn run<S>(
+    output: &KgData,
+    block_metadata: &BlockMetadata,
+    storage: &Arc<S>,
+) -> Result<(), IndexingError>
+where
+    S: StorageBackend + Send + Sync + 'static,
+{
+    let entries = JournalModel::map_edits_to_journal(&output.edits);
+    if entries.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = storage.get_pool().begin().await?;
+    storage
+        .append_ops_journal(&entries, block_metadata, &mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use indexer_utils::network_ids::Network;
 use stream::utils::BlockMetadata;
 
 use crate::block_handler::utils::handle_task_result;
@@ -13,7 +14,8 @@ pub async fn run<S>(
     removed_members: &Vec<RemovedMember>,
     added_editors: &Vec<AddedMember>,
     removed_editors: &Vec<RemovedMember>,
-    _block_metadata: &BlockMetadata,
+    block_metadata: &BlockMetadata,
+    network: &Network,
     storage: &Arc<S>,
 ) -> Result<(), IndexingError>
 where
@@ -23,18 +25,20 @@ where
         let storage = Arc::clone(storage);
         let added_members = added_members.clone();
         let removed_members = removed_members.clone();
+        let network = network.clone();
         tokio::spawn(async move {
             let mut tx = storage.get_pool().begin().await?;
 
             // Process added members
             if !added_members.is_empty() {
-                let members_to_add = MembershipModel::map_added_members(&added_members);
+                let members_to_add = MembershipModel::map_added_members(&added_members, &network);
                 storage.insert_members(&members_to_add, &mut tx).await?;
             }
 
             // Process removed members
             if !removed_members.is_empty() {
-                let members_to_remove = MembershipModel::map_removed_members(&removed_members);
+                let members_to_remove =
+                    MembershipModel::map_removed_members(&removed_members, &network);
                 storage.remove_members(&members_to_remove, &mut tx).await?;
             }
 
@@ -47,19 +51,26 @@ where
         let storage = Arc::clone(storage);
         let added_editors = added_editors.clone();
         let removed_editors = removed_editors.clone();
+        let network = network.clone();
+        let block_metadata = block_metadata.clone();
         tokio::spawn(async move {
             let mut tx = storage.get_pool().begin().await?;
 
             // Process added editors
             if !added_editors.is_empty() {
-                let editors_to_add = MembershipModel::map_added_editors(&added_editors);
-                storage.insert_editors(&editors_to_add, &mut tx).await?;
+                let editors_to_add = MembershipModel::map_added_editors(&added_editors, &network);
+                storage
+                    .insert_editors(&editors_to_add, &block_metadata, &mut tx)
+                    .await?;
             }
 
             // Process removed editors
             if !removed_editors.is_empty() {
-                let editors_to_remove = MembershipModel::map_removed_editors(&removed_editors);
-                storage.remove_editors(&editors_to_remove, &mut tx).await?;
+                let editors_to_remove =
+                    MembershipModel::map_removed_editors(&removed_editors, &network);
+                storage
+                    .remove_editors(&editors_to_remove, &block_metadata, &mut tx)
+                    .await?;
             }
 
             tx.commit().await?;
@@ -1,5 +1,10 @@
+pub mod analytics_handler;
+pub mod dispatcher;
 pub mod edit_handler;
+pub mod journal_handler;
 pub mod membership_handler;
+pub mod plugin_handler;
+pub mod proposal_handler;
 pub mod root_handler;
 pub mod space_handler;
 pub mod subspace_handler;
@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use stream::utils::BlockMetadata;
+
+use crate::{error::IndexingError, models::spaces::SpacesModel, storage::StorageBackend, KgData};
+
+/// Writes the plugins installed in spaces in the block: the admin plugins of
+/// personal spaces and the voting and membership plugins of public spaces.
+/// Runs once the block's spaces are written, as a space's first plugins are
+/// installed in the block the space is created in.
+pub async fn run<S>(
+    output: &KgData,
+    block_metadata: &BlockMetadata,
+    storage: &Arc<S>,
+) -> Result<(), IndexingError>
+where
+    S: StorageBackend + Send + Sync + 'static,
+{
+    let admins =
+        SpacesModel::map_personal_admin_changes(&output.personal_admin_changes, &output.network);
+    let governance = SpacesModel::map_governance_plugin_changes(
+        &output.governance_plugin_changes,
+        &output.network,
+    );
+    if admins.is_empty() && governance.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = storage.get_pool().begin().await?;
+    storage
+        .change_personal_admins(&admins, block_metadata, &mut tx)
+        .await?;
+    storage
+        .change_governance_plugins(&governance, block_metadata, &mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use stream::utils::BlockMetadata;
+
+use crate::{
+    error::IndexingError, models::proposals::ProposalsModel, storage::StorageBackend, KgData,
+};
+
+/// Writes the proposals created in the block, with the previews of those to
+/// publish an edit, and snapshots the electorate of every one of them. Runs
+/// once the block's membership changes are written, so the snapshot is the
+/// electorate as of the end of the block.
+///
+/// Then moves the proposals executed or canceled in the block out of the
+/// `created` status. A proposal created and settled in the same block is
+/// written first, so it's settled too.
+pub async fn run<S>(
+    output: &KgData,
+    block_metadata: &BlockMetadata,
+    storage: &Arc<S>,
+) -> Result<(), IndexingError>
+where
+    S: StorageBackend + Send + Sync + 'static,
+{
+    let previews = ProposalsModel::map_proposal_previews(output);
+    let snapshots = ProposalsModel::map_proposal_snapshots(output);
+    let proposals = ProposalsModel::map_proposals(output);
+    let executions = ProposalsModel::map_proposal_executions(output);
+    let cancellations = ProposalsModel::map_proposal_cancellations(output);
+    if proposals.is_empty() && executions.is_empty() && cancellations.is_empty() {
+        return Ok(());
+    }
+
+    let mut tx = storage.get_pool().begin().await?;
+    storage
+        .insert_proposal_previews(&previews, block_metadata, &mut tx)
+        .await?;
+    storage
+        .snapshot_proposal_electorates(&snapshots, block_metadata, &mut tx)
+        .await?;
+    storage
+        .insert_proposals(&proposals, block_metadata, &mut tx)
+        .await?;
+    storage
+        .execute_proposals(&executions, block_metadata, &mut tx)
+        .await?;
+    storage
+        .cancel_proposals(&cancellations, block_metadata, &mut tx)
+        .await?;
+    tx.commit().await?;
+
+    Ok(())
+}
@@ -2,10 +2,10 @@ use std::sync::Arc;
 
 use chrono::{DateTime, Local, Utc};
 use stream::utils::{self, BlockMetadata};
-use tracing::{info, instrument, Instrument};
+use tracing::{info, instrument, warn, Instrument};
 
 use crate::block_handler::{
-    edit_handler, membership_handler, space_handler, subspace_handler, utils::handle_task_result,
+    analytics_handler, edit_handler, journal_handler, membership_handler, plugin_handler, proposal_handler, space_handler, subspace_handler, utils::handle_task_result,
 };
 use crate::cache::properties_cache::ImmutableCache;
 
@@ -46,6 +46,7 @@ where
         block_number = block_metadata.block_number,
         block_time = %block_datetime_local.format("%Y-%m-%d %H:%M:%S"),
         drift = %drift_str,
+        drift_secs = utils::drift(block_metadata).num_seconds(),
         edit_count = output.edits.len(),
         space_count = output.spaces.len(),
         "Processing block"
@@ -55,11 +56,14 @@ where
         let storage = Arc::clone(storage);
         let block_metadata = block_metadata.clone();
         let spaces = output.spaces.clone();
+        let network = output.network.clone();
         let block_number = block_metadata.block_number;
 
         tokio::spawn(
-            async move { space_handler::run(&spaces, &block_metadata, &storage).await }
-                .instrument(tracing::info_span!("space_task", block_number = block_number))
+            async move {
+                space_handler::run(&spaces, &block_metadata, &network, &storage).await
+            }
+            .instrument(tracing::info_span!("space_task", block_number = block_number))
         )
     };
 
@@ -68,17 +72,29 @@ where
         let properties_cache = Arc::clone(properties_cache);
         let block_metadata = block_metadata.clone();
         let edits = output.edits.clone();
+        let unowned_properties = output.unowned_properties.clone();
         let block_number = block_metadata.block_number;
         let edit_count = edits.len();
         
         tokio::spawn(
             async move {
-                edit_handler::run(&edits, &block_metadata, &storage, &properties_cache).await
+                edit_handler::run(
+                    &edits,
+                    &unowned_properties,
+                    &block_metadata,
+                    &storage,
+                    &properties_cache,
+                )
+                .await
             }
             .instrument(tracing::info_span!("edit_task", block_number = block_number, edit_count = edit_count))
         )
     };
 
+    // Members and editors are counted on their space's row, so the block's
+    // spaces are written before the members and editors they're created with
+    let space_result = space_task.await;
+
     let membership_task = {
         let storage = Arc::clone(storage);
         let block_metadata = block_metadata.clone();
@@ -86,6 +102,7 @@ where
         let removed_members = output.removed_members.clone();
         let added_editors = output.added_editors.clone();
         let removed_editors = output.removed_editors.clone();
+        let network = output.network.clone();
         let block_number = block_metadata.block_number;
         let member_count = added_members.len() + removed_members.len();
         let editor_count = added_editors.len() + removed_editors.len();
@@ -98,6 +115,7 @@ where
                     &added_editors,
                     &removed_editors,
                     &block_metadata,
+                    &network,
                     &storage,
                 )
                 .await
@@ -115,6 +133,7 @@ where
         let block_metadata = block_metadata.clone();
         let added_subspaces = output.added_subspaces.clone();
         let removed_subspaces = output.removed_subspaces.clone();
+        let network = output.network.clone();
         let block_number = block_metadata.block_number;
         let subspace_count = added_subspaces.len() + removed_subspaces.len();
         
@@ -124,6 +143,7 @@ where
                     &added_subspaces,
                     &removed_subspaces,
                     &block_metadata,
+                    &network,
                     &storage,
                 )
                 .await
@@ -135,14 +155,21 @@ where
         )
     };
 
-    let (space_result, edit_result, membership_result, subspace_result) =
-        tokio::join!(space_task, edit_task, membership_task, subspace_task);
+    let (edit_result, membership_result, subspace_result) =
+        tokio::join!(edit_task, membership_task, subspace_task);
 
     handle_task_result(space_result)?;
     handle_task_result(edit_result)?;
     handle_task_result(membership_result)?;
     handle_task_result(subspace_result)?;
 
+    plugin_handler::run(output, block_metadata, storage).await?;
+    proposal_handler::run(output, block_metadata, storage).await?;
+    journal_handler::run(output, block_metadata, storage).await?;
+
+    // Counts the edits and entities written above, so it runs last
+    analytics_handler::run(output, block_metadata, storage).await?;
+
     info!(
         block_number = block_metadata.block_number,
         "Successfully processed block"
@@ -150,3 +177,37 @@ where
     
     Ok(())
 }
+
+/// Runs the block unless the indexer already processed it, and adds it to
+/// the indexer's ledger of processed blocks once it's written. Returns false
+/// if the block was skipped.
+///
+/// Every write of [`run`] can be repeated, so a block that fails partway is
+/// only added to the ledger once it's processed again in full.
+pub async fn run_once<S, C>(
+    output: &KgData,
+    block_metadata: &BlockMetadata,
+    storage: &Arc<S>,
+    properties_cache: &Arc<C>,
+    indexer_id: &str,
+) -> Result<bool, IndexingError>
+where
+    S: StorageBackend + Send + Sync + 'static,
+    C: ImmutableCache + Send + Sync + 'static,
+{
+    if storage.is_block_processed(indexer_id, block_metadata).await? {
+        warn!(
+            block_number = block_metadata.block_number,
+            indexer_id = indexer_id,
+            "Block was already processed, skipping"
+        );
+        return Ok(false);
+    }
+
+    run(output, block_metadata, storage, properties_cache).await?;
+    storage
+        .record_processed_block(indexer_id, block_metadata)
+        .await?;
+
+    Ok(true)
+}
@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use indexer_utils::network_ids::Network;
 use stream::utils::BlockMetadata;
 
 use crate::{
@@ -8,17 +9,18 @@ use crate::{
 
 pub async fn run<S>(
     output: &Vec<CreatedSpace>,
-    _block_metadata: &BlockMetadata,
+    block_metadata: &BlockMetadata,
+    network: &Network,
     storage: &Arc<S>,
 ) -> Result<(), IndexingError>
 where
     S: StorageBackend + Send + Sync + 'static,
 {
-    let created_spaces = SpacesModel::map_created_spaces(output);
+    let created_spaces = SpacesModel::map_created_spaces(output, network);
     let mut tx = storage.get_pool().begin().await?;
     storage
         .clone()
-        .insert_spaces(&created_spaces, &mut tx)
+        .insert_spaces(&created_spaces, block_metadata, &mut tx)
         .await?;
     tx.commit().await?;
 
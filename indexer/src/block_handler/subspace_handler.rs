@@ -1,5 +1,6 @@
 use std::sync::Arc;
 
+use indexer_utils::network_ids::Network;
 use stream::utils::BlockMetadata;
 
 use crate::block_handler::utils::handle_task_result;
@@ -12,6 +13,7 @@ pub async fn run<S>(
     added_subspaces: &Vec<AddedSubspace>,
     removed_subspaces: &Vec<RemovedSubspace>,
     _block_metadata: &BlockMetadata,
+    network: &Network,
     storage: &Arc<S>,
 ) -> Result<(), IndexingError>
 where
@@ -21,18 +23,21 @@ where
         let storage = Arc::clone(storage);
         let added_subspaces = added_subspaces.clone();
         let removed_subspaces = removed_subspaces.clone();
+        let network = network.clone();
         tokio::spawn(async move {
             let mut tx = storage.get_pool().begin().await?;
 
             // Process added subspaces
             if !added_subspaces.is_empty() {
-                let subspaces_to_add = SubspaceModel::map_added_subspaces(&added_subspaces);
+                let subspaces_to_add =
+                    SubspaceModel::map_added_subspaces(&added_subspaces, &network);
                 storage.insert_subspaces(&subspaces_to_add, &mut tx).await?;
             }
 
             // Process removed subspaces
             if !removed_subspaces.is_empty() {
-                let subspaces_to_remove = SubspaceModel::map_removed_subspaces(&removed_subspaces);
+                let subspaces_to_remove =
+                    SubspaceModel::map_removed_subspaces(&removed_subspaces, &network);
                 storage.remove_subspaces(&subspaces_to_remove, &mut tx).await?;
             }
 
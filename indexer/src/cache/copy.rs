@@ -0,0 +1,192 @@
+//! Copies IPFS cache entries between two cache databases, such as from
+//! production into a local database, so a new environment can index edits
+//! without fetching their content from IPFS again.
+//!
+//! Entries are read in batches in the order they were cached and inserted
+//! into the target, where entries already cached under the same URI are
+//! kept as they are.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::error::IndexingError;
+
+pub const DEFAULT_BATCH_SIZE: i64 = 1000;
+
+/// The entries that are copied. Every filter that's set must match.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CacheSelection {
+    /// The cache records the timestamp of the block an edit was published
+    /// in, in seconds, so ranges of blocks are selected by timestamp.
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    /// Content URIs, with the `ipfs://` prefix.
+    pub uris: Vec<String>,
+    pub space_ids: Vec<Uuid>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CacheCopyReport {
+    /// The entries that matched the selection in the source.
+    pub selected: u64,
+    pub copied: u64,
+    /// The entries that were already cached in the target.
+    pub skipped: u64,
+}
+
+#[derive(sqlx::FromRow)]
+struct CacheRow {
+    id: i32,
+    uri: String,
+    json: Option<serde_json::Value>,
+    is_errored: bool,
+    block: String,
+    space: Uuid,
+    content_hash: Option<String>,
+    content_length: Option<i32>,
+    compression: Option<String>,
+    encoding: Option<String>,
+    fetch_duration_ms: Option<i32>,
+    gateway: Option<String>,
+    raw_bytes: Option<Vec<u8>>,
+}
+
+pub struct CacheCopy {
+    source: PgPool,
+    target: PgPool,
+    batch_size: i64,
+}
+
+impl CacheCopy {
+    pub fn new(source: PgPool, target: PgPool, batch_size: i64) -> Self {
+        CacheCopy {
+            source,
+            target,
+            batch_size,
+        }
+    }
+
+    pub async fn run(&self, selection: &CacheSelection) -> Result<CacheCopyReport, IndexingError> {
+        let mut report = CacheCopyReport::default();
+        let mut after = 0;
+
+        loop {
+            let rows = sqlx::query_as::<_, CacheRow>(
+                r#"
+                    SELECT id, uri, json, is_errored, block, space, content_hash,
+                        content_length, compression, encoding, fetch_duration_ms, gateway, raw_bytes
+                    FROM ipfs_cache
+                    WHERE id > $1
+                        AND ($2::bigint IS NULL OR block::bigint >= $2)
+                        AND ($3::bigint IS NULL OR block::bigint <= $3)
+                        AND (cardinality($4::text[]) = 0 OR uri = ANY($4))
+                        AND (cardinality($5::uuid[]) = 0 OR space = ANY($5))
+                    ORDER BY id
+                    LIMIT $6
+                "#,
+            )
+            .bind(after)
+            .bind(selection.since)
+            .bind(selection.until)
+            .bind(&selection.uris)
+            .bind(&selection.space_ids)
+            .bind(self.batch_size)
+            .fetch_all(&self.source)
+            .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            after = last.id;
+
+            let copied = self.insert(&rows).await?;
+            report.selected += rows.len() as u64;
+            report.copied += copied;
+            report.skipped += rows.len() as u64 - copied;
+
+            info!(
+                copied = report.copied,
+                skipped = report.skipped,
+                "Copied cache entries"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Inserts the entries the target doesn't have and returns how many
+    /// were inserted.
+    async fn insert(&self, rows: &[CacheRow]) -> Result<u64, IndexingError> {
+        let uris: Vec<&str> = rows.iter().map(|row| row.uri.as_str()).collect();
+        let json: Vec<Option<serde_json::Value>> =
+            rows.iter().map(|row| row.json.clone()).collect();
+        let is_errored: Vec<bool> = rows.iter().map(|row| row.is_errored).collect();
+        let blocks: Vec<&str> = rows.iter().map(|row| row.block.as_str()).collect();
+        let spaces: Vec<Uuid> = rows.iter().map(|row| row.space).collect();
+        let content_hashes: Vec<Option<&str>> =
+            rows.iter().map(|row| row.content_hash.as_deref()).collect();
+        let content_lengths: Vec<Option<i32>> = rows.iter().map(|row| row.content_length).collect();
+        let compressions: Vec<Option<&str>> =
+            rows.iter().map(|row| row.compression.as_deref()).collect();
+        let encodings: Vec<Option<&str>> = rows.iter().map(|row| row.encoding.as_deref()).collect();
+        let fetch_durations: Vec<Option<i32>> =
+            rows.iter().map(|row| row.fetch_duration_ms).collect();
+        let gateways: Vec<Option<&str>> = rows.iter().map(|row| row.gateway.as_deref()).collect();
+        let raw_bytes: Vec<Option<&[u8]>> =
+            rows.iter().map(|row| row.raw_bytes.as_deref()).collect();
+
+        let result = sqlx::query(
+            r#"
+                INSERT INTO ipfs_cache (
+                    uri, json, is_errored, block, space, content_hash,
+                    content_length, compression, encoding, fetch_duration_ms, gateway, raw_bytes
+                )
+                SELECT * FROM UNNEST(
+                    $1::text[], $2::jsonb[], $3::boolean[], $4::text[], $5::uuid[], $6::text[],
+                    $7::integer[], $8::text[], $9::text[], $10::integer[], $11::text[],
+                    $12::bytea[]
+                )
+                ON CONFLICT (uri) DO NOTHING
+            "#,
+        )
+        .bind(uris)
+        .bind(json)
+        .bind(is_errored)
+        .bind(blocks)
+        .bind(spaces)
+        .bind(content_hashes)
+        .bind(content_lengths)
+        .bind(compressions)
+        .bind(encodings)
+        .bind(fetch_durations)
+        .bind(gateways)
+        .bind(raw_bytes)
+        .execute(&self.target)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+/// Returns the URI a CID is cached under, which is the CID with the
+/// `ipfs://` prefix.
+pub fn cache_uri(cid: &str) -> String {
+    let cid = cid.trim();
+    if cid.starts_with("ipfs://") {
+        cid.to_string()
+    } else {
+        format!("ipfs://{}", cid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_uri() {
+        assert_eq!(cache_uri("bafkreib"), "ipfs://bafkreib");
+        assert_eq!(cache_uri(" ipfs://bafkreib"), "ipfs://bafkreib");
+    }
+}
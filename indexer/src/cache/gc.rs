@@ -0,0 +1,165 @@
+//! Collects IPFS cache entries that no edit or proposal refers to anymore,
+//! such as the content of blocklisted DAOs' edits, so the cache database
+//! doesn't keep growing with content the indexer will never read.
+//!
+//! An entry is collected if it was cached for a space of a blocklisted DAO,
+//! or if its block was indexed and neither an edit, whether applied,
+//! unverified or unauthorized, nor a proposal preview refers to its URI or
+//! content. Errored entries are kept so `redecode-cache` can still fix
+//! them. Collected entries are moved to `ipfs_cache_archive` unless they're
+//! deleted, since a replay from before their block reads them again.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::error::IndexingError;
+
+/// What happens to the entries that are collected.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CollectMode {
+    /// The entries are only counted.
+    DryRun,
+    #[default]
+    Archive,
+    Delete,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct CacheGcReport {
+    /// The entries no edit or proposal refers to.
+    pub orphaned: u64,
+    pub archived: u64,
+    pub deleted: u64,
+}
+
+pub struct CacheCollector {
+    pool: PgPool,
+    batch_size: i64,
+}
+
+impl CacheCollector {
+    pub fn new(pool: PgPool, batch_size: i64) -> Self {
+        CacheCollector { pool, batch_size }
+    }
+
+    /// Collects the orphaned entries, counting every entry of the given
+    /// spaces as orphaned.
+    pub async fn run(
+        &self,
+        mode: CollectMode,
+        blocked_space_ids: &[Uuid],
+    ) -> Result<CacheGcReport, IndexingError> {
+        let mut report = CacheGcReport::default();
+        let mut after = 0;
+
+        loop {
+            // Entries of blocks the indexer hasn't reached yet aren't
+            // referenced until it does, so only entries up to the latest
+            // block an edit was applied in are considered.
+            let ids: Vec<i32> = sqlx::query_scalar(
+                r#"
+                    SELECT c.id
+                    FROM ipfs_cache c
+                    WHERE c.id > $1
+                        AND (
+                            c.space = ANY($2)
+                            OR (
+                                NOT c.is_errored
+                                AND c.block::bigint <= (
+                                    SELECT max(created_at::bigint) FROM edits
+                                )
+                                AND NOT EXISTS (SELECT 1 FROM edits WHERE cid = c.uri)
+                                AND NOT EXISTS (
+                                    SELECT 1 FROM edits WHERE content_hash = c.content_hash
+                                )
+                                AND NOT EXISTS (
+                                    SELECT 1 FROM unverified_edits WHERE cid = c.uri
+                                )
+                                AND NOT EXISTS (
+                                    SELECT 1 FROM unauthorized_edits WHERE cid = c.uri
+                                )
+                                AND NOT EXISTS (
+                                    SELECT 1 FROM proposal_previews WHERE content_uri = c.uri
+                                )
+                            )
+                        )
+                    ORDER BY c.id
+                    LIMIT $3
+                "#,
+            )
+            .bind(after)
+            .bind(blocked_space_ids)
+            .bind(self.batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+            let Some(last) = ids.last() else {
+                break;
+            };
+            after = *last;
+            report.orphaned += ids.len() as u64;
+
+            match mode {
+                CollectMode::DryRun => {}
+                CollectMode::Archive => report.archived += self.archive(&ids).await?,
+                CollectMode::Delete => report.deleted += self.delete(&ids).await?,
+            }
+
+            info!(
+                orphaned = report.orphaned,
+                archived = report.archived,
+                deleted = report.deleted,
+                "Collected cache entries"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Moves the entries to the archive and returns how many were moved. An
+    /// entry that was archived before is replaced.
+    async fn archive(&self, ids: &[i32]) -> Result<u64, IndexingError> {
+        let result = sqlx::query(
+            r#"
+                WITH moved AS (
+                    DELETE FROM ipfs_cache WHERE id = ANY($1)
+                    RETURNING uri, json, is_errored, block, space, content_hash, content_length,
+                        compression, encoding, fetch_duration_ms, gateway, raw_bytes
+                )
+                INSERT INTO ipfs_cache_archive (
+                    uri, json, is_errored, block, space, content_hash, content_length,
+                    compression, encoding, fetch_duration_ms, gateway, raw_bytes
+                )
+                SELECT * FROM moved
+                ON CONFLICT (uri) DO UPDATE SET
+                    json = EXCLUDED.json,
+                    is_errored = EXCLUDED.is_errored,
+                    block = EXCLUDED.block,
+                    space = EXCLUDED.space,
+                    content_hash = EXCLUDED.content_hash,
+                    content_length = EXCLUDED.content_length,
+                    compression = EXCLUDED.compression,
+                    encoding = EXCLUDED.encoding,
+                    fetch_duration_ms = EXCLUDED.fetch_duration_ms,
+                    gateway = EXCLUDED.gateway,
+                    raw_bytes = EXCLUDED.raw_bytes,
+                    archived_at = now()
+            "#,
+        )
+        .bind(ids)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    async fn delete(&self, ids: &[i32]) -> Result<u64, IndexingError> {
+        let result = sqlx::query("DELETE FROM ipfs_cache WHERE id = ANY($1)")
+            .bind(ids)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
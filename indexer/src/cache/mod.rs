@@ -1,6 +1,10 @@
 use async_trait::async_trait;
+pub mod copy;
+pub mod gc;
 pub mod postgres;
 pub mod properties_cache;
+pub mod redecode;
+pub mod shared_properties_cache;
 
 use thiserror::Error;
 use uuid::Uuid;
@@ -24,6 +28,14 @@ pub struct PreprocessedEdit {
     pub edit: Option<Edit>,
     pub is_errored: bool,
     pub space_id: Uuid,
+    /// The checksummed sender of the transaction that published the edit.
+    /// It isn't cached, so it's only set once the edit is matched to its
+    /// chain event.
+    pub sender: Option<String>,
+    /// The position of the edit's event among the edits published in its
+    /// block, which is the order the block's edits are applied in. Like the
+    /// sender, it's only set once the edit is matched to its chain event.
+    pub event_index: usize,
 }
 
 #[async_trait]
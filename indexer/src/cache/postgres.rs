@@ -1,6 +1,5 @@
-use std::env;
-
-use sqlx::{postgres::PgPoolOptions, Postgres, Row};
+use indexer_utils::config::DatabaseConfig;
+use sqlx::{Postgres, Row};
 use uuid::Uuid;
 use wire::pb::grc20::Edit;
 
@@ -11,15 +10,8 @@ pub struct PostgresCache {
 }
 
 impl PostgresCache {
-    pub async fn new() -> Result<Self, CacheError> {
-        let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-
-        let database_url_static = database_url.as_str();
-
-        let pool = PgPoolOptions::new()
-            .max_connections(20)
-            .connect(database_url_static)
-            .await?;
+    pub async fn new(database_url: &str, database: &DatabaseConfig) -> Result<Self, CacheError> {
+        let pool = database.connect(database_url).await?;
 
         return Ok(PostgresCache { pool });
     }
@@ -46,6 +38,8 @@ impl CacheBackend for PostgresCache {
                 is_errored: true,
                 space_id: space,
                 cid: uri.clone(),
+                sender: None,
+                event_index: 0,
             });
         }
 
@@ -58,6 +52,8 @@ impl CacheBackend for PostgresCache {
             is_errored: false,
             space_id: space,
             cid: uri.clone(),
+            sender: None,
+            event_index: 0,
         })
     }
 }
@@ -1,9 +1,16 @@
-use std::{collections::HashMap, sync::Arc};
-
-use tokio::sync::RwLock;
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use dashmap::{mapref::entry::Entry, DashMap};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
 use uuid::Uuid;
 
-use crate::models::properties::DataType;
+use crate::models::properties::{DataType, PropertyItem};
 use crate::storage::{postgres::PostgresStorage, StorageError};
 
 pub struct PropertiesCache {
@@ -22,31 +29,149 @@ pub struct PropertiesCache {
     /// Currently DataType enum has 6 variants. Rust will use a u8 to
     /// represent the data type, so it's safe to store the DataType enum
     /// directly.
-    inner: Arc<RwLock<HashMap<Uuid, DataType>>>,
+    ///
+    /// The map is sharded so lookups are synchronous and only contend
+    /// with writes to the same shard, letting large edits be validated
+    /// without serializing on a single lock.
+    inner: DashMap<Uuid, DataType>,
+    /// How long cached data types are trusted before a refresh replaces the
+    /// ones that differ from storage. Tracked for the whole cache rather
+    /// than per property to keep entries small.
+    max_age: Option<Duration>,
+    reconciled_at: Mutex<Instant>,
+    /// The properties dropped or replaced because they were invalidated or
+    /// differed from storage, for metrics.
+    invalidations: AtomicU64,
 }
 
 impl PropertiesCache {
     pub fn new() -> Self {
         Self {
-            inner: Arc::new(RwLock::new(HashMap::new())),
+            inner: DashMap::new(),
+            max_age: None,
+            reconciled_at: Mutex::new(Instant::now()),
+            invalidations: AtomicU64::new(0),
         }
     }
 
+    /// Replaces cached data types that differ from storage on the first
+    /// refresh after `max_age`, so a long-running indexer recovers from a
+    /// cache that diverged from the properties table.
+    pub fn with_max_age(mut self, max_age: Option<Duration>) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    /// The number of properties invalidated since the cache was built.
+    pub fn invalidations(&self) -> u64 {
+        self.invalidations.load(Ordering::Relaxed)
+    }
+
     pub async fn from_storage(storage: &PostgresStorage) -> Result<Self, StorageError> {
-        let properties = storage.get_all_properties().await?;
-        let mut cache_map = HashMap::new();
-        
-        for property in properties {
-            cache_map.insert(property.id, property.data_type);
-        }
+        let cache = Self::new();
+        let loaded = cache.refresh(storage).await?;
 
         tracing::info!(
             "Initialized PropertiesCache from database with {} properties",
-            cache_map.len()
+            loaded
         );
 
-        Ok(Self {
-            inner: Arc::new(RwLock::new(cache_map)),
+        Ok(cache)
+    }
+
+    /// Loads properties written to storage since the cache was built, for
+    /// example by another indexer instance. Cached properties are kept, so
+    /// properties inserted while a block is being written aren't dropped
+    /// before their transaction commits. Returns the number of properties
+    /// added.
+    pub async fn refresh(&self, storage: &PostgresStorage) -> Result<usize, StorageError> {
+        let properties = storage.get_all_properties().await?;
+
+        Ok(self.apply(properties, Instant::now()))
+    }
+
+    fn apply(&self, properties: Vec<PropertyItem>, now: Instant) -> usize {
+        let stale = self.max_age.is_some_and(|max_age| {
+            let mut reconciled_at = self.reconciled_at.lock().unwrap();
+            let stale = now.saturating_duration_since(*reconciled_at) >= max_age;
+            if stale {
+                *reconciled_at = now;
+            }
+            stale
+        });
+        let mut added = 0;
+
+        for property in properties {
+            match self.inner.entry(property.id) {
+                Entry::Occupied(mut cached) if *cached.get() != property.data_type => {
+                    if stale {
+                        tracing::warn!(
+                            property_id = %property.id,
+                            cached = ?cached.get(),
+                            stored = ?property.data_type,
+                            "[PropertiesCache][Refresh] Replaced a stale data type with the stored one"
+                        );
+                        cached.insert(property.data_type);
+                        self.invalidations.fetch_add(1, Ordering::Relaxed);
+                        continue;
+                    }
+
+                    tracing::warn!(
+                        property_id = %property.id,
+                        cached = ?cached.get(),
+                        stored = ?property.data_type,
+                        "[PropertiesCache][Refresh] Cached data type differs from storage, invalidate the property to reload it"
+                    );
+                }
+                Entry::Occupied(_) => {}
+                Entry::Vacant(entry) => {
+                    entry.insert(property.data_type);
+                    added += 1;
+                }
+            }
+        }
+
+        added
+    }
+
+    /// Drops a property from the cache so the next refresh reloads it from
+    /// storage.
+    pub fn invalidate(&self, key: &Uuid) {
+        if self.inner.remove(key).is_some() {
+            self.invalidations.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Refreshes the cache from storage every `interval` until the returned
+    /// task is aborted. Failed refreshes are logged and retried on the next
+    /// tick.
+    pub fn spawn_refresh(
+        self: &Arc<Self>,
+        storage: Arc<PostgresStorage>,
+        interval: Duration,
+    ) -> JoinHandle<()> {
+        let cache = Arc::clone(self);
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            // The first tick completes immediately and the cache was just built.
+            ticker.tick().await;
+
+            loop {
+                ticker.tick().await;
+
+                match cache.refresh(&storage).await {
+                    Ok(added) => tracing::debug!(
+                        added,
+                        invalidations = cache.invalidations(),
+                        "Refreshed PropertiesCache"
+                    ),
+                    Err(error) => {
+                        tracing::error!(error = %error, "Failed to refresh PropertiesCache")
+                    }
+                }
+            }
         })
     }
 }
@@ -60,38 +185,58 @@ pub enum PropertiesCacheError {
 //  made in the same block with the same id but different data types.
 //  This likely would only happen if done intentionally but we should
 //  potentially protect against it.
+//
+// Lookups are synchronous so validating an edit doesn't await once per
+// value. Caches that load properties from elsewhere do so in `prefetch`,
+// which is called once per edit with every property the edit references.
 #[async_trait::async_trait]
-pub trait ImmutableCache {
+pub trait ImmutableCache: Sync {
     async fn insert(&self, key: &Uuid, value: DataType);
-    async fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError>;
+    fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError>;
+
+    /// Replaces the data type of a property once its migration was applied,
+    /// which is the only way a cached property changes. Other indexer
+    /// instances keep their cached data type until they invalidate it.
+    async fn migrate(&self, key: &Uuid, value: DataType);
+
+    /// Inserts a batch of properties, such as the ones created by a block.
+    async fn insert_many(&self, properties: &[PropertyItem]) {
+        for property in properties {
+            self.insert(&property.id, property.data_type).await;
+        }
+    }
+
+    /// Makes the given properties available to `get`. Properties that can't
+    /// be found are skipped.
+    async fn prefetch(&self, _keys: &[Uuid]) {}
 }
 
 #[async_trait::async_trait]
 impl ImmutableCache for PropertiesCache {
     async fn insert(&self, key: &Uuid, value: DataType) {
-        {
-            let read = self.inner.read().await;
-            if let Some(_) = read.get(key) {
+        match self.inner.entry(*key) {
+            Entry::Occupied(_) => {
                 tracing::info!(
                     "[PropertiesCache][Insert] Found invalid write to existing property id {:?} with value {:?}",
                     key,
                     &value
                 );
-                return;
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(value);
             }
         }
-
-        let mut write = self.inner.write().await;
-        write.insert(*key, value.clone());
     }
 
-    async fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
-        let read = self.inner.read().await;
-
-        return match read.get(key) {
+    fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
+        match self.inner.get(key) {
             Some(value) => Ok(*value),
             None => Err(PropertiesCacheError::PropertyNotFoundError),
-        };
+        }
+    }
+
+    async fn migrate(&self, key: &Uuid, value: DataType) {
+        self.inner.insert(*key, value);
     }
 }
 
@@ -107,7 +252,7 @@ mod tests {
 
         cache.insert(&key, DataType::String).await;
 
-        let result = cache.get(&key).await;
+        let result = cache.get(&key);
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), DataType::String);
     }
@@ -119,32 +264,32 @@ mod tests {
         // Test Text
         let text_uuid = Uuid::new_v4();
         cache.insert(&text_uuid, DataType::String).await;
-        assert_eq!(cache.get(&text_uuid).await.unwrap(), DataType::String);
+        assert_eq!(cache.get(&text_uuid).unwrap(), DataType::String);
 
         // Test Number
         let number_uuid = Uuid::new_v4();
         cache.insert(&number_uuid, DataType::Number).await;
-        assert_eq!(cache.get(&number_uuid).await.unwrap(), DataType::Number);
+        assert_eq!(cache.get(&number_uuid).unwrap(), DataType::Number);
 
         // Test Checkbox
         let checkbox_uuid = Uuid::new_v4();
         cache.insert(&checkbox_uuid, DataType::Boolean).await;
-        assert_eq!(cache.get(&checkbox_uuid).await.unwrap(), DataType::Boolean);
+        assert_eq!(cache.get(&checkbox_uuid).unwrap(), DataType::Boolean);
 
         // Test Time
         let time_uuid = Uuid::new_v4();
         cache.insert(&time_uuid, DataType::Time).await;
-        assert_eq!(cache.get(&time_uuid).await.unwrap(), DataType::Time);
+        assert_eq!(cache.get(&time_uuid).unwrap(), DataType::Time);
 
         // Test Point
         let point_uuid = Uuid::new_v4();
         cache.insert(&point_uuid, DataType::Point).await;
-        assert_eq!(cache.get(&point_uuid).await.unwrap(), DataType::Point);
+        assert_eq!(cache.get(&point_uuid).unwrap(), DataType::Point);
 
         // Test Relation
         let relation_uuid = Uuid::new_v4();
         cache.insert(&relation_uuid, DataType::Relation).await;
-        assert_eq!(cache.get(&relation_uuid).await.unwrap(), DataType::Relation);
+        assert_eq!(cache.get(&relation_uuid).unwrap(), DataType::Relation);
     }
 
     #[tokio::test]
@@ -154,14 +299,14 @@ mod tests {
 
         // Insert initial value
         cache.insert(&key, DataType::String).await;
-        let initial_value = cache.get(&key).await.unwrap();
+        let initial_value = cache.get(&key).unwrap();
         assert_eq!(initial_value, DataType::String);
 
         // Attempt to overwrite with different data type
         cache.insert(&key, DataType::Number).await;
 
         // Value should remain unchanged (immutable)
-        let final_value = cache.get(&key).await.unwrap();
+        let final_value = cache.get(&key).unwrap();
         assert_eq!(final_value, DataType::String); // Should still be Text, not Number
     }
 
@@ -172,7 +317,7 @@ mod tests {
 
         // Insert initial value
         cache.insert(&key, DataType::Boolean).await;
-        let initial_value = cache.get(&key).await.unwrap();
+        let initial_value = cache.get(&key).unwrap();
         assert_eq!(initial_value, DataType::Boolean);
 
         // Attempt multiple overwrites with different data types
@@ -183,16 +328,69 @@ mod tests {
         cache.insert(&key, DataType::Relation).await;
 
         // Value should remain unchanged
-        let final_value = cache.get(&key).await.unwrap();
+        let final_value = cache.get(&key).unwrap();
         assert_eq!(final_value, DataType::Boolean); // Should still be Checkbox
     }
 
+    #[tokio::test]
+    async fn test_invalidate_removes_property() {
+        let cache = PropertiesCache::new();
+        let key = Uuid::new_v4();
+
+        cache.insert(&key, DataType::String).await;
+        cache.invalidate(&key);
+        assert!(cache.get(&key).is_err());
+
+        // An invalidated property can be cached with a new data type
+        cache.insert(&key, DataType::Number).await;
+        assert_eq!(cache.get(&key).unwrap(), DataType::Number);
+        assert_eq!(cache.invalidations(), 1);
+
+        // Invalidating a property that isn't cached isn't counted
+        cache.invalidate(&Uuid::new_v4());
+        assert_eq!(cache.invalidations(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_max_age_replaces_diverged_data_types() {
+        let now = Instant::now();
+        let cache = PropertiesCache::new().with_max_age(Some(Duration::from_secs(60)));
+        *cache.reconciled_at.lock().unwrap() = now;
+        let (diverged, unstored, added) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        cache.insert(&diverged, DataType::String).await;
+        cache.insert(&unstored, DataType::Boolean).await;
+        let stored = || {
+            vec![
+                PropertyItem {
+                    id: diverged,
+                    data_type: DataType::Number,
+                },
+                PropertyItem {
+                    id: added,
+                    data_type: DataType::Time,
+                },
+            ]
+        };
+
+        // Within the max age the cached data type is kept
+        assert_eq!(cache.apply(stored(), now + Duration::from_secs(30)), 1);
+        assert_eq!(cache.get(&diverged).unwrap(), DataType::String);
+        assert_eq!(cache.get(&added).unwrap(), DataType::Time);
+
+        assert_eq!(cache.apply(stored(), now + Duration::from_secs(60)), 0);
+        assert_eq!(cache.get(&diverged).unwrap(), DataType::Number);
+        // Properties that aren't stored yet are kept
+        assert_eq!(cache.get(&unstored).unwrap(), DataType::Boolean);
+        assert_eq!(cache.invalidations(), 1);
+    }
+
     #[tokio::test]
     async fn test_get_nonexistent_property_returns_error() {
         let cache = PropertiesCache::new();
         let nonexistent_key = Uuid::new_v4();
 
-        let result = cache.get(&nonexistent_key).await;
+        let result = cache.get(&nonexistent_key);
         assert!(result.is_err());
 
         match result {
@@ -232,7 +430,7 @@ mod tests {
         }
 
         // Only one value should have been stored (whichever won the race)
-        let result = cache.get(&key).await;
+        let result = cache.get(&key);
         assert!(result.is_ok());
         let value = result.unwrap();
         // Should be one of the valid DataType variants
@@ -261,12 +459,12 @@ mod tests {
         cache.insert(&prop3, DataType::Boolean).await;
 
         // Verify all are stored correctly
-        assert_eq!(cache.get(&prop1).await.unwrap(), DataType::String);
-        assert_eq!(cache.get(&prop2).await.unwrap(), DataType::Number);
-        assert_eq!(cache.get(&prop3).await.unwrap(), DataType::Boolean);
+        assert_eq!(cache.get(&prop1).unwrap(), DataType::String);
+        assert_eq!(cache.get(&prop2).unwrap(), DataType::Number);
+        assert_eq!(cache.get(&prop3).unwrap(), DataType::Boolean);
 
         // Verify we can still get error for non-existent property
         let prop4 = Uuid::new_v4();
-        assert!(cache.get(&prop4).await.is_err());
+        assert!(cache.get(&prop4).is_err());
     }
 }
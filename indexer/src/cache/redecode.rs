@@ -0,0 +1,134 @@
+//! Decodes cached edits again from the raw bytes they were fetched with, so
+//! a fix to the edit decoder can be applied to historical content without
+//! fetching it from IPFS again.
+//!
+//! Entries whose bytes now decode are updated with the decoded edit, its
+//! hash and the format it was encoded in. Entries cached before their raw
+//! bytes were stored are skipped.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::{info, warn};
+use wire::{deserialize::deserialize_with_format, hash::edit_hash};
+
+use super::CacheError;
+use crate::error::IndexingError;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RedecodeReport {
+    /// The entries with raw bytes that were decoded again.
+    pub checked: u64,
+    /// The errored entries that decode now.
+    pub fixed: u64,
+    /// The entries that decode to a different edit than the cached one.
+    pub changed: u64,
+    /// The entries that still don't decode. Entries that decoded before are
+    /// left as they are.
+    pub failed: u64,
+}
+
+#[derive(sqlx::FromRow)]
+struct RawRow {
+    id: i32,
+    uri: String,
+    is_errored: bool,
+    content_hash: Option<String>,
+    raw_bytes: Vec<u8>,
+}
+
+pub struct CacheRedecoder {
+    pool: PgPool,
+    batch_size: i64,
+}
+
+impl CacheRedecoder {
+    pub fn new(pool: PgPool, batch_size: i64) -> Self {
+        CacheRedecoder { pool, batch_size }
+    }
+
+    /// Decodes the errored entries again, or every entry if `all` is set.
+    pub async fn run(&self, all: bool) -> Result<RedecodeReport, IndexingError> {
+        let mut report = RedecodeReport::default();
+        let mut after = 0;
+
+        loop {
+            let rows = sqlx::query_as::<_, RawRow>(
+                r#"
+                    SELECT id, uri, is_errored, content_hash, raw_bytes
+                    FROM ipfs_cache
+                    WHERE id > $1 AND raw_bytes IS NOT NULL AND ($2 OR is_errored)
+                    ORDER BY id
+                    LIMIT $3
+                "#,
+            )
+            .bind(after)
+            .bind(all)
+            .bind(self.batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            after = last.id;
+
+            for row in &rows {
+                self.redecode(row, &mut report).await?;
+            }
+
+            info!(
+                checked = report.checked,
+                fixed = report.fixed,
+                changed = report.changed,
+                "Decoded cache entries"
+            );
+        }
+
+        Ok(report)
+    }
+
+    async fn redecode(
+        &self,
+        row: &RawRow,
+        report: &mut RedecodeReport,
+    ) -> Result<(), IndexingError> {
+        report.checked += 1;
+
+        let (edit, format) = match deserialize_with_format(&row.raw_bytes) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                warn!(uri = %row.uri, error = %error, "Cache entry still doesn't decode");
+                report.failed += 1;
+                return Ok(());
+            }
+        };
+
+        let content_hash = edit_hash(&edit);
+        if row.is_errored {
+            report.fixed += 1;
+        } else if row.content_hash.as_deref() == Some(content_hash.as_str()) {
+            return Ok(());
+        } else {
+            report.changed += 1;
+        }
+
+        // Stored the way the cache writes it, as the JSON of an `Option<Edit>`.
+        let json = serde_json::to_value(Some(&edit)).map_err(CacheError::from)?;
+        sqlx::query(
+            r#"
+                UPDATE ipfs_cache
+                SET json = $2, is_errored = false, content_hash = $3,
+                    compression = $4, encoding = $5
+                WHERE id = $1
+            "#,
+        )
+        .bind(row.id)
+        .bind(json)
+        .bind(content_hash)
+        .bind(format.codec.as_str())
+        .bind(format.encoding.as_str())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
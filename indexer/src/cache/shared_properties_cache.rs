@@ -0,0 +1,320 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use thiserror::Error;
+use uuid::Uuid;
+
+use super::properties_cache::{ImmutableCache, PropertiesCache, PropertiesCacheError};
+use crate::models::properties::DataType;
+
+/// The Redis hash holding property id -> data type.
+const REDIS_KEY: &str = "gaia:properties";
+
+#[derive(Error, Debug)]
+pub enum PropertyStoreError {
+    #[error("Property store error: {0}")]
+    Redis(#[from] redis::RedisError),
+
+    #[error("Property store error: invalid data type '{0}'")]
+    InvalidDataType(String),
+}
+
+/// A store of property data types shared by several indexer instances.
+#[async_trait]
+pub trait PropertyStore: Send + Sync {
+    /// Stores the data type of a property unless the property is already
+    /// stored. Returns the data type that is stored afterwards, which is the
+    /// existing one if another instance stored the property first.
+    async fn set_if_absent(
+        &self,
+        key: &Uuid,
+        data_type: DataType,
+    ) -> Result<DataType, PropertyStoreError>;
+
+    /// Returns the data types of the given properties, in order, or `None`
+    /// for properties that aren't stored.
+    async fn get_many(&self, keys: &[Uuid]) -> Result<Vec<Option<DataType>>, PropertyStoreError>;
+
+    /// Stores the data type of a property, replacing the stored one.
+    async fn set(&self, key: &Uuid, data_type: DataType) -> Result<(), PropertyStoreError>;
+}
+
+pub struct RedisPropertyStore {
+    connection: ConnectionManager,
+}
+
+impl RedisPropertyStore {
+    pub async fn connect(redis_url: &str) -> Result<Self, PropertyStoreError> {
+        let client = redis::Client::open(redis_url)?;
+        let connection = client.get_connection_manager().await?;
+
+        Ok(RedisPropertyStore { connection })
+    }
+}
+
+#[async_trait]
+impl PropertyStore for RedisPropertyStore {
+    async fn set_if_absent(
+        &self,
+        key: &Uuid,
+        data_type: DataType,
+    ) -> Result<DataType, PropertyStoreError> {
+        let mut connection = self.connection.clone();
+        let field = key.to_string();
+
+        let inserted: bool = connection
+            .hset_nx(REDIS_KEY, &field, data_type.as_ref())
+            .await?;
+        if inserted {
+            return Ok(data_type);
+        }
+
+        let stored: Option<String> = connection.hget(REDIS_KEY, &field).await?;
+        match stored {
+            Some(stored) => parse_data_type(&stored),
+            // Only happens if the hash was deleted in between.
+            None => Ok(data_type),
+        }
+    }
+
+    async fn get_many(&self, keys: &[Uuid]) -> Result<Vec<Option<DataType>>, PropertyStoreError> {
+        let mut connection = self.connection.clone();
+        let fields: Vec<String> = keys.iter().map(Uuid::to_string).collect();
+
+        // HMGET directly, as `hget` sends HGET for a single field and the
+        // reply shape would differ.
+        let stored: Vec<Option<String>> = redis::cmd("HMGET")
+            .arg(REDIS_KEY)
+            .arg(&fields)
+            .query_async(&mut connection)
+            .await?;
+
+        stored
+            .iter()
+            .map(|value| value.as_deref().map(parse_data_type).transpose())
+            .collect()
+    }
+
+    async fn set(&self, key: &Uuid, data_type: DataType) -> Result<(), PropertyStoreError> {
+        let mut connection = self.connection.clone();
+
+        let _: () = connection
+            .hset(REDIS_KEY, key.to_string(), data_type.as_ref())
+            .await?;
+
+        Ok(())
+    }
+}
+
+fn parse_data_type(value: &str) -> Result<DataType, PropertyStoreError> {
+    DataType::try_from(value).map_err(|_| PropertyStoreError::InvalidDataType(value.to_string()))
+}
+
+/// A `PropertiesCache` backed by a `PropertyStore` shared between indexer
+/// instances, for example one per space shard.
+///
+/// Properties missing from the local cache are loaded from the shared store
+/// when an edit is prefetched, so lookups stay local. Writes go to the
+/// shared store first so the first instance to see a
+/// property decides its data type for every instance. If the shared store
+/// is unavailable the cache keeps working locally.
+pub struct SharedPropertiesCache<S> {
+    local: Arc<PropertiesCache>,
+    store: S,
+}
+
+impl<S: PropertyStore> SharedPropertiesCache<S> {
+    pub fn new(local: Arc<PropertiesCache>, store: S) -> Self {
+        Self { local, store }
+    }
+}
+
+#[async_trait]
+impl<S: PropertyStore> ImmutableCache for SharedPropertiesCache<S> {
+    async fn insert(&self, key: &Uuid, value: DataType) {
+        if self.local.get(key).is_ok() {
+            // Logs and ignores the write, as the property is immutable.
+            self.local.insert(key, value).await;
+            return;
+        }
+
+        let stored = match self.store.set_if_absent(key, value).await {
+            Ok(stored) => stored,
+            Err(error) => {
+                tracing::error!(
+                    error = %error,
+                    property_id = %key,
+                    "[SharedPropertiesCache][Insert] Failed to write to the shared store"
+                );
+                value
+            }
+        };
+
+        if stored != value {
+            tracing::info!(
+                "[SharedPropertiesCache][Insert] Found invalid write to existing property id {:?} with value {:?}",
+                key,
+                &value
+            );
+        }
+
+        self.local.insert(key, stored).await;
+    }
+
+    fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
+        self.local.get(key)
+    }
+
+    async fn migrate(&self, key: &Uuid, value: DataType) {
+        if let Err(error) = self.store.set(key, value).await {
+            tracing::error!(
+                error = %error,
+                property_id = %key,
+                "[SharedPropertiesCache][Migrate] Failed to write to the shared store"
+            );
+        }
+
+        self.local.migrate(key, value).await;
+    }
+
+    async fn prefetch(&self, keys: &[Uuid]) {
+        let mut missing: Vec<Uuid> = keys
+            .iter()
+            .filter(|key| self.local.get(key).is_err())
+            .copied()
+            .collect();
+        missing.sort_unstable();
+        missing.dedup();
+
+        if missing.is_empty() {
+            return;
+        }
+
+        match self.store.get_many(&missing).await {
+            Ok(stored) => {
+                for (key, value) in missing.iter().zip(stored) {
+                    if let Some(value) = value {
+                        self.local.insert(key, value).await;
+                    }
+                }
+            }
+            Err(error) => {
+                tracing::warn!(
+                    error = %error,
+                    missing = missing.len(),
+                    "[SharedPropertiesCache][Prefetch] Failed to read from the shared store"
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use tokio::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemoryPropertyStore {
+        properties: Mutex<HashMap<Uuid, DataType>>,
+    }
+
+    #[async_trait]
+    impl PropertyStore for Arc<MemoryPropertyStore> {
+        async fn set_if_absent(
+            &self,
+            key: &Uuid,
+            data_type: DataType,
+        ) -> Result<DataType, PropertyStoreError> {
+            Ok(*self
+                .properties
+                .lock()
+                .await
+                .entry(*key)
+                .or_insert(data_type))
+        }
+
+        async fn get_many(
+            &self,
+            keys: &[Uuid],
+        ) -> Result<Vec<Option<DataType>>, PropertyStoreError> {
+            let properties = self.properties.lock().await;
+            Ok(keys
+                .iter()
+                .map(|key| properties.get(key).copied())
+                .collect())
+        }
+
+        async fn set(&self, key: &Uuid, data_type: DataType) -> Result<(), PropertyStoreError> {
+            self.properties.lock().await.insert(*key, data_type);
+            Ok(())
+        }
+    }
+
+    fn instance(
+        store: &Arc<MemoryPropertyStore>,
+    ) -> SharedPropertiesCache<Arc<MemoryPropertyStore>> {
+        SharedPropertiesCache::new(Arc::new(PropertiesCache::new()), store.clone())
+    }
+
+    #[tokio::test]
+    async fn test_properties_are_shared_between_instances() {
+        let store = Arc::new(MemoryPropertyStore::default());
+        let first = instance(&store);
+        let second = instance(&store);
+        let key = Uuid::new_v4();
+
+        second.prefetch(&[key]).await;
+        assert!(second.get(&key).is_err());
+
+        first.insert(&key, DataType::Number).await;
+
+        // Not visible until prefetched into the local cache
+        assert!(second.get(&key).is_err());
+        second.prefetch(&[key, key]).await;
+        assert_eq!(second.get(&key).unwrap(), DataType::Number);
+        assert_eq!(second.local.get(&key).unwrap(), DataType::Number);
+    }
+
+    #[tokio::test]
+    async fn test_first_instance_decides_data_type() {
+        let store = Arc::new(MemoryPropertyStore::default());
+        let first = instance(&store);
+        let second = instance(&store);
+        let key = Uuid::new_v4();
+
+        first.insert(&key, DataType::Relation).await;
+        second.insert(&key, DataType::String).await;
+
+        assert_eq!(first.get(&key).unwrap(), DataType::Relation);
+        assert_eq!(second.get(&key).unwrap(), DataType::Relation);
+    }
+
+    #[tokio::test]
+    async fn test_migrate_replaces_shared_data_type() {
+        let store = Arc::new(MemoryPropertyStore::default());
+        let first = instance(&store);
+        let key = Uuid::new_v4();
+
+        first.insert(&key, DataType::Number).await;
+        first.migrate(&key, DataType::String).await;
+        assert_eq!(first.get(&key).unwrap(), DataType::String);
+
+        let second = instance(&store);
+        second.prefetch(&[key]).await;
+        assert_eq!(second.get(&key).unwrap(), DataType::String);
+    }
+
+    #[test]
+    fn test_parse_data_type() {
+        for data_type in DataType::all_variants() {
+            assert_eq!(parse_data_type(data_type.as_ref()).unwrap(), data_type);
+        }
+        assert!(matches!(
+            parse_data_type("Unknown"),
+            Err(PropertyStoreError::InvalidDataType(_))
+        ));
+    }
+}
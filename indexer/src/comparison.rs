@@ -0,0 +1,402 @@
+//! Compares the knowledge graph tables of two databases, such as the
+//! databases of two indexer versions that indexed the same blocks.
+//!
+//! Each table is walked in primary key order on one side and the keys of
+//! every batch are looked up on the other side, so neither database has to
+//! sort the other's rows. Rows are compared by a hash of the columns both
+//! databases have, so a column added by a newer version doesn't make every
+//! row differ.
+
+use std::collections::HashMap;
+
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::error::IndexingError;
+
+pub const DEFAULT_BATCH_SIZE: i64 = 5000;
+
+/// A table that's compared, with its primary key columns and their types.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ComparedTable {
+    pub name: &'static str,
+    pub key: &'static [(&'static str, &'static str)],
+}
+
+/// The tables written by the knowledge graph indexer, in the order they're
+/// compared.
+pub const COMPARED_TABLES: [ComparedTable; 8] = [
+    ComparedTable {
+        name: "spaces",
+        key: &[("id", "uuid")],
+    },
+    ComparedTable {
+        name: "entities",
+        key: &[("id", "uuid")],
+    },
+    ComparedTable {
+        name: "properties",
+        key: &[("id", "uuid")],
+    },
+    ComparedTable {
+        name: "values",
+        key: &[("id", "text")],
+    },
+    ComparedTable {
+        name: "relations",
+        key: &[("id", "uuid")],
+    },
+    ComparedTable {
+        name: "members",
+        key: &[("address", "text"), ("space_id", "uuid")],
+    },
+    ComparedTable {
+        name: "editors",
+        key: &[("address", "text"), ("space_id", "uuid")],
+    },
+    ComparedTable {
+        name: "subspaces",
+        key: &[("parent_space_id", "uuid"), ("child_space_id", "uuid")],
+    },
+];
+
+impl ComparedTable {
+    pub fn find(name: &str) -> Option<ComparedTable> {
+        COMPARED_TABLES
+            .iter()
+            .find(|table| table.name == name)
+            .copied()
+    }
+
+    fn key_array(&self) -> String {
+        let columns: Vec<String> = self
+            .key
+            .iter()
+            .map(|(column, _)| format!("t.{}::text", column))
+            .collect();
+        format!("ARRAY[{}]", columns.join(", "))
+    }
+
+    fn key_list(&self) -> String {
+        let columns: Vec<String> = self
+            .key
+            .iter()
+            .map(|(column, _)| format!("t.{}", column))
+            .collect();
+        columns.join(", ")
+    }
+
+    /// The key columns cast from text parameters, starting at `$first`.
+    fn key_params(&self, first: usize) -> String {
+        let params: Vec<String> = self
+            .key
+            .iter()
+            .enumerate()
+            .map(|(index, (_, ty))| format!("${}::{}", first + index, ty))
+            .collect();
+        format!("({})", params.join(", "))
+    }
+}
+
+/// The divergences found in one table.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct TableComparison {
+    pub table: String,
+    pub left_rows: i64,
+    pub right_rows: i64,
+    pub only_in_left: i64,
+    pub only_in_right: i64,
+    /// Rows in both databases whose compared columns differ.
+    pub different: i64,
+    /// Keys of the first divergent rows of each kind, with composite keys
+    /// joined by `/`.
+    pub only_in_left_sample: Vec<String>,
+    pub only_in_right_sample: Vec<String>,
+    pub different_sample: Vec<String>,
+    /// Columns that are only in one of the databases and aren't compared.
+    pub skipped_columns: Vec<String>,
+}
+
+impl TableComparison {
+    pub fn is_identical(&self) -> bool {
+        self.only_in_left == 0 && self.only_in_right == 0 && self.different == 0
+    }
+}
+
+/// The block a cursor in the `meta` table was saved at.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, sqlx::FromRow)]
+pub struct IndexedBlock {
+    pub id: String,
+    pub block_number: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ComparisonReport {
+    /// The cursors of each database, as they should be at the same block
+    /// for the tables to match.
+    pub left_cursors: Vec<IndexedBlock>,
+    pub right_cursors: Vec<IndexedBlock>,
+    pub tables: Vec<TableComparison>,
+}
+
+impl ComparisonReport {
+    pub fn is_identical(&self) -> bool {
+        self.tables.iter().all(TableComparison::is_identical)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct HashedRow {
+    key: Vec<String>,
+    hash: String,
+}
+
+pub struct DatabaseComparison {
+    left: PgPool,
+    right: PgPool,
+    batch_size: i64,
+    sample_size: usize,
+}
+
+impl DatabaseComparison {
+    pub fn new(left: PgPool, right: PgPool, batch_size: i64, sample_size: usize) -> Self {
+        DatabaseComparison {
+            left,
+            right,
+            batch_size,
+            sample_size,
+        }
+    }
+
+    /// Compares the given tables. The databases should be at rest, as rows
+    /// written during the comparison may be reported as divergent.
+    pub async fn run(&self, tables: &[ComparedTable]) -> Result<ComparisonReport, IndexingError> {
+        let mut report = ComparisonReport {
+            left_cursors: cursors(&self.left).await?,
+            right_cursors: cursors(&self.right).await?,
+            tables: Vec::new(),
+        };
+
+        for table in tables {
+            let comparison = self.compare_table(table).await?;
+            info!(
+                table = table.name,
+                only_in_left = comparison.only_in_left,
+                only_in_right = comparison.only_in_right,
+                different = comparison.different,
+                "Compared table"
+            );
+            report.tables.push(comparison);
+        }
+
+        Ok(report)
+    }
+
+    async fn compare_table(&self, table: &ComparedTable) -> Result<TableComparison, IndexingError> {
+        let left_columns = columns(&self.left, table.name).await?;
+        let right_columns = columns(&self.right, table.name).await?;
+
+        let shared: Vec<String> = left_columns
+            .iter()
+            .filter(|column| right_columns.contains(column))
+            .cloned()
+            .collect();
+        let mut skipped_columns: Vec<String> = left_columns
+            .iter()
+            .chain(&right_columns)
+            .filter(|column| !shared.contains(column))
+            .cloned()
+            .collect();
+        skipped_columns.sort();
+
+        let hash = format!(
+            "md5(ROW({})::text)",
+            shared
+                .iter()
+                .map(|column| format!("t.\"{}\"", column))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+
+        let mut comparison = TableComparison {
+            table: table.name.to_string(),
+            skipped_columns,
+            ..Default::default()
+        };
+
+        // Left to right finds rows missing on the right and different rows
+        let mut cursor: Option<Vec<String>> = None;
+        loop {
+            let rows = self
+                .batch(&self.left, table, &hash, cursor.as_ref())
+                .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            cursor = Some(last.key.clone());
+            comparison.left_rows += rows.len() as i64;
+
+            let matches = self.lookup(&self.right, table, &hash, &rows).await?;
+            for row in &rows {
+                match matches.get(&row.key) {
+                    None => {
+                        comparison.only_in_left += 1;
+                        sample(&mut comparison.only_in_left_sample, row, self.sample_size);
+                    }
+                    Some(hash) if *hash != row.hash => {
+                        comparison.different += 1;
+                        sample(&mut comparison.different_sample, row, self.sample_size);
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        // Right to left only needs to find rows missing on the left
+        let mut cursor: Option<Vec<String>> = None;
+        loop {
+            let rows = self
+                .batch(&self.right, table, &hash, cursor.as_ref())
+                .await?;
+            let Some(last) = rows.last() else {
+                break;
+            };
+            cursor = Some(last.key.clone());
+            comparison.right_rows += rows.len() as i64;
+
+            let matches = self.lookup(&self.left, table, &hash, &rows).await?;
+            for row in rows.iter().filter(|row| !matches.contains_key(&row.key)) {
+                comparison.only_in_right += 1;
+                sample(&mut comparison.only_in_right_sample, row, self.sample_size);
+            }
+        }
+
+        Ok(comparison)
+    }
+
+    /// Returns the next batch of rows after the cursor, in key order.
+    async fn batch(
+        &self,
+        pool: &PgPool,
+        table: &ComparedTable,
+        hash: &str,
+        cursor: Option<&Vec<String>>,
+    ) -> Result<Vec<HashedRow>, IndexingError> {
+        let after = match cursor {
+            Some(_) => format!("WHERE ({}) > {}", table.key_list(), table.key_params(2)),
+            None => String::new(),
+        };
+        let sql = format!(
+            "SELECT {} AS key, {} AS hash FROM {} t {} ORDER BY {} LIMIT $1",
+            table.key_array(),
+            hash,
+            table.name,
+            after,
+            table.key_list(),
+        );
+
+        let mut query = sqlx::query_as::<_, HashedRow>(&sql).bind(self.batch_size);
+        for value in cursor.into_iter().flatten() {
+            query = query.bind(value);
+        }
+
+        Ok(query.fetch_all(pool).await?)
+    }
+
+    /// Returns the hashes of the rows with the same keys as `rows`.
+    async fn lookup(
+        &self,
+        pool: &PgPool,
+        table: &ComparedTable,
+        hash: &str,
+        rows: &[HashedRow],
+    ) -> Result<HashMap<Vec<String>, String>, IndexingError> {
+        let params: Vec<String> = table
+            .key
+            .iter()
+            .enumerate()
+            .map(|(index, _)| format!("${}::text[]", index + 1))
+            .collect();
+        let casts: Vec<String> = table
+            .key
+            .iter()
+            .enumerate()
+            .map(|(index, (_, ty))| format!("k.c{}::{}", index, ty))
+            .collect();
+        let aliases: Vec<String> = (0..table.key.len())
+            .map(|index| format!("c{}", index))
+            .collect();
+
+        let sql = format!(
+            "SELECT {} AS key, {} AS hash FROM {} t
+            WHERE ({}) IN (SELECT {} FROM UNNEST({}) AS k({}))",
+            table.key_array(),
+            hash,
+            table.name,
+            table.key_list(),
+            casts.join(", "),
+            params.join(", "),
+            aliases.join(", "),
+        );
+
+        let mut query = sqlx::query_as::<_, HashedRow>(&sql);
+        for index in 0..table.key.len() {
+            let values: Vec<&str> = rows.iter().map(|row| row.key[index].as_str()).collect();
+            query = query.bind(values);
+        }
+
+        Ok(query
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| (row.key, row.hash))
+            .collect())
+    }
+}
+
+fn sample(samples: &mut Vec<String>, row: &HashedRow, sample_size: usize) {
+    if samples.len() < sample_size {
+        samples.push(row.key.join("/"));
+    }
+}
+
+/// The columns of a table in the current schema, in order.
+pub(crate) async fn columns(pool: &PgPool, table: &str) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar(
+        r#"
+            SELECT column_name::text FROM information_schema.columns
+            WHERE table_schema = current_schema() AND table_name = $1
+            ORDER BY ordinal_position
+        "#,
+    )
+    .bind(table)
+    .fetch_all(pool)
+    .await
+}
+
+async fn cursors(pool: &PgPool) -> Result<Vec<IndexedBlock>, IndexingError> {
+    Ok(
+        sqlx::query_as::<_, IndexedBlock>("SELECT id, block_number FROM meta ORDER BY id")
+            .fetch_all(pool)
+            .await?,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_sql() {
+        let members = ComparedTable::find("members").unwrap();
+
+        assert_eq!(
+            members.key_array(),
+            "ARRAY[t.address::text, t.space_id::text]"
+        );
+        assert_eq!(members.key_list(), "t.address, t.space_id");
+        assert_eq!(members.key_params(2), "($2::text, $3::uuid)");
+        assert_eq!(ComparedTable::find("ipfs_cache"), None);
+    }
+}
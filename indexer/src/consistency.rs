@@ -0,0 +1,237 @@
+//! Checks the knowledge graph tables for rows that reference missing rows.
+//!
+//! Foreign key triggers are disabled so blocks can be written in batches,
+//! so nothing stops a bug or a partial write from leaving, say, a relation
+//! to an entity that was never written. [`ConsistencyChecker`] finds those
+//! rows, and can delete the ones that are safe to delete.
+
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::info;
+
+use crate::error::IndexingError;
+
+pub const DEFAULT_SAMPLE_SIZE: i64 = 20;
+
+/// A kind of inconsistency, with the rows it's found in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// Relations from or to an entity that doesn't exist.
+    DanglingRelation,
+    /// Values of a property that doesn't exist.
+    UnknownValueProperty,
+    /// Proposals counted for a space that doesn't exist.
+    UnknownProposalSpace,
+    /// Members of a space that doesn't exist.
+    UnknownMemberSpace,
+    /// Editors of a space that doesn't exist.
+    UnknownEditorSpace,
+}
+
+impl IssueKind {
+    pub const ALL: [IssueKind; 5] = [
+        IssueKind::DanglingRelation,
+        IssueKind::UnknownValueProperty,
+        IssueKind::UnknownProposalSpace,
+        IssueKind::UnknownMemberSpace,
+        IssueKind::UnknownEditorSpace,
+    ];
+
+    /// The rows with the issue, and the expression identifying each of them
+    /// in the report.
+    fn source(&self) -> (&'static str, &'static str) {
+        match self {
+            IssueKind::DanglingRelation => (
+                "relations r
+                WHERE NOT EXISTS (SELECT 1 FROM entities e WHERE e.id = r.from_entity_id)
+                    OR NOT EXISTS (SELECT 1 FROM entities e WHERE e.id = r.to_entity_id)",
+                "r.id::text",
+            ),
+            IssueKind::UnknownValueProperty => (
+                "values v
+                WHERE NOT EXISTS (SELECT 1 FROM properties p WHERE p.id = v.property_id)",
+                "v.id",
+            ),
+            IssueKind::UnknownProposalSpace => (
+                "counted_proposals c
+                WHERE NOT EXISTS (SELECT 1 FROM spaces s WHERE s.id = c.space_id)",
+                "c.space_id || '/' || c.proposal_id || '/' || c.event",
+            ),
+            IssueKind::UnknownMemberSpace => (
+                "members m
+                WHERE NOT EXISTS (SELECT 1 FROM spaces s WHERE s.id = m.space_id)",
+                "m.space_id || '/' || m.address",
+            ),
+            IssueKind::UnknownEditorSpace => (
+                "editors m
+                WHERE NOT EXISTS (SELECT 1 FROM spaces s WHERE s.id = m.space_id)",
+                "m.space_id || '/' || m.address",
+            ),
+        }
+    }
+
+    /// The statement that deletes the rows with the issue, if they can be
+    /// deleted. Counted proposals are kept, as deleting them would count
+    /// the proposals again if they're replayed.
+    fn fix(&self) -> Option<&'static str> {
+        match self {
+            IssueKind::DanglingRelation => Some(
+                "DELETE FROM relations r
+                WHERE NOT EXISTS (SELECT 1 FROM entities e WHERE e.id = r.from_entity_id)
+                    OR NOT EXISTS (SELECT 1 FROM entities e WHERE e.id = r.to_entity_id)",
+            ),
+            IssueKind::UnknownValueProperty => Some(
+                "DELETE FROM values v
+                WHERE NOT EXISTS (SELECT 1 FROM properties p WHERE p.id = v.property_id)",
+            ),
+            IssueKind::UnknownProposalSpace => None,
+            IssueKind::UnknownMemberSpace => Some(
+                "DELETE FROM members m
+                WHERE NOT EXISTS (SELECT 1 FROM spaces s WHERE s.id = m.space_id)",
+            ),
+            IssueKind::UnknownEditorSpace => Some(
+                "DELETE FROM editors m
+                WHERE NOT EXISTS (SELECT 1 FROM spaces s WHERE s.id = m.space_id)",
+            ),
+        }
+    }
+}
+
+/// The result of one check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CheckResult {
+    pub kind: IssueKind,
+    pub count: i64,
+    /// The first rows with the issue, by identifier.
+    pub sample: Vec<String>,
+    pub fixable: bool,
+    /// The number of rows deleted, if fixes were applied.
+    pub fixed: Option<u64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ConsistencyReport {
+    pub checks: Vec<CheckResult>,
+}
+
+impl ConsistencyReport {
+    /// Whether any issue is left after the fixes that were applied.
+    pub fn has_issues(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.count > check.fixed.unwrap_or(0) as i64)
+    }
+}
+
+pub struct ConsistencyChecker {
+    pool: PgPool,
+    sample_size: i64,
+}
+
+impl ConsistencyChecker {
+    pub fn new(pool: PgPool, sample_size: i64) -> Self {
+        ConsistencyChecker { pool, sample_size }
+    }
+
+    /// Runs every check. If `fix` is set, fixable issues are deleted in a
+    /// single transaction once they're counted.
+    pub async fn run(&self, fix: bool) -> Result<ConsistencyReport, IndexingError> {
+        let mut tx = self.pool.begin().await?;
+        let mut report = ConsistencyReport::default();
+
+        for kind in IssueKind::ALL {
+            let (source, item) = kind.source();
+
+            let count: i64 = sqlx::query_scalar(&format!("SELECT count(*) FROM {}", source))
+                .fetch_one(&mut *tx)
+                .await?;
+            let sample: Vec<String> = if count > 0 {
+                sqlx::query_scalar(&format!(
+                    "SELECT {} FROM {} ORDER BY 1 LIMIT $1",
+                    item, source
+                ))
+                .bind(self.sample_size)
+                .fetch_all(&mut *tx)
+                .await?
+            } else {
+                Vec::new()
+            };
+
+            let fixed = match kind.fix() {
+                Some(statement) if fix => Some(
+                    sqlx::query(statement)
+                        .execute(&mut *tx)
+                        .await?
+                        .rows_affected(),
+                ),
+                _ => None,
+            };
+
+            info!(kind = ?kind, count = count, fixed = ?fixed, "Checked consistency");
+
+            report.checks.push(CheckResult {
+                kind,
+                count,
+                sample,
+                fixable: kind.fix().is_some(),
+                fixed,
+            });
+        }
+
+        tx.commit().await?;
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(kind: IssueKind, count: i64, fixed: Option<u64>) -> CheckResult {
+        CheckResult {
+            kind,
+            count,
+            sample: vec![],
+            fixable: kind.fix().is_some(),
+            fixed,
+        }
+    }
+
+    #[test]
+    fn test_report_has_issues() {
+        let mut report = ConsistencyReport {
+            checks: vec![
+                check(IssueKind::DanglingRelation, 2, Some(2)),
+                check(IssueKind::UnknownMemberSpace, 0, None),
+            ],
+        };
+        assert!(!report.has_issues());
+
+        report
+            .checks
+            .push(check(IssueKind::UnknownProposalSpace, 1, None));
+        assert!(report.has_issues());
+    }
+
+    #[test]
+    fn test_report_serialization() {
+        let report = ConsistencyReport {
+            checks: vec![check(IssueKind::UnknownValueProperty, 1, None)],
+        };
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "checks": [{
+                    "kind": "unknown_value_property",
+                    "count": 1,
+                    "sample": [],
+                    "fixable": true,
+                    "fixed": null,
+                }]
+            })
+        );
+    }
+}
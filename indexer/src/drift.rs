@@ -0,0 +1,150 @@
+//! Tracks how far the indexer is behind the chain head.
+//!
+//! The drift of each block is measured from its timestamp when it's
+//! processed. It's logged as `drift_secs` with every block, and the
+//! [`DriftMonitor`] warns when it rises above the configured alert threshold.
+//! Above the throttle threshold, the indexer defers work that isn't needed
+//! to write blocks, like the search sync and the mirror, until it catches
+//! up.
+
+use std::{
+    sync::atomic::{AtomicBool, AtomicU64, Ordering},
+    time::Duration,
+};
+
+use indexer_utils::config::DriftConfig;
+use stream::utils::{self, BlockMetadata};
+use tracing::{info, warn};
+
+pub struct DriftMonitor {
+    alert_after: Option<Duration>,
+    throttle_after: Option<Duration>,
+    /// The drift of the latest block, in milliseconds.
+    drift_ms: AtomicU64,
+    alerting: AtomicBool,
+    throttled: AtomicBool,
+}
+
+impl DriftMonitor {
+    pub fn new(alert_after: Option<Duration>, throttle_after: Option<Duration>) -> Self {
+        DriftMonitor {
+            alert_after,
+            throttle_after,
+            drift_ms: AtomicU64::new(0),
+            alerting: AtomicBool::new(false),
+            throttled: AtomicBool::new(false),
+        }
+    }
+
+    pub fn from_config(config: &DriftConfig) -> Self {
+        DriftMonitor::new(config.alert_after(), config.throttle_after())
+    }
+
+    /// Records the drift of the block being processed.
+    pub fn record(&self, block: &BlockMetadata) {
+        // Blocks timestamped ahead of the local clock have no drift
+        self.record_drift(utils::drift(block).to_std().unwrap_or_default());
+    }
+
+    /// Records the drift, and logs when it crosses a threshold.
+    pub fn record_drift(&self, drift: Duration) {
+        self.drift_ms
+            .store(drift.as_millis() as u64, Ordering::Relaxed);
+
+        let drift_secs = drift.as_secs();
+        if let Some(alert_after) = self.alert_after {
+            let alerting = drift > alert_after;
+            if alerting != self.alerting.swap(alerting, Ordering::Relaxed) {
+                if alerting {
+                    warn!(
+                        drift_secs = drift_secs,
+                        alert_secs = alert_after.as_secs(),
+                        "Indexer is falling behind the chain head"
+                    );
+                } else {
+                    info!(
+                        drift_secs = drift_secs,
+                        "Indexer caught up with the chain head"
+                    );
+                }
+            }
+        }
+
+        if let Some(throttle_after) = self.throttle_after {
+            let throttled = drift > throttle_after;
+            if throttled != self.throttled.swap(throttled, Ordering::Relaxed) {
+                if throttled {
+                    info!(
+                        drift_secs = drift_secs,
+                        throttle_secs = throttle_after.as_secs(),
+                        "Deferring the search sync and the mirror until the indexer catches up"
+                    );
+                } else {
+                    info!(
+                        drift_secs = drift_secs,
+                        "Catching up the deferred search sync and mirror"
+                    );
+                }
+            }
+        }
+    }
+
+    /// The drift of the latest block.
+    pub fn drift(&self) -> Duration {
+        Duration::from_millis(self.drift_ms.load(Ordering::Relaxed))
+    }
+
+    /// Whether the latest block's drift is above the throttle threshold.
+    pub fn is_throttled(&self) -> bool {
+        self.throttled.load(Ordering::Relaxed)
+    }
+
+    /// Whether the latest block's drift is above the alert threshold.
+    pub fn is_alerting(&self) -> bool {
+        self.alerting.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds() {
+        let monitor = DriftMonitor::new(
+            Some(Duration::from_secs(60)),
+            Some(Duration::from_secs(3600)),
+        );
+
+        monitor.record_drift(Duration::from_secs(30));
+        assert_eq!(monitor.drift(), Duration::from_secs(30));
+        assert!(!monitor.is_alerting());
+        assert!(!monitor.is_throttled());
+
+        monitor.record_drift(Duration::from_secs(600));
+        assert!(monitor.is_alerting());
+        assert!(!monitor.is_throttled());
+
+        monitor.record_drift(Duration::from_secs(7200));
+        assert!(monitor.is_throttled());
+
+        monitor.record_drift(Duration::from_millis(1500));
+        assert_eq!(monitor.drift(), Duration::from_millis(1500));
+        assert!(!monitor.is_alerting());
+        assert!(!monitor.is_throttled());
+    }
+
+    #[test]
+    fn test_disabled_thresholds() {
+        let monitor = DriftMonitor::from_config(&DriftConfig::default());
+
+        monitor.record(&BlockMetadata {
+            cursor: String::new(),
+            block_number: 1,
+            timestamp: "0".to_string(),
+        });
+        assert!(monitor.drift() > Duration::from_secs(3600));
+        assert!(!monitor.is_alerting());
+        assert!(!monitor.is_throttled());
+    }
+}
@@ -1,8 +1,18 @@
+use indexer_utils::{config::ConfigError, decoded::DecodeError as BlockDecodeError};
 use prost::DecodeError;
+use stream::cursor::CursorStoreError;
 use thiserror::Error;
 use tokio::task::JoinError;
 
-use crate::{cache::CacheError, storage::StorageError};
+use crate::{
+    archive::ArchiveError,
+    backup::BackupError,
+    cache::{shared_properties_cache::PropertyStoreError, CacheError},
+    mirror::MirrorError,
+    notifications::NotificationError,
+    search::SearchError,
+    storage::StorageError,
+};
 
 #[derive(Error, Debug)]
 pub enum IndexingError {
@@ -15,9 +25,36 @@ pub enum IndexingError {
     #[error("Indexing error: {0}")]
     DecodeError(#[from] DecodeError),
 
+    #[error("Indexing error: {0}")]
+    BlockDecodeError(#[from] BlockDecodeError),
+
     #[error("Indexing error: {0}")]
     TaskError(#[from] JoinError),
 
     #[error("Indexing error: {0}")]
     SqlxError(#[from] sqlx::Error),
+
+    #[error("Indexing error: {0}")]
+    CursorError(#[from] CursorStoreError),
+
+    #[error("Indexing error: {0}")]
+    ConfigError(#[from] ConfigError),
+
+    #[error("Indexing error: {0}")]
+    PropertyStoreError(#[from] PropertyStoreError),
+
+    #[error("Indexing error: {0}")]
+    NotificationError(#[from] NotificationError),
+
+    #[error("Indexing error: {0}")]
+    SearchError(#[from] SearchError),
+
+    #[error("Indexing error: {0}")]
+    BackupError(#[from] BackupError),
+
+    #[error("Indexing error: {0}")]
+    ArchiveError(#[from] ArchiveError),
+
+    #[error("Indexing error: {0}")]
+    MirrorError(#[from] MirrorError),
 }
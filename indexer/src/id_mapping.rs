@@ -0,0 +1,138 @@
+//! Maps the derived ids of a database to the ids another id scheme derives.
+//!
+//! Switching a deployment to another [`IdScheme`] means reindexing it into a
+//! new database, since every space and value id changes. [`IdMapper`] writes
+//! the id each space and value will have with the new scheme to
+//! `id_mappings`, so clients and other databases referring to the old ids
+//! can be migrated to the new ones.
+
+use std::collections::HashMap;
+
+use indexer_utils::id::IdScheme;
+use serde::Serialize;
+use sqlx::PgPool;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::error::IndexingError;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct IdMappingReport {
+    pub scheme: IdScheme,
+    pub spaces: u64,
+    pub values: u64,
+    /// The values of spaces that don't exist, which can't be mapped.
+    pub skipped_values: u64,
+}
+
+pub struct IdMapper {
+    pool: PgPool,
+    batch_size: i64,
+}
+
+impl IdMapper {
+    pub fn new(pool: PgPool, batch_size: i64) -> Self {
+        IdMapper { pool, batch_size }
+    }
+
+    /// Maps every space and value id to the id the scheme derives for it.
+    /// Mappings written by an earlier run are replaced.
+    pub async fn run(&self, scheme: IdScheme) -> Result<IdMappingReport, IndexingError> {
+        let space_ids = self.map_spaces(scheme).await?;
+        let mut report = IdMappingReport {
+            scheme,
+            spaces: space_ids.len() as u64,
+            values: 0,
+            skipped_values: 0,
+        };
+        info!(scheme = %scheme, spaces = report.spaces, "Mapped space ids");
+
+        let mut after = (String::new(), Uuid::nil());
+        loop {
+            let values: Vec<(String, Uuid, Uuid, Uuid)> = sqlx::query_as(
+                r#"
+                    SELECT id, entity_id, property_id, space_id
+                    FROM values
+                    WHERE (id, space_id) > ($1, $2)
+                    ORDER BY id, space_id
+                    LIMIT $3
+                "#,
+            )
+            .bind(&after.0)
+            .bind(after.1)
+            .bind(self.batch_size)
+            .fetch_all(&self.pool)
+            .await?;
+            let Some((id, _, _, space_id)) = values.last() else {
+                break;
+            };
+            after = (id.clone(), *space_id);
+
+            let mut old_ids = Vec::with_capacity(values.len());
+            let mut new_ids = Vec::with_capacity(values.len());
+            for (id, entity_id, property_id, space_id) in values {
+                let Some(new_space_id) = space_ids.get(&space_id) else {
+                    report.skipped_values += 1;
+                    continue;
+                };
+                new_ids.push(scheme.value_id(&entity_id, &property_id, new_space_id));
+                old_ids.push(id);
+            }
+
+            report.values += self.write("value", scheme, &old_ids, &new_ids).await?;
+            info!(
+                values = report.values,
+                skipped = report.skipped_values,
+                "Mapped value ids"
+            );
+        }
+
+        Ok(report)
+    }
+
+    /// Maps the space ids, and returns the new id of each space.
+    async fn map_spaces(&self, scheme: IdScheme) -> Result<HashMap<Uuid, Uuid>, IndexingError> {
+        let spaces: Vec<(Uuid, String, String)> =
+            sqlx::query_as("SELECT id, network, dao_address FROM spaces")
+                .fetch_all(&self.pool)
+                .await?;
+
+        let space_ids: HashMap<Uuid, Uuid> = spaces
+            .iter()
+            .map(|(id, network, dao_address)| (*id, scheme.space_id(network, dao_address)))
+            .collect();
+
+        for chunk in spaces.chunks(self.batch_size as usize) {
+            let old_ids: Vec<String> = chunk.iter().map(|(id, ..)| id.to_string()).collect();
+            let new_ids: Vec<Uuid> = chunk.iter().map(|(id, ..)| space_ids[id]).collect();
+            self.write("space", scheme, &old_ids, &new_ids).await?;
+        }
+
+        Ok(space_ids)
+    }
+
+    async fn write(
+        &self,
+        kind: &str,
+        scheme: IdScheme,
+        old_ids: &[String],
+        new_ids: &[Uuid],
+    ) -> Result<u64, IndexingError> {
+        let result = sqlx::query(
+            r#"
+                INSERT INTO id_mappings (kind, scheme, old_id, new_id)
+                SELECT $1, $2, old_id, new_id
+                FROM UNNEST($3::text[], $4::uuid[]) AS m(old_id, new_id)
+                ON CONFLICT (kind, scheme, old_id) DO UPDATE SET new_id = EXCLUDED.new_id
+            "#,
+        )
+        .bind(kind)
+        .bind(scheme.as_str())
+        .bind(old_ids)
+        .bind(new_ids)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -1,13 +1,27 @@
 use cache::PreprocessedEdit;
+use indexer_utils::network_ids::Network;
+use models::{properties::PropertyItem, proposals::ProposalPreview};
 use stream::utils::BlockMetadata;
 
+pub mod archive;
+pub mod backup;
 pub mod block_handler;
 pub mod cache;
+pub mod comparison;
+pub mod consistency;
+pub mod drift;
 pub mod error;
+pub mod id_mapping;
+pub mod mirror;
 pub mod models;
+pub mod moderation;
+pub mod notifications;
 pub mod preprocess;
+pub mod search;
+pub mod sharding;
 pub mod storage;
 pub mod validators;
+pub mod verification;
 
 pub mod test_utils;
 
@@ -16,6 +30,12 @@ pub struct PersonalSpace {
     pub dao_address: String,
     pub space_address: String,
     pub personal_plugin: String,
+    /// The address that sent the transaction creating the space, if the
+    /// stream includes it.
+    pub creator: Option<String>,
+    /// The hash of the transaction creating the space, if the stream
+    /// includes it.
+    pub tx_hash: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -24,6 +44,12 @@ pub struct PublicSpace {
     pub space_address: String,
     pub membership_plugin: String,
     pub governance_plugin: String,
+    /// The address that sent the transaction creating the space, if the
+    /// stream includes it.
+    pub creator: Option<String>,
+    /// The hash of the transaction creating the space, if the stream
+    /// includes it.
+    pub tx_hash: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -56,6 +82,84 @@ pub struct RemovedSubspace {
     pub subspace_address: String,
 }
 
+/// The voting and membership plugins installed in a public space, including
+/// the space's first ones when it's created. Later plugins replace the
+/// space's governance, while proposals of the earlier ones still belong to
+/// the space.
+#[derive(Clone, Debug)]
+pub struct ChangedGovernancePlugins {
+    pub dao_address: String,
+    pub main_voting_plugin: String,
+    pub membership_plugin: String,
+}
+
+/// An admin plugin installed in a personal space, including the space's
+/// first one when it's created. A later plugin replaces the space's admin.
+#[derive(Clone, Debug)]
+pub struct ChangedPersonalAdmin {
+    pub dao_address: String,
+    pub personal_plugin: String,
+    /// The admin the plugin was installed for, if the event names one.
+    pub admin: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalType {
+    PublishEdit,
+    AddMember,
+    RemoveMember,
+    AddEditor,
+    RemoveEditor,
+    AddSubspace,
+    RemoveSubspace,
+}
+
+impl ProposalType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalType::PublishEdit => "publish_edit",
+            ProposalType::AddMember => "add_member",
+            ProposalType::RemoveMember => "remove_member",
+            ProposalType::AddEditor => "add_editor",
+            ProposalType::RemoveEditor => "remove_editor",
+            ProposalType::AddSubspace => "add_subspace",
+            ProposalType::RemoveSubspace => "remove_subspace",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CreatedProposal {
+    pub proposal_id: String,
+    pub proposal_type: ProposalType,
+    pub creator: String,
+    pub dao_address: String,
+    pub plugin_address: String,
+    /// What a proposal to publish an edit changes, if its content was
+    /// cached by the time it was created.
+    pub preview: Option<ProposalPreview>,
+}
+
+/// Executed proposals only name the plugin that executed them, not their
+/// DAO.
+#[derive(Clone, Debug)]
+pub struct ExecutedProposal {
+    pub proposal_id: String,
+    pub plugin_address: String,
+    /// The hash of the transaction that executed the proposal.
+    pub tx_hash: String,
+}
+
+/// Proposals withdrawn by their creator or vetoed by an editor. Like
+/// executed proposals, they only name their plugin.
+#[derive(Clone, Debug)]
+pub struct CanceledProposal {
+    pub proposal_id: String,
+    pub plugin_address: String,
+    pub canceled_by: String,
+}
+
 #[derive(Clone, Debug)]
 pub struct KgData {
     pub block: BlockMetadata,
@@ -66,7 +170,16 @@ pub struct KgData {
     pub removed_members: Vec<RemovedMember>,
     pub added_subspaces: Vec<AddedSubspace>,
     pub removed_subspaces: Vec<RemovedSubspace>,
+    pub personal_admin_changes: Vec<ChangedPersonalAdmin>,
+    pub governance_plugin_changes: Vec<ChangedGovernancePlugins>,
+    pub created_proposals: Vec<CreatedProposal>,
+    pub executed_proposals: Vec<ExecutedProposal>,
+    pub canceled_proposals: Vec<CanceledProposal>,
     // Note for now that we only need the dao address. Eventually we'll
     // index the plugin addresses as well.
     pub spaces: Vec<CreatedSpace>,
+    pub network: Network,
+    /// The properties created by edits in spaces owned by other shards. They
+    /// aren't written, but are cached so this shard's values can use them.
+    pub unowned_properties: Vec<PropertyItem>,
 }
@@ -1,27 +1,63 @@
 use indexer::{
+    archive::BlockArchive,
     block_handler::root_handler,
-    cache::{postgres::PostgresCache, properties_cache::PropertiesCache},
+    cache::{
+        postgres::PostgresCache,
+        properties_cache::{ImmutableCache, PropertiesCache},
+        shared_properties_cache::{RedisPropertyStore, SharedPropertiesCache},
+    },
+    drift::DriftMonitor,
     error::IndexingError,
+    mirror::MirrorSync,
+    moderation::Moderator,
+    notifications::dispatcher::WebhookDispatcher,
     preprocess,
+    search::SearchSync,
+    sharding::{Shard, ShardLease},
     storage::postgres::PostgresStorage,
+    verification::{AuthorVerifier, EditorPermissions},
     KgData,
 };
+use indexer_utils::{
+    config::{Config, ConfigError, LimitsConfig},
+    id,
+    network_ids::Network,
+};
 use std::{env, sync::Arc};
 
 use axiom_rs::Client as AxiomClient;
 use dotenv::dotenv;
-use stream::{pb::sf::substreams::rpc::v2::BlockScopedData, PreprocessedSink};
+use stream::{
+    cursor::{CursorStore, PostgresCursorStore},
+    pb::sf::substreams::rpc::v2::BlockScopedData,
+    recorder::{load_archive, open_store},
+    test_source::replay_preprocessed,
+    PreprocessedSink,
+};
 use tracing::{error, info, instrument};
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
 const PKG_FILE: &str = "geo_substream.spkg";
 const MODULE_NAME: &str = "geo_out";
-const START_BLOCK: i64 = 67162;
 
-struct KgIndexer {
+struct KgIndexer<C> {
     storage: Arc<PostgresStorage>,
     ipfs_cache: Arc<PostgresCache>,
-    properties_cache: Arc<PropertiesCache>,
+    properties_cache: Arc<C>,
+    cursor_store: PostgresCursorStore,
+    cursor_id: String,
+    network: Network,
+    limits: LimitsConfig,
+    shard: Option<Shard>,
+    api_token: Option<String>,
+    notifier: Option<Arc<WebhookDispatcher>>,
+    search: Option<SearchSync>,
+    mirror: Option<MirrorSync>,
+    archive: Option<BlockArchive>,
+    verifier: Option<AuthorVerifier>,
+    editor_permissions: Option<EditorPermissions>,
+    moderator: Option<Moderator>,
+    drift: DriftMonitor,
 }
 
 use serde_json::{json, Value};
@@ -133,27 +169,64 @@ impl tracing::field::Visit for JsonVisitor {
     }
 }
 
-impl KgIndexer {
+impl<C> KgIndexer<C> {
     pub fn new(
-        storage: PostgresStorage,
+        storage: Arc<PostgresStorage>,
         ipfs_cache: PostgresCache,
-        properties_cache: PropertiesCache,
+        properties_cache: Arc<C>,
+        notifier: Option<Arc<WebhookDispatcher>>,
+        search: Option<SearchSync>,
+        archive: Option<BlockArchive>,
+        config: &Config,
     ) -> Self {
+        let cursor_store = PostgresCursorStore::new(storage.pool.clone());
+        let verifier = AuthorVerifier::from_config(storage.pool.clone(), &config.verification);
+        let editor_permissions =
+            EditorPermissions::from_config(storage.pool.clone(), &config.verification);
+        let moderator = Moderator::from_config(&config.moderation);
+
         KgIndexer {
-            storage: Arc::new(storage),
+            cursor_store,
+            cursor_id: cursor_id(config),
+            storage,
             ipfs_cache: Arc::new(ipfs_cache),
-            properties_cache: Arc::new(properties_cache),
+            properties_cache,
+            network: config.network(),
+            limits: config.limits.clone(),
+            shard: Shard::from_config(&config.sharding),
+            api_token: config.substreams.api_token.clone(),
+            notifier,
+            search,
+            mirror: None,
+            archive,
+            verifier,
+            editor_permissions,
+            moderator,
+            drift: DriftMonitor::from_config(&config.drift),
         }
     }
+
+    /// Mirrors each block into an analytical database after it's written.
+    pub fn with_mirror(mut self, mirror: Option<MirrorSync>) -> Self {
+        self.mirror = mirror;
+        self
+    }
 }
 
-impl PreprocessedSink<KgData> for KgIndexer {
+impl<C> PreprocessedSink<KgData> for KgIndexer<C>
+where
+    C: ImmutableCache + Send + Sync + 'static,
+{
     type Error = IndexingError;
 
+    fn api_token(&self) -> Option<String> {
+        self.api_token.clone()
+    }
+
     #[instrument(skip(self), name = "load_cursor")]
     async fn load_persisted_cursor(&self) -> Result<Option<String>, Self::Error> {
-        self.storage
-            .load_cursor("kg_indexer")
+        self.cursor_store
+            .load(&self.cursor_id)
             .await
             .map_err(IndexingError::from)
     }
@@ -161,8 +234,8 @@ impl PreprocessedSink<KgData> for KgIndexer {
     #[instrument(skip(self), fields(block = block))]
     async fn persist_cursor(&self, cursor: String, block: u64) -> Result<(), Self::Error> {
         info!(cursor = %cursor, block = block, "Persisting cursor");
-        self.storage
-            .persist_cursor("kg_indexer", &cursor, &block)
+        self.cursor_store
+            .persist(&self.cursor_id, &cursor, block)
             .await
             .map_err(IndexingError::from)
     }
@@ -178,10 +251,19 @@ impl PreprocessedSink<KgData> for KgIndexer {
         &self,
         block_data: &BlockScopedData,
     ) -> Result<KgData, Self::Error> {
-        let kg_data =
-            preprocess::preprocess_block_scoped_data(block_data, &self.ipfs_cache).await?;
+        let kg_data = preprocess::preprocess_block_scoped_data(
+            block_data,
+            &self.ipfs_cache,
+            &self.network,
+            &self.limits,
+        )
+        .await?;
 
-        Ok(kg_data)
+        // Spaces this shard doesn't own are indexed by other shards.
+        match &self.shard {
+            Some(shard) => Ok(shard.filter(kg_data)),
+            None => Ok(kg_data),
+        }
     }
 
     #[instrument(skip_all, fields(
@@ -192,9 +274,17 @@ impl PreprocessedSink<KgData> for KgIndexer {
     ))]
     async fn process_block_scoped_data(
         &self,
-        _block_data: &BlockScopedData,
+        block_data: &BlockScopedData,
         decoded_data: KgData,
     ) -> Result<(), Self::Error> {
+        self.drift.record(&decoded_data.block);
+
+        // Archived before anything is written, so every block the database
+        // reflects can be replayed from the archive.
+        if let Some(archive) = &self.archive {
+            archive.archive(block_data).await?;
+        }
+
         info!(
             edit_count = decoded_data.edits.len(),
             space_count = decoded_data.spaces.len(),
@@ -210,13 +300,67 @@ impl PreprocessedSink<KgData> for KgIndexer {
         // of each event.
         //
         // async fn process_block(&self, block_data: &DecodedBlockData, _raw_block_data: &BlockScopedData);
-        root_handler::run(
+        //
+        // Edits are verified here rather than while preprocessing, so editors
+        // written by earlier blocks are already in the database.
+        let decoded_data = match &self.verifier {
+            Some(verifier) => verifier.verify(decoded_data).await?,
+            None => decoded_data,
+        };
+        let decoded_data = match &self.editor_permissions {
+            Some(editor_permissions) => editor_permissions.enforce(decoded_data).await?,
+            None => decoded_data,
+        };
+        let decoded_data = match &self.moderator {
+            Some(moderator) => moderator.moderate(decoded_data).await?,
+            None => decoded_data,
+        };
+
+        // A block streamed again, such as after restarting from an older
+        // cursor, was already written and notified.
+        let processed = root_handler::run_once(
             &decoded_data,
             &decoded_data.block,
             &self.storage,
             &self.properties_cache,
+            &self.cursor_id,
         )
         .await?;
+        if !processed {
+            return Ok(());
+        }
+
+        // Deliveries run in the background and a failure to schedule them
+        // doesn't fail the block, which is already committed.
+        if let Some(notifier) = &self.notifier {
+            if let Err(error) = notifier.notify(&decoded_data).await {
+                error!(error = %error, "Failed to send webhook notifications");
+            }
+        }
+
+        // While the indexer is far behind the chain head, the search sync
+        // and the mirror are deferred to the first block after it catches up.
+        let throttled = self.drift.is_throttled();
+
+        // The search index catches up the next time the entities change or
+        // it's rebuilt, so a failed sync doesn't fail the block either.
+        if let Some(search) = &self.search {
+            if throttled {
+                search.defer_block(&decoded_data);
+            } else if let Err(error) = search.sync_block(&decoded_data).await {
+                error!(error = %error, "Failed to sync the search index");
+            }
+        }
+
+        // Rows of a failed block are mirrored again the next time they
+        // change, so the mirror doesn't fail the block either.
+        if let Some(mirror) = &self.mirror {
+            if throttled {
+                mirror.defer_block(&decoded_data);
+            } else if let Err(error) = mirror.mirror_block(&decoded_data).await {
+                error!(error = %error, "Failed to mirror the block");
+            }
+        }
 
         Ok(())
     }
@@ -229,30 +373,121 @@ async fn main() -> Result<(), IndexingError> {
     // Initialize tracing
     init_tracing()?;
 
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
-    let storage = PostgresStorage::new(&database_url).await;
+    let config = match load_config() {
+        Ok(config) => config,
+        Err(error) => {
+            error!(error = %error, "Invalid configuration");
+            flush_axiom_logs().await;
+            std::process::exit(1);
+        }
+    };
+    id::select_scheme(config.network.id_scheme);
+    let database_url = config.database_url()?;
+
+    let storage = PostgresStorage::connect(database_url, &config.database).await;
 
     match storage {
         Ok(result) => {
-            let cache = PostgresCache::new().await?;
-            let properties_cache = PropertiesCache::from_storage(&result).await?;
+            if config.database.run_migrations {
+                info!("Running database migrations");
+                result.migrate().await?;
+            }
+            result.check_id_scheme(config.network.id_scheme).await?;
+            if let Some(count) = config.database.value_partitions {
+                result.partition_values(count).await?;
+            }
 
-            let indexer = KgIndexer::new(result, cache, properties_cache);
+            // Stands by until no other instance indexes this shard.
+            let lease = match Shard::from_config(&config.sharding) {
+                Some(shard) => {
+                    info!(
+                        shard = shard.index,
+                        shard_count = shard.count,
+                        "Indexing shard"
+                    );
+                    let interval = config.sharding.lease_retry_interval();
+                    Some(ShardLease::acquire(&result.pool, &cursor_id(&config), interval).await?)
+                }
+                None => None,
+            };
+
+            let cache = PostgresCache::new(database_url, &config.database).await?;
+            let properties_cache = Arc::new(
+                PropertiesCache::from_storage(&result)
+                    .await?
+                    .with_max_age(config.properties_cache.max_age()),
+            );
+            let storage = Arc::new(result);
+
+            let notifier = if config.notifications.enabled {
+                info!("Sending webhook notifications");
+                Some(Arc::new(WebhookDispatcher::new(
+                    storage.pool.clone(),
+                    config.notifications.clone(),
+                    Shard::from_config(&config.sharding),
+                )?))
+            } else {
+                None
+            };
+
+            let search = SearchSync::from_config(storage.pool.clone(), &config.search)?;
+            if let Some(search) = &search {
+                info!(engine = ?config.search.engine, "Syncing entities to the search index");
+
+                if config.search.reindex {
+                    let written = search.reindex().await?;
+                    info!(documents = written, "Rebuilt the search index");
+                }
+            }
+
+            let mirror = MirrorSync::from_config(storage.pool.clone(), &config.mirror)?;
+            if let Some(mirror) = &mirror {
+                mirror.create_tables().await?;
+                info!(database = %config.mirror.database, "Mirroring blocks to ClickHouse");
+            }
 
-            let endpoint_url =
-                env::var("SUBSTREAMS_ENDPOINT").expect("SUBSTREAMS_ENDPOINT not set");
+            let archive = BlockArchive::from_config(&config.archive)?;
+            if archive.is_some() {
+                info!("Archiving block outputs");
+            }
 
-            info!(
-                endpoint = %endpoint_url,
-                package = PKG_FILE,
-                module = MODULE_NAME,
-                start_block = START_BLOCK,
-                "Starting indexer"
-            );
+            // Picks up properties written by other indexer instances and
+            // reloads invalidated ones, or stale ones with a max age.
+            if let Some(interval) = config.properties_cache.refresh_interval() {
+                properties_cache.spawn_refresh(storage.clone(), interval);
+            }
 
-            let _result = indexer
-                .run(&endpoint_url, PKG_FILE, MODULE_NAME, START_BLOCK, 0)
-                .await;
+            match &config.properties_cache.redis_url {
+                Some(redis_url) => {
+                    info!("Sharing the properties cache through Redis");
+                    let store = RedisPropertyStore::connect(redis_url).await?;
+                    let properties_cache = SharedPropertiesCache::new(properties_cache, store);
+                    let indexer = KgIndexer::new(
+                        storage,
+                        cache,
+                        Arc::new(properties_cache),
+                        notifier,
+                        search,
+                        archive,
+                        &config,
+                    )
+                    .with_mirror(mirror);
+                    start(indexer, &config, lease).await;
+                }
+                None => {
+                    let indexer = KgIndexer::new(
+                        storage,
+                        cache,
+                        properties_cache,
+                        notifier,
+                        search,
+                        archive,
+                        &config,
+                    )
+                    .with_mirror(mirror);
+                    start(indexer, &config, lease).await;
+                }
+            }
         }
         Err(error) => {
             error!("Error initializing stream: {}", error);
@@ -266,6 +501,148 @@ async fn main() -> Result<(), IndexingError> {
     Ok(())
 }
 
+/// Replays the block archive in `REPLAY_ARCHIVE_DIR` or the configured
+/// archive if replaying is enabled, otherwise streams blocks from the
+/// configured substreams endpoint. Exits the process if either fails, or if
+/// the shard lease is lost while streaming so a standby can take over.
+async fn start<C>(mut indexer: KgIndexer<C>, config: &Config, lease: Option<ShardLease>)
+where
+    C: ImmutableCache + Send + Sync + 'static,
+{
+    info!(network = %indexer.network.id, chain_id = ?indexer.network.chain_id, "Indexing network");
+
+    // Replays blocks archived with BLOCK_ARCHIVE_DIR instead of streaming,
+    // to reproduce indexing bugs. Run this against a scratch database as
+    // the cursor is persisted while replaying.
+    if let Ok(archive_dir) = env::var("REPLAY_ARCHIVE_DIR") {
+        info!(archive_dir = %archive_dir, "Replaying block archive");
+
+        let replay_result = match open_store(&archive_dir) {
+            Ok(store) => match load_archive(store.as_ref()).await {
+                Ok(blocks) => replay_preprocessed(&indexer, &blocks).await,
+                Err(error) => Err(error),
+            },
+            Err(error) => Err(error),
+        };
+
+        if let Err(error) = replay_result {
+            error!(error = %error, "Replay failed");
+            flush_axiom_logs().await;
+            std::process::exit(1);
+        }
+
+        flush_axiom_logs().await;
+        info!("Replay complete");
+        return;
+    }
+
+    // Rebuilds the database from the configured archive. Replayed blocks
+    // are already archived, so they aren't archived again.
+    if config.archive.replay {
+        if let Some(archive) = indexer.archive.take() {
+            if let Err(error) = replay_archive(&indexer, &archive).await {
+                error!(error = %error, "Archive replay failed");
+                flush_axiom_logs().await;
+                std::process::exit(1);
+            }
+
+            flush_axiom_logs().await;
+            info!("Archive replay complete");
+            return;
+        }
+    }
+
+    let start_block = config.substreams.start_block;
+    let endpoint_url = match config.substreams_endpoint() {
+        Ok(endpoint_url) => endpoint_url,
+        Err(error) => {
+            error!(error = %error, "Invalid configuration");
+            flush_axiom_logs().await;
+            std::process::exit(1);
+        }
+    };
+
+    info!(
+        endpoint = %endpoint_url,
+        package = PKG_FILE,
+        module = MODULE_NAME,
+        start_block = start_block,
+        "Starting indexer"
+    );
+
+    let lease_lost = async {
+        match lease {
+            Some(lease) => {
+                lease.watch(config.sharding.lease_retry_interval()).await;
+            }
+            None => std::future::pending().await,
+        }
+    };
+
+    tokio::select! {
+        result = indexer.run(endpoint_url, PKG_FILE, MODULE_NAME, start_block, 0) => {
+            if let Err(error) = result {
+                error!(error = %error, "Stream terminated");
+                flush_axiom_logs().await;
+                std::process::exit(1);
+            }
+        }
+        _ = lease_lost => {
+            flush_axiom_logs().await;
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Processes the archived blocks after the block of the persisted cursor,
+/// one at a time so the whole archive is never held in memory.
+async fn replay_archive<C>(
+    indexer: &KgIndexer<C>,
+    archive: &BlockArchive,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>>
+where
+    C: ImmutableCache + Send + Sync + 'static,
+{
+    let indexed_block: Option<String> =
+        sqlx::query_scalar("SELECT block_number FROM meta WHERE id = $1")
+            .bind(&indexer.cursor_id)
+            .fetch_optional(&indexer.storage.pool)
+            .await?;
+    let indexed_block = indexed_block.and_then(|block| block.parse().ok());
+
+    let blocks = archive.blocks_after(indexed_block).await?;
+    info!(
+        from_block = ?indexed_block,
+        blocks = blocks.len(),
+        "Replaying archived blocks"
+    );
+
+    for block_number in blocks {
+        let block = archive.load(block_number).await?;
+        replay_preprocessed(indexer, &[block]).await?;
+    }
+
+    Ok(())
+}
+
+/// The id the indexer persists its cursor under, which also names the lease
+/// of its shard when sharding is enabled.
+fn cursor_id(config: &Config) -> String {
+    match Shard::from_config(&config.sharding) {
+        Some(shard) => config.network().cursor_id(&shard.cursor_id("kg_indexer")),
+        None => config.network().cursor_id("kg_indexer"),
+    }
+}
+
+/// Loads the config and checks the settings every mode needs, so a
+/// misconfigured indexer exits before connecting to anything.
+fn load_config() -> Result<Config, ConfigError> {
+    let config = Config::load()?;
+    config.database_url()?;
+
+    Ok(config)
+}
+
 async fn flush_axiom_logs() {
     let axiom_dataset = env::var("AXIOM_DATASET").unwrap_or_else(|_| "gaia.indexer".to_string());
 
@@ -0,0 +1,278 @@
+use async_trait::async_trait;
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+use super::{
+    check_response, MirrorError, MirrorWriter, MirroredEdit, MirroredRelation, MirroredValue,
+};
+
+/// The mirrored tables. They're `ReplacingMergeTree`s versioned by block
+/// number, so merges keep the latest row with each key and drop the deleted
+/// ones. Queries read the latest rows with `FINAL`.
+const TABLES: [&str; 3] = [
+    r#"
+        CREATE TABLE IF NOT EXISTS `values` (
+            id String,
+            property_id UUID,
+            entity_id UUID,
+            space_id UUID,
+            string Nullable(String),
+            language Nullable(String),
+            unit Nullable(String),
+            boolean Nullable(Bool),
+            number Nullable(Float64),
+            point Nullable(String),
+            time Nullable(String),
+            block_number UInt64,
+            is_deleted UInt8
+        )
+        ENGINE = ReplacingMergeTree(block_number, is_deleted)
+        ORDER BY (space_id, entity_id, id)
+    "#,
+    r#"
+        CREATE TABLE IF NOT EXISTS `relations` (
+            id UUID,
+            entity_id UUID,
+            type_id UUID,
+            space_id UUID,
+            from_entity_id UUID,
+            from_space_id Nullable(UUID),
+            from_version_id Nullable(UUID),
+            to_entity_id UUID,
+            to_space_id Nullable(UUID),
+            to_version_id Nullable(UUID),
+            position Nullable(String),
+            verified Nullable(Bool),
+            block_number UInt64,
+            is_deleted UInt8
+        )
+        ENGINE = ReplacingMergeTree(block_number, is_deleted)
+        ORDER BY (space_id, id)
+    "#,
+    r#"
+        CREATE TABLE IF NOT EXISTS `edits` (
+            space_id UUID,
+            content_hash String,
+            cid String,
+            block_number UInt64,
+            created_at DateTime('UTC')
+        )
+        ENGINE = ReplacingMergeTree(block_number)
+        ORDER BY (space_id, content_hash)
+    "#,
+];
+
+/// Writes to a ClickHouse server over its HTTP interface. Rows are sent and
+/// read as `JSONEachRow`, and values are passed to queries as parameters.
+pub struct ClickHouseWriter {
+    client: reqwest::Client,
+    url: String,
+    database: String,
+    user: Option<String>,
+    password: Option<String>,
+}
+
+impl ClickHouseWriter {
+    pub fn new(
+        url: &str,
+        database: &str,
+        user: Option<String>,
+        password: Option<String>,
+    ) -> Result<Self, MirrorError> {
+        Ok(ClickHouseWriter {
+            client: reqwest::Client::builder().build()?,
+            url: url.trim_end_matches('/').to_string(),
+            database: database.to_string(),
+            user,
+            password,
+        })
+    }
+
+    /// Runs a statement with the query parameters and body, and returns
+    /// the response's body.
+    async fn execute(
+        &self,
+        query: &str,
+        params: &[(&str, String)],
+        body: String,
+    ) -> Result<String, MirrorError> {
+        let mut url_params = vec![
+            ("database".to_string(), self.database.clone()),
+            ("query".to_string(), query.to_string()),
+            // UInt64 columns are read as numbers rather than strings
+            (
+                "output_format_json_quote_64bit_integers".to_string(),
+                "0".to_string(),
+            ),
+        ];
+        url_params.extend(
+            params
+                .iter()
+                .map(|(name, value)| (format!("param_{}", name), value.clone())),
+        );
+
+        let mut request = self.client.post(&self.url).query(&url_params).body(body);
+        if let Some(user) = &self.user {
+            request = request.header("X-ClickHouse-User", user);
+        }
+        if let Some(password) = &self.password {
+            request = request.header("X-ClickHouse-Key", password);
+        }
+
+        Ok(check_response(request.send().await?).await?.text().await?)
+    }
+
+    async fn insert<T: Serialize>(&self, table: &str, rows: &[T]) -> Result<(), MirrorError> {
+        self.execute(
+            &format!("INSERT INTO `{}` FORMAT JSONEachRow", table),
+            &[],
+            to_json_rows(rows)?,
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    async fn select<T: DeserializeOwned>(
+        &self,
+        query: &str,
+        params: &[(&str, String)],
+    ) -> Result<Vec<T>, MirrorError> {
+        let body = self
+            .execute(
+                &format!("{} FORMAT JSONEachRow", query),
+                params,
+                String::new(),
+            )
+            .await?;
+
+        from_json_rows(&body)
+    }
+}
+
+#[async_trait]
+impl MirrorWriter for ClickHouseWriter {
+    async fn create_tables(&self) -> Result<(), MirrorError> {
+        for table in TABLES {
+            self.execute(table, &[], String::new()).await?;
+        }
+
+        Ok(())
+    }
+
+    async fn write_values(&self, values: &[MirroredValue]) -> Result<(), MirrorError> {
+        self.insert("values", values).await
+    }
+
+    async fn write_relations(&self, relations: &[MirroredRelation]) -> Result<(), MirrorError> {
+        self.insert("relations", relations).await
+    }
+
+    async fn write_edits(&self, edits: &[MirroredEdit]) -> Result<(), MirrorError> {
+        self.insert("edits", edits).await
+    }
+
+    async fn values(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<MirroredValue>, MirrorError> {
+        self.select(
+            r#"
+                SELECT * FROM `values` FINAL
+                WHERE space_id = {space_id:UUID}
+                    AND has({entity_ids:Array(UUID)}, entity_id)
+                    AND is_deleted = 0
+            "#,
+            &[
+                ("space_id", space_id.to_string()),
+                ("entity_ids", uuid_array(entity_ids)),
+            ],
+        )
+        .await
+    }
+
+    async fn relations(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+        relation_ids: &[Uuid],
+    ) -> Result<Vec<MirroredRelation>, MirrorError> {
+        self.select(
+            r#"
+                SELECT * FROM `relations` FINAL
+                WHERE space_id = {space_id:UUID}
+                    AND (
+                        has({entity_ids:Array(UUID)}, from_entity_id)
+                        OR has({entity_ids:Array(UUID)}, to_entity_id)
+                        OR has({relation_ids:Array(UUID)}, id)
+                    )
+                    AND is_deleted = 0
+            "#,
+            &[
+                ("space_id", space_id.to_string()),
+                ("entity_ids", uuid_array(entity_ids)),
+                ("relation_ids", uuid_array(relation_ids)),
+            ],
+        )
+        .await
+    }
+}
+
+/// Formats ids as a ClickHouse array literal, for a query parameter.
+fn uuid_array(ids: &[Uuid]) -> String {
+    let ids: Vec<String> = ids.iter().map(|id| format!("'{}'", id)).collect();
+    format!("[{}]", ids.join(","))
+}
+
+fn to_json_rows<T: Serialize>(rows: &[T]) -> Result<String, MirrorError> {
+    let mut body = String::new();
+    for row in rows {
+        body.push_str(&serde_json::to_string(row)?);
+        body.push('\n');
+    }
+
+    Ok(body)
+}
+
+fn from_json_rows<T: DeserializeOwned>(body: &str) -> Result<Vec<T>, MirrorError> {
+    body.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats_uuid_arrays() {
+        let id = Uuid::parse_str("5871e8f7-b719-420f-b7b5-a3e5b4b6e0f1").unwrap();
+
+        assert_eq!(uuid_array(&[]), "[]");
+        assert_eq!(
+            uuid_array(&[id, id]),
+            "['5871e8f7-b719-420f-b7b5-a3e5b4b6e0f1','5871e8f7-b719-420f-b7b5-a3e5b4b6e0f1']"
+        );
+    }
+
+    #[test]
+    fn test_json_rows_round_trip() {
+        let relation = MirroredRelation {
+            id: Uuid::new_v4(),
+            from_space_id: Some(Uuid::new_v4()),
+            position: Some("a0".to_string()),
+            block_number: 42,
+            ..Default::default()
+        };
+
+        let body = to_json_rows(&[relation.clone(), relation.clone()]).unwrap();
+        assert_eq!(body.lines().count(), 2);
+        assert_eq!(
+            from_json_rows::<MirroredRelation>(&body).unwrap(),
+            vec![relation.clone(), relation]
+        );
+        assert!(from_json_rows::<MirroredRelation>("").unwrap().is_empty());
+    }
+}
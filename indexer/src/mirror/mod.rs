@@ -0,0 +1,505 @@
+//! Mirrors values, relations and edits into ClickHouse, so analytical
+//! queries like aggregations across every space don't run against the
+//! operational Postgres database.
+//!
+//! The mirror is written after a block is committed.
+//! [`MirrorSync::mirror_block`] reads the current rows of the entities and
+//! relations the block's edits changed and writes them with the block number
+//! as their version. Rows the mirror has that are gone from Postgres are
+//! written again as deleted. The mirror's tables keep the latest version of
+//! each row. Blocks can be deferred while the indexer catches up, and their
+//! rows are mirrored with the next block that isn't.
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use indexer_utils::{config::MirrorConfig, id};
+use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use thiserror::Error;
+use uuid::Uuid;
+use wire::pb::grc20::{op::Payload, Edit};
+
+use crate::KgData;
+
+pub mod clickhouse;
+
+/// The most ids read from the database and the mirror at once.
+const BATCH_SIZE: usize = 1000;
+
+#[derive(Error, Debug)]
+pub enum MirrorError {
+    #[error("Mirror error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Mirror error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Mirror responded with status {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("Mirror error: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// A row of the values table, as it's mirrored.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MirroredValue {
+    pub id: String,
+    pub property_id: Uuid,
+    pub entity_id: Uuid,
+    pub space_id: Uuid,
+    pub string: Option<String>,
+    pub language: Option<String>,
+    pub unit: Option<String>,
+    pub boolean: Option<bool>,
+    pub number: Option<f64>,
+    pub point: Option<String>,
+    pub time: Option<String>,
+    /// The block the row was written in, which versions it.
+    #[sqlx(skip)]
+    pub block_number: u64,
+    #[sqlx(skip)]
+    pub is_deleted: u8,
+}
+
+/// A row of the relations table, as it's mirrored.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct MirroredRelation {
+    pub id: Uuid,
+    pub entity_id: Uuid,
+    pub type_id: Uuid,
+    pub space_id: Uuid,
+    pub from_entity_id: Uuid,
+    pub from_space_id: Option<Uuid>,
+    pub from_version_id: Option<Uuid>,
+    pub to_entity_id: Uuid,
+    pub to_space_id: Option<Uuid>,
+    pub to_version_id: Option<Uuid>,
+    pub position: Option<String>,
+    pub verified: Option<bool>,
+    #[sqlx(skip)]
+    pub block_number: u64,
+    #[sqlx(skip)]
+    pub is_deleted: u8,
+}
+
+/// An applied edit. Edits are never deleted.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MirroredEdit {
+    pub space_id: Uuid,
+    pub content_hash: String,
+    pub cid: String,
+    pub block_number: u64,
+    /// The timestamp of the block, in seconds.
+    pub created_at: i64,
+}
+
+/// An analytical database the knowledge graph is mirrored into.
+#[async_trait]
+pub trait MirrorWriter: Send + Sync {
+    /// Creates the mirrored tables if they don't exist.
+    async fn create_tables(&self) -> Result<(), MirrorError>;
+
+    /// Adds the rows, which replace the rows with the same id and an older
+    /// block number.
+    async fn write_values(&self, values: &[MirroredValue]) -> Result<(), MirrorError>;
+
+    async fn write_relations(&self, relations: &[MirroredRelation]) -> Result<(), MirrorError>;
+
+    async fn write_edits(&self, edits: &[MirroredEdit]) -> Result<(), MirrorError>;
+
+    /// The mirrored values of the entities in a space that aren't deleted.
+    async fn values(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<MirroredValue>, MirrorError>;
+
+    /// The mirrored relations of a space that aren't deleted and either
+    /// have one of the ids or are from or to one of the entities.
+    async fn relations(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+        relation_ids: &[Uuid],
+    ) -> Result<Vec<MirroredRelation>, MirrorError>;
+}
+
+/// The entities and relations of a space that edits changed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MirrorChanges {
+    pub entity_ids: Vec<Uuid>,
+    pub relation_ids: Vec<Uuid>,
+}
+
+impl MirrorChanges {
+    /// Adds the entities and relations an edit may change, in op order.
+    ///
+    /// Values converted when a property's data type changes aren't picked
+    /// up. They're mirrored the next time their entities change.
+    pub fn add_edit(&mut self, edit: &Edit) {
+        for op in &edit.ops {
+            let (entity_id, relation_id) = match &op.payload {
+                Some(Payload::UpdateEntity(entity)) => (Some(&entity.id), None),
+                Some(Payload::UnsetEntityValues(unset)) => (Some(&unset.id), None),
+                Some(Payload::DeleteEntity(entity)) => (Some(entity), None),
+                Some(Payload::CreateRelation(relation)) => {
+                    (Some(&relation.from_entity), Some(&relation.id))
+                }
+                Some(Payload::UpdateRelation(relation)) => (None, Some(&relation.id)),
+                Some(Payload::UnsetRelationFields(relation)) => (None, Some(&relation.id)),
+                Some(Payload::DeleteRelation(relation)) => (None, Some(relation)),
+                _ => (None, None),
+            };
+
+            if let Some(entity_id) = entity_id.and_then(|id| to_uuid(id)) {
+                if !self.entity_ids.contains(&entity_id) {
+                    self.entity_ids.push(entity_id);
+                }
+            }
+            if let Some(relation_id) = relation_id.and_then(|id| to_uuid(id)) {
+                if !self.relation_ids.contains(&relation_id) {
+                    self.relation_ids.push(relation_id);
+                }
+            }
+        }
+    }
+}
+
+fn to_uuid(bytes: &[u8]) -> Option<Uuid> {
+    id::transform_id_bytes(bytes.to_vec())
+        .ok()
+        .map(Uuid::from_bytes)
+}
+
+/// Returns the changes of a block's applied edits, by space.
+pub fn block_changes(data: &KgData) -> BTreeMap<Uuid, MirrorChanges> {
+    let mut changes: BTreeMap<Uuid, MirrorChanges> = BTreeMap::new();
+
+    for edit in &data.edits {
+        if let Some(decoded) = &edit.edit {
+            changes.entry(edit.space_id).or_default().add_edit(decoded);
+        }
+    }
+
+    changes
+}
+
+/// The changes of deferred blocks, mirrored with the next block that's
+/// mirrored.
+#[derive(Default)]
+struct Backlog {
+    /// The changed entity and relation ids, by space.
+    changes: BTreeMap<Uuid, (HashSet<Uuid>, HashSet<Uuid>)>,
+    /// The block number, space id and cid of each edit.
+    edits: Vec<(String, Uuid, String)>,
+}
+
+/// Writes the rows blocks changed from the database to a mirror.
+pub struct MirrorSync {
+    pool: PgPool,
+    writer: Arc<dyn MirrorWriter>,
+    backlog: Mutex<Backlog>,
+}
+
+impl MirrorSync {
+    pub fn new(pool: PgPool, writer: Arc<dyn MirrorWriter>) -> Self {
+        MirrorSync {
+            pool,
+            writer,
+            backlog: Mutex::new(Backlog::default()),
+        }
+    }
+
+    /// Connects to the configured ClickHouse server, or returns `None` if
+    /// no mirror URL is set.
+    pub fn from_config(pool: PgPool, config: &MirrorConfig) -> Result<Option<Self>, MirrorError> {
+        let Some(url) = &config.url else {
+            return Ok(None);
+        };
+
+        let writer = clickhouse::ClickHouseWriter::new(
+            url,
+            &config.database,
+            config.user.clone(),
+            config.password.clone(),
+        )?;
+
+        Ok(Some(MirrorSync::new(pool, Arc::new(writer))))
+    }
+
+    pub async fn create_tables(&self) -> Result<(), MirrorError> {
+        self.writer.create_tables().await
+    }
+
+    /// Remembers the changes of a block, so they're mirrored with the next
+    /// block that's mirrored instead.
+    pub fn defer_block(&self, data: &KgData) {
+        let mut backlog = self.backlog.lock().unwrap();
+
+        for (space_id, changes) in block_changes(data) {
+            let (entity_ids, relation_ids) = backlog.changes.entry(space_id).or_default();
+            entity_ids.extend(changes.entity_ids);
+            relation_ids.extend(changes.relation_ids);
+        }
+
+        let block_number = data.block.block_number.to_string();
+        backlog.edits.extend(
+            data.edits
+                .iter()
+                .map(|edit| (block_number.clone(), edit.space_id, edit.cid.clone())),
+        );
+    }
+
+    /// Mirrors the rows changed by the edits of a block, which must already
+    /// be written, and by the blocks deferred before it. Rows are versioned
+    /// by this block's number. Returns the number of rows written.
+    pub async fn mirror_block(&self, data: &KgData) -> Result<usize, MirrorError> {
+        self.defer_block(data);
+        let backlog = std::mem::take(&mut *self.backlog.lock().unwrap());
+        let block_number = data.block.block_number;
+        let mut written = 0;
+
+        for (space_id, (entity_ids, relation_ids)) in backlog.changes {
+            let entity_ids: Vec<Uuid> = entity_ids.into_iter().collect();
+            let relation_ids: Vec<Uuid> = relation_ids.into_iter().collect();
+            let batches = entity_ids
+                .len()
+                .max(relation_ids.len())
+                .div_ceil(BATCH_SIZE);
+
+            for batch in 0..batches {
+                let entity_ids = batch_of(&entity_ids, batch);
+                let relation_ids = batch_of(&relation_ids, batch);
+
+                written += self
+                    .mirror_values(space_id, entity_ids, block_number)
+                    .await?;
+                written += self
+                    .mirror_relations(space_id, entity_ids, relation_ids, block_number)
+                    .await?;
+            }
+        }
+
+        written += self.mirror_edits(backlog.edits).await?;
+
+        Ok(written)
+    }
+
+    /// Mirrors edits, given by their block number, space id and cid.
+    async fn mirror_edits(&self, edits: Vec<(String, Uuid, String)>) -> Result<usize, MirrorError> {
+        if edits.is_empty() {
+            return Ok(0);
+        }
+
+        // Edits are keyed by space, and the same content can be posted in
+        // several spaces and blocks
+        let (blocks, (space_ids, cids)): (Vec<String>, (Vec<Uuid>, Vec<String>)) = edits
+            .into_iter()
+            .map(|(block, space_id, cid)| (block, (space_id, cid)))
+            .unzip();
+        let mirrored: Vec<MirroredEdit> =
+            sqlx::query_as::<_, (Uuid, String, String, String, String)>(
+                r#"
+                    SELECT space_id, content_hash, cid, created_at, created_at_block
+                    FROM edits
+                    WHERE (created_at_block, space_id, cid) IN (
+                        SELECT * FROM UNNEST($1::text[], $2::uuid[], $3::text[])
+                    )
+                "#,
+            )
+            .bind(&blocks)
+            .bind(&space_ids)
+            .bind(&cids)
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .map(
+                |(space_id, content_hash, cid, created_at, created_at_block)| MirroredEdit {
+                    space_id,
+                    content_hash,
+                    cid,
+                    block_number: created_at_block.parse().unwrap_or(0),
+                    created_at: created_at.parse().unwrap_or(0),
+                },
+            )
+            .collect();
+        if !mirrored.is_empty() {
+            self.writer.write_edits(&mirrored).await?;
+        }
+
+        Ok(mirrored.len())
+    }
+
+    async fn mirror_values(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+        block_number: u64,
+    ) -> Result<usize, MirrorError> {
+        if entity_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let current = sqlx::query_as::<_, MirroredValue>(
+            r#"
+                SELECT id, property_id, entity_id, space_id, string, language, unit,
+                    boolean, number::float8 AS number, point, time
+                FROM values
+                WHERE space_id = $1 AND entity_id = ANY($2)
+            "#,
+        )
+        .bind(space_id)
+        .bind(entity_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let mirrored = self.writer.values(space_id, entity_ids).await?;
+
+        let live: HashSet<&str> = current.iter().map(|value| value.id.as_str()).collect();
+        let deleted: Vec<MirroredValue> = mirrored
+            .into_iter()
+            .filter(|value| !live.contains(value.id.as_str()))
+            .map(|value| MirroredValue {
+                is_deleted: 1,
+                ..value
+            })
+            .collect();
+        let rows: Vec<MirroredValue> = current
+            .into_iter()
+            .chain(deleted)
+            .map(|value| MirroredValue {
+                block_number,
+                ..value
+            })
+            .collect();
+
+        if !rows.is_empty() {
+            self.writer.write_values(&rows).await?;
+        }
+
+        Ok(rows.len())
+    }
+
+    async fn mirror_relations(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+        relation_ids: &[Uuid],
+        block_number: u64,
+    ) -> Result<usize, MirrorError> {
+        if entity_ids.is_empty() && relation_ids.is_empty() {
+            return Ok(0);
+        }
+
+        // Deleting an entity deletes the relations to it too
+        let current = sqlx::query_as::<_, MirroredRelation>(
+            r#"
+                SELECT id, entity_id, type_id, space_id, from_entity_id, from_space_id,
+                    from_version_id, to_entity_id, to_space_id, to_version_id, position, verified
+                FROM relations
+                WHERE space_id = $1
+                    AND (from_entity_id = ANY($2) OR to_entity_id = ANY($2) OR id = ANY($3))
+            "#,
+        )
+        .bind(space_id)
+        .bind(entity_ids)
+        .bind(relation_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        let mirrored = self
+            .writer
+            .relations(space_id, entity_ids, relation_ids)
+            .await?;
+
+        let live: HashSet<Uuid> = current.iter().map(|relation| relation.id).collect();
+        let deleted: Vec<MirroredRelation> = mirrored
+            .into_iter()
+            .filter(|relation| !live.contains(&relation.id))
+            .map(|relation| MirroredRelation {
+                is_deleted: 1,
+                ..relation
+            })
+            .collect();
+        let rows: Vec<MirroredRelation> = current
+            .into_iter()
+            .chain(deleted)
+            .map(|relation| MirroredRelation {
+                block_number,
+                ..relation
+            })
+            .collect();
+
+        if !rows.is_empty() {
+            self.writer.write_relations(&rows).await?;
+        }
+
+        Ok(rows.len())
+    }
+}
+
+/// The ids of a batch, which is empty past the last batch.
+fn batch_of(ids: &[Uuid], batch: usize) -> &[Uuid] {
+    ids.chunks(BATCH_SIZE).nth(batch).unwrap_or(&[])
+}
+
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response, MirrorError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    Err(MirrorError::Status {
+        status: status.as_u16(),
+        body: response.text().await.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::builder::{relation, value, EditBuilder};
+
+    #[test]
+    fn test_batches() {
+        let ids: Vec<Uuid> = (0..BATCH_SIZE + 1).map(|_| Uuid::new_v4()).collect();
+
+        assert_eq!(batch_of(&ids, 0).len(), BATCH_SIZE);
+        assert_eq!(batch_of(&ids, 1), &ids[BATCH_SIZE..]);
+        assert!(batch_of(&ids, 2).is_empty());
+        assert!(batch_of(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_changes_of_an_edit() {
+        let (person, topic, property) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (relation_id, deleted_relation) = (Uuid::new_v4(), Uuid::new_v4());
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(property, wire::pb::grc20::DataType::Text)
+            .update_entity(person, [value(property, "Alice")])
+            .create_relation(relation(
+                relation_id,
+                Uuid::new_v4(),
+                property,
+                person,
+                topic,
+            ))
+            .delete_relation(deleted_relation)
+            .delete_entity(topic)
+            .build();
+
+        let mut changes = MirrorChanges::default();
+        changes.add_edit(&edit);
+
+        assert_eq!(
+            changes,
+            MirrorChanges {
+                entity_ids: vec![person, topic],
+                relation_ids: vec![relation_id, deleted_relation],
+            }
+        );
+    }
+}
@@ -0,0 +1,284 @@
+use std::collections::HashSet;
+
+use chrono::{DateTime, Datelike, Days};
+use indexer_utils::{checksum_address, id};
+use uuid::Uuid;
+
+use crate::{models::entities::EntitiesModel, KgData};
+
+/// The periods activity is rolled up over. Weeks start on Monday, and both
+/// periods are in UTC.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RollupPeriod {
+    Day,
+    Week,
+}
+
+impl RollupPeriod {
+    pub const ALL: [RollupPeriod; 2] = [RollupPeriod::Day, RollupPeriod::Week];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RollupPeriod::Day => "day",
+            RollupPeriod::Week => "week",
+        }
+    }
+
+    /// Returns the first day of the period containing a unix timestamp,
+    /// formatted as `YYYY-MM-DD`.
+    pub fn start(&self, timestamp: i64) -> String {
+        let date = DateTime::from_timestamp(timestamp, 0)
+            .unwrap_or_default()
+            .date_naive();
+
+        let start = match self {
+            RollupPeriod::Day => date,
+            RollupPeriod::Week => date
+                .checked_sub_days(Days::new(date.weekday().num_days_from_monday().into()))
+                .unwrap_or(date),
+        };
+
+        start.format("%Y-%m-%d").to_string()
+    }
+}
+
+/// An author of an edit published to a space.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ActiveEditorItem {
+    pub space_id: Uuid,
+    pub editor_id: Uuid,
+}
+
+/// An entity written by an edit to a space. It's counted as new if it was
+/// created in the block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityActivityItem {
+    pub space_id: Uuid,
+    pub entity_id: Uuid,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalActivityItem {
+    pub space_id: Uuid,
+    pub proposal_id: String,
+}
+
+/// An executed proposal, whose space is looked up from its plugin when the
+/// activity is recorded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExecutedProposalActivityItem {
+    pub plugin_address: String,
+    pub proposal_id: String,
+}
+
+/// The activity of a block that's counted into the space activity rollups.
+/// Edits are counted from the `edits` table once they're written, so
+/// duplicate content isn't counted.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockActivity {
+    pub editors: Vec<ActiveEditorItem>,
+    pub entities: Vec<EntityActivityItem>,
+    pub created_proposals: Vec<ProposalActivityItem>,
+    pub executed_proposals: Vec<ExecutedProposalActivityItem>,
+}
+
+impl BlockActivity {
+    pub fn is_empty(&self) -> bool {
+        self.editors.is_empty()
+            && self.entities.is_empty()
+            && self.created_proposals.is_empty()
+            && self.executed_proposals.is_empty()
+    }
+}
+
+/// The activity of a space over one rollup period.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ActivityRollup {
+    /// The first day of the period, formatted as `YYYY-MM-DD`.
+    pub period_start: String,
+    pub edits: i32,
+    pub active_editors: i32,
+    pub proposals_created: i32,
+    pub proposals_executed: i32,
+    pub new_entities: i32,
+}
+
+pub struct AnalyticsModel;
+
+impl AnalyticsModel {
+    /// Maps the edits and proposals of a block to the activity they add to
+    /// the rollups. Entities written to several spaces in the block are
+    /// attributed to the first one.
+    pub fn map_block_activity(data: &KgData) -> BlockActivity {
+        let mut activity = BlockActivity::default();
+        let mut seen_editors = HashSet::new();
+        let mut seen_entities = HashSet::new();
+
+        for preprocessed in &data.edits {
+            let Some(edit) = &preprocessed.edit else {
+                continue;
+            };
+
+            for author in &edit.authors {
+                let Ok(editor_id) = id::transform_id_bytes(author.clone()) else {
+                    continue;
+                };
+                let editor = ActiveEditorItem {
+                    space_id: preprocessed.space_id,
+                    editor_id: Uuid::from_bytes(editor_id),
+                };
+
+                if seen_editors.insert(editor.clone()) {
+                    activity.editors.push(editor);
+                }
+            }
+
+            for entity in EntitiesModel::map_edit_to_entities(edit, &data.block) {
+                if seen_entities.insert(entity.id) {
+                    activity.entities.push(EntityActivityItem {
+                        space_id: preprocessed.space_id,
+                        entity_id: entity.id,
+                    });
+                }
+            }
+        }
+
+        activity.created_proposals = data
+            .created_proposals
+            .iter()
+            .map(|proposal| ProposalActivityItem {
+                space_id: data
+                    .network
+                    .space_id(&checksum_address(&proposal.dao_address)),
+                proposal_id: proposal.proposal_id.clone(),
+            })
+            .collect();
+
+        activity.executed_proposals = data
+            .executed_proposals
+            .iter()
+            .map(|proposal| ExecutedProposalActivityItem {
+                plugin_address: checksum_address(&proposal.plugin_address),
+                proposal_id: proposal.proposal_id.clone(),
+            })
+            .collect();
+
+        activity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::PreprocessedEdit, CreatedProposal, ProposalType};
+    use indexer_utils::network_ids::Network;
+    use stream::utils::BlockMetadata;
+    use wire::builder::{value, EditBuilder};
+
+    #[test]
+    fn test_period_start() {
+        // Friday 2009-02-13 23:31:30 UTC
+        assert_eq!(RollupPeriod::Day.start(1234567890), "2009-02-13");
+        assert_eq!(RollupPeriod::Week.start(1234567890), "2009-02-09");
+        // Monday starts its own week
+        assert_eq!(RollupPeriod::Week.start(1234137600), "2009-02-09");
+    }
+
+    #[test]
+    fn test_map_block_activity() {
+        let network = Network::default();
+        let (space_a, space_b) = (Uuid::new_v4(), Uuid::new_v4());
+        let (author, entity) = (Uuid::new_v4(), Uuid::new_v4());
+        let property = Uuid::new_v4();
+
+        let edit = || {
+            EditBuilder::new(Uuid::new_v4())
+                .author(author)
+                .update_entity(entity, [value(property, "value")])
+                .build()
+        };
+        let preprocessed = |space_id, edit| PreprocessedEdit {
+            cid: "ipfs://edit".to_string(),
+            edit,
+            is_errored: false,
+            space_id,
+            sender: None,
+            event_index: 0,
+        };
+
+        let data = KgData {
+            block: BlockMetadata {
+                cursor: "1".to_string(),
+                block_number: 1,
+                timestamp: "1234567890".to_string(),
+            },
+            edits: vec![
+                preprocessed(space_a, Some(edit())),
+                preprocessed(space_a, Some(edit())),
+                preprocessed(space_b, Some(edit())),
+                preprocessed(space_b, None),
+            ],
+            added_editors: vec![],
+            removed_editors: vec![],
+            added_members: vec![],
+            removed_members: vec![],
+            added_subspaces: vec![],
+            removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![CreatedProposal {
+                proposal_id: "1".to_string(),
+                proposal_type: ProposalType::PublishEdit,
+                creator: "0x0000000000000000000000000000000000000001".to_string(),
+                dao_address: "0x0000000000000000000000000000000000000002".to_string(),
+                plugin_address: "0x0000000000000000000000000000000000000003".to_string(),
+                preview: None,
+            }],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            spaces: vec![],
+            network: network.clone(),
+            unowned_properties: vec![],
+        };
+
+        let activity = AnalyticsModel::map_block_activity(&data);
+
+        assert_eq!(
+            activity.editors,
+            vec![
+                ActiveEditorItem {
+                    space_id: space_a,
+                    editor_id: author
+                },
+                ActiveEditorItem {
+                    space_id: space_b,
+                    editor_id: author
+                },
+            ]
+        );
+        // The property is written as an entity too
+        assert_eq!(
+            activity.entities,
+            vec![
+                EntityActivityItem {
+                    space_id: space_a,
+                    entity_id: entity
+                },
+                EntityActivityItem {
+                    space_id: space_a,
+                    entity_id: property
+                },
+            ]
+        );
+        assert_eq!(
+            activity.created_proposals,
+            vec![ProposalActivityItem {
+                space_id: network.space_id(&checksum_address(
+                    "0x0000000000000000000000000000000000000002"
+                )),
+                proposal_id: "1".to_string(),
+            }]
+        );
+        assert!(activity.executed_proposals.is_empty());
+    }
+}
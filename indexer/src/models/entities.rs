@@ -1,9 +1,11 @@
 use std::collections::HashSet;
 
-use indexer_utils::id;
+use indexer_utils::{id, system_ids::TYPES_PROPERTY};
 use stream::utils::BlockMetadata;
 use uuid::Uuid;
-use wire::pb::grc20::{op::Payload, Edit};
+use wire::pb::grc20::{op::Payload, Edit, Op};
+
+use crate::models::{relations::SetRelationItem, values::ValueOp};
 
 #[derive(Clone)]
 pub struct EntityItem {
@@ -14,6 +16,36 @@ pub struct EntityItem {
     pub updated_at_block: String,
 }
 
+/// An entity with its values and outgoing relations in a space.
+#[derive(Clone)]
+pub struct HydratedEntity {
+    pub entity: EntityItem,
+    pub values: Vec<ValueOp>,
+    pub relations: Vec<SetRelationItem>,
+}
+
+/// The entities an edit changes in its space, whose `last_edited_at` and
+/// `last_edited_block` are moved to the edit's block along with those of
+/// their types and the space.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EditedEntities {
+    /// Entities whose values are set or unset, that relations are created
+    /// from, or that are deleted.
+    pub entity_ids: Vec<Uuid>,
+    /// Relations that are updated or deleted. The entities they're from are
+    /// read from storage.
+    pub relation_ids: Vec<Uuid>,
+    /// Types the edit gives to entities, which aren't stored yet when the
+    /// types of the edited entities are read.
+    pub type_ids: Vec<Uuid>,
+}
+
+impl EditedEntities {
+    pub fn is_empty(&self) -> bool {
+        self.entity_ids.is_empty() && self.relation_ids.is_empty()
+    }
+}
+
 pub struct EntitiesModel;
 
 impl EntitiesModel {
@@ -274,6 +306,23 @@ impl EntitiesModel {
                             seen.insert(relation_id);
                         }
                     }
+                    Payload::DeleteEntity(_) => {
+                        let Some(entity_id) = deleted_entity_id(op) else {
+                            continue;
+                        };
+
+                        if !seen.contains(&entity_id) {
+                            entities.push(EntityItem {
+                                id: entity_id,
+                                created_at: block.timestamp.clone(),
+                                created_at_block: block.block_number.to_string(),
+                                updated_at: block.timestamp.clone(),
+                                updated_at_block: block.block_number.to_string(),
+                            });
+
+                            seen.insert(entity_id);
+                        }
+                    }
                     // @TODO: Payload::UpdateRelation(relation)
                     // @TODO: Payload::UnsetRelationFields(relation)
                     _ => {
@@ -285,4 +334,148 @@ impl EntitiesModel {
 
         return entities;
     }
+
+    /// Returns the entities an edit changes in its space. Entities only
+    /// referenced by the edit, as a property, a relation type or the entity a
+    /// relation points to, aren't edited.
+    pub fn map_edit_to_edited_entities(edit: &Edit) -> EditedEntities {
+        let mut edited = EditedEntities::default();
+
+        fn push(ids: &mut Vec<Uuid>, id: Option<Uuid>) {
+            if let Some(id) = id {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+        }
+
+        for op in &edit.ops {
+            match &op.payload {
+                Some(Payload::UpdateEntity(entity)) => {
+                    push(&mut edited.entity_ids, op_id(&entity.id));
+                }
+                Some(Payload::UnsetEntityValues(entity)) => {
+                    push(&mut edited.entity_ids, op_id(&entity.id));
+                }
+                Some(Payload::CreateRelation(relation)) => {
+                    push(&mut edited.entity_ids, op_id(&relation.from_entity));
+
+                    if op_id(&relation.r#type) == Some(TYPES_PROPERTY) {
+                        push(&mut edited.type_ids, op_id(&relation.to_entity));
+                    }
+                }
+                Some(Payload::UpdateRelation(relation)) => {
+                    push(&mut edited.relation_ids, op_id(&relation.id));
+                }
+                Some(Payload::UnsetRelationFields(relation)) => {
+                    push(&mut edited.relation_ids, op_id(&relation.id));
+                }
+                Some(Payload::DeleteRelation(relation_id)) => {
+                    push(&mut edited.relation_ids, op_id(relation_id));
+                }
+                Some(Payload::DeleteEntity(_)) => {
+                    push(&mut edited.entity_ids, deleted_entity_id(op));
+                }
+                _ => {}
+            }
+        }
+
+        edited
+    }
+
+    /// Returns the entities an edit deletes from its space, in op order.
+    ///
+    /// Deleting an entity removes its values and the relations from or to it
+    /// in the space. The entity itself is kept, as other spaces may still
+    /// describe it.
+    pub fn map_edit_to_deleted_entities(edit: &Edit) -> Vec<Uuid> {
+        let mut deleted: Vec<Uuid> = Vec::new();
+
+        for entity_id in edit.ops.iter().filter_map(deleted_entity_id) {
+            if !deleted.contains(&entity_id) {
+                deleted.push(entity_id);
+            }
+        }
+
+        deleted
+    }
+}
+
+/// Returns the id in an op, or `None` if it's malformed.
+fn op_id(bytes: &[u8]) -> Option<Uuid> {
+    id::transform_id_bytes(bytes.to_vec())
+        .ok()
+        .map(Uuid::from_bytes)
+}
+
+/// Returns the entity a `DeleteEntity` op deletes, or `None` for any other
+/// op or a malformed id.
+pub fn deleted_entity_id(op: &Op) -> Option<Uuid> {
+    let Some(Payload::DeleteEntity(entity_id)) = &op.payload else {
+        return None;
+    };
+
+    match id::transform_id_bytes(entity_id.clone()) {
+        Ok(entity_id_bytes) => Some(Uuid::from_bytes(entity_id_bytes)),
+        Err(_) => {
+            tracing::error!(
+                "[Entities][DeleteEntity] Could not transform Vec<u8> for entity.id {:?}",
+                entity_id
+            );
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::builder::{relation, relation_update, value, EditBuilder};
+
+    #[test]
+    fn test_map_edit_to_edited_entities() {
+        let (entity, other, property) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (type_id, relation_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (updated_relation, deleted_relation) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(property, "Name")])
+            .unset_entity_values(entity, [property])
+            .create_relation(relation(
+                relation_id,
+                Uuid::new_v4(),
+                TYPES_PROPERTY,
+                entity,
+                type_id,
+            ))
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                property,
+                entity,
+                other,
+            ))
+            .update_relation(relation_update(updated_relation))
+            .delete_relation(deleted_relation)
+            .delete_entity(other)
+            .build();
+
+        assert_eq!(
+            EntitiesModel::map_edit_to_edited_entities(&edit),
+            EditedEntities {
+                entity_ids: vec![entity, other],
+                relation_ids: vec![updated_relation, deleted_relation],
+                type_ids: vec![type_id],
+            }
+        );
+    }
+
+    #[test]
+    fn test_referenced_entities_are_not_edited() {
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(Uuid::new_v4(), wire::pb::grc20::DataType::Text)
+            .build();
+
+        assert!(EntitiesModel::map_edit_to_edited_entities(&edit).is_empty());
+    }
 }
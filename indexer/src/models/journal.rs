@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+
+use indexer_utils::id;
+use uuid::Uuid;
+use wire::{
+    hash::edit_hash,
+    pb::grc20::{op::Payload, Op},
+};
+
+use crate::cache::PreprocessedEdit;
+
+/// The kind of an op in the journal, named after the op's payload.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OpKind {
+    UpdateEntity,
+    UnsetEntityValues,
+    DeleteEntity,
+    CreateRelation,
+    UpdateRelation,
+    UnsetRelationFields,
+    DeleteRelation,
+    CreateProperty,
+    UpdateProperty,
+}
+
+impl OpKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpKind::UpdateEntity => "update_entity",
+            OpKind::UnsetEntityValues => "unset_entity_values",
+            OpKind::DeleteEntity => "delete_entity",
+            OpKind::CreateRelation => "create_relation",
+            OpKind::UpdateRelation => "update_relation",
+            OpKind::UnsetRelationFields => "unset_relation_fields",
+            OpKind::DeleteRelation => "delete_relation",
+            OpKind::CreateProperty => "create_property",
+            OpKind::UpdateProperty => "update_property",
+        }
+    }
+}
+
+/// An op of an edit to append to the journal. The op is only journaled if
+/// its edit was applied to the space in the block.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub space_id: Uuid,
+    pub edit_id: Option<Uuid>,
+    /// The content hash the edit is recorded under in `edits`.
+    pub content_hash: String,
+    /// The position of the op in the edit.
+    pub op_index: usize,
+    pub kind: OpKind,
+    /// The entity, relation or property the op targets.
+    pub target_id: Uuid,
+}
+
+/// An op as it's read back from the journal.
+#[derive(Clone, Debug, PartialEq, Eq, sqlx::FromRow)]
+pub struct JournaledOp {
+    /// The op's position in the journal, which readers keep as their cursor.
+    pub sequence: i64,
+    pub block_number: i64,
+    pub space_id: Uuid,
+    pub edit_id: Option<Uuid>,
+    pub op_index: i32,
+    pub op_kind: String,
+    pub target_id: Uuid,
+}
+
+pub struct JournalModel;
+
+impl JournalModel {
+    /// Maps the ops of a block's edits to journal entries, in the order the
+    /// edits and their ops are applied. Edits that couldn't be decoded, and
+    /// ops with a malformed target id, are skipped. Content published to a
+    /// space more than once is only journaled once.
+    pub fn map_edits_to_journal(edits: &[PreprocessedEdit]) -> Vec<JournalEntry> {
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+
+        for preprocessed in edits {
+            let Some(edit) = preprocessed.edit.as_ref() else {
+                continue;
+            };
+            if preprocessed.is_errored {
+                continue;
+            }
+
+            let content_hash = edit_hash(edit);
+            if !seen.insert((preprocessed.space_id, content_hash.clone())) {
+                continue;
+            }
+
+            let edit_id = id::transform_id_bytes(edit.id.clone())
+                .ok()
+                .map(Uuid::from_bytes);
+
+            for (op_index, op) in edit.ops.iter().enumerate() {
+                let Some((kind, target_id)) = op_target(op) else {
+                    continue;
+                };
+
+                entries.push(JournalEntry {
+                    space_id: preprocessed.space_id,
+                    edit_id,
+                    content_hash: content_hash.clone(),
+                    op_index,
+                    kind,
+                    target_id,
+                });
+            }
+        }
+
+        entries
+    }
+}
+
+fn op_target(op: &Op) -> Option<(OpKind, Uuid)> {
+    let (kind, target) = match op.payload.as_ref()? {
+        Payload::UpdateEntity(entity) => (OpKind::UpdateEntity, &entity.id),
+        Payload::UnsetEntityValues(entity) => (OpKind::UnsetEntityValues, &entity.id),
+        Payload::DeleteEntity(entity_id) => (OpKind::DeleteEntity, entity_id),
+        Payload::CreateRelation(relation) => (OpKind::CreateRelation, &relation.id),
+        Payload::UpdateRelation(relation) => (OpKind::UpdateRelation, &relation.id),
+        Payload::UnsetRelationFields(relation) => (OpKind::UnsetRelationFields, &relation.id),
+        Payload::DeleteRelation(relation_id) => (OpKind::DeleteRelation, relation_id),
+        Payload::CreateProperty(property) => (OpKind::CreateProperty, &property.id),
+        Payload::UpdateProperty(property) => (OpKind::UpdateProperty, &property.id),
+    };
+
+    let target = id::transform_id_bytes(target.clone()).ok()?;
+    Some((kind, Uuid::from_bytes(target)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::builder::{relation, value, EditBuilder};
+    use wire::pb::grc20::DataType;
+
+    fn preprocessed(space_id: Uuid, edit: wire::pb::grc20::Edit) -> PreprocessedEdit {
+        PreprocessedEdit {
+            cid: "ipfs://journal".to_string(),
+            edit: Some(edit),
+            is_errored: false,
+            space_id,
+            sender: None,
+            event_index: 0,
+        }
+    }
+
+    #[test]
+    fn test_map_edits_to_journal() {
+        let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+        let (edit_id, entity, property, relation_id) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+
+        let edit = EditBuilder::new(edit_id)
+            .create_property(property, DataType::Text)
+            .update_entity(entity, [value(property, "Journaled")])
+            .create_relation(relation(
+                relation_id,
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                entity,
+                entity,
+            ))
+            .delete_entity(entity)
+            .build();
+        let content_hash = edit_hash(&edit);
+
+        let entries = JournalModel::map_edits_to_journal(&[
+            preprocessed(space_id, edit.clone()),
+            // The same content again in the same space isn't applied again
+            preprocessed(space_id, edit.clone()),
+            preprocessed(other_space_id, edit),
+        ]);
+
+        let ops: Vec<(Uuid, usize, OpKind, Uuid)> = entries
+            .iter()
+            .map(|e| (e.space_id, e.op_index, e.kind, e.target_id))
+            .collect();
+        assert_eq!(
+            ops,
+            vec![
+                (space_id, 0, OpKind::CreateProperty, property),
+                (space_id, 1, OpKind::UpdateEntity, entity),
+                (space_id, 2, OpKind::CreateRelation, relation_id),
+                (space_id, 3, OpKind::DeleteEntity, entity),
+                (other_space_id, 0, OpKind::CreateProperty, property),
+                (other_space_id, 1, OpKind::UpdateEntity, entity),
+                (other_space_id, 2, OpKind::CreateRelation, relation_id),
+                (other_space_id, 3, OpKind::DeleteEntity, entity),
+            ]
+        );
+        assert!(entries
+            .iter()
+            .all(|e| e.edit_id == Some(edit_id) && e.content_hash == content_hash));
+    }
+
+    #[test]
+    fn test_skips_malformed_targets_and_errored_edits() {
+        let space_id = Uuid::new_v4();
+        let mut edit = EditBuilder::new(Uuid::new_v4())
+            .delete_relation(Uuid::new_v4())
+            .delete_entity(Uuid::new_v4())
+            .build();
+        edit.ops[0].payload = Some(Payload::DeleteRelation(vec![1, 2, 3]));
+
+        let entries = JournalModel::map_edits_to_journal(&[
+            preprocessed(space_id, edit.clone()),
+            PreprocessedEdit {
+                is_errored: true,
+                ..preprocessed(Uuid::new_v4(), edit)
+            },
+        ]);
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].op_index, 1);
+        assert_eq!(entries[0].kind, OpKind::DeleteEntity);
+    }
+}
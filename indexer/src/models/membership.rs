@@ -1,4 +1,4 @@
-use indexer_utils::{checksum_address, id::derive_space_id, network_ids::GEO};
+use indexer_utils::{checksum_address, network_ids::Network};
 use uuid::Uuid;
 
 use crate::{AddedMember, RemovedMember};
@@ -19,11 +19,14 @@ pub struct MembershipModel;
 
 impl MembershipModel {
     /// Maps added members from KgData to database-ready MemberItem structs
-    pub fn map_added_members(added_members: &Vec<AddedMember>) -> Vec<MemberItem> {
+    pub fn map_added_members(
+        added_members: &Vec<AddedMember>,
+        network: &Network,
+    ) -> Vec<MemberItem> {
         let mut members = Vec::new();
 
         for member in added_members {
-            let space_id = derive_space_id(GEO, &checksum_address(member.dao_address.clone()));
+            let space_id = network.space_id(&checksum_address(member.dao_address.clone()));
             
             members.push(MemberItem {
                 address: checksum_address(member.editor_address.clone()),
@@ -35,11 +38,14 @@ impl MembershipModel {
     }
 
     /// Maps removed members from KgData to database-ready MemberItem structs
-    pub fn map_removed_members(removed_members: &Vec<RemovedMember>) -> Vec<MemberItem> {
+    pub fn map_removed_members(
+        removed_members: &Vec<RemovedMember>,
+        network: &Network,
+    ) -> Vec<MemberItem> {
         let mut members = Vec::new();
 
         for member in removed_members {
-            let space_id = derive_space_id(GEO, &checksum_address(member.dao_address.clone()));
+            let space_id = network.space_id(&checksum_address(member.dao_address.clone()));
             
             members.push(MemberItem {
                 address: checksum_address(member.editor_address.clone()),
@@ -51,11 +57,14 @@ impl MembershipModel {
     }
 
     /// Maps added editors from KgData to database-ready EditorItem structs
-    pub fn map_added_editors(added_editors: &Vec<AddedMember>) -> Vec<EditorItem> {
+    pub fn map_added_editors(
+        added_editors: &Vec<AddedMember>,
+        network: &Network,
+    ) -> Vec<EditorItem> {
         let mut editors = Vec::new();
 
         for editor in added_editors {
-            let space_id = derive_space_id(GEO, &checksum_address(editor.dao_address.clone()));
+            let space_id = network.space_id(&checksum_address(editor.dao_address.clone()));
             
             editors.push(EditorItem {
                 address: checksum_address(editor.editor_address.clone()),
@@ -67,11 +76,14 @@ impl MembershipModel {
     }
 
     /// Maps removed editors from KgData to database-ready EditorItem structs
-    pub fn map_removed_editors(removed_editors: &Vec<RemovedMember>) -> Vec<EditorItem> {
+    pub fn map_removed_editors(
+        removed_editors: &Vec<RemovedMember>,
+        network: &Network,
+    ) -> Vec<EditorItem> {
         let mut editors = Vec::new();
 
         for editor in removed_editors {
-            let space_id = derive_space_id(GEO, &checksum_address(editor.dao_address.clone()));
+            let space_id = network.space_id(&checksum_address(editor.dao_address.clone()));
             
             editors.push(EditorItem {
                 address: checksum_address(editor.editor_address.clone()),
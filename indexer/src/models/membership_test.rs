@@ -2,7 +2,11 @@
 mod tests {
     use crate::models::membership::MembershipModel;
     use crate::{AddedMember, RemovedMember};
-    use indexer_utils::{checksum_address, id::derive_space_id, network_ids::GEO};
+    use indexer_utils::{
+        checksum_address,
+        id::derive_space_id,
+        network_ids::{Network, GEO},
+    };
 
     fn create_added_member(dao_address: &str, editor_address: &str) -> AddedMember {
         AddedMember {
@@ -21,7 +25,7 @@ mod tests {
     #[test]
     fn test_map_added_members_empty() {
         let added_members = vec![];
-        let result = MembershipModel::map_added_members(&added_members);
+        let result = MembershipModel::map_added_members(&added_members, &Network::default());
         assert_eq!(result.len(), 0);
     }
 
@@ -31,7 +35,7 @@ mod tests {
         let editor_addr = "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd1";
         
         let added_members = vec![create_added_member(dao_addr, editor_addr)];
-        let result = MembershipModel::map_added_members(&added_members);
+        let result = MembershipModel::map_added_members(&added_members, &Network::default());
         
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].address, checksum_address(editor_addr.to_string()));
@@ -49,7 +53,7 @@ mod tests {
             create_added_member(dao_addr1, editor_addr1),
             create_added_member(dao_addr2, editor_addr2),
         ];
-        let result = MembershipModel::map_added_members(&added_members);
+        let result = MembershipModel::map_added_members(&added_members, &Network::default());
         
         assert_eq!(result.len(), 2);
         
@@ -70,7 +74,7 @@ mod tests {
             create_added_member(dao_addr, editor_addr1),
             create_added_member(dao_addr, editor_addr2),
         ];
-        let result = MembershipModel::map_added_members(&added_members);
+        let result = MembershipModel::map_added_members(&added_members, &Network::default());
         
         assert_eq!(result.len(), 2);
         
@@ -85,7 +89,7 @@ mod tests {
     #[test]
     fn test_map_removed_members_empty() {
         let removed_members = vec![];
-        let result = MembershipModel::map_removed_members(&removed_members);
+        let result = MembershipModel::map_removed_members(&removed_members, &Network::default());
         assert_eq!(result.len(), 0);
     }
 
@@ -95,7 +99,7 @@ mod tests {
         let editor_addr = "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd1";
         
         let removed_members = vec![create_removed_member(dao_addr, editor_addr)];
-        let result = MembershipModel::map_removed_members(&removed_members);
+        let result = MembershipModel::map_removed_members(&removed_members, &Network::default());
         
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].address, checksum_address(editor_addr.to_string()));
@@ -113,7 +117,7 @@ mod tests {
             create_removed_member(dao_addr1, editor_addr1),
             create_removed_member(dao_addr2, editor_addr2),
         ];
-        let result = MembershipModel::map_removed_members(&removed_members);
+        let result = MembershipModel::map_removed_members(&removed_members, &Network::default());
         
         assert_eq!(result.len(), 2);
         
@@ -127,7 +131,7 @@ mod tests {
     #[test]
     fn test_map_added_editors_empty() {
         let added_editors = vec![];
-        let result = MembershipModel::map_added_editors(&added_editors);
+        let result = MembershipModel::map_added_editors(&added_editors, &Network::default());
         assert_eq!(result.len(), 0);
     }
 
@@ -137,7 +141,7 @@ mod tests {
         let editor_addr = "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd1";
         
         let added_editors = vec![create_added_member(dao_addr, editor_addr)];
-        let result = MembershipModel::map_added_editors(&added_editors);
+        let result = MembershipModel::map_added_editors(&added_editors, &Network::default());
         
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].address, checksum_address(editor_addr.to_string()));
@@ -155,7 +159,7 @@ mod tests {
             create_added_member(dao_addr1, editor_addr1),
             create_added_member(dao_addr2, editor_addr2),
         ];
-        let result = MembershipModel::map_added_editors(&added_editors);
+        let result = MembershipModel::map_added_editors(&added_editors, &Network::default());
         
         assert_eq!(result.len(), 2);
         
@@ -176,7 +180,7 @@ mod tests {
             create_added_member(dao_addr, editor_addr1),
             create_added_member(dao_addr, editor_addr2),
         ];
-        let result = MembershipModel::map_added_editors(&added_editors);
+        let result = MembershipModel::map_added_editors(&added_editors, &Network::default());
         
         assert_eq!(result.len(), 2);
         
@@ -191,7 +195,7 @@ mod tests {
     #[test]
     fn test_map_removed_editors_empty() {
         let removed_editors = vec![];
-        let result = MembershipModel::map_removed_editors(&removed_editors);
+        let result = MembershipModel::map_removed_editors(&removed_editors, &Network::default());
         assert_eq!(result.len(), 0);
     }
 
@@ -201,7 +205,7 @@ mod tests {
         let editor_addr = "0xabcdefabcdefabcdefabcdefabcdefabcdefabcd1";
         
         let removed_editors = vec![create_removed_member(dao_addr, editor_addr)];
-        let result = MembershipModel::map_removed_editors(&removed_editors);
+        let result = MembershipModel::map_removed_editors(&removed_editors, &Network::default());
         
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].address, checksum_address(editor_addr.to_string()));
@@ -219,7 +223,7 @@ mod tests {
             create_removed_member(dao_addr1, editor_addr1),
             create_removed_member(dao_addr2, editor_addr2),
         ];
-        let result = MembershipModel::map_removed_editors(&removed_editors);
+        let result = MembershipModel::map_removed_editors(&removed_editors, &Network::default());
         
         assert_eq!(result.len(), 2);
         
@@ -236,7 +240,7 @@ mod tests {
         let editor_addr = "0xABCDEFABCDEFABCDEFABCDEFABCDEFABCDEFABC1"; // uppercase
         
         let added_members = vec![create_added_member(dao_addr, editor_addr)];
-        let result = MembershipModel::map_added_members(&added_members);
+        let result = MembershipModel::map_added_members(&added_members, &Network::default());
         
         assert_eq!(result.len(), 1);
         // Verify that addresses are properly checksummed
@@ -254,8 +258,8 @@ mod tests {
         let added_members = vec![create_added_member(dao_addr, editor_addr1)];
         let added_editors = vec![create_added_member(dao_addr, editor_addr2)];
         
-        let member_result = MembershipModel::map_added_members(&added_members);
-        let editor_result = MembershipModel::map_added_editors(&added_editors);
+        let member_result = MembershipModel::map_added_members(&added_members, &Network::default());
+        let editor_result = MembershipModel::map_added_editors(&added_editors, &Network::default());
         
         assert_eq!(member_result[0].space_id, editor_result[0].space_id);
         
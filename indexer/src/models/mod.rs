@@ -1,7 +1,12 @@
+pub mod analytics;
 pub mod entities;
+pub mod journal;
 pub mod membership;
+pub mod neighborhood;
 pub mod properties;
+pub mod proposals;
 pub mod relations;
+pub mod schema;
 pub mod spaces;
 pub mod subspaces;
 pub mod values;
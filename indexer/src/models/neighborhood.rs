@@ -0,0 +1,32 @@
+use uuid::Uuid;
+
+use crate::models::relations::SetRelationItem;
+
+/// The furthest a neighborhood is walked from its entity, in hops.
+pub const MAX_NEIGHBORHOOD_DEPTH: u32 = 3;
+
+/// The most relations followed from each entity while walking a
+/// neighborhood, so a highly connected entity doesn't pull in most of the
+/// graph.
+pub const NEIGHBORHOOD_FAN_OUT: i64 = 50;
+
+/// The subgraph around an entity in a space, as returned to graph
+/// visualization clients.
+#[derive(Clone, Debug)]
+pub struct Neighborhood {
+    /// The entity the neighborhood was walked from.
+    pub entity_id: Uuid,
+    pub space_id: Uuid,
+    /// The entities reached, nearest first. The first is the entity itself.
+    pub nodes: Vec<NeighborhoodNode>,
+    /// The relations between the reached entities, in either direction.
+    pub relations: Vec<SetRelationItem>,
+}
+
+/// An entity reached while walking a neighborhood.
+#[derive(Clone, Debug, PartialEq, Eq, sqlx::FromRow)]
+pub struct NeighborhoodNode {
+    pub entity_id: Uuid,
+    /// The fewest hops from the neighborhood's entity.
+    pub depth: i32,
+}
@@ -1,9 +1,14 @@
 use indexer_utils::id;
-use std::collections::HashMap;
+use indexer_utils::system_ids::{DESCRIPTION_PROPERTY, NAME_PROPERTY, RENDERABLE_TYPE_PROPERTY};
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use thiserror::Error;
 use uuid::Uuid;
 use wire::pb::grc20::{op::Payload, DataType as PbDataType, Edit};
 
+use crate::models::values::{convert_value, ValueOp};
+use crate::validators::ValidationError;
+
 // Constants for PostgreSQL enum values - must match the data type enum in the db
 pub const DATA_TYPE_STRING: &str = "String";
 pub const DATA_TYPE_NUMBER: &str = "Number";
@@ -118,6 +123,51 @@ pub struct PropertyItem {
     pub data_type: DataType,
 }
 
+/// The human-readable description of a property, copied from the values and
+/// relations of the property entity.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PropertyMetadata {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub renderable_type: Option<Uuid>,
+}
+
+/// A request to change the data type of an existing property, and whether it
+/// was applied. Every request is recorded for auditing.
+#[derive(Clone, Debug)]
+pub struct PropertyMigrationItem {
+    pub property_id: Uuid,
+    pub space_id: Uuid,
+    pub from_type: DataType,
+    pub to_type: DataType,
+    /// `None` if the migration was applied.
+    pub rejection: Option<PropertyMigrationError>,
+    pub converted_values: usize,
+    pub created_at: String,
+    pub created_at_block: String,
+}
+
+impl PropertyMigrationItem {
+    pub fn is_applied(&self) -> bool {
+        self.rejection.is_none()
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum PropertyMigrationError {
+    #[error("only public spaces can migrate properties")]
+    NotGoverned,
+
+    #[error("value {0} is not a valid {1}: {2}")]
+    IncompatibleValue(Uuid, DataType, ValidationError),
+
+    #[error("{0} values can't be converted to relations")]
+    ValuesExist(usize),
+
+    #[error("{0} relations use the property as their type")]
+    RelationsExist(i64),
+}
+
 pub struct PropertiesModel;
 
 impl PropertiesModel {
@@ -157,6 +207,147 @@ impl PropertiesModel {
         // the edit.
         squash_properties(&properties)
     }
+
+    /// Returns the properties created by a block's edits, for writing them
+    /// in a single batch. Each edit's ops are squashed as in
+    /// `map_edit_to_properties`, and a property created by several edits
+    /// keeps the data type of the first one, since created properties can't
+    /// change their data type.
+    pub fn map_edits_to_properties<'a>(
+        edits: impl IntoIterator<Item = &'a Edit>,
+    ) -> Vec<PropertyItem> {
+        let mut seen = HashSet::new();
+
+        edits
+            .into_iter()
+            .flat_map(Self::map_edit_to_properties)
+            .filter(|property| seen.insert(property.id))
+            .collect()
+    }
+
+    /// Returns the data type changes requested by an edit. Only the last
+    /// change of each property applies.
+    pub fn map_edit_to_property_updates(edit: &Edit) -> Vec<PropertyItem> {
+        let mut properties: Vec<PropertyItem> = Vec::new();
+
+        for op in &edit.ops {
+            if let Some(Payload::UpdateProperty(property)) = &op.payload {
+                match id::transform_id_bytes(property.id.clone()) {
+                    Ok(property_id_bytes) => {
+                        if let Some(data_type) = native_type_to_data_type(property.data_type) {
+                            properties.push(PropertyItem {
+                                id: Uuid::from_bytes(property_id_bytes),
+                                data_type,
+                            });
+                        }
+                    }
+                    Err(_) => tracing::error!(
+                        "[Properties][UpdateProperty] Could not transform Vec<u8> for property.id {:?}",
+                        &property.id
+                    ),
+                }
+            }
+        }
+
+        squash_properties(&properties)
+    }
+
+    /// Converts the stored values of a property to a new data type. Fails
+    /// without converting anything if a single value isn't compatible.
+    ///
+    /// Relation properties don't have values, so a property can only become
+    /// a relation property while it has no values, and can only stop being
+    /// one while no relations use it as their type.
+    pub fn convert_values(
+        from_type: DataType,
+        to_type: DataType,
+        values: Vec<ValueOp>,
+        relation_count: i64,
+    ) -> Result<Vec<ValueOp>, PropertyMigrationError> {
+        if from_type == DataType::Relation && relation_count > 0 {
+            return Err(PropertyMigrationError::RelationsExist(relation_count));
+        }
+
+        if to_type == DataType::Relation {
+            if !values.is_empty() {
+                return Err(PropertyMigrationError::ValuesExist(values.len()));
+            }
+
+            return Ok(values);
+        }
+
+        values
+            .into_iter()
+            .map(|value| {
+                let value_id = value.id;
+
+                convert_value(value, to_type).map_err(|error| {
+                    PropertyMigrationError::IncompatibleValue(value_id, to_type, error)
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the ids of the properties whose name, description or renderable
+    /// type may be changed by an edit: the properties created in the edit and
+    /// every entity the edit sets, unsets or deletes one of those fields on.
+    /// Ids that aren't properties are ignored when the metadata is written.
+    pub fn map_edit_to_property_metadata(edit: &Edit) -> Vec<Uuid> {
+        let mut property_ids = Vec::new();
+        let mut seen = HashSet::new();
+
+        for op in &edit.ops {
+            let id = match &op.payload {
+                Some(Payload::CreateProperty(property)) => Some(&property.id),
+                Some(Payload::UpdateEntity(entity))
+                    if entity
+                        .values
+                        .iter()
+                        .any(|value| is_metadata_property(&value.property)) =>
+                {
+                    Some(&entity.id)
+                }
+                Some(Payload::UnsetEntityValues(unset))
+                    if unset
+                        .properties
+                        .iter()
+                        .any(|property| is_metadata_property(property)) =>
+                {
+                    Some(&unset.id)
+                }
+                Some(Payload::CreateRelation(relation))
+                    if is_metadata_property(&relation.r#type) =>
+                {
+                    Some(&relation.from_entity)
+                }
+                Some(Payload::DeleteEntity(entity)) => Some(entity),
+                _ => None,
+            };
+
+            let property_id = id.and_then(|id| id::transform_id_bytes(id.clone()).ok());
+
+            if let Some(property_id) = property_id.map(Uuid::from_bytes) {
+                if seen.insert(property_id) {
+                    property_ids.push(property_id);
+                }
+            }
+        }
+
+        property_ids
+    }
+}
+
+fn is_metadata_property(property: &[u8]) -> bool {
+    id::transform_id_bytes(property.to_vec())
+        .map(Uuid::from_bytes)
+        .is_ok_and(|property_id| {
+            [
+                NAME_PROPERTY,
+                DESCRIPTION_PROPERTY,
+                RENDERABLE_TYPE_PROPERTY,
+            ]
+            .contains(&property_id)
+        })
 }
 
 fn squash_properties(properties: &Vec<PropertyItem>) -> Vec<PropertyItem> {
@@ -185,3 +376,179 @@ fn native_type_to_data_type(native_type: i32) -> Option<DataType> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::values::ValueChangeType;
+    use wire::builder::{relation, value, EditBuilder};
+    use wire::pb::grc20::DataType as PbDataType;
+
+    fn value_op(string: Option<&str>, number: Option<f64>) -> ValueOp {
+        ValueOp {
+            id: Uuid::new_v4(),
+            change_type: ValueChangeType::SET,
+            entity_id: Uuid::new_v4(),
+            property_id: Uuid::new_v4(),
+            space_id: Uuid::new_v4(),
+            language: None,
+            unit: None,
+            options: None,
+            edit_id: None,
+            string: string.map(str::to_string),
+            number,
+            boolean: None,
+            time: None,
+            point: None,
+        }
+    }
+
+    #[test]
+    fn test_map_edit_to_property_updates_keeps_last_change() {
+        let property_id = Uuid::new_v4();
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(Uuid::new_v4(), PbDataType::Text)
+            .update_property(property_id, PbDataType::Number)
+            .update_property(property_id, PbDataType::Checkbox)
+            .build();
+
+        let updates = PropertiesModel::map_edit_to_property_updates(&edit);
+
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].id, property_id);
+        assert_eq!(updates[0].data_type, DataType::Boolean);
+    }
+
+    #[test]
+    fn test_map_edits_to_properties_keeps_first_edit() {
+        let (first, second) = (Uuid::new_v4(), Uuid::new_v4());
+
+        let edits = [
+            EditBuilder::new(Uuid::new_v4())
+                .create_property(first, PbDataType::Text)
+                .create_property(first, PbDataType::Number)
+                .build(),
+            EditBuilder::new(Uuid::new_v4())
+                .create_property(first, PbDataType::Checkbox)
+                .create_property(second, PbDataType::Time)
+                .build(),
+        ];
+
+        let mut properties = PropertiesModel::map_edits_to_properties(&edits);
+        properties.sort_by_key(|property| property.id);
+        let mut expected = vec![(first, DataType::Number), (second, DataType::Time)];
+        expected.sort_by_key(|(id, _)| *id);
+
+        assert_eq!(
+            properties
+                .iter()
+                .map(|property| (property.id, property.data_type))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_convert_values() {
+        let converted = PropertiesModel::convert_values(
+            DataType::String,
+            DataType::Number,
+            vec![value_op(Some("1.5"), None)],
+            0,
+        )
+        .unwrap();
+        assert_eq!(converted[0].number, Some(1.5));
+        assert_eq!(converted[0].string, None);
+
+        let converted = PropertiesModel::convert_values(
+            DataType::Number,
+            DataType::String,
+            vec![value_op(None, Some(42.0))],
+            0,
+        )
+        .unwrap();
+        assert_eq!(converted[0].string, Some("42".to_string()));
+        assert_eq!(converted[0].number, None);
+    }
+
+    #[test]
+    fn test_convert_values_rejects_incompatible_values() {
+        let compatible = value_op(Some("1"), None);
+        let incompatible = value_op(Some("one"), None);
+        let incompatible_id = incompatible.id;
+
+        assert!(matches!(
+            PropertiesModel::convert_values(
+                DataType::String,
+                DataType::Number,
+                vec![compatible, incompatible],
+                0,
+            ),
+            Err(PropertyMigrationError::IncompatibleValue(id, DataType::Number, _)) if id == incompatible_id
+        ));
+    }
+
+    #[test]
+    fn test_convert_values_to_and_from_relations() {
+        assert_eq!(
+            PropertiesModel::convert_values(
+                DataType::String,
+                DataType::Relation,
+                vec![value_op(Some("1"), None)],
+                0,
+            )
+            .err(),
+            Some(PropertyMigrationError::ValuesExist(1))
+        );
+        assert_eq!(
+            PropertiesModel::convert_values(DataType::Relation, DataType::String, vec![], 3).err(),
+            Some(PropertyMigrationError::RelationsExist(3))
+        );
+        assert!(
+            PropertiesModel::convert_values(DataType::Relation, DataType::Number, vec![], 0)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_map_edit_to_property_metadata() {
+        let (created, named, unset, rendered, deleted, other) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(created, PbDataType::Text)
+            .update_entity(named, [value(NAME_PROPERTY, "Name")])
+            .update_entity(created, [value(DESCRIPTION_PROPERTY, "Description")])
+            .unset_entity_values(unset, [DESCRIPTION_PROPERTY])
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                RENDERABLE_TYPE_PROPERTY,
+                rendered,
+                Uuid::new_v4(),
+            ))
+            .delete_entity(deleted)
+            .update_entity(other, [value(Uuid::new_v4(), "Unrelated")])
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                other,
+                Uuid::new_v4(),
+            ))
+            .build();
+
+        assert_eq!(
+            PropertiesModel::map_edit_to_property_metadata(&edit),
+            vec![created, named, unset, rendered, deleted]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::BTreeSet;
+
+use indexer_utils::{checksum_address, id};
+use uuid::Uuid;
+use wire::pb::grc20::{op::Payload, Edit};
+
+use crate::{KgData, ProposalType};
+
+/// A summary of what a proposed edit changes, computed from the cached edit
+/// when the proposal is created so it can be shown before it's executed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProposalPreview {
+    pub content_uri: String,
+    /// The entities whose values or relations the edit changes, or that it
+    /// deletes, in id order.
+    pub entity_ids: Vec<Uuid>,
+    /// The properties whose values the edit sets or unsets, or that it
+    /// creates or updates, in id order.
+    pub property_ids: Vec<Uuid>,
+    pub values_set: usize,
+    pub values_unset: usize,
+    pub relations_created: usize,
+    pub relations_updated: usize,
+    pub relations_deleted: usize,
+    pub entities_deleted: usize,
+}
+
+impl ProposalPreview {
+    pub fn from_edit(content_uri: &str, edit: &Edit) -> Self {
+        let mut preview = ProposalPreview {
+            content_uri: content_uri.to_string(),
+            ..Default::default()
+        };
+        let mut entity_ids = BTreeSet::new();
+        let mut property_ids = BTreeSet::new();
+
+        let touch = |ids: &mut BTreeSet<Uuid>, bytes: &[u8]| {
+            if let Ok(id) = id::transform_id_bytes(bytes.to_vec()) {
+                ids.insert(Uuid::from_bytes(id));
+            }
+        };
+
+        for op in &edit.ops {
+            match &op.payload {
+                Some(Payload::UpdateEntity(entity)) => {
+                    touch(&mut entity_ids, &entity.id);
+                    for value in &entity.values {
+                        touch(&mut property_ids, &value.property);
+                    }
+                    preview.values_set += entity.values.len();
+                }
+                Some(Payload::UnsetEntityValues(entity)) => {
+                    touch(&mut entity_ids, &entity.id);
+                    for property in &entity.properties {
+                        touch(&mut property_ids, property);
+                    }
+                    preview.values_unset += entity.properties.len();
+                }
+                Some(Payload::DeleteEntity(entity_id)) => {
+                    touch(&mut entity_ids, entity_id);
+                    preview.entities_deleted += 1;
+                }
+                Some(Payload::CreateRelation(relation)) => {
+                    touch(&mut entity_ids, &relation.from_entity);
+                    touch(&mut entity_ids, &relation.to_entity);
+                    preview.relations_created += 1;
+                }
+                Some(Payload::UpdateRelation(_)) | Some(Payload::UnsetRelationFields(_)) => {
+                    preview.relations_updated += 1;
+                }
+                Some(Payload::DeleteRelation(_)) => {
+                    preview.relations_deleted += 1;
+                }
+                Some(Payload::CreateProperty(property))
+                | Some(Payload::UpdateProperty(property)) => {
+                    touch(&mut property_ids, &property.id);
+                }
+                None => {}
+            }
+        }
+
+        preview.entity_ids = entity_ids.into_iter().collect();
+        preview.property_ids = property_ids.into_iter().collect();
+        preview
+    }
+}
+
+/// The preview of a proposal created in a space.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalPreviewItem {
+    pub space_id: Uuid,
+    pub proposal_id: String,
+    pub preview: ProposalPreview,
+}
+
+/// A proposal whose space's electorate is snapshotted when it's created.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalSnapshotItem {
+    pub space_id: Uuid,
+    pub proposal_id: String,
+}
+
+/// A proposal created in a space. Addresses are checksummed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalItem {
+    pub space_id: Uuid,
+    pub proposal_id: String,
+    pub plugin_address: String,
+    pub proposal_type: ProposalType,
+    pub creator: String,
+}
+
+/// A proposal executed in the block, named by its plugin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalExecutionItem {
+    pub plugin_address: String,
+    pub proposal_id: String,
+    pub tx_hash: String,
+}
+
+/// A proposal withdrawn or vetoed in the block, named by its plugin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProposalCancellationItem {
+    pub plugin_address: String,
+    pub proposal_id: String,
+    pub canceled_by: String,
+}
+
+pub struct ProposalsModel;
+
+impl ProposalsModel {
+    /// Returns the previews of the proposals created in the block. Only
+    /// proposals to publish an edit whose content was cached have one.
+    pub fn map_proposal_previews(data: &KgData) -> Vec<ProposalPreviewItem> {
+        data.created_proposals
+            .iter()
+            .filter_map(|proposal| {
+                Some(ProposalPreviewItem {
+                    space_id: data
+                        .network
+                        .space_id(&checksum_address(&proposal.dao_address)),
+                    proposal_id: proposal.proposal_id.clone(),
+                    preview: proposal.preview.clone()?,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the proposals created in the block, of every type, whose
+    /// electorate is snapshotted.
+    pub fn map_proposal_snapshots(data: &KgData) -> Vec<ProposalSnapshotItem> {
+        data.created_proposals
+            .iter()
+            .map(|proposal| ProposalSnapshotItem {
+                space_id: data
+                    .network
+                    .space_id(&checksum_address(&proposal.dao_address)),
+                proposal_id: proposal.proposal_id.clone(),
+            })
+            .collect()
+    }
+
+    pub fn map_proposals(data: &KgData) -> Vec<ProposalItem> {
+        data.created_proposals
+            .iter()
+            .map(|proposal| ProposalItem {
+                space_id: data
+                    .network
+                    .space_id(&checksum_address(&proposal.dao_address)),
+                proposal_id: proposal.proposal_id.clone(),
+                plugin_address: checksum_address(&proposal.plugin_address),
+                proposal_type: proposal.proposal_type,
+                creator: checksum_address(&proposal.creator),
+            })
+            .collect()
+    }
+
+    pub fn map_proposal_executions(data: &KgData) -> Vec<ProposalExecutionItem> {
+        data.executed_proposals
+            .iter()
+            .map(|proposal| ProposalExecutionItem {
+                plugin_address: checksum_address(&proposal.plugin_address),
+                proposal_id: proposal.proposal_id.clone(),
+                tx_hash: proposal.tx_hash.clone(),
+            })
+            .collect()
+    }
+
+    pub fn map_proposal_cancellations(data: &KgData) -> Vec<ProposalCancellationItem> {
+        data.canceled_proposals
+            .iter()
+            .map(|proposal| ProposalCancellationItem {
+                plugin_address: checksum_address(&proposal.plugin_address),
+                proposal_id: proposal.proposal_id.clone(),
+                canceled_by: checksum_address(&proposal.canceled_by),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::builder::{relation, relation_update, value, EditBuilder};
+    use wire::pb::grc20::DataType;
+
+    #[test]
+    fn test_preview_from_edit() {
+        let (alice, bob, carol) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (name, age, friend) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let relation_id = Uuid::new_v4();
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(age, DataType::Number)
+            .update_entity(alice, [value(name, "Alice"), value(age, "42")])
+            .unset_entity_values(bob, [name])
+            .create_relation(relation(relation_id, Uuid::new_v4(), friend, alice, bob))
+            .update_relation(relation_update(relation_id))
+            .delete_relation(Uuid::new_v4())
+            .delete_entity(carol)
+            .build();
+
+        let preview = ProposalPreview::from_edit("ipfs://proposed", &edit);
+
+        let mut entity_ids = vec![alice, bob, carol];
+        entity_ids.sort();
+        let mut property_ids = vec![name, age];
+        property_ids.sort();
+        assert_eq!(
+            preview,
+            ProposalPreview {
+                content_uri: "ipfs://proposed".to_string(),
+                entity_ids,
+                property_ids,
+                values_set: 2,
+                values_unset: 1,
+                relations_created: 1,
+                relations_updated: 1,
+                relations_deleted: 1,
+                entities_deleted: 1,
+            }
+        );
+    }
+}
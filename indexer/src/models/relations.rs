@@ -5,6 +5,8 @@ use tracing::{debug, instrument, warn};
 use uuid::Uuid;
 use wire::pb::grc20::{op::Payload, Edit};
 
+use crate::models::entities::deleted_entity_id;
+
 #[derive(Clone, Debug)]
 pub struct SetRelationItem {
     pub id: Uuid,
@@ -19,6 +21,23 @@ pub struct SetRelationItem {
     pub position: Option<String>,
     pub space_id: Uuid,
     pub verified: Option<bool>,
+    /// The id of the edit that created the relation, so it can be traced
+    /// back to the edit's row in `edits`.
+    pub edit_id: Option<Uuid>,
+}
+
+impl SetRelationItem {
+    /// The space the from entity is resolved in. Relations without a
+    /// from_space point into their own space.
+    pub fn from_space(&self) -> Uuid {
+        resolve_space(&self.from_space_id, &self.space_id)
+    }
+
+    /// The space the to entity is resolved in. Relations without a to_space
+    /// point into their own space.
+    pub fn to_space(&self) -> Uuid {
+        resolve_space(&self.to_space_id, &self.space_id)
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -204,6 +223,9 @@ impl RelationsModel {
         Vec<Uuid>,
     ) {
         let mut relations = Vec::new();
+        let edit_id = id::transform_id_bytes(edit.id.clone())
+            .ok()
+            .map(Uuid::from_bytes);
 
         for op in &edit.ops {
             if let Some(op_type) = &op.payload {
@@ -265,29 +287,13 @@ impl RelationsModel {
                         let from_id = Uuid::from_bytes(from_id_bytes.unwrap());
                         let to_id = Uuid::from_bytes(to_id_bytes.unwrap());
 
-                        let to_space = relation
-                            .to_space
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
-
-                        let from_space = relation
-                            .from_space
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
-
-                        let from_version = relation
-                            .from_version
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
-
-                        let to_version = relation
-                            .to_version
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
+                        let to_space = optional_id(&relation.to_space, "to_space");
+
+                        let from_space = optional_id(&relation.from_space, "from_space");
+
+                        let from_version = optional_id(&relation.from_version, "from_version");
+
+                        let to_version = optional_id(&relation.to_version, "to_version");
 
                         relations.push(RelationItem::Create(SetRelationItem {
                             id: relation_id,
@@ -302,6 +308,7 @@ impl RelationsModel {
                             to_space_id: to_space,
                             to_version_id: to_version,
                             verified: relation.verified,
+                            edit_id,
                         }));
                     }
                     Payload::DeleteRelation(relation_id) => {
@@ -335,29 +342,14 @@ impl RelationsModel {
 
                         let relation_id = Uuid::from_bytes(relation_id_bytes.unwrap());
 
-                        let to_space = updated_relation
-                            .to_space
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
-
-                        let from_space = updated_relation
-                            .from_space
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
-
-                        let from_version = updated_relation
-                            .from_version
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
-
-                        let to_version = updated_relation
-                            .to_version
-                            .clone()
-                            .and_then(|s| id::transform_id_bytes(s).ok())
-                            .map(|s| Uuid::from_bytes(s).to_string());
+                        let to_space = optional_id(&updated_relation.to_space, "to_space");
+
+                        let from_space = optional_id(&updated_relation.from_space, "from_space");
+
+                        let from_version =
+                            optional_id(&updated_relation.from_version, "from_version");
+
+                        let to_version = optional_id(&updated_relation.to_version, "to_version");
 
                         relations.push(RelationItem::Update(UpdateRelationItem {
                             id: relation_id,
@@ -394,6 +386,51 @@ impl RelationsModel {
                             verified: unset_fields.verified,
                         }));
                     }
+                    Payload::DeleteEntity(_) => {
+                        let Some(entity_id) = deleted_entity_id(op) else {
+                            continue;
+                        };
+
+                        // Relations from or to the entity that already exist are
+                        // deleted in storage. Relations created earlier in this
+                        // edit are deleted here so the squash drops them. A
+                        // relation created again since only counts with the
+                        // entities it was last created with.
+                        let mut endpoints: HashMap<Uuid, Option<(Uuid, Uuid)>> = HashMap::new();
+                        for relation in &relations {
+                            match relation {
+                                RelationItem::Create(relation) => {
+                                    endpoints.insert(
+                                        relation.id,
+                                        Some((relation.from_id, relation.to_id)),
+                                    );
+                                }
+                                RelationItem::Delete(relation) => {
+                                    endpoints.insert(relation.id, None);
+                                }
+                                _ => {}
+                            }
+                        }
+
+                        let created_ids: Vec<Uuid> = endpoints
+                            .into_iter()
+                            .filter_map(|(relation_id, endpoints)| match endpoints {
+                                Some((from_id, to_id))
+                                    if from_id == entity_id || to_id == entity_id =>
+                                {
+                                    Some(relation_id)
+                                }
+                                _ => None,
+                            })
+                            .collect();
+
+                        for relation_id in created_ids {
+                            relations.push(RelationItem::Delete(DeleteRelationItem {
+                                id: relation_id,
+                                space_id: *space_id,
+                            }));
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -446,244 +483,216 @@ impl RelationsModel {
             delete_relations,
         );
     }
+
+    /// Returns the spaces other than their own that the given relations
+    /// point into, in the order they are first referenced.
+    pub fn referenced_spaces(
+        set_relations: &[SetRelationItem],
+        update_relations: &[UpdateRelationItem],
+    ) -> Vec<Uuid> {
+        let references = set_relations
+            .iter()
+            .flat_map(|relation| {
+                [&relation.from_space_id, &relation.to_space_id]
+                    .map(|space| (space, relation.space_id))
+            })
+            .chain(update_relations.iter().flat_map(|relation| {
+                [&relation.from_space_id, &relation.to_space_id]
+                    .map(|space| (space, relation.space_id))
+            }));
+
+        let mut spaces = Vec::new();
+
+        for (space, own_space) in references {
+            let Some(space) = space.as_deref().and_then(|id| Uuid::parse_str(id).ok()) else {
+                continue;
+            };
+
+            if space != own_space && !spaces.contains(&space) {
+                spaces.push(space);
+            }
+        }
+
+        spaces
+    }
+}
+
+/// Derives the id of an optional relation field the same way as the
+/// relation's required ids. Malformed ids are dropped rather than failing the
+/// relation.
+fn optional_id(bytes: &Option<Vec<u8>>, field: &str) -> Option<String> {
+    let bytes = bytes.as_ref()?;
+
+    match id::transform_id_bytes(bytes.clone()) {
+        Ok(id) => Some(Uuid::from_bytes(id).to_string()),
+        Err(_) => {
+            warn!(
+                bytes = ?bytes,
+                field,
+                "[Relations] Could not transform Vec<u8> for optional relation id"
+            );
+            None
+        }
+    }
+}
+
+fn resolve_space(space_id: &Option<String>, default: &Uuid) -> Uuid {
+    space_id
+        .as_deref()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .unwrap_or(*default)
+}
+
+/// The state of a relation after some of an edit's ops were applied to it.
+enum SquashedRelation {
+    Create(SetRelationItem),
+    /// Changes to a relation created before the edit. The update and the
+    /// unset never touch the same field, so they can be written in either
+    /// order.
+    Change(Option<UpdateRelationItem>, Option<UnsetRelationItem>),
+    Delete(DeleteRelationItem),
 }
 
-fn squash_relations(relation_ops: &Vec<RelationItem>) -> Vec<RelationItem> {
-    let mut hash: HashMap<Uuid, RelationItem> = HashMap::new();
+/// Squashes the ops of an edit into at most a create, an update and an unset,
+/// or a delete per relation, in the order each relation is first referenced.
+///
+/// Later ops win. A create replaces everything before it, including a
+/// delete, and a delete drops everything before it. Updates and unsets are
+/// merged into a create before them, and otherwise into each other field by
+/// field. An update or unset after a delete is dropped, as the relation no
+/// longer exists.
+fn squash_relations(relation_ops: &[RelationItem]) -> Vec<RelationItem> {
+    let mut order: Vec<Uuid> = Vec::new();
+    let mut squashed: HashMap<Uuid, SquashedRelation> = HashMap::new();
 
     for op in relation_ops {
-        let seen = hash.get(op.id());
-        if let Some(existing) = seen {
-            let merged = match (existing.clone(), op.clone()) {
-                // create -> create: Overwrite with 2nd create enum
-                (RelationItem::Create { .. }, RelationItem::Create { .. }) => op.clone(),
-
-                // create -> update: Attempt to merge optional fields into a create enum
-                (
-                    RelationItem::Create(SetRelationItem {
-                        id,
-                        entity_id,
-                        type_id,
-                        from_id,
-                        to_id,
-                        from_space_id: e_from_space_id,
-                        from_version_id: e_from_version_id,
-                        to_space_id: e_to_space_id,
-                        to_version_id: e_to_version_id,
-                        position: e_position,
-                        verified: e_verified,
-                        ..
-                    }),
-                    RelationItem::Update(UpdateRelationItem {
-                        from_space_id: u_from_space_id,
-                        from_version_id: u_from_version_id,
-                        to_space_id: u_to_space_id,
-                        to_version_id: u_to_version_id,
-                        position: u_position,
-                        space_id: u_space_id,
-                        verified: u_verified,
-                        ..
-                    }),
-                ) => RelationItem::Create(SetRelationItem {
-                    id,
-                    entity_id,
-                    type_id,
-                    from_id,
-                    to_id,
-                    from_space_id: u_from_space_id.or(e_from_space_id),
-                    from_version_id: u_from_version_id.or(e_from_version_id),
-                    to_space_id: u_to_space_id.or(e_to_space_id),
-                    to_version_id: u_to_version_id.or(e_to_version_id),
-                    position: u_position.or(e_position),
-                    space_id: u_space_id,
-                    verified: u_verified.or(e_verified),
-                }),
-
-                // create -> delete: Overwrite with delete enum
-                (RelationItem::Create { .. }, RelationItem::Delete { .. }) => op.clone(),
-
-                // create -> unset: Attempt to merge optional fields into create enum
-                (
-                    RelationItem::Create(SetRelationItem {
-                        id,
-                        entity_id,
-                        type_id,
-                        from_id,
-                        to_id,
-                        from_space_id: e_from_space_id,
-                        from_version_id: e_from_version_id,
-                        to_space_id: e_to_space_id,
-                        to_version_id: e_to_version_id,
-                        position: e_position,
-                        verified: e_verified,
-                        ..
-                    }),
-                    RelationItem::Unset(UnsetRelationItem {
-                        from_space_id: u_from_space_id,
-                        from_version_id: u_from_version_id,
-                        to_space_id: u_to_space_id,
-                        to_version_id: u_to_version_id,
-                        position: u_position,
-                        space_id: u_space_id,
-                        verified: u_verified,
-                        ..
-                    }),
-                ) => RelationItem::Create(SetRelationItem {
-                    id,
-                    entity_id,
-                    type_id,
-                    from_id,
-                    to_id,
-                    from_space_id: if u_from_space_id == Some(true) {
-                        None
-                    } else {
-                        e_from_space_id
-                    },
-                    from_version_id: if u_from_version_id == Some(true) {
-                        None
-                    } else {
-                        e_from_version_id
-                    },
-                    to_space_id: if u_to_space_id == Some(true) {
-                        None
-                    } else {
-                        e_to_space_id
-                    },
-                    to_version_id: if u_to_version_id == Some(true) {
-                        None
-                    } else {
-                        e_to_version_id
-                    },
-                    position: if u_position == Some(true) {
-                        None
-                    } else {
-                        e_position
-                    },
-                    space_id: u_space_id,
-                    verified: if u_verified == Some(true) {
-                        None
-                    } else {
-                        e_verified
-                    },
-                }),
-
-                // update -> create: Overwrite with create enum
-                (RelationItem::Update { .. }, RelationItem::Create { .. }) => op.clone(),
-
-                // update -> update: Attempt to merge optional fields into update enum
-                (
-                    RelationItem::Update(UpdateRelationItem {
-                        id,
-                        from_space_id: e_from_space_id,
-                        from_version_id: e_from_version_id,
-                        to_space_id: e_to_space_id,
-                        to_version_id: e_to_version_id,
-                        position: e_position,
-                        verified: e_verified,
-                        ..
-                    }),
-                    RelationItem::Update(UpdateRelationItem {
-                        from_space_id: u_from_space_id,
-                        from_version_id: u_from_version_id,
-                        to_space_id: u_to_space_id,
-                        to_version_id: u_to_version_id,
-                        position: u_position,
-                        space_id: u_space_id,
-                        verified: u_verified,
-                        ..
-                    }),
-                ) => RelationItem::Update(UpdateRelationItem {
-                    id,
-                    from_space_id: u_from_space_id.or(e_from_space_id),
-                    from_version_id: u_from_version_id.or(e_from_version_id),
-                    to_space_id: u_to_space_id.or(e_to_space_id),
-                    to_version_id: u_to_version_id.or(e_to_version_id),
-                    position: u_position.or(e_position),
-                    space_id: u_space_id,
-                    verified: u_verified.or(e_verified),
-                }),
-
-                // update -> delete: Overwrite with delete enum
-                (RelationItem::Update { .. }, RelationItem::Delete { .. }) => op.clone(),
-
-                // update -> unset: Attempt to merge optional fields into update enum
-                (
-                    RelationItem::Update(UpdateRelationItem {
-                        id,
-                        from_space_id: e_from_space_id,
-                        from_version_id: e_from_version_id,
-                        to_space_id: e_to_space_id,
-                        to_version_id: e_to_version_id,
-                        position: e_position,
-                        verified: e_verified,
-                        ..
-                    }),
-                    RelationItem::Unset(UnsetRelationItem {
-                        from_space_id: u_from_space_id,
-                        from_version_id: u_from_version_id,
-                        to_space_id: u_to_space_id,
-                        to_version_id: u_to_version_id,
-                        position: u_position,
-                        space_id: u_space_id,
-                        verified: u_verified,
-                        ..
-                    }),
-                ) => RelationItem::Update(UpdateRelationItem {
-                    id,
-                    from_space_id: if u_from_space_id == Some(true) {
-                        None
-                    } else {
-                        e_from_space_id
-                    },
-                    from_version_id: if u_from_version_id == Some(true) {
-                        None
-                    } else {
-                        e_from_version_id
-                    },
-                    to_space_id: if u_to_space_id == Some(true) {
-                        None
-                    } else {
-                        e_to_space_id
-                    },
-                    to_version_id: if u_to_version_id == Some(true) {
-                        None
-                    } else {
-                        e_to_version_id
-                    },
-                    position: if u_position == Some(true) {
-                        None
-                    } else {
-                        e_position
-                    },
-                    space_id: u_space_id,
-                    verified: if u_verified == Some(true) {
-                        None
-                    } else {
-                        e_verified
-                    },
-                }),
-
-                // delete -> create: Overwrite with create enum
-                (RelationItem::Delete { .. }, RelationItem::Create { .. }) => op.clone(),
-
-                // delete -> update: Overwrite with update enum
-                (RelationItem::Delete { .. }, RelationItem::Update { .. }) => op.clone(),
-
-                // delete -> unset: Keep delete enum (unset on deleted item is no-op)
-                (RelationItem::Delete { .. }, RelationItem::Unset { .. }) => existing.clone(),
-
-                // delete -> delete: Skip (keep existing delete)
-                (RelationItem::Delete { .. }, RelationItem::Delete { .. }) => existing.clone(),
-
-                // Handle any remaining combinations (shouldn't occur with proper enum design)
-                _ => op.clone(),
-            };
+        let existing = squashed.remove(op.id());
+        if existing.is_none() {
+            order.push(*op.id());
+        }
+
+        let merged = match (existing, op.clone()) {
+            (_, RelationItem::Create(create)) => SquashedRelation::Create(create),
+            (_, RelationItem::Delete(delete)) => SquashedRelation::Delete(delete),
+
+            (Some(SquashedRelation::Delete(delete)), _) => SquashedRelation::Delete(delete),
+
+            (Some(SquashedRelation::Create(mut create)), RelationItem::Update(update)) => {
+                create.from_space_id = update.from_space_id.or(create.from_space_id);
+                create.from_version_id = update.from_version_id.or(create.from_version_id);
+                create.to_space_id = update.to_space_id.or(create.to_space_id);
+                create.to_version_id = update.to_version_id.or(create.to_version_id);
+                create.position = update.position.or(create.position);
+                create.verified = update.verified.or(create.verified);
+                SquashedRelation::Create(create)
+            }
+            (Some(SquashedRelation::Create(mut create)), RelationItem::Unset(unset)) => {
+                clear_unset(&mut create.from_space_id, unset.from_space_id);
+                clear_unset(&mut create.from_version_id, unset.from_version_id);
+                clear_unset(&mut create.to_space_id, unset.to_space_id);
+                clear_unset(&mut create.to_version_id, unset.to_version_id);
+                clear_unset(&mut create.position, unset.position);
+                clear_unset(&mut create.verified, unset.verified);
+                SquashedRelation::Create(create)
+            }
+
+            (None, RelationItem::Update(update)) => SquashedRelation::Change(Some(update), None),
+            (None, RelationItem::Unset(unset)) => SquashedRelation::Change(None, Some(unset)),
+
+            (Some(SquashedRelation::Change(existing, unset)), RelationItem::Update(update)) => {
+                let update = match existing {
+                    Some(mut existing) => {
+                        existing.from_space_id = update.from_space_id.or(existing.from_space_id);
+                        existing.from_version_id =
+                            update.from_version_id.or(existing.from_version_id);
+                        existing.to_space_id = update.to_space_id.or(existing.to_space_id);
+                        existing.to_version_id = update.to_version_id.or(existing.to_version_id);
+                        existing.position = update.position.or(existing.position);
+                        existing.verified = update.verified.or(existing.verified);
+                        existing
+                    }
+                    None => update,
+                };
+
+                // Fields set again are no longer unset
+                let unset = unset.map(|mut unset| {
+                    keep_unset(&mut unset.from_space_id, &update.from_space_id);
+                    keep_unset(&mut unset.from_version_id, &update.from_version_id);
+                    keep_unset(&mut unset.to_space_id, &update.to_space_id);
+                    keep_unset(&mut unset.to_version_id, &update.to_version_id);
+                    keep_unset(&mut unset.position, &update.position);
+                    keep_unset(&mut unset.verified, &update.verified);
+                    unset
+                });
+
+                SquashedRelation::Change(Some(update), unset)
+            }
+            (Some(SquashedRelation::Change(update, existing)), RelationItem::Unset(unset)) => {
+                let unset = match existing {
+                    Some(mut existing) => {
+                        merge_unset(&mut existing.from_space_id, unset.from_space_id);
+                        merge_unset(&mut existing.from_version_id, unset.from_version_id);
+                        merge_unset(&mut existing.to_space_id, unset.to_space_id);
+                        merge_unset(&mut existing.to_version_id, unset.to_version_id);
+                        merge_unset(&mut existing.position, unset.position);
+                        merge_unset(&mut existing.verified, unset.verified);
+                        existing
+                    }
+                    None => unset,
+                };
+
+                // Fields unset afterwards are no longer updated
+                let update = update.map(|mut update| {
+                    clear_unset(&mut update.from_space_id, unset.from_space_id);
+                    clear_unset(&mut update.from_version_id, unset.from_version_id);
+                    clear_unset(&mut update.to_space_id, unset.to_space_id);
+                    clear_unset(&mut update.to_version_id, unset.to_version_id);
+                    clear_unset(&mut update.position, unset.position);
+                    clear_unset(&mut update.verified, unset.verified);
+                    update
+                });
+
+                SquashedRelation::Change(update, Some(unset))
+            }
+        };
+
+        squashed.insert(*op.id(), merged);
+    }
 
-            hash.insert(*op.id(), merged);
-        } else {
-            hash.insert(*op.id(), op.clone());
+    let mut relations = Vec::with_capacity(order.len());
+
+    for relation_id in order {
+        match squashed.remove(&relation_id) {
+            Some(SquashedRelation::Create(create)) => relations.push(RelationItem::Create(create)),
+            Some(SquashedRelation::Change(update, unset)) => {
+                relations.extend(update.map(RelationItem::Update));
+                relations.extend(unset.map(RelationItem::Unset));
+            }
+            Some(SquashedRelation::Delete(delete)) => relations.push(RelationItem::Delete(delete)),
+            None => {}
         }
     }
 
-    hash.into_values().collect()
+    relations
+}
+
+/// Clears a field if an unset op unsets it.
+fn clear_unset<T>(field: &mut Option<T>, unset: Option<bool>) {
+    if unset == Some(true) {
+        *field = None;
+    }
+}
+
+/// Keeps unsetting a field only if a later update doesn't set it.
+fn keep_unset<T>(unset: &mut Option<bool>, update: &Option<T>) {
+    if update.is_some() {
+        *unset = None;
+    }
+}
+
+/// Unsets a field if either unset op unsets it.
+fn merge_unset(existing: &mut Option<bool>, unset: Option<bool>) {
+    if unset == Some(true) {
+        *existing = Some(true);
+    }
 }
@@ -1,6 +1,7 @@
 use std::vec;
 use uuid::Uuid;
 
+use wire::builder::{relation, relation_update, EditBuilder};
 use wire::pb::grc20::op::Payload;
 use wire::pb::grc20::{Edit, Op, Relation, RelationUpdate, UnsetRelationFields};
 
@@ -174,6 +175,10 @@ mod tests {
         assert_eq!(deleted_relations.len(), 0);
 
         verify_set_relation(&set_relations[0], &space_id);
+        assert_eq!(
+            set_relations[0].edit_id,
+            Some(Uuid::parse_str("78901234-1234-4012-8def-123456789012").unwrap())
+        );
     }
 
     #[test]
@@ -636,12 +641,12 @@ mod tests {
         let (set_relations, update_relations, unset_relations, deleted_relations) =
             RelationsModel::map_edit_to_relations(&edit, &space_id);
 
+        // The unset fields are dropped from the update and still unset
         assert_eq!(set_relations.len(), 0);
         assert_eq!(update_relations.len(), 1);
-        assert_eq!(unset_relations.len(), 0);
+        assert_eq!(unset_relations.len(), 1);
         assert_eq!(deleted_relations.len(), 0);
 
-        // Verify the update relation has the fields unset
         assert_eq!(
             update_relations[0].id,
             Uuid::parse_str("12345678-1234-4012-8def-123456789012").unwrap()
@@ -653,6 +658,12 @@ mod tests {
             Some("12345670-1234-4012-8def-123456789012".to_string())
         ); // Unchanged
         assert_eq!(update_relations[0].verified, Some(false)); // Unchanged
+
+        assert_eq!(unset_relations[0].id, update_relations[0].id);
+        assert_eq!(unset_relations[0].from_space_id, Some(true));
+        assert_eq!(unset_relations[0].position, Some(true));
+        assert_eq!(unset_relations[0].to_space_id, None);
+        assert_eq!(unset_relations[0].verified, None);
     }
 
     #[test]
@@ -748,20 +759,116 @@ mod tests {
         let (set_relations, update_relations, unset_relations, deleted_relations) =
             RelationsModel::map_edit_to_relations(&edit, &space_id);
 
+        // The fields set again are no longer unset, the others still are
         assert_eq!(set_relations.len(), 0);
         assert_eq!(update_relations.len(), 1);
-        assert_eq!(unset_relations.len(), 0);
+        assert_eq!(unset_relations.len(), 1);
         assert_eq!(deleted_relations.len(), 0);
 
-        // Verify the update has values from the unset operation and the update operation
         assert_eq!(
             update_relations[0].id,
             Uuid::parse_str("12345678-1234-4012-8def-123456789012").unwrap()
         );
-        assert_eq!(update_relations[0].from_space_id, None); // Unset by previous operation
-        assert_eq!(update_relations[0].to_space_id, None); // Unset by previous operation
+        assert_eq!(update_relations[0].from_space_id, None); // Not updated
+        assert_eq!(update_relations[0].to_space_id, None); // Not updated
         assert_eq!(update_relations[0].position, Some("new_pos".to_string())); // Set by update
         assert_eq!(update_relations[0].verified, Some(true)); // Set by update
+
+        assert_eq!(unset_relations[0].id, update_relations[0].id);
+        assert_eq!(unset_relations[0].from_space_id, Some(true)); // Still unset
+        assert_eq!(unset_relations[0].to_space_id, Some(true)); // Still unset
+        assert_eq!(unset_relations[0].position, None); // Set again by update
+        assert_eq!(unset_relations[0].verified, None);
+    }
+
+    #[test]
+    fn test_squash_delete_then_update() {
+        let update_op = create_edit_with_update_relation().ops[0].clone();
+        let unset_op = create_edit_with_unset_relation().ops[0].clone();
+        let delete_op = create_edit_with_delete_relation().ops[0].clone();
+
+        let edit = Edit {
+            ops: vec![delete_op, update_op, unset_op],
+            ..create_edit_with_delete_relation()
+        };
+
+        let space_id = Uuid::parse_str("87654321-4321-4321-4321-876543210987").unwrap();
+        let (set_relations, update_relations, unset_relations, deleted_relations) =
+            RelationsModel::map_edit_to_relations(&edit, &space_id);
+
+        // The relation no longer exists, so it isn't updated or unset
+        assert_eq!(set_relations.len(), 0);
+        assert_eq!(update_relations.len(), 0);
+        assert_eq!(unset_relations.len(), 0);
+        assert_eq!(
+            deleted_relations,
+            vec![Uuid::parse_str("12345678-1234-4012-8def-123456789012").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_squash_unset_then_unset() {
+        let unset_op = create_edit_with_unset_relation().ops[0].clone();
+        let unset_verified_op = Op {
+            payload: Some(Payload::UnsetRelationFields(UnsetRelationFields {
+                id: bytes("12345678-1234-4012-8def-123456789012"),
+                from_space: Some(false),
+                to_space: None,
+                position: None,
+                verified: Some(true),
+                from_version: None,
+                to_version: None,
+            })),
+        };
+
+        let edit = Edit {
+            ops: vec![unset_op, unset_verified_op],
+            ..create_edit_with_unset_relation()
+        };
+
+        let space_id = Uuid::parse_str("87654321-4321-4321-4321-876543210987").unwrap();
+        let (_, update_relations, unset_relations, _) =
+            RelationsModel::map_edit_to_relations(&edit, &space_id);
+
+        // Fields unset by either op are unset
+        assert_eq!(update_relations.len(), 0);
+        assert_eq!(unset_relations.len(), 1);
+        assert_eq!(unset_relations[0].from_space_id, Some(true));
+        assert_eq!(unset_relations[0].to_space_id, Some(true));
+        assert_eq!(unset_relations[0].position, Some(true));
+        assert_eq!(unset_relations[0].verified, Some(true));
+    }
+
+    #[test]
+    fn test_squash_keeps_edit_order() {
+        let ids: Vec<Uuid> = (0..16).map(|_| Uuid::new_v4()).collect();
+        let (entity, relation_type) = (Uuid::new_v4(), Uuid::new_v4());
+
+        // Every relation is created, then updated in reverse order
+        let mut builder = EditBuilder::new(Uuid::new_v4());
+        for id in &ids {
+            builder = builder.create_relation(relation(*id, entity, relation_type, entity, entity));
+        }
+        for id in ids.iter().rev() {
+            builder = builder.update_relation(RelationUpdate {
+                position: Some("a".to_string()),
+                ..relation_update(*id)
+            });
+        }
+        let edit = builder.build();
+
+        let (set_relations, update_relations, _, _) =
+            RelationsModel::map_edit_to_relations(&edit, &Uuid::new_v4());
+
+        // Relations keep the order of their first op
+        assert_eq!(update_relations.len(), 0);
+        assert_eq!(
+            set_relations.iter().map(|relation| relation.id).collect::<Vec<_>>(),
+            ids
+        );
+        assert!(set_relations
+            .iter()
+            .all(|relation| relation.position == Some("a".to_string())));
     }
 
     #[test]
@@ -1217,4 +1324,133 @@ mod tests {
         assert_eq!(rel3.verified, None);
         assert_eq!(rel3.space_id, space_id);
     }
+
+    #[test]
+    fn test_delete_entity_deletes_relations_created_before_it() {
+        let space_id = Uuid::new_v4();
+        let (entity, other_entity, relation_type) =
+            (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let (before, unrelated, after) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_relation(relation(
+                before,
+                Uuid::new_v4(),
+                relation_type,
+                other_entity,
+                entity,
+            ))
+            .create_relation(relation(
+                unrelated,
+                Uuid::new_v4(),
+                relation_type,
+                other_entity,
+                other_entity,
+            ))
+            .delete_entity(entity)
+            .create_relation(relation(
+                after,
+                Uuid::new_v4(),
+                relation_type,
+                entity,
+                other_entity,
+            ))
+            .build();
+
+        let (set_relations, update_relations, unset_relations, delete_relations) =
+            RelationsModel::map_edit_to_relations(&edit, &space_id);
+
+        let mut set_ids: Vec<Uuid> = set_relations.iter().map(|r| r.id).collect();
+        set_ids.sort();
+        let mut expected = vec![unrelated, after];
+        expected.sort();
+
+        assert_eq!(set_ids, expected);
+        assert_eq!(delete_relations, vec![before]);
+        assert!(update_relations.is_empty());
+        assert!(unset_relations.is_empty());
+    }
+
+    #[test]
+    fn test_delete_entity_keeps_relations_created_again_without_it() {
+        let space_id = Uuid::new_v4();
+        let (entity, other_entity, relation_type) =
+            (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let relation_id = Uuid::new_v4();
+
+        // The second create replaces the relation to the deleted entity
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_relation(relation(
+                relation_id,
+                Uuid::new_v4(),
+                relation_type,
+                other_entity,
+                entity,
+            ))
+            .create_relation(relation(
+                relation_id,
+                Uuid::new_v4(),
+                relation_type,
+                other_entity,
+                other_entity,
+            ))
+            .delete_entity(entity)
+            .build();
+
+        let (set_relations, _, _, delete_relations) =
+            RelationsModel::map_edit_to_relations(&edit, &space_id);
+
+        assert_eq!(set_relations.len(), 1);
+        assert_eq!(set_relations[0].to_id, other_entity);
+        assert!(delete_relations.is_empty());
+    }
+
+    #[test]
+    fn test_relation_spaces_default_to_own_space() {
+        let edit = create_edit_with_create_relation();
+        let space_id = Uuid::parse_str("87654321-4321-4321-4321-876543210987").unwrap();
+        let (set_relations, _, _, _) = RelationsModel::map_edit_to_relations(&edit, &space_id);
+
+        assert_eq!(set_relations[0].from_space(), space_id);
+        assert_eq!(
+            set_relations[0].to_space(),
+            Uuid::parse_str("67890123-1234-4012-8def-123456789012").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_malformed_relation_space_is_dropped() {
+        let mut edit = create_edit_with_create_relation();
+
+        if let Some(Payload::CreateRelation(relation)) = &mut edit.ops[0].payload {
+            relation.to_space = Some(vec![1, 2, 3]);
+        }
+
+        let space_id = Uuid::parse_str("87654321-4321-4321-4321-876543210987").unwrap();
+        let (set_relations, _, _, _) = RelationsModel::map_edit_to_relations(&edit, &space_id);
+
+        assert_eq!(set_relations.len(), 1);
+        assert_eq!(set_relations[0].to_space_id, None);
+        assert_eq!(set_relations[0].to_space(), space_id);
+    }
+
+    #[test]
+    fn test_referenced_spaces() {
+        let space_id = Uuid::parse_str("87654321-4321-4321-4321-876543210987").unwrap();
+        let (set_relations, _, _, _) =
+            RelationsModel::map_edit_to_relations(&create_edit_with_create_relation(), &space_id);
+        let (_, mut update_relations, _, _) =
+            RelationsModel::map_edit_to_relations(&create_edit_with_update_relation(), &space_id);
+
+        // References to the relation's own space aren't cross-space
+        update_relations[0].from_space_id = Some(space_id.to_string());
+
+        assert_eq!(
+            RelationsModel::referenced_spaces(&set_relations, &update_relations),
+            vec![
+                Uuid::parse_str("67890123-1234-4012-8def-123456789012").unwrap(),
+                Uuid::parse_str("12345670-1234-4012-8def-123456789012").unwrap(),
+            ]
+        );
+    }
 }
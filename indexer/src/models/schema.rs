@@ -0,0 +1,78 @@
+use uuid::Uuid;
+
+use crate::models::properties::{DataType, PropertyMetadata};
+
+/// A type and the properties its entities are expected to have in a space.
+///
+/// Types are entities with a types relation to the schema type, and a type's
+/// properties are the targets of its properties relations. Both are regular
+/// relations, so a type's schema can differ between spaces.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TypeSchema {
+    pub id: Uuid,
+    pub space_id: Uuid,
+    pub name: Option<String>,
+    /// In the order of the type's properties relations.
+    pub properties: Vec<SchemaProperty>,
+}
+
+/// A property attached to a type.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SchemaProperty {
+    pub id: Uuid,
+    pub data_type: DataType,
+    pub metadata: PropertyMetadata,
+}
+
+impl TypeSchema {
+    pub fn property(&self, property_id: &Uuid) -> Option<&SchemaProperty> {
+        self.properties
+            .iter()
+            .find(|property| property.id == *property_id)
+    }
+
+    /// Returns the ids of the given properties that aren't part of the schema.
+    pub fn unexpected_properties<'a>(
+        &self,
+        property_ids: impl IntoIterator<Item = &'a Uuid>,
+    ) -> Vec<Uuid> {
+        property_ids
+            .into_iter()
+            .filter(|property_id| self.property(property_id).is_none())
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema(property_ids: &[Uuid]) -> TypeSchema {
+        TypeSchema {
+            id: Uuid::new_v4(),
+            space_id: Uuid::new_v4(),
+            name: Some("Person".to_string()),
+            properties: property_ids
+                .iter()
+                .map(|id| SchemaProperty {
+                    id: *id,
+                    data_type: DataType::String,
+                    metadata: PropertyMetadata::default(),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_unexpected_properties() {
+        let (expected, unexpected) = (Uuid::new_v4(), Uuid::new_v4());
+        let schema = schema(&[expected]);
+
+        assert!(schema.property(&expected).is_some());
+        assert_eq!(
+            schema.unexpected_properties(&[expected, unexpected]),
+            vec![unexpected]
+        );
+    }
+}
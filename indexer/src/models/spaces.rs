@@ -1,7 +1,11 @@
-use indexer_utils::{checksum_address, id::derive_space_id, network_ids::GEO};
+use indexer_utils::system_ids::{
+    COVER_PROPERTY, DESCRIPTION_PROPERTY, IMAGE_URL_PROPERTY, NAME_PROPERTY, TYPES_PROPERTY,
+};
+use indexer_utils::{checksum_address, id, network_ids::Network};
 use uuid::Uuid;
+use wire::pb::grc20::{op::Payload, Edit};
 
-use crate::CreatedSpace;
+use crate::{ChangedGovernancePlugins, ChangedPersonalAdmin, CreatedSpace};
 
 #[derive(Clone, Debug)]
 pub enum SpaceType {
@@ -18,19 +22,51 @@ pub struct SpaceItem {
     pub voting_address: Option<String>,
     pub membership_address: Option<String>,
     pub personal_address: Option<String>,
+    pub network: String,
+    pub chain_id: Option<u64>,
+    pub creator: Option<String>,
+    pub tx_hash: Option<String>,
+}
+
+/// The display metadata of a space, read from the values of the space's
+/// own entity in the space.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpaceMetadata {
+    pub entity_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    /// The URL of the space entity's cover image.
+    pub cover: Option<String>,
+}
+
+/// An admin plugin installed in a personal space. Addresses are checksummed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PersonalAdminItem {
+    pub space_id: Uuid,
+    pub personal_address: String,
+    pub admin_address: Option<String>,
+}
+
+/// The voting and membership plugins installed in a public space. Addresses
+/// are checksummed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GovernancePluginsItem {
+    pub space_id: Uuid,
+    pub voting_address: String,
+    pub membership_address: String,
 }
 
 pub struct SpacesModel;
 
 impl SpacesModel {
-    pub fn map_created_spaces(spaces: &Vec<CreatedSpace>) -> Vec<SpaceItem> {
+    pub fn map_created_spaces(spaces: &Vec<CreatedSpace>, network: &Network) -> Vec<SpaceItem> {
         let mut created_spaces = Vec::new();
 
         for space in spaces {
             let space_item = match space {
                 CreatedSpace::Personal(personal) => {
                     let space_id =
-                        derive_space_id(GEO, &checksum_address(personal.dao_address.clone()));
+                        network.space_id(&checksum_address(personal.dao_address.clone()));
 
                     SpaceItem {
                         id: space_id,
@@ -40,11 +76,14 @@ impl SpacesModel {
                         voting_address: None,
                         membership_address: None,
                         personal_address: Some(checksum_address(personal.personal_plugin.clone())),
+                        network: network.id.clone(),
+                        chain_id: network.chain_id,
+                        creator: personal.creator.as_deref().map(checksum_address),
+                        tx_hash: personal.tx_hash.clone(),
                     }
                 }
                 CreatedSpace::Public(public) => {
-                    let space_id =
-                        derive_space_id(GEO, &checksum_address(public.dao_address.clone()));
+                    let space_id = network.space_id(&checksum_address(public.dao_address.clone()));
 
                     SpaceItem {
                         id: space_id,
@@ -56,6 +95,10 @@ impl SpacesModel {
                             public.membership_plugin.clone(),
                         )),
                         personal_address: None,
+                        network: network.id.clone(),
+                        chain_id: network.chain_id,
+                        creator: public.creator.as_deref().map(checksum_address),
+                        tx_hash: public.tx_hash.clone(),
                     }
                 }
             };
@@ -65,4 +108,108 @@ impl SpacesModel {
 
         return created_spaces;
     }
+
+    pub fn map_personal_admin_changes(
+        changes: &[ChangedPersonalAdmin],
+        network: &Network,
+    ) -> Vec<PersonalAdminItem> {
+        changes
+            .iter()
+            .map(|change| PersonalAdminItem {
+                space_id: network.space_id(&checksum_address(&change.dao_address)),
+                personal_address: checksum_address(&change.personal_plugin),
+                admin_address: change.admin.as_deref().map(checksum_address),
+            })
+            .collect()
+    }
+
+    pub fn map_governance_plugin_changes(
+        changes: &[ChangedGovernancePlugins],
+        network: &Network,
+    ) -> Vec<GovernancePluginsItem> {
+        changes
+            .iter()
+            .map(|change| GovernancePluginsItem {
+                space_id: network.space_id(&checksum_address(&change.dao_address)),
+                voting_address: checksum_address(&change.main_voting_plugin),
+                membership_address: checksum_address(&change.membership_plugin),
+            })
+            .collect()
+    }
+
+    /// Returns whether an edit may change the metadata of the space it's
+    /// applied to. Deleted relations and entities count as changes as their
+    /// types aren't known from the edit.
+    pub fn edit_changes_metadata(edit: &Edit) -> bool {
+        edit.ops.iter().any(|op| match &op.payload {
+            Some(Payload::UpdateEntity(entity)) => entity
+                .values
+                .iter()
+                .any(|value| is_metadata_property(&value.property)),
+            Some(Payload::UnsetEntityValues(unset)) => unset
+                .properties
+                .iter()
+                .any(|property| is_metadata_property(property)),
+            Some(Payload::CreateRelation(relation)) => {
+                is_id(&relation.r#type, &[TYPES_PROPERTY, COVER_PROPERTY])
+            }
+            Some(Payload::UpdateRelation(_))
+            | Some(Payload::DeleteRelation(_))
+            | Some(Payload::DeleteEntity(_)) => true,
+            _ => false,
+        })
+    }
+}
+
+fn is_metadata_property(property: &[u8]) -> bool {
+    is_id(
+        property,
+        &[NAME_PROPERTY, DESCRIPTION_PROPERTY, IMAGE_URL_PROPERTY],
+    )
+}
+
+fn is_id(bytes: &[u8], ids: &[Uuid]) -> bool {
+    id::transform_id_bytes(bytes.to_vec())
+        .map(Uuid::from_bytes)
+        .is_ok_and(|id| ids.contains(&id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexer_utils::system_ids::SPACE_TYPE;
+    use wire::builder::{relation, value, EditBuilder};
+
+    #[test]
+    fn test_edit_changes_metadata() {
+        let entity = Uuid::new_v4();
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(NAME_PROPERTY, "Space")])
+            .build();
+        assert!(SpacesModel::edit_changes_metadata(&edit));
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                TYPES_PROPERTY,
+                entity,
+                SPACE_TYPE,
+            ))
+            .build();
+        assert!(SpacesModel::edit_changes_metadata(&edit));
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(Uuid::new_v4(), "Other")])
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                entity,
+                Uuid::new_v4(),
+            ))
+            .build();
+        assert!(!SpacesModel::edit_changes_metadata(&edit));
+    }
 }
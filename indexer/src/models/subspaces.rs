@@ -1,4 +1,4 @@
-use indexer_utils::{checksum_address, id::derive_space_id, network_ids::GEO};
+use indexer_utils::{checksum_address, network_ids::Network};
 use uuid::Uuid;
 
 use crate::{AddedSubspace, RemovedSubspace};
@@ -13,14 +13,16 @@ pub struct SubspaceModel;
 
 impl SubspaceModel {
     /// Maps added subspaces from KgData to database-ready SubspaceItem structs
-    pub fn map_added_subspaces(added_subspaces: &Vec<AddedSubspace>) -> Vec<SubspaceItem> {
+    pub fn map_added_subspaces(
+        added_subspaces: &Vec<AddedSubspace>,
+        network: &Network,
+    ) -> Vec<SubspaceItem> {
         let mut subspaces = Vec::new();
 
         for subspace in added_subspaces {
-            let parent_space_id =
-                derive_space_id(GEO, &checksum_address(subspace.dao_address.clone()));
+            let parent_space_id = network.space_id(&checksum_address(subspace.dao_address.clone()));
             let subspace_id =
-                derive_space_id(GEO, &checksum_address(subspace.subspace_address.clone()));
+                network.space_id(&checksum_address(subspace.subspace_address.clone()));
 
             subspaces.push(SubspaceItem {
                 subspace_id,
@@ -32,14 +34,16 @@ impl SubspaceModel {
     }
 
     /// Maps removed subspaces from KgData to database-ready SubspaceItem structs
-    pub fn map_removed_subspaces(removed_subspaces: &Vec<RemovedSubspace>) -> Vec<SubspaceItem> {
+    pub fn map_removed_subspaces(
+        removed_subspaces: &Vec<RemovedSubspace>,
+        network: &Network,
+    ) -> Vec<SubspaceItem> {
         let mut subspaces = Vec::new();
 
         for subspace in removed_subspaces {
-            let parent_space_id =
-                derive_space_id(GEO, &checksum_address(subspace.dao_address.clone()));
+            let parent_space_id = network.space_id(&checksum_address(subspace.dao_address.clone()));
             let subspace_id =
-                derive_space_id(GEO, &checksum_address(subspace.subspace_address.clone()));
+                network.space_id(&checksum_address(subspace.subspace_address.clone()));
 
             subspaces.push(SubspaceItem {
                 subspace_id,
@@ -58,7 +62,7 @@ mod tests {
     #[test]
     fn test_map_added_subspaces_empty() {
         let added_subspaces = vec![];
-        let result = SubspaceModel::map_added_subspaces(&added_subspaces);
+        let result = SubspaceModel::map_added_subspaces(&added_subspaces, &Network::default());
         assert_eq!(result.len(), 0);
     }
 
@@ -75,14 +79,14 @@ mod tests {
             },
         ];
 
-        let result = SubspaceModel::map_added_subspaces(&added_subspaces);
+        let result = SubspaceModel::map_added_subspaces(&added_subspaces, &Network::default());
         assert_eq!(result.len(), 2);
     }
 
     #[test]
     fn test_map_removed_subspaces_empty() {
         let removed_subspaces = vec![];
-        let result = SubspaceModel::map_removed_subspaces(&removed_subspaces);
+        let result = SubspaceModel::map_removed_subspaces(&removed_subspaces, &Network::default());
         assert_eq!(result.len(), 0);
     }
 
@@ -93,7 +97,24 @@ mod tests {
             subspace_address: "0xfedcba0987654321fedcba0987654321fedcba09".to_string(),
         }];
 
-        let result = SubspaceModel::map_removed_subspaces(&removed_subspaces);
+        let result = SubspaceModel::map_removed_subspaces(&removed_subspaces, &Network::default());
         assert_eq!(result.len(), 1);
     }
+
+    #[test]
+    fn test_map_added_subspaces_per_network() {
+        let added_subspaces = vec![AddedSubspace {
+            dao_address: "0x1234567890abcdef1234567890abcdef12345678".to_string(),
+            subspace_address: "0xfedcba0987654321fedcba0987654321fedcba09".to_string(),
+        }];
+
+        let geo = SubspaceModel::map_added_subspaces(&added_subspaces, &Network::default());
+        let other = SubspaceModel::map_added_subspaces(
+            &added_subspaces,
+            &Network::new(indexer_utils::network_ids::POLYGON, Some(137)),
+        );
+
+        assert_ne!(geo[0].parent_space_id, other[0].parent_space_id);
+        assert_ne!(geo[0].subspace_id, other[0].subspace_id);
+    }
 }
@@ -1,14 +1,14 @@
 use indexer_utils::id;
-use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 use tracing::{debug, instrument, warn};
 use uuid::Uuid;
 use wire::pb::grc20::{op::Payload, options, Edit, Op};
 
 use crate::cache::properties_cache::ImmutableCache;
-use crate::validators::{validate_by_datatype, ValidatedValue};
+use crate::models::entities::deleted_entity_id;
+use crate::models::properties::DataType;
+use crate::validators::{validate_by_datatype, ValidatedValue, ValidationError};
 
 #[derive(Clone)]
 pub enum ValueChangeType {
@@ -25,6 +25,12 @@ pub struct ValueOp {
     pub space_id: Uuid,
     pub language: Option<String>,
     pub unit: Option<String>,
+    /// The value's options as they were written in the edit, see
+    /// [`options_json`].
+    pub options: Option<serde_json::Value>,
+    /// The id of the edit that last set the value, so it can be traced back
+    /// to the edit's row in `edits`.
+    pub edit_id: Option<Uuid>,
     // Data type specific fields
     pub string: Option<String>,
     pub number: Option<f64>,
@@ -33,6 +39,19 @@ pub struct ValueOp {
     pub point: Option<String>,
 }
 
+impl ValueOp {
+    /// Returns the value as it was written in the edit, before it was parsed
+    /// by its property's data type.
+    pub fn raw_value(&self) -> Option<String> {
+        self.string
+            .clone()
+            .or_else(|| self.number.map(|number| number.to_string()))
+            .or_else(|| self.boolean.map(|boolean| if boolean { "1" } else { "0" }.to_string()))
+            .or_else(|| self.time.clone())
+            .or_else(|| self.point.clone())
+    }
+}
+
 pub struct ValuesModel;
 
 impl ValuesModel {
@@ -46,9 +65,23 @@ impl ValuesModel {
         C: ImmutableCache + Send + Sync + 'static,
     {
         let mut value_ops: Vec<ValueOp> = Vec::new();
+        let edit_id = id::transform_id_bytes(edit.id.clone())
+            .ok()
+            .map(Uuid::from_bytes);
+
+        // Load every referenced property up front so the lookups below are
+        // synchronous.
+        cache.prefetch(&referenced_properties(edit)).await;
 
         for op in &edit.ops {
-            let mut ops = value_op_from_op(op, space_id, cache).await;
+            // Values of the entity that already exist are deleted in storage.
+            // Values set earlier in this edit are dropped here.
+            if let Some(entity_id) = deleted_entity_id(op) {
+                value_ops.retain(|value_op| value_op.entity_id != entity_id);
+                continue;
+            }
+
+            let mut ops = value_op_from_op(op, space_id, edit_id, cache);
             value_ops.append(&mut ops);
         }
 
@@ -84,6 +117,20 @@ impl ValuesModel {
     }
 }
 
+/// Returns the ids of the properties set by an edit, skipping malformed ids.
+fn referenced_properties(edit: &Edit) -> Vec<Uuid> {
+    edit.ops
+        .iter()
+        .filter_map(|op| match &op.payload {
+            Some(Payload::UpdateEntity(entity)) => Some(&entity.values),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|value| id::transform_id_bytes(value.property.clone()).ok())
+        .map(Uuid::from_bytes)
+        .collect()
+}
+
 fn squash_values(value_ops: &Vec<ValueOp>) -> Vec<ValueOp> {
     let mut hash = HashMap::new();
 
@@ -96,22 +143,12 @@ fn squash_values(value_ops: &Vec<ValueOp>) -> Vec<ValueOp> {
     return result;
 }
 
-fn derive_value_id(entity_id: &Uuid, property_id: &Uuid, space_id: &Uuid) -> Uuid {
-    let mut hasher = DefaultHasher::new();
-    entity_id.hash(&mut hasher);
-    property_id.hash(&mut hasher);
-    space_id.hash(&mut hasher);
-    let hash_value = hasher.finish();
-
-    // Create a deterministic UUID from the hash
-    let mut bytes = [0u8; 16];
-    bytes[0..8].copy_from_slice(&hash_value.to_be_bytes());
-    bytes[8..16].copy_from_slice(&hash_value.to_be_bytes());
-
-    Uuid::from_bytes(bytes)
-}
-
-async fn value_op_from_op<C>(op: &Op, space_id: &Uuid, cache: &Arc<C>) -> Vec<ValueOp>
+fn value_op_from_op<C>(
+    op: &Op,
+    space_id: &Uuid,
+    edit_id: Option<Uuid>,
+    cache: &Arc<C>,
+) -> Vec<ValueOp>
 where
     C: ImmutableCache + Send + Sync + 'static,
 {
@@ -143,15 +180,18 @@ where
                             let property_id = Uuid::from_bytes(property_id_bytes.unwrap());
 
                             let (language, unit) = extract_options(&value.options);
+                            let options = options_json(&value.options);
 
                             let base_op = ValueOp {
-                                id: derive_value_id(&entity_id, &property_id, space_id),
+                                id: id::derive_value_id(&entity_id, &property_id, space_id),
                                 change_type: ValueChangeType::SET,
                                 property_id,
                                 entity_id,
                                 space_id: space_id.clone(),
                                 language,
                                 unit,
+                                options,
+                                edit_id,
                                 string: None,
                                 number: None,
                                 boolean: None,
@@ -161,7 +201,6 @@ where
 
                             if let Some(populated_op) =
                                 populate_value_fields_by_datatype(base_op, &value.value, cache)
-                                    .await
                             {
                                 values.push(populated_op);
                             } else {
@@ -208,13 +247,15 @@ where
                                 Uuid::from_bytes(property_id_bytes.unwrap());
 
                             values.push(ValueOp {
-                                id: derive_value_id(&entity_id, &property_id, space_id),
+                                id: id::derive_value_id(&entity_id, &property_id, space_id),
                                 change_type: ValueChangeType::DELETE,
                                 property_id,
                                 entity_id,
                                 space_id: space_id.clone(),
                                 language: None,
                                 unit: None,
+                                options: None,
+                                edit_id,
                                 string: None,
                                 number: None,
                                 boolean: None,
@@ -233,13 +274,13 @@ where
         };
     }
 
-    return values;
+    values
 }
 
 /// Validates and populates the appropriate type-specific field based on data type.
 /// Returns None if validation fails, indicating the value should be filtered out.
 #[instrument(skip_all, fields(property_id = %base_op.property_id, entity_id = %base_op.entity_id))]
-pub async fn populate_value_fields_by_datatype<C>(
+pub fn populate_value_fields_by_datatype<C>(
     mut base_op: ValueOp,
     raw_value: &str,
     cache: &Arc<C>,
@@ -253,31 +294,12 @@ where
     }
 
     // Try to get the data type from cache
-    if let Ok(data_type) = cache.get(&base_op.property_id).await {
+    if let Ok(data_type) = cache.get(&base_op.property_id) {
         match validate_by_datatype(data_type, raw_value) {
             Ok(validated_value) => {
-                // Set the appropriate field based on the validated value
-                match validated_value {
-                    ValidatedValue::Text(text) => {
-                        // Even if it's a relation type, store as string
-                        // Relations will be filtered out later
-                        base_op.string = Some(text);
-                    }
-                    ValidatedValue::Number(num) => {
-                        base_op.number = Some(num);
-                    }
-                    ValidatedValue::Checkbox(bool_val) => {
-                        base_op.boolean = Some(bool_val);
-                    }
-                    ValidatedValue::Time(_) => {
-                        base_op.time = Some(raw_value.to_string());
-                    }
-                    ValidatedValue::Point(_) => {
-                        base_op.point = Some(raw_value.to_string());
-                    }
-                }
+                set_validated_value(&mut base_op, validated_value, raw_value);
 
-                return Some(base_op);
+                Some(base_op)
             }
             Err(error) => {
                 // If validation fails, log the error and filter out the value
@@ -289,7 +311,7 @@ where
                     error = %error,
                     "Value validation failed, filtering out"
                 );
-                return None;
+                None
             }
         }
     }
@@ -300,7 +322,46 @@ where
             entity_id = %base_op.entity_id,
             "Property not found in cache, filtering out value"
         );
-        return None;
+        None
+    }
+}
+
+/// Parses a stored value as another data type, for migrating its property
+/// to that data type. The typed field of the old data type is cleared.
+pub fn convert_value(mut value: ValueOp, data_type: DataType) -> Result<ValueOp, ValidationError> {
+    let raw_value = value.raw_value().unwrap_or_default();
+    let validated_value = validate_by_datatype(data_type, &raw_value)?;
+
+    value.string = None;
+    value.number = None;
+    value.boolean = None;
+    value.time = None;
+    value.point = None;
+    set_validated_value(&mut value, validated_value, &raw_value);
+
+    Ok(value)
+}
+
+fn set_validated_value(op: &mut ValueOp, validated_value: ValidatedValue, raw_value: &str) {
+    // Set the appropriate field based on the validated value
+    match validated_value {
+        ValidatedValue::Text(text) => {
+            // Even if it's a relation type, store as string
+            // Relations will be filtered out later
+            op.string = Some(text);
+        }
+        ValidatedValue::Number(num) => {
+            op.number = Some(num);
+        }
+        ValidatedValue::Checkbox(bool_val) => {
+            op.boolean = Some(bool_val);
+        }
+        ValidatedValue::Time(_) => {
+            op.time = Some(raw_value.to_string());
+        }
+        ValidatedValue::Point(_) => {
+            op.point = Some(raw_value.to_string());
+        }
     }
 }
 
@@ -308,10 +369,10 @@ where
 mod tests {
     use super::*;
     use crate::cache::properties_cache::{ImmutableCache, PropertiesCacheError};
-    use crate::models::properties::DataType;
     use std::collections::HashMap;
+    use std::sync::RwLock;
     use tokio::runtime::Runtime;
-    use tokio::sync::RwLock;
+    use wire::builder::{value, EditBuilder};
 
     // Mock cache implementation for testing
     #[derive(Default)]
@@ -330,17 +391,21 @@ mod tests {
     #[async_trait::async_trait]
     impl ImmutableCache for MockPropertiesCache {
         async fn insert(&self, key: &Uuid, value: DataType) {
-            let mut write = self.inner.write().await;
+            let mut write = self.inner.write().unwrap();
             write.insert(*key, value);
         }
 
-        async fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
-            let read = self.inner.read().await;
+        fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
+            let read = self.inner.read().unwrap();
             match read.get(key) {
                 Some(value) => Ok(*value),
                 None => Err(PropertiesCacheError::PropertyNotFoundError),
             }
         }
+
+        async fn migrate(&self, key: &Uuid, value: DataType) {
+            self.insert(key, value).await;
+        }
     }
 
     #[test]
@@ -365,6 +430,8 @@ mod tests {
             space_id,
             language: None,
             unit: None,
+            options: None,
+            edit_id: None,
             string: None,
             number: None,
             boolean: None,
@@ -381,6 +448,8 @@ mod tests {
             space_id,
             language: None,
             unit: None,
+            options: None,
+            edit_id: None,
             string: None,
             number: None,
             boolean: None,
@@ -389,16 +458,9 @@ mod tests {
         };
 
         // Validate results
-        let valid_result = rt.block_on(populate_value_fields_by_datatype(
-            valid_number_op,
-            "123.45",
-            &cache,
-        ));
-        let invalid_result = rt.block_on(populate_value_fields_by_datatype(
-            invalid_number_op,
-            "not-a-number",
-            &cache,
-        ));
+        let valid_result = populate_value_fields_by_datatype(valid_number_op, "123.45", &cache);
+        let invalid_result =
+            populate_value_fields_by_datatype(invalid_number_op, "not-a-number", &cache);
 
         // Valid number should be returned with number field populated
         assert!(valid_result.is_some());
@@ -409,6 +471,26 @@ mod tests {
         // Invalid number should return None (filtered out)
         assert!(invalid_result.is_none());
     }
+
+    #[test]
+    fn test_referenced_properties() {
+        let (name, age, entity) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let mut edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(name, "Alice"), value(age, "30")])
+            .unset_entity_values(entity, [Uuid::new_v4()])
+            .update_entity(Uuid::new_v4(), [value(name, "Bob")])
+            .build();
+        // Malformed property ids are skipped
+        if let Some(Payload::UpdateEntity(entity)) = &mut edit.ops[2].payload {
+            entity.values.push(wire::pb::grc20::Value {
+                property: vec![1; 4],
+                value: "Carol".to_string(),
+                options: None,
+            });
+        }
+
+        assert_eq!(referenced_properties(&edit), vec![name, age, name]);
+    }
 }
 
 fn extract_options(options: &Option<wire::pb::grc20::Options>) -> (Option<String>, Option<String>) {
@@ -444,3 +526,35 @@ fn extract_options(options: &Option<wire::pb::grc20::Options>) -> (Option<String
         (None, None)
     }
 }
+
+/// Converts a value's options to JSON, keeping every field even when it
+/// can't be read as a language or unit, e.g.
+/// `{"text":{"language":"…"}}` or `{"number":{"unit":"…"}}`. Byte fields
+/// holding an id are written as a UUID, other bytes as UTF-8 text, or as
+/// `0x`-prefixed hex if they aren't valid UTF-8.
+pub fn options_json(options: &Option<wire::pb::grc20::Options>) -> Option<serde_json::Value> {
+    let value = options.as_ref()?.value.as_ref()?;
+
+    let (kind, field, bytes) = match value {
+        options::Value::Text(text_opts) => ("text", "language", &text_opts.language),
+        options::Value::Number(number_opts) => ("number", "unit", &number_opts.unit),
+    };
+
+    let mut fields = serde_json::Map::new();
+    if let Some(bytes) = bytes {
+        fields.insert(field.to_string(), bytes_json(bytes));
+    }
+
+    Some(serde_json::json!({ kind: fields }))
+}
+
+fn bytes_json(bytes: &[u8]) -> serde_json::Value {
+    if let Ok(id_bytes) = id::transform_id_bytes(bytes.to_vec()) {
+        return Uuid::from_bytes(id_bytes).to_string().into();
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.into(),
+        Err(_) => format!("0x{}", hex::encode(bytes)).into(),
+    }
+}
@@ -1,11 +1,12 @@
 use crate::cache::properties_cache::{ImmutableCache, PropertiesCacheError};
 use crate::models::properties::DataType;
-use crate::models::values::{ValueChangeType, ValuesModel};
+use crate::models::values::{options_json, ValueChangeType, ValuesModel};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::RwLock;
 use tokio::runtime::Runtime;
-use tokio::sync::RwLock;
 use uuid::Uuid;
+use wire::builder::{value, EditBuilder};
 use wire::pb::grc20::{
     op::Payload, options, Edit, Entity, NumberOptions, Op, Options, TextOptions, UnsetEntityValues,
     Value,
@@ -28,17 +29,21 @@ impl MockPropertiesCache {
 #[async_trait::async_trait]
 impl ImmutableCache for MockPropertiesCache {
     async fn insert(&self, key: &Uuid, value: DataType) {
-        let mut write = self.inner.write().await;
+        let mut write = self.inner.write().unwrap();
         write.insert(*key, value);
     }
 
-    async fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
-        let read = self.inner.read().await;
+    fn get(&self, key: &Uuid) -> Result<DataType, PropertiesCacheError> {
+        let read = self.inner.read().unwrap();
         match read.get(key) {
             Some(value) => Ok(*value),
             None => Err(PropertiesCacheError::PropertyNotFoundError),
         }
     }
+
+    async fn migrate(&self, key: &Uuid, value: DataType) {
+        self.insert(key, value).await;
+    }
 }
 
 #[cfg(test)]
@@ -113,6 +118,10 @@ mod tests {
             Uuid::parse_str("550e8400-e29b-41d4-a716-446655440000").unwrap()
         );
         assert_eq!(created_op.string, Some("value1".to_string()));
+        assert_eq!(
+            created_op.edit_id,
+            Some(Uuid::parse_str("f47ac10b-58cc-4372-a567-0e02b2c3d479").unwrap())
+        );
         // The id is now a deterministically generated UUID, so we just check it exists
         assert_ne!(created_op.id, Uuid::nil());
         assert!(matches!(created_op.change_type, ValueChangeType::SET));
@@ -688,6 +697,10 @@ mod tests {
         assert_eq!(created_op.string, Some("Hello World".to_string()));
         assert_eq!(created_op.language, Some("en".to_string()));
         assert_eq!(created_op.unit, None);
+        assert_eq!(
+            created_op.options,
+            Some(serde_json::json!({ "text": { "language": "en" } }))
+        );
         assert!(matches!(created_op.change_type, ValueChangeType::SET));
     }
 
@@ -753,6 +766,10 @@ mod tests {
         assert_eq!(created_op.number, Some(42.5));
         assert_eq!(created_op.language, None);
         assert_eq!(created_op.unit, Some(unit_uuid.to_string()));
+        assert_eq!(
+            created_op.options,
+            Some(serde_json::json!({ "number": { "unit": unit_uuid.to_string() } }))
+        );
         assert!(matches!(created_op.change_type, ValueChangeType::SET));
     }
 
@@ -810,6 +827,7 @@ mod tests {
         assert_eq!(created_op.string, Some("test value".to_string()));
         assert_eq!(created_op.language, None);
         assert_eq!(created_op.unit, None);
+        assert_eq!(created_op.options, None);
         assert!(matches!(created_op.change_type, ValueChangeType::SET));
     }
 
@@ -984,4 +1002,49 @@ mod tests {
         assert_eq!(plain_value.language, None);
         assert_eq!(plain_value.unit, None);
     }
+
+    #[test]
+    fn test_map_edit_to_values_delete_entity() {
+        let (entity, other_entity, property) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let space_id = Uuid::new_v4();
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(property, "before")])
+            .update_entity(other_entity, [value(property, "other")])
+            .delete_entity(entity)
+            .update_entity(entity, [value(property, "after")])
+            .build();
+        let cache = Arc::new(MockPropertiesCache::new());
+
+        let rt = Runtime::new().unwrap();
+        rt.block_on(cache.insert(&property, DataType::String));
+        let (created, deleted) =
+            rt.block_on(ValuesModel::map_edit_to_values(&edit, &space_id, &cache));
+
+        // Values set before the delete are dropped, values set after it are kept
+        let mut strings: Vec<_> = created.iter().filter_map(|op| op.string.clone()).collect();
+        strings.sort();
+        assert_eq!(strings, vec!["after".to_string(), "other".to_string()]);
+        assert!(deleted.is_empty());
+    }
+
+    #[test]
+    fn test_options_json_keeps_unreadable_fields() {
+        // A unit that isn't an id is dropped from the unit column, but kept
+        // in the options
+        let options = Some(Options {
+            value: Some(options::Value::Number(NumberOptions {
+                unit: Some(vec![0xff, 0x00]),
+            })),
+        });
+        assert_eq!(
+            options_json(&options),
+            Some(serde_json::json!({ "number": { "unit": "0xff00" } }))
+        );
+
+        let options = Some(Options {
+            value: Some(options::Value::Text(TextOptions { language: None })),
+        });
+        assert_eq!(options_json(&options), Some(serde_json::json!({ "text": {} })));
+        assert_eq!(options_json(&None), None);
+    }
 }
@@ -0,0 +1,342 @@
+//! Moderation of edits before they're applied.
+//!
+//! Every edit of a block is passed through a [`Moderator`]'s hooks in
+//! order. A hook can let the edit through, rewrite it or reject it, so
+//! deployments can plug their own policy engine in next to the configured
+//! [`Blocklist`] of content CIDs and entity ids.
+
+use std::{collections::HashSet, sync::Arc};
+
+use async_trait::async_trait;
+use indexer_utils::{config::ModerationConfig, id};
+use stream::utils::BlockMetadata;
+use tracing::warn;
+use uuid::Uuid;
+use wire::pb::grc20::{op::Payload, Edit, Op};
+
+use crate::{cache::PreprocessedEdit, error::IndexingError, KgData};
+
+/// What happens to an edit after it's moderated.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Moderation {
+    Allow,
+    /// The edit is applied as given instead.
+    Rewrite(Edit),
+    /// The edit isn't applied, for the given reason.
+    Reject(String),
+}
+
+/// A policy that's checked before an edit is applied.
+#[async_trait]
+pub trait ModerationHook: Send + Sync {
+    async fn moderate(
+        &self,
+        edit: &PreprocessedEdit,
+        block: &BlockMetadata,
+    ) -> Result<Moderation, IndexingError>;
+}
+
+/// Rejects edits by their content CID and drops the ops of any edit that
+/// write to blocked entities.
+///
+/// Ops are dropped if they update, unset or delete a blocked entity, create
+/// it as a property, or create a relation from, to or as one. Ops on
+/// existing relations only carry the relation's id, so they're dropped if
+/// that id is blocked.
+pub struct Blocklist {
+    cids: HashSet<String>,
+    entity_ids: HashSet<Uuid>,
+}
+
+impl Blocklist {
+    pub fn new(
+        cids: impl IntoIterator<Item = String>,
+        entity_ids: impl IntoIterator<Item = Uuid>,
+    ) -> Self {
+        Blocklist {
+            cids: cids.into_iter().map(|cid| normalize_cid(&cid)).collect(),
+            entity_ids: entity_ids.into_iter().collect(),
+        }
+    }
+
+    /// Returns the configured blocklist, or `None` if nothing is blocked.
+    /// Entity ids are expected to be validated with the config.
+    pub fn from_config(config: &ModerationConfig) -> Option<Self> {
+        if config.blocked_cids.is_empty() && config.blocked_entity_ids.is_empty() {
+            return None;
+        }
+
+        Some(Blocklist::new(
+            config.blocked_cids.iter().cloned(),
+            config
+                .blocked_entity_ids
+                .iter()
+                .filter_map(|id| Uuid::parse_str(id).ok()),
+        ))
+    }
+
+    fn blocks_op(&self, op: &Op) -> bool {
+        let ids: Vec<&Vec<u8>> = match &op.payload {
+            Some(Payload::UpdateEntity(entity)) => vec![&entity.id],
+            Some(Payload::UnsetEntityValues(unset)) => vec![&unset.id],
+            Some(Payload::DeleteEntity(id)) => vec![id],
+            Some(Payload::CreateProperty(property)) | Some(Payload::UpdateProperty(property)) => {
+                vec![&property.id]
+            }
+            Some(Payload::CreateRelation(relation)) => vec![
+                &relation.id,
+                &relation.from_entity,
+                &relation.to_entity,
+                &relation.entity,
+            ],
+            Some(Payload::UpdateRelation(update)) => vec![&update.id],
+            Some(Payload::UnsetRelationFields(unset)) => vec![&unset.id],
+            Some(Payload::DeleteRelation(id)) => vec![id],
+            None => vec![],
+        };
+
+        ids.into_iter().any(|id| {
+            id::transform_id_bytes(id.clone())
+                .is_ok_and(|id| self.entity_ids.contains(&Uuid::from_bytes(id)))
+        })
+    }
+}
+
+#[async_trait]
+impl ModerationHook for Blocklist {
+    async fn moderate(
+        &self,
+        edit: &PreprocessedEdit,
+        _block: &BlockMetadata,
+    ) -> Result<Moderation, IndexingError> {
+        if self.cids.contains(normalize_cid(&edit.cid).as_str()) {
+            return Ok(Moderation::Reject("the content CID is blocked".to_string()));
+        }
+
+        let Some(content) = &edit.edit else {
+            return Ok(Moderation::Allow);
+        };
+        if self.entity_ids.is_empty() || !content.ops.iter().any(|op| self.blocks_op(op)) {
+            return Ok(Moderation::Allow);
+        }
+
+        let mut rewritten = content.clone();
+        rewritten.ops.retain(|op| !self.blocks_op(op));
+
+        Ok(Moderation::Rewrite(rewritten))
+    }
+}
+
+fn normalize_cid(cid: &str) -> String {
+    cid.trim().trim_start_matches("ipfs://").to_string()
+}
+
+/// Runs the edits of each block through a chain of moderation hooks.
+#[derive(Clone, Default)]
+pub struct Moderator {
+    hooks: Vec<Arc<dyn ModerationHook>>,
+}
+
+impl Moderator {
+    pub fn new() -> Self {
+        Moderator::default()
+    }
+
+    /// Adds a hook that's run after the ones already added.
+    pub fn with_hook(mut self, hook: Arc<dyn ModerationHook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Returns a moderator for the configured blocklist, or `None` if
+    /// nothing is blocked.
+    pub fn from_config(config: &ModerationConfig) -> Option<Self> {
+        Blocklist::from_config(config)
+            .map(|blocklist| Moderator::new().with_hook(Arc::new(blocklist)))
+    }
+
+    /// Moderates the edits of a block. Each hook sees the edit as rewritten
+    /// by the hooks before it, and a rejected edit is dropped from the block
+    /// without running the remaining hooks. Errored edits are still passed
+    /// to the hooks, as their CID may be blocked.
+    pub async fn moderate(&self, mut data: KgData) -> Result<KgData, IndexingError> {
+        let mut edits = Vec::with_capacity(data.edits.len());
+
+        'edits: for mut edit in std::mem::take(&mut data.edits) {
+            for hook in &self.hooks {
+                match hook.moderate(&edit, &data.block).await? {
+                    Moderation::Allow => {}
+                    Moderation::Rewrite(rewritten) => {
+                        warn!(
+                            space_id = %edit.space_id,
+                            cid = %edit.cid,
+                            dropped_ops = edit
+                                .edit
+                                .as_ref()
+                                .map_or(0, |edit| edit.ops.len())
+                                .saturating_sub(rewritten.ops.len()),
+                            "Edit was rewritten by moderation"
+                        );
+                        edit.edit = Some(rewritten);
+                    }
+                    Moderation::Reject(reason) => {
+                        warn!(
+                            space_id = %edit.space_id,
+                            cid = %edit.cid,
+                            reason = %reason,
+                            "Edit was rejected by moderation"
+                        );
+                        continue 'edits;
+                    }
+                }
+            }
+
+            edits.push(edit);
+        }
+
+        data.edits = edits;
+        Ok(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexer_utils::network_ids::Network;
+    use wire::builder::{relation, value, EditBuilder};
+
+    fn preprocessed(cid: &str, edit: Edit) -> PreprocessedEdit {
+        PreprocessedEdit {
+            cid: cid.to_string(),
+            edit: Some(edit),
+            is_errored: false,
+            space_id: Uuid::new_v4(),
+            sender: None,
+            event_index: 0,
+        }
+    }
+
+    fn block(edits: Vec<PreprocessedEdit>) -> KgData {
+        KgData {
+            block: BlockMetadata {
+                cursor: "1".to_string(),
+                block_number: 1,
+                timestamp: "1234567890".to_string(),
+            },
+            edits,
+            added_editors: vec![],
+            removed_editors: vec![],
+            added_members: vec![],
+            removed_members: vec![],
+            added_subspaces: vec![],
+            removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            spaces: vec![],
+            network: Network::default(),
+            unowned_properties: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_blocklist() {
+        let (blocked, allowed, property) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let blocklist = Blocklist::new(["ipfs://blocked".to_string()], [blocked]);
+        let block_metadata = block(vec![]).block;
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(blocked, [value(property, "hidden")])
+            .update_entity(allowed, [value(property, "shown")])
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                property,
+                allowed,
+                blocked,
+            ))
+            .delete_entity(blocked)
+            .build();
+
+        let moderation = blocklist
+            .moderate(&preprocessed("ipfs://other", edit.clone()), &block_metadata)
+            .await
+            .unwrap();
+        let Moderation::Rewrite(rewritten) = moderation else {
+            panic!("Expected the edit to be rewritten, got {:?}", moderation);
+        };
+        assert_eq!(rewritten.ops, vec![edit.ops[1].clone()]);
+
+        // The prefix is optional on both sides
+        assert!(matches!(
+            blocklist
+                .moderate(&preprocessed("blocked", edit.clone()), &block_metadata)
+                .await
+                .unwrap(),
+            Moderation::Reject(_)
+        ));
+
+        let untouched = EditBuilder::new(Uuid::new_v4())
+            .update_entity(allowed, [value(property, "shown")])
+            .build();
+        assert_eq!(
+            blocklist
+                .moderate(&preprocessed("ipfs://other", untouched), &block_metadata)
+                .await
+                .unwrap(),
+            Moderation::Allow
+        );
+    }
+
+    struct RejectUnnamed;
+
+    #[async_trait]
+    impl ModerationHook for RejectUnnamed {
+        async fn moderate(
+            &self,
+            edit: &PreprocessedEdit,
+            _block: &BlockMetadata,
+        ) -> Result<Moderation, IndexingError> {
+            match &edit.edit {
+                Some(edit) if edit.name.is_empty() => Ok(Moderation::Reject("unnamed".to_string())),
+                _ => Ok(Moderation::Allow),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_moderator_runs_hooks_in_order() {
+        let (blocked, allowed, property) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let moderator = Moderator::new()
+            .with_hook(Arc::new(Blocklist::new(
+                ["ipfs://blocked".to_string()],
+                [blocked],
+            )))
+            .with_hook(Arc::new(RejectUnnamed));
+
+        let named = EditBuilder::new(Uuid::new_v4())
+            .name("Named")
+            .update_entity(blocked, [value(property, "hidden")])
+            .update_entity(allowed, [value(property, "shown")])
+            .build();
+        let unnamed = EditBuilder::new(Uuid::new_v4()).build();
+
+        let data = moderator
+            .moderate(block(vec![
+                preprocessed("ipfs://named", named.clone()),
+                preprocessed("ipfs://blocked", named.clone()),
+                preprocessed("ipfs://unnamed", unnamed),
+            ]))
+            .await
+            .unwrap();
+
+        assert_eq!(data.edits.len(), 1);
+        assert_eq!(data.edits[0].cid, "ipfs://named");
+        assert_eq!(
+            data.edits[0].edit.as_ref().unwrap().ops,
+            vec![named.ops[1].clone()]
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::{sync::Arc, time::Duration};
+
+use indexer_utils::{checksum_address, config::NotificationsConfig};
+use sqlx::{PgPool, Row};
+use tokio::task::JoinHandle;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::{sharding::Shard, KgData};
+
+use super::{
+    events_from_block, sign, EventType, Notification, NotificationError, NotificationEvent,
+    Webhook, DELIVERY_HEADER, EVENT_HEADER, SIGNATURE_HEADER,
+};
+
+/// Posts the notifications of committed blocks to the registered webhooks.
+///
+/// Each delivery runs in its own task so a slow webhook doesn't hold up
+/// indexing. Failed deliveries are retried with exponential backoff and
+/// recorded in `webhook_dead_letters` once they run out of attempts.
+pub struct WebhookDispatcher {
+    pool: PgPool,
+    client: reqwest::Client,
+    config: NotificationsConfig,
+    shard: Option<Shard>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(
+        pool: PgPool,
+        config: NotificationsConfig,
+        shard: Option<Shard>,
+    ) -> Result<Self, NotificationError> {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout())
+            .build()?;
+
+        Ok(WebhookDispatcher {
+            pool,
+            client,
+            config,
+            shard,
+        })
+    }
+
+    /// Returns the registered webhooks. Event types this version doesn't
+    /// know are ignored.
+    pub async fn webhooks(&self) -> Result<Vec<Webhook>, NotificationError> {
+        let rows = sqlx::query(
+            "SELECT id, url, secret, space_id, event_types FROM webhooks ORDER BY created_at, id",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                let id: Uuid = row.try_get("id")?;
+                let event_types: Vec<String> = row.try_get("event_types")?;
+
+                Ok(Webhook {
+                    id,
+                    url: row.try_get("url")?,
+                    secret: row.try_get("secret")?,
+                    space_id: row.try_get("space_id")?,
+                    event_types: event_types
+                        .iter()
+                        .filter_map(|event_type| match event_type.parse::<EventType>() {
+                            Ok(event_type) => Some(event_type),
+                            Err(error) => {
+                                warn!(webhook_id = %id, error = %error, "Ignoring webhook event type");
+                                None
+                            }
+                        })
+                        .collect(),
+                })
+            })
+            .collect()
+    }
+
+    pub async fn register(&self, webhook: &Webhook) -> Result<(), NotificationError> {
+        let event_types: Vec<&str> = webhook.event_types.iter().map(EventType::as_str).collect();
+
+        sqlx::query(
+            "INSERT INTO webhooks (id, url, secret, space_id, event_types) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(webhook.id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(webhook.space_id)
+        .bind(&event_types)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Spawns a delivery for every event of a committed block and every
+    /// webhook it matches. Returns the delivery tasks, which don't need to be
+    /// awaited.
+    pub async fn notify(
+        self: &Arc<Self>,
+        data: &KgData,
+    ) -> Result<Vec<JoinHandle<Result<(), NotificationError>>>, NotificationError> {
+        let webhooks = self.webhooks().await?;
+        if webhooks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let mut deliveries = Vec::new();
+
+        for event in events_from_block(data) {
+            let Some(event) = self.resolve(event).await? else {
+                continue;
+            };
+
+            let notification = Arc::new(Notification {
+                id: Uuid::new_v4(),
+                block_number: data.block.block_number,
+                block_timestamp: data.block.timestamp.clone(),
+                event,
+            });
+
+            for webhook in webhooks
+                .iter()
+                .filter(|webhook| webhook.matches(&notification.event))
+            {
+                let dispatcher = self.clone();
+                let webhook = webhook.clone();
+                let notification = notification.clone();
+
+                deliveries.push(tokio::spawn(async move {
+                    dispatcher.deliver(&webhook, &notification).await
+                }));
+            }
+        }
+
+        Ok(deliveries)
+    }
+
+    /// Looks up the space of executed proposals, including those of plugins
+    /// the space has since replaced, and drops the events another shard
+    /// notifies about. Proposals of unknown spaces are only notified
+    /// about by the first shard.
+    async fn resolve(
+        &self,
+        event: NotificationEvent,
+    ) -> Result<Option<NotificationEvent>, NotificationError> {
+        let event = match event {
+            NotificationEvent::ProposalExecuted {
+                space_id: None,
+                proposal_id,
+                plugin_address,
+            } => {
+                let space_id: Option<Uuid> = sqlx::query_scalar(
+                    r#"
+                    SELECT id FROM spaces WHERE main_voting_address = $1 OR personal_address = $1
+                    UNION ALL
+                    SELECT space_id FROM space_plugins_history
+                    WHERE plugin_type = 'voting' AND plugin_address = $1
+                    UNION ALL
+                    SELECT space_id FROM personal_admin_history WHERE personal_address = $1
+                    LIMIT 1
+                    "#,
+                )
+                .bind(checksum_address(&plugin_address))
+                .fetch_optional(&self.pool)
+                .await?;
+
+                NotificationEvent::ProposalExecuted {
+                    space_id,
+                    proposal_id,
+                    plugin_address,
+                }
+            }
+            event => event,
+        };
+
+        let owned = match (&self.shard, event.space_id()) {
+            (None, _) => true,
+            (Some(shard), Some(space_id)) => shard.owns(&space_id),
+            (Some(shard), None) => shard.index == 0,
+        };
+
+        Ok(owned.then_some(event))
+    }
+
+    /// Posts a notification to a webhook until it succeeds or runs out of
+    /// attempts, in which case it's dead-lettered.
+    pub async fn deliver(
+        &self,
+        webhook: &Webhook,
+        notification: &Notification,
+    ) -> Result<(), NotificationError> {
+        let body = serde_json::to_vec(notification)?;
+        let signature = sign(&webhook.secret, &body);
+        let mut attempt = 1;
+
+        loop {
+            let result = self
+                .client
+                .post(&webhook.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(EVENT_HEADER, notification.event.event_type().as_str())
+                .header(DELIVERY_HEADER, notification.id.to_string())
+                .body(body.clone())
+                .send()
+                .await
+                .map_err(NotificationError::from)
+                .and_then(|response| match response.status() {
+                    status if status.is_success() => Ok(()),
+                    status => Err(NotificationError::Status(status.as_u16())),
+                });
+
+            let error = match result {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+
+            if attempt >= self.config.max_attempts {
+                error!(
+                    webhook_id = %webhook.id,
+                    notification_id = %notification.id,
+                    attempts = attempt,
+                    error = %error,
+                    "Webhook delivery failed, dead-lettering"
+                );
+                self.dead_letter(webhook, notification, &error, attempt)
+                    .await?;
+                return Err(error);
+            }
+
+            warn!(
+                webhook_id = %webhook.id,
+                notification_id = %notification.id,
+                attempt = attempt,
+                error = %error,
+                "Webhook delivery failed, retrying"
+            );
+            tokio::time::sleep(retry_delay(self.config.retry_base_ms, attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    async fn dead_letter(
+        &self,
+        webhook: &Webhook,
+        notification: &Notification,
+        error: &NotificationError,
+        attempts: u32,
+    ) -> Result<(), NotificationError> {
+        sqlx::query(
+            "INSERT INTO webhook_dead_letters (webhook_id, notification_id, event_type, payload, error, attempts, block_number)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(webhook.id)
+        .bind(notification.id)
+        .bind(notification.event.event_type().as_str())
+        .bind(serde_json::to_value(notification)?)
+        .bind(error.to_string())
+        .bind(attempts as i32)
+        .bind(notification.block_number as i64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// The delay before retrying after the given failed attempt, doubling from
+/// `base_ms` after the first.
+fn retry_delay(base_ms: u64, attempt: u32) -> Duration {
+    let factor = 2u64.saturating_pow(attempt.saturating_sub(1));
+    Duration::from_millis(base_ms.saturating_mul(factor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles() {
+        assert_eq!(retry_delay(500, 1), Duration::from_millis(500));
+        assert_eq!(retry_delay(500, 2), Duration::from_millis(1000));
+        assert_eq!(retry_delay(500, 4), Duration::from_millis(4000));
+        assert_eq!(retry_delay(500, 100), Duration::from_millis(u64::MAX));
+    }
+}
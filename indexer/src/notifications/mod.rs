@@ -0,0 +1,383 @@
+//! Webhook notifications for the events of indexed blocks.
+//!
+//! Operators register webhooks in the `webhooks` table, optionally filtered
+//! to a space and to event types. Once a block is committed the indexer
+//! posts a JSON notification for every matching event, signed with the
+//! webhook's secret.
+
+use std::{fmt, str::FromStr};
+
+use hmac::{Hmac, Mac};
+use indexer_utils::checksum_address;
+use serde::Serialize;
+use sha2::Sha256;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{KgData, ProposalType};
+
+pub mod dispatcher;
+
+/// The header carrying the hex HMAC-SHA256 of the body, prefixed with
+/// `sha256=`.
+pub const SIGNATURE_HEADER: &str = "X-Gaia-Signature";
+pub const EVENT_HEADER: &str = "X-Gaia-Event";
+/// The notification id, which stays the same across retries.
+pub const DELIVERY_HEADER: &str = "X-Gaia-Delivery";
+
+#[derive(Error, Debug)]
+pub enum NotificationError {
+    #[error("Notification error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Notification error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Webhook responded with status {0}")]
+    Status(u16),
+
+    #[error("Notification error: {0}")]
+    Serialize(#[from] serde_json::Error),
+
+    #[error("Unknown event type '{0}'")]
+    UnknownEventType(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    EditPublished,
+    ProposalCreated,
+    ProposalExecuted,
+    MemberAdded,
+}
+
+impl EventType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventType::EditPublished => "edit_published",
+            EventType::ProposalCreated => "proposal_created",
+            EventType::ProposalExecuted => "proposal_executed",
+            EventType::MemberAdded => "member_added",
+        }
+    }
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for EventType {
+    type Err = NotificationError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "edit_published" => Ok(EventType::EditPublished),
+            "proposal_created" => Ok(EventType::ProposalCreated),
+            "proposal_executed" => Ok(EventType::ProposalExecuted),
+            "member_added" => Ok(EventType::MemberAdded),
+            _ => Err(NotificationError::UnknownEventType(s.to_string())),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum NotificationEvent {
+    EditPublished {
+        space_id: Uuid,
+        cid: String,
+    },
+    ProposalCreated {
+        space_id: Uuid,
+        proposal_id: String,
+        proposal_type: ProposalType,
+        creator: String,
+    },
+    /// The space is `None` until it's looked up from the plugin address.
+    ProposalExecuted {
+        space_id: Option<Uuid>,
+        proposal_id: String,
+        plugin_address: String,
+    },
+    MemberAdded {
+        space_id: Uuid,
+        address: String,
+    },
+}
+
+impl NotificationEvent {
+    pub fn event_type(&self) -> EventType {
+        match self {
+            NotificationEvent::EditPublished { .. } => EventType::EditPublished,
+            NotificationEvent::ProposalCreated { .. } => EventType::ProposalCreated,
+            NotificationEvent::ProposalExecuted { .. } => EventType::ProposalExecuted,
+            NotificationEvent::MemberAdded { .. } => EventType::MemberAdded,
+        }
+    }
+
+    pub fn space_id(&self) -> Option<Uuid> {
+        match self {
+            NotificationEvent::EditPublished { space_id, .. } => Some(*space_id),
+            NotificationEvent::ProposalCreated { space_id, .. } => Some(*space_id),
+            NotificationEvent::ProposalExecuted { space_id, .. } => *space_id,
+            NotificationEvent::MemberAdded { space_id, .. } => Some(*space_id),
+        }
+    }
+}
+
+/// The JSON body posted to a webhook.
+#[derive(Clone, Debug, Serialize)]
+pub struct Notification {
+    pub id: Uuid,
+    pub block_number: u64,
+    pub block_timestamp: String,
+    #[serde(flatten)]
+    pub event: NotificationEvent,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    pub secret: String,
+    /// Only events of this space are posted when set.
+    pub space_id: Option<Uuid>,
+    /// Only events of these types are posted unless empty.
+    pub event_types: Vec<EventType>,
+}
+
+impl Webhook {
+    pub fn matches(&self, event: &NotificationEvent) -> bool {
+        let space_matches = self
+            .space_id
+            .is_none_or(|space_id| event.space_id() == Some(space_id));
+
+        space_matches
+            && (self.event_types.is_empty() || self.event_types.contains(&event.event_type()))
+    }
+}
+
+/// Returns the events of a block that webhooks can subscribe to. Edits that
+/// failed to load aren't published.
+pub fn events_from_block(data: &KgData) -> Vec<NotificationEvent> {
+    let space_id = |dao_address: &str| data.network.space_id(&checksum_address(dao_address));
+
+    let edits = data
+        .edits
+        .iter()
+        .filter(|edit| !edit.is_errored && edit.edit.is_some())
+        .map(|edit| NotificationEvent::EditPublished {
+            space_id: edit.space_id,
+            cid: edit.cid.clone(),
+        });
+
+    let created_proposals =
+        data.created_proposals
+            .iter()
+            .map(|proposal| NotificationEvent::ProposalCreated {
+                space_id: space_id(&proposal.dao_address),
+                proposal_id: proposal.proposal_id.clone(),
+                proposal_type: proposal.proposal_type,
+                creator: proposal.creator.clone(),
+            });
+
+    let executed_proposals =
+        data.executed_proposals
+            .iter()
+            .map(|proposal| NotificationEvent::ProposalExecuted {
+                space_id: None,
+                proposal_id: proposal.proposal_id.clone(),
+                plugin_address: proposal.plugin_address.clone(),
+            });
+
+    let added_members = data
+        .added_members
+        .iter()
+        .map(|member| NotificationEvent::MemberAdded {
+            space_id: space_id(&member.dao_address),
+            address: checksum_address(&member.editor_address),
+        });
+
+    edits
+        .chain(created_proposals)
+        .chain(executed_proposals)
+        .chain(added_members)
+        .collect()
+}
+
+/// Signs a notification body with a webhook's secret, for the
+/// [`SIGNATURE_HEADER`].
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body);
+
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::PreprocessedEdit, AddedMember, CreatedProposal, ExecutedProposal};
+    use indexer_utils::network_ids::Network;
+    use stream::utils::BlockMetadata;
+
+    const DAO: &str = "0x1234567890abcdef1234567890abcdef12345678";
+
+    fn block() -> KgData {
+        let network = Network::default();
+
+        KgData {
+            block: BlockMetadata {
+                cursor: "1".to_string(),
+                block_number: 1,
+                timestamp: "1234567890".to_string(),
+            },
+            edits: vec![
+                PreprocessedEdit {
+                    cid: "ipfs://errored".to_string(),
+                    edit: None,
+                    is_errored: true,
+                    space_id: network.space_id(&checksum_address(DAO)),
+                    sender: None,
+                    event_index: 0,
+                },
+                PreprocessedEdit {
+                    cid: "ipfs://edit".to_string(),
+                    edit: Some(wire::builder::EditBuilder::new(Uuid::new_v4()).build()),
+                    is_errored: false,
+                    space_id: network.space_id(&checksum_address(DAO)),
+                    sender: None,
+                    event_index: 0,
+                },
+            ],
+            added_editors: vec![],
+            removed_editors: vec![],
+            added_members: vec![AddedMember {
+                dao_address: DAO.to_string(),
+                editor_address: "0x0000000000000000000000000000000000000001".to_string(),
+            }],
+            removed_members: vec![],
+            added_subspaces: vec![],
+            removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![CreatedProposal {
+                proposal_id: "1".to_string(),
+                proposal_type: ProposalType::AddMember,
+                creator: "0x0000000000000000000000000000000000000002".to_string(),
+                dao_address: DAO.to_string(),
+                plugin_address: "0x0000000000000000000000000000000000000003".to_string(),
+                preview: None,
+            }],
+            executed_proposals: vec![ExecutedProposal {
+                proposal_id: "1".to_string(),
+                plugin_address: "0x0000000000000000000000000000000000000003".to_string(),
+                tx_hash: "0x04".to_string(),
+            }],
+            canceled_proposals: vec![],
+            spaces: vec![],
+            network,
+            unowned_properties: vec![],
+        }
+    }
+
+    #[test]
+    fn test_events_from_block() {
+        let data = block();
+        let space_id = data.network.space_id(&checksum_address(DAO));
+
+        let event_types: Vec<EventType> = events_from_block(&data)
+            .iter()
+            .map(NotificationEvent::event_type)
+            .collect();
+
+        assert_eq!(
+            event_types,
+            vec![
+                EventType::EditPublished,
+                EventType::ProposalCreated,
+                EventType::ProposalExecuted,
+                EventType::MemberAdded,
+            ]
+        );
+        assert_eq!(events_from_block(&data)[0].space_id(), Some(space_id));
+        assert_eq!(events_from_block(&data)[2].space_id(), None);
+    }
+
+    #[test]
+    fn test_webhook_filters() {
+        let space_id = Uuid::new_v4();
+        let event = NotificationEvent::MemberAdded {
+            space_id,
+            address: "0x0000000000000000000000000000000000000001".to_string(),
+        };
+        let webhook = |space_id, event_types| Webhook {
+            id: Uuid::new_v4(),
+            url: "http://localhost".to_string(),
+            secret: "secret".to_string(),
+            space_id,
+            event_types,
+        };
+
+        assert!(webhook(None, vec![]).matches(&event));
+        assert!(webhook(Some(space_id), vec![EventType::MemberAdded]).matches(&event));
+        assert!(!webhook(Some(Uuid::new_v4()), vec![]).matches(&event));
+        assert!(!webhook(None, vec![EventType::EditPublished]).matches(&event));
+    }
+
+    #[test]
+    fn test_notification_json() {
+        let notification = Notification {
+            id: Uuid::nil(),
+            block_number: 7,
+            block_timestamp: "1234567890".to_string(),
+            event: NotificationEvent::ProposalExecuted {
+                space_id: None,
+                proposal_id: "1".to_string(),
+                plugin_address: "0xplugin".to_string(),
+            },
+        };
+
+        assert_eq!(
+            serde_json::to_value(&notification).unwrap(),
+            serde_json::json!({
+                "id": "00000000-0000-0000-0000-000000000000",
+                "block_number": 7,
+                "block_timestamp": "1234567890",
+                "type": "proposal_executed",
+                "space_id": null,
+                "proposal_id": "1",
+                "plugin_address": "0xplugin",
+            })
+        );
+    }
+
+    #[test]
+    fn test_sign() {
+        // HMAC-SHA256 test case 2 from RFC 4231
+        assert_eq!(
+            sign("Jefe", b"what do ya want for nothing?"),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_event_type_round_trips() {
+        for event_type in [
+            EventType::EditPublished,
+            EventType::ProposalCreated,
+            EventType::ProposalExecuted,
+            EventType::MemberAdded,
+        ] {
+            assert_eq!(
+                event_type.as_str().parse::<EventType>().unwrap(),
+                event_type
+            );
+        }
+        assert!("edit".parse::<EventType>().is_err());
+    }
+}
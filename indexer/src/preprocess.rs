@@ -1,22 +1,47 @@
 use futures::future::join_all;
-use indexer_utils::get_blocklist;
+use indexer_utils::{
+    checksum_address,
+    config::LimitsConfig,
+    decoded::{decode_block, DecodedBlock},
+    get_blocklist, id,
+    network_ids::Network,
+    validate_address, AddressError,
+};
 use prost::Message;
-use std::{collections::HashSet, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 use stream::pb::sf::substreams::rpc::v2::BlockScopedData;
+use thiserror::Error;
 use tokio::{sync::Mutex, task};
 use tokio_retry::{
     strategy::{jitter, ExponentialBackoff},
     Retry,
 };
 use tracing::{debug, info, instrument, warn};
-use wire::pb::chain::GeoOutput;
+use uuid::Uuid;
+use wire::{
+    pb::grc20::{op::Payload, Edit},
+    validate::Diagnostic,
+    validate_edit,
+};
 
 use crate::{
     cache::{postgres::PostgresCache, CacheBackend, PreprocessedEdit},
     error::IndexingError,
-    AddedMember, AddedSubspace, CreatedSpace, KgData, PersonalSpace, PublicSpace, RemovedSubspace,
+    models::proposals::ProposalPreview,
+    AddedMember, AddedSubspace, CanceledProposal, ChangedGovernancePlugins, ChangedPersonalAdmin,
+    CreatedProposal, CreatedSpace, ExecutedProposal, KgData, PersonalSpace, ProposalType,
+    PublicSpace, RemovedSubspace,
 };
 
+/// Fields added to events later are empty in blocks streamed by older
+/// versions of the substream.
+fn non_empty(field: &str) -> Option<String> {
+    (!field.is_empty()).then(|| field.to_string())
+}
+
 /// Matches spaces with their corresponding plugins based on DAO address
 /// Returns a vector of CreatedSpace variants (Public or Personal)
 #[instrument(skip_all, fields(space_count = spaces.len(), governance_plugin_count = governance_plugins.len(), personal_plugin_count = personal_plugins.len()))]
@@ -39,6 +64,8 @@ pub fn match_spaces_with_plugins(
                 space_address: space.space_address.clone(),
                 membership_plugin: governance_plugin.member_access_address.clone(),
                 governance_plugin: governance_plugin.main_voting_address.clone(),
+                creator: non_empty(&space.creator),
+                tx_hash: non_empty(&space.tx_hash),
             }));
         }
         // Otherwise, try to find a matching personal plugin (for personal spaces)
@@ -50,6 +77,8 @@ pub fn match_spaces_with_plugins(
                 dao_address: space.dao_address.clone(),
                 space_address: space.space_address.clone(),
                 personal_plugin: personal_plugin.personal_admin_address.clone(),
+                creator: non_empty(&space.creator),
+                tx_hash: non_empty(&space.tx_hash),
             }));
         }
         // If no matching plugin is found, we skip this space
@@ -101,6 +130,80 @@ pub fn map_initial_editors_added(
         .collect()
 }
 
+/// Infers the initial editor of each personal space created in the block from
+/// its admin plugin, as personal spaces don't always emit an editor event for
+/// their creator. Editors that were already added are skipped.
+pub fn map_personal_space_initial_editors(
+    created_spaces: &[CreatedSpace],
+    personal_plugins: &[wire::pb::chain::GeoPersonalSpaceAdminPluginCreated],
+    added_editors: &[AddedMember],
+) -> Vec<AddedMember> {
+    let mut initial_editors: Vec<AddedMember> = Vec::new();
+
+    for space in created_spaces {
+        let CreatedSpace::Personal(space) = space else {
+            continue;
+        };
+
+        let Some(plugin) = personal_plugins
+            .iter()
+            .find(|plugin| plugin.dao_address == space.dao_address)
+        else {
+            continue;
+        };
+
+        if plugin.initial_editor.is_empty() {
+            continue;
+        }
+
+        let is_added = |editor: &AddedMember| {
+            editor.dao_address == space.dao_address
+                && editor
+                    .editor_address
+                    .eq_ignore_ascii_case(&plugin.initial_editor)
+        };
+
+        if added_editors.iter().any(is_added) || initial_editors.iter().any(is_added) {
+            continue;
+        }
+
+        initial_editors.push(AddedMember {
+            dao_address: space.dao_address.clone(),
+            editor_address: plugin.initial_editor.clone(),
+        });
+    }
+
+    initial_editors
+}
+
+/// Maps governance plugin events to ChangedGovernancePlugins structs
+pub fn map_governance_plugin_changes(
+    governance_plugins: &[wire::pb::chain::GeoGovernancePluginCreated],
+) -> Vec<ChangedGovernancePlugins> {
+    governance_plugins
+        .iter()
+        .map(|p| ChangedGovernancePlugins {
+            dao_address: p.dao_address.clone(),
+            main_voting_plugin: p.main_voting_address.clone(),
+            membership_plugin: p.member_access_address.clone(),
+        })
+        .collect()
+}
+
+/// Maps personal admin plugin events to ChangedPersonalAdmin structs
+pub fn map_personal_admin_changes(
+    personal_plugins: &[wire::pb::chain::GeoPersonalSpaceAdminPluginCreated],
+) -> Vec<ChangedPersonalAdmin> {
+    personal_plugins
+        .iter()
+        .map(|p| ChangedPersonalAdmin {
+            dao_address: p.dao_address.clone(),
+            personal_plugin: p.personal_admin_address.clone(),
+            admin: (!p.initial_editor.is_empty()).then(|| p.initial_editor.clone()),
+        })
+        .collect()
+}
+
 /// Maps member events to AddedMember structs
 pub fn map_members_added(members: &[wire::pb::chain::MemberAdded]) -> Vec<AddedMember> {
     members
@@ -136,6 +239,432 @@ pub fn map_subspaces_removed(
         .collect()
 }
 
+/// Maps the proposal created events of every proposal type to CreatedProposal
+/// structs
+pub fn map_created_proposals(geo: &wire::pb::chain::GeoOutput) -> Vec<CreatedProposal> {
+    // The events of each proposal type are separate messages with the same
+    // proposal fields
+    macro_rules! proposals {
+        ($events:expr, $proposal_type:expr) => {
+            $events.iter().map(|p| CreatedProposal {
+                proposal_id: p.proposal_id.clone(),
+                proposal_type: $proposal_type,
+                creator: p.creator.clone(),
+                dao_address: p.dao_address.clone(),
+                plugin_address: p.plugin_address.clone(),
+                preview: None,
+            })
+        };
+    }
+
+    proposals!(geo.edits, ProposalType::PublishEdit)
+        .chain(proposals!(
+            geo.proposed_added_members,
+            ProposalType::AddMember
+        ))
+        .chain(proposals!(
+            geo.proposed_removed_members,
+            ProposalType::RemoveMember
+        ))
+        .chain(proposals!(
+            geo.proposed_added_editors,
+            ProposalType::AddEditor
+        ))
+        .chain(proposals!(
+            geo.proposed_removed_editors,
+            ProposalType::RemoveEditor
+        ))
+        .chain(proposals!(
+            geo.proposed_added_subspaces,
+            ProposalType::AddSubspace
+        ))
+        .chain(proposals!(
+            geo.proposed_removed_subspaces,
+            ProposalType::RemoveSubspace
+        ))
+        .collect()
+}
+
+/// How many times a proposed edit is read from the cache before the proposal
+/// is indexed without a preview. Unlike published edits, a missing proposed
+/// edit doesn't hold up the block.
+const PROPOSAL_PREVIEW_RETRIES: usize = 10;
+
+/// Fills in the previews of the proposals to publish an edit from their
+/// cached content. Proposals whose content isn't cached, or couldn't be
+/// decoded, are left without one.
+pub async fn add_proposal_previews(
+    created_proposals: &mut [CreatedProposal],
+    proposed_edits: &[wire::pb::chain::PublishEditProposalCreated],
+    cache: &Arc<PostgresCache>,
+) {
+    let lookups = proposed_edits
+        .iter()
+        .filter(|proposal| {
+            !proposal.content_uri.is_empty()
+                && !get_blocklist()
+                    .dao_addresses
+                    .contains(&proposal.dao_address.as_str())
+        })
+        .map(|proposal| async move {
+            let retry = ExponentialBackoff::from_millis(10)
+                .factor(2)
+                .max_delay(std::time::Duration::from_secs(5))
+                .map(jitter)
+                .take(PROPOSAL_PREVIEW_RETRIES);
+
+            let preview =
+                match Retry::spawn(retry, async || cache.get(&proposal.content_uri).await).await {
+                    Ok(cached) => cached.edit.map(|mut edit| {
+                        remove_invalid_ops(&mut edit);
+                        ProposalPreview::from_edit(&proposal.content_uri, &edit)
+                    }),
+                    Err(e) => {
+                        warn!(
+                            dao_address = %proposal.dao_address,
+                            proposal_id = %proposal.proposal_id,
+                            content_uri = %proposal.content_uri,
+                            error = %e,
+                            "Proposed edit isn't cached, indexing proposal without a preview"
+                        );
+                        None
+                    }
+                };
+
+            (
+                (proposal.dao_address.clone(), proposal.proposal_id.clone()),
+                preview,
+            )
+        });
+
+    let previews: HashMap<_, _> = join_all(lookups).await.into_iter().collect();
+
+    for proposal in created_proposals
+        .iter_mut()
+        .filter(|proposal| proposal.proposal_type == ProposalType::PublishEdit)
+    {
+        let key = (proposal.dao_address.clone(), proposal.proposal_id.clone());
+        proposal.preview = previews.get(&key).cloned().flatten();
+    }
+}
+
+/// Maps proposal executed events to ExecutedProposal structs
+pub fn map_executed_proposals(
+    proposals: &[wire::pb::chain::ProposalExecuted],
+) -> Vec<ExecutedProposal> {
+    proposals
+        .iter()
+        .map(|p| ExecutedProposal {
+            proposal_id: p.proposal_id.clone(),
+            plugin_address: p.plugin_address.clone(),
+            tx_hash: p.tx_hash.clone(),
+        })
+        .collect()
+}
+
+/// Maps proposal canceled events to CanceledProposal structs
+pub fn map_canceled_proposals(
+    proposals: &[wire::pb::chain::ProposalCanceled],
+) -> Vec<CanceledProposal> {
+    proposals
+        .iter()
+        .map(|p| CanceledProposal {
+            proposal_id: p.proposal_id.clone(),
+            plugin_address: p.plugin_address.clone(),
+            canceled_by: p.canceled_by.clone(),
+        })
+        .collect()
+}
+
+/// Validates an edit with `wire::validate_edit` and removes every op that
+/// has a problem, so malformed ops never reach storage. Problems with the
+/// edit itself, like a malformed author, are reported but don't remove
+/// anything. Returns every problem found.
+pub fn remove_invalid_ops(edit: &mut Edit) -> Vec<Diagnostic> {
+    let diagnostics = validate_edit(edit);
+
+    let invalid_ops: HashSet<usize> = diagnostics
+        .iter()
+        .filter_map(|diagnostic| diagnostic.op_index)
+        .collect();
+
+    if !invalid_ops.is_empty() {
+        let mut index = 0;
+        edit.ops.retain(|_| {
+            let keep = !invalid_ops.contains(&index);
+            index += 1;
+            keep
+        });
+    }
+
+    diagnostics
+}
+
+/// A size limit an edit is over.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum LimitViolation {
+    #[error("Edit is {bytes} bytes, over the limit of {max}")]
+    TooLarge { bytes: usize, max: usize },
+
+    #[error("Edit has {ops} ops, over the limit of {max}")]
+    TooManyOps { ops: usize, max: usize },
+
+    #[error("Edit sets {values} values on entity {entity_id}, over the limit of {max}")]
+    TooManyValues {
+        entity_id: Uuid,
+        values: usize,
+        max: usize,
+    },
+}
+
+/// Checks an edit against the configured size limits, cheapest first.
+/// Values set on malformed entity ids aren't counted, as their ops are
+/// removed anyway.
+pub fn check_edit_limits(edit: &Edit, limits: &LimitsConfig) -> Result<(), LimitViolation> {
+    if let Some(max) = limits.max_edit_bytes {
+        let bytes = edit.encoded_len();
+        if bytes > max {
+            return Err(LimitViolation::TooLarge { bytes, max });
+        }
+    }
+
+    if let Some(max) = limits.max_ops_per_edit {
+        if edit.ops.len() > max {
+            return Err(LimitViolation::TooManyOps {
+                ops: edit.ops.len(),
+                max,
+            });
+        }
+    }
+
+    if let Some(max) = limits.max_values_per_entity {
+        let mut values: HashMap<&[u8], usize> = HashMap::new();
+
+        for op in &edit.ops {
+            if let Some(Payload::UpdateEntity(entity)) = &op.payload {
+                let count = values.entry(entity.id.as_slice()).or_default();
+                *count += entity.values.len();
+
+                if *count > max {
+                    if let Ok(entity_id) = id::transform_id_bytes(entity.id.clone()) {
+                        return Err(LimitViolation::TooManyValues {
+                            entity_id: Uuid::from_bytes(entity_id),
+                            values: *count,
+                            max,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A chain event that was rejected because one of its addresses is
+/// malformed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedEvent {
+    /// The `GeoOutput` field the event was emitted in.
+    pub event: &'static str,
+    pub field: &'static str,
+    pub error: AddressError,
+}
+
+/// Keeps the events whose addresses are all valid and records the rest.
+fn retain_valid_addresses<T>(
+    events: &mut Vec<T>,
+    event: &'static str,
+    addresses: fn(&T) -> Vec<(&'static str, &str)>,
+    rejected: &mut Vec<RejectedEvent>,
+) {
+    events.retain(|item| {
+        let invalid = addresses(item)
+            .into_iter()
+            .find_map(|(field, address)| validate_address(address).err().map(|e| (field, e)));
+
+        match invalid {
+            Some((field, error)) => {
+                rejected.push(RejectedEvent {
+                    event,
+                    field,
+                    error,
+                });
+                false
+            }
+            None => true,
+        }
+    });
+}
+
+/// Removes the events with a malformed address from the block's output, so
+/// they're rejected before they're mapped instead of reaching storage with
+/// an address `checksum_address` can't normalize. Addresses that may be
+/// empty, like the sender of an edit, are only checked when set. Returns the
+/// rejected events.
+pub fn reject_invalid_addresses(geo: &mut wire::pb::chain::GeoOutput) -> Vec<RejectedEvent> {
+    let mut rejected = Vec::new();
+
+    retain_valid_addresses(
+        &mut geo.spaces_created,
+        "spaces_created",
+        |e| {
+            let mut addresses = vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("space_address", e.space_address.as_str()),
+            ];
+            if !e.creator.is_empty() {
+                addresses.push(("creator", e.creator.as_str()));
+            }
+            addresses
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.governance_plugins_created,
+        "governance_plugins_created",
+        |e| {
+            vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("main_voting_address", e.main_voting_address.as_str()),
+                ("member_access_address", e.member_access_address.as_str()),
+            ]
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.personal_plugins_created,
+        "personal_plugins_created",
+        |e| {
+            let mut addresses = vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("personal_admin_address", e.personal_admin_address.as_str()),
+            ];
+            if !e.initial_editor.is_empty() {
+                addresses.push(("initial_editor", e.initial_editor.as_str()));
+            }
+            addresses
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.editors_added,
+        "editors_added",
+        |e| {
+            vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("editor_address", e.editor_address.as_str()),
+            ]
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.initial_editors_added,
+        "initial_editors_added",
+        |e| {
+            let mut addresses = vec![("dao_address", e.dao_address.as_str())];
+            addresses.extend(e.addresses.iter().map(|a| ("addresses", a.as_str())));
+            addresses
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.members_added,
+        "members_added",
+        |e| {
+            vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("member_address", e.member_address.as_str()),
+            ]
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.subspaces_added,
+        "subspaces_added",
+        |e| {
+            vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("subspace", e.subspace.as_str()),
+            ]
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.subspaces_removed,
+        "subspaces_removed",
+        |e| {
+            vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("subspace", e.subspace.as_str()),
+            ]
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.edits_published,
+        "edits_published",
+        |e| {
+            let mut addresses = vec![
+                ("dao_address", e.dao_address.as_str()),
+                ("plugin_address", e.plugin_address.as_str()),
+            ];
+            if !e.sender.is_empty() {
+                addresses.push(("sender", e.sender.as_str()));
+            }
+            addresses
+        },
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.executed_proposals,
+        "executed_proposals",
+        |e| vec![("plugin_address", e.plugin_address.as_str())],
+        &mut rejected,
+    );
+    retain_valid_addresses(
+        &mut geo.canceled_proposals,
+        "canceled_proposals",
+        |e| {
+            vec![
+                ("plugin_address", e.plugin_address.as_str()),
+                ("canceled_by", e.canceled_by.as_str()),
+            ]
+        },
+        &mut rejected,
+    );
+
+    // The events of each proposal type have the same address fields
+    macro_rules! proposals {
+        ($($events:ident),*) => {
+            $(retain_valid_addresses(
+                &mut geo.$events,
+                stringify!($events),
+                |p| {
+                    vec![
+                        ("dao_address", p.dao_address.as_str()),
+                        ("plugin_address", p.plugin_address.as_str()),
+                        ("creator", p.creator.as_str()),
+                    ]
+                },
+                &mut rejected,
+            );)*
+        };
+    }
+
+    proposals!(
+        edits,
+        proposed_added_members,
+        proposed_removed_members,
+        proposed_added_editors,
+        proposed_removed_editors,
+        proposed_added_subspaces,
+        proposed_removed_subspaces
+    );
+
+    rejected
+}
+
 /// Preprocesses block scoped data from the substream
 #[instrument(skip_all, fields(
     block_number = block_data.clock.as_ref().map(|c| c.number).unwrap_or(0),
@@ -144,37 +673,41 @@ pub fn map_subspaces_removed(
 pub async fn preprocess_block_scoped_data(
     block_data: &BlockScopedData,
     ipfs_cache: &Arc<PostgresCache>,
+    network: &Network,
+    limits: &LimitsConfig,
 ) -> Result<KgData, IndexingError> {
-    let output = stream::utils::output(block_data);
-    let block_metadata = stream::utils::block_metadata(block_data);
-    let geo = GeoOutput::decode(output.value.as_slice())?;
+    let DecodedBlock {
+        block: block_metadata,
+        output: mut geo,
+        blocklisted_edits: blocklisted_count,
+        ..
+    } = decode_block(block_data)?;
+
+    for rejected in reject_invalid_addresses(&mut geo) {
+        warn!(
+            event = rejected.event,
+            field = rejected.field,
+            error = %rejected.error,
+            "Rejected chain event with an invalid address"
+        );
+    }
     let cache = ipfs_cache;
     let edits = Arc::new(Mutex::new(Vec::<PreprocessedEdit>::new()));
 
     let mut handles = Vec::new();
-    let mut blocklisted_count = 0;
-    let total_edits = geo.edits_published.len();
+    let total_edits = geo.edits_published.len() + blocklisted_count;
 
     // @TODO: We can separate this cache reading step into a separate module
-    for chain_edit in geo.edits_published.clone() {
-        if get_blocklist()
-            .dao_addresses
-            .contains(&chain_edit.dao_address.as_str())
-        {
-            blocklisted_count += 1;
-            debug!(
-                dao_address = %chain_edit.dao_address,
-                content_uri = %chain_edit.content_uri,
-                "Skipping blocklisted DAO"
-            );
-            continue;
-        }
-
+    for (event_index, chain_edit) in geo.edits_published.clone().into_iter().enumerate() {
         let cache = cache.clone();
         let edits_clone = edits.clone();
+        let limits = limits.clone();
 
         let content_uri = chain_edit.content_uri.clone();
         let dao_address = chain_edit.dao_address.clone();
+        let sender = Some(chain_edit.sender.clone())
+            .filter(|sender| !sender.is_empty())
+            .map(checksum_address);
 
         let handle = task::spawn(async move {
             // We retry requests to the cache in the case that the cache is
@@ -186,7 +719,10 @@ pub async fn preprocess_block_scoped_data(
                 .map(jitter);
 
             match Retry::spawn(retry, async || cache.get(&content_uri).await).await {
-                Ok(cached_edit_entry) => {
+                Ok(mut cached_edit_entry) => {
+                    cached_edit_entry.sender = sender;
+                    cached_edit_entry.event_index = event_index;
+
                     if cached_edit_entry.is_errored {
                         warn!(
                             dao_address = %dao_address,
@@ -195,6 +731,42 @@ pub async fn preprocess_block_scoped_data(
                         );
                     }
 
+                    // Edits over a limit are treated like edits that failed
+                    // to decode, so they're skipped without holding up the
+                    // block.
+                    let violation = cached_edit_entry
+                        .edit
+                        .as_ref()
+                        .and_then(|edit| check_edit_limits(edit, &limits).err());
+                    if let Some(violation) = violation {
+                        warn!(
+                            dao_address = %dao_address,
+                            content_uri = %content_uri,
+                            violation = %violation,
+                            "Edit is over a size limit, marking it as errored"
+                        );
+                        cached_edit_entry.edit = None;
+                        cached_edit_entry.is_errored = true;
+                    }
+
+                    if let Some(edit) = cached_edit_entry.edit.as_mut() {
+                        let diagnostics = remove_invalid_ops(edit);
+
+                        if !diagnostics.is_empty() {
+                            warn!(
+                                dao_address = %dao_address,
+                                content_uri = %content_uri,
+                                diagnostic_count = diagnostics.len(),
+                                diagnostics = %diagnostics
+                                    .iter()
+                                    .map(|diagnostic| diagnostic.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join("; "),
+                                "Edit is not well-formed, removed invalid ops"
+                            );
+                        }
+                    }
+
                     {
                         let mut edits_guard = edits_clone.lock().await;
                         edits_guard.push(cached_edit_entry);
@@ -233,10 +805,13 @@ pub async fn preprocess_block_scoped_data(
     }
 
     // Extract the edits from the Arc<Mutex<>> for further processing
-    let final_edits = {
+    let mut final_edits = {
         let edits_guard = edits.lock().await;
         edits_guard.clone() // Clone the vector to move it out of the mutex
     };
+    // Edits are pushed as their fetches complete, so they're put back in the
+    // order they were published on chain
+    final_edits.sort_by_key(|edit| edit.event_index);
 
     if blocklisted_count > 0 {
         info!(
@@ -259,6 +834,15 @@ pub async fn preprocess_block_scoped_data(
     let initial_editors = map_initial_editors_added(&geo.initial_editors_added);
     added_editors.extend(initial_editors.clone());
 
+    // Personal spaces may be created without an editor event for their
+    // creator, so the creator is inferred from the admin plugin
+    let personal_space_editors = map_personal_space_initial_editors(
+        &created_spaces,
+        &geo.personal_plugins_created,
+        &added_editors,
+    );
+    added_editors.extend(personal_space_editors);
+
     let mut added_members = map_members_added(&geo.members_added);
 
     // If any added editors come from a space created at the same time, add
@@ -282,6 +866,12 @@ pub async fn preprocess_block_scoped_data(
 
     let added_subspaces = map_subspaces_added(&geo.subspaces_added);
     let removed_subspaces = map_subspaces_removed(&geo.subspaces_removed);
+    let personal_admin_changes = map_personal_admin_changes(&geo.personal_plugins_created);
+    let governance_plugin_changes = map_governance_plugin_changes(&geo.governance_plugins_created);
+    let mut created_proposals = map_created_proposals(&geo);
+    add_proposal_previews(&mut created_proposals, &geo.edits, cache).await;
+    let executed_proposals = map_executed_proposals(&geo.executed_proposals);
+    let canceled_proposals = map_canceled_proposals(&geo.canceled_proposals);
 
     let kg_data = KgData {
         edits: final_edits.clone(),
@@ -292,7 +882,14 @@ pub async fn preprocess_block_scoped_data(
         removed_members: vec![],
         added_subspaces: added_subspaces.clone(),
         removed_subspaces: removed_subspaces.clone(),
+        personal_admin_changes,
+        governance_plugin_changes,
+        created_proposals,
+        executed_proposals,
+        canceled_proposals,
         block: block_metadata,
+        network: network.clone(),
+        unowned_properties: vec![],
     };
 
     info!(
@@ -311,14 +908,80 @@ pub async fn preprocess_block_scoped_data(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wire::pb::chain::{
-        GeoGovernancePluginCreated, GeoPersonalSpaceAdminPluginCreated, GeoSpaceCreated,
+    use uuid::Uuid;
+    use wire::{
+        builder::{value, EditBuilder},
+        pb::{
+            chain::{
+                GeoGovernancePluginCreated, GeoPersonalSpaceAdminPluginCreated, GeoSpaceCreated,
+            },
+            grc20::op::Payload,
+        },
     };
 
+    #[test]
+    fn test_check_edit_limits() {
+        let (entity, other, property) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(property, "a"), value(property, "b")])
+            .update_entity(other, [value(property, "c")])
+            .update_entity(entity, [value(property, "d")])
+            .build();
+
+        assert_eq!(check_edit_limits(&edit, &LimitsConfig::default()), Ok(()));
+
+        let limits = LimitsConfig {
+            max_ops_per_edit: Some(3),
+            max_edit_bytes: Some(edit.encoded_len()),
+            max_values_per_entity: Some(3),
+        };
+        assert_eq!(check_edit_limits(&edit, &limits), Ok(()));
+
+        assert_eq!(
+            check_edit_limits(
+                &edit,
+                &LimitsConfig {
+                    max_edit_bytes: Some(10),
+                    ..limits.clone()
+                }
+            ),
+            Err(LimitViolation::TooLarge {
+                bytes: edit.encoded_len(),
+                max: 10
+            })
+        );
+        assert_eq!(
+            check_edit_limits(
+                &edit,
+                &LimitsConfig {
+                    max_ops_per_edit: Some(2),
+                    ..limits.clone()
+                }
+            ),
+            Err(LimitViolation::TooManyOps { ops: 3, max: 2 })
+        );
+        // Values are counted across every op on the entity
+        assert_eq!(
+            check_edit_limits(
+                &edit,
+                &LimitsConfig {
+                    max_values_per_entity: Some(2),
+                    ..limits
+                }
+            ),
+            Err(LimitViolation::TooManyValues {
+                entity_id: entity,
+                values: 3,
+                max: 2
+            })
+        );
+    }
+
     fn create_test_space(dao_address: &str, space_address: &str) -> GeoSpaceCreated {
         GeoSpaceCreated {
             dao_address: dao_address.to_string(),
             space_address: space_address.to_string(),
+            ..GeoSpaceCreated::default()
         }
     }
 
@@ -444,6 +1107,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_match_space_creator() {
+        let spaces = vec![
+            GeoSpaceCreated {
+                creator: "creator1".to_string(),
+                tx_hash: "0x01".to_string(),
+                ..create_test_space("dao1", "space1")
+            },
+            create_test_space("dao2", "space2"),
+        ];
+        let governance_plugins = vec![create_test_governance_plugin("dao1", "voting1", "member1")];
+        let personal_plugins = vec![create_test_personal_plugin("dao2", "admin2", "editor2")];
+
+        let result = match_spaces_with_plugins(&spaces, &governance_plugins, &personal_plugins);
+
+        match &result[..] {
+            [CreatedSpace::Public(public_space), CreatedSpace::Personal(personal_space)] => {
+                assert_eq!(public_space.creator.as_deref(), Some("creator1"));
+                assert_eq!(public_space.tx_hash.as_deref(), Some("0x01"));
+                // Older streams don't include the creator
+                assert_eq!(personal_space.creator, None);
+                assert_eq!(personal_space.tx_hash, None);
+            }
+            _ => panic!("Expected a public and a personal space"),
+        }
+    }
+
     #[test]
     fn test_space_with_no_matching_plugin() {
         let spaces = vec![create_test_space("dao3", "space3")];
@@ -650,6 +1340,68 @@ mod tests {
         assert_eq!(result.len(), 0);
     }
 
+    #[test]
+    fn test_reject_invalid_addresses() {
+        let dao = "0x7e3dfcf5e438bab9d1c6f1a4542c916432fb9feb";
+        let plugin = "0x5A0b54D5dc17e0AadC383d2db43B0a0D3E029c4c";
+        let mut geo = wire::pb::chain::GeoOutput {
+            editors_added: vec![
+                create_test_editor_added(dao, plugin),
+                create_test_editor_added(dao, "0x1234"),
+            ],
+            initial_editors_added: vec![create_test_initial_editor_added(
+                dao,
+                vec![plugin, "editor"],
+            )],
+            edits_published: vec![wire::pb::chain::EditPublished {
+                content_uri: "ipfs://edit".to_string(),
+                plugin_address: plugin.to_string(),
+                dao_address: dao.to_string(),
+                sender: String::new(),
+            }],
+            edits: vec![wire::pb::chain::PublishEditProposalCreated {
+                proposal_id: "1".to_string(),
+                creator: plugin.to_lowercase().replacen("0x5a", "0x5A", 1),
+                dao_address: dao.to_string(),
+                plugin_address: plugin.to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let rejected = reject_invalid_addresses(&mut geo);
+
+        assert_eq!(
+            rejected,
+            vec![
+                RejectedEvent {
+                    event: "editors_added",
+                    field: "editor_address",
+                    error: AddressError::Malformed("0x1234".to_string()),
+                },
+                RejectedEvent {
+                    event: "initial_editors_added",
+                    field: "addresses",
+                    error: AddressError::Malformed("editor".to_string()),
+                },
+                RejectedEvent {
+                    event: "edits",
+                    field: "creator",
+                    error: AddressError::Checksum {
+                        address: "0x5A0b54d5dc17e0aadc383d2db43b0a0d3e029c4c".to_string(),
+                        expected: plugin.to_string(),
+                    },
+                },
+            ]
+        );
+        assert_eq!(geo.editors_added.len(), 1);
+        assert_eq!(geo.editors_added[0].editor_address, plugin);
+        assert!(geo.initial_editors_added.is_empty());
+        // An edit without a sender is kept
+        assert_eq!(geo.edits_published.len(), 1);
+        assert!(geo.edits.is_empty());
+    }
+
     #[test]
     fn test_map_members_added_empty() {
         let members = vec![];
@@ -799,6 +1551,66 @@ mod tests {
             .any(|m| m.dao_address == "dao3" && m.editor_address == "editor4"));
     }
 
+    #[test]
+    fn test_personal_space_creator_added_as_initial_editor() {
+        let spaces = vec![
+            create_test_space("dao1", "space1"),
+            create_test_space("dao2", "space2"),
+            create_test_space("dao3", "space3"),
+        ];
+        let governance_plugins = vec![create_test_governance_plugin("dao1", "voting1", "member1")];
+        let personal_plugins = vec![
+            create_test_personal_plugin("dao2", "admin2", "creator2"),
+            create_test_personal_plugin("dao3", "admin3", "creator3"),
+            create_test_personal_plugin("dao4", "admin4", "creator4"), // No space created
+        ];
+
+        // dao3's creator already has an editor event, with different casing
+        let editors = vec![create_test_editor_added("dao3", "CREATOR3")];
+
+        let created_spaces =
+            match_spaces_with_plugins(&spaces, &governance_plugins, &personal_plugins);
+        let mut added_editors = map_editors_added(&editors);
+
+        let inferred =
+            map_personal_space_initial_editors(&created_spaces, &personal_plugins, &added_editors);
+
+        assert_eq!(inferred.len(), 1);
+        assert_eq!(inferred[0].dao_address, "dao2");
+        assert_eq!(inferred[0].editor_address, "creator2");
+
+        // Like other editors of newly created spaces, the creator also becomes
+        // a member
+        added_editors.extend(inferred);
+        let created_space_dao_addresses: std::collections::HashSet<String> = created_spaces
+            .iter()
+            .map(|space| match space {
+                CreatedSpace::Personal(personal_space) => personal_space.dao_address.clone(),
+                CreatedSpace::Public(public_space) => public_space.dao_address.clone(),
+            })
+            .collect();
+        let added_members: Vec<&AddedMember> = added_editors
+            .iter()
+            .filter(|editor| created_space_dao_addresses.contains(&editor.dao_address))
+            .collect();
+
+        assert!(added_members
+            .iter()
+            .any(|m| m.dao_address == "dao2" && m.editor_address == "creator2"));
+        assert!(!added_members.iter().any(|m| m.dao_address == "dao4"));
+    }
+
+    #[test]
+    fn test_personal_space_without_initial_editor() {
+        let spaces = vec![create_test_space("dao1", "space1")];
+        let personal_plugins = vec![create_test_personal_plugin("dao1", "admin1", "")];
+        let created_spaces = match_spaces_with_plugins(&spaces, &[], &personal_plugins);
+
+        assert!(
+            map_personal_space_initial_editors(&created_spaces, &personal_plugins, &[]).is_empty()
+        );
+    }
+
     #[test]
     fn test_map_subspaces_added_empty() {
         let subspaces = vec![];
@@ -868,4 +1680,141 @@ mod tests {
         assert_eq!(result[2].dao_address, "dao1");
         assert_eq!(result[2].subspace_address, "subspace3");
     }
+
+    #[test]
+    fn test_map_created_proposals() {
+        let geo = wire::pb::chain::GeoOutput {
+            edits: vec![wire::pb::chain::PublishEditProposalCreated {
+                proposal_id: "1".to_string(),
+                creator: "creator1".to_string(),
+                dao_address: "dao1".to_string(),
+                plugin_address: "plugin1".to_string(),
+                ..Default::default()
+            }],
+            proposed_added_members: vec![wire::pb::chain::AddMemberProposalCreated {
+                proposal_id: "2".to_string(),
+                creator: "creator2".to_string(),
+                dao_address: "dao2".to_string(),
+                plugin_address: "plugin2".to_string(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let result = map_created_proposals(&geo);
+
+        assert_eq!(result.len(), 2);
+        assert_eq!(result[0].proposal_id, "1");
+        assert_eq!(result[0].proposal_type, ProposalType::PublishEdit);
+        assert_eq!(result[0].creator, "creator1");
+        assert_eq!(result[0].dao_address, "dao1");
+        assert_eq!(result[1].proposal_id, "2");
+        assert_eq!(result[1].proposal_type, ProposalType::AddMember);
+        assert_eq!(result[1].plugin_address, "plugin2");
+    }
+
+    #[test]
+    fn test_remove_invalid_ops() {
+        let entity = Uuid::new_v4();
+        let mut edit = EditBuilder::new(Uuid::new_v4())
+            .author(Uuid::new_v4())
+            .update_entity(entity, [])
+            .delete_relation(Uuid::new_v4())
+            .update_entity(entity, [])
+            .build();
+        edit.authors[0].truncate(4);
+        edit.ops[1].payload = Some(Payload::DeleteRelation(vec![1; 4]));
+
+        let diagnostics = remove_invalid_ops(&mut edit);
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].op_index, None);
+        assert_eq!(diagnostics[1].op_index, Some(1));
+        assert_eq!(edit.ops.len(), 2);
+        assert!(edit
+            .ops
+            .iter()
+            .all(|op| matches!(op.payload, Some(Payload::UpdateEntity(_)))));
+    }
+
+    #[test]
+    fn test_remove_invalid_ops_well_formed_edit() {
+        let mut edit = EditBuilder::new(Uuid::new_v4())
+            .delete_relation(Uuid::new_v4())
+            .build();
+
+        assert!(remove_invalid_ops(&mut edit).is_empty());
+        assert_eq!(edit.ops.len(), 1);
+    }
+
+    /// The package the indexer streams from, which has to be rebuilt when
+    /// the substream's schema changes.
+    fn shipped_package() -> stream::pb::sf::substreams::v1::Package {
+        let content = std::fs::read(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/../geo_substream.spkg"
+        ))
+        .unwrap();
+        stream::pb::sf::substreams::v1::Package::decode(content.as_slice()).unwrap()
+    }
+
+    /// The fields of a message of the shipped package's schema, by name and
+    /// number.
+    fn shipped_fields(message: &str) -> Vec<(String, i32)> {
+        let package = shipped_package();
+        let schema = package
+            .proto_files
+            .iter()
+            .find(|file| file.name() == "schema.proto")
+            .unwrap();
+        schema
+            .message_type
+            .iter()
+            .find(|message_type| message_type.name() == message)
+            .unwrap()
+            .field
+            .iter()
+            .map(|field| (field.name().to_string(), field.number()))
+            .collect()
+    }
+
+    #[test]
+    fn test_shipped_package_reports_senders() {
+        assert!(shipped_fields("EditPublished").contains(&("sender".to_string(), 4)));
+    }
+
+    #[test]
+    fn test_shipped_package_maps_canceled_proposals() {
+        use stream::pb::sf::substreams::v1::module::input::{Input, Map};
+
+        let package = shipped_package();
+        let modules = package.modules.unwrap().modules;
+        assert!(modules
+            .iter()
+            .any(|module| module.name == "map_proposals_canceled"));
+
+        let geo_out = modules
+            .iter()
+            .find(|module| module.name == "geo_out")
+            .unwrap();
+        let last_input = geo_out.inputs.last().and_then(|input| input.input.clone());
+        assert_eq!(
+            last_input,
+            Some(Input::Map(Map {
+                module_name: "map_proposals_canceled".to_string(),
+            }))
+        );
+        assert!(shipped_fields("GeoOutput").contains(&("canceled_proposals".to_string(), 22)));
+    }
+
+    #[test]
+    fn test_shipped_package_reports_execution_transactions() {
+        assert!(shipped_fields("ProposalExecuted").contains(&("tx_hash".to_string(), 3)));
+    }
+
+    #[test]
+    fn test_shipped_package_reports_space_creators() {
+        let fields = shipped_fields("GeoSpaceCreated");
+        assert!(fields.contains(&("creator".to_string(), 3)));
+        assert!(fields.contains(&("tx_hash".to_string(), 4)));
+    }
 }
@@ -0,0 +1,143 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use uuid::Uuid;
+
+use super::{check_response, SearchDocument, SearchError, SearchIndex};
+
+/// An Elasticsearch index, written through the bulk API.
+pub struct ElasticsearchIndex {
+    client: reqwest::Client,
+    url: String,
+    index: String,
+    api_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BulkResponse {
+    errors: bool,
+}
+
+impl ElasticsearchIndex {
+    pub fn new(url: &str, index: &str, api_key: Option<String>) -> Result<Self, SearchError> {
+        Ok(ElasticsearchIndex {
+            client: reqwest::Client::builder().build()?,
+            url: url.trim_end_matches('/').to_string(),
+            index: index.to_string(),
+            api_key,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", self.url, path));
+
+        match &self.api_key {
+            Some(api_key) => request.header(
+                reqwest::header::AUTHORIZATION,
+                format!("ApiKey {}", api_key),
+            ),
+            None => request,
+        }
+    }
+
+    /// Sends a bulk request. The bulk API responds with 200 even if some
+    /// actions fail, so the response body is checked too.
+    async fn bulk(&self, body: String) -> Result<(), SearchError> {
+        let response = self
+            .request(reqwest::Method::POST, "/_bulk")
+            .header(reqwest::header::CONTENT_TYPE, "application/x-ndjson")
+            .body(body)
+            .send()
+            .await?;
+        let response = check_response(response).await?;
+        let body = response.text().await?;
+
+        if serde_json::from_str::<BulkResponse>(&body)?.errors {
+            return Err(SearchError::Status { status: 200, body });
+        }
+
+        Ok(())
+    }
+}
+
+/// The newline-delimited body of a bulk request indexing the documents.
+fn index_body(index: &str, documents: &[SearchDocument]) -> Result<String, SearchError> {
+    let mut body = String::new();
+
+    for document in documents {
+        body.push_str(&json!({ "index": { "_index": index, "_id": document.id } }).to_string());
+        body.push('\n');
+        body.push_str(&serde_json::to_string(document)?);
+        body.push('\n');
+    }
+
+    Ok(body)
+}
+
+/// The newline-delimited body of a bulk request deleting the documents.
+fn delete_body(index: &str, ids: &[Uuid]) -> String {
+    ids.iter()
+        .map(|id| format!("{}\n", json!({ "delete": { "_index": index, "_id": id } })))
+        .collect()
+}
+
+#[async_trait]
+impl SearchIndex for ElasticsearchIndex {
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), SearchError> {
+        self.bulk(index_body(&self.index, documents)?).await
+    }
+
+    async fn delete(&self, ids: &[Uuid]) -> Result<(), SearchError> {
+        self.bulk(delete_body(&self.index, ids)).await
+    }
+
+    async fn clear(&self) -> Result<(), SearchError> {
+        let response = self
+            .request(
+                reqwest::Method::POST,
+                &format!("/{}/_delete_by_query", self.index),
+            )
+            .json(&json!({ "query": { "match_all": {} } }))
+            .send()
+            .await?;
+
+        // The index doesn't exist before the first write
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(());
+        }
+        check_response(response).await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bulk_bodies() {
+        let document = SearchDocument {
+            id: Uuid::nil(),
+            name: Some("Alice".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            index_body("entities", &[document]).unwrap(),
+            concat!(
+                r#"{"index":{"_id":"00000000-0000-0000-0000-000000000000","_index":"entities"}}"#,
+                "\n",
+                r#"{"id":"00000000-0000-0000-0000-000000000000","name":"Alice","description":null,"types":[],"space_ids":[]}"#,
+                "\n",
+            )
+        );
+        assert_eq!(
+            delete_body("entities", &[Uuid::nil()]),
+            concat!(
+                r#"{"delete":{"_id":"00000000-0000-0000-0000-000000000000","_index":"entities"}}"#,
+                "\n",
+            )
+        );
+    }
+}
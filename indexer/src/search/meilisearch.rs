@@ -0,0 +1,69 @@
+use async_trait::async_trait;
+use uuid::Uuid;
+
+use super::{check_response, SearchDocument, SearchError, SearchIndex};
+
+/// A Meilisearch index. Meilisearch applies writes asynchronously, so
+/// documents become searchable shortly after a write returns.
+pub struct MeilisearchIndex {
+    client: reqwest::Client,
+    /// The url of the index, like `http://localhost:7700/indexes/entities`.
+    index_url: String,
+    api_key: Option<String>,
+}
+
+impl MeilisearchIndex {
+    pub fn new(url: &str, index: &str, api_key: Option<String>) -> Result<Self, SearchError> {
+        Ok(MeilisearchIndex {
+            client: reqwest::Client::builder().build()?,
+            index_url: format!("{}/indexes/{}", url.trim_end_matches('/'), index),
+            api_key,
+        })
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}{}", self.index_url, path));
+
+        match &self.api_key {
+            Some(api_key) => request.bearer_auth(api_key),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl SearchIndex for MeilisearchIndex {
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), SearchError> {
+        let response = self
+            .request(reqwest::Method::POST, "/documents?primaryKey=id")
+            .json(documents)
+            .send()
+            .await?;
+        check_response(response).await?;
+
+        Ok(())
+    }
+
+    async fn delete(&self, ids: &[Uuid]) -> Result<(), SearchError> {
+        let response = self
+            .request(reqwest::Method::POST, "/documents/delete-batch")
+            .json(ids)
+            .send()
+            .await?;
+        check_response(response).await?;
+
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), SearchError> {
+        let response = self
+            .request(reqwest::Method::DELETE, "/documents")
+            .send()
+            .await?;
+        check_response(response).await?;
+
+        Ok(())
+    }
+}
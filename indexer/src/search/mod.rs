@@ -0,0 +1,371 @@
+//! Mirrors entity names, descriptions and types into an external search
+//! engine so products get typo-tolerant search over the knowledge graph.
+//!
+//! The index holds one document per entity. [`SearchSync::sync_block`]
+//! updates the entities an indexed block changed, and
+//! [`SearchSync::reindex`] rebuilds the index from the database.
+
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use indexer_utils::{
+    config::{SearchConfig, SearchEngine},
+    id,
+    system_ids::{DESCRIPTION_PROPERTY, NAME_PROPERTY, TYPES_PROPERTY},
+};
+use serde::Serialize;
+use sqlx::PgPool;
+use thiserror::Error;
+use tracing::info;
+use uuid::Uuid;
+use wire::pb::grc20::{op::Payload, Edit};
+
+use crate::KgData;
+
+pub mod elasticsearch;
+pub mod meilisearch;
+
+#[derive(Error, Debug)]
+pub enum SearchError {
+    #[error("Search error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Search error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Search engine responded with status {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("Search error: {0}")]
+    Serialize(#[from] serde_json::Error),
+}
+
+/// An entity as it's stored in the search index.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, sqlx::FromRow)]
+pub struct SearchDocument {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub types: Vec<Uuid>,
+    /// The spaces with values or relations on the entity.
+    pub space_ids: Vec<Uuid>,
+}
+
+impl SearchDocument {
+    /// Whether there's nothing to search the entity by, in which case it's
+    /// left out of the index.
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none() && self.description.is_none() && self.types.is_empty()
+    }
+}
+
+/// A search engine index that documents are written to.
+#[async_trait]
+pub trait SearchIndex: Send + Sync {
+    /// Adds the documents, replacing any with the same id.
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), SearchError>;
+
+    async fn delete(&self, ids: &[Uuid]) -> Result<(), SearchError>;
+
+    /// Removes every document.
+    async fn clear(&self) -> Result<(), SearchError>;
+}
+
+/// Returns the entities whose name, description or types an edit may
+/// change, in op order.
+///
+/// Types removed with `DeleteRelation` aren't picked up, as the relation's
+/// entity isn't known from the edit. They're dropped from the index the next
+/// time the entity changes or the index is rebuilt.
+pub fn map_edit_to_search_entities(edit: &Edit) -> Vec<Uuid> {
+    let mut entity_ids = Vec::new();
+    let mut seen = HashSet::new();
+
+    for op in &edit.ops {
+        let id = match &op.payload {
+            Some(Payload::UpdateEntity(entity))
+                if entity
+                    .values
+                    .iter()
+                    .any(|value| is_search_property(&value.property)) =>
+            {
+                Some(&entity.id)
+            }
+            Some(Payload::UnsetEntityValues(unset))
+                if unset
+                    .properties
+                    .iter()
+                    .any(|property| is_search_property(property)) =>
+            {
+                Some(&unset.id)
+            }
+            Some(Payload::CreateRelation(relation)) if is_id(&relation.r#type, TYPES_PROPERTY) => {
+                Some(&relation.from_entity)
+            }
+            Some(Payload::DeleteEntity(entity)) => Some(entity),
+            _ => None,
+        };
+
+        let entity_id = id.and_then(|id| id::transform_id_bytes(id.clone()).ok());
+
+        if let Some(entity_id) = entity_id.map(Uuid::from_bytes) {
+            if seen.insert(entity_id) {
+                entity_ids.push(entity_id);
+            }
+        }
+    }
+
+    entity_ids
+}
+
+fn is_search_property(property: &[u8]) -> bool {
+    is_id(property, NAME_PROPERTY) || is_id(property, DESCRIPTION_PROPERTY)
+}
+
+fn is_id(bytes: &[u8], expected: Uuid) -> bool {
+    id::transform_id_bytes(bytes.to_vec()).is_ok_and(|id| Uuid::from_bytes(id) == expected)
+}
+
+/// Writes entities from the database to a search index.
+pub struct SearchSync {
+    pool: PgPool,
+    index: Arc<dyn SearchIndex>,
+    batch_size: usize,
+    /// The entities changed by deferred blocks.
+    deferred: Mutex<HashSet<Uuid>>,
+}
+
+impl SearchSync {
+    pub fn new(pool: PgPool, index: Arc<dyn SearchIndex>, batch_size: usize) -> Self {
+        SearchSync {
+            pool,
+            index,
+            batch_size,
+            deferred: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Connects to the configured search engine, or returns `None` if no
+    /// search URL is set.
+    pub fn from_config(pool: PgPool, config: &SearchConfig) -> Result<Option<Self>, SearchError> {
+        let Some(url) = &config.url else {
+            return Ok(None);
+        };
+
+        let index: Arc<dyn SearchIndex> = match config.engine {
+            SearchEngine::Meilisearch => Arc::new(meilisearch::MeilisearchIndex::new(
+                url,
+                &config.index,
+                config.api_key.clone(),
+            )?),
+            SearchEngine::Elasticsearch => Arc::new(elasticsearch::ElasticsearchIndex::new(
+                url,
+                &config.index,
+                config.api_key.clone(),
+            )?),
+        };
+
+        Ok(Some(SearchSync::new(pool, index, config.batch_size)))
+    }
+
+    /// Updates the entities changed by the edits of a block, which must
+    /// already be written, and by the blocks deferred before it. Entities
+    /// left without a name, description or type are removed. Returns the
+    /// number of entities synced.
+    pub async fn sync_block(&self, data: &KgData) -> Result<usize, SearchError> {
+        let mut entity_ids: Vec<Uuid> = std::mem::take(&mut *self.deferred.lock().unwrap())
+            .into_iter()
+            .collect();
+        let mut seen: HashSet<Uuid> = entity_ids.iter().copied().collect();
+
+        for edit in data.edits.iter().filter_map(|edit| edit.edit.as_ref()) {
+            for entity_id in map_edit_to_search_entities(edit) {
+                if seen.insert(entity_id) {
+                    entity_ids.push(entity_id);
+                }
+            }
+        }
+
+        for chunk in entity_ids.chunks(self.batch_size) {
+            let (empty, documents): (Vec<SearchDocument>, Vec<SearchDocument>) = self
+                .documents(chunk)
+                .await?
+                .into_iter()
+                .partition(SearchDocument::is_empty);
+
+            if !documents.is_empty() {
+                self.index.upsert(&documents).await?;
+            }
+            if !empty.is_empty() {
+                let ids: Vec<Uuid> = empty.iter().map(|document| document.id).collect();
+                self.index.delete(&ids).await?;
+            }
+        }
+
+        Ok(entity_ids.len())
+    }
+
+    /// Remembers the entities changed by the edits of a block, so they're
+    /// synced with the next block that's synced instead.
+    pub fn defer_block(&self, data: &KgData) {
+        let mut deferred = self.deferred.lock().unwrap();
+        for edit in data.edits.iter().filter_map(|edit| edit.edit.as_ref()) {
+            deferred.extend(map_edit_to_search_entities(edit));
+        }
+    }
+
+    /// Clears the index and writes every entity to it. Returns the number
+    /// of documents written.
+    pub async fn reindex(&self) -> Result<usize, SearchError> {
+        self.index.clear().await?;
+
+        let mut last_id = Uuid::nil();
+        let mut written = 0;
+
+        loop {
+            let entity_ids: Vec<Uuid> =
+                sqlx::query_scalar("SELECT id FROM entities WHERE id > $1 ORDER BY id LIMIT $2")
+                    .bind(last_id)
+                    .bind(self.batch_size as i64)
+                    .fetch_all(&self.pool)
+                    .await?;
+
+            let Some(last) = entity_ids.last() else {
+                break;
+            };
+            last_id = *last;
+
+            let documents: Vec<SearchDocument> = self
+                .documents(&entity_ids)
+                .await?
+                .into_iter()
+                .filter(|document| !document.is_empty())
+                .collect();
+
+            if !documents.is_empty() {
+                self.index.upsert(&documents).await?;
+                written += documents.len();
+            }
+
+            info!(
+                written = written,
+                last_id = %last_id,
+                "Reindexing search documents"
+            );
+        }
+
+        Ok(written)
+    }
+
+    /// Loads the documents of the given entities, in order. The first name
+    /// and description by space id are used when several spaces set them.
+    pub async fn documents(&self, entity_ids: &[Uuid]) -> Result<Vec<SearchDocument>, SearchError> {
+        let documents = sqlx::query_as::<_, SearchDocument>(
+            r#"
+            SELECT
+                ids.id,
+                (
+                    SELECT v.string FROM values v
+                    WHERE v.entity_id = ids.id AND v.property_id = $2 AND v.string IS NOT NULL
+                    ORDER BY v.space_id LIMIT 1
+                ) AS name,
+                (
+                    SELECT v.string FROM values v
+                    WHERE v.entity_id = ids.id AND v.property_id = $3 AND v.string IS NOT NULL
+                    ORDER BY v.space_id LIMIT 1
+                ) AS description,
+                ARRAY(
+                    SELECT DISTINCT r.to_entity_id FROM relations r
+                    WHERE r.from_entity_id = ids.id AND r.type_id = $4
+                    ORDER BY r.to_entity_id
+                ) AS types,
+                ARRAY(
+                    SELECT v.space_id FROM values v WHERE v.entity_id = ids.id
+                    UNION
+                    SELECT r.space_id FROM relations r WHERE r.from_entity_id = ids.id
+                    ORDER BY 1
+                ) AS space_ids
+            FROM UNNEST($1::uuid[]) WITH ORDINALITY AS ids(id, ordinality)
+            ORDER BY ids.ordinality
+            "#,
+        )
+        .bind(entity_ids)
+        .bind(NAME_PROPERTY)
+        .bind(DESCRIPTION_PROPERTY)
+        .bind(TYPES_PROPERTY)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(documents)
+    }
+}
+
+/// Returns the response if its status is a success, or its status and body
+/// as an error otherwise.
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response, SearchError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    Err(SearchError::Status {
+        status: status.as_u16(),
+        body: response.text().await.unwrap_or_default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::builder::{relation, value, EditBuilder};
+
+    #[test]
+    fn test_map_edit_to_search_entities() {
+        let (named, typed, other, deleted) = (
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+        );
+        let other_property = Uuid::new_v4();
+
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(named, [value(NAME_PROPERTY, "Alice")])
+            .update_entity(other, [value(other_property, "ignored")])
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                TYPES_PROPERTY,
+                typed,
+                Uuid::new_v4(),
+            ))
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                other_property,
+                other,
+                Uuid::new_v4(),
+            ))
+            .unset_entity_values(named, [DESCRIPTION_PROPERTY])
+            .delete_entity(deleted)
+            .build();
+
+        assert_eq!(
+            map_edit_to_search_entities(&edit),
+            vec![named, typed, deleted]
+        );
+    }
+
+    #[test]
+    fn test_document_is_empty() {
+        assert!(SearchDocument::default().is_empty());
+        assert!(!SearchDocument {
+            types: vec![Uuid::new_v4()],
+            ..Default::default()
+        }
+        .is_empty());
+    }
+}
@@ -0,0 +1,328 @@
+use std::time::Duration;
+
+use indexer_utils::{checksum_address, config::ShardingConfig};
+use sqlx::{Connection, PgConnection, PgPool};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::{models::properties::PropertiesModel, CreatedSpace, KgData};
+
+/// The subset of spaces an indexer instance owns when indexing is split
+/// between several instances.
+///
+/// Spaces are assigned to shards with jump consistent hashing over their
+/// ids, so every instance agrees on the assignment without coordinating and
+/// adding a shard only moves spaces onto the new shard. Every instance
+/// streams every block and drops the events of spaces it doesn't own. All
+/// instances must use the same shard count.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Shard {
+    pub index: u32,
+    pub count: u32,
+}
+
+impl Shard {
+    pub fn new(index: u32, count: u32) -> Self {
+        assert!(
+            index < count,
+            "shard {} is out of range 0..{}",
+            index,
+            count
+        );
+        Shard { index, count }
+    }
+
+    /// Returns the configured shard, or `None` if sharding is disabled.
+    pub fn from_config(config: &ShardingConfig) -> Option<Self> {
+        config
+            .is_enabled()
+            .then(|| Shard::new(config.index, config.count))
+    }
+
+    /// Returns the shard a space belongs to out of `count` shards.
+    pub fn of(space_id: &Uuid, count: u32) -> u32 {
+        let (high, low) = space_id.as_u64_pair();
+        jump_hash(high ^ low, count)
+    }
+
+    pub fn owns(&self, space_id: &Uuid) -> bool {
+        Shard::of(space_id, self.count) == self.index
+    }
+
+    /// Scopes a cursor id to the shard, as each shard streams and persists
+    /// its cursor independently.
+    pub fn cursor_id(&self, cursor_id: &str) -> String {
+        format!("{}:shard-{}-of-{}", cursor_id, self.index, self.count)
+    }
+
+    /// Drops the events of every space owned by another shard. Subspace
+    /// events belong to the parent space. Executed and canceled proposals are
+    /// kept.
+    ///
+    /// Values in any space can use properties created in any other space, so
+    /// the properties created by dropped edits are kept to be cached.
+    pub fn filter(&self, mut data: KgData) -> KgData {
+        let network = data.network.clone();
+        let owns_dao =
+            |dao_address: &str| self.owns(&network.space_id(&checksum_address(dao_address)));
+
+        let (edits, unowned_edits): (Vec<_>, Vec<_>) = std::mem::take(&mut data.edits)
+            .into_iter()
+            .partition(|edit| self.owns(&edit.space_id));
+        data.edits = edits;
+        data.unowned_properties
+            .extend(PropertiesModel::map_edits_to_properties(
+                unowned_edits
+                    .iter()
+                    .filter(|edit| !edit.is_errored)
+                    .filter_map(|edit| edit.edit.as_ref()),
+            ));
+        data.spaces.retain(|space| match space {
+            CreatedSpace::Personal(space) => owns_dao(&space.dao_address),
+            CreatedSpace::Public(space) => owns_dao(&space.dao_address),
+        });
+        data.added_editors
+            .retain(|editor| owns_dao(&editor.dao_address));
+        data.removed_editors
+            .retain(|editor| owns_dao(&editor.dao_address));
+        data.added_members
+            .retain(|member| owns_dao(&member.dao_address));
+        data.removed_members
+            .retain(|member| owns_dao(&member.dao_address));
+        data.added_subspaces
+            .retain(|subspace| owns_dao(&subspace.dao_address));
+        data.removed_subspaces
+            .retain(|subspace| owns_dao(&subspace.dao_address));
+        data.personal_admin_changes
+            .retain(|change| owns_dao(&change.dao_address));
+        data.governance_plugin_changes
+            .retain(|change| owns_dao(&change.dao_address));
+        data.created_proposals
+            .retain(|proposal| owns_dao(&proposal.dao_address));
+        // Executed and canceled proposals can't be attributed to a space until
+        // their plugin is looked up, so they're kept by every shard
+
+        data
+    }
+}
+
+/// Jump consistent hash (Lamping and Veach, 2014). Maps a key to one of
+/// `buckets` buckets such that growing the number of buckets by one only
+/// moves keys into the new bucket.
+fn jump_hash(mut key: u64, buckets: u32) -> u32 {
+    let mut bucket: i64 = -1;
+    let mut next: i64 = 0;
+
+    while next < buckets as i64 {
+        bucket = next;
+        key = key.wrapping_mul(2862933555777941757).wrapping_add(1);
+        next = ((bucket + 1) as f64 * ((1u64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+
+    bucket as u32
+}
+
+/// Exclusive ownership of a shard, so only one of the instances configured
+/// for a shard indexes it and the others stand by to take over.
+///
+/// The lease is a session-level Postgres advisory lock held on a dedicated
+/// connection. It is released when the lease is dropped or its connection
+/// is lost, at which point a standby acquires it.
+pub struct ShardLease {
+    connection: PgConnection,
+    key: String,
+}
+
+impl ShardLease {
+    /// Waits until the lease for `key` is acquired, retrying every
+    /// `retry_interval`.
+    pub async fn acquire(
+        pool: &PgPool,
+        key: &str,
+        retry_interval: Duration,
+    ) -> Result<Self, sqlx::Error> {
+        // Detached so the pool doesn't recycle the connection holding the lock.
+        let mut connection = pool.acquire().await?.detach();
+        let mut waiting = false;
+
+        loop {
+            let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock(hashtext($1))")
+                .bind(key)
+                .fetch_one(&mut connection)
+                .await?;
+
+            if acquired {
+                info!(key = %key, "Acquired shard lease");
+                return Ok(ShardLease {
+                    connection,
+                    key: key.to_string(),
+                });
+            }
+
+            if !waiting {
+                info!(key = %key, "Shard lease is held by another instance, standing by");
+                waiting = true;
+            }
+
+            tokio::time::sleep(retry_interval).await;
+        }
+    }
+
+    /// Checks the lease every `interval` and returns once it is lost. The
+    /// lock is held for as long as its session is alive.
+    pub async fn watch(mut self, interval: Duration) -> sqlx::Error {
+        loop {
+            tokio::time::sleep(interval).await;
+
+            if let Err(error) = self.connection.ping().await {
+                tracing::error!(key = %self.key, error = %error, "Lost shard lease");
+                return error;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{cache::PreprocessedEdit, test_utils::KgDataBuilder, AddedMember, PublicSpace};
+    use indexer_utils::network_ids::Network;
+    use stream::utils::BlockMetadata;
+    use wire::{builder::EditBuilder, pb::grc20::DataType};
+
+    fn space_ids(count: usize) -> Vec<Uuid> {
+        (0..count).map(|_| Uuid::new_v4()).collect()
+    }
+
+    fn dao_in_shard(network: &Network, index: u32, count: u32) -> String {
+        (0..)
+            .map(|i| format!("0x{:040x}", i))
+            .find(|dao| {
+                let space_id = network.space_id(&checksum_address(dao.as_str()));
+                Shard::of(&space_id, count) == index
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_every_space_has_one_owner() {
+        let shards: Vec<Shard> = (0..4).map(|index| Shard::new(index, 4)).collect();
+
+        for space_id in space_ids(1000) {
+            let owners = shards.iter().filter(|shard| shard.owns(&space_id)).count();
+            assert_eq!(owners, 1);
+        }
+    }
+
+    #[test]
+    fn test_adding_a_shard_only_moves_spaces_to_it() {
+        let space_ids = space_ids(1000);
+        let mut moved = 0;
+
+        for space_id in &space_ids {
+            let before = Shard::of(space_id, 4);
+            let after = Shard::of(space_id, 5);
+
+            if before != after {
+                assert_eq!(after, 4);
+                moved += 1;
+            }
+        }
+
+        // Roughly a fifth of the spaces move to the new shard
+        assert!((100..300).contains(&moved), "moved {} spaces", moved);
+    }
+
+    #[test]
+    fn test_filter_keeps_owned_spaces() {
+        let network = Network::default();
+        let owned = dao_in_shard(&network, 0, 2);
+        let other = dao_in_shard(&network, 1, 2);
+        let member = |dao: &str| AddedMember {
+            dao_address: dao.to_string(),
+            editor_address: "0x0000000000000000000000000000000000000001".to_string(),
+        };
+        let edit = |dao: &str| PreprocessedEdit {
+            cid: dao.to_string(),
+            edit: None,
+            is_errored: true,
+            space_id: network.space_id(&checksum_address(dao)),
+            sender: None,
+            event_index: 0,
+        };
+
+        let data = KgData {
+            block: BlockMetadata {
+                cursor: "1".to_string(),
+                block_number: 1,
+                timestamp: "1234567890".to_string(),
+            },
+            edits: vec![edit(&owned), edit(&other)],
+            added_editors: vec![member(&owned), member(&other)],
+            removed_editors: vec![],
+            added_members: vec![member(&other)],
+            removed_members: vec![],
+            added_subspaces: vec![],
+            removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            spaces: vec![CreatedSpace::Public(PublicSpace {
+                dao_address: other.clone(),
+                space_address: "space".to_string(),
+                membership_plugin: "membership".to_string(),
+                governance_plugin: "governance".to_string(),
+                creator: None,
+                tx_hash: None,
+            })],
+            network: network.clone(),
+            unowned_properties: vec![],
+        };
+
+        let filtered = Shard::new(0, 2).filter(data);
+
+        assert_eq!(filtered.edits.len(), 1);
+        assert_eq!(filtered.edits[0].cid, owned);
+        assert_eq!(filtered.added_editors.len(), 1);
+        assert_eq!(filtered.added_editors[0].dao_address, owned);
+        assert!(filtered.added_members.is_empty());
+        assert!(filtered.spaces.is_empty());
+    }
+
+    #[test]
+    fn test_filter_keeps_properties_of_unowned_spaces() {
+        let network = Network::default();
+        let other = dao_in_shard(&network, 1, 2);
+        let property = Uuid::new_v4();
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(property, DataType::Text)
+            .build();
+
+        let filtered = Shard::new(0, 2).filter(
+            KgDataBuilder::new(1)
+                .edit(PreprocessedEdit {
+                    cid: "ipfs://unowned-property".to_string(),
+                    edit: Some(edit),
+                    is_errored: false,
+                    space_id: network.space_id(&checksum_address(&other)),
+                    sender: None,
+                    event_index: 0,
+                })
+                .build(),
+        );
+
+        assert!(filtered.edits.is_empty());
+        assert_eq!(filtered.unowned_properties.len(), 1);
+        assert_eq!(filtered.unowned_properties[0].id, property);
+    }
+
+    #[test]
+    fn test_cursor_id_is_scoped_to_shard() {
+        assert_eq!(
+            Shard::new(1, 4).cursor_id("kg_indexer"),
+            "kg_indexer:shard-1-of-4"
+        );
+    }
+}
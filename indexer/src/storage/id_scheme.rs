@@ -0,0 +1,65 @@
+//! Records the scheme a database's space and value ids are derived with.
+//!
+//! Ids derived with different schemes never match, so an indexer started
+//! with another scheme than the one a database was indexed with would write
+//! a second copy of every space. [`PostgresStorage::check_id_scheme`] makes
+//! it refuse to start instead.
+
+use indexer_utils::id::IdScheme;
+use tracing::info;
+use uuid::Uuid;
+
+use super::{postgres::PostgresStorage, StorageError};
+
+impl PostgresStorage {
+    /// Checks the database's ids are derived with the scheme. The scheme is
+    /// recorded if the database has none recorded yet, unless its spaces
+    /// were indexed with another one.
+    pub async fn check_id_scheme(&self, scheme: IdScheme) -> Result<(), StorageError> {
+        let recorded: Option<String> = sqlx::query_scalar("SELECT scheme FROM id_scheme")
+            .fetch_optional(&self.pool)
+            .await?;
+
+        let recorded = match recorded {
+            Some(recorded) => recorded,
+            None => {
+                let indexed = self.indexed_id_scheme().await?.unwrap_or(scheme);
+                info!(scheme = %indexed, "Recording the id scheme");
+
+                sqlx::query(
+                    "INSERT INTO id_scheme (scheme) VALUES ($1) ON CONFLICT (id) DO NOTHING",
+                )
+                .bind(indexed.as_str())
+                .execute(&self.pool)
+                .await?;
+                sqlx::query_scalar("SELECT scheme FROM id_scheme")
+                    .fetch_one(&self.pool)
+                    .await?
+            }
+        };
+
+        if recorded != scheme.as_str() {
+            return Err(StorageError::IdSchemeMismatch {
+                recorded,
+                configured: scheme,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// The scheme the spaces were indexed with, for databases indexed before
+    /// the scheme was recorded.
+    async fn indexed_id_scheme(&self) -> Result<Option<IdScheme>, StorageError> {
+        let spaces: Vec<(Uuid, String, String)> =
+            sqlx::query_as("SELECT id, network, dao_address FROM spaces ORDER BY id LIMIT 100")
+                .fetch_all(&self.pool)
+                .await?;
+
+        Ok(spaces.iter().find_map(|(space_id, network, dao_address)| {
+            IdScheme::ALL
+                .into_iter()
+                .find(|scheme| scheme.space_id(network, dao_address) == *space_id)
+        }))
+    }
+}
@@ -1,17 +1,31 @@
 use async_trait::async_trait;
+use indexer_utils::id::IdScheme;
 use sqlx::Postgres;
+use stream::utils::BlockMetadata;
 use uuid::Uuid;
 
+pub mod id_scheme;
+pub mod notify;
+pub mod pagination;
+pub mod partitions;
 pub mod postgres;
 
+use pagination::{Page, PageRequest};
+
 use thiserror::Error;
 
 use crate::models::{
-    entities::EntityItem,
+    analytics::BlockActivity,
+    entities::{EditedEntities, EntityItem},
+    journal::JournalEntry,
     membership::{EditorItem, MemberItem},
-    properties::PropertyItem,
+    properties::{PropertyItem, PropertyMigrationItem},
+    proposals::{
+        ProposalCancellationItem, ProposalExecutionItem, ProposalItem, ProposalPreviewItem,
+        ProposalSnapshotItem,
+    },
     relations::{SetRelationItem, UnsetRelationItem, UpdateRelationItem},
-    spaces::SpaceItem,
+    spaces::{GovernancePluginsItem, PersonalAdminItem, SpaceItem},
     subspaces::SubspaceItem,
     values::ValueOp,
 };
@@ -20,6 +34,20 @@ use crate::models::{
 pub enum StorageError {
     #[error("Storage error: {0}")]
     Database(#[from] sqlx::Error),
+
+    #[error("Migration error: {0}")]
+    Migrate(#[from] sqlx::migrate::MigrateError),
+
+    #[error("Invalid page cursor: {0}")]
+    InvalidCursor(String),
+
+    #[error(
+        "The database's ids are derived with the {recorded} id scheme, not the configured {configured}; reindex into a new database to switch schemes"
+    )]
+    IdSchemeMismatch {
+        recorded: String,
+        configured: IdScheme,
+    },
 }
 
 #[async_trait]
@@ -41,6 +69,10 @@ pub trait StorageBackend: Send + Sync {
         space_id: &Uuid,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Creates the relations. Relations that already exist only get the
+    /// spaces and versions of their endpoints, their position, verified flag
+    /// and edit overwritten, as a relation's space, type, entity and
+    /// endpoints never change.
     async fn insert_relations(
         &self,
         relations: &Vec<SetRelationItem>,
@@ -62,16 +94,81 @@ pub trait StorageBackend: Send + Sync {
         space_id: &Uuid,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Locks the rows of the given entities and of the entities
+    /// `record_edited_entities` moves for the edit, in id order. Edits of
+    /// different spaces are written concurrently and share entities, so an
+    /// edit locks every entity row it writes at once, before writing any.
+    async fn lock_entities(
+        &self,
+        entity_ids: &[Uuid],
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Moves the `last_edited_at` and `last_edited_block` of the entities an
+    /// edit changed, of their types in the space and of the space to the
+    /// edit's block.
+    async fn record_edited_entities(
+        &self,
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Deletes the values of the given entities in a space and the relations
+    /// from or to them in the space.
+    async fn delete_entities(
+        &self,
+        entity_ids: &[Uuid],
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
     async fn insert_properties(
         &self,
         properties: &Vec<PropertyItem>,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Changes the data type of a property and converts its values if the
+    /// migration is allowed, and records the request either way. Returns
+    /// `None` if the property doesn't exist or already has the data type.
+    async fn migrate_property(
+        &self,
+        property: &PropertyItem,
+        space_id: &Uuid,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<PropertyMigrationItem>, StorageError>;
+    /// Copies the name, description and renderable type of the given
+    /// properties in a space onto the properties. Ids that aren't properties
+    /// are ignored.
+    async fn update_property_metadata(
+        &self,
+        property_ids: &[Uuid],
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Copies the name, description and cover of a space's entity onto the
+    /// space. The space entity is the entity typed as a space in the space.
+    async fn update_space_metadata(
+        &self,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Inserts spaces created in the block, recording the block and
+    /// transaction they were created in and the address that created them.
+    /// Spaces that are already indexed keep their original record.
     async fn insert_spaces(
         &self,
         spaces: &Vec<SpaceItem>,
+        block: &BlockMetadata,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Returns the given space ids that aren't indexed spaces, in order.
+    async fn find_unknown_spaces(
+        &self,
+        space_ids: &[Uuid],
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<Uuid>, StorageError>;
     async fn insert_members(
         &self,
         members: &Vec<MemberItem>,
@@ -82,14 +179,20 @@ pub trait StorageBackend: Send + Sync {
         members: &Vec<MemberItem>,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Adds editors and opens their period in the editor history at the
+    /// block.
     async fn insert_editors(
         &self,
         editors: &Vec<EditorItem>,
+        block: &BlockMetadata,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Removes editors and closes their period in the editor history at the
+    /// block.
     async fn remove_editors(
         &self,
         editors: &Vec<EditorItem>,
+        block: &BlockMetadata,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
     async fn insert_subspaces(
@@ -102,4 +205,133 @@ pub trait StorageBackend: Send + Sync {
         subspaces: &Vec<SubspaceItem>,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError>;
+    /// Makes admin plugins installed in the block the current admins of
+    /// their personal spaces, and records the admins they replace in the
+    /// spaces' admin history. Plugins of unknown or public spaces, and
+    /// plugins installed before a space's current admin, are ignored.
+    async fn change_personal_admins(
+        &self,
+        admins: &[PersonalAdminItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Makes voting and membership plugins installed in the block the
+    /// current plugins of their public spaces, and records the plugins they
+    /// replace in `space_plugins_history`, so the spaces' earlier proposals
+    /// still resolve to them. Plugins of unknown or personal spaces, and
+    /// plugins installed before a space's current ones, are ignored.
+    async fn change_governance_plugins(
+        &self,
+        plugins: &[GovernancePluginsItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Records that an edit with the given content hash was applied to a
+    /// space, along with the edit's id that its values and relations are
+    /// traced back to. Returns false if the same content was already applied
+    /// to the space, in which case the edit should be skipped.
+    async fn record_edit(
+        &self,
+        space_id: &Uuid,
+        content_hash: &str,
+        cid: &str,
+        edit_id: Option<Uuid>,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, StorageError>;
+    /// Adds the activity of a block, and the edits it recorded, to the daily
+    /// and weekly rollups of each space. Must run after the block's edits
+    /// and entities are written. Proposals and editors are only counted once
+    /// per period, however often they're recorded.
+    async fn record_activity(
+        &self,
+        activity: &BlockActivity,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Appends the ops of the edits applied in the block to the ops journal.
+    /// Must run after the block's edits are recorded, as ops of edits that
+    /// weren't recorded in the block, like edits that were skipped or
+    /// rejected, aren't journaled. Ops already in the journal are skipped.
+    async fn append_ops_journal(
+        &self,
+        entries: &[JournalEntry],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Writes the previews of proposals created in the block, replacing the
+    /// preview of a proposal that's already written.
+    async fn insert_proposal_previews(
+        &self,
+        previews: &[ProposalPreviewItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Snapshots the editors and members of the spaces of proposals created
+    /// in the block. A proposal that already has a snapshot keeps it, so the
+    /// block can be processed again once its space's membership changed.
+    async fn snapshot_proposal_electorates(
+        &self,
+        proposals: &[ProposalSnapshotItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Writes the proposals created in the block with the `created` status.
+    /// A proposal that's already written keeps its status.
+    async fn insert_proposals(
+        &self,
+        proposals: &[ProposalItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Marks proposals executed in the block as `executed`, with the block's
+    /// number and timestamp and the executing transaction's hash. Only
+    /// proposals that are still `created` change.
+    async fn execute_proposals(
+        &self,
+        proposals: &[ProposalExecutionItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Marks proposals withdrawn or vetoed in the block as `canceled`, with
+    /// the address that canceled them. Only proposals that are still
+    /// `created` change, and a `proposal_updated` notification is sent for
+    /// each of them.
+    async fn cancel_proposals(
+        &self,
+        proposals: &[ProposalCancellationItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError>;
+    /// Returns whether the block is in the indexer's ledger of processed
+    /// blocks.
+    async fn is_block_processed(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<bool, StorageError>;
+    /// Adds the block to the indexer's ledger of processed blocks. Must run
+    /// once every write of the block is committed.
+    async fn record_processed_block(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<(), StorageError>;
+    /// Returns a page of the relations of a type stored in a space, in id
+    /// order.
+    async fn get_relations_by_type(
+        &self,
+        space_id: &Uuid,
+        type_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError>;
+    /// Returns a page of the relations from an entity as it exists in a
+    /// space, in id order. A relation without a from_space starts in its own
+    /// space.
+    async fn get_outgoing_relations(
+        &self,
+        entity_id: &Uuid,
+        space_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError>;
 }
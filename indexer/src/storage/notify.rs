@@ -0,0 +1,180 @@
+//! Notifications of indexed changes, sent with Postgres' `NOTIFY` so services
+//! sharing the database, like the API, can `LISTEN` for them instead of
+//! polling.
+//!
+//! Notifications are queued in the transaction that makes the change, and
+//! Postgres only delivers them once it commits, so listeners never hear of a
+//! change that was rolled back. Identical notifications of a transaction are
+//! delivered once. Edits and proposals are only notified the first time
+//! they're recorded, not when a block is processed again. Payloads are
+//! compact JSON objects:
+//!
+//! - `space_updated`: `{"space_id":"…","change":"edit"}`, where `change` is
+//!   one of [`SpaceChange`].
+//! - `proposal_updated`: `{"space_id":"…","proposal_id":"…","status":"created"}`,
+//!   where `status` is one of [`ProposalStatus`].
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::StorageError;
+
+pub const SPACE_UPDATED_CHANNEL: &str = "space_updated";
+pub const PROPOSAL_UPDATED_CHANNEL: &str = "proposal_updated";
+
+/// What changed in a space.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SpaceChange {
+    Created,
+    Edit,
+    Members,
+    Editors,
+    Subspaces,
+    Admin,
+    Governance,
+}
+
+impl SpaceChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpaceChange::Created => "created",
+            SpaceChange::Edit => "edit",
+            SpaceChange::Members => "members",
+            SpaceChange::Editors => "editors",
+            SpaceChange::Subspaces => "subspaces",
+            SpaceChange::Admin => "admin",
+            SpaceChange::Governance => "governance",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Created,
+    Executed,
+    Canceled,
+}
+
+impl ProposalStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProposalStatus::Created => "created",
+            ProposalStatus::Executed => "executed",
+            ProposalStatus::Canceled => "canceled",
+        }
+    }
+}
+
+/// The payload of a `space_updated` notification.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpaceUpdated {
+    pub space_id: Uuid,
+    pub change: SpaceChange,
+}
+
+/// The payload of a `proposal_updated` notification.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProposalUpdated {
+    pub space_id: Uuid,
+    pub proposal_id: String,
+    pub status: ProposalStatus,
+}
+
+/// Queues a `space_updated` notification for each of the spaces.
+pub(crate) async fn notify_spaces_updated(
+    space_ids: &[Uuid],
+    change: SpaceChange,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), StorageError> {
+    if space_ids.is_empty() {
+        return Ok(());
+    }
+
+    sqlx::query(
+        r#"
+            SELECT pg_notify($1, json_build_object('space_id', space_id, 'change', $2::text)::text)
+            FROM (SELECT DISTINCT UNNEST($3::uuid[]) AS space_id) AS t
+        "#,
+    )
+    .bind(SPACE_UPDATED_CHANNEL)
+    .bind(change.as_str())
+    .bind(space_ids)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+/// Queues a `proposal_updated` notification for each of the proposals,
+/// given as pairs of space and proposal ids.
+pub(crate) async fn notify_proposals_updated(
+    proposals: &[(Uuid, String)],
+    status: ProposalStatus,
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), StorageError> {
+    if proposals.is_empty() {
+        return Ok(());
+    }
+
+    let (space_ids, proposal_ids): (Vec<Uuid>, Vec<&str>) = proposals
+        .iter()
+        .map(|(space_id, proposal_id)| (*space_id, proposal_id.as_str()))
+        .unzip();
+
+    sqlx::query(
+        r#"
+            SELECT pg_notify(
+                $1,
+                json_build_object(
+                    'space_id', space_id, 'proposal_id', proposal_id, 'status', $2::text
+                )::text
+            )
+            FROM UNNEST($3::uuid[], $4::text[]) AS t(space_id, proposal_id)
+        "#,
+    )
+    .bind(PROPOSAL_UPDATED_CHANNEL)
+    .bind(status.as_str())
+    .bind(&space_ids)
+    .bind(&proposal_ids)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_payloads_round_trip() {
+        let space_id = Uuid::parse_str("5871e8f7-b719-420f-b7b5-a3e5b4b6e0f1").unwrap();
+
+        assert_eq!(
+            serde_json::from_str::<SpaceUpdated>(&format!(
+                r#"{{"space_id" : "{}", "change" : "{}"}}"#,
+                space_id,
+                SpaceChange::Subspaces.as_str()
+            ))
+            .unwrap(),
+            SpaceUpdated {
+                space_id,
+                change: SpaceChange::Subspaces,
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&ProposalUpdated {
+                space_id,
+                proposal_id: "7".to_string(),
+                status: ProposalStatus::Executed,
+            })
+            .unwrap(),
+            format!(
+                r#"{{"space_id":"{}","proposal_id":"7","status":"executed"}}"#,
+                space_id
+            )
+        );
+    }
+}
@@ -0,0 +1,176 @@
+//! Keyset pagination for the bulk read methods of [`PostgresStorage`].
+//!
+//! Rows are read in id order and a page's cursor is the id of its last row,
+//! so pages stay stable while rows are written and reading a page doesn't
+//! get slower the further into a table it is.
+//!
+//! [`PostgresStorage`]: super::postgres::PostgresStorage
+
+use uuid::Uuid;
+
+use super::StorageError;
+
+/// The most rows a page holds, whatever limit is requested.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+
+/// The position and size of a page to read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PageRequest {
+    /// The cursor of the previous page, or `None` for the first page.
+    pub after: Option<String>,
+    pub limit: usize,
+}
+
+impl Default for PageRequest {
+    fn default() -> Self {
+        PageRequest {
+            after: None,
+            limit: DEFAULT_PAGE_SIZE,
+        }
+    }
+}
+
+impl PageRequest {
+    pub fn first(limit: usize) -> Self {
+        PageRequest { after: None, limit }
+    }
+
+    /// The request for the page after `page`, or `None` if it was the last.
+    pub fn next<T>(&self, page: &Page<T>) -> Option<Self> {
+        page.next_cursor.as_ref().map(|cursor| PageRequest {
+            after: Some(cursor.clone()),
+            limit: self.limit,
+        })
+    }
+
+    /// The limit clamped to between one and [`MAX_PAGE_SIZE`].
+    pub fn page_size(&self) -> usize {
+        self.limit.clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// The number of rows to fetch, one more than the page size so it's
+    /// known whether there's a next page.
+    pub(crate) fn fetch_limit(&self) -> i64 {
+        self.page_size() as i64 + 1
+    }
+
+    /// Parses the cursor of a table keyed by uuid.
+    pub(crate) fn after_uuid(&self) -> Result<Option<Uuid>, StorageError> {
+        self.after
+            .as_deref()
+            .map(|cursor| {
+                Uuid::parse_str(cursor).map_err(|_| StorageError::InvalidCursor(cursor.to_string()))
+            })
+            .transpose()
+    }
+
+    /// Parses the cursor of a table keyed by a sequence.
+    pub(crate) fn after_sequence(&self) -> Result<Option<i64>, StorageError> {
+        self.after
+            .as_deref()
+            .map(|cursor| {
+                cursor
+                    .parse()
+                    .map_err(|_| StorageError::InvalidCursor(cursor.to_string()))
+            })
+            .transpose()
+    }
+}
+
+/// A page of rows in id order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The cursor to read the next page from, or `None` if this is the last
+    /// page.
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    /// Builds a page from rows fetched with [`PageRequest::fetch_limit`],
+    /// dropping the extra row and setting the cursor if it was fetched.
+    pub(crate) fn from_rows(
+        mut items: Vec<T>,
+        request: &PageRequest,
+        cursor: impl Fn(&T) -> String,
+    ) -> Self {
+        let has_more = items.len() > request.page_size();
+        items.truncate(request.page_size());
+
+        let next_cursor = match items.last() {
+            Some(last) if has_more => Some(cursor(last)),
+            _ => None,
+        };
+
+        Page { items, next_cursor }
+    }
+}
+
+/// Narrows the relations listed in a space. Unset fields match any relation.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RelationFilter {
+    pub type_id: Option<Uuid>,
+    pub from_entity_id: Option<Uuid>,
+    pub to_entity_id: Option<Uuid>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_from_rows() {
+        let request = PageRequest::first(2);
+
+        let page = Page::from_rows(vec![1, 2, 3], &request, |row| row.to_string());
+        assert_eq!(page.items, vec![1, 2]);
+        assert_eq!(page.next_cursor, Some("2".to_string()));
+        assert_eq!(
+            request.next(&page),
+            Some(PageRequest {
+                after: Some("2".to_string()),
+                limit: 2
+            })
+        );
+
+        let page = Page::from_rows(vec![1, 2], &request, |row| row.to_string());
+        assert_eq!(page.next_cursor, None);
+        assert_eq!(request.next(&page), None);
+    }
+
+    #[test]
+    fn test_page_size_is_clamped() {
+        assert_eq!(PageRequest::first(0).page_size(), 1);
+        assert_eq!(PageRequest::first(5000).page_size(), MAX_PAGE_SIZE);
+        assert_eq!(PageRequest::default().fetch_limit(), 101);
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        let request = PageRequest {
+            after: Some("not-a-uuid".to_string()),
+            limit: 10,
+        };
+
+        assert!(matches!(
+            request.after_uuid(),
+            Err(StorageError::InvalidCursor(cursor)) if cursor == "not-a-uuid"
+        ));
+        assert_eq!(PageRequest::default().after_uuid().unwrap(), None);
+        assert!(matches!(
+            request.after_sequence(),
+            Err(StorageError::InvalidCursor(_))
+        ));
+        assert_eq!(
+            PageRequest {
+                after: Some("42".to_string()),
+                limit: 10
+            }
+            .after_sequence()
+            .unwrap(),
+            Some(42)
+        );
+    }
+}
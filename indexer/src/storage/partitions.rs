@@ -0,0 +1,170 @@
+//! Hash partitions of the values table.
+//!
+//! Values are partitioned by a hash of their space id, so each partition
+//! holds the values of a share of the spaces and queries that filter by
+//! space id only scan one partition. The table is created with 16
+//! partitions; [`PostgresStorage::partition_values`] changes how many it
+//! has.
+
+use tracing::info;
+
+use super::{postgres::PostgresStorage, StorageError};
+
+/// A partition of the values table, which holds the values of the spaces
+/// whose hash modulo `modulus` is `remainder`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValuePartition {
+    pub name: String,
+    pub modulus: u32,
+    pub remainder: u32,
+}
+
+impl ValuePartition {
+    /// Reads a partition from its bound, like
+    /// `FOR VALUES WITH (modulus 16, remainder 3)`.
+    fn from_bound(name: String, bound: &str) -> Option<Self> {
+        let bound = bound.strip_prefix("FOR VALUES WITH (")?.strip_suffix(')')?;
+        let (modulus, remainder) = bound.split_once(", ")?;
+
+        Some(ValuePartition {
+            name,
+            modulus: modulus.strip_prefix("modulus ")?.parse().ok()?,
+            remainder: remainder.strip_prefix("remainder ")?.parse().ok()?,
+        })
+    }
+
+    fn name_for(modulus: u32, remainder: u32) -> String {
+        format!("values_{}_{}", modulus, remainder)
+    }
+}
+
+impl PostgresStorage {
+    /// The partitions of the values table, by remainder.
+    pub async fn value_partitions(&self) -> Result<Vec<ValuePartition>, StorageError> {
+        let rows: Vec<(String, String)> = sqlx::query_as(
+            r#"
+                SELECT c.relname::text, pg_get_expr(c.relpartbound, c.oid)
+                FROM pg_inherits i
+                JOIN pg_class c ON c.oid = i.inhrelid
+                WHERE i.inhparent = 'values'::regclass
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut partitions: Vec<ValuePartition> = rows
+            .into_iter()
+            .filter_map(|(name, bound)| ValuePartition::from_bound(name, &bound))
+            .collect();
+        partitions.sort_by_key(|partition| partition.remainder);
+
+        Ok(partitions)
+    }
+
+    /// Repartitions the values table into `count` partitions, unless it
+    /// already has them, and returns whether it did. The table is locked
+    /// while its rows are moved, so this is meant to run on startup before
+    /// any block is indexed.
+    pub async fn partition_values(&self, count: u32) -> Result<bool, StorageError> {
+        let partitions = self.value_partitions().await?;
+        if partitions.len() == count as usize
+            && partitions
+                .iter()
+                .all(|partition| partition.modulus == count)
+        {
+            return Ok(false);
+        }
+
+        info!(
+            from = partitions.len(),
+            to = count,
+            "Repartitioning the values table"
+        );
+
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("LOCK TABLE values IN ACCESS EXCLUSIVE MODE")
+            .execute(&mut *tx)
+            .await?;
+
+        // The old partitions are detached and renamed first so the new ones
+        // can cover every remainder, and their rows are moved into the new
+        // ones.
+        let detached: Vec<String> = partitions
+            .iter()
+            .map(|partition| format!("{}_detached", partition.name))
+            .collect();
+        for (partition, detached) in partitions.iter().zip(&detached) {
+            sqlx::query(&format!(
+                r#"ALTER TABLE values DETACH PARTITION "{}""#,
+                partition.name
+            ))
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(
+                r#"ALTER TABLE "{}" RENAME TO "{}""#,
+                partition.name, detached
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+        for remainder in 0..count {
+            sqlx::query(&format!(
+                r#"CREATE TABLE "{}" PARTITION OF values FOR VALUES WITH (MODULUS {}, REMAINDER {})"#,
+                ValuePartition::name_for(count, remainder),
+                count,
+                remainder
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+        for detached in &detached {
+            sqlx::query(&format!(
+                r#"
+                    INSERT INTO values (
+                        id, property_id, entity_id, space_id, string, language, unit,
+                        options, edit_id, boolean, number, point, time
+                    )
+                    SELECT
+                        id, property_id, entity_id, space_id, string, language, unit,
+                        options, edit_id, boolean, number, point, time
+                    FROM "{}"
+                "#,
+                detached
+            ))
+            .execute(&mut *tx)
+            .await?;
+            sqlx::query(&format!(r#"DROP TABLE "{}""#, detached))
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reads_partition_bounds() {
+        assert_eq!(
+            ValuePartition::from_bound(
+                "values_16_3".to_string(),
+                "FOR VALUES WITH (modulus 16, remainder 3)"
+            ),
+            Some(ValuePartition {
+                name: "values_16_3".to_string(),
+                modulus: 16,
+                remainder: 3,
+            })
+        );
+        assert_eq!(
+            ValuePartition::from_bound("values_default".to_string(), "DEFAULT"),
+            None
+        );
+        assert_eq!(ValuePartition::name_for(4, 0), "values_4_0");
+    }
+}
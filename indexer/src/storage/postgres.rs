@@ -1,23 +1,44 @@
+use std::collections::HashMap;
+
 use async_trait::async_trait;
 
-use sqlx::{postgres::PgPoolOptions, Postgres, QueryBuilder, Row};
-use tracing::error;
+use indexer_utils::config::DatabaseConfig;
+use indexer_utils::system_ids::{
+    COVER_PROPERTY, DESCRIPTION_PROPERTY, IMAGE_URL_PROPERTY, NAME_PROPERTY, PROPERTIES_PROPERTY,
+    RENDERABLE_TYPE_PROPERTY, SCHEMA_TYPE, SPACE_TYPE, TYPES_PROPERTY,
+};
+use serde::Deserialize;
+use sqlx::{migrate::Migrator, postgres::PgRow, types::Json, Postgres, QueryBuilder, Row};
+use stream::utils::BlockMetadata;
 use uuid::Uuid;
 
 use crate::models::{
-    entities::EntityItem,
+    analytics::{ActivityRollup, BlockActivity, RollupPeriod},
+    entities::{EditedEntities, EntityItem, HydratedEntity},
+    journal::{JournalEntry, JournaledOp},
     membership::{EditorItem, MemberItem},
+    neighborhood::{Neighborhood, NeighborhoodNode, MAX_NEIGHBORHOOD_DEPTH, NEIGHBORHOOD_FAN_OUT},
     properties::{
-        DataType, PropertyItem, DATA_TYPE_BOOLEAN, DATA_TYPE_NUMBER, DATA_TYPE_POINT,
+        DataType, PropertiesModel, PropertyItem, PropertyMetadata, PropertyMigrationError,
+        PropertyMigrationItem, DATA_TYPE_BOOLEAN, DATA_TYPE_NUMBER, DATA_TYPE_POINT,
         DATA_TYPE_RELATION, DATA_TYPE_STRING, DATA_TYPE_TIME,
     },
+    proposals::{
+        ProposalCancellationItem, ProposalExecutionItem, ProposalItem, ProposalPreviewItem,
+        ProposalSnapshotItem,
+    },
     relations::{SetRelationItem, UnsetRelationItem, UpdateRelationItem},
-    spaces::{SpaceItem, SpaceType},
+    schema::{SchemaProperty, TypeSchema},
+    spaces::{GovernancePluginsItem, PersonalAdminItem, SpaceItem, SpaceMetadata, SpaceType},
     subspaces::SubspaceItem,
     values::{ValueChangeType, ValueOp},
 };
 
-use super::{StorageBackend, StorageError};
+use super::{
+    notify::{notify_proposals_updated, notify_spaces_updated, ProposalStatus, SpaceChange},
+    pagination::{Page, PageRequest, RelationFilter},
+    StorageBackend, StorageError,
+};
 
 #[derive(sqlx::FromRow)]
 struct EntityRow {
@@ -28,7 +49,7 @@ struct EntityRow {
     updated_at_block: String,
 }
 
-#[derive(sqlx::FromRow)]
+#[derive(sqlx::FromRow, Deserialize)]
 struct RelationRow {
     id: Uuid,
     type_id: Uuid,
@@ -42,22 +63,113 @@ struct RelationRow {
     to_version_id: Option<Uuid>,
     verified: Option<bool>,
     position: Option<String>,
+    edit_id: Option<Uuid>,
+}
+
+impl From<RelationRow> for SetRelationItem {
+    fn from(row: RelationRow) -> Self {
+        SetRelationItem {
+            id: row.id,
+            type_id: row.type_id,
+            entity_id: row.entity_id,
+            space_id: row.space_id,
+            from_id: row.from_entity_id,
+            from_space_id: row.from_space_id.map(|id| id.to_string()),
+            from_version_id: row.from_version_id.map(|id| id.to_string()),
+            to_id: row.to_entity_id,
+            to_space_id: row.to_space_id.map(|id| id.to_string()),
+            to_version_id: row.to_version_id.map(|id| id.to_string()),
+            verified: row.verified,
+            position: row.position,
+            edit_id: row.edit_id,
+        }
+    }
+}
+
+/// A value as it's aggregated into JSON when hydrating entities.
+#[derive(Deserialize)]
+struct ValueRow {
+    id: Uuid,
+    property_id: Uuid,
+    entity_id: Uuid,
+    space_id: Uuid,
+    language: Option<String>,
+    unit: Option<String>,
+    options: Option<serde_json::Value>,
+    edit_id: Option<Uuid>,
+    string: Option<String>,
+    number: Option<f64>,
+    boolean: Option<bool>,
+    time: Option<String>,
+    point: Option<String>,
+}
+
+impl From<ValueRow> for ValueOp {
+    fn from(row: ValueRow) -> Self {
+        ValueOp {
+            id: row.id,
+            property_id: row.property_id,
+            entity_id: row.entity_id,
+            space_id: row.space_id,
+            language: row.language,
+            unit: row.unit,
+            options: row.options,
+            edit_id: row.edit_id,
+            string: row.string,
+            number: row.number,
+            boolean: row.boolean,
+            time: row.time,
+            point: row.point,
+            change_type: ValueChangeType::SET,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct HydratedEntityRow {
+    id: Uuid,
+    created_at: String,
+    created_at_block: String,
+    updated_at: String,
+    updated_at_block: String,
+    values: Json<Vec<ValueRow>>,
+    relations: Json<Vec<RelationRow>>,
 }
 
+/// The schema migrations generated by drizzle in the API project, embedded
+/// so the indexers can set up a fresh database themselves.
+///
+/// Databases migrated with `bun run db:migrate` don't record these in
+/// `_sqlx_migrations`, so only run them against databases that were set up
+/// this way from the start.
+pub static MIGRATOR: Migrator = sqlx::migrate!("../api/drizzle");
+
 pub struct PostgresStorage {
     pub pool: sqlx::Pool<Postgres>,
 }
 
 impl PostgresStorage {
     pub async fn new(database_url: &String) -> Result<Self, StorageError> {
-        let pool = PgPoolOptions::new()
-            .max_connections(20)
-            .connect(database_url.as_str())
-            .await?;
+        Self::connect(database_url, &DatabaseConfig::default()).await
+    }
+
+    /// Connects with the pool size and timeouts of `database`.
+    pub async fn connect(
+        database_url: &str,
+        database: &DatabaseConfig,
+    ) -> Result<Self, StorageError> {
+        let pool = database.connect(database_url).await?;
 
         return Ok(PostgresStorage { pool });
     }
 
+    /// Applies the embedded migrations that haven't been applied yet.
+    pub async fn migrate(&self) -> Result<(), StorageError> {
+        MIGRATOR.run(&self.pool).await?;
+
+        Ok(())
+    }
+
     pub async fn get_entity(&self, entity_id: &String) -> Result<EntityItem, StorageError> {
         let entity_uuid = Uuid::parse_str(entity_id)
             .map_err(|e| sqlx::Error::Decode(format!("Invalid UUID format: {}", e).into()))?;
@@ -84,7 +196,7 @@ impl PostgresStorage {
         let row = sqlx::query(
             r#"SELECT
                 id, property_id, entity_id, space_id,
-                language, unit, string,
+                language, unit, options, edit_id, string,
                 number::float8 as number, boolean, time, point
                 FROM values WHERE id = $1"#,
         )
@@ -92,40 +204,7 @@ impl PostgresStorage {
         .fetch_one(&self.pool)
         .await?;
 
-        let id = Uuid::parse_str(row.try_get::<&str, _>("id")?).map_err(|e| {
-            sqlx::Error::Decode(format!("Invalid UUID format for id: {}", e).into())
-        })?;
-
-        let property_id: Uuid = row.try_get("property_id")?;
-        let entity_id: Uuid = row.try_get("entity_id")?;
-
-        let space_id: Uuid = row.try_get("space_id")?;
-
-        let language: Option<String> = row.try_get("language")?;
-        let unit: Option<String> = row.try_get("unit")?;
-        let text: Option<String> = row.try_get("string")?;
-
-        let number: Option<f64> = row.try_get("number")?;
-
-        let boolean: Option<bool> = row.try_get("boolean")?;
-        let time: Option<String> = row.try_get("time")?;
-        let point: Option<String> = row.try_get("point")?;
-
-        Ok(ValueOp {
-            id,
-            property_id,
-            entity_id,
-            space_id,
-
-            language,
-            unit,
-            string: text,
-            number,
-            boolean,
-            time,
-            point,
-            change_type: ValueChangeType::SET,
-        })
+        value_from_row(&row)
     }
 
     pub async fn get_relation(
@@ -135,27 +214,308 @@ impl PostgresStorage {
         let relation_uuid = Uuid::parse_str(relation_id)
             .map_err(|e| sqlx::Error::Decode(format!("Invalid UUID format: {}", e).into()))?;
 
-        let query = sqlx::query_as!(
-            RelationRow,
-            "SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position FROM relations WHERE id = $1",
-            relation_uuid
+        let query = sqlx::query_as::<_, RelationRow>(
+            "SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position, edit_id FROM relations WHERE id = $1",
         )
+        .bind(relation_uuid)
         .fetch_one(&self.pool)
         .await?;
 
-        Ok(SetRelationItem {
-            id: query.id,
-            type_id: query.type_id,
-            entity_id: query.entity_id,
-            space_id: query.space_id,
-            from_id: query.from_entity_id,
-            from_space_id: query.from_space_id.map(|id| id.to_string()),
-            from_version_id: query.from_version_id.map(|id| id.to_string()),
-            to_id: query.to_entity_id,
-            to_space_id: query.to_space_id.map(|id| id.to_string()),
-            to_version_id: query.to_version_id.map(|id| id.to_string()),
-            verified: query.verified,
-            position: query.position,
+        Ok(query.into())
+    }
+
+    /// Returns the relations to an entity as it exists in a space, including
+    /// relations stored in other spaces that point into this one.
+    pub async fn get_incoming_relations(
+        &self,
+        entity_id: &Uuid,
+        space_id: &Uuid,
+    ) -> Result<Vec<SetRelationItem>, StorageError> {
+        let rows = sqlx::query_as::<_, RelationRow>(
+            r#"
+                SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position, edit_id
+                FROM relations
+                WHERE to_entity_id = $1 AND COALESCE(to_space_id, space_id) = $2
+                ORDER BY position NULLS LAST, id
+            "#,
+        )
+        .bind(entity_id)
+        .bind(space_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(SetRelationItem::from).collect())
+    }
+
+    /// Returns the given entities with their values and outgoing relations
+    /// in a space, in one query. Entities are returned in the order of
+    /// `entity_ids`, and ids without an entity are skipped.
+    pub async fn get_entities_with_values(
+        &self,
+        entity_ids: &[Uuid],
+        space_id: &Uuid,
+    ) -> Result<Vec<HydratedEntity>, StorageError> {
+        let rows = sqlx::query_as::<_, HydratedEntityRow>(
+            r#"
+                SELECT
+                    e.id, e.created_at, e.created_at_block, e.updated_at, e.updated_at_block,
+                    (
+                        SELECT COALESCE(json_agg(v ORDER BY v.property_id, v.id), '[]')
+                        FROM (
+                            SELECT id, property_id, entity_id, space_id, language, unit, options, edit_id, string,
+                                number::float8 AS number, boolean, time, point
+                            FROM values
+                            WHERE entity_id = e.id AND space_id = $2
+                        ) v
+                    ) AS values,
+                    (
+                        SELECT COALESCE(json_agg(r ORDER BY r.position NULLS LAST, r.id), '[]')
+                        FROM (
+                            SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position, edit_id
+                            FROM relations
+                            WHERE from_entity_id = e.id AND COALESCE(from_space_id, space_id) = $2
+                        ) r
+                    ) AS relations
+                FROM UNNEST($1::uuid[]) WITH ORDINALITY AS ids(id, ordinality)
+                JOIN entities e ON e.id = ids.id
+                ORDER BY ids.ordinality
+            "#,
+        )
+        .bind(entity_ids)
+        .bind(space_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| HydratedEntity {
+                entity: EntityItem {
+                    id: row.id,
+                    created_at: row.created_at,
+                    created_at_block: row.created_at_block,
+                    updated_at: row.updated_at,
+                    updated_at_block: row.updated_at_block,
+                },
+                values: row.values.0.into_iter().map(ValueOp::from).collect(),
+                relations: row
+                    .relations
+                    .0
+                    .into_iter()
+                    .map(SetRelationItem::from)
+                    .collect(),
+            })
+            .collect())
+    }
+
+    /// Returns a page of entities in id order.
+    pub async fn list_entities(
+        &self,
+        page: &PageRequest,
+    ) -> Result<Page<EntityItem>, StorageError> {
+        let rows = sqlx::query_as::<_, EntityRow>(
+            r#"
+                SELECT id, created_at, created_at_block, updated_at, updated_at_block
+                FROM entities
+                WHERE $1::uuid IS NULL OR id > $1
+                ORDER BY id
+                LIMIT $2
+            "#,
+        )
+        .bind(page.after_uuid()?)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let entities = rows
+            .into_iter()
+            .map(|row| EntityItem {
+                id: row.id,
+                created_at: row.created_at,
+                created_at_block: row.created_at_block,
+                updated_at: row.updated_at,
+                updated_at_block: row.updated_at_block,
+            })
+            .collect();
+
+        Ok(Page::from_rows(entities, page, |entity| {
+            entity.id.to_string()
+        }))
+    }
+
+    /// Returns a page of the values of an entity across spaces, in id order.
+    pub async fn list_values_for_entity(
+        &self,
+        entity_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<ValueOp>, StorageError> {
+        let rows = sqlx::query(
+            r#"
+                SELECT
+                    id, property_id, entity_id, space_id,
+                    language, unit, options, edit_id, string,
+                    number::float8 as number, boolean, time, point
+                FROM values
+                WHERE entity_id = $1 AND ($2::text IS NULL OR id > $2)
+                ORDER BY id
+                LIMIT $3
+            "#,
+        )
+        .bind(entity_id)
+        .bind(&page.after)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let values = rows
+            .iter()
+            .map(value_from_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Page::from_rows(values, page, |value| value.id.to_string()))
+    }
+
+    /// Returns a page of the ops journal in the order the ops were applied,
+    /// optionally only the ops of one space. A page's cursor is the sequence
+    /// of its last op, so readers can tail the journal by persisting it.
+    pub async fn list_ops_journal(
+        &self,
+        space_id: Option<&Uuid>,
+        page: &PageRequest,
+    ) -> Result<Page<JournaledOp>, StorageError> {
+        let ops = sqlx::query_as::<_, JournaledOp>(
+            r#"
+                SELECT sequence, block_number, space_id, edit_id, op_index, op_kind, target_id
+                FROM ops_journal
+                WHERE ($1::uuid IS NULL OR space_id = $1)
+                    AND ($2::int8 IS NULL OR sequence > $2)
+                ORDER BY sequence
+                LIMIT $3
+            "#,
+        )
+        .bind(space_id)
+        .bind(page.after_sequence()?)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Page::from_rows(ops, page, |op| op.sequence.to_string()))
+    }
+
+    /// Returns a page of the relations stored in a space that match a filter,
+    /// in id order.
+    pub async fn list_relations(
+        &self,
+        space_id: &Uuid,
+        filter: &RelationFilter,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        let rows = sqlx::query_as::<_, RelationRow>(
+            r#"
+                SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position, edit_id
+                FROM relations
+                WHERE space_id = $1
+                    AND ($2::uuid IS NULL OR type_id = $2)
+                    AND ($3::uuid IS NULL OR from_entity_id = $3)
+                    AND ($4::uuid IS NULL OR to_entity_id = $4)
+                    AND ($5::uuid IS NULL OR id > $5)
+                ORDER BY id
+                LIMIT $6
+            "#,
+        )
+        .bind(space_id)
+        .bind(filter.type_id)
+        .bind(filter.from_entity_id)
+        .bind(filter.to_entity_id)
+        .bind(page.after_uuid()?)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let relations = rows.into_iter().map(SetRelationItem::from).collect();
+
+        Ok(Page::from_rows(relations, page, |relation| {
+            relation.id.to_string()
+        }))
+    }
+
+    /// Returns the entities within `depth` hops of an entity over the
+    /// relations stored in a space, following relations in either direction,
+    /// and the relations between them. Only relations of the given types are
+    /// followed if a filter is passed.
+    ///
+    /// The depth is capped at [`MAX_NEIGHBORHOOD_DEPTH`], and at most
+    /// [`NEIGHBORHOOD_FAN_OUT`] relations are followed from each entity, by
+    /// position.
+    pub async fn get_neighborhood(
+        &self,
+        entity_id: &Uuid,
+        space_id: &Uuid,
+        depth: u32,
+        relation_type_filter: Option<&[Uuid]>,
+    ) -> Result<Neighborhood, StorageError> {
+        let depth = depth.min(MAX_NEIGHBORHOOD_DEPTH) as i32;
+
+        // Each path is walked separately and stops before revisiting one of
+        // its entities, so cycles end the walk rather than looping.
+        let nodes = sqlx::query_as::<_, NeighborhoodNode>(
+            r#"
+                WITH RECURSIVE walk (entity_id, depth, path) AS (
+                    SELECT $1::uuid, 0, ARRAY[$1::uuid]
+                    UNION ALL
+                    SELECT next.entity_id, walk.depth + 1, walk.path || next.entity_id
+                    FROM walk
+                    CROSS JOIN LATERAL (
+                        SELECT CASE
+                            WHEN r.from_entity_id = walk.entity_id THEN r.to_entity_id
+                            ELSE r.from_entity_id
+                        END AS entity_id
+                        FROM relations r
+                        WHERE r.space_id = $2
+                            AND (r.from_entity_id = walk.entity_id OR r.to_entity_id = walk.entity_id)
+                            AND ($4::uuid[] IS NULL OR r.type_id = ANY($4))
+                        ORDER BY r.position NULLS LAST, r.id
+                        LIMIT $5
+                    ) next
+                    WHERE walk.depth < $3 AND NOT next.entity_id = ANY(walk.path)
+                )
+                SELECT entity_id, MIN(depth) AS depth
+                FROM walk
+                GROUP BY entity_id
+                ORDER BY depth, entity_id
+            "#,
+        )
+        .bind(entity_id)
+        .bind(space_id)
+        .bind(depth)
+        .bind(relation_type_filter)
+        .bind(NEIGHBORHOOD_FAN_OUT)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let entity_ids: Vec<Uuid> = nodes.iter().map(|node| node.entity_id).collect();
+
+        let relations = sqlx::query_as::<_, RelationRow>(
+            r#"
+                SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position, edit_id
+                FROM relations
+                WHERE space_id = $1
+                    AND from_entity_id = ANY($2)
+                    AND to_entity_id = ANY($2)
+                    AND ($3::uuid[] IS NULL OR type_id = ANY($3))
+                ORDER BY position NULLS LAST, id
+            "#,
+        )
+        .bind(space_id)
+        .bind(&entity_ids)
+        .bind(relation_type_filter)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Neighborhood {
+            entity_id: *entity_id,
+            space_id: *space_id,
+            nodes,
+            relations: relations.into_iter().map(SetRelationItem::from).collect(),
         })
     }
 
@@ -183,6 +543,188 @@ impl PostgresStorage {
         })
     }
 
+    pub async fn get_property_metadata(
+        &self,
+        property_id: &Uuid,
+    ) -> Result<PropertyMetadata, StorageError> {
+        let row =
+            sqlx::query("SELECT name, description, renderable_type FROM properties WHERE id = $1")
+                .bind(property_id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(PropertyMetadata {
+            name: row.get("name"),
+            description: row.get("description"),
+            renderable_type: row.get("renderable_type"),
+        })
+    }
+
+    /// Returns the activity rollups of a space for a period, oldest first.
+    pub async fn get_space_activity(
+        &self,
+        space_id: &Uuid,
+        period: RollupPeriod,
+    ) -> Result<Vec<ActivityRollup>, StorageError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT period_start::text, edits, active_editors, proposals_created,
+                proposals_executed, new_entities
+            FROM space_activity_rollups
+            WHERE space_id = $1 AND period = $2
+            ORDER BY period_start
+            "#,
+        )
+        .bind(space_id)
+        .bind(period.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ActivityRollup {
+                period_start: row.get("period_start"),
+                edits: row.get("edits"),
+                active_editors: row.get("active_editors"),
+                proposals_created: row.get("proposals_created"),
+                proposals_executed: row.get("proposals_executed"),
+                new_entities: row.get("new_entities"),
+            })
+            .collect())
+    }
+
+    pub async fn get_space_metadata(&self, space_id: &Uuid) -> Result<SpaceMetadata, StorageError> {
+        let row =
+            sqlx::query("SELECT entity_id, name, description, cover FROM spaces WHERE id = $1")
+                .bind(space_id)
+                .fetch_one(&self.pool)
+                .await?;
+
+        Ok(SpaceMetadata {
+            entity_id: row.get("entity_id"),
+            name: row.get("name"),
+            description: row.get("description"),
+            cover: row.get("cover"),
+        })
+    }
+
+    /// Returns the schema of a type in a space, or `None` if the entity isn't
+    /// a type in the space.
+    pub async fn get_type_schema(
+        &self,
+        type_id: &Uuid,
+        space_id: &Uuid,
+    ) -> Result<Option<TypeSchema>, StorageError> {
+        let row = sqlx::query(
+            r#"
+                SELECT
+                    EXISTS (
+                        SELECT 1 FROM relations r
+                        WHERE r.from_entity_id = $1 AND r.space_id = $2
+                            AND r.type_id = $3 AND r.to_entity_id = $4
+                    ) AS is_type,
+                    (
+                        SELECT v.string FROM values v
+                        WHERE v.entity_id = $1 AND v.space_id = $2 AND v.property_id = $5
+                    ) AS name
+            "#,
+        )
+        .bind(type_id)
+        .bind(space_id)
+        .bind(TYPES_PROPERTY)
+        .bind(SCHEMA_TYPE)
+        .bind(NAME_PROPERTY)
+        .fetch_one(&self.pool)
+        .await?;
+
+        if !row.get::<bool, _>("is_type") {
+            return Ok(None);
+        }
+
+        // A property attached several times is listed at its first position
+        let rows = sqlx::query(
+            r#"
+                SELECT p.id, p.type::text AS type, p.name, p.description, p.renderable_type
+                FROM (
+                    SELECT DISTINCT ON (r.to_entity_id) r.to_entity_id, r.position, r.id
+                    FROM relations r
+                    WHERE r.from_entity_id = $1 AND r.space_id = $2 AND r.type_id = $3
+                    ORDER BY r.to_entity_id, r.position NULLS LAST, r.id
+                ) r
+                JOIN properties p ON p.id = r.to_entity_id
+                ORDER BY r.position NULLS LAST, r.id
+            "#,
+        )
+        .bind(type_id)
+        .bind(space_id)
+        .bind(PROPERTIES_PROPERTY)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut properties = Vec::with_capacity(rows.len());
+
+        for row in rows {
+            let type_value: String = row.get("type");
+
+            let data_type = string_to_data_type(&type_value).ok_or_else(|| {
+                sqlx::Error::Decode(
+                    format!("Invalid enum value '{}' for dataTypes enum", type_value).into(),
+                )
+            })?;
+
+            properties.push(SchemaProperty {
+                id: row.get("id"),
+                data_type,
+                metadata: PropertyMetadata {
+                    name: row.get("name"),
+                    description: row.get("description"),
+                    renderable_type: row.get("renderable_type"),
+                },
+            });
+        }
+
+        Ok(Some(TypeSchema {
+            id: *type_id,
+            space_id: *space_id,
+            name: row.get("name"),
+            properties,
+        }))
+    }
+
+    /// Returns every space below a space in the subspace hierarchy, nearest
+    /// first.
+    pub async fn get_descendant_spaces(&self, space_id: &Uuid) -> Result<Vec<Uuid>, StorageError> {
+        let descendants = sqlx::query_scalar(
+            r#"
+                SELECT descendant_space_id FROM subspace_closure
+                WHERE ancestor_space_id = $1
+                ORDER BY depth, descendant_space_id
+            "#,
+        )
+        .bind(space_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(descendants)
+    }
+
+    /// Returns every space above a space in the subspace hierarchy, nearest
+    /// first.
+    pub async fn get_ancestor_spaces(&self, space_id: &Uuid) -> Result<Vec<Uuid>, StorageError> {
+        let ancestors = sqlx::query_scalar(
+            r#"
+                SELECT ancestor_space_id FROM subspace_closure
+                WHERE descendant_space_id = $1
+                ORDER BY depth, ancestor_space_id
+            "#,
+        )
+        .bind(space_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(ancestors)
+    }
+
     pub async fn get_all_properties(&self) -> Result<Vec<PropertyItem>, StorageError> {
         let rows = sqlx::query("SELECT id, type::text as type FROM properties")
             .fetch_all(&self.pool)
@@ -245,34 +787,33 @@ impl PostgresStorage {
             space_id: query.space_id,
         })
     }
-
-    pub async fn load_cursor(&self, id: &str) -> Result<Option<String>, StorageError> {
-        let result = sqlx::query!("SELECT cursor FROM meta WHERE id = $1", id)
-            .fetch_optional(&self.pool)
-            .await?;
-
-        Ok(result.map(|row| row.cursor))
-    }
-
-    pub async fn persist_cursor(
-        &self,
-        id: &str,
-        cursor: &str,
-        block: &u64,
-    ) -> Result<(), StorageError> {
-        sqlx::query!(
-            "INSERT INTO meta (id, cursor, block_number) VALUES ($1, $2, $3) ON CONFLICT (id) DO UPDATE SET cursor = $2, block_number = $3",
-            id,
-            cursor,
-            block.to_string()
-        )
-        .execute(&self.pool)
-        .await?;
-
-        Ok(())
-    }
 }
 
+/// The entities whose `last_edited_at` an edit moves, as the `touched` CTE:
+/// the edited entities, the entities its updated and deleted relations are
+/// from, the types it gives them and the types they have in the space.
+/// Updated and deleted relations are read before they're written, so they're
+/// still from the entities they were from before the edit. Binds the edited
+/// entities to `$1`, the relations to `$2`, the space to `$3`, the given
+/// types to `$4` and the types property to `$5`.
+const EDITED_ENTITIES: &str = r#"
+    edited AS (
+        SELECT UNNEST($1::uuid[]) AS id
+        UNION
+        SELECT r.from_entity_id FROM relations r
+        WHERE r.space_id = $3 AND r.id = ANY($2::uuid[])
+    ),
+    touched AS (
+        SELECT id FROM edited
+        UNION
+        SELECT UNNEST($4::uuid[])
+        UNION
+        SELECT r.to_entity_id FROM relations r
+        WHERE r.space_id = $3 AND r.type_id = $5
+            AND r.from_entity_id IN (SELECT id FROM edited)
+    )
+"#;
+
 #[async_trait]
 impl StorageBackend for PostgresStorage {
     fn get_pool(&self) -> &sqlx::Pool<Postgres> {
@@ -284,6 +825,13 @@ impl StorageBackend for PostgresStorage {
         entities: &Vec<EntityItem>,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError> {
+        // Entities are shared by every space, and edits of different spaces
+        // are written concurrently. Edits lock the rows they write with
+        // lock_entities first, and new rows are inserted in id order too, so
+        // their transactions don't deadlock each other.
+        let mut entities: Vec<&EntityItem> = entities.iter().collect();
+        entities.sort_by_key(|entity| entity.id);
+
         let ids: Vec<Uuid> = entities.iter().map(|x| x.id).collect();
         let created_ats: Vec<String> = entities.iter().map(|x| x.created_at.clone()).collect();
         let created_at_blocks: Vec<String> = entities
@@ -329,6 +877,8 @@ impl StorageBackend for PostgresStorage {
         let mut space_ids = Vec::with_capacity(values.len());
         let mut languages = Vec::with_capacity(values.len());
         let mut units = Vec::with_capacity(values.len());
+        let mut options = Vec::with_capacity(values.len());
+        let mut edit_ids = Vec::with_capacity(values.len());
         // Type-specific columns
         let mut text_values = Vec::with_capacity(values.len());
         let mut number_values = Vec::with_capacity(values.len());
@@ -343,6 +893,8 @@ impl StorageBackend for PostgresStorage {
             space_ids.push(&prop.space_id);
             languages.push(&prop.language);
             units.push(&prop.unit);
+            options.push(&prop.options);
+            edit_ids.push(prop.edit_id);
 
             // Add type-specific values
             text_values.push(prop.string.as_deref());
@@ -355,7 +907,7 @@ impl StorageBackend for PostgresStorage {
         let query = r#"
                 INSERT INTO values (
                     id, entity_id, property_id, space_id, language, unit,
-                    string, number, boolean, time, point
+                    string, number, boolean, time, point, options, edit_id
                 )
                 SELECT * FROM UNNEST(
                     $1::text[],
@@ -368,11 +920,15 @@ impl StorageBackend for PostgresStorage {
                     $8::numeric[],
                     $9::boolean[],
                     $10::text[],
-                    $11::text[]
+                    $11::text[],
+                    $12::jsonb[],
+                    $13::uuid[]
                 )
-                ON CONFLICT (id) DO UPDATE SET
+                ON CONFLICT (id, space_id) DO UPDATE SET
                     language = EXCLUDED.language,
                     unit = EXCLUDED.unit,
+                    options = EXCLUDED.options,
+                    edit_id = EXCLUDED.edit_id,
                     string = EXCLUDED.string,
                     number = EXCLUDED.number,
                     boolean = EXCLUDED.boolean,
@@ -392,6 +948,8 @@ impl StorageBackend for PostgresStorage {
             .bind(&boolean_values)
             .bind(&time_values)
             .bind(&point_values)
+            .bind(&options)
+            .bind(&edit_ids)
             .execute(&mut **tx)
             .await?;
 
@@ -438,11 +996,14 @@ impl StorageBackend for PostgresStorage {
         let mut entity_ids = Vec::with_capacity(relations.len());
         let mut from_ids = Vec::with_capacity(relations.len());
         let mut from_space_ids = Vec::with_capacity(relations.len());
+        let mut from_version_ids = Vec::with_capacity(relations.len());
         let mut to_ids = Vec::with_capacity(relations.len());
         let mut to_space_ids = Vec::with_capacity(relations.len());
+        let mut to_version_ids = Vec::with_capacity(relations.len());
         let mut type_ids = Vec::with_capacity(relations.len());
         let mut positions = Vec::with_capacity(relations.len());
         let mut verified = Vec::with_capacity(relations.len());
+        let mut edit_ids = Vec::with_capacity(relations.len());
 
         for rel in relations {
             ids.push(&rel.id);
@@ -450,27 +1011,48 @@ impl StorageBackend for PostgresStorage {
             entity_ids.push(&rel.entity_id);
             from_ids.push(&rel.from_id);
             from_space_ids.push(&rel.from_space_id);
+            from_version_ids.push(&rel.from_version_id);
             to_ids.push(&rel.to_id);
             to_space_ids.push(&rel.to_space_id);
+            to_version_ids.push(&rel.to_version_id);
             type_ids.push(&rel.type_id);
             positions.push(&rel.position);
             verified.push(&rel.verified);
+            edit_ids.push(rel.edit_id);
         }
 
+        // All relations are upserted in one statement. A relation listed more
+        // than once is written as its last row, as ON CONFLICT can't update
+        // the same row twice in a statement. Only the fields relation updates
+        // can change are updated, as a relation's space, type, entity and
+        // endpoints are fixed when it's created.
         let query = r#"
                 INSERT INTO relations (
-                    id, space_id, entity_id, from_entity_id, from_space_id,
-                    to_entity_id, to_space_id, type_id, position, verified
+                    id, space_id, entity_id, from_entity_id, from_space_id, from_version_id,
+                    to_entity_id, to_space_id, to_version_id, type_id, position, verified, edit_id
                 )
-                SELECT * FROM UNNEST(
-                    $1::uuid[], $2::uuid[], $3::uuid[], $4::uuid[], $5::uuid[],
-                    $6::uuid[], $7::uuid[], $8::uuid[], $9::text[], $10::boolean[]
+                SELECT DISTINCT ON (r.id)
+                    r.id, r.space_id, r.entity_id, r.from_entity_id, r.from_space_id,
+                    r.from_version_id, r.to_entity_id, r.to_space_id, r.to_version_id,
+                    r.type_id, r.position, r.verified, r.edit_id
+                FROM UNNEST(
+                    $1::uuid[], $2::uuid[], $3::uuid[], $4::uuid[], $5::uuid[], $6::uuid[],
+                    $7::uuid[], $8::uuid[], $9::uuid[], $10::uuid[], $11::text[],
+                    $12::boolean[], $13::uuid[]
+                ) WITH ORDINALITY AS r(
+                    id, space_id, entity_id, from_entity_id, from_space_id, from_version_id,
+                    to_entity_id, to_space_id, to_version_id, type_id, position, verified,
+                    edit_id, ordinality
                 )
+                ORDER BY r.id, r.ordinality DESC
                 ON CONFLICT (id) DO UPDATE SET
                     to_space_id = EXCLUDED.to_space_id,
+                    to_version_id = EXCLUDED.to_version_id,
                     from_space_id = EXCLUDED.from_space_id,
+                    from_version_id = EXCLUDED.from_version_id,
                     position = EXCLUDED.position,
-                    verified = EXCLUDED.verified
+                    verified = EXCLUDED.verified,
+                    edit_id = EXCLUDED.edit_id
             "#;
 
         sqlx::query(query)
@@ -479,11 +1061,14 @@ impl StorageBackend for PostgresStorage {
             .bind(&entity_ids)
             .bind(&from_ids)
             .bind(&from_space_ids)
+            .bind(&from_version_ids)
             .bind(&to_ids)
             .bind(&to_space_ids)
+            .bind(&to_version_ids)
             .bind(&type_ids)
             .bind(&positions)
             .bind(&verified)
+            .bind(&edit_ids)
             .execute(&mut **tx)
             .await?;
 
@@ -499,30 +1084,58 @@ impl StorageBackend for PostgresStorage {
             return Ok(());
         }
 
-        // @TODO:
-        // This is tricky since we only want to update if the values are actually set,
-        // not if they're None
+        let relations = merge_relation_updates(relations);
 
-        // Create a query builder for PostgreSQL
-        let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
-            "UPDATE relations (id, from_space_id, to_space_id, position, verified) ",
-        );
+        let mut ids = Vec::with_capacity(relations.len());
+        let mut space_ids = Vec::with_capacity(relations.len());
+        let mut from_space_ids = Vec::with_capacity(relations.len());
+        let mut from_version_ids = Vec::with_capacity(relations.len());
+        let mut to_space_ids = Vec::with_capacity(relations.len());
+        let mut to_version_ids = Vec::with_capacity(relations.len());
+        let mut positions = Vec::with_capacity(relations.len());
+        let mut verified = Vec::with_capacity(relations.len());
 
-        // Start the VALUES section
-        query_builder.push_values(relations, |mut b, relation| {
-            b.push_bind(&relation.id);
-            b.push_bind(&relation.from_space_id);
-            b.push_bind(&relation.to_space_id);
-            b.push_bind(&relation.position);
-            b.push_bind(&relation.verified);
-        });
+        for rel in &relations {
+            ids.push(&rel.id);
+            space_ids.push(&rel.space_id);
+            from_space_ids.push(&rel.from_space_id);
+            from_version_ids.push(&rel.from_version_id);
+            to_space_ids.push(&rel.to_space_id);
+            to_version_ids.push(&rel.to_version_id);
+            positions.push(&rel.position);
+            verified.push(&rel.verified);
+        }
 
-        // Execute the query
-        let result = query_builder.build().execute(&mut **tx).await;
+        // Only the fields an update sets are changed, the others are kept
+        let query = r#"
+                UPDATE relations SET
+                    from_space_id = COALESCE(u.from_space_id, relations.from_space_id),
+                    from_version_id = COALESCE(u.from_version_id, relations.from_version_id),
+                    to_space_id = COALESCE(u.to_space_id, relations.to_space_id),
+                    to_version_id = COALESCE(u.to_version_id, relations.to_version_id),
+                    position = COALESCE(u.position, relations.position),
+                    verified = COALESCE(u.verified, relations.verified)
+                FROM UNNEST(
+                    $1::uuid[], $2::uuid[], $3::uuid[], $4::uuid[], $5::uuid[], $6::uuid[],
+                    $7::text[], $8::boolean[]
+                ) AS u(
+                    id, space_id, from_space_id, from_version_id, to_space_id, to_version_id,
+                    position, verified
+                )
+                WHERE relations.id = u.id AND relations.space_id = u.space_id
+            "#;
 
-        if let Err(error) = result {
-            error!("Error writing relations: {}", error);
-        }
+        sqlx::query(query)
+            .bind(&ids)
+            .bind(&space_ids)
+            .bind(&from_space_ids)
+            .bind(&from_version_ids)
+            .bind(&to_space_ids)
+            .bind(&to_version_ids)
+            .bind(&positions)
+            .bind(&verified)
+            .execute(&mut **tx)
+            .await?;
 
         Ok(())
     }
@@ -599,20 +1212,133 @@ impl StorageBackend for PostgresStorage {
         Ok(())
     }
 
-    /// Properties are a special, knowledge-graph wide concept. A property
-    /// is a semantic representation of values. e.g., a value might be
-    /// "Byron", but without any further context we don't know what "Byron"
-    /// represents. Properties are entities which provide semantic meaning,
-    /// so there might be a Property called "Name". This Property has a
-    /// Data Type of "Text". By associating the value "Byron" with the Property
-    /// "Name", we provide semantic meaning to the pair.
-    ///
-    /// The knowledge graph engine validates that all values associated with
-    /// a property correctly conform to the property's Data Type. Additionally,
-    /// changing the Property's Data Type is not allowed.
-    async fn insert_properties(
+    async fn lock_entities(
         &self,
-        properties: &Vec<PropertyItem>,
+        entity_ids: &[Uuid],
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if entity_ids.is_empty() && edited.is_empty() {
+            return Ok(());
+        }
+
+        let query = format!(
+            r#"
+            WITH {EDITED_ENTITIES},
+            locked AS (
+                SELECT e.id FROM entities e
+                WHERE e.id IN (SELECT id FROM touched UNION SELECT UNNEST($6::uuid[]))
+                ORDER BY e.id
+                FOR UPDATE
+            )
+            SELECT COUNT(*) FROM locked
+            "#
+        );
+        sqlx::query(&query)
+            .bind(&edited.entity_ids)
+            .bind(&edited.relation_ids)
+            .bind(space_id)
+            .bind(&edited.type_ids)
+            .bind(TYPES_PROPERTY)
+            .bind(entity_ids)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn record_edited_entities(
+        &self,
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if edited.is_empty() {
+            return Ok(());
+        }
+
+        // The rows were locked by lock_entities before the edit's entities
+        // were written, and the space's relations haven't changed since, so
+        // the same rows are updated.
+        let query = format!(
+            r#"
+            WITH {EDITED_ENTITIES}
+            UPDATE entities e SET last_edited_at = $6, last_edited_block = $7
+            WHERE e.id IN (SELECT id FROM touched)
+            "#
+        );
+        sqlx::query(&query)
+            .bind(&edited.entity_ids)
+            .bind(&edited.relation_ids)
+            .bind(space_id)
+            .bind(&edited.type_ids)
+            .bind(TYPES_PROPERTY)
+            .bind(&block.timestamp)
+            .bind(block.block_number.to_string())
+            .execute(&mut **tx)
+            .await?;
+
+        sqlx::query("UPDATE spaces SET last_edited_at = $2, last_edited_block = $3 WHERE id = $1")
+            .bind(space_id)
+            .bind(&block.timestamp)
+            .bind(block.block_number.to_string())
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn delete_entities(
+        &self,
+        entity_ids: &[Uuid],
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if entity_ids.is_empty() {
+            return Ok(());
+        }
+
+        sqlx::query(
+            "DELETE FROM values
+                     WHERE space_id = $1 AND entity_id IN
+                     (SELECT * FROM UNNEST($2::uuid[]))",
+        )
+        .bind(space_id)
+        .bind(entity_ids)
+        .execute(&mut **tx)
+        .await?;
+
+        sqlx::query(
+            "DELETE FROM relations
+                     WHERE space_id = $1 AND (
+                        from_entity_id IN (SELECT * FROM UNNEST($2::uuid[]))
+                        OR to_entity_id IN (SELECT * FROM UNNEST($2::uuid[]))
+                     )",
+        )
+        .bind(space_id)
+        .bind(entity_ids)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Properties are a special, knowledge-graph wide concept. A property
+    /// is a semantic representation of values. e.g., a value might be
+    /// "Byron", but without any further context we don't know what "Byron"
+    /// represents. Properties are entities which provide semantic meaning,
+    /// so there might be a Property called "Name". This Property has a
+    /// Data Type of "Text". By associating the value "Byron" with the Property
+    /// "Name", we provide semantic meaning to the pair.
+    ///
+    /// The knowledge graph engine validates that all values associated with
+    /// a property correctly conform to the property's Data Type. Additionally,
+    /// changing the Property's Data Type is not allowed.
+    async fn insert_properties(
+        &self,
+        properties: &Vec<PropertyItem>,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError> {
         if properties.is_empty() {
@@ -623,6 +1349,10 @@ impl StorageBackend for PostgresStorage {
         let mut ids = Vec::with_capacity(properties.len());
         let mut types = Vec::with_capacity(properties.len());
 
+        // Sorted by id like entities, since properties are global too
+        let mut properties: Vec<&PropertyItem> = properties.iter().collect();
+        properties.sort_by_key(|property| property.id);
+
         for property in properties {
             ids.push(&property.id);
             types.push(property.data_type.as_ref());
@@ -649,9 +1379,204 @@ impl StorageBackend for PostgresStorage {
         Ok(())
     }
 
+    /// Only public spaces, whose edits are applied through governance
+    /// proposals, can migrate a property. The property is global, so the
+    /// values of every space are converted.
+    async fn migrate_property(
+        &self,
+        property: &PropertyItem,
+        space_id: &Uuid,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<PropertyMigrationItem>, StorageError> {
+        let from_type: Option<String> =
+            sqlx::query_scalar("SELECT type::text FROM properties WHERE id = $1 FOR UPDATE")
+                .bind(property.id)
+                .fetch_optional(&mut **tx)
+                .await?;
+
+        let from_type = match from_type.as_deref().and_then(string_to_data_type) {
+            Some(from_type) if from_type != property.data_type => from_type,
+            _ => return Ok(None),
+        };
+
+        let space_type: Option<String> =
+            sqlx::query_scalar("SELECT type::text FROM spaces WHERE id = $1")
+                .bind(space_id)
+                .fetch_optional(&mut **tx)
+                .await?;
+
+        let converted = if space_type.as_deref() == Some("Public") {
+            let rows = sqlx::query(
+                r#"SELECT
+                    id, property_id, entity_id, space_id,
+                    language, unit, options, edit_id, string,
+                    number::float8 as number, boolean, time, point
+                    FROM values WHERE property_id = $1 FOR UPDATE"#,
+            )
+            .bind(property.id)
+            .fetch_all(&mut **tx)
+            .await?;
+
+            let values = rows
+                .iter()
+                .map(value_from_row)
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let relation_count: i64 =
+                sqlx::query_scalar("SELECT COUNT(*) FROM relations WHERE type_id = $1")
+                    .bind(property.id)
+                    .fetch_one(&mut **tx)
+                    .await?;
+
+            PropertiesModel::convert_values(from_type, property.data_type, values, relation_count)
+        } else {
+            Err(PropertyMigrationError::NotGoverned)
+        };
+
+        let migration = PropertyMigrationItem {
+            property_id: property.id,
+            space_id: *space_id,
+            from_type,
+            to_type: property.data_type,
+            rejection: converted.as_ref().err().cloned(),
+            converted_values: converted.as_ref().map_or(0, Vec::len),
+            created_at: block.timestamp.clone(),
+            created_at_block: block.block_number.to_string(),
+        };
+
+        if let Ok(values) = &converted {
+            self.insert_values(values, tx).await?;
+
+            sqlx::query(r#"UPDATE properties SET type = $2::"dataTypes" WHERE id = $1"#)
+                .bind(property.id)
+                .bind(property.data_type.as_ref())
+                .execute(&mut **tx)
+                .await?;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO property_migrations (
+                property_id, space_id, from_type, to_type, applied, reason,
+                converted_values, created_at, created_at_block
+            )
+            VALUES ($1, $2, $3::"dataTypes", $4::"dataTypes", $5, $6, $7, $8, $9)
+            "#,
+        )
+        .bind(migration.property_id)
+        .bind(migration.space_id)
+        .bind(migration.from_type.as_ref())
+        .bind(migration.to_type.as_ref())
+        .bind(migration.is_applied())
+        .bind(migration.rejection.as_ref().map(ToString::to_string))
+        .bind(migration.converted_values as i32)
+        .bind(&migration.created_at)
+        .bind(&migration.created_at_block)
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(Some(migration))
+    }
+
+    /// Property metadata is global while values and relations are per space,
+    /// so the last space to change a property's metadata determines it.
+    async fn update_property_metadata(
+        &self,
+        property_ids: &[Uuid],
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if property_ids.is_empty() {
+            return Ok(());
+        }
+
+        let query = r#"
+                UPDATE properties p SET
+                    name = (
+                        SELECT v.string FROM values v
+                        WHERE v.entity_id = p.id AND v.property_id = $3 AND v.space_id = $2
+                    ),
+                    description = (
+                        SELECT v.string FROM values v
+                        WHERE v.entity_id = p.id AND v.property_id = $4 AND v.space_id = $2
+                    ),
+                    renderable_type = (
+                        SELECT r.to_entity_id FROM relations r
+                        WHERE r.from_entity_id = p.id AND r.type_id = $5 AND r.space_id = $2
+                        ORDER BY r.position NULLS LAST, r.id
+                        LIMIT 1
+                    )
+                WHERE p.id IN (SELECT * FROM UNNEST($1::uuid[]))
+            "#;
+
+        sqlx::query(query)
+            .bind(property_ids)
+            .bind(space_id)
+            .bind(NAME_PROPERTY)
+            .bind(DESCRIPTION_PROPERTY)
+            .bind(RENDERABLE_TYPE_PROPERTY)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn update_space_metadata(
+        &self,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        // The cover image may live in another space than the relation to it
+        let query = r#"
+                UPDATE spaces s SET
+                    entity_id = e.id,
+                    name = (
+                        SELECT v.string FROM values v
+                        WHERE v.entity_id = e.id AND v.property_id = $4 AND v.space_id = s.id
+                    ),
+                    description = (
+                        SELECT v.string FROM values v
+                        WHERE v.entity_id = e.id AND v.property_id = $5 AND v.space_id = s.id
+                    ),
+                    cover = (
+                        SELECT v.string FROM relations r
+                        JOIN values v ON v.entity_id = r.to_entity_id
+                            AND v.space_id = COALESCE(r.to_space_id, r.space_id)
+                            AND v.property_id = $7
+                        WHERE r.from_entity_id = e.id AND r.type_id = $6 AND r.space_id = s.id
+                        ORDER BY r.position NULLS LAST, r.id
+                        LIMIT 1
+                    )
+                FROM (
+                    SELECT (
+                        SELECT r.from_entity_id FROM relations r
+                        WHERE r.space_id = $1 AND r.type_id = $2 AND r.to_entity_id = $3
+                        ORDER BY r.position NULLS LAST, r.id
+                        LIMIT 1
+                    ) AS id
+                ) e
+                WHERE s.id = $1
+            "#;
+
+        sqlx::query(query)
+            .bind(space_id)
+            .bind(TYPES_PROPERTY)
+            .bind(SPACE_TYPE)
+            .bind(NAME_PROPERTY)
+            .bind(DESCRIPTION_PROPERTY)
+            .bind(COVER_PROPERTY)
+            .bind(IMAGE_URL_PROPERTY)
+            .execute(&mut **tx)
+            .await?;
+
+        Ok(())
+    }
+
     async fn insert_spaces(
         &self,
         spaces: &Vec<SpaceItem>,
+        block: &BlockMetadata,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError> {
         if spaces.is_empty() {
@@ -665,6 +1590,10 @@ impl StorageBackend for PostgresStorage {
         let mut main_voting_addresses: Vec<Option<String>> = Vec::new();
         let mut membership_addresses: Vec<Option<String>> = Vec::new();
         let mut personal_addresses: Vec<Option<String>> = Vec::new();
+        let mut networks: Vec<String> = Vec::new();
+        let mut chain_ids: Vec<Option<i64>> = Vec::new();
+        let mut creators: Vec<Option<String>> = Vec::new();
+        let mut tx_hashes: Vec<Option<String>> = Vec::new();
 
         for space in spaces {
             ids.push(space.id);
@@ -677,28 +1606,61 @@ impl StorageBackend for PostgresStorage {
             main_voting_addresses.push(space.voting_address.clone());
             membership_addresses.push(space.membership_address.clone());
             personal_addresses.push(space.personal_address.clone());
+            networks.push(space.network.clone());
+            chain_ids.push(space.chain_id.map(|chain_id| chain_id as i64));
+            creators.push(space.creator.clone());
+            tx_hashes.push(space.tx_hash.clone());
         }
 
-        sqlx::query!(
+        sqlx::query(
             r#"
-            INSERT INTO spaces (id, type, dao_address, space_address, main_voting_address, membership_address, personal_address)
-            SELECT id, type::"spaceTypes", dao_address, space_address, main_voting_address, membership_address, personal_address
-            FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[])
-            AS t(id, type, dao_address, space_address, main_voting_address, membership_address, personal_address)
+            INSERT INTO spaces (id, type, dao_address, space_address, main_voting_address, membership_address, personal_address, network, chain_id, creator, created_tx_hash, created_at, created_at_block)
+            SELECT id, type::"spaceTypes", dao_address, space_address, main_voting_address, membership_address, personal_address, network, chain_id, creator, created_tx_hash, $12, $13
+            FROM UNNEST($1::uuid[], $2::text[], $3::text[], $4::text[], $5::text[], $6::text[], $7::text[], $8::text[], $9::bigint[], $10::text[], $11::text[])
+            AS t(id, type, dao_address, space_address, main_voting_address, membership_address, personal_address, network, chain_id, creator, created_tx_hash)
             ON CONFLICT (id) DO NOTHING
             "#,
-            &ids,
-            &types,
-            &dao_addresses,
-            &space_addresses,
-            &main_voting_addresses as &[Option<String>],
-            &membership_addresses as &[Option<String>],
-            &personal_addresses as &[Option<String>]
         )
+        .bind(&ids)
+        .bind(&types)
+        .bind(&dao_addresses)
+        .bind(&space_addresses)
+        .bind(&main_voting_addresses)
+        .bind(&membership_addresses)
+        .bind(&personal_addresses)
+        .bind(&networks)
+        .bind(&chain_ids)
+        .bind(&creators)
+        .bind(&tx_hashes)
+        .bind(&block.timestamp)
+        .bind(block.block_number.to_string())
         .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        notify_spaces_updated(&ids, SpaceChange::Created, tx).await
+    }
+
+    async fn find_unknown_spaces(
+        &self,
+        space_ids: &[Uuid],
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<Uuid>, StorageError> {
+        if space_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let unknown = sqlx::query_scalar(
+            r#"
+                SELECT t.id FROM UNNEST($1::uuid[]) WITH ORDINALITY AS t(id, n)
+                WHERE NOT EXISTS (SELECT 1 FROM spaces s WHERE s.id = t.id)
+                ORDER BY t.n
+            "#,
+        )
+        .bind(space_ids)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        Ok(unknown)
     }
 
     async fn insert_members(
@@ -718,13 +1680,21 @@ impl StorageBackend for PostgresStorage {
             space_ids.push(member.space_id);
         }
 
+        // Member counts only change by the members that were actually added
         sqlx::query!(
             r#"
-            INSERT INTO members (address, space_id)
-            SELECT address, space_id
-            FROM UNNEST($1::text[], $2::uuid[])
-            AS t(address, space_id)
-            ON CONFLICT (address, space_id) DO NOTHING
+            WITH inserted AS (
+                INSERT INTO members (address, space_id)
+                SELECT address, space_id
+                FROM UNNEST($1::text[], $2::uuid[])
+                AS t(address, space_id)
+                ON CONFLICT (address, space_id) DO NOTHING
+                RETURNING space_id
+            )
+            UPDATE spaces s
+            SET member_count = s.member_count + c.added
+            FROM (SELECT space_id, count(*) AS added FROM inserted GROUP BY space_id) AS c
+            WHERE s.id = c.space_id
             "#,
             &addresses,
             &space_ids
@@ -732,7 +1702,7 @@ impl StorageBackend for PostgresStorage {
         .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        notify_spaces_updated(&space_ids, SpaceChange::Members, tx).await
     }
 
     async fn remove_members(
@@ -754,12 +1724,19 @@ impl StorageBackend for PostgresStorage {
 
         sqlx::query!(
             r#"
-            DELETE FROM members
-            WHERE (address, space_id) IN (
-                SELECT address, space_id
-                FROM UNNEST($1::text[], $2::uuid[])
-                AS t(address, space_id)
+            WITH deleted AS (
+                DELETE FROM members
+                WHERE (address, space_id) IN (
+                    SELECT address, space_id
+                    FROM UNNEST($1::text[], $2::uuid[])
+                    AS t(address, space_id)
+                )
+                RETURNING space_id
             )
+            UPDATE spaces s
+            SET member_count = s.member_count - c.removed
+            FROM (SELECT space_id, count(*) AS removed FROM deleted GROUP BY space_id) AS c
+            WHERE s.id = c.space_id
             "#,
             &addresses,
             &space_ids
@@ -767,12 +1744,13 @@ impl StorageBackend for PostgresStorage {
         .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        notify_spaces_updated(&space_ids, SpaceChange::Members, tx).await
     }
 
     async fn insert_editors(
         &self,
         editors: &Vec<EditorItem>,
+        block: &BlockMetadata,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError> {
         if editors.is_empty() {
@@ -787,26 +1765,53 @@ impl StorageBackend for PostgresStorage {
             space_ids.push(editor.space_id);
         }
 
-        sqlx::query!(
+        // The editors, their periods and the spaces' editor counts are
+        // written in one statement. A period is only opened if the editor
+        // doesn't already have one, so re-adding an editor or reprocessing
+        // the block doesn't split it.
+        sqlx::query(
             r#"
-            INSERT INTO editors (address, space_id)
-            SELECT address, space_id
-            FROM UNNEST($1::text[], $2::uuid[])
-            AS t(address, space_id)
-            ON CONFLICT (address, space_id) DO NOTHING
+            WITH input AS (
+                SELECT DISTINCT address, space_id
+                FROM UNNEST($1::text[], $2::uuid[]) AS t(address, space_id)
+            ),
+            inserted AS (
+                INSERT INTO editors (address, space_id)
+                SELECT address, space_id FROM input
+                ON CONFLICT (address, space_id) DO NOTHING
+                RETURNING space_id
+            ),
+            history AS (
+                INSERT INTO editor_history (space_id, address, added_at_block)
+                SELECT t.space_id, t.address, $3
+                FROM input t
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM editor_history h
+                    WHERE h.space_id = t.space_id
+                        AND h.address = t.address
+                        AND h.removed_at_block IS NULL
+                )
+                ON CONFLICT (space_id, address, added_at_block) DO NOTHING
+            )
+            UPDATE spaces s
+            SET editor_count = s.editor_count + c.added
+            FROM (SELECT space_id, count(*) AS added FROM inserted GROUP BY space_id) AS c
+            WHERE s.id = c.space_id
             "#,
-            &addresses,
-            &space_ids
         )
+        .bind(&addresses)
+        .bind(&space_ids)
+        .bind(block.block_number as i64)
         .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        notify_spaces_updated(&space_ids, SpaceChange::Editors, tx).await
     }
 
     async fn remove_editors(
         &self,
         editors: &Vec<EditorItem>,
+        block: &BlockMetadata,
         tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
     ) -> Result<(), StorageError> {
         if editors.is_empty() {
@@ -821,22 +1826,44 @@ impl StorageBackend for PostgresStorage {
             space_ids.push(editor.space_id);
         }
 
-        sqlx::query!(
+        // The editors are removed, their periods closed and the spaces'
+        // editor counts updated in one statement. Periods opened after the
+        // block are left open, so reprocessing an older block doesn't close
+        // them.
+        sqlx::query(
             r#"
-            DELETE FROM editors
-            WHERE (address, space_id) IN (
-                SELECT address, space_id
-                FROM UNNEST($1::text[], $2::uuid[])
-                AS t(address, space_id)
+            WITH input AS (
+                SELECT DISTINCT address, space_id
+                FROM UNNEST($1::text[], $2::uuid[]) AS t(address, space_id)
+            ),
+            deleted AS (
+                DELETE FROM editors e
+                USING input t
+                WHERE e.address = t.address AND e.space_id = t.space_id
+                RETURNING e.space_id
+            ),
+            history AS (
+                UPDATE editor_history h
+                SET removed_at_block = $3
+                FROM input t
+                WHERE h.space_id = t.space_id
+                    AND h.address = t.address
+                    AND h.added_at_block <= $3
+                    AND h.removed_at_block IS NULL
             )
+            UPDATE spaces s
+            SET editor_count = s.editor_count - c.removed
+            FROM (SELECT space_id, count(*) AS removed FROM deleted GROUP BY space_id) AS c
+            WHERE s.id = c.space_id
             "#,
-            &addresses,
-            &space_ids
         )
+        .bind(&addresses)
+        .bind(&space_ids)
+        .bind(block.block_number as i64)
         .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        notify_spaces_updated(&space_ids, SpaceChange::Editors, tx).await
     }
 
     async fn insert_subspaces(
@@ -870,7 +1897,8 @@ impl StorageBackend for PostgresStorage {
         .execute(&mut **tx)
         .await?;
 
-        Ok(())
+        refresh_subspace_closure(&subspace_ids, tx).await?;
+        notify_spaces_updated(&parent_space_ids, SpaceChange::Subspaces, tx).await
     }
 
     async fn remove_subspaces(
@@ -905,8 +1933,843 @@ impl StorageBackend for PostgresStorage {
         .execute(&mut **tx)
         .await?;
 
+        refresh_subspace_closure(&subspace_ids, tx).await?;
+        notify_spaces_updated(&parent_space_ids, SpaceChange::Subspaces, tx).await
+    }
+
+    async fn change_personal_admins(
+        &self,
+        admins: &[PersonalAdminItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if admins.is_empty() {
+            return Ok(());
+        }
+
+        let space_ids: Vec<Uuid> = admins.iter().map(|a| a.space_id).collect();
+        let personal_addresses: Vec<&str> =
+            admins.iter().map(|a| a.personal_address.as_str()).collect();
+        let admin_addresses: Vec<Option<&str>> =
+            admins.iter().map(|a| a.admin_address.as_deref()).collect();
+
+        // The last plugin of a space in the block wins. A plugin only
+        // replaces the space's admin if no admin was installed after the
+        // block, so reprocessing an older block doesn't roll the space back.
+        let changed: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            WITH input AS (
+                SELECT DISTINCT ON (t.space_id) t.space_id, t.personal_address, t.admin_address
+                FROM UNNEST($1::uuid[], $2::text[], $3::text[])
+                    WITH ORDINALITY AS t(space_id, personal_address, admin_address, n)
+                JOIN spaces s ON s.id = t.space_id AND s.type = 'Personal'
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM personal_admin_history h
+                    WHERE h.space_id = t.space_id AND h.added_at_block > $4
+                )
+                ORDER BY t.space_id, t.n DESC
+            ),
+            replaced AS (
+                UPDATE personal_admin_history h
+                SET replaced_at_block = $4
+                FROM input t
+                WHERE h.space_id = t.space_id
+                    AND h.personal_address <> t.personal_address
+                    AND h.replaced_at_block IS NULL
+            ),
+            added AS (
+                INSERT INTO personal_admin_history (space_id, personal_address, admin_address, added_at_block)
+                SELECT t.space_id, t.personal_address, t.admin_address, $4
+                FROM input t
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM personal_admin_history h
+                    WHERE h.space_id = t.space_id
+                        AND h.personal_address = t.personal_address
+                        AND h.replaced_at_block IS NULL
+                )
+                ON CONFLICT (space_id, personal_address, added_at_block) DO NOTHING
+            )
+            UPDATE spaces s
+            SET personal_address = t.personal_address
+            FROM input t
+            WHERE s.id = t.space_id AND s.personal_address IS DISTINCT FROM t.personal_address
+            RETURNING s.id
+            "#,
+        )
+        .bind(&space_ids)
+        .bind(&personal_addresses)
+        .bind(&admin_addresses)
+        .bind(block.block_number as i64)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        notify_spaces_updated(&changed, SpaceChange::Admin, tx).await
+    }
+
+    async fn change_governance_plugins(
+        &self,
+        plugins: &[GovernancePluginsItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if plugins.is_empty() {
+            return Ok(());
+        }
+
+        let space_ids: Vec<Uuid> = plugins.iter().map(|p| p.space_id).collect();
+        let voting_addresses: Vec<&str> =
+            plugins.iter().map(|p| p.voting_address.as_str()).collect();
+        let membership_addresses: Vec<&str> = plugins
+            .iter()
+            .map(|p| p.membership_address.as_str())
+            .collect();
+
+        // As with personal admins, the last plugins of a space in the block
+        // win, and only if no plugins were installed after the block. Each
+        // plugin only replaces the earlier plugin of its own type.
+        let changed: Vec<Uuid> = sqlx::query_scalar(
+            r#"
+            WITH input AS (
+                SELECT DISTINCT ON (t.space_id) t.space_id, t.voting_address, t.membership_address
+                FROM UNNEST($1::uuid[], $2::text[], $3::text[])
+                    WITH ORDINALITY AS t(space_id, voting_address, membership_address, n)
+                JOIN spaces s ON s.id = t.space_id AND s.type = 'Public'
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM space_plugins_history h
+                    WHERE h.space_id = t.space_id AND h.added_at_block > $4
+                )
+                ORDER BY t.space_id, t.n DESC
+            ),
+            plugins AS (
+                SELECT t.space_id, p.plugin_type, p.plugin_address
+                FROM input t
+                CROSS JOIN LATERAL (
+                    VALUES ('voting', t.voting_address), ('membership', t.membership_address)
+                ) AS p(plugin_type, plugin_address)
+            ),
+            replaced AS (
+                UPDATE space_plugins_history h
+                SET replaced_at_block = $4
+                FROM plugins p
+                WHERE h.space_id = p.space_id
+                    AND h.plugin_type = p.plugin_type
+                    AND h.plugin_address <> p.plugin_address
+                    AND h.replaced_at_block IS NULL
+            ),
+            added AS (
+                INSERT INTO space_plugins_history (space_id, plugin_type, plugin_address, added_at_block)
+                SELECT p.space_id, p.plugin_type, p.plugin_address, $4
+                FROM plugins p
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM space_plugins_history h
+                    WHERE h.space_id = p.space_id
+                        AND h.plugin_type = p.plugin_type
+                        AND h.plugin_address = p.plugin_address
+                        AND h.replaced_at_block IS NULL
+                )
+                ON CONFLICT (space_id, plugin_type, plugin_address, added_at_block) DO NOTHING
+            )
+            UPDATE spaces s
+            SET main_voting_address = t.voting_address, membership_address = t.membership_address
+            FROM input t
+            WHERE s.id = t.space_id
+                AND (s.main_voting_address IS DISTINCT FROM t.voting_address
+                    OR s.membership_address IS DISTINCT FROM t.membership_address)
+            RETURNING s.id
+            "#,
+        )
+        .bind(&space_ids)
+        .bind(&voting_addresses)
+        .bind(&membership_addresses)
+        .bind(block.block_number as i64)
+        .fetch_all(&mut **tx)
+        .await?;
+
+        notify_spaces_updated(&changed, SpaceChange::Governance, tx).await
+    }
+
+    async fn record_edit(
+        &self,
+        space_id: &Uuid,
+        content_hash: &str,
+        cid: &str,
+        edit_id: Option<Uuid>,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, StorageError> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO edits (space_id, content_hash, cid, edit_id, created_at, created_at_block)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (space_id, content_hash) DO NOTHING
+            "#,
+        )
+        .bind(space_id)
+        .bind(content_hash)
+        .bind(cid)
+        .bind(edit_id)
+        .bind(&block.timestamp)
+        .bind(block.block_number.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        let recorded = result.rows_affected() == 1;
+        if recorded {
+            notify_spaces_updated(&[*space_id], SpaceChange::Edit, tx).await?;
+        }
+
+        Ok(recorded)
+    }
+
+    async fn record_activity(
+        &self,
+        activity: &BlockActivity,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        let block_number = block.block_number.to_string();
+        let timestamp: i64 = block.timestamp.parse().unwrap_or(0);
+        let mut deltas: HashMap<Uuid, ActivityRollup> = HashMap::new();
+
+        let edits: Vec<(Uuid, i64)> = sqlx::query_as(
+            "SELECT space_id, COUNT(*) FROM edits WHERE created_at_block = $1 GROUP BY space_id",
+        )
+        .bind(&block_number)
+        .fetch_all(&mut **tx)
+        .await?;
+        for (space_id, count) in edits {
+            deltas.entry(space_id).or_default().edits += count as i32;
+        }
+
+        if !activity.entities.is_empty() {
+            let space_ids: Vec<Uuid> = activity.entities.iter().map(|e| e.space_id).collect();
+            let entity_ids: Vec<Uuid> = activity.entities.iter().map(|e| e.entity_id).collect();
+
+            let new_entities: Vec<(Uuid, i64)> = sqlx::query_as(
+                r#"
+                SELECT u.space_id, COUNT(*)
+                FROM UNNEST($1::uuid[], $2::uuid[]) AS u(space_id, entity_id)
+                JOIN entities e ON e.id = u.entity_id
+                WHERE e.created_at_block = $3
+                GROUP BY u.space_id
+                "#,
+            )
+            .bind(&space_ids)
+            .bind(&entity_ids)
+            .bind(&block_number)
+            .fetch_all(&mut **tx)
+            .await?;
+            for (space_id, count) in new_entities {
+                deltas.entry(space_id).or_default().new_entities += count as i32;
+            }
+        }
+
+        // Edits and new entities are counted from what the block wrote, which
+        // is still there if the block is processed again, so each space only
+        // counts them once per block.
+        if !deltas.is_empty() {
+            let space_ids: Vec<Uuid> = deltas.keys().copied().collect();
+            let counted: Vec<Uuid> = sqlx::query_scalar(
+                r#"
+                INSERT INTO counted_blocks (space_id, block_number)
+                SELECT space_id, $2 FROM UNNEST($1::uuid[]) AS space_id
+                ON CONFLICT DO NOTHING
+                RETURNING space_id
+                "#,
+            )
+            .bind(&space_ids)
+            .bind(block.block_number as i64)
+            .fetch_all(&mut **tx)
+            .await?;
+            deltas.retain(|space_id, _| counted.contains(space_id));
+        }
+
+        if !activity.created_proposals.is_empty() {
+            let space_ids: Vec<Uuid> = activity
+                .created_proposals
+                .iter()
+                .map(|p| p.space_id)
+                .collect();
+            let proposal_ids: Vec<&str> = activity
+                .created_proposals
+                .iter()
+                .map(|p| p.proposal_id.as_str())
+                .collect();
+
+            let counted: Vec<(Uuid, String)> = sqlx::query_as(
+                r#"
+                INSERT INTO counted_proposals (space_id, proposal_id, event)
+                SELECT space_id, proposal_id, 'created'
+                FROM UNNEST($1::uuid[], $2::text[]) AS u(space_id, proposal_id)
+                ON CONFLICT DO NOTHING
+                RETURNING space_id, proposal_id
+                "#,
+            )
+            .bind(&space_ids)
+            .bind(&proposal_ids)
+            .fetch_all(&mut **tx)
+            .await?;
+            for (space_id, _) in &counted {
+                deltas.entry(*space_id).or_default().proposals_created += 1;
+            }
+            notify_proposals_updated(&counted, ProposalStatus::Created, tx).await?;
+        }
+
+        // Every shard sees every executed proposal, so they're only counted
+        // by the first shard to record them. Proposals of plugins a space has
+        // since replaced are found through the plugin histories.
+        if !activity.executed_proposals.is_empty() {
+            let plugin_addresses: Vec<&str> = activity
+                .executed_proposals
+                .iter()
+                .map(|p| p.plugin_address.as_str())
+                .collect();
+            let proposal_ids: Vec<&str> = activity
+                .executed_proposals
+                .iter()
+                .map(|p| p.proposal_id.as_str())
+                .collect();
+
+            let counted: Vec<(Uuid, String)> = sqlx::query_as(
+                r#"
+                INSERT INTO counted_proposals (space_id, proposal_id, event)
+                SELECT DISTINCT s.space_id, u.proposal_id, 'executed'
+                FROM UNNEST($1::text[], $2::text[]) AS u(plugin_address, proposal_id)
+                CROSS JOIN LATERAL (
+                    SELECT id AS space_id FROM spaces
+                    WHERE main_voting_address = u.plugin_address
+                        OR personal_address = u.plugin_address
+                    UNION
+                    SELECT space_id FROM space_plugins_history
+                    WHERE plugin_type = 'voting' AND plugin_address = u.plugin_address
+                    UNION
+                    SELECT space_id FROM personal_admin_history
+                    WHERE personal_address = u.plugin_address
+                ) s
+                ON CONFLICT DO NOTHING
+                RETURNING space_id, proposal_id
+                "#,
+            )
+            .bind(&plugin_addresses)
+            .bind(&proposal_ids)
+            .fetch_all(&mut **tx)
+            .await?;
+            for (space_id, _) in &counted {
+                deltas.entry(*space_id).or_default().proposals_executed += 1;
+            }
+            notify_proposals_updated(&counted, ProposalStatus::Executed, tx).await?;
+        }
+
+        let editor_space_ids: Vec<Uuid> = activity.editors.iter().map(|e| e.space_id).collect();
+        let editor_ids: Vec<Uuid> = activity.editors.iter().map(|e| e.editor_id).collect();
+
+        for period in RollupPeriod::ALL {
+            let period_start = period.start(timestamp);
+            let mut period_deltas = deltas.clone();
+
+            if !editor_ids.is_empty() {
+                let new_editors: Vec<Uuid> = sqlx::query_scalar(
+                    r#"
+                    INSERT INTO space_active_editors (space_id, period, period_start, editor_id)
+                    SELECT space_id, $3, $4::date, editor_id
+                    FROM UNNEST($1::uuid[], $2::uuid[]) AS u(space_id, editor_id)
+                    ON CONFLICT DO NOTHING
+                    RETURNING space_id
+                    "#,
+                )
+                .bind(&editor_space_ids)
+                .bind(&editor_ids)
+                .bind(period.as_str())
+                .bind(&period_start)
+                .fetch_all(&mut **tx)
+                .await?;
+                for space_id in new_editors {
+                    period_deltas.entry(space_id).or_default().active_editors += 1;
+                }
+            }
+
+            if period_deltas.is_empty() {
+                continue;
+            }
+
+            let (space_ids, rollups): (Vec<Uuid>, Vec<ActivityRollup>) =
+                period_deltas.into_iter().unzip();
+            let column = |f: fn(&ActivityRollup) -> i32| rollups.iter().map(f).collect::<Vec<_>>();
+
+            sqlx::query(
+                r#"
+                INSERT INTO space_activity_rollups (
+                    space_id, period, period_start, edits, active_editors,
+                    proposals_created, proposals_executed, new_entities
+                )
+                SELECT u.space_id, $2, $3::date, u.edits, u.active_editors,
+                    u.proposals_created, u.proposals_executed, u.new_entities
+                FROM UNNEST($1::uuid[], $4::int[], $5::int[], $6::int[], $7::int[], $8::int[])
+                    AS u(space_id, edits, active_editors, proposals_created, proposals_executed, new_entities)
+                ON CONFLICT (space_id, period, period_start) DO UPDATE SET
+                    edits = space_activity_rollups.edits + EXCLUDED.edits,
+                    active_editors = space_activity_rollups.active_editors + EXCLUDED.active_editors,
+                    proposals_created = space_activity_rollups.proposals_created + EXCLUDED.proposals_created,
+                    proposals_executed = space_activity_rollups.proposals_executed + EXCLUDED.proposals_executed,
+                    new_entities = space_activity_rollups.new_entities + EXCLUDED.new_entities
+                "#,
+            )
+            .bind(&space_ids)
+            .bind(period.as_str())
+            .bind(&period_start)
+            .bind(column(|r| r.edits))
+            .bind(column(|r| r.active_editors))
+            .bind(column(|r| r.proposals_created))
+            .bind(column(|r| r.proposals_executed))
+            .bind(column(|r| r.new_entities))
+            .execute(&mut **tx)
+            .await?;
+        }
+
         Ok(())
     }
+
+    async fn append_ops_journal(
+        &self,
+        entries: &[JournalEntry],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let space_ids: Vec<Uuid> = entries.iter().map(|e| e.space_id).collect();
+        let edit_ids: Vec<Option<Uuid>> = entries.iter().map(|e| e.edit_id).collect();
+        let content_hashes: Vec<&str> = entries.iter().map(|e| e.content_hash.as_str()).collect();
+        let op_indexes: Vec<i32> = entries.iter().map(|e| e.op_index as i32).collect();
+        let op_kinds: Vec<&str> = entries.iter().map(|e| e.kind.as_str()).collect();
+        let target_ids: Vec<Uuid> = entries.iter().map(|e| e.target_id).collect();
+
+        // WITH ORDINALITY keeps the sequence in the order the ops are applied
+        sqlx::query(
+            r#"
+            INSERT INTO ops_journal (
+                block_number, space_id, edit_id, content_hash, op_index, op_kind, target_id
+            )
+            SELECT $1, u.space_id, u.edit_id, u.content_hash, u.op_index, u.op_kind, u.target_id
+            FROM UNNEST($2::uuid[], $3::uuid[], $4::text[], $5::int4[], $6::text[], $7::uuid[])
+                WITH ORDINALITY AS u(space_id, edit_id, content_hash, op_index, op_kind, target_id, ordinality)
+            WHERE EXISTS (
+                SELECT 1 FROM edits e
+                WHERE e.space_id = u.space_id
+                    AND e.content_hash = u.content_hash
+                    AND e.created_at_block = $8
+            )
+            ORDER BY u.ordinality
+            ON CONFLICT (space_id, content_hash, op_index) DO NOTHING
+            "#,
+        )
+        .bind(block.block_number as i64)
+        .bind(&space_ids)
+        .bind(&edit_ids)
+        .bind(&content_hashes)
+        .bind(&op_indexes)
+        .bind(&op_kinds)
+        .bind(&target_ids)
+        .bind(block.block_number.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_proposal_previews(
+        &self,
+        previews: &[ProposalPreviewItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if previews.is_empty() {
+            return Ok(());
+        }
+
+        let block_number = block.block_number.to_string();
+        let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            "INSERT INTO proposal_previews (space_id, proposal_id, content_uri, entity_ids, \
+             property_ids, values_set, values_unset, relations_created, relations_updated, \
+             relations_deleted, entities_deleted, created_at_block) ",
+        );
+        query_builder.push_values(previews, |mut b, item| {
+            let preview = &item.preview;
+            b.push_bind(item.space_id)
+                .push_bind(&item.proposal_id)
+                .push_bind(&preview.content_uri)
+                .push_bind(&preview.entity_ids)
+                .push_bind(&preview.property_ids)
+                .push_bind(preview.values_set as i32)
+                .push_bind(preview.values_unset as i32)
+                .push_bind(preview.relations_created as i32)
+                .push_bind(preview.relations_updated as i32)
+                .push_bind(preview.relations_deleted as i32)
+                .push_bind(preview.entities_deleted as i32)
+                .push_bind(&block_number);
+        });
+        // A block that's processed again may have found content that
+        // wasn't cached the first time
+        query_builder.push(
+            " ON CONFLICT (space_id, proposal_id) DO UPDATE SET \
+             content_uri = EXCLUDED.content_uri, \
+             entity_ids = EXCLUDED.entity_ids, \
+             property_ids = EXCLUDED.property_ids, \
+             values_set = EXCLUDED.values_set, \
+             values_unset = EXCLUDED.values_unset, \
+             relations_created = EXCLUDED.relations_created, \
+             relations_updated = EXCLUDED.relations_updated, \
+             relations_deleted = EXCLUDED.relations_deleted, \
+             entities_deleted = EXCLUDED.entities_deleted",
+        );
+
+        query_builder.build().execute(&mut **tx).await?;
+
+        Ok(())
+    }
+
+    async fn snapshot_proposal_electorates(
+        &self,
+        proposals: &[ProposalSnapshotItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if proposals.is_empty() {
+            return Ok(());
+        }
+
+        let space_ids: Vec<Uuid> = proposals.iter().map(|p| p.space_id).collect();
+        let proposal_ids: Vec<&str> = proposals.iter().map(|p| p.proposal_id.as_str()).collect();
+
+        sqlx::query(
+            r#"
+            WITH proposals AS (
+                SELECT DISTINCT p.space_id, p.proposal_id
+                FROM UNNEST($1::uuid[], $2::text[]) AS p(space_id, proposal_id)
+                WHERE NOT EXISTS (
+                    SELECT 1 FROM proposal_snapshots s
+                    WHERE s.space_id = p.space_id AND s.proposal_id = p.proposal_id
+                )
+            )
+            INSERT INTO proposal_snapshots (space_id, proposal_id, role, address, created_at_block)
+            SELECT p.space_id, p.proposal_id, 'editor', e.address, $3
+            FROM proposals p JOIN editors e ON e.space_id = p.space_id
+            UNION ALL
+            SELECT p.space_id, p.proposal_id, 'member', m.address, $3
+            FROM proposals p JOIN members m ON m.space_id = p.space_id
+            ON CONFLICT DO NOTHING
+            "#,
+        )
+        .bind(&space_ids)
+        .bind(&proposal_ids)
+        .bind(block.block_number.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn insert_proposals(
+        &self,
+        proposals: &[ProposalItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if proposals.is_empty() {
+            return Ok(());
+        }
+
+        let plugin_addresses: Vec<&str> = proposals
+            .iter()
+            .map(|p| p.plugin_address.as_str())
+            .collect();
+        let proposal_ids: Vec<&str> = proposals.iter().map(|p| p.proposal_id.as_str()).collect();
+        let space_ids: Vec<Uuid> = proposals.iter().map(|p| p.space_id).collect();
+        let proposal_types: Vec<&str> =
+            proposals.iter().map(|p| p.proposal_type.as_str()).collect();
+        let creators: Vec<&str> = proposals.iter().map(|p| p.creator.as_str()).collect();
+
+        sqlx::query(
+            r#"
+            INSERT INTO proposals (
+                plugin_address, proposal_id, space_id, proposal_type, creator, created_at_block
+            )
+            SELECT *, $6
+            FROM UNNEST($1::text[], $2::text[], $3::uuid[], $4::text[], $5::text[])
+            ON CONFLICT (plugin_address, proposal_id) DO NOTHING
+            "#,
+        )
+        .bind(&plugin_addresses)
+        .bind(&proposal_ids)
+        .bind(&space_ids)
+        .bind(&proposal_types)
+        .bind(&creators)
+        .bind(block.block_number.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn execute_proposals(
+        &self,
+        proposals: &[ProposalExecutionItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if proposals.is_empty() {
+            return Ok(());
+        }
+
+        let plugin_addresses: Vec<&str> = proposals
+            .iter()
+            .map(|p| p.plugin_address.as_str())
+            .collect();
+        let proposal_ids: Vec<&str> = proposals.iter().map(|p| p.proposal_id.as_str()).collect();
+        let tx_hashes: Vec<&str> = proposals.iter().map(|p| p.tx_hash.as_str()).collect();
+
+        sqlx::query(
+            r#"
+            UPDATE proposals p
+            SET status = 'executed',
+                executed_at = $4,
+                executed_at_block = $5,
+                executed_tx_hash = NULLIF(u.tx_hash, '')
+            FROM UNNEST($1::text[], $2::text[], $3::text[]) AS u(plugin_address, proposal_id, tx_hash)
+            WHERE p.plugin_address = u.plugin_address
+                AND p.proposal_id = u.proposal_id
+                AND p.status = 'created'
+            "#,
+        )
+        .bind(&plugin_addresses)
+        .bind(&proposal_ids)
+        .bind(&tx_hashes)
+        .bind(&block.timestamp)
+        .bind(block.block_number.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn cancel_proposals(
+        &self,
+        proposals: &[ProposalCancellationItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        if proposals.is_empty() {
+            return Ok(());
+        }
+
+        let plugin_addresses: Vec<&str> = proposals
+            .iter()
+            .map(|p| p.plugin_address.as_str())
+            .collect();
+        let proposal_ids: Vec<&str> = proposals.iter().map(|p| p.proposal_id.as_str()).collect();
+        let canceled_by: Vec<&str> = proposals.iter().map(|p| p.canceled_by.as_str()).collect();
+
+        let canceled: Vec<(Uuid, String)> = sqlx::query_as(
+            r#"
+            UPDATE proposals p
+            SET status = 'canceled', canceled_by = u.canceled_by, canceled_at_block = $4
+            FROM UNNEST($1::text[], $2::text[], $3::text[])
+                AS u(plugin_address, proposal_id, canceled_by)
+            WHERE p.plugin_address = u.plugin_address
+                AND p.proposal_id = u.proposal_id
+                AND p.status = 'created'
+            RETURNING p.space_id, p.proposal_id
+            "#,
+        )
+        .bind(&plugin_addresses)
+        .bind(&proposal_ids)
+        .bind(&canceled_by)
+        .bind(block.block_number.to_string())
+        .fetch_all(&mut **tx)
+        .await?;
+        notify_proposals_updated(&canceled, ProposalStatus::Canceled, tx).await?;
+
+        Ok(())
+    }
+
+    async fn is_block_processed(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<bool, StorageError> {
+        let processed: bool = sqlx::query_scalar(
+            "SELECT EXISTS (SELECT 1 FROM processed_blocks WHERE indexer_id = $1 AND block_number = $2)",
+        )
+        .bind(indexer_id)
+        .bind(block.block_number as i64)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(processed)
+    }
+
+    async fn record_processed_block(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<(), StorageError> {
+        sqlx::query(
+            r#"
+            INSERT INTO processed_blocks (indexer_id, block_number, cursor)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (indexer_id, block_number) DO NOTHING
+            "#,
+        )
+        .bind(indexer_id)
+        .bind(block.block_number as i64)
+        .bind(&block.cursor)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_relations_by_type(
+        &self,
+        space_id: &Uuid,
+        type_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        let filter = RelationFilter {
+            type_id: Some(*type_id),
+            ..Default::default()
+        };
+
+        self.list_relations(space_id, &filter, page).await
+    }
+
+    async fn get_outgoing_relations(
+        &self,
+        entity_id: &Uuid,
+        space_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        let rows = sqlx::query_as::<_, RelationRow>(
+            r#"
+                SELECT id, type_id, entity_id, space_id, from_entity_id, from_space_id, from_version_id, to_entity_id, to_space_id, to_version_id, verified, position, edit_id
+                FROM relations
+                WHERE from_entity_id = $1
+                    AND COALESCE(from_space_id, space_id) = $2
+                    AND ($3::uuid IS NULL OR id > $3)
+                ORDER BY id
+                LIMIT $4
+            "#,
+        )
+        .bind(entity_id)
+        .bind(space_id)
+        .bind(page.after_uuid()?)
+        .bind(page.fetch_limit())
+        .fetch_all(&self.pool)
+        .await?;
+
+        let relations = rows.into_iter().map(SetRelationItem::from).collect();
+
+        Ok(Page::from_rows(relations, page, |relation| {
+            relation.id.to_string()
+        }))
+    }
+}
+
+/// Rebuilds the ancestors of the given spaces and their descendants in the
+/// subspace closure. Adding or removing a subspace only changes the ancestors
+/// of the subspace and everything below it.
+async fn refresh_subspace_closure(
+    space_ids: &[Uuid],
+    tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+) -> Result<(), StorageError> {
+    let affected: Vec<Uuid> = sqlx::query_scalar(
+        r#"
+            WITH RECURSIVE affected(space_id) AS (
+                SELECT UNNEST($1::uuid[])
+                UNION
+                SELECT s.child_space_id
+                FROM subspaces s
+                JOIN affected a ON s.parent_space_id = a.space_id
+            )
+            SELECT space_id FROM affected
+        "#,
+    )
+    .bind(space_ids)
+    .fetch_all(&mut **tx)
+    .await?;
+
+    sqlx::query("DELETE FROM subspace_closure WHERE descendant_space_id = ANY($1)")
+        .bind(&affected)
+        .execute(&mut **tx)
+        .await?;
+
+    // Paths stop at spaces they already visited, so cycles in the hierarchy
+    // terminate
+    sqlx::query(
+        r#"
+            WITH RECURSIVE ancestors(descendant_space_id, ancestor_space_id, depth, path) AS (
+                SELECT s.child_space_id, s.parent_space_id, 1, ARRAY[s.child_space_id, s.parent_space_id]
+                FROM subspaces s
+                WHERE s.child_space_id = ANY($1)
+                UNION ALL
+                SELECT a.descendant_space_id, s.parent_space_id, a.depth + 1, a.path || s.parent_space_id
+                FROM ancestors a
+                JOIN subspaces s ON s.child_space_id = a.ancestor_space_id
+                WHERE NOT s.parent_space_id = ANY(a.path)
+            )
+            INSERT INTO subspace_closure (ancestor_space_id, descendant_space_id, depth)
+            SELECT ancestor_space_id, descendant_space_id, MIN(depth)
+            FROM ancestors
+            GROUP BY ancestor_space_id, descendant_space_id
+        "#,
+    )
+    .bind(&affected)
+    .execute(&mut **tx)
+    .await?;
+
+    Ok(())
+}
+
+fn value_from_row(row: &PgRow) -> Result<ValueOp, StorageError> {
+    let id = Uuid::parse_str(row.try_get::<&str, _>("id")?)
+        .map_err(|e| sqlx::Error::Decode(format!("Invalid UUID format for id: {}", e).into()))?;
+
+    let property_id: Uuid = row.try_get("property_id")?;
+    let entity_id: Uuid = row.try_get("entity_id")?;
+
+    let space_id: Uuid = row.try_get("space_id")?;
+
+    let language: Option<String> = row.try_get("language")?;
+    let unit: Option<String> = row.try_get("unit")?;
+    let options: Option<serde_json::Value> = row.try_get("options")?;
+    let edit_id: Option<Uuid> = row.try_get("edit_id")?;
+    let text: Option<String> = row.try_get("string")?;
+
+    let number: Option<f64> = row.try_get("number")?;
+
+    let boolean: Option<bool> = row.try_get("boolean")?;
+    let time: Option<String> = row.try_get("time")?;
+    let point: Option<String> = row.try_get("point")?;
+
+    Ok(ValueOp {
+        id,
+        property_id,
+        entity_id,
+        space_id,
+
+        language,
+        unit,
+        options,
+        edit_id,
+        string: text,
+        number,
+        boolean,
+        time,
+        point,
+        change_type: ValueChangeType::SET,
+    })
 }
 
 fn string_to_data_type(s: &str) -> Option<DataType> {
@@ -920,3 +2783,32 @@ fn string_to_data_type(s: &str) -> Option<DataType> {
         _ => None,
     }
 }
+
+/// Merges the updates to the same relation into one, in their order, so each
+/// relation is updated once. Fields set by a later update win over earlier
+/// ones, and fields it leaves unset are kept.
+fn merge_relation_updates(relations: &[UpdateRelationItem]) -> Vec<UpdateRelationItem> {
+    let mut merged: Vec<UpdateRelationItem> = Vec::with_capacity(relations.len());
+    let mut positions: HashMap<(Uuid, Uuid), usize> = HashMap::new();
+
+    for relation in relations {
+        let Some(&index) = positions.get(&(relation.id, relation.space_id)) else {
+            positions.insert((relation.id, relation.space_id), merged.len());
+            merged.push(relation.clone());
+            continue;
+        };
+
+        let existing = &mut merged[index];
+        existing.from_space_id = relation.from_space_id.clone().or(existing.from_space_id.take());
+        existing.from_version_id = relation
+            .from_version_id
+            .clone()
+            .or(existing.from_version_id.take());
+        existing.to_space_id = relation.to_space_id.clone().or(existing.to_space_id.take());
+        existing.to_version_id = relation.to_version_id.clone().or(existing.to_version_id.take());
+        existing.position = relation.position.clone().or(existing.position.take());
+        existing.verified = relation.verified.or(existing.verified);
+    }
+
+    merged
+}
@@ -0,0 +1,475 @@
+use async_trait::async_trait;
+use indexer_utils::faults::FaultInjector;
+use sqlx::Postgres;
+use std::sync::Mutex;
+use stream::utils::BlockMetadata;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        analytics::BlockActivity,
+        entities::{EditedEntities, EntityItem},
+        journal::JournalEntry,
+        membership::{EditorItem, MemberItem},
+        properties::{PropertyItem, PropertyMigrationItem},
+        proposals::{
+            ProposalCancellationItem, ProposalExecutionItem, ProposalItem, ProposalPreviewItem,
+            ProposalSnapshotItem,
+        },
+        relations::{SetRelationItem, UnsetRelationItem, UpdateRelationItem},
+        spaces::{GovernancePluginsItem, PersonalAdminItem, SpaceItem},
+        subspaces::SubspaceItem,
+        values::ValueOp,
+    },
+    storage::{
+        pagination::{Page, PageRequest},
+        StorageBackend, StorageError,
+    },
+};
+
+/// Which calls to a [`FaultyStorage`] fail.
+#[derive(Clone, Debug, Default)]
+pub struct StorageFaults {
+    /// The rate at which calls fail, from 0 for never to 1 for always.
+    pub error_rate: f64,
+    /// The names of the methods that fail, like `insert_spaces`, or every
+    /// method if empty.
+    pub methods: Vec<&'static str>,
+}
+
+impl StorageFaults {
+    /// Calls to the given methods fail every time.
+    pub fn always(methods: impl IntoIterator<Item = &'static str>) -> Self {
+        StorageFaults {
+            error_rate: 1.0,
+            methods: methods.into_iter().collect(),
+        }
+    }
+}
+
+/// Storage that fails some of its calls with a database error before they
+/// reach the storage it wraps, so tests can check how the handlers and the
+/// processed blocks ledger cope with a failing database. Failures are drawn
+/// from a seeded generator, so a run can be reproduced from its seed.
+pub struct FaultyStorage<S> {
+    inner: S,
+    faults: Mutex<StorageFaults>,
+    injector: FaultInjector,
+}
+
+impl<S: StorageBackend> FaultyStorage<S> {
+    pub fn new(inner: S, faults: StorageFaults, seed: u64) -> Self {
+        Self {
+            inner,
+            faults: Mutex::new(faults),
+            injector: FaultInjector::new(seed),
+        }
+    }
+
+    /// The storage calls are passed on to.
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// Changes which calls fail from now on, like `StorageFaults::default()`
+    /// for none.
+    pub fn set_faults(&self, faults: StorageFaults) {
+        *self.faults.lock().unwrap() = faults;
+    }
+
+    /// The number of calls failed so far.
+    pub fn injected(&self) -> usize {
+        self.injector.injected()
+    }
+
+    fn fault(&self, method: &str) -> Result<(), StorageError> {
+        let faults = self.faults.lock().unwrap();
+        let applies = faults.methods.is_empty() || faults.methods.contains(&method);
+        if applies && self.injector.roll(faults.error_rate) {
+            return Err(StorageError::Database(sqlx::Error::PoolTimedOut));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S: StorageBackend> StorageBackend for FaultyStorage<S> {
+    fn get_pool(&self) -> &sqlx::Pool<Postgres> {
+        self.inner.get_pool()
+    }
+
+    async fn insert_entities(
+        &self,
+        entities: &Vec<EntityItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_entities")?;
+        self.inner.insert_entities(entities, tx).await
+    }
+
+    async fn insert_values(
+        &self,
+        properties: &Vec<ValueOp>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_values")?;
+        self.inner.insert_values(properties, tx).await
+    }
+
+    async fn delete_values(
+        &self,
+        value_ids: &Vec<Uuid>,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("delete_values")?;
+        self.inner.delete_values(value_ids, space_id, tx).await
+    }
+
+    async fn insert_relations(
+        &self,
+        relations: &Vec<SetRelationItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_relations")?;
+        self.inner.insert_relations(relations, tx).await
+    }
+
+    async fn update_relations(
+        &self,
+        relations: &Vec<UpdateRelationItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("update_relations")?;
+        self.inner.update_relations(relations, tx).await
+    }
+
+    async fn unset_relation_fields(
+        &self,
+        relations: &Vec<UnsetRelationItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("unset_relation_fields")?;
+        self.inner.unset_relation_fields(relations, tx).await
+    }
+
+    async fn delete_relations(
+        &self,
+        relation_ids: &Vec<Uuid>,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("delete_relations")?;
+        self.inner
+            .delete_relations(relation_ids, space_id, tx)
+            .await
+    }
+
+    async fn lock_entities(
+        &self,
+        entity_ids: &[Uuid],
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("lock_entities")?;
+        self.inner
+            .lock_entities(entity_ids, edited, space_id, tx)
+            .await
+    }
+
+    async fn record_edited_entities(
+        &self,
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("record_edited_entities")?;
+        self.inner
+            .record_edited_entities(edited, space_id, block, tx)
+            .await
+    }
+
+    async fn delete_entities(
+        &self,
+        entity_ids: &[Uuid],
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("delete_entities")?;
+        self.inner.delete_entities(entity_ids, space_id, tx).await
+    }
+
+    async fn insert_properties(
+        &self,
+        properties: &Vec<PropertyItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_properties")?;
+        self.inner.insert_properties(properties, tx).await
+    }
+
+    async fn migrate_property(
+        &self,
+        property: &PropertyItem,
+        space_id: &Uuid,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<PropertyMigrationItem>, StorageError> {
+        self.fault("migrate_property")?;
+        self.inner
+            .migrate_property(property, space_id, block, tx)
+            .await
+    }
+
+    async fn update_property_metadata(
+        &self,
+        property_ids: &[Uuid],
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("update_property_metadata")?;
+        self.inner
+            .update_property_metadata(property_ids, space_id, tx)
+            .await
+    }
+
+    async fn update_space_metadata(
+        &self,
+        space_id: &Uuid,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("update_space_metadata")?;
+        self.inner.update_space_metadata(space_id, tx).await
+    }
+
+    async fn insert_spaces(
+        &self,
+        spaces: &Vec<SpaceItem>,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_spaces")?;
+        self.inner.insert_spaces(spaces, block, tx).await
+    }
+
+    async fn find_unknown_spaces(
+        &self,
+        space_ids: &[Uuid],
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<Uuid>, StorageError> {
+        self.fault("find_unknown_spaces")?;
+        self.inner.find_unknown_spaces(space_ids, tx).await
+    }
+
+    async fn insert_members(
+        &self,
+        members: &Vec<MemberItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_members")?;
+        self.inner.insert_members(members, tx).await
+    }
+
+    async fn remove_members(
+        &self,
+        members: &Vec<MemberItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("remove_members")?;
+        self.inner.remove_members(members, tx).await
+    }
+
+    async fn insert_editors(
+        &self,
+        editors: &Vec<EditorItem>,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_editors")?;
+        self.inner.insert_editors(editors, block, tx).await
+    }
+
+    async fn remove_editors(
+        &self,
+        editors: &Vec<EditorItem>,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("remove_editors")?;
+        self.inner.remove_editors(editors, block, tx).await
+    }
+
+    async fn insert_subspaces(
+        &self,
+        subspaces: &Vec<SubspaceItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_subspaces")?;
+        self.inner.insert_subspaces(subspaces, tx).await
+    }
+
+    async fn remove_subspaces(
+        &self,
+        subspaces: &Vec<SubspaceItem>,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("remove_subspaces")?;
+        self.inner.remove_subspaces(subspaces, tx).await
+    }
+
+    async fn change_personal_admins(
+        &self,
+        admins: &[PersonalAdminItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("change_personal_admins")?;
+        self.inner.change_personal_admins(admins, block, tx).await
+    }
+
+    async fn change_governance_plugins(
+        &self,
+        plugins: &[GovernancePluginsItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("change_governance_plugins")?;
+        self.inner
+            .change_governance_plugins(plugins, block, tx)
+            .await
+    }
+
+    async fn record_edit(
+        &self,
+        space_id: &Uuid,
+        content_hash: &str,
+        cid: &str,
+        edit_id: Option<Uuid>,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, StorageError> {
+        self.fault("record_edit")?;
+        self.inner
+            .record_edit(space_id, content_hash, cid, edit_id, block, tx)
+            .await
+    }
+
+    async fn record_activity(
+        &self,
+        activity: &BlockActivity,
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("record_activity")?;
+        self.inner.record_activity(activity, block, tx).await
+    }
+
+    async fn append_ops_journal(
+        &self,
+        entries: &[JournalEntry],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("append_ops_journal")?;
+        self.inner.append_ops_journal(entries, block, tx).await
+    }
+
+    async fn insert_proposal_previews(
+        &self,
+        previews: &[ProposalPreviewItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_proposal_previews")?;
+        self.inner
+            .insert_proposal_previews(previews, block, tx)
+            .await
+    }
+
+    async fn snapshot_proposal_electorates(
+        &self,
+        proposals: &[ProposalSnapshotItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("snapshot_proposal_electorates")?;
+        self.inner
+            .snapshot_proposal_electorates(proposals, block, tx)
+            .await
+    }
+
+    async fn insert_proposals(
+        &self,
+        proposals: &[ProposalItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("insert_proposals")?;
+        self.inner.insert_proposals(proposals, block, tx).await
+    }
+
+    async fn execute_proposals(
+        &self,
+        proposals: &[ProposalExecutionItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("execute_proposals")?;
+        self.inner.execute_proposals(proposals, block, tx).await
+    }
+
+    async fn cancel_proposals(
+        &self,
+        proposals: &[ProposalCancellationItem],
+        block: &BlockMetadata,
+        tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.fault("cancel_proposals")?;
+        self.inner.cancel_proposals(proposals, block, tx).await
+    }
+
+    async fn is_block_processed(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<bool, StorageError> {
+        self.fault("is_block_processed")?;
+        self.inner.is_block_processed(indexer_id, block).await
+    }
+
+    async fn record_processed_block(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<(), StorageError> {
+        self.fault("record_processed_block")?;
+        self.inner.record_processed_block(indexer_id, block).await
+    }
+
+    async fn get_relations_by_type(
+        &self,
+        space_id: &Uuid,
+        type_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        self.fault("get_relations_by_type")?;
+        self.inner
+            .get_relations_by_type(space_id, type_id, page)
+            .await
+    }
+
+    async fn get_outgoing_relations(
+        &self,
+        entity_id: &Uuid,
+        space_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        self.fault("get_outgoing_relations")?;
+        self.inner
+            .get_outgoing_relations(entity_id, space_id, page)
+            .await
+    }
+}
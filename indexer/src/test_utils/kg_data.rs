@@ -0,0 +1,242 @@
+use indexer_utils::network_ids::Network;
+use stream::utils::BlockMetadata;
+
+use crate::{
+    cache::PreprocessedEdit, AddedMember, AddedSubspace, CanceledProposal,
+    ChangedGovernancePlugins, ChangedPersonalAdmin, CreatedProposal, CreatedSpace,
+    ExecutedProposal, KgData, PersonalSpace, PublicSpace, RemovedMember, RemovedSubspace,
+};
+
+/// The timestamp of built blocks unless another one is set.
+pub const DEFAULT_TIMESTAMP: &str = "1234567890";
+
+/// Builds the [`KgData`] of a block for tests. Everything the block doesn't
+/// set is empty, the block's cursor is its number and its network is the
+/// default network.
+pub struct KgDataBuilder {
+    data: KgData,
+}
+
+impl KgDataBuilder {
+    pub fn new(block_number: u64) -> Self {
+        Self {
+            data: KgData {
+                block: BlockMetadata {
+                    cursor: block_number.to_string(),
+                    block_number,
+                    timestamp: DEFAULT_TIMESTAMP.to_string(),
+                },
+                edits: vec![],
+                added_editors: vec![],
+                removed_editors: vec![],
+                added_members: vec![],
+                removed_members: vec![],
+                added_subspaces: vec![],
+                removed_subspaces: vec![],
+                personal_admin_changes: vec![],
+                governance_plugin_changes: vec![],
+                created_proposals: vec![],
+                executed_proposals: vec![],
+                canceled_proposals: vec![],
+                spaces: vec![],
+                network: Network::default(),
+                unowned_properties: vec![],
+            },
+        }
+    }
+
+    pub fn timestamp(mut self, timestamp: impl Into<String>) -> Self {
+        self.data.block.timestamp = timestamp.into();
+        self
+    }
+
+    pub fn network(mut self, network: Network) -> Self {
+        self.data.network = network;
+        self
+    }
+
+    pub fn edit(mut self, edit: PreprocessedEdit) -> Self {
+        self.data.edits.push(edit);
+        self
+    }
+
+    pub fn edits(mut self, edits: impl IntoIterator<Item = PreprocessedEdit>) -> Self {
+        self.data.edits.extend(edits);
+        self
+    }
+
+    pub fn space(mut self, space: CreatedSpace) -> Self {
+        self.data.spaces.push(space);
+        self
+    }
+
+    pub fn spaces(mut self, spaces: impl IntoIterator<Item = CreatedSpace>) -> Self {
+        self.data.spaces.extend(spaces);
+        self
+    }
+
+    /// Creates a public space for the DAO, with plugin addresses derived
+    /// from the DAO's address.
+    pub fn public_space(self, dao_address: &str) -> Self {
+        self.space(CreatedSpace::Public(PublicSpace {
+            dao_address: dao_address.to_string(),
+            space_address: format!("{}_space", dao_address),
+            membership_plugin: format!("{}_membership_plugin", dao_address),
+            governance_plugin: format!("{}_governance_plugin", dao_address),
+            creator: None,
+            tx_hash: None,
+        }))
+    }
+
+    /// Creates a personal space for the DAO, with plugin addresses derived
+    /// from the DAO's address.
+    pub fn personal_space(self, dao_address: &str) -> Self {
+        self.space(CreatedSpace::Personal(PersonalSpace {
+            dao_address: dao_address.to_string(),
+            space_address: format!("{}_space", dao_address),
+            personal_plugin: format!("{}_personal_plugin", dao_address),
+            creator: None,
+            tx_hash: None,
+        }))
+    }
+
+    pub fn added_member(mut self, dao_address: &str, editor_address: &str) -> Self {
+        self.data
+            .added_members
+            .push(member(dao_address, editor_address));
+        self
+    }
+
+    pub fn added_members(mut self, members: impl IntoIterator<Item = AddedMember>) -> Self {
+        self.data.added_members.extend(members);
+        self
+    }
+
+    pub fn removed_member(mut self, dao_address: &str, editor_address: &str) -> Self {
+        self.data
+            .removed_members
+            .push(removed_member(dao_address, editor_address));
+        self
+    }
+
+    pub fn removed_members(mut self, members: impl IntoIterator<Item = RemovedMember>) -> Self {
+        self.data.removed_members.extend(members);
+        self
+    }
+
+    pub fn added_editor(mut self, dao_address: &str, editor_address: &str) -> Self {
+        self.data
+            .added_editors
+            .push(member(dao_address, editor_address));
+        self
+    }
+
+    pub fn added_editors(mut self, editors: impl IntoIterator<Item = AddedMember>) -> Self {
+        self.data.added_editors.extend(editors);
+        self
+    }
+
+    pub fn removed_editor(mut self, dao_address: &str, editor_address: &str) -> Self {
+        self.data
+            .removed_editors
+            .push(removed_member(dao_address, editor_address));
+        self
+    }
+
+    pub fn removed_editors(mut self, editors: impl IntoIterator<Item = RemovedMember>) -> Self {
+        self.data.removed_editors.extend(editors);
+        self
+    }
+
+    pub fn added_subspace(mut self, dao_address: &str, subspace_address: &str) -> Self {
+        self.data.added_subspaces.push(AddedSubspace {
+            dao_address: dao_address.to_string(),
+            subspace_address: subspace_address.to_string(),
+        });
+        self
+    }
+
+    pub fn added_subspaces(mut self, subspaces: impl IntoIterator<Item = AddedSubspace>) -> Self {
+        self.data.added_subspaces.extend(subspaces);
+        self
+    }
+
+    pub fn removed_subspace(mut self, dao_address: &str, subspace_address: &str) -> Self {
+        self.data.removed_subspaces.push(RemovedSubspace {
+            dao_address: dao_address.to_string(),
+            subspace_address: subspace_address.to_string(),
+        });
+        self
+    }
+
+    pub fn removed_subspaces(
+        mut self,
+        subspaces: impl IntoIterator<Item = RemovedSubspace>,
+    ) -> Self {
+        self.data.removed_subspaces.extend(subspaces);
+        self
+    }
+
+    pub fn personal_admin_change(
+        mut self,
+        dao_address: &str,
+        personal_plugin: &str,
+        admin: Option<&str>,
+    ) -> Self {
+        self.data.personal_admin_changes.push(ChangedPersonalAdmin {
+            dao_address: dao_address.to_string(),
+            personal_plugin: personal_plugin.to_string(),
+            admin: admin.map(str::to_string),
+        });
+        self
+    }
+
+    pub fn governance_plugin_change(
+        mut self,
+        dao_address: &str,
+        main_voting_plugin: &str,
+        membership_plugin: &str,
+    ) -> Self {
+        self.data
+            .governance_plugin_changes
+            .push(ChangedGovernancePlugins {
+                dao_address: dao_address.to_string(),
+                main_voting_plugin: main_voting_plugin.to_string(),
+                membership_plugin: membership_plugin.to_string(),
+            });
+        self
+    }
+
+    pub fn created_proposal(mut self, proposal: CreatedProposal) -> Self {
+        self.data.created_proposals.push(proposal);
+        self
+    }
+
+    pub fn executed_proposal(mut self, proposal: ExecutedProposal) -> Self {
+        self.data.executed_proposals.push(proposal);
+        self
+    }
+
+    pub fn canceled_proposal(mut self, proposal: CanceledProposal) -> Self {
+        self.data.canceled_proposals.push(proposal);
+        self
+    }
+
+    pub fn build(self) -> KgData {
+        self.data
+    }
+}
+
+fn member(dao_address: &str, editor_address: &str) -> AddedMember {
+    AddedMember {
+        dao_address: dao_address.to_string(),
+        editor_address: editor_address.to_string(),
+    }
+}
+
+fn removed_member(dao_address: &str, editor_address: &str) -> RemovedMember {
+    RemovedMember {
+        dao_address: dao_address.to_string(),
+        editor_address: editor_address.to_string(),
+    }
+}
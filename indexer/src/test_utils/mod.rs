@@ -1,3 +1,17 @@
+#[cfg(feature = "faults")]
+pub mod faulty_storage;
+pub mod kg_data;
+pub mod recording_storage;
+pub mod scenario;
+#[cfg(feature = "testcontainers")]
+pub mod test_database;
 pub mod test_storage;
 
+#[cfg(feature = "faults")]
+pub use faulty_storage::*;
+pub use kg_data::*;
+pub use recording_storage::*;
+pub use scenario::*;
+#[cfg(feature = "testcontainers")]
+pub use test_database::*;
 pub use test_storage::*;
\ No newline at end of file
@@ -0,0 +1,596 @@
+use async_trait::async_trait;
+use sqlx::Postgres;
+use std::{collections::HashSet, sync::Mutex};
+use stream::utils::BlockMetadata;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        analytics::BlockActivity,
+        entities::{EditedEntities, EntityItem},
+        journal::JournalEntry,
+        membership::{EditorItem, MemberItem},
+        properties::{PropertyItem, PropertyMigrationItem},
+        proposals::{
+            ProposalCancellationItem, ProposalExecutionItem, ProposalItem, ProposalPreviewItem,
+            ProposalSnapshotItem,
+        },
+        relations::{SetRelationItem, UnsetRelationItem, UpdateRelationItem},
+        spaces::{GovernancePluginsItem, PersonalAdminItem, SpaceItem},
+        subspaces::SubspaceItem,
+        values::ValueOp,
+    },
+    storage::{
+        pagination::{Page, PageRequest},
+        StorageBackend, StorageError,
+    },
+};
+
+/// A call made to a [`RecordingStorage`], with its arguments.
+#[derive(Clone)]
+pub enum StorageCall {
+    InsertEntities(Vec<EntityItem>),
+    InsertValues(Vec<ValueOp>),
+    DeleteValues(Vec<Uuid>, Uuid),
+    InsertRelations(Vec<SetRelationItem>),
+    UpdateRelations(Vec<UpdateRelationItem>),
+    UnsetRelationFields(Vec<UnsetRelationItem>),
+    DeleteRelations(Vec<Uuid>, Uuid),
+    DeleteEntities(Vec<Uuid>, Uuid),
+    LockEntities(Vec<Uuid>, EditedEntities, Uuid),
+    RecordEditedEntities(EditedEntities, Uuid),
+    InsertProperties(Vec<PropertyItem>),
+    MigrateProperty(PropertyItem, Uuid),
+    UpdatePropertyMetadata(Vec<Uuid>, Uuid),
+    UpdateSpaceMetadata(Uuid),
+    InsertSpaces(Vec<SpaceItem>),
+    FindUnknownSpaces(Vec<Uuid>),
+    InsertMembers(Vec<MemberItem>),
+    RemoveMembers(Vec<MemberItem>),
+    InsertEditors(Vec<EditorItem>),
+    RemoveEditors(Vec<EditorItem>),
+    InsertSubspaces(Vec<SubspaceItem>),
+    RemoveSubspaces(Vec<SubspaceItem>),
+    ChangePersonalAdmins(Vec<PersonalAdminItem>),
+    ChangeGovernancePlugins(Vec<GovernancePluginsItem>),
+    RecordEdit {
+        space_id: Uuid,
+        content_hash: String,
+        cid: String,
+        edit_id: Option<Uuid>,
+    },
+    RecordActivity(BlockActivity),
+    AppendOpsJournal(Vec<JournalEntry>),
+    InsertProposalPreviews(Vec<ProposalPreviewItem>),
+    SnapshotProposalElectorates(Vec<ProposalSnapshotItem>),
+    InsertProposals(Vec<ProposalItem>),
+    ExecuteProposals(Vec<ProposalExecutionItem>),
+    CancelProposals(Vec<ProposalCancellationItem>),
+    IsBlockProcessed(String, u64),
+    RecordProcessedBlock(String, u64),
+    GetRelationsByType(Uuid, Uuid, PageRequest),
+    GetOutgoingRelations(Uuid, Uuid, PageRequest),
+}
+
+impl StorageCall {
+    /// The name of the called method.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StorageCall::InsertEntities(_) => "insert_entities",
+            StorageCall::InsertValues(_) => "insert_values",
+            StorageCall::DeleteValues(..) => "delete_values",
+            StorageCall::InsertRelations(_) => "insert_relations",
+            StorageCall::UpdateRelations(_) => "update_relations",
+            StorageCall::UnsetRelationFields(_) => "unset_relation_fields",
+            StorageCall::DeleteRelations(..) => "delete_relations",
+            StorageCall::DeleteEntities(..) => "delete_entities",
+            StorageCall::LockEntities(..) => "lock_entities",
+            StorageCall::RecordEditedEntities(..) => "record_edited_entities",
+            StorageCall::InsertProperties(_) => "insert_properties",
+            StorageCall::MigrateProperty(..) => "migrate_property",
+            StorageCall::UpdatePropertyMetadata(..) => "update_property_metadata",
+            StorageCall::UpdateSpaceMetadata(_) => "update_space_metadata",
+            StorageCall::InsertSpaces(_) => "insert_spaces",
+            StorageCall::FindUnknownSpaces(_) => "find_unknown_spaces",
+            StorageCall::InsertMembers(_) => "insert_members",
+            StorageCall::RemoveMembers(_) => "remove_members",
+            StorageCall::InsertEditors(_) => "insert_editors",
+            StorageCall::RemoveEditors(_) => "remove_editors",
+            StorageCall::InsertSubspaces(_) => "insert_subspaces",
+            StorageCall::RemoveSubspaces(_) => "remove_subspaces",
+            StorageCall::ChangePersonalAdmins(_) => "change_personal_admins",
+            StorageCall::ChangeGovernancePlugins(_) => "change_governance_plugins",
+            StorageCall::RecordEdit { .. } => "record_edit",
+            StorageCall::RecordActivity(_) => "record_activity",
+            StorageCall::AppendOpsJournal(_) => "append_ops_journal",
+            StorageCall::InsertProposalPreviews(_) => "insert_proposal_previews",
+            StorageCall::SnapshotProposalElectorates(_) => "snapshot_proposal_electorates",
+            StorageCall::InsertProposals(_) => "insert_proposals",
+            StorageCall::ExecuteProposals(_) => "execute_proposals",
+            StorageCall::CancelProposals(_) => "cancel_proposals",
+            StorageCall::IsBlockProcessed(..) => "is_block_processed",
+            StorageCall::RecordProcessedBlock(..) => "record_processed_block",
+            StorageCall::GetRelationsByType(..) => "get_relations_by_type",
+            StorageCall::GetOutgoingRelations(..) => "get_outgoing_relations",
+        }
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    calls: Vec<StorageCall>,
+    edits: HashSet<(Uuid, String)>,
+    spaces: HashSet<Uuid>,
+    processed_blocks: HashSet<(String, u64)>,
+}
+
+/// Storage that records every call and its arguments instead of writing
+/// them, so the handlers' ordering, squashing and validation can be
+/// asserted from the calls they make.
+///
+/// The handlers still begin and commit their transactions on the pool, so
+/// it must connect to a database, but nothing is written to it and it needs
+/// no tables. Reads answer from the recorded calls: an edit's content is
+/// recorded once per space, spaces are known once they're inserted, and
+/// blocks are processed once they're recorded. Relations are never found
+/// and property migrations never apply.
+pub struct RecordingStorage {
+    pool: sqlx::Pool<Postgres>,
+    recorded: Mutex<Recorded>,
+}
+
+impl RecordingStorage {
+    pub fn new(pool: sqlx::Pool<Postgres>) -> Self {
+        Self {
+            pool,
+            recorded: Mutex::new(Recorded::default()),
+        }
+    }
+
+    /// Treats the spaces as indexed before any call.
+    pub fn with_spaces(self, space_ids: impl IntoIterator<Item = Uuid>) -> Self {
+        self.recorded.lock().unwrap().spaces.extend(space_ids);
+        self
+    }
+
+    /// The calls made so far, in the order they were made.
+    pub fn calls(&self) -> Vec<StorageCall> {
+        self.recorded.lock().unwrap().calls.clone()
+    }
+
+    /// The names of the methods called so far, in the order they were called.
+    pub fn call_names(&self) -> Vec<&'static str> {
+        self.recorded
+            .lock()
+            .unwrap()
+            .calls
+            .iter()
+            .map(StorageCall::name)
+            .collect()
+    }
+
+    /// Forgets the calls made so far, keeping the recorded edits, spaces and
+    /// blocks.
+    pub fn clear_calls(&self) {
+        self.recorded.lock().unwrap().calls.clear();
+    }
+
+    fn record(&self, call: StorageCall) {
+        self.recorded.lock().unwrap().calls.push(call);
+    }
+}
+
+#[async_trait]
+impl StorageBackend for RecordingStorage {
+    fn get_pool(&self) -> &sqlx::Pool<Postgres> {
+        &self.pool
+    }
+
+    async fn insert_entities(
+        &self,
+        entities: &Vec<EntityItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertEntities(entities.clone()));
+        Ok(())
+    }
+
+    async fn insert_values(
+        &self,
+        properties: &Vec<ValueOp>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertValues(properties.clone()));
+        Ok(())
+    }
+
+    async fn delete_values(
+        &self,
+        value_ids: &Vec<Uuid>,
+        space_id: &Uuid,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::DeleteValues(value_ids.clone(), *space_id));
+        Ok(())
+    }
+
+    async fn insert_relations(
+        &self,
+        relations: &Vec<SetRelationItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertRelations(relations.clone()));
+        Ok(())
+    }
+
+    async fn update_relations(
+        &self,
+        relations: &Vec<UpdateRelationItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::UpdateRelations(relations.clone()));
+        Ok(())
+    }
+
+    async fn unset_relation_fields(
+        &self,
+        relations: &Vec<UnsetRelationItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::UnsetRelationFields(relations.clone()));
+        Ok(())
+    }
+
+    async fn delete_relations(
+        &self,
+        relation_ids: &Vec<Uuid>,
+        space_id: &Uuid,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::DeleteRelations(
+            relation_ids.clone(),
+            *space_id,
+        ));
+        Ok(())
+    }
+
+    async fn lock_entities(
+        &self,
+        entity_ids: &[Uuid],
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::LockEntities(
+            entity_ids.to_vec(),
+            edited.clone(),
+            *space_id,
+        ));
+        Ok(())
+    }
+
+    async fn record_edited_entities(
+        &self,
+        edited: &EditedEntities,
+        space_id: &Uuid,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::RecordEditedEntities(edited.clone(), *space_id));
+        Ok(())
+    }
+
+    async fn delete_entities(
+        &self,
+        entity_ids: &[Uuid],
+        space_id: &Uuid,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::DeleteEntities(entity_ids.to_vec(), *space_id));
+        Ok(())
+    }
+
+    async fn insert_properties(
+        &self,
+        properties: &Vec<PropertyItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertProperties(properties.clone()));
+        Ok(())
+    }
+
+    async fn migrate_property(
+        &self,
+        property: &PropertyItem,
+        space_id: &Uuid,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Option<PropertyMigrationItem>, StorageError> {
+        self.record(StorageCall::MigrateProperty(property.clone(), *space_id));
+        Ok(None)
+    }
+
+    async fn update_property_metadata(
+        &self,
+        property_ids: &[Uuid],
+        space_id: &Uuid,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::UpdatePropertyMetadata(
+            property_ids.to_vec(),
+            *space_id,
+        ));
+        Ok(())
+    }
+
+    async fn update_space_metadata(
+        &self,
+        space_id: &Uuid,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::UpdateSpaceMetadata(*space_id));
+        Ok(())
+    }
+
+    async fn insert_spaces(
+        &self,
+        spaces: &Vec<SpaceItem>,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        let mut recorded = self.recorded.lock().unwrap();
+        recorded.spaces.extend(spaces.iter().map(|space| space.id));
+        recorded
+            .calls
+            .push(StorageCall::InsertSpaces(spaces.clone()));
+        Ok(())
+    }
+
+    async fn find_unknown_spaces(
+        &self,
+        space_ids: &[Uuid],
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<Vec<Uuid>, StorageError> {
+        let mut recorded = self.recorded.lock().unwrap();
+        recorded
+            .calls
+            .push(StorageCall::FindUnknownSpaces(space_ids.to_vec()));
+
+        let mut unknown: Vec<Uuid> = space_ids
+            .iter()
+            .filter(|space_id| !recorded.spaces.contains(space_id))
+            .copied()
+            .collect();
+        unknown.sort();
+        unknown.dedup();
+        Ok(unknown)
+    }
+
+    async fn insert_members(
+        &self,
+        members: &Vec<MemberItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertMembers(members.clone()));
+        Ok(())
+    }
+
+    async fn remove_members(
+        &self,
+        members: &Vec<MemberItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::RemoveMembers(members.clone()));
+        Ok(())
+    }
+
+    async fn insert_editors(
+        &self,
+        editors: &Vec<EditorItem>,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertEditors(editors.clone()));
+        Ok(())
+    }
+
+    async fn remove_editors(
+        &self,
+        editors: &Vec<EditorItem>,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::RemoveEditors(editors.clone()));
+        Ok(())
+    }
+
+    async fn insert_subspaces(
+        &self,
+        subspaces: &Vec<SubspaceItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertSubspaces(subspaces.clone()));
+        Ok(())
+    }
+
+    async fn remove_subspaces(
+        &self,
+        subspaces: &Vec<SubspaceItem>,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::RemoveSubspaces(subspaces.clone()));
+        Ok(())
+    }
+
+    async fn change_personal_admins(
+        &self,
+        admins: &[PersonalAdminItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::ChangePersonalAdmins(admins.to_vec()));
+        Ok(())
+    }
+
+    async fn change_governance_plugins(
+        &self,
+        plugins: &[GovernancePluginsItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::ChangeGovernancePlugins(plugins.to_vec()));
+        Ok(())
+    }
+
+    async fn record_edit(
+        &self,
+        space_id: &Uuid,
+        content_hash: &str,
+        cid: &str,
+        edit_id: Option<Uuid>,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<bool, StorageError> {
+        let mut recorded = self.recorded.lock().unwrap();
+        recorded.calls.push(StorageCall::RecordEdit {
+            space_id: *space_id,
+            content_hash: content_hash.to_string(),
+            cid: cid.to_string(),
+            edit_id,
+        });
+
+        Ok(recorded.edits.insert((*space_id, content_hash.to_string())))
+    }
+
+    async fn record_activity(
+        &self,
+        activity: &BlockActivity,
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::RecordActivity(activity.clone()));
+        Ok(())
+    }
+
+    async fn append_ops_journal(
+        &self,
+        entries: &[JournalEntry],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::AppendOpsJournal(entries.to_vec()));
+        Ok(())
+    }
+
+    async fn insert_proposal_previews(
+        &self,
+        previews: &[ProposalPreviewItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertProposalPreviews(previews.to_vec()));
+        Ok(())
+    }
+
+    async fn snapshot_proposal_electorates(
+        &self,
+        proposals: &[ProposalSnapshotItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::SnapshotProposalElectorates(proposals.to_vec()));
+        Ok(())
+    }
+
+    async fn insert_proposals(
+        &self,
+        proposals: &[ProposalItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::InsertProposals(proposals.to_vec()));
+        Ok(())
+    }
+
+    async fn execute_proposals(
+        &self,
+        proposals: &[ProposalExecutionItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::ExecuteProposals(proposals.to_vec()));
+        Ok(())
+    }
+
+    async fn cancel_proposals(
+        &self,
+        proposals: &[ProposalCancellationItem],
+        _block: &BlockMetadata,
+        _tx: &mut sqlx::Transaction<'_, sqlx::Postgres>,
+    ) -> Result<(), StorageError> {
+        self.record(StorageCall::CancelProposals(proposals.to_vec()));
+        Ok(())
+    }
+
+    async fn is_block_processed(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<bool, StorageError> {
+        let mut recorded = self.recorded.lock().unwrap();
+        let key = (indexer_id.to_string(), block.block_number);
+        recorded
+            .calls
+            .push(StorageCall::IsBlockProcessed(key.0.clone(), key.1));
+
+        Ok(recorded.processed_blocks.contains(&key))
+    }
+
+    async fn record_processed_block(
+        &self,
+        indexer_id: &str,
+        block: &BlockMetadata,
+    ) -> Result<(), StorageError> {
+        let mut recorded = self.recorded.lock().unwrap();
+        let key = (indexer_id.to_string(), block.block_number);
+        recorded
+            .calls
+            .push(StorageCall::RecordProcessedBlock(key.0.clone(), key.1));
+        recorded.processed_blocks.insert(key);
+        Ok(())
+    }
+
+    async fn get_relations_by_type(
+        &self,
+        space_id: &Uuid,
+        type_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        self.record(StorageCall::GetRelationsByType(
+            *space_id,
+            *type_id,
+            page.clone(),
+        ));
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    }
+
+    async fn get_outgoing_relations(
+        &self,
+        entity_id: &Uuid,
+        space_id: &Uuid,
+        page: &PageRequest,
+    ) -> Result<Page<SetRelationItem>, StorageError> {
+        self.record(StorageCall::GetOutgoingRelations(
+            *entity_id,
+            *space_id,
+            page.clone(),
+        ));
+        Ok(Page {
+            items: vec![],
+            next_cursor: None,
+        })
+    }
+}
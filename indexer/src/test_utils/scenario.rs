@@ -0,0 +1,628 @@
+//! Table-driven scenarios for integration tests, read from YAML files.
+//!
+//! A scenario lists the blocks to index, each with the spaces it creates,
+//! the edits it publishes and its membership and subspace events, and what
+//! the knowledge graph should hold once they're indexed:
+//!
+//! ```yaml
+//! name: member joins a space
+//! blocks:
+//!   - number: 1
+//!     spaces:
+//!       - public: "0x5a0b54d5dc17e0aadc383d2db43b0a0d3e029c4c"
+//!     edits:
+//!       - space: "0x5a0b54d5dc17e0aadc383d2db43b0a0d3e029c4c"
+//!         cid: ipfs://scenario
+//!         id: 6b1f5c8e-7d44-4a5c-9e3f-1d2c3b4a5f60
+//!         ops:
+//!           - create_property: { id: a126ca53-0c8e-48d5-b888-82c734c38935, data_type: text }
+//!           - update_entity:
+//!               id: 3a3b2e0c-5c0f-4a47-9a3e-0f4d8e2b1c7d
+//!               values: { a126ca53-0c8e-48d5-b888-82c734c38935: Scenario }
+//!     added_members:
+//!       - { dao: "0x5a0b54d5dc17e0aadc383d2db43b0a0d3e029c4c", address: "0x9e1f1a56b5c2cb8a2bb6b2a7b3f0c58e9f5d6a1b" }
+//! expect:
+//!   members:
+//!     - { dao: "0x5a0b54d5dc17e0aadc383d2db43b0a0d3e029c4c", address: "0x9e1f1a56b5c2cb8a2bb6b2a7b3f0c58e9f5d6a1b" }
+//!   values:
+//!     - space: "0x5a0b54d5dc17e0aadc383d2db43b0a0d3e029c4c"
+//!       entity: 3a3b2e0c-5c0f-4a47-9a3e-0f4d8e2b1c7d
+//!       property: a126ca53-0c8e-48d5-b888-82c734c38935
+//!       value: Scenario
+//! ```
+//!
+//! Spaces are named by their DAO's address everywhere, and their ids are
+//! derived on the scenario's network.
+//!
+//! Besides its expectations, a scenario can be checked against a golden
+//! snapshot of everything indexed into its spaces, kept in `snapshots/` next
+//! to the scenario as JSON, so changes to what the handlers write show up in
+//! review. Set `UPDATE_SNAPSHOTS=1` to write the snapshots instead of
+//! checking them.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use indexer_utils::{checksum_address, id, network_ids::Network};
+use serde::Deserialize;
+use serde_json::Value as Json;
+use thiserror::Error;
+use uuid::Uuid;
+use wire::{
+    builder::{relation, value, EditBuilder},
+    pb::grc20::DataType,
+};
+
+use super::KgDataBuilder;
+use crate::{
+    cache::PreprocessedEdit, error::IndexingError, storage::postgres::PostgresStorage, KgData,
+};
+
+#[derive(Error, Debug)]
+pub enum ScenarioError {
+    #[error("Couldn't read scenario {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("Invalid scenario {path}: {source}")]
+    Yaml {
+        path: PathBuf,
+        source: serde_yaml::Error,
+    },
+
+    #[error("Invalid scenario {name}: {reason}")]
+    Invalid { name: String, reason: String },
+
+    #[error("Invalid snapshot {path}: {source}")]
+    Snapshot {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+/// The tables in a snapshot, with the query for their rows. `$1` is the ids
+/// of the scenario's spaces and `$2` the ids of the properties it creates.
+/// Columns the database fills in, like serial ids, are left out.
+const SNAPSHOT_TABLES: [(&str, &str); 10] = [
+    (
+        "spaces",
+        "SELECT id, type::text, dao_address, space_address, main_voting_address,
+            membership_address, personal_address, entity_id, name, description, cover
+        FROM spaces WHERE id = ANY($1)",
+    ),
+    (
+        "members",
+        "SELECT space_id, address FROM members WHERE space_id = ANY($1)",
+    ),
+    (
+        "editors",
+        "SELECT space_id, address FROM editors WHERE space_id = ANY($1)",
+    ),
+    (
+        "editor_history",
+        "SELECT space_id, address, added_at_block, removed_at_block
+        FROM editor_history WHERE space_id = ANY($1)",
+    ),
+    (
+        "subspaces",
+        "SELECT parent_space_id, child_space_id FROM subspaces WHERE parent_space_id = ANY($1)",
+    ),
+    (
+        "edits",
+        "SELECT space_id, content_hash, cid, edit_id, created_at, created_at_block
+        FROM edits WHERE space_id = ANY($1)",
+    ),
+    (
+        "properties",
+        "SELECT id, type::text, name, description, renderable_type
+        FROM properties WHERE id = ANY($2)",
+    ),
+    (
+        "entities",
+        "SELECT id, created_at, created_at_block, updated_at, updated_at_block
+        FROM entities WHERE id IN (
+            SELECT entity_id FROM values WHERE space_id = ANY($1)
+            UNION SELECT from_entity_id FROM relations WHERE space_id = ANY($1)
+            UNION SELECT to_entity_id FROM relations WHERE space_id = ANY($1)
+        )",
+    ),
+    (
+        "values",
+        "SELECT id, space_id, entity_id, property_id, string, boolean, number::text AS number,
+            point, time, language, unit, options, edit_id
+        FROM values WHERE space_id = ANY($1)",
+    ),
+    (
+        "relations",
+        "SELECT id, space_id, entity_id, type_id, from_entity_id, from_space_id,
+            to_entity_id, to_space_id, position, verified, edit_id
+        FROM relations WHERE space_id = ANY($1)",
+    ),
+];
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Scenario {
+    pub name: String,
+    /// The file the scenario was loaded from.
+    #[serde(skip)]
+    pub path: PathBuf,
+    /// The network space ids are derived on, the default network if unset.
+    #[serde(default)]
+    pub network: Option<String>,
+    /// Spaces and ops are written as single-key maps, like
+    /// `public: "0x..."`, rather than with YAML tags.
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub blocks: Vec<ScenarioBlock>,
+    #[serde(default)]
+    pub expect: ScenarioExpectations,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioBlock {
+    pub number: u64,
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub spaces: Vec<ScenarioSpace>,
+    #[serde(default)]
+    pub edits: Vec<ScenarioEdit>,
+    #[serde(default)]
+    pub added_members: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub removed_members: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub added_editors: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub removed_editors: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub added_subspaces: Vec<ScenarioSubspace>,
+    #[serde(default)]
+    pub removed_subspaces: Vec<ScenarioSubspace>,
+}
+
+/// A space created for a DAO.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioSpace {
+    Public(String),
+    Personal(String),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioEdit {
+    /// The DAO of the space the edit is published to.
+    pub space: String,
+    pub cid: String,
+    pub id: Uuid,
+    /// Whether the edit's content couldn't be decoded, in which case its
+    /// ops are ignored.
+    #[serde(default)]
+    pub errored: bool,
+    #[serde(default)]
+    pub ops: Vec<ScenarioOp>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum ScenarioOp {
+    CreateProperty {
+        id: Uuid,
+        data_type: String,
+    },
+    UpdateEntity {
+        id: Uuid,
+        /// The values to set, by property.
+        values: BTreeMap<Uuid, String>,
+    },
+    UnsetEntityValues {
+        id: Uuid,
+        properties: Vec<Uuid>,
+    },
+    DeleteEntity(Uuid),
+    CreateRelation {
+        id: Uuid,
+        entity: Uuid,
+        #[serde(rename = "type")]
+        relation_type: Uuid,
+        from: Uuid,
+        to: Uuid,
+    },
+    DeleteRelation(Uuid),
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioMember {
+    pub dao: String,
+    pub address: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioSubspace {
+    pub dao: String,
+    pub subspace: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioValue {
+    pub space: String,
+    pub entity: Uuid,
+    pub property: Uuid,
+    /// The value as it was written in the edit.
+    pub value: String,
+}
+
+/// What the knowledge graph holds once the blocks are indexed. Anything not
+/// listed isn't checked.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioExpectations {
+    /// The DAOs with a space.
+    #[serde(default)]
+    pub spaces: Vec<String>,
+    #[serde(default)]
+    pub members: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub absent_members: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub editors: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub absent_editors: Vec<ScenarioMember>,
+    #[serde(default)]
+    pub subspaces: Vec<ScenarioSubspace>,
+    #[serde(default)]
+    pub absent_subspaces: Vec<ScenarioSubspace>,
+    #[serde(default)]
+    pub values: Vec<ScenarioValue>,
+}
+
+impl Scenario {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ScenarioError> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|source| ScenarioError::Io {
+            path: path.to_path_buf(),
+            source,
+        })?;
+
+        let mut scenario: Scenario =
+            serde_yaml::from_str(&contents).map_err(|source| ScenarioError::Yaml {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        scenario.path = path.to_path_buf();
+
+        Ok(scenario)
+    }
+
+    /// Loads every `.yaml` file in the directory, in file name order.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Vec<Self>, ScenarioError> {
+        let dir = dir.as_ref();
+        let io_error = |source| ScenarioError::Io {
+            path: dir.to_path_buf(),
+            source,
+        };
+
+        let mut paths = Vec::new();
+        for entry in fs::read_dir(dir).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "yaml")
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        paths.iter().map(Self::load).collect()
+    }
+
+    pub fn network(&self) -> Network {
+        match &self.network {
+            Some(network) => Network::new(network.clone(), None),
+            None => Network::default(),
+        }
+    }
+
+    /// The id of the DAO's space on the scenario's network.
+    pub fn space_id(&self, dao_address: &str) -> Uuid {
+        self.network().space_id(&checksum_address(dao_address))
+    }
+
+    /// The blocks to index, in order.
+    pub fn blocks(&self) -> Result<Vec<KgData>, ScenarioError> {
+        self.blocks
+            .iter()
+            .map(|block| {
+                let mut builder = KgDataBuilder::new(block.number).network(self.network());
+                if let Some(timestamp) = &block.timestamp {
+                    builder = builder.timestamp(timestamp);
+                }
+
+                for space in &block.spaces {
+                    builder = match space {
+                        ScenarioSpace::Public(dao) => builder.public_space(dao),
+                        ScenarioSpace::Personal(dao) => builder.personal_space(dao),
+                    };
+                }
+                for edit in &block.edits {
+                    builder = builder.edit(self.edit(edit)?);
+                }
+                for member in &block.added_members {
+                    builder = builder.added_member(&member.dao, &member.address);
+                }
+                for member in &block.removed_members {
+                    builder = builder.removed_member(&member.dao, &member.address);
+                }
+                for editor in &block.added_editors {
+                    builder = builder.added_editor(&editor.dao, &editor.address);
+                }
+                for editor in &block.removed_editors {
+                    builder = builder.removed_editor(&editor.dao, &editor.address);
+                }
+                for subspace in &block.added_subspaces {
+                    builder = builder.added_subspace(&subspace.dao, &subspace.subspace);
+                }
+                for subspace in &block.removed_subspaces {
+                    builder = builder.removed_subspace(&subspace.dao, &subspace.subspace);
+                }
+
+                Ok(builder.build())
+            })
+            .collect()
+    }
+
+    fn edit(&self, edit: &ScenarioEdit) -> Result<PreprocessedEdit, ScenarioError> {
+        let mut builder = EditBuilder::new(edit.id);
+        for op in &edit.ops {
+            builder = match op {
+                ScenarioOp::CreateProperty { id, data_type } => {
+                    let data_type =
+                        DataType::from_str_name(&data_type.to_uppercase()).ok_or_else(|| {
+                            ScenarioError::Invalid {
+                                name: self.name.clone(),
+                                reason: format!("unknown data type '{}'", data_type),
+                            }
+                        })?;
+                    builder.create_property(*id, data_type)
+                }
+                ScenarioOp::UpdateEntity { id, values } => builder.update_entity(
+                    *id,
+                    values
+                        .iter()
+                        .map(|(property, raw)| value(*property, raw.clone())),
+                ),
+                ScenarioOp::UnsetEntityValues { id, properties } => {
+                    builder.unset_entity_values(*id, properties.iter().copied())
+                }
+                ScenarioOp::DeleteEntity(id) => builder.delete_entity(*id),
+                ScenarioOp::CreateRelation {
+                    id,
+                    entity,
+                    relation_type,
+                    from,
+                    to,
+                } => builder.create_relation(relation(*id, *entity, *relation_type, *from, *to)),
+                ScenarioOp::DeleteRelation(id) => builder.delete_relation(*id),
+            };
+        }
+
+        Ok(PreprocessedEdit {
+            cid: edit.cid.clone(),
+            edit: (!edit.errored).then(|| builder.build()),
+            is_errored: edit.errored,
+            space_id: self.space_id(&edit.space),
+            sender: None,
+            event_index: 0,
+        })
+    }
+
+    /// Checks the expectations against the storage, and returns the ones
+    /// that don't hold.
+    pub async fn verify(&self, storage: &PostgresStorage) -> Result<Vec<String>, IndexingError> {
+        let expect = &self.expect;
+        let mut failures = Vec::new();
+
+        for dao in &expect.spaces {
+            let exists: bool =
+                sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM spaces WHERE id = $1)")
+                    .bind(self.space_id(dao))
+                    .fetch_one(&storage.pool)
+                    .await?;
+            if !exists {
+                failures.push(format!("{} has no space", dao));
+            }
+        }
+
+        for (members, table, present) in [
+            (&expect.members, "members", true),
+            (&expect.absent_members, "members", false),
+            (&expect.editors, "editors", true),
+            (&expect.absent_editors, "editors", false),
+        ] {
+            for member in members {
+                let exists: bool = sqlx::query_scalar(&format!(
+                    "SELECT EXISTS (SELECT 1 FROM {} WHERE address = $1 AND space_id = $2)",
+                    table
+                ))
+                .bind(checksum_address(&member.address))
+                .bind(self.space_id(&member.dao))
+                .fetch_one(&storage.pool)
+                .await?;
+                if exists != present {
+                    failures.push(format!(
+                        "{} is {}in the {} of {}",
+                        member.address,
+                        if present { "not " } else { "" },
+                        table,
+                        member.dao
+                    ));
+                }
+            }
+        }
+
+        for (subspaces, present) in [(&expect.subspaces, true), (&expect.absent_subspaces, false)] {
+            for subspace in subspaces {
+                let exists: bool = sqlx::query_scalar(
+                    "SELECT EXISTS (
+                        SELECT 1 FROM subspaces WHERE parent_space_id = $1 AND child_space_id = $2
+                    )",
+                )
+                .bind(self.space_id(&subspace.dao))
+                .bind(self.space_id(&subspace.subspace))
+                .fetch_one(&storage.pool)
+                .await?;
+                if exists != present {
+                    failures.push(format!(
+                        "{} is {}a subspace of {}",
+                        subspace.subspace,
+                        if present { "not " } else { "" },
+                        subspace.dao
+                    ));
+                }
+            }
+        }
+
+        for expected in &expect.values {
+            let value_id = id::derive_value_id(
+                &expected.entity,
+                &expected.property,
+                &self.space_id(&expected.space),
+            );
+            match storage.get_value(&value_id.to_string()).await {
+                Ok(value) if value.raw_value().as_deref() == Some(expected.value.as_str()) => {}
+                Ok(value) => failures.push(format!(
+                    "the value of {} for {} is {:?}, not {:?}",
+                    expected.entity,
+                    expected.property,
+                    value.raw_value(),
+                    expected.value
+                )),
+                Err(_) => failures.push(format!(
+                    "{} has no value for {}",
+                    expected.entity, expected.property
+                )),
+            }
+        }
+
+        Ok(failures)
+    }
+
+    /// Everything indexed into the spaces the scenario creates, and the
+    /// properties it creates, as a JSON object of rows by table. Rows are in
+    /// a canonical order, so equal states have equal snapshots.
+    pub async fn snapshot(&self, storage: &PostgresStorage) -> Result<Json, IndexingError> {
+        let space_ids: Vec<Uuid> = self
+            .blocks
+            .iter()
+            .flat_map(|block| &block.spaces)
+            .map(|space| match space {
+                ScenarioSpace::Public(dao) | ScenarioSpace::Personal(dao) => self.space_id(dao),
+            })
+            .collect();
+        let property_ids: Vec<Uuid> = self
+            .blocks
+            .iter()
+            .flat_map(|block| &block.edits)
+            .flat_map(|edit| &edit.ops)
+            .filter_map(|op| match op {
+                ScenarioOp::CreateProperty { id, .. } => Some(*id),
+                _ => None,
+            })
+            .collect();
+
+        let mut tables = serde_json::Map::new();
+        for (table, query) in SNAPSHOT_TABLES {
+            let rows: String = sqlx::query_scalar(&format!(
+                "SELECT coalesce(jsonb_agg(to_jsonb(r) ORDER BY to_jsonb(r)::text), '[]')::text
+                FROM ({}) r",
+                query
+            ))
+            .bind(&space_ids)
+            .bind(&property_ids)
+            .fetch_one(&storage.pool)
+            .await?;
+            let rows = serde_json::from_str(&rows).expect("Postgres aggregates valid JSON");
+            tables.insert(table.to_string(), rows);
+        }
+
+        Ok(Json::Object(tables))
+    }
+
+    /// Where the scenario's golden snapshot is kept: `snapshots/` next to
+    /// the scenario, with the scenario's file name.
+    pub fn snapshot_path(&self) -> PathBuf {
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let name = self.path.file_stem().unwrap_or_default();
+        dir.join("snapshots").join(name).with_extension("json")
+    }
+
+    /// Compares a snapshot with the scenario's golden snapshot, and returns
+    /// the rows that differ: `- table: row` for rows of the golden missing
+    /// from the snapshot, and `+ table: row` for rows the golden doesn't
+    /// have. With `UPDATE_SNAPSHOTS` set, the snapshot is written as the
+    /// golden instead.
+    pub fn check_snapshot(&self, snapshot: &Json) -> Result<Vec<String>, ScenarioError> {
+        let path = self.snapshot_path();
+        let io_error = |source| ScenarioError::Io {
+            path: path.clone(),
+            source,
+        };
+        let snapshot_error = |source| ScenarioError::Snapshot {
+            path: path.clone(),
+            source,
+        };
+
+        if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            let mut contents = serde_json::to_string_pretty(snapshot).map_err(snapshot_error)?;
+            contents.push('\n');
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir).map_err(io_error)?;
+            }
+            fs::write(&path, contents).map_err(io_error)?;
+            return Ok(Vec::new());
+        }
+
+        let contents = fs::read_to_string(&path).map_err(io_error)?;
+        let golden: Json = serde_json::from_str(&contents).map_err(snapshot_error)?;
+
+        Ok(diff_snapshots(&golden, snapshot))
+    }
+}
+
+/// The rows of each table of one snapshot that the other doesn't have.
+fn diff_snapshots(golden: &Json, snapshot: &Json) -> Vec<String> {
+    let rows = |snapshot: &Json| -> BTreeSet<(String, String)> {
+        snapshot
+            .as_object()
+            .into_iter()
+            .flatten()
+            .flat_map(|(table, rows)| {
+                rows.as_array()
+                    .into_iter()
+                    .flatten()
+                    .map(move |row| (table.clone(), row.to_string()))
+            })
+            .collect()
+    };
+    let (golden, snapshot) = (rows(golden), rows(snapshot));
+
+    let missing = golden
+        .difference(&snapshot)
+        .map(|(table, row)| format!("- {}: {}", table, row));
+    let unexpected = snapshot
+        .difference(&golden)
+        .map(|(table, row)| format!("+ {}: {}", table, row));
+
+    missing.chain(unexpected).collect()
+}
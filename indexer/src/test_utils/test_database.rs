@@ -0,0 +1,68 @@
+//! Ephemeral Postgres databases for integration tests.
+//!
+//! Each [`TestDatabase`] runs its own Postgres container with the schema
+//! migrations applied, so tests using one don't share tables with other
+//! tests and don't need a `DATABASE_URL`. The container is removed when the
+//! database is dropped. Starting one needs a Docker daemon.
+
+use std::sync::Arc;
+
+use testcontainers::{
+    core::{IntoContainerPort, WaitFor},
+    runners::AsyncRunner,
+    ContainerAsync, GenericImage, ImageExt, TestcontainersError,
+};
+use thiserror::Error;
+
+use crate::storage::{postgres::PostgresStorage, StorageError};
+
+const IMAGE: &str = "postgres";
+const TAG: &str = "16-alpine";
+const PASSWORD: &str = "postgres";
+// Logged by the temporary server that runs the init scripts too, so like the
+// testcontainers postgres module this waits for it on both streams
+const READY: &str = "database system is ready to accept connections";
+
+#[derive(Error, Debug)]
+pub enum TestDatabaseError {
+    #[error("Container error: {0}")]
+    Container(#[from] TestcontainersError),
+    #[error("Storage error: {0}")]
+    Storage(#[from] StorageError),
+}
+
+/// A migrated Postgres database in a container of its own.
+pub struct TestDatabase {
+    pub storage: Arc<PostgresStorage>,
+    pub url: String,
+    _container: ContainerAsync<GenericImage>,
+}
+
+impl TestDatabase {
+    /// Starts a Postgres container and applies the migrations to it.
+    pub async fn start() -> Result<Self, TestDatabaseError> {
+        let container = GenericImage::new(IMAGE, TAG)
+            .with_exposed_port(5432.tcp())
+            .with_wait_for(WaitFor::message_on_stderr(READY))
+            .with_wait_for(WaitFor::message_on_stdout(READY))
+            .with_env_var("POSTGRES_PASSWORD", PASSWORD)
+            .start()
+            .await?;
+
+        let host = container.get_host().await?;
+        let port = container.get_host_port_ipv4(5432).await?;
+        let url = format!(
+            "postgres://postgres:{}@{}:{}/postgres",
+            PASSWORD, host, port
+        );
+
+        let storage = Arc::new(PostgresStorage::new(&url).await?);
+        storage.migrate().await?;
+
+        Ok(TestDatabase {
+            storage,
+            url,
+            _container: container,
+        })
+    }
+}
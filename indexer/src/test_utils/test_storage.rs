@@ -145,6 +145,33 @@ impl TestStorage {
             .collect())
     }
 
+    /// Test helper: Get the recorded data type migrations of a property, in
+    /// the order they were requested
+    pub async fn get_property_migrations(
+        &self,
+        property_id: &Uuid,
+    ) -> Result<Vec<PropertyMigrationRow>, IndexingError> {
+        let rows = sqlx::query(
+            "SELECT space_id, from_type::text, to_type::text, applied, reason, converted_values FROM property_migrations WHERE property_id = $1 ORDER BY id",
+        )
+        .bind(property_id)
+        .fetch_all(self.get_pool())
+        .await
+        .map_err(|e| IndexingError::StorageError(StorageError::Database(e)))?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| PropertyMigrationRow {
+                space_id: row.get("space_id"),
+                from_type: row.get("from_type"),
+                to_type: row.get("to_type"),
+                applied: row.get("applied"),
+                reason: row.get("reason"),
+                converted_values: row.get("converted_values"),
+            })
+            .collect())
+    }
+
     /// Test helper: Count total records in a table
     pub async fn count_records(&self, table_name: &str) -> Result<i64, IndexingError> {
         let query = format!("SELECT COUNT(*) as count FROM {}", table_name);
@@ -204,6 +231,16 @@ pub struct ValueRow {
     pub point: Option<String>,
 }
 
+#[derive(Debug, Clone)]
+pub struct PropertyMigrationRow {
+    pub space_id: Uuid,
+    pub from_type: String,
+    pub to_type: String,
+    pub applied: bool,
+    pub reason: Option<String>,
+    pub converted_values: i32,
+}
+
 #[derive(Debug, Clone)]
 pub struct RelationRow {
     pub id: Uuid,
@@ -0,0 +1,418 @@
+//! Checks that edits were published by someone allowed to write them.
+//!
+//! Edits name their authors, but anyone can claim to be an author. An edit
+//! is verified if the transaction that published it was sent by an editor of
+//! the space it's published to, or of one of its authors' spaces, as authors
+//! are identified by their personal space. Editors added in the same block
+//! count, as they're written alongside the edits.
+//!
+//! Public spaces can also be held to their editors alone: with
+//! [`EditorPermissions`], edits to a public space are only applied if their
+//! sender was an editor of the space at the edit's block, according to the
+//! editor history, so replaying old blocks uses the editors of the time.
+//!
+//! The sender comes from the substream, which only reports it from package
+//! v1.0.4. Edits without a sender aren't checked by either, as running an
+//! older package would otherwise flag or drop every edit.
+
+use std::collections::HashSet;
+
+use indexer_utils::{
+    checksum_address,
+    config::{AuthorVerification, VerificationConfig},
+    id,
+};
+use sqlx::PgPool;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::{cache::PreprocessedEdit, error::IndexingError, CreatedSpace, KgData};
+
+/// An edit that couldn't be verified, as it's recorded in `unverified_edits`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnverifiedEdit {
+    pub space_id: Uuid,
+    pub cid: String,
+    pub sender: Option<String>,
+}
+
+pub struct AuthorVerifier {
+    pool: PgPool,
+    mode: AuthorVerification,
+}
+
+impl AuthorVerifier {
+    pub fn new(pool: PgPool, mode: AuthorVerification) -> Self {
+        AuthorVerifier { pool, mode }
+    }
+
+    /// Returns a verifier for the configured mode, or `None` if verification
+    /// is off.
+    pub fn from_config(pool: PgPool, config: &VerificationConfig) -> Option<Self> {
+        match config.authors {
+            AuthorVerification::Off => None,
+            mode => Some(AuthorVerifier::new(pool, mode)),
+        }
+    }
+
+    /// Records the edits of a block that can't be verified, and drops them
+    /// from the block if they're rejected. Edits without a sender aren't
+    /// checked. Errored edits aren't applied anyway, so they're skipped.
+    pub async fn verify(&self, mut data: KgData) -> Result<KgData, IndexingError> {
+        warn_unchecked(&data, "authors");
+
+        let unverified = self.unverified(&data).await?;
+        if unverified.is_empty() {
+            return Ok(data);
+        }
+
+        let rejected = self.mode == AuthorVerification::Reject;
+
+        for index in &unverified {
+            let edit = &data.edits[*index];
+            warn!(
+                space_id = %edit.space_id,
+                cid = %edit.cid,
+                sender = edit.sender.as_deref().unwrap_or("unknown"),
+                rejected = rejected,
+                "Edit wasn't published by an author or editor"
+            );
+        }
+
+        let edits: Vec<UnverifiedEdit> = unverified
+            .iter()
+            .map(|index| {
+                let edit = &data.edits[*index];
+                UnverifiedEdit {
+                    space_id: edit.space_id,
+                    cid: edit.cid.clone(),
+                    sender: edit.sender.clone(),
+                }
+            })
+            .collect();
+        self.record(&edits, rejected, &data).await?;
+
+        if rejected {
+            let unverified: HashSet<usize> = unverified.into_iter().collect();
+            let mut index = 0;
+            data.edits.retain(|_| {
+                let keep = !unverified.contains(&index);
+                index += 1;
+                keep
+            });
+        }
+
+        Ok(data)
+    }
+
+    /// Returns the indexes of the edits in the block that can't be verified.
+    async fn unverified(&self, data: &KgData) -> Result<Vec<usize>, IndexingError> {
+        let block_editors = block_editors(data);
+
+        let mut unverified = Vec::new();
+        // The edits left to check against the editors table, one row per
+        // space the sender could be an editor of
+        let (mut indexes, mut space_ids, mut senders) = (Vec::new(), Vec::new(), Vec::new());
+
+        for (index, edit) in data.edits.iter().enumerate() {
+            if edit.edit.is_none() {
+                continue;
+            }
+            let Some(sender) = &edit.sender else {
+                continue;
+            };
+
+            let spaces = candidate_spaces(edit);
+            if spaces
+                .iter()
+                .any(|space_id| block_editors.contains(&(*space_id, sender.clone())))
+            {
+                continue;
+            }
+
+            for space_id in spaces {
+                indexes.push(index as i32);
+                space_ids.push(space_id);
+                senders.push(sender.clone());
+            }
+        }
+
+        if indexes.is_empty() {
+            return Ok(unverified);
+        }
+
+        let verified: HashSet<i32> = sqlx::query_scalar::<_, i32>(
+            r#"
+                SELECT DISTINCT c.index
+                FROM UNNEST($1::int[], $2::uuid[], $3::text[]) AS c(index, space_id, sender)
+                JOIN editors e ON e.space_id = c.space_id AND e.address = c.sender
+            "#,
+        )
+        .bind(&indexes)
+        .bind(&space_ids)
+        .bind(&senders)
+        .fetch_all(&self.pool)
+        .await?
+        .into_iter()
+        .collect();
+
+        let mut checked = HashSet::new();
+        for index in indexes {
+            if checked.insert(index) && !verified.contains(&index) {
+                unverified.push(index as usize);
+            }
+        }
+        unverified.sort_unstable();
+
+        Ok(unverified)
+    }
+
+    async fn record(
+        &self,
+        edits: &[UnverifiedEdit],
+        rejected: bool,
+        data: &KgData,
+    ) -> Result<(), IndexingError> {
+        let space_ids: Vec<Uuid> = edits.iter().map(|edit| edit.space_id).collect();
+        let cids: Vec<&str> = edits.iter().map(|edit| edit.cid.as_str()).collect();
+        let senders: Vec<Option<&str>> = edits.iter().map(|edit| edit.sender.as_deref()).collect();
+
+        sqlx::query(
+            r#"
+                INSERT INTO unverified_edits (space_id, cid, sender, rejected, created_at, created_at_block)
+                SELECT u.space_id, u.cid, u.sender, $4, $5, $6
+                FROM UNNEST($1::uuid[], $2::text[], $3::text[]) AS u(space_id, cid, sender)
+                ON CONFLICT (space_id, cid) DO NOTHING
+            "#,
+        )
+        .bind(&space_ids)
+        .bind(&cids)
+        .bind(&senders)
+        .bind(rejected)
+        .bind(&data.block.timestamp)
+        .bind(data.block.block_number.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Keeps edits to public spaces that weren't published by an editor of the
+/// space from being applied.
+pub struct EditorPermissions {
+    pool: PgPool,
+}
+
+impl EditorPermissions {
+    pub fn new(pool: PgPool) -> Self {
+        EditorPermissions { pool }
+    }
+
+    /// Returns the enforcer if strict editor permissions are configured.
+    pub fn from_config(pool: PgPool, config: &VerificationConfig) -> Option<Self> {
+        config.strict_editors.then(|| EditorPermissions::new(pool))
+    }
+
+    /// Records the edits of a block to public spaces whose sender wasn't an
+    /// editor of the space at the block in `unauthorized_edits`, and drops
+    /// them from the block. Editors added in the block count, as do editors
+    /// removed in it. Edits without a sender aren't checked.
+    pub async fn enforce(&self, mut data: KgData) -> Result<KgData, IndexingError> {
+        warn_unchecked(&data, "editors");
+
+        let unauthorized = self.unauthorized(&data).await?;
+        if unauthorized.is_empty() {
+            return Ok(data);
+        }
+
+        let (mut space_ids, mut cids, mut senders) = (Vec::new(), Vec::new(), Vec::new());
+        for index in &unauthorized {
+            let edit = &data.edits[*index];
+            warn!(
+                space_id = %edit.space_id,
+                cid = %edit.cid,
+                sender = edit.sender.as_deref().unwrap_or("unknown"),
+                "Edit to a public space wasn't published by an editor"
+            );
+
+            space_ids.push(edit.space_id);
+            cids.push(edit.cid.clone());
+            senders.push(edit.sender.clone());
+        }
+
+        sqlx::query(
+            r#"
+                INSERT INTO unauthorized_edits (space_id, cid, sender, created_at, created_at_block)
+                SELECT u.space_id, u.cid, u.sender, $4, $5
+                FROM UNNEST($1::uuid[], $2::text[], $3::text[]) AS u(space_id, cid, sender)
+                ON CONFLICT (space_id, cid) DO NOTHING
+            "#,
+        )
+        .bind(&space_ids)
+        .bind(&cids)
+        .bind(&senders)
+        .bind(&data.block.timestamp)
+        .bind(data.block.block_number.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        let unauthorized: HashSet<usize> = unauthorized.into_iter().collect();
+        let mut index = 0;
+        data.edits.retain(|_| {
+            let keep = !unauthorized.contains(&index);
+            index += 1;
+            keep
+        });
+
+        Ok(data)
+    }
+
+    /// Returns the indexes of the edits in the block that are unauthorized.
+    async fn unauthorized(&self, data: &KgData) -> Result<Vec<usize>, IndexingError> {
+        let block_editors = block_editors(data);
+        // Spaces created in the block aren't in the database yet
+        let created_public_spaces: Vec<Uuid> = data
+            .spaces
+            .iter()
+            .filter_map(|space| match space {
+                CreatedSpace::Public(space) => {
+                    Some(data.network.space_id(&checksum_address(&space.dao_address)))
+                }
+                CreatedSpace::Personal(_) => None,
+            })
+            .collect();
+
+        let (mut indexes, mut space_ids, mut senders) = (Vec::new(), Vec::new(), Vec::new());
+        for (index, edit) in data.edits.iter().enumerate() {
+            if edit.edit.is_none() {
+                continue;
+            }
+            let Some(sender) = &edit.sender else {
+                continue;
+            };
+            if block_editors.contains(&(edit.space_id, sender.clone())) {
+                continue;
+            }
+
+            indexes.push(index as i32);
+            space_ids.push(edit.space_id);
+            senders.push(sender.clone());
+        }
+
+        if indexes.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let unauthorized = sqlx::query_scalar::<_, i32>(
+            r#"
+                SELECT c.index
+                FROM UNNEST($1::int[], $2::uuid[], $3::text[]) AS c(index, space_id, sender)
+                WHERE (
+                    c.space_id = ANY($4)
+                    OR EXISTS (SELECT 1 FROM spaces s WHERE s.id = c.space_id AND s.type = 'Public')
+                )
+                AND NOT EXISTS (
+                    SELECT 1 FROM editor_history h
+                    WHERE h.space_id = c.space_id
+                        AND h.address = c.sender
+                        AND h.added_at_block <= $5
+                        AND (h.removed_at_block IS NULL OR h.removed_at_block >= $5)
+                )
+                ORDER BY c.index
+            "#,
+        )
+        .bind(&indexes)
+        .bind(&space_ids)
+        .bind(&senders)
+        .bind(&created_public_spaces)
+        .bind(data.block.block_number as i64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(unauthorized
+            .into_iter()
+            .map(|index| index as usize)
+            .collect())
+    }
+}
+
+/// The editors added in a block, by space id and checksummed address.
+fn block_editors(data: &KgData) -> HashSet<(Uuid, String)> {
+    data.added_editors
+        .iter()
+        .map(|editor| {
+            (
+                data.network
+                    .space_id(&checksum_address(&editor.dao_address)),
+                checksum_address(&editor.editor_address),
+            )
+        })
+        .collect()
+}
+
+/// Warns about the edits of a block that have no sender and so can't be
+/// checked, which happens when the substream package predates senders.
+fn warn_unchecked(data: &KgData, check: &str) {
+    let unchecked = data
+        .edits
+        .iter()
+        .filter(|edit| edit.edit.is_some() && edit.sender.is_none())
+        .count();
+    if unchecked > 0 {
+        warn!(
+            block_number = data.block.block_number,
+            count = unchecked,
+            check = check,
+            "Edits without a sender weren't checked"
+        );
+    }
+}
+
+/// The spaces an editor of which may publish an edit: the space it's
+/// published to and the spaces of its authors, in that order.
+pub fn candidate_spaces(edit: &PreprocessedEdit) -> Vec<Uuid> {
+    let mut spaces = vec![edit.space_id];
+
+    let authors = edit.edit.iter().flat_map(|edit| &edit.authors);
+    for author in authors {
+        let Ok(author) = id::transform_id_bytes(author.clone()) else {
+            continue;
+        };
+        let author = Uuid::from_bytes(author);
+
+        if !spaces.contains(&author) {
+            spaces.push(author);
+        }
+    }
+
+    spaces
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::builder::EditBuilder;
+
+    #[test]
+    fn test_candidate_spaces() {
+        let (space_id, author) = (Uuid::new_v4(), Uuid::new_v4());
+        let mut edit = EditBuilder::new(Uuid::new_v4())
+            .author(author)
+            .author(space_id)
+            .author(author)
+            .build();
+        edit.authors.push(vec![1, 2, 3]);
+
+        let preprocessed = PreprocessedEdit {
+            cid: "ipfs://edit".to_string(),
+            edit: Some(edit),
+            is_errored: false,
+            space_id,
+            sender: None,
+            event_index: 0,
+        };
+
+        assert_eq!(candidate_spaces(&preprocessed), vec![space_id, author]);
+    }
+}
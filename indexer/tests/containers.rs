@@ -0,0 +1,61 @@
+//! Integration tests against ephemeral Postgres containers, run with
+//! `cargo test -p indexer --features testcontainers --test containers`.
+//! They need a Docker daemon but no `DATABASE_URL`.
+#![cfg(feature = "testcontainers")]
+
+use std::sync::Arc;
+
+use indexer::{
+    block_handler::root_handler,
+    cache::properties_cache::PropertiesCache,
+    storage::postgres::MIGRATOR,
+    test_utils::{Scenario, TestDatabase},
+};
+
+#[tokio::test]
+async fn test_migrations_apply_to_empty_database() {
+    let database = TestDatabase::start().await.expect("database should start");
+
+    let applied: i64 = sqlx::query_scalar("SELECT count(*) FROM _sqlx_migrations")
+        .fetch_one(&database.storage.pool)
+        .await
+        .unwrap();
+    assert_eq!(applied as usize, MIGRATOR.iter().count());
+}
+
+/// Runs each scenario in a database of its own, so no tables have to be
+/// cleared between them and the tests can run in parallel.
+#[tokio::test]
+async fn test_scenarios() {
+    let scenarios = Scenario::load_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scenarios"))
+        .expect("scenarios should load");
+    assert!(!scenarios.is_empty());
+
+    for scenario in scenarios {
+        let database = TestDatabase::start().await.expect("database should start");
+        let properties_cache = Arc::new(PropertiesCache::new());
+
+        for block in scenario.blocks().expect("scenario blocks should build") {
+            root_handler::run(&block, &block.block, &database.storage, &properties_cache)
+                .await
+                .expect("scenario blocks should index");
+        }
+
+        let failures = scenario.verify(&database.storage).await.unwrap();
+        assert!(
+            failures.is_empty(),
+            "scenario '{}' failed: {:?}",
+            scenario.name,
+            failures
+        );
+
+        let snapshot = scenario.snapshot(&database.storage).await.unwrap();
+        let changes = scenario.check_snapshot(&snapshot).unwrap();
+        assert!(
+            changes.is_empty(),
+            "scenario '{}' doesn't match its snapshot:\n{}",
+            scenario.name,
+            changes.join("\n")
+        );
+    }
+}
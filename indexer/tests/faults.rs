@@ -0,0 +1,150 @@
+//! Indexes blocks through storage that fails on purpose, run with
+//! `cargo test -p indexer --features faults --test faults`.
+#![cfg(feature = "faults")]
+
+use std::{env, sync::Arc};
+
+use dotenv::dotenv;
+use indexer::{
+    block_handler::root_handler,
+    cache::properties_cache::PropertiesCache,
+    error::IndexingError,
+    storage::{postgres::PostgresStorage, StorageBackend},
+    test_utils::{FaultyStorage, KgDataBuilder, StorageFaults},
+};
+use uuid::Uuid;
+
+async fn faulty_storage(
+    faults: StorageFaults,
+) -> Result<Arc<FaultyStorage<PostgresStorage>>, IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = PostgresStorage::new(&database_url).await?;
+
+    Ok(Arc::new(FaultyStorage::new(storage, faults, 0)))
+}
+
+/// A lowercase address no other test indexes.
+fn unique_address() -> String {
+    let hex = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    format!("0x{}", &hex[..40])
+}
+
+async fn space_count(storage: &FaultyStorage<PostgresStorage>, dao_address: &str) -> i64 {
+    sqlx::query_scalar("SELECT count(*) FROM spaces WHERE lower(dao_address) = $1")
+        .bind(dao_address)
+        .fetch_one(storage.get_pool())
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_failed_block_is_not_recorded_as_processed() -> Result<(), IndexingError> {
+    let storage = faulty_storage(StorageFaults::always(["insert_spaces"])).await?;
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer_id = format!("faults-{}", Uuid::new_v4());
+    let dao_address = unique_address();
+    let data = KgDataBuilder::new(1).public_space(&dao_address).build();
+
+    let result =
+        root_handler::run_once(&data, &data.block, &storage, &properties_cache, &indexer_id).await;
+    assert!(result.is_err());
+    assert_eq!(storage.injected(), 1);
+    assert!(!storage.is_block_processed(&indexer_id, &data.block).await?);
+    assert_eq!(space_count(&storage, &dao_address).await, 0);
+
+    // Once the database recovers the block is processed in full, and only once
+    storage.set_faults(StorageFaults::default());
+    assert!(
+        root_handler::run_once(&data, &data.block, &storage, &properties_cache, &indexer_id)
+            .await?
+    );
+    assert_eq!(space_count(&storage, &dao_address).await, 1);
+    assert!(
+        !root_handler::run_once(&data, &data.block, &storage, &properties_cache, &indexer_id)
+            .await?
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_is_processed_again_if_recording_it_fails() -> Result<(), IndexingError> {
+    let storage = faulty_storage(StorageFaults::always(["record_processed_block"])).await?;
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer_id = format!("faults-{}", Uuid::new_v4());
+    let dao_address = unique_address();
+    let data = KgDataBuilder::new(1).public_space(&dao_address).build();
+
+    // The block's writes are committed, but it's missing from the ledger
+    let result =
+        root_handler::run_once(&data, &data.block, &storage, &properties_cache, &indexer_id).await;
+    assert!(result.is_err());
+    assert_eq!(space_count(&storage, &dao_address).await, 1);
+    assert!(!storage.is_block_processed(&indexer_id, &data.block).await?);
+
+    // Processing it again repeats its writes without duplicating them
+    storage.set_faults(StorageFaults::default());
+    assert!(
+        root_handler::run_once(&data, &data.block, &storage, &properties_cache, &indexer_id)
+            .await?
+    );
+    assert_eq!(space_count(&storage, &dao_address).await, 1);
+    assert!(storage.is_block_processed(&indexer_id, &data.block).await?);
+
+    Ok(())
+}
+
+/// Retries blocks that fail at random until they're processed, and checks
+/// they're processed exactly once.
+#[tokio::test]
+async fn test_blocks_are_processed_once_under_random_faults() -> Result<(), IndexingError> {
+    let faults = StorageFaults {
+        error_rate: 0.3,
+        methods: vec![
+            "is_block_processed",
+            "insert_spaces",
+            "insert_members",
+            "record_processed_block",
+        ],
+    };
+    let storage = faulty_storage(faults).await?;
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer_id = format!("faults-{}", Uuid::new_v4());
+
+    let addresses: Vec<String> = (0..10).map(|_| unique_address()).collect();
+    for (block, dao_address) in addresses.iter().enumerate() {
+        let data = KgDataBuilder::new(block as u64 + 1)
+            .public_space(dao_address)
+            .added_member(dao_address, &unique_address())
+            .build();
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            assert!(attempts <= 20, "block {} kept failing", block + 1);
+            match root_handler::run_once(
+                &data,
+                &data.block,
+                &storage,
+                &properties_cache,
+                &indexer_id,
+            )
+            .await
+            {
+                Ok(processed) => {
+                    assert!(processed);
+                    break;
+                }
+                Err(_) => continue,
+            }
+        }
+    }
+
+    assert!(storage.injected() > 0);
+    for dao_address in &addresses {
+        assert_eq!(space_count(&storage, dao_address).await, 1);
+    }
+
+    Ok(())
+}
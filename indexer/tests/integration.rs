@@ -3,29 +3,78 @@ use std::{
     env,
     hash::{Hash, Hasher},
     sync::Arc,
+    time::Duration,
 };
 use stream::utils::BlockMetadata;
 use uuid::Uuid;
-use wire::pb::grc20::{
-    op::Payload, DataType as PbDataType, Edit, Entity, Op, Property, Relation, UnsetEntityValues,
-    Value,
+use wire::{
+    builder::{relation, relation_update, value, EditBuilder},
+    pb::grc20::{
+        options, DataType as PbDataType, Edit, Options, Relation, RelationUpdate, TextOptions,
+        Value,
+    },
 };
 
 use dotenv::dotenv;
 use indexer::{
+    backup,
     block_handler::root_handler,
-    cache::{properties_cache::{PropertiesCache, ImmutableCache}, PreprocessedEdit},
+    cache::{
+        copy::{CacheCopy, CacheSelection},
+        gc::{CacheCollector, CollectMode},
+        properties_cache::{ImmutableCache, PropertiesCache},
+        redecode::CacheRedecoder,
+        PreprocessedEdit,
+    },
+    comparison::{ComparedTable, DatabaseComparison},
+    consistency::{ConsistencyChecker, IssueKind},
     error::IndexingError,
-    models::properties::DataType,
-    storage::{postgres::PostgresStorage, StorageError},
-    test_utils::TestStorage,
-    AddedMember, AddedSubspace, CreatedSpace, KgData, PersonalSpace, PublicSpace, RemovedMember,
-    RemovedSubspace,
+    id_mapping::IdMapper,
+    mirror::{
+        MirrorError, MirrorSync, MirrorWriter, MirroredEdit, MirroredRelation, MirroredValue,
+    },
+    models::{
+        analytics::{ActivityRollup, RollupPeriod},
+        neighborhood::{Neighborhood, NeighborhoodNode},
+        properties::{DataType, PropertyItem, PropertyMetadata},
+        proposals::ProposalPreview,
+        relations::{SetRelationItem, UpdateRelationItem},
+        spaces::SpaceMetadata,
+    },
+    notifications::{dispatcher::WebhookDispatcher, sign, EventType, Webhook, SIGNATURE_HEADER},
+    search::{SearchDocument, SearchError, SearchIndex, SearchSync},
+    sharding::{Shard, ShardLease},
+    storage::{
+        notify::{
+            ProposalStatus, ProposalUpdated, SpaceChange, SpaceUpdated, PROPOSAL_UPDATED_CHANNEL,
+            SPACE_UPDATED_CHANNEL,
+        },
+        pagination::{PageRequest, RelationFilter},
+        postgres::PostgresStorage,
+        StorageBackend, StorageError,
+    },
+    test_utils::{KgDataBuilder, RecordingStorage, Scenario, StorageCall, TestStorage},
+    verification::{AuthorVerifier, EditorPermissions},
+    AddedMember, AddedSubspace, CanceledProposal, CreatedProposal, CreatedSpace, ExecutedProposal,
+    KgData, PersonalSpace, PublicSpace, RemovedMember, RemovedSubspace,
+};
+use indexer_utils::{
+    checksum_address,
+    config::{AuthorVerification, NotificationsConfig},
+    id::{derive_space_id, derive_value_id, IdScheme},
+    network_ids::{Network, GEO},
+    system_ids::{
+        COVER_PROPERTY, DESCRIPTION_PROPERTY, IMAGE_URL_PROPERTY, NAME_PROPERTY,
+        PROPERTIES_PROPERTY, RENDERABLE_TYPE_PROPERTY, SCHEMA_TYPE, SPACE_TYPE, TYPES_PROPERTY,
+    },
 };
-use indexer_utils::{checksum_address, id::derive_space_id, network_ids::GEO};
 use serial_test::serial;
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+};
 
 struct TestIndexer {
     storage: Arc<PostgresStorage>,
@@ -52,6 +101,7 @@ impl TestIndexer {
 // @TODO: Different test for the cache preprocessing
 
 #[tokio::test]
+#[serial]
 async fn main() -> Result<(), IndexingError> {
     dotenv().ok();
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
@@ -60,78 +110,54 @@ async fn main() -> Result<(), IndexingError> {
     let item = PreprocessedEdit {
         space_id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440007").unwrap(),
         is_errored: false,
-        edit: Some(make_edit(
-            "f47ac10b-58cc-4372-a567-0e02b2c3d479",
-            "Name",
-            "f47ac10b-58cc-4372-a567-0e02b2c3d480",
-            vec![
-                make_entity_op(
-                    TestEntityOpType::UPDATE,
-                    "550e8400-e29b-41d4-a716-446655440001",
-                    vec![
-                        TestValue {
-                            property_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c1".to_string(),
-                            value: Some("Test entity".to_string()),
-                        },
-                        TestValue {
-                            property_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c2".to_string(),
-                            value: Some("1".to_string()),
-                        },
+        edit: Some(
+            EditBuilder::new(id("f47ac10b-58cc-4372-a567-0e02b2c3d479"))
+                .name("Name")
+                .author(id("f47ac10b-58cc-4372-a567-0e02b2c3d480"))
+                .update_entity(
+                    id("550e8400-e29b-41d4-a716-446655440001"),
+                    [
+                        value(id("6ba7b810-9dad-11d1-80b4-00c04fd430c1"), "Test entity"),
+                        value(id("6ba7b810-9dad-11d1-80b4-00c04fd430c2"), "1"),
                     ],
-                ),
-                make_entity_op(
-                    TestEntityOpType::UPDATE,
-                    "550e8400-e29b-41d4-a716-446655440002",
-                    vec![TestValue {
-                        property_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c2".to_string(),
-                        value: Some("2".to_string()),
-                    }],
-                ),
-                make_entity_op(
-                    TestEntityOpType::UNSET,
-                    "550e8400-e29b-41d4-a716-446655440002",
-                    vec![TestValue {
-                        property_id: "6ba7b810-9dad-11d1-80b4-00c04fd430c2".to_string(),
-                        value: None,
-                    }],
-                ),
-                make_relation_op(
-                    TestRelationOpType::CREATE,
-                    "7ba7b810-9dad-11d1-80b4-00c04fd430c1",
-                    "550e8400-e29b-41d4-a716-446655440001",
-                    "8ba7b810-9dad-11d1-80b4-00c04fd430c1",
-                    "550e8400-e29b-41d4-a716-446655440003",
-                    "550e8400-e29b-41d4-a716-446655440004",
-                ),
-                make_relation_op(
-                    TestRelationOpType::UPDATE,
-                    "7ba7b810-9dad-11d1-80b4-00c04fd430c1",
-                    "550e8400-e29b-41d4-a716-446655440001",
-                    "8ba7b810-9dad-11d1-80b4-00c04fd430c1",
-                    "550e8400-e29b-41d4-a716-446655440003",
-                    "550e8400-e29b-41d4-a716-446655440004",
-                ),
-                make_relation_op(
-                    TestRelationOpType::CREATE,
-                    "7ba7b810-9dad-11d1-80b4-00c04fd430c2",
-                    "550e8400-e29b-41d4-a716-446655440001",
-                    "8ba7b810-9dad-11d1-80b4-00c04fd430c1",
-                    "550e8400-e29b-41d4-a716-446655440003",
-                    "550e8400-e29b-41d4-a716-446655440004",
-                ),
-                make_relation_op(
-                    TestRelationOpType::DELETE,
-                    "7ba7b810-9dad-11d1-80b4-00c04fd430c2",
-                    "550e8400-e29b-41d4-a716-446655440001",
-                    "8ba7b810-9dad-11d1-80b4-00c04fd430c1",
-                    "550e8400-e29b-41d4-a716-446655440003",
-                    "550e8400-e29b-41d4-a716-446655440004",
-                ),
-                make_property_op("6ba7b810-9dad-11d1-80b4-00c04fd430c1", PbDataType::Text),
-                make_property_op("6ba7b810-9dad-11d1-80b4-00c04fd430c2", PbDataType::Number),
-            ],
-        )),
+                )
+                .update_entity(
+                    id("550e8400-e29b-41d4-a716-446655440002"),
+                    [value(id("6ba7b810-9dad-11d1-80b4-00c04fd430c2"), "2")],
+                )
+                .unset_entity_values(
+                    id("550e8400-e29b-41d4-a716-446655440002"),
+                    [id("6ba7b810-9dad-11d1-80b4-00c04fd430c2")],
+                )
+                .create_relation(relation(
+                    id("7ba7b810-9dad-11d1-80b4-00c04fd430c1"),
+                    id("550e8400-e29b-41d4-a716-446655440001"),
+                    id("8ba7b810-9dad-11d1-80b4-00c04fd430c1"),
+                    id("550e8400-e29b-41d4-a716-446655440003"),
+                    id("550e8400-e29b-41d4-a716-446655440004"),
+                ))
+                .update_relation(RelationUpdate {
+                    verified: Some(true),
+                    ..relation_update(id("7ba7b810-9dad-11d1-80b4-00c04fd430c1"))
+                })
+                .create_relation(relation(
+                    id("7ba7b810-9dad-11d1-80b4-00c04fd430c2"),
+                    id("550e8400-e29b-41d4-a716-446655440001"),
+                    id("8ba7b810-9dad-11d1-80b4-00c04fd430c1"),
+                    id("550e8400-e29b-41d4-a716-446655440003"),
+                    id("550e8400-e29b-41d4-a716-446655440004"),
+                ))
+                .delete_relation(id("7ba7b810-9dad-11d1-80b4-00c04fd430c2"))
+                .create_property(id("6ba7b810-9dad-11d1-80b4-00c04fd430c1"), PbDataType::Text)
+                .create_property(
+                    id("6ba7b810-9dad-11d1-80b4-00c04fd430c2"),
+                    PbDataType::Number,
+                )
+                .build(),
+        ),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let block = BlockMetadata {
@@ -154,6 +180,13 @@ async fn main() -> Result<(), IndexingError> {
             removed_members: vec![],
             added_subspaces: vec![],
             removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            network: Network::default(),
+            unowned_properties: vec![],
         }])
         .await?;
 
@@ -282,33 +315,27 @@ async fn test_validation_rejects_invalid_number() -> Result<(), IndexingError> {
 
     // Create a Number property
     let property_id = "11111111-1111-1111-1111-111111111111";
-    let property_op = make_property_op(property_id, PbDataType::Number);
-
-    // Try to set an invalid number value (contains letters)
-    let invalid_entity_op = make_entity_op(
-        TestEntityOpType::UPDATE,
-        "22222222-2222-2222-2222-222222222222",
-        vec![TestValue {
-            property_id: property_id.to_string(),
-            value: Some("not_a_number".to_string()),
-        }],
-    );
-
-    let edit = make_edit(
-        "33333333-3333-3333-3333-333333333333",
-        "Validation Test Edit",
-        "44444444-4444-4444-4444-444444444444",
-        vec![property_op, invalid_entity_op],
-    );
+    let edit = EditBuilder::new(id("33333333-3333-3333-3333-333333333333"))
+        .name("Validation Test Edit")
+        .author(id("44444444-4444-4444-4444-444444444444"))
+        .create_property(id(property_id), PbDataType::Number)
+        // Try to set an invalid number value (contains letters)
+        .update_entity(
+            id("22222222-2222-2222-2222-222222222222"),
+            [value(id(property_id), "not_a_number")],
+        )
+        .build();
 
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("55555555-5555-5555-5555-555555555555").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
-    let kg_data = make_kg_data_with_spaces(10, vec![item], vec![]);
+    let kg_data = KgDataBuilder::new(10).edit(item).build();
     let blocks = vec![kg_data];
 
     // Run the indexer - this should succeed (no crash) but invalid data should be rejected
@@ -347,33 +374,27 @@ async fn test_validation_rejects_invalid_checkbox() -> Result<(), IndexingError>
 
     // Create a Checkbox property
     let property_id = "66666666-6666-6666-6666-666666666666";
-    let property_op = make_property_op(property_id, PbDataType::Checkbox);
-
-    // Try to set an invalid checkbox value (should be 0 or 1)
-    let invalid_entity_op = make_entity_op(
-        TestEntityOpType::UPDATE,
-        "77777777-7777-7777-7777-777777777777",
-        vec![TestValue {
-            property_id: property_id.to_string(),
-            value: Some("2".to_string()), // Invalid: checkboxes only accept 0 or 1
-        }],
-    );
-
-    let edit = make_edit(
-        "88888888-8888-8888-8888-888888888888",
-        "Checkbox Validation Test",
-        "99999999-9999-9999-9999-999999999999",
-        vec![property_op, invalid_entity_op],
-    );
+    let edit = EditBuilder::new(id("88888888-8888-8888-8888-888888888888"))
+        .name("Checkbox Validation Test")
+        .author(id("99999999-9999-9999-9999-999999999999"))
+        .create_property(id(property_id), PbDataType::Checkbox)
+        // Try to set an invalid checkbox value (should be 0 or 1)
+        .update_entity(
+            id("77777777-7777-7777-7777-777777777777"),
+            [value(id(property_id), "2")],
+        ) // Invalid: checkboxes only accept 0 or 1
+        .build();
 
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
-    let kg_data = make_kg_data_with_spaces(11, vec![item], vec![]);
+    let kg_data = KgDataBuilder::new(11).edit(item).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -412,33 +433,27 @@ async fn test_validation_rejects_invalid_time() -> Result<(), IndexingError> {
 
     // Create a Time property
     let property_id = "bbbbbbbb-bbbb-bbbb-bbbb-bbbbbbbbbbbb";
-    let property_op = make_property_op(property_id, PbDataType::Time);
-
-    // Try to set an invalid time value
-    let invalid_entity_op = make_entity_op(
-        TestEntityOpType::UPDATE,
-        "cccccccc-cccc-cccc-cccc-cccccccccccc",
-        vec![TestValue {
-            property_id: property_id.to_string(),
-            value: Some("not-a-valid-time".to_string()),
-        }],
-    );
-
-    let edit = make_edit(
-        "dddddddd-dddd-dddd-dddd-dddddddddddd",
-        "Time Validation Test",
-        "eeeeeeee-eeee-eeee-eeee-eeeeeeeeeeee",
-        vec![property_op, invalid_entity_op],
-    );
+    let edit = EditBuilder::new(id("dddddddd-dddd-dddd-dddd-dddddddddddd"))
+        .name("Time Validation Test")
+        .author(id("eeeeeeee-eeee-eeee-eeee-eeeeeeeeeeee"))
+        .create_property(id(property_id), PbDataType::Time)
+        // Try to set an invalid time value
+        .update_entity(
+            id("cccccccc-cccc-cccc-cccc-cccccccccccc"),
+            [value(id(property_id), "not-a-valid-time")],
+        )
+        .build();
 
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("ffffffff-ffff-ffff-ffff-ffffffffffff").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
-    let kg_data = make_kg_data_with_spaces(12, vec![item], vec![]);
+    let kg_data = KgDataBuilder::new(12).edit(item).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -477,33 +492,27 @@ async fn test_validation_rejects_invalid_point() -> Result<(), IndexingError> {
 
     // Create a Point property
     let property_id = "12345678-1234-1234-1234-123456789012";
-    let property_op = make_property_op(property_id, PbDataType::Point);
-
-    // Try to set an invalid point value (should be "x,y" format)
-    let invalid_entity_op = make_entity_op(
-        TestEntityOpType::UPDATE,
-        "23456789-2345-2345-2345-234567890123",
-        vec![TestValue {
-            property_id: property_id.to_string(),
-            value: Some("invalid-point-format".to_string()),
-        }],
-    );
-
-    let edit = make_edit(
-        "34567890-3456-3456-3456-345678901234",
-        "Point Validation Test",
-        "45678901-4567-4567-4567-456789012345",
-        vec![property_op, invalid_entity_op],
-    );
+    let edit = EditBuilder::new(id("34567890-3456-3456-3456-345678901234"))
+        .name("Point Validation Test")
+        .author(id("45678901-4567-4567-4567-456789012345"))
+        .create_property(id(property_id), PbDataType::Point)
+        // Try to set an invalid point value (should be "x,y" format)
+        .update_entity(
+            id("23456789-2345-2345-2345-234567890123"),
+            [value(id(property_id), "invalid-point-format")],
+        )
+        .build();
 
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("56789012-5678-5678-5678-567890123456").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
-    let kg_data = make_kg_data_with_spaces(13, vec![item], vec![]);
+    let kg_data = KgDataBuilder::new(13).edit(item).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -544,61 +553,39 @@ async fn test_validation_allows_valid_data_mixed_with_invalid() -> Result<(), In
     let number_prop_id = "67890123-6789-6789-6789-678901234567";
     let text_prop_id = "78901234-7890-7890-7890-789012345678";
 
-    let number_prop_op = make_property_op(number_prop_id, PbDataType::Number);
-    let text_prop_op = make_property_op(text_prop_id, PbDataType::Text);
-
-    // Entity with mixed valid and invalid values
-    let mixed_entity_op = make_entity_op(
-        TestEntityOpType::UPDATE,
-        "89012345-8901-8901-8901-890123456789",
-        vec![
-            TestValue {
-                property_id: number_prop_id.to_string(),
-                value: Some("42.5".to_string()), // Valid number
-            },
-            TestValue {
-                property_id: text_prop_id.to_string(),
-                value: Some("Valid text".to_string()), // Valid text
-            },
-        ],
-    );
-
-    // Another entity with invalid number but valid text
-    let invalid_entity_op = make_entity_op(
-        TestEntityOpType::UPDATE,
-        "90123456-9012-9012-9012-901234567890",
-        vec![
-            TestValue {
-                property_id: number_prop_id.to_string(),
-                value: Some("not_a_number".to_string()), // Invalid number
-            },
-            TestValue {
-                property_id: text_prop_id.to_string(),
-                value: Some("Another valid text".to_string()), // Valid text
-            },
-        ],
-    );
-
-    let edit = make_edit(
-        "01234567-0123-0123-0123-012345678901",
-        "Mixed Validation Test",
-        "10987654-1098-1098-1098-109876543210",
-        vec![
-            number_prop_op,
-            text_prop_op,
-            mixed_entity_op,
-            invalid_entity_op,
-        ],
-    );
+    let edit = EditBuilder::new(id("01234567-0123-0123-0123-012345678901"))
+        .name("Mixed Validation Test")
+        .author(id("10987654-1098-1098-1098-109876543210"))
+        .create_property(id(number_prop_id), PbDataType::Number)
+        .create_property(id(text_prop_id), PbDataType::Text)
+        // Entity with mixed valid and invalid values
+        .update_entity(
+            id("89012345-8901-8901-8901-890123456789"),
+            [
+                value(id(number_prop_id), "42.5"),     // Valid number
+                value(id(text_prop_id), "Valid text"), // Valid text
+            ],
+        )
+        // Another entity with invalid number but valid text
+        .update_entity(
+            id("90123456-9012-9012-9012-901234567890"),
+            [
+                value(id(number_prop_id), "not_a_number"), // Invalid number
+                value(id(text_prop_id), "Another valid text"), // Valid text
+            ],
+        )
+        .build();
 
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("21098765-2109-2109-2109-210987654321").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
-    let kg_data = make_kg_data_with_spaces(14, vec![item], vec![]);
+    let kg_data = KgDataBuilder::new(14).edit(item).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -662,19 +649,70 @@ async fn test_validation_allows_valid_data_mixed_with_invalid() -> Result<(), In
     Ok(())
 }
 
-fn derive_value_id(entity_id: &Uuid, property_id: &Uuid, space_id: &Uuid) -> Uuid {
-    let mut hasher = DefaultHasher::new();
-    entity_id.hash(&mut hasher);
-    property_id.hash(&mut hasher);
-    space_id.hash(&mut hasher);
-    let hash_value = hasher.finish();
+#[tokio::test]
+#[serial]
+async fn test_duplicate_edit_content_is_skipped() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let space_id = Uuid::new_v4();
+    let other_space_id = Uuid::new_v4();
+    let entity_id = Uuid::new_v4();
+    let property_id = Uuid::new_v4();
+
+    let first = EditBuilder::new(Uuid::new_v4())
+        .create_property(property_id, PbDataType::Text)
+        .update_entity(entity_id, [value(property_id, "First")])
+        .build();
+    let second = EditBuilder::new(Uuid::new_v4())
+        .update_entity(entity_id, [value(property_id, "Second")])
+        .build();
+
+    let edit = |edit: &wire::pb::grc20::Edit, space_id: Uuid, cid: &str| PreprocessedEdit {
+        edit: Some(edit.clone()),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    // The first edit is republished under a different CID after the second
+    // edit, which must not revert the value.
+    let blocks = vec![
+        KgDataBuilder::new(1)
+            .edits(vec![edit(&first, space_id, "ipfs://first")])
+            .build(),
+        KgDataBuilder::new(2)
+            .edits(vec![edit(&second, space_id, "ipfs://second")])
+            .build(),
+        KgDataBuilder::new(3)
+            .edits(vec![edit(&first, space_id, "ipfs://first-copy")])
+            .build(),
+        KgDataBuilder::new(4)
+            .edits(vec![edit(&first, other_space_id, "ipfs://first-copy")])
+            .build(),
+    ];
+
+    indexer.run(&blocks).await?;
+
+    let value = storage
+        .get_value(&derive_value_id(&entity_id, &property_id, &space_id).to_string())
+        .await
+        .unwrap();
+    assert_eq!(value.string, Some("Second".to_string()));
 
-    // Create a deterministic UUID from the hash
-    let mut bytes = [0u8; 16];
-    bytes[0..8].copy_from_slice(&hash_value.to_be_bytes());
-    bytes[8..16].copy_from_slice(&hash_value.to_be_bytes());
+    // The same content is still applied once to every other space.
+    let value = storage
+        .get_value(&derive_value_id(&entity_id, &property_id, &other_space_id).to_string())
+        .await
+        .unwrap();
+    assert_eq!(value.string, Some("First".to_string()));
 
-    Uuid::from_bytes(bytes)
+    Ok(())
 }
 
 #[tokio::test]
@@ -687,33 +725,36 @@ async fn test_property_no_overwrite() -> Result<(), IndexingError> {
     // First edit - create property with Text type
     let item = PreprocessedEdit {
         space_id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440005").unwrap(),
-        edit: Some(make_edit(
-            "f47ac10b-58cc-4372-a567-0e02b2c3d481",
-            "First Edit",
-            "f47ac10b-58cc-4372-a567-0e02b2c3d480",
-            vec![make_property_op(
-                "aba7b810-9dad-11d1-80b4-00c04fd430c1",
-                PbDataType::Text,
-            )],
-        )),
+        edit: Some(
+            EditBuilder::new(id("f47ac10b-58cc-4372-a567-0e02b2c3d481"))
+                .name("First Edit")
+                .author(id("f47ac10b-58cc-4372-a567-0e02b2c3d480"))
+                .create_property(id("aba7b810-9dad-11d1-80b4-00c04fd430c1"), PbDataType::Text)
+                .build(),
+        ),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     // Second edit - attempt to create same property with Number type
     let second_edit = PreprocessedEdit {
         space_id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440006").unwrap(),
-        edit: Some(make_edit(
-            "f47ac10b-58cc-4372-a567-0e02b2c3d482",
-            "Second Edit",
-            "f47ac10b-58cc-4372-a567-0e02b2c3d480",
-            vec![make_property_op(
-                "aba7b810-9dad-11d1-80b4-00c04fd430c1",
-                PbDataType::Number,
-            )],
-        )),
+        edit: Some(
+            EditBuilder::new(id("f47ac10b-58cc-4372-a567-0e02b2c3d482"))
+                .name("Second Edit")
+                .author(id("f47ac10b-58cc-4372-a567-0e02b2c3d480"))
+                .create_property(
+                    id("aba7b810-9dad-11d1-80b4-00c04fd430c1"),
+                    PbDataType::Number,
+                )
+                .build(),
+        ),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let block = BlockMetadata {
@@ -737,6 +778,13 @@ async fn test_property_no_overwrite() -> Result<(), IndexingError> {
             removed_members: vec![],
             added_subspaces: vec![],
             removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            network: Network::default(),
+            unowned_properties: vec![],
         }])
         .await?;
 
@@ -765,6 +813,13 @@ async fn test_property_no_overwrite() -> Result<(), IndexingError> {
             removed_members: vec![],
             added_subspaces: vec![],
             removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            network: Network::default(),
+            unowned_properties: vec![],
         }])
         .await?;
 
@@ -794,23 +849,30 @@ async fn test_property_squashing() -> Result<(), IndexingError> {
     // Single edit with multiple CreateProperty ops for the same property ID
     let edit_with_duplicate_properties = PreprocessedEdit {
         space_id: Uuid::parse_str("550e8400-e29b-41d4-a716-446655440008").unwrap(),
-        edit: Some(make_edit(
-            "f47ac10b-58cc-4372-a567-0e02b2c3d483",
-            "Squash Test Edit",
-            "f47ac10b-58cc-4372-a567-0e02b2c3d480",
-            vec![
+        edit: Some(
+            EditBuilder::new(id("f47ac10b-58cc-4372-a567-0e02b2c3d483"))
+                .name("Squash Test Edit")
+                .author(id("f47ac10b-58cc-4372-a567-0e02b2c3d480"))
                 // First: create property with Text type
-                make_property_op("bba7b810-9dad-11d1-80b4-00c04fd430c1", PbDataType::Text),
+                .create_property(id("bba7b810-9dad-11d1-80b4-00c04fd430c1"), PbDataType::Text)
                 // Second: create same property with Number type
-                make_property_op("bba7b810-9dad-11d1-80b4-00c04fd430c1", PbDataType::Number),
+                .create_property(
+                    id("bba7b810-9dad-11d1-80b4-00c04fd430c1"),
+                    PbDataType::Number,
+                )
                 // Third: create same property with Checkbox type (this should be the final one)
-                make_property_op("bba7b810-9dad-11d1-80b4-00c04fd430c1", PbDataType::Checkbox),
+                .create_property(
+                    id("bba7b810-9dad-11d1-80b4-00c04fd430c1"),
+                    PbDataType::Checkbox,
+                )
                 // Different property to ensure squashing only affects same IDs
-                make_property_op("bba7b810-9dad-11d1-80b4-00c04fd430c2", PbDataType::Time),
-            ],
-        )),
+                .create_property(id("bba7b810-9dad-11d1-80b4-00c04fd430c2"), PbDataType::Time)
+                .build(),
+        ),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let block = BlockMetadata {
@@ -834,6 +896,13 @@ async fn test_property_squashing() -> Result<(), IndexingError> {
             removed_members: vec![],
             added_subspaces: vec![],
             removed_subspaces: vec![],
+            personal_admin_changes: vec![],
+            governance_plugin_changes: vec![],
+            created_proposals: vec![],
+            executed_proposals: vec![],
+            canceled_proposals: vec![],
+            network: Network::default(),
+            unowned_properties: vec![],
         }])
         .await?;
 
@@ -866,109 +935,8 @@ async fn test_property_squashing() -> Result<(), IndexingError> {
     Ok(())
 }
 
-fn make_edit(id: &str, name: &str, author: &str, ops: Vec<Op>) -> Edit {
-    Edit {
-        id: Uuid::parse_str(id).unwrap().as_bytes().to_vec(),
-        name: String::from(name),
-        ops,
-        authors: vec![Uuid::parse_str(author).unwrap().as_bytes().to_vec()],
-        language: None,
-    }
-}
-
-struct TestValue {
-    pub property_id: String,
-    pub value: Option<String>,
-}
-
-enum TestEntityOpType {
-    UPDATE,
-    UNSET,
-}
-
-fn make_entity_op(op_type: TestEntityOpType, entity: &str, values: Vec<TestValue>) -> Op {
-    match op_type {
-        TestEntityOpType::UPDATE => Op {
-            payload: Some(Payload::UpdateEntity(Entity {
-                id: Uuid::parse_str(entity).unwrap().as_bytes().to_vec(),
-                values: values
-                    .iter()
-                    .map(|v| Value {
-                        property: Uuid::parse_str(&v.property_id).unwrap().as_bytes().to_vec(),
-                        value: v.value.clone().unwrap(),
-                        options: None,
-                    })
-                    .collect(),
-            })),
-        },
-        TestEntityOpType::UNSET => Op {
-            payload: Some(Payload::UnsetEntityValues(UnsetEntityValues {
-                id: Uuid::parse_str(entity).unwrap().as_bytes().to_vec(),
-                properties: values
-                    .iter()
-                    .map(|v| Uuid::parse_str(&v.property_id).unwrap().as_bytes().to_vec())
-                    .collect(),
-            })),
-        },
-    }
-}
-
-fn make_property_op(property_id: &str, property_type: PbDataType) -> Op {
-    Op {
-        payload: Some(Payload::CreateProperty(Property {
-            id: Uuid::parse_str(property_id).unwrap().as_bytes().to_vec(),
-            data_type: property_type as i32,
-        })),
-    }
-}
-
-enum TestRelationOpType {
-    CREATE,
-    UPDATE,
-    DELETE,
-}
-
-fn make_relation_op(
-    op_type: TestRelationOpType,
-    relation_id: &str,
-    entity_id: &str,
-    type_id: &str,
-    from_entity: &str,
-    to_entity: &str,
-) -> Op {
-    match op_type {
-        TestRelationOpType::CREATE => Op {
-            payload: Some(Payload::CreateRelation(Relation {
-                id: Uuid::parse_str(relation_id).unwrap().as_bytes().to_vec(),
-                r#type: Uuid::parse_str(type_id).unwrap().as_bytes().to_vec(),
-                entity: Uuid::parse_str(entity_id).unwrap().as_bytes().to_vec(),
-                from_entity: Uuid::parse_str(from_entity).unwrap().as_bytes().to_vec(),
-                from_space: None,
-                from_version: None,
-                to_entity: Uuid::parse_str(to_entity).unwrap().as_bytes().to_vec(),
-                to_space: None,
-                to_version: None,
-                position: None,
-                verified: None,
-            })),
-        },
-        TestRelationOpType::UPDATE => Op {
-            payload: Some(Payload::UpdateRelation(wire::pb::grc20::RelationUpdate {
-                id: Uuid::parse_str(relation_id).unwrap().as_bytes().to_vec(),
-                from_space: None,
-                from_version: None,
-                to_space: None,
-                to_version: None,
-                position: None,
-                verified: Some(true),
-            })),
-        },
-        TestRelationOpType::DELETE => Op {
-            payload: Some(Payload::DeleteRelation(
-                Uuid::parse_str(relation_id).unwrap().as_bytes().to_vec(),
-            )),
-        },
-    }
+fn id(id: &str) -> Uuid {
+    Uuid::parse_str(id).unwrap()
 }
 
 // Helper functions for creating spaces
@@ -977,6 +945,8 @@ fn make_personal_space(dao_address: &str) -> CreatedSpace {
         dao_address: dao_address.to_string(),
         space_address: format!("{}_space", dao_address),
         personal_plugin: format!("{}_personal_plugin", dao_address),
+        creator: None,
+        tx_hash: None,
     })
 }
 
@@ -986,31 +956,11 @@ fn make_public_space(dao_address: &str) -> CreatedSpace {
         space_address: format!("{}_space", dao_address),
         membership_plugin: format!("{}_membership_plugin", dao_address),
         governance_plugin: format!("{}_governance_plugin", dao_address),
+        creator: None,
+        tx_hash: None,
     })
 }
 
-fn make_kg_data_with_spaces(
-    block_number: u64,
-    edits: Vec<PreprocessedEdit>,
-    spaces: Vec<CreatedSpace>,
-) -> KgData {
-    KgData {
-        block: BlockMetadata {
-            cursor: block_number.to_string(),
-            block_number,
-            timestamp: "1234567890".to_string(),
-        },
-        edits,
-        spaces,
-        added_editors: vec![],
-        added_members: vec![],
-        removed_editors: vec![],
-        removed_members: vec![],
-        added_subspaces: vec![],
-        removed_subspaces: vec![],
-    }
-}
-
 #[tokio::test]
 #[serial]
 async fn test_space_indexing_personal() -> Result<(), IndexingError> {
@@ -1033,7 +983,7 @@ async fn test_space_indexing_personal() -> Result<(), IndexingError> {
         make_personal_space(&dao_address2),
     ];
 
-    let kg_data = make_kg_data_with_spaces(1, vec![], spaces);
+    let kg_data = KgDataBuilder::new(1).spaces(spaces).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1107,7 +1057,7 @@ async fn test_space_indexing_public() -> Result<(), IndexingError> {
         make_public_space(&dao_address2),
     ];
 
-    let kg_data = make_kg_data_with_spaces(2, vec![], spaces);
+    let kg_data = KgDataBuilder::new(2).spaces(spaces).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1195,7 +1145,7 @@ async fn test_space_indexing_mixed() -> Result<(), IndexingError> {
         make_personal_space(&personal_dao2),
     ];
 
-    let kg_data = make_kg_data_with_spaces(3, vec![], spaces);
+    let kg_data = KgDataBuilder::new(3).spaces(spaces).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1258,7 +1208,7 @@ async fn test_space_indexing_empty() -> Result<(), IndexingError> {
     let indexer = TestIndexer::new(storage.clone(), properties_cache);
 
     // Create test data with no spaces
-    let kg_data = make_kg_data_with_spaces(4, vec![], vec![]);
+    let kg_data = KgDataBuilder::new(4).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1307,30 +1257,6 @@ fn make_removed_member(dao_address: &str, editor_address: &str) -> RemovedMember
     }
 }
 
-fn make_kg_data_with_membership(
-    block_number: u64,
-    added_members: Vec<AddedMember>,
-    removed_members: Vec<RemovedMember>,
-    added_editors: Vec<AddedMember>,
-    removed_editors: Vec<RemovedMember>,
-) -> KgData {
-    KgData {
-        block: BlockMetadata {
-            cursor: block_number.to_string(),
-            block_number,
-            timestamp: "1234567890".to_string(),
-        },
-        edits: vec![],
-        spaces: vec![],
-        added_members,
-        removed_members,
-        added_editors,
-        removed_editors,
-        added_subspaces: vec![],
-        removed_subspaces: vec![],
-    }
-}
-
 #[tokio::test]
 #[serial]
 async fn test_membership_indexing_added_members() -> Result<(), IndexingError> {
@@ -1353,7 +1279,7 @@ async fn test_membership_indexing_added_members() -> Result<(), IndexingError> {
         make_added_member(&dao_address, &member_address2),
     ];
 
-    let kg_data = make_kg_data_with_membership(1, added_members, vec![], vec![], vec![]);
+    let kg_data = KgDataBuilder::new(1).added_members(added_members).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1398,7 +1324,7 @@ async fn test_membership_indexing_added_editors() -> Result<(), IndexingError> {
         make_added_member(&dao_address, &editor_address2),
     ];
 
-    let kg_data = make_kg_data_with_membership(1, vec![], vec![], added_editors, vec![]);
+    let kg_data = KgDataBuilder::new(1).added_editors(added_editors).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1439,11 +1365,13 @@ async fn test_membership_indexing_removed_members() -> Result<(), IndexingError>
 
     // First add a member
     let added_members = vec![make_added_member(&dao_address, &member_address)];
-    let kg_data_add = make_kg_data_with_membership(1, added_members, vec![], vec![], vec![]);
+    let kg_data_add = KgDataBuilder::new(1).added_members(added_members).build();
 
     // Then remove the member
     let removed_members = vec![make_removed_member(&dao_address, &member_address)];
-    let kg_data_remove = make_kg_data_with_membership(2, vec![], removed_members, vec![], vec![]);
+    let kg_data_remove = KgDataBuilder::new(2)
+        .removed_members(removed_members)
+        .build();
 
     let blocks = vec![kg_data_add, kg_data_remove];
 
@@ -1480,11 +1408,13 @@ async fn test_membership_indexing_removed_editors() -> Result<(), IndexingError>
 
     // First add an editor
     let added_editors = vec![make_added_member(&dao_address, &editor_address)];
-    let kg_data_add = make_kg_data_with_membership(1, vec![], vec![], added_editors, vec![]);
+    let kg_data_add = KgDataBuilder::new(1).added_editors(added_editors).build();
 
     // Then remove the editor
     let removed_editors = vec![make_removed_member(&dao_address, &editor_address)];
-    let kg_data_remove = make_kg_data_with_membership(2, vec![], vec![], vec![], removed_editors);
+    let kg_data_remove = KgDataBuilder::new(2)
+        .removed_editors(removed_editors)
+        .build();
 
     let blocks = vec![kg_data_add, kg_data_remove];
 
@@ -1538,13 +1468,12 @@ async fn test_membership_indexing_mixed_operations() -> Result<(), IndexingError
         make_removed_member(&dao_address, &editor_address1), // Remove first editor
     ];
 
-    let kg_data = make_kg_data_with_membership(
-        1,
-        added_members,
-        removed_members,
-        added_editors,
-        removed_editors,
-    );
+    let kg_data = KgDataBuilder::new(1)
+        .added_members(added_members)
+        .removed_members(removed_members)
+        .added_editors(added_editors)
+        .removed_editors(removed_editors)
+        .build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1607,7 +1536,10 @@ async fn test_membership_indexing_multiple_spaces() -> Result<(), IndexingError>
         make_added_member(&dao_address2, &editor_address), // Same editor in different spaces
     ];
 
-    let kg_data = make_kg_data_with_membership(1, added_members, vec![], added_editors, vec![]);
+    let kg_data = KgDataBuilder::new(1)
+        .added_members(added_members)
+        .added_editors(added_editors)
+        .build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1643,6 +1575,81 @@ async fn test_membership_indexing_multiple_spaces() -> Result<(), IndexingError>
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn test_membership_indexing_bulk() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_addresses: Vec<String> = (0..4)
+        .map(|_| generate_unique_address("bulk_membership_dao"))
+        .collect();
+    let addresses: Vec<String> = (0..50)
+        .map(|_| generate_unique_address("bulk_membership_editor"))
+        .collect();
+    let space_ids: Vec<Uuid> = dao_addresses
+        .iter()
+        .map(|dao_address| derive_space_id(GEO, &checksum_address(dao_address.to_string())))
+        .collect();
+
+    // Every address joins every space, and each event is seen twice
+    let added: Vec<AddedMember> = dao_addresses
+        .iter()
+        .flat_map(|dao_address| {
+            addresses
+                .iter()
+                .map(|address| make_added_member(dao_address, address))
+        })
+        .flat_map(|member| [member.clone(), member])
+        .collect();
+    let removed: Vec<RemovedMember> = dao_addresses
+        .iter()
+        .flat_map(|dao_address| {
+            addresses[..25]
+                .iter()
+                .map(|address| make_removed_member(dao_address, address))
+        })
+        .collect();
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .added_members(added.clone())
+                .added_editors(added)
+                .build(),
+            KgDataBuilder::new(2)
+                .removed_members(removed.clone())
+                .removed_editors(removed)
+                .build(),
+        ])
+        .await?;
+
+    for table in ["members", "editors"] {
+        let count: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM {} WHERE space_id = ANY($1)",
+            table
+        ))
+        .bind(&space_ids)
+        .fetch_one(storage.get_pool())
+        .await?;
+        assert_eq!(count, 100, "{}", table);
+    }
+
+    let history: Vec<(i64, Option<i64>, i64)> = sqlx::query_as(
+        "SELECT added_at_block, removed_at_block, COUNT(*) FROM editor_history
+        WHERE space_id = ANY($1) GROUP BY 1, 2 ORDER BY 2",
+    )
+    .bind(&space_ids)
+    .fetch_all(storage.get_pool())
+    .await?;
+    assert_eq!(history, vec![(1, Some(2), 100), (1, None, 100)]);
+
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn test_membership_indexing_empty() -> Result<(), IndexingError> {
@@ -1653,7 +1660,7 @@ async fn test_membership_indexing_empty() -> Result<(), IndexingError> {
     let properties_cache = Arc::new(PropertiesCache::new());
     let indexer = TestIndexer::new(postgres_storage, properties_cache);
 
-    let kg_data = make_kg_data_with_membership(1, vec![], vec![], vec![], vec![]);
+    let kg_data = KgDataBuilder::new(1).build();
     let blocks = vec![kg_data];
 
     // Run the indexer - should not fail with empty membership data
@@ -1678,7 +1685,7 @@ async fn test_space_indexing_duplicate_dao_addresses() -> Result<(), IndexingErr
         make_public_space(&dao_address),
     ];
 
-    let kg_data = make_kg_data_with_spaces(5, vec![], spaces);
+    let kg_data = KgDataBuilder::new(5).spaces(spaces).build();
     let blocks = vec![kg_data];
 
     // Run the indexer - this should work since space IDs are derived differently
@@ -1697,23 +1704,21 @@ async fn test_space_indexing_with_edits() -> Result<(), IndexingError> {
     let properties_cache = Arc::new(PropertiesCache::new());
     let indexer = TestIndexer::new(storage.clone(), properties_cache);
 
-    // Create some property operations
     let property_id = "1cc6995f-6cc2-4c7a-9592-1466bf95f6be";
-    let property_op = make_property_op(property_id, PbDataType::Text);
-
     // Create a test edit
-    let edit = make_edit(
-        "08c4f093-7858-4b7c-9b94-b82e448abcff",
-        "Test Edit",
-        "2cc6995f-6cc2-4c7a-9592-1466bf95f6be",
-        vec![property_op],
-    );
+    let edit = EditBuilder::new(id("08c4f093-7858-4b7c-9b94-b82e448abcff"))
+        .name("Test Edit")
+        .author(id("2cc6995f-6cc2-4c7a-9592-1466bf95f6be"))
+        .create_property(id(property_id), PbDataType::Text)
+        .build();
 
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("3cc6995f-6cc2-4c7a-9592-1466bf95f6be").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     // Create spaces alongside edits
@@ -1722,7 +1727,7 @@ async fn test_space_indexing_with_edits() -> Result<(), IndexingError> {
         make_public_space(&generate_unique_address("space_with_edits_test_public")),
     ];
 
-    let kg_data = make_kg_data_with_spaces(6, vec![item], spaces);
+    let kg_data = KgDataBuilder::new(6).edit(item).spaces(spaces).build();
     let blocks = vec![kg_data];
 
     // Run the indexer
@@ -1745,28 +1750,6 @@ fn make_removed_subspace(dao_address: &str, subspace_address: &str) -> RemovedSu
     }
 }
 
-fn make_kg_data_with_subspaces(
-    block_number: u64,
-    added_subspaces: Vec<AddedSubspace>,
-    removed_subspaces: Vec<RemovedSubspace>,
-) -> KgData {
-    KgData {
-        block: BlockMetadata {
-            cursor: block_number.to_string(),
-            block_number,
-            timestamp: "1234567890".to_string(),
-        },
-        edits: vec![],
-        spaces: vec![],
-        added_members: vec![],
-        removed_members: vec![],
-        added_editors: vec![],
-        removed_editors: vec![],
-        added_subspaces,
-        removed_subspaces,
-    }
-}
-
 #[tokio::test]
 #[serial]
 async fn test_subspace_indexing_added_subspaces() -> Result<(), IndexingError> {
@@ -1791,14 +1774,16 @@ async fn test_subspace_indexing_added_subspaces() -> Result<(), IndexingError> {
         make_personal_space(&subspace_address1),
         make_personal_space(&subspace_address2),
     ];
-    let kg_data_spaces = make_kg_data_with_spaces(1, vec![], spaces);
+    let kg_data_spaces = KgDataBuilder::new(1).spaces(spaces).build();
 
     // Then create the subspace relationships
     let added_subspaces = vec![
         make_added_subspace(&parent_dao_address, &subspace_address1),
         make_added_subspace(&parent_dao_address, &subspace_address2),
     ];
-    let kg_data_subspaces = make_kg_data_with_subspaces(2, added_subspaces, vec![]);
+    let kg_data_subspaces = KgDataBuilder::new(2)
+        .added_subspaces(added_subspaces)
+        .build();
 
     let blocks = vec![kg_data_spaces, kg_data_subspaces];
 
@@ -1830,18 +1815,22 @@ async fn test_subspace_indexing_removed_subspaces() -> Result<(), IndexingError>
         make_personal_space(&parent_dao_address),
         make_personal_space(&subspace_address),
     ];
-    let kg_data_spaces = make_kg_data_with_spaces(1, vec![], spaces);
+    let kg_data_spaces = KgDataBuilder::new(1).spaces(spaces).build();
 
     // Then add a subspace
     let added_subspaces = vec![make_added_subspace(&parent_dao_address, &subspace_address)];
-    let kg_data_add = make_kg_data_with_subspaces(2, added_subspaces, vec![]);
+    let kg_data_add = KgDataBuilder::new(2)
+        .added_subspaces(added_subspaces)
+        .build();
 
     // Then remove the subspace
     let removed_subspaces = vec![make_removed_subspace(
         &parent_dao_address,
         &subspace_address,
     )];
-    let kg_data_remove = make_kg_data_with_subspaces(3, vec![], removed_subspaces);
+    let kg_data_remove = KgDataBuilder::new(3)
+        .removed_subspaces(removed_subspaces)
+        .build();
 
     let blocks = vec![kg_data_spaces, kg_data_add, kg_data_remove];
 
@@ -1877,7 +1866,7 @@ async fn test_subspace_indexing_mixed_operations() -> Result<(), IndexingError>
         make_personal_space(&subspace_address2),
         make_personal_space(&subspace_address3),
     ];
-    let kg_data_spaces = make_kg_data_with_spaces(1, vec![], spaces);
+    let kg_data_spaces = KgDataBuilder::new(1).spaces(spaces).build();
 
     // Then create subspace relationships with mixed operations
     let added_subspaces = vec![
@@ -1888,7 +1877,10 @@ async fn test_subspace_indexing_mixed_operations() -> Result<(), IndexingError>
     let removed_subspaces = vec![
         make_removed_subspace(&parent_dao_address, &subspace_address1), // Remove first subspace
     ];
-    let kg_data_subspaces = make_kg_data_with_subspaces(2, added_subspaces, removed_subspaces);
+    let kg_data_subspaces = KgDataBuilder::new(2)
+        .added_subspaces(added_subspaces)
+        .removed_subspaces(removed_subspaces)
+        .build();
 
     let blocks = vec![kg_data_spaces, kg_data_subspaces];
 
@@ -1922,14 +1914,16 @@ async fn test_subspace_indexing_multiple_parents() -> Result<(), IndexingError>
         make_personal_space(&parent_dao_address2),
         make_personal_space(&subspace_address),
     ];
-    let kg_data_spaces = make_kg_data_with_spaces(1, vec![], spaces);
+    let kg_data_spaces = KgDataBuilder::new(1).spaces(spaces).build();
 
     // Then create subspace relationships
     let added_subspaces = vec![
         make_added_subspace(&parent_dao_address1, &subspace_address),
         make_added_subspace(&parent_dao_address2, &subspace_address), // Same subspace in different parent spaces
     ];
-    let kg_data_subspaces = make_kg_data_with_subspaces(2, added_subspaces, vec![]);
+    let kg_data_subspaces = KgDataBuilder::new(2)
+        .added_subspaces(added_subspaces)
+        .build();
 
     let blocks = vec![kg_data_spaces, kg_data_subspaces];
 
@@ -1939,6 +1933,84 @@ async fn test_subspace_indexing_multiple_parents() -> Result<(), IndexingError>
     Ok(())
 }
 
+#[tokio::test]
+#[serial]
+async fn test_subspace_closure() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let postgres_storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let test_storage = TestStorage::new(postgres_storage.clone());
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(postgres_storage.clone(), properties_cache);
+
+    test_storage.clear_table("subspace_closure").await?;
+    test_storage.clear_table("subspaces").await?;
+    test_storage.clear_table("spaces").await?;
+
+    let root = generate_unique_address("closure_test_root");
+    let middle = generate_unique_address("closure_test_middle");
+    let leaf = generate_unique_address("closure_test_leaf");
+    let space_id = |address: &str| Network::default().space_id(&checksum_address(address));
+
+    let spaces = vec![
+        make_personal_space(&root),
+        make_personal_space(&middle),
+        make_personal_space(&leaf),
+    ];
+
+    // The leaf is added below the middle space before the middle space is
+    // attached to the root, and a cycle back to the root is ignored
+    let blocks = vec![
+        KgDataBuilder::new(1).spaces(spaces).build(),
+        KgDataBuilder::new(2)
+            .added_subspaces(vec![make_added_subspace(&middle, &leaf)])
+            .build(),
+        KgDataBuilder::new(3)
+            .added_subspaces(vec![
+                make_added_subspace(&root, &middle),
+                make_added_subspace(&leaf, &root),
+            ])
+            .build(),
+    ];
+
+    indexer.run(&blocks).await?;
+
+    assert_eq!(
+        postgres_storage
+            .get_descendant_spaces(&space_id(&root))
+            .await?,
+        vec![space_id(&middle), space_id(&leaf)]
+    );
+    assert_eq!(
+        postgres_storage
+            .get_ancestor_spaces(&space_id(&leaf))
+            .await?,
+        vec![space_id(&middle), space_id(&root)]
+    );
+
+    let blocks = vec![KgDataBuilder::new(4)
+        .removed_subspaces(vec![
+            make_removed_subspace(&root, &middle),
+            make_removed_subspace(&leaf, &root),
+        ])
+        .build()];
+
+    indexer.run(&blocks).await?;
+
+    assert!(postgres_storage
+        .get_descendant_spaces(&space_id(&root))
+        .await?
+        .is_empty());
+    assert_eq!(
+        postgres_storage
+            .get_ancestor_spaces(&space_id(&leaf))
+            .await?,
+        vec![space_id(&middle)]
+    );
+
+    Ok(())
+}
+
 #[tokio::test]
 #[serial]
 async fn test_subspace_indexing_empty() -> Result<(), IndexingError> {
@@ -1949,7 +2021,7 @@ async fn test_subspace_indexing_empty() -> Result<(), IndexingError> {
     let properties_cache = Arc::new(PropertiesCache::new());
     let indexer = TestIndexer::new(postgres_storage, properties_cache);
 
-    let kg_data = make_kg_data_with_subspaces(1, vec![], vec![]);
+    let kg_data = KgDataBuilder::new(1).build();
     let blocks = vec![kg_data];
 
     // Run the indexer - should not fail with empty subspace data
@@ -1999,6 +2071,13 @@ async fn test_subspace_indexing_with_other_operations() -> Result<(), IndexingEr
         removed_editors: vec![],
         added_subspaces,
         removed_subspaces: vec![],
+        personal_admin_changes: vec![],
+        governance_plugin_changes: vec![],
+        created_proposals: vec![],
+        executed_proposals: vec![],
+        canceled_proposals: vec![],
+        network: Network::default(),
+        unowned_properties: vec![],
     };
     let blocks = vec![kg_data];
 
@@ -2016,8 +2095,10 @@ async fn test_properties_cache_initialization_from_database() -> Result<(), Inde
     let storage = Arc::new(PostgresStorage::new(&database_url).await?);
     let test_storage = TestStorage::new(storage.clone());
     
-    // Clear properties table to ensure clean test state
+    // Clear properties table to ensure clean test state. Applied edits are
+    // cleared too so later runs re-index the properties they create.
     test_storage.clear_table("properties").await?;
+    test_storage.clear_table("edits").await?;
     
     // Define test properties with all data types
     let test_properties = vec![
@@ -2033,8 +2114,10 @@ async fn test_properties_cache_initialization_from_database() -> Result<(), Inde
     let properties_cache_empty = Arc::new(PropertiesCache::new());
     let indexer = TestIndexer::new(storage.clone(), properties_cache_empty);
     
-    // Create property operations for each test property
-    let mut property_ops = Vec::new();
+    // Create an edit with a property operation for each test property
+    let mut builder = EditBuilder::new(id("77777777-7777-7777-7777-777777777777"))
+        .name("Properties Cache Test Edit")
+        .author(id("88888888-8888-8888-8888-888888888888"));
     for (property_id, data_type) in &test_properties {
         let pb_data_type = match data_type {
             DataType::String => PbDataType::Text,
@@ -2044,25 +2127,20 @@ async fn test_properties_cache_initialization_from_database() -> Result<(), Inde
             DataType::Point => PbDataType::Point,
             DataType::Relation => PbDataType::Relation,
         };
-        property_ops.push(make_property_op(property_id, pb_data_type));
+        builder = builder.create_property(id(property_id), pb_data_type);
     }
-    
-    // Create an edit with all property operations
-    let edit = make_edit(
-        "77777777-7777-7777-7777-777777777777",
-        "Properties Cache Test Edit",
-        "88888888-8888-8888-8888-888888888888",
-        property_ops,
-    );
+    let edit = builder.build();
     
     let item = PreprocessedEdit {
         edit: Some(edit),
         is_errored: false,
         space_id: Uuid::parse_str("99999999-9999-9999-9999-999999999999").unwrap(),
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
     
-    let kg_data = make_kg_data_with_spaces(1, vec![item], vec![]);
+    let kg_data = KgDataBuilder::new(1).edit(item).build();
     let blocks = vec![kg_data];
     
     // Run the indexer to create properties in database
@@ -2084,7 +2162,7 @@ async fn test_properties_cache_initialization_from_database() -> Result<(), Inde
     // Verify all properties are loaded into the cache
     for (property_id, expected_data_type) in &test_properties {
         let property_uuid = Uuid::parse_str(property_id).unwrap();
-        let cached_data_type = initialized_cache.get(&property_uuid).await
+        let cached_data_type = initialized_cache.get(&property_uuid)
             .map_err(|_| IndexingError::StorageError(StorageError::Database(sqlx::Error::RowNotFound)))?;
         assert_eq!(cached_data_type, *expected_data_type, 
                    "Property {} should have data type {:?} in cache", property_id, expected_data_type);
@@ -2092,7 +2170,7 @@ async fn test_properties_cache_initialization_from_database() -> Result<(), Inde
     
     // Test cache behavior: accessing non-existent property should return error
     let non_existent_id = Uuid::parse_str("aaaaaaaa-aaaa-aaaa-aaaa-aaaaaaaaaaaa").unwrap();
-    let result = initialized_cache.get(&non_existent_id).await;
+    let result = initialized_cache.get(&non_existent_id);
     assert!(result.is_err(), "Non-existent property should return error");
     
     // Test empty database scenario
@@ -2101,8 +2179,4305 @@ async fn test_properties_cache_initialization_from_database() -> Result<(), Inde
         .map_err(|e| IndexingError::StorageError(e))?;
     
     // Any property lookup should fail on empty cache
-    let result = empty_cache.get(&test_properties[0].0.parse().unwrap()).await;
+    let result = empty_cache.get(&test_properties[0].0.parse().unwrap());
     assert!(result.is_err(), "Empty cache should return error for any property");
     
     Ok(())
 }
+
+#[tokio::test]
+#[serial]
+async fn test_properties_cache_refresh() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+
+    let cache = PropertiesCache::from_storage(&storage).await?;
+    let cached = Uuid::new_v4();
+    let created_later = Uuid::new_v4();
+    cache.insert(&cached, DataType::String).await;
+
+    // Written by another instance after the cache was built
+    let mut tx = storage.pool.begin().await?;
+    storage
+        .insert_properties(
+            &vec![
+                PropertyItem {
+                    id: created_later,
+                    data_type: DataType::Number,
+                },
+                PropertyItem {
+                    id: cached,
+                    data_type: DataType::Relation,
+                },
+            ],
+            &mut tx,
+        )
+        .await?;
+    tx.commit().await?;
+    assert!(cache.get(&created_later).is_err());
+
+    assert_eq!(cache.refresh(&storage).await?, 1);
+    assert_eq!(cache.get(&created_later).unwrap(), DataType::Number);
+    // Cached properties are immutable until they are invalidated
+    assert_eq!(cache.get(&cached).unwrap(), DataType::String);
+
+    cache.invalidate(&cached);
+    cache.refresh(&storage).await?;
+    assert_eq!(cache.get(&cached).unwrap(), DataType::Relation);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_shard_lease_is_exclusive() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = PostgresStorage::new(&database_url).await?;
+    let key = "kg_indexer:shard-0-of-2:test";
+    let retry_interval = Duration::from_millis(50);
+
+    let lease = ShardLease::acquire(&storage.pool, key, retry_interval).await?;
+
+    // Another instance stands by while the lease is held
+    let standby = tokio::time::timeout(
+        Duration::from_millis(500),
+        ShardLease::acquire(&storage.pool, key, retry_interval),
+    )
+    .await;
+    assert!(standby.is_err());
+
+    // and takes over once it is released
+    drop(lease);
+    let standby = tokio::time::timeout(
+        Duration::from_secs(5),
+        ShardLease::acquire(&storage.pool, key, retry_interval),
+    )
+    .await;
+    assert!(matches!(standby, Ok(Ok(_))));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_shards_use_properties_created_in_other_shards() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let network = Network::default();
+
+    // Each shard runs with its own properties cache, like separate instances
+    let shards: Vec<(Shard, TestIndexer)> = (0..2)
+        .map(|index| {
+            let cache = Arc::new(PropertiesCache::new());
+            (Shard::new(index, 2), TestIndexer::new(storage.clone(), cache))
+        })
+        .collect();
+    let dao_in_shard = |index: u32| {
+        (0..)
+            .map(|_| generate_unique_address("sharded_properties_test"))
+            .find(|dao| Shard::of(&network.space_id(&checksum_address(dao)), 2) == index)
+            .unwrap()
+    };
+    let (first_dao, second_dao) = (dao_in_shard(0), dao_in_shard(1));
+    let second_space = network.space_id(&checksum_address(&second_dao));
+    let (property, entity) = (Uuid::new_v4(), Uuid::new_v4());
+
+    // The property is created in the first shard's space and used in the
+    // second shard's space in the same block
+    let create = EditBuilder::new(Uuid::new_v4())
+        .create_property(property, PbDataType::Text)
+        .build();
+    let update = EditBuilder::new(Uuid::new_v4())
+        .update_entity(entity, [value(property, "Sharded")])
+        .build();
+    let blocks = vec![
+        KgDataBuilder::new(1)
+            .public_space(&first_dao)
+            .public_space(&second_dao)
+            .build(),
+        KgDataBuilder::new(2)
+            .edits(vec![
+                PreprocessedEdit {
+                    edit: Some(create),
+                    is_errored: false,
+                    space_id: network.space_id(&checksum_address(&first_dao)),
+                    cid: "ipfs://sharded-properties-create".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+                PreprocessedEdit {
+                    edit: Some(update),
+                    is_errored: false,
+                    space_id: second_space,
+                    cid: "ipfs://sharded-properties-update".to_string(),
+                    sender: None,
+                    event_index: 1,
+                },
+            ])
+            .build(),
+    ];
+
+    for (shard, indexer) in &shards {
+        let filtered = blocks.iter().cloned().map(|block| shard.filter(block));
+        indexer.run(&filtered.collect()).await?;
+    }
+
+    let value = storage
+        .get_value(&derive_value_id(&entity, &property, &second_space).to_string())
+        .await?;
+    assert_eq!(value.string, Some("Sharded".to_string()));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_delete_entity_cascades_within_space() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let space_id = Uuid::new_v4();
+    let other_space_id = Uuid::new_v4();
+    let (entity_id, other_entity_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let (name, relation_type) = (Uuid::new_v4(), Uuid::new_v4());
+    let (deleted_relation, kept_relation, created_relation) =
+        (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let setup = EditBuilder::new(Uuid::new_v4())
+        .create_property(name, PbDataType::Text)
+        .create_property(relation_type, PbDataType::Relation)
+        .update_entity(entity_id, [value(name, "Alice")])
+        .update_entity(other_entity_id, [value(name, "Bob")])
+        .create_relation(relation(
+            deleted_relation,
+            Uuid::new_v4(),
+            relation_type,
+            other_entity_id,
+            entity_id,
+        ))
+        .create_relation(relation(
+            kept_relation,
+            Uuid::new_v4(),
+            relation_type,
+            other_entity_id,
+            other_entity_id,
+        ))
+        .build();
+    let other_space_setup = EditBuilder::new(Uuid::new_v4())
+        .update_entity(entity_id, [value(name, "Alice")])
+        .build();
+    // Ops before the delete are dropped, ops after it still apply
+    let delete = EditBuilder::new(Uuid::new_v4())
+        .update_entity(entity_id, [value(name, "Dropped")])
+        .delete_entity(entity_id)
+        .create_relation(relation(
+            created_relation,
+            Uuid::new_v4(),
+            relation_type,
+            entity_id,
+            other_entity_id,
+        ))
+        .build();
+
+    let edit = |edit: &wire::pb::grc20::Edit, space_id: Uuid, cid: &str| PreprocessedEdit {
+        edit: Some(edit.clone()),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .edits(vec![
+                    edit(&setup, space_id, "ipfs://setup"),
+                    edit(&other_space_setup, other_space_id, "ipfs://other-setup"),
+                ])
+                .build(),
+            KgDataBuilder::new(2)
+                .edits(vec![edit(&delete, space_id, "ipfs://delete")])
+                .build(),
+        ])
+        .await?;
+
+    let value_id =
+        |entity_id: &Uuid, space_id: &Uuid| derive_value_id(entity_id, &name, space_id).to_string();
+
+    assert!(storage
+        .get_value(&value_id(&entity_id, &space_id))
+        .await
+        .is_err());
+    assert!(storage
+        .get_relation(&deleted_relation.to_string())
+        .await
+        .is_err());
+
+    // Other entities and other spaces are untouched
+    assert_eq!(
+        storage
+            .get_value(&value_id(&other_entity_id, &space_id))
+            .await?
+            .string,
+        Some("Bob".to_string())
+    );
+    assert_eq!(
+        storage
+            .get_value(&value_id(&entity_id, &other_space_id))
+            .await?
+            .string,
+        Some("Alice".to_string())
+    );
+    assert!(storage
+        .get_relation(&kept_relation.to_string())
+        .await
+        .is_ok());
+    assert!(storage
+        .get_relation(&created_relation.to_string())
+        .await
+        .is_ok());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_last_edited_is_bubbled_to_types_and_spaces() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("last_edited_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (person, person_type, friend) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let (name, friends, friend_relation) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let create = EditBuilder::new(Uuid::new_v4())
+        .create_property(name, PbDataType::Text)
+        .create_property(friends, PbDataType::Relation)
+        .update_entity(person, [value(name, "Alice")])
+        .create_relation(relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            TYPES_PROPERTY,
+            person,
+            person_type,
+        ))
+        .create_relation(relation(
+            friend_relation,
+            Uuid::new_v4(),
+            friends,
+            person,
+            friend,
+        ))
+        .build();
+    let unfriend = EditBuilder::new(Uuid::new_v4())
+        .delete_relation(friend_relation)
+        .build();
+
+    let edit_item = |edit: Edit, cid: &str| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    let last_edited_block = |table: &'static str, id: Uuid| {
+        let storage = storage.clone();
+        async move {
+            sqlx::query_scalar::<_, Option<String>>(&format!(
+                "SELECT last_edited_block FROM {} WHERE id = $1",
+                table
+            ))
+            .bind(id)
+            .fetch_one(storage.get_pool())
+            .await
+            .unwrap()
+        }
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            KgDataBuilder::new(2)
+                .edits(vec![edit_item(create, "ipfs://last-edited-create")])
+                .build(),
+        ])
+        .await?;
+
+    assert_eq!(last_edited_block("entities", person).await, Some("2".to_string()));
+    assert_eq!(last_edited_block("entities", person_type).await, Some("2".to_string()));
+    assert_eq!(last_edited_block("spaces", space_id).await, Some("2".to_string()));
+    // Entities that are only referenced aren't edited
+    assert_eq!(last_edited_block("entities", friend).await, None);
+    assert_eq!(last_edited_block("entities", name).await, None);
+
+    indexer
+        .run(&vec![KgDataBuilder::new(3)
+            .edits(vec![edit_item(unfriend, "ipfs://last-edited-unfriend")])
+            .build()])
+        .await?;
+
+    // The deleted relation was from the person, whose type is stored
+    assert_eq!(last_edited_block("entities", person).await, Some("3".to_string()));
+    assert_eq!(last_edited_block("entities", person_type).await, Some("3".to_string()));
+    assert_eq!(last_edited_block("spaces", space_id).await, Some("3".to_string()));
+    assert_eq!(last_edited_block("entities", friend).await, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_concurrent_edits_sharing_types() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let (first_address, second_address) = (
+        generate_unique_address("concurrent_types_first"),
+        generate_unique_address("concurrent_types_second"),
+    );
+    let first_space = Network::default().space_id(&checksum_address(&first_address));
+    let second_space = Network::default().space_id(&checksum_address(&second_address));
+    let name = Uuid::new_v4();
+    let (low, high) = {
+        let (a, b) = (Uuid::new_v4(), Uuid::new_v4());
+        (a.min(b), a.max(b))
+    };
+
+    let edit_item = |edit: Edit, space_id: Uuid, cid: String| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid,
+        sender: None,
+        event_index: 0,
+    };
+    // Each space types its entity with the other space's, so an edit of one
+    // writes the entity the other edit writes first
+    let typed = |space_id: Uuid, entity: Uuid, entity_type: Uuid| {
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .create_property(name, PbDataType::Text)
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                TYPES_PROPERTY,
+                entity,
+                entity_type,
+            ))
+            .build();
+        edit_item(
+            edit,
+            space_id,
+            format!("ipfs://concurrent-types-{}", space_id),
+        )
+    };
+    let renamed = |space_id: Uuid, entity: Uuid, block: u64| {
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_entity(entity, [value(name, format!("block {}", block))])
+            .build();
+        edit_item(
+            edit,
+            space_id,
+            format!("ipfs://concurrent-types-{}-{}", space_id, block),
+        )
+    };
+
+    let name_in = |space_id: Uuid, entity: Uuid| {
+        let storage = storage.clone();
+        async move {
+            sqlx::query_scalar::<_, Option<String>>(
+                "SELECT string FROM values WHERE space_id = $1 AND entity_id = $2 AND property_id = $3",
+            )
+            .bind(space_id)
+            .bind(entity)
+            .bind(name)
+            .fetch_optional(storage.get_pool())
+            .await
+            .unwrap()
+            .flatten()
+        }
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![
+                    make_public_space(&first_address),
+                    make_public_space(&second_address),
+                ])
+                .build(),
+            KgDataBuilder::new(2)
+                .edits(vec![
+                    typed(first_space, low, high),
+                    typed(second_space, high, low),
+                ])
+                .build(),
+        ])
+        .await?;
+
+    // The spaces' edits of a block are written concurrently, and neither is
+    // lost to the other
+    for block in 3..23 {
+        indexer
+            .run(&vec![KgDataBuilder::new(block)
+                .edits(vec![
+                    renamed(first_space, low, block),
+                    renamed(second_space, high, block),
+                ])
+                .build()])
+            .await?;
+
+        let expected = Some(format!("block {}", block));
+        assert_eq!(name_in(first_space, low).await, expected);
+        assert_eq!(name_in(second_space, high).await, expected);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_relation_upserts_within_a_block() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("relation_upserts_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (person, friend, friends, friend_relation) =
+        (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let (from_version, to_version, new_to_version) =
+        (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let create = EditBuilder::new(Uuid::new_v4())
+        .create_property(friends, PbDataType::Relation)
+        .create_relation(Relation {
+            from_version: Some(from_version.as_bytes().to_vec()),
+            to_version: Some(to_version.as_bytes().to_vec()),
+            position: Some("a".to_string()),
+            ..relation(friend_relation, Uuid::new_v4(), friends, person, friend)
+        })
+        .build();
+    let reorder = EditBuilder::new(Uuid::new_v4())
+        .update_relation(RelationUpdate {
+            to_version: Some(new_to_version.as_bytes().to_vec()),
+            position: Some("b".to_string()),
+            ..relation_update(friend_relation)
+        })
+        .build();
+    let verify = EditBuilder::new(Uuid::new_v4())
+        .update_relation(RelationUpdate {
+            verified: Some(true),
+            ..relation_update(friend_relation)
+        })
+        .build();
+
+    let edit_item = |edit: Edit, cid: &str, event_index: usize| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index,
+    };
+
+    // The relation is created and updated by two edits of the same block
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            KgDataBuilder::new(2)
+                .edits(vec![
+                    edit_item(create, "ipfs://relation-upserts-create", 0),
+                    edit_item(reorder, "ipfs://relation-upserts-reorder", 1),
+                ])
+                .build(),
+            KgDataBuilder::new(3)
+                .edits(vec![edit_item(verify, "ipfs://relation-upserts-verify", 0)])
+                .build(),
+        ])
+        .await?;
+
+    let stored = storage.get_relation(&friend_relation.to_string()).await?;
+    assert_eq!(stored.from_version_id, Some(from_version.to_string()));
+    assert_eq!(stored.to_version_id, Some(new_to_version.to_string()));
+    assert_eq!(stored.position, Some("b".to_string()));
+    assert_eq!(stored.verified, Some(true));
+
+    // Batches that list a relation more than once are written in one
+    // statement, with the rows applied in order
+    let mut tx = storage.get_pool().begin().await.unwrap();
+    let upsert = |position: &str| SetRelationItem {
+        position: Some(position.to_string()),
+        ..stored.clone()
+    };
+    // A relation's type, entity and endpoints are kept when it's created again
+    let recreated = SetRelationItem {
+        type_id: Uuid::new_v4(),
+        to_id: Uuid::new_v4(),
+        ..upsert("d")
+    };
+    storage
+        .insert_relations(&vec![upsert("c"), recreated], &mut tx)
+        .await?;
+    let update = UpdateRelationItem {
+        id: friend_relation,
+        space_id,
+        from_space_id: None,
+        from_version_id: None,
+        to_space_id: None,
+        to_version_id: None,
+        position: None,
+        verified: None,
+    };
+    storage
+        .update_relations(
+            &vec![
+                UpdateRelationItem {
+                    position: Some("e".to_string()),
+                    verified: Some(true),
+                    ..update.clone()
+                },
+                UpdateRelationItem {
+                    verified: Some(false),
+                    ..update.clone()
+                },
+            ],
+            &mut tx,
+        )
+        .await?;
+    tx.commit().await.unwrap();
+
+    let stored = storage.get_relation(&friend_relation.to_string()).await?;
+    assert_eq!(stored.position, Some("e".to_string()));
+    assert_eq!(stored.verified, Some(false));
+    assert_eq!(stored.to_version_id, Some(new_to_version.to_string()));
+    assert_eq!((stored.type_id, stored.to_id), (friends, friend));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_property_metadata_is_indexed() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let space_id = Uuid::new_v4();
+    let (property_id, renderable_type) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let create = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .create_property(DESCRIPTION_PROPERTY, PbDataType::Text)
+        .create_property(RENDERABLE_TYPE_PROPERTY, PbDataType::Relation)
+        .create_property(property_id, PbDataType::Text)
+        .update_entity(
+            property_id,
+            [
+                value(NAME_PROPERTY, "Website"),
+                value(DESCRIPTION_PROPERTY, "The website of a project"),
+            ],
+        )
+        .create_relation(relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            RENDERABLE_TYPE_PROPERTY,
+            property_id,
+            renderable_type,
+        ))
+        .build();
+    let unset = EditBuilder::new(Uuid::new_v4())
+        .unset_entity_values(property_id, [DESCRIPTION_PROPERTY])
+        .build();
+
+    let edit = |edit: &wire::pb::grc20::Edit, cid: &str| PreprocessedEdit {
+        edit: Some(edit.clone()),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![edit(&create, "ipfs://create")])
+            .build()])
+        .await?;
+
+    assert_eq!(
+        storage.get_property_metadata(&property_id).await?,
+        PropertyMetadata {
+            name: Some("Website".to_string()),
+            description: Some("The website of a project".to_string()),
+            renderable_type: Some(renderable_type),
+        }
+    );
+
+    indexer
+        .run(&vec![KgDataBuilder::new(2)
+            .edits(vec![edit(&unset, "ipfs://unset")])
+            .build()])
+        .await?;
+
+    let metadata = storage.get_property_metadata(&property_id).await?;
+    assert_eq!(metadata.name, Some("Website".to_string()));
+    assert_eq!(metadata.description, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_type_schema() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let (type_id, age, email) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let attach = |property: Uuid, position: &str| Relation {
+        position: Some(position.to_string()),
+        ..relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            PROPERTIES_PROPERTY,
+            type_id,
+            property,
+        )
+    };
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .create_property(TYPES_PROPERTY, PbDataType::Relation)
+        .create_property(PROPERTIES_PROPERTY, PbDataType::Relation)
+        .create_property(age, PbDataType::Number)
+        .create_property(email, PbDataType::Text)
+        .update_entity(type_id, [value(NAME_PROPERTY, "Person")])
+        .update_entity(email, [value(NAME_PROPERTY, "Email")])
+        .create_relation(relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            TYPES_PROPERTY,
+            type_id,
+            SCHEMA_TYPE,
+        ))
+        .create_relation(attach(age, "b"))
+        .create_relation(attach(email, "a"))
+        .build();
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![PreprocessedEdit {
+                edit: Some(edit),
+                is_errored: false,
+                space_id,
+                cid: "ipfs://type".to_string(),
+                sender: None,
+                event_index: 0,
+            }])
+            .build()])
+        .await?;
+
+    let schema = storage
+        .get_type_schema(&type_id, &space_id)
+        .await?
+        .expect("type schema");
+
+    assert_eq!(schema.name, Some("Person".to_string()));
+    assert_eq!(
+        schema
+            .properties
+            .iter()
+            .map(|property| (property.id, property.data_type))
+            .collect::<Vec<_>>(),
+        vec![(email, DataType::String), (age, DataType::Number)]
+    );
+    assert_eq!(
+        schema.properties[0].metadata.name,
+        Some("Email".to_string())
+    );
+
+    // Types are scoped to the spaces they're declared in
+    assert!(storage
+        .get_type_schema(&type_id, &other_space_id)
+        .await?
+        .is_none());
+    assert!(storage.get_type_schema(&age, &space_id).await?.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_space_metadata_is_indexed() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("space_metadata_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (space_entity, cover) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .create_property(DESCRIPTION_PROPERTY, PbDataType::Text)
+        .create_property(IMAGE_URL_PROPERTY, PbDataType::Text)
+        .update_entity(
+            space_entity,
+            [
+                value(NAME_PROPERTY, "Crypto"),
+                value(DESCRIPTION_PROPERTY, "Everything crypto"),
+            ],
+        )
+        .update_entity(cover, [value(IMAGE_URL_PROPERTY, "ipfs://cover")])
+        .create_relation(relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            TYPES_PROPERTY,
+            space_entity,
+            SPACE_TYPE,
+        ))
+        .create_relation(relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            COVER_PROPERTY,
+            space_entity,
+            cover,
+        ))
+        .build();
+
+    let rename = EditBuilder::new(Uuid::new_v4())
+        .update_entity(space_entity, [value(NAME_PROPERTY, "Crypto News")])
+        .unset_entity_values(space_entity, [DESCRIPTION_PROPERTY])
+        .build();
+
+    let edit_item = |edit: Edit, cid: &str| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            KgDataBuilder::new(2)
+                .edits(vec![edit_item(edit, "ipfs://space-metadata")])
+                .build(),
+        ])
+        .await?;
+
+    assert_eq!(
+        storage.get_space_metadata(&space_id).await?,
+        SpaceMetadata {
+            entity_id: Some(space_entity),
+            name: Some("Crypto".to_string()),
+            description: Some("Everything crypto".to_string()),
+            cover: Some("ipfs://cover".to_string()),
+        }
+    );
+
+    indexer
+        .run(&vec![KgDataBuilder::new(3)
+            .edits(vec![edit_item(rename, "ipfs://space-metadata-rename")])
+            .build()])
+        .await?;
+
+    let metadata = storage.get_space_metadata(&space_id).await?;
+    assert_eq!(metadata.name, Some("Crypto News".to_string()));
+    assert_eq!(metadata.description, None);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cross_space_relations() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let (from_id, to_id, relation_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_relation(Relation {
+            to_space: Some(other_space_id.as_bytes().to_vec()),
+            ..relation(relation_id, Uuid::new_v4(), Uuid::new_v4(), from_id, to_id)
+        })
+        .build();
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![PreprocessedEdit {
+                edit: Some(edit),
+                is_errored: false,
+                space_id,
+                cid: "ipfs://cross-space".to_string(),
+                sender: None,
+                event_index: 0,
+            }])
+            .build()])
+        .await?;
+
+    let outgoing = storage
+        .get_outgoing_relations(&from_id, &space_id, &PageRequest::default())
+        .await?
+        .items;
+    assert_eq!(outgoing.len(), 1);
+    assert_eq!(outgoing[0].id, relation_id);
+    assert_eq!(outgoing[0].to_space(), other_space_id);
+
+    // The relation is stored in its own space but resolves in the target space
+    let incoming = storage
+        .get_incoming_relations(&to_id, &other_space_id)
+        .await?;
+    assert_eq!(incoming.len(), 1);
+    assert_eq!(incoming[0].space_id, space_id);
+    assert!(storage
+        .get_incoming_relations(&to_id, &space_id)
+        .await?
+        .is_empty());
+
+    let mut tx = storage.get_pool().begin().await?;
+    let unknown = storage
+        .find_unknown_spaces(&[other_space_id], &mut tx)
+        .await?;
+    tx.rollback().await?;
+    assert_eq!(unknown, vec![other_space_id]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_neighborhood() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let (a, b, c, d, e, f) = (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+    let (friend, likes) = (Uuid::new_v4(), Uuid::new_v4());
+    let link = |relation_type, from, to| {
+        let id = Uuid::new_v4();
+        (id, relation(id, Uuid::new_v4(), relation_type, from, to))
+    };
+
+    // a -> b -> c -> d with c -> b closing a cycle, and a -> e of another
+    // type
+    let (a_b, a_b_relation) = link(friend, a, b);
+    let (b_c, b_c_relation) = link(friend, b, c);
+    let (c_d, c_d_relation) = link(friend, c, d);
+    let (c_b, c_b_relation) = link(friend, c, b);
+    let (a_e, a_e_relation) = link(likes, a, e);
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_relation(a_b_relation)
+        .create_relation(b_c_relation)
+        .create_relation(c_d_relation)
+        .create_relation(c_b_relation)
+        .create_relation(a_e_relation)
+        .build();
+    let other_edit = EditBuilder::new(Uuid::new_v4())
+        .create_relation(link(friend, a, f).1)
+        .build();
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![
+                PreprocessedEdit {
+                    edit: Some(edit),
+                    is_errored: false,
+                    space_id,
+                    cid: "ipfs://neighborhood".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+                PreprocessedEdit {
+                    edit: Some(other_edit),
+                    is_errored: false,
+                    space_id: other_space_id,
+                    cid: "ipfs://neighborhood-other".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+            ])
+            .build()])
+        .await?;
+
+    let node = |entity_id, depth| NeighborhoodNode { entity_id, depth };
+    let sorted = |mut ids: Vec<Uuid>| {
+        ids.sort();
+        ids
+    };
+    let relation_ids =
+        |neighborhood: &Neighborhood| sorted(neighborhood.relations.iter().map(|r| r.id).collect());
+    let node_ids = |neighborhood: &Neighborhood, depth| {
+        sorted(
+            neighborhood
+                .nodes
+                .iter()
+                .filter(|node| node.depth == depth)
+                .map(|node| node.entity_id)
+                .collect(),
+        )
+    };
+
+    let neighborhood = storage.get_neighborhood(&a, &space_id, 2, None).await?;
+    assert_eq!(neighborhood.nodes[0], node(a, 0));
+    assert_eq!(node_ids(&neighborhood, 1), sorted(vec![b, e]));
+    assert_eq!(node_ids(&neighborhood, 2), vec![c]);
+    assert_eq!(neighborhood.nodes.len(), 4);
+    assert_eq!(
+        relation_ids(&neighborhood),
+        sorted(vec![a_b, b_c, c_b, a_e])
+    );
+
+    // Relations are followed backwards, and the depth is capped
+    let neighborhood = storage
+        .get_neighborhood(&d, &space_id, 10, Some(&[friend]))
+        .await?;
+    assert_eq!(
+        neighborhood.nodes,
+        vec![node(d, 0), node(c, 1), node(b, 2), node(a, 3)]
+    );
+    assert_eq!(
+        relation_ids(&neighborhood),
+        sorted(vec![a_b, b_c, c_d, c_b])
+    );
+
+    let neighborhood = storage.get_neighborhood(&a, &space_id, 0, None).await?;
+    assert_eq!(neighborhood.nodes, vec![node(a, 0)]);
+    assert!(neighborhood.relations.is_empty());
+
+    let neighborhood = storage
+        .get_neighborhood(&a, &other_space_id, 3, None)
+        .await?;
+    assert_eq!(neighborhood.nodes, vec![node(a, 0), node(f, 1)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_list_pagination() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    // Entity ids at the end of the id order so the pages only hold them
+    let entity_ids: Vec<Uuid> = (0..3)
+        .map(|i| Uuid::parse_str(&format!("ffffffff-ffff-4fff-bfff-fffffffffff{}", i)).unwrap())
+        .collect();
+    let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let (name, friend, likes) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let relation_ids: Vec<Uuid> = (0..3).map(|_| Uuid::new_v4()).collect();
+    let valued = Uuid::new_v4();
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(name, PbDataType::Text)
+        .update_entity(entity_ids[0], [value(name, "a")])
+        .update_entity(entity_ids[1], [value(name, "b")])
+        .update_entity(entity_ids[2], [value(name, "c")])
+        .update_entity(valued, [value(name, "d")])
+        .create_relation(relation(
+            relation_ids[0],
+            Uuid::new_v4(),
+            friend,
+            entity_ids[0],
+            entity_ids[1],
+        ))
+        .create_relation(relation(
+            relation_ids[1],
+            Uuid::new_v4(),
+            friend,
+            entity_ids[0],
+            entity_ids[2],
+        ))
+        .create_relation(relation(
+            relation_ids[2],
+            Uuid::new_v4(),
+            likes,
+            entity_ids[1],
+            entity_ids[2],
+        ))
+        .build();
+    let other_edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(name, PbDataType::Text)
+        .update_entity(valued, [value(name, "other")])
+        .build();
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![
+                PreprocessedEdit {
+                    edit: Some(edit),
+                    is_errored: false,
+                    space_id,
+                    cid: "ipfs://pagination".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+                PreprocessedEdit {
+                    edit: Some(other_edit),
+                    is_errored: false,
+                    space_id: other_space_id,
+                    cid: "ipfs://pagination-other".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+            ])
+            .build()])
+        .await?;
+
+    let request = PageRequest {
+        after: Some("ffffffff-ffff-4fff-bfff-ffffffffffef".to_string()),
+        limit: 2,
+    };
+    let page = storage.list_entities(&request).await?;
+    let ids: Vec<Uuid> = page.items.iter().map(|entity| entity.id).collect();
+    assert_eq!(ids, entity_ids[..2]);
+
+    let request = request.next(&page).expect("a next page");
+    let page = storage.list_entities(&request).await?;
+    let ids: Vec<Uuid> = page.items.iter().map(|entity| entity.id).collect();
+    assert_eq!(ids, entity_ids[2..]);
+    assert!(request.next(&page).is_none());
+
+    // The values of an entity across spaces
+    let page = storage
+        .list_values_for_entity(&valued, &PageRequest::first(1))
+        .await?;
+    assert_eq!(page.items.len(), 1);
+    let next = PageRequest::first(1).next(&page).expect("a next page");
+    let last = storage.list_values_for_entity(&valued, &next).await?;
+    assert_eq!(last.items.len(), 1);
+    assert!(last.next_cursor.is_none());
+    let mut spaces = vec![page.items[0].space_id, last.items[0].space_id];
+    spaces.sort();
+    let mut expected = vec![space_id, other_space_id];
+    expected.sort();
+    assert_eq!(spaces, expected);
+
+    // Relations in id order, across pages
+    let mut expected = relation_ids.clone();
+    expected.sort();
+    let mut listed = Vec::new();
+    let mut request = Some(PageRequest::first(2));
+    while let Some(current) = request {
+        let page = storage
+            .list_relations(&space_id, &RelationFilter::default(), &current)
+            .await?;
+        listed.extend(page.items.iter().map(|relation| relation.id));
+        request = current.next(&page);
+    }
+    assert_eq!(listed, expected);
+
+    let filter = RelationFilter {
+        type_id: Some(friend),
+        from_entity_id: Some(entity_ids[0]),
+        ..Default::default()
+    };
+    let page = storage
+        .list_relations(&space_id, &filter, &PageRequest::default())
+        .await?;
+    let mut ids: Vec<Uuid> = page.items.iter().map(|relation| relation.id).collect();
+    ids.sort();
+    let mut expected = relation_ids[..2].to_vec();
+    expected.sort();
+    assert_eq!(ids, expected);
+    assert!(page.next_cursor.is_none());
+
+    assert!(matches!(
+        storage
+            .list_relations(
+                &space_id,
+                &filter,
+                &PageRequest {
+                    after: Some("not-a-cursor".to_string()),
+                    limit: 2,
+                },
+            )
+            .await,
+        Err(StorageError::InvalidCursor(_))
+    ));
+
+    // Relations of a type, across pages
+    let first = storage
+        .get_relations_by_type(&space_id, &friend, &PageRequest::first(1))
+        .await?;
+    let next = PageRequest::first(1).next(&first).expect("a next page");
+    let last = storage
+        .get_relations_by_type(&space_id, &friend, &next)
+        .await?;
+    assert!(last.next_cursor.is_none());
+    let ids: Vec<Uuid> = first
+        .items
+        .iter()
+        .chain(&last.items)
+        .map(|relation| relation.id)
+        .collect();
+    assert_eq!(ids, expected);
+
+    // Relations from an entity, across pages
+    let first = storage
+        .get_outgoing_relations(&entity_ids[0], &space_id, &PageRequest::first(1))
+        .await?;
+    let next = PageRequest::first(1).next(&first).expect("a next page");
+    let last = storage
+        .get_outgoing_relations(&entity_ids[0], &space_id, &next)
+        .await?;
+    assert!(last.next_cursor.is_none());
+    let ids: Vec<Uuid> = first
+        .items
+        .iter()
+        .chain(&last.items)
+        .map(|relation| relation.id)
+        .collect();
+    assert_eq!(ids, expected);
+    assert!(storage
+        .get_outgoing_relations(&entity_ids[0], &other_space_id, &PageRequest::default())
+        .await?
+        .items
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_get_entities_with_values() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let (alice, bob, missing) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let (name, age, friend) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let relation_id = Uuid::new_v4();
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(name, PbDataType::Text)
+        .create_property(age, PbDataType::Number)
+        .update_entity(
+            alice,
+            [
+                Value {
+                    options: Some(Options {
+                        value: Some(options::Value::Text(TextOptions {
+                            language: Some(b"en".to_vec()),
+                        })),
+                    }),
+                    ..value(name, "Alice")
+                },
+                value(age, "42"),
+            ],
+        )
+        .update_entity(bob, [value(name, "Bob")])
+        .create_relation(relation(relation_id, Uuid::new_v4(), friend, alice, bob))
+        .build();
+    let other_edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(name, PbDataType::Text)
+        .update_entity(alice, [value(name, "Alicia")])
+        .build();
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![
+                PreprocessedEdit {
+                    edit: Some(edit),
+                    is_errored: false,
+                    space_id,
+                    cid: "ipfs://hydration".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+                PreprocessedEdit {
+                    edit: Some(other_edit),
+                    is_errored: false,
+                    space_id: other_space_id,
+                    cid: "ipfs://hydration-other".to_string(),
+                    sender: None,
+                    event_index: 0,
+                },
+            ])
+            .build()])
+        .await?;
+
+    let entities = storage
+        .get_entities_with_values(&[bob, missing, alice], &space_id)
+        .await?;
+
+    let ids: Vec<Uuid> = entities.iter().map(|hydrated| hydrated.entity.id).collect();
+    assert_eq!(ids, vec![bob, alice]);
+
+    assert_eq!(entities[0].values.len(), 1);
+    assert_eq!(entities[0].values[0].string, Some("Bob".to_string()));
+    assert!(entities[0].relations.is_empty());
+
+    let alice_entity = &entities[1];
+    let name_value = alice_entity
+        .values
+        .iter()
+        .find(|value| value.property_id == name)
+        .expect("name value");
+    assert_eq!(name_value.string, Some("Alice".to_string()));
+    assert_eq!(name_value.space_id, space_id);
+    assert_eq!(name_value.language, Some("en".to_string()));
+    assert_eq!(
+        name_value.options,
+        Some(serde_json::json!({ "text": { "language": "en" } }))
+    );
+    let age_value = alice_entity
+        .values
+        .iter()
+        .find(|value| value.property_id == age)
+        .expect("age value");
+    assert_eq!(age_value.number, Some(42.0));
+    assert_eq!(alice_entity.values.len(), 2);
+    assert_eq!(alice_entity.relations.len(), 1);
+    assert_eq!(alice_entity.relations[0].id, relation_id);
+    assert_eq!(alice_entity.relations[0].to_id, bob);
+
+    let entities = storage
+        .get_entities_with_values(&[alice], &other_space_id)
+        .await?;
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].values.len(), 1);
+    assert_eq!(entities[0].values[0].string, Some("Alicia".to_string()));
+    assert!(entities[0].relations.is_empty());
+
+    assert!(storage
+        .get_entities_with_values(&[], &space_id)
+        .await?
+        .is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_author_verification() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    let (space_id, author_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let editor = checksum_address(generate_unique_address("verification_editor"));
+    let author = checksum_address(generate_unique_address("verification_author"));
+    let stranger = checksum_address(generate_unique_address("verification_stranger"));
+    let block_editor = checksum_address(generate_unique_address("verification_block"));
+    let block_dao = generate_unique_address("verification_dao");
+    let block_space_id = Network::default().space_id(&checksum_address(&block_dao));
+
+    for (address, space) in [(&editor, space_id), (&author, author_space_id)] {
+        sqlx::query("INSERT INTO editors (address, space_id) VALUES ($1, $2)")
+            .bind(address)
+            .bind(space)
+            .execute(&pool)
+            .await?;
+    }
+
+    let block = |run: &str| {
+        let edit = |name: &str, space_id, sender: Option<&String>, author: Option<Uuid>| {
+            let mut edit = EditBuilder::new(Uuid::new_v4());
+            if let Some(author) = author {
+                edit = edit.author(author);
+            }
+            PreprocessedEdit {
+                cid: format!("ipfs://verification-{}-{}", run, name),
+                edit: Some(edit.build()),
+                is_errored: false,
+                space_id,
+                sender: sender.cloned(),
+                event_index: 0,
+            }
+        };
+
+        let mut data = KgDataBuilder::new(1)
+            .edits(vec![
+                edit("editor", space_id, Some(&editor), None),
+                edit("author", space_id, Some(&author), Some(author_space_id)),
+                edit("stranger", space_id, Some(&stranger), Some(Uuid::new_v4())),
+                edit("unknown", space_id, None, None),
+                edit("block", block_space_id, Some(&block_editor), None),
+            ])
+            .build();
+        data.added_editors = vec![AddedMember {
+            dao_address: block_dao.clone(),
+            editor_address: block_editor.to_lowercase(),
+        }];
+        data
+    };
+    let unverified = |run: &'static str| {
+        let pool = pool.clone();
+        async move {
+            sqlx::query_as::<_, (String, Option<String>, bool)>(
+                "SELECT cid, sender, rejected FROM unverified_edits WHERE cid LIKE $1 ORDER BY cid",
+            )
+            .bind(format!("ipfs://verification-{}-%", run))
+            .fetch_all(&pool)
+            .await
+        }
+    };
+    sqlx::query("DELETE FROM unverified_edits WHERE cid LIKE 'ipfs://verification-%'")
+        .execute(&pool)
+        .await?;
+
+    let flagging = AuthorVerifier::new(pool.clone(), AuthorVerification::Flag);
+    let data = flagging.verify(block("flag")).await?;
+    assert_eq!(data.edits.len(), 5);
+    // Edits without a sender aren't checked
+    assert_eq!(
+        unverified("flag").await?,
+        vec![(
+            "ipfs://verification-flag-stranger".to_string(),
+            Some(stranger.clone()),
+            false
+        )]
+    );
+
+    let rejecting = AuthorVerifier::new(pool.clone(), AuthorVerification::Reject);
+    let data = rejecting.verify(block("reject")).await?;
+    let cids: Vec<&str> = data.edits.iter().map(|edit| edit.cid.as_str()).collect();
+    assert_eq!(
+        cids,
+        vec![
+            "ipfs://verification-reject-editor",
+            "ipfs://verification-reject-author",
+            "ipfs://verification-reject-unknown",
+            "ipfs://verification-reject-block",
+        ]
+    );
+    let rejected = unverified("reject").await?;
+    assert_eq!(rejected.len(), 1);
+    assert!(rejected.iter().all(|(_, _, rejected)| *rejected));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_editor_permissions() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+    let indexer = TestIndexer::new(storage.clone(), Arc::new(PropertiesCache::new()));
+
+    let dao_address = generate_unique_address("permissions_dao");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let editor = generate_unique_address("permissions_editor");
+    let former = generate_unique_address("permissions_former");
+    let stranger = checksum_address(generate_unique_address("permissions_stranger"));
+
+    let mut created = KgDataBuilder::new(1)
+        .spaces(vec![make_public_space(&dao_address)])
+        .build();
+    created.added_editors = [&editor, &former]
+        .into_iter()
+        .map(|address| AddedMember {
+            dao_address: dao_address.clone(),
+            editor_address: address.clone(),
+        })
+        .collect();
+    let mut removed = KgDataBuilder::new(5).build();
+    removed.removed_editors = vec![RemovedMember {
+        dao_address: dao_address.clone(),
+        editor_address: former.clone(),
+    }];
+    indexer.run(&vec![created, removed]).await?;
+
+    let history = sqlx::query_as::<_, (String, i64, Option<i64>)>(
+        "SELECT address, added_at_block, removed_at_block FROM editor_history WHERE space_id = $1 ORDER BY address",
+    )
+    .bind(space_id)
+    .fetch_all(&pool)
+    .await?;
+    let mut expected = vec![
+        (checksum_address(&editor), 1, None),
+        (checksum_address(&former), 1, Some(5)),
+    ];
+    expected.sort();
+    assert_eq!(history, expected);
+
+    let block = |block_number: u64| {
+        let edit = |name: &str, space_id, sender: Option<String>| PreprocessedEdit {
+            cid: format!("ipfs://permissions-{}-{}", block_number, name),
+            edit: Some(EditBuilder::new(Uuid::new_v4()).build()),
+            is_errored: false,
+            space_id,
+            sender,
+            event_index: 0,
+        };
+
+        KgDataBuilder::new(block_number)
+            .edits(vec![
+                edit("editor", space_id, Some(checksum_address(&editor))),
+                edit("former", space_id, Some(checksum_address(&former))),
+                edit("stranger", space_id, Some(stranger.clone())),
+                edit("unknown", space_id, None),
+                edit("personal", Uuid::new_v4(), Some(stranger.clone())),
+            ])
+            .build()
+    };
+    let cids = |data: &KgData| -> Vec<String> {
+        data.edits
+            .iter()
+            .map(|edit| {
+                edit.cid
+                    .trim_start_matches("ipfs://permissions-")
+                    .to_string()
+            })
+            .collect()
+    };
+    sqlx::query("DELETE FROM unauthorized_edits WHERE space_id = $1")
+        .bind(space_id)
+        .execute(&pool)
+        .await?;
+
+    let permissions = EditorPermissions::new(pool.clone());
+    // Editors removed in a block were still editors at that block
+    // and edits without a sender aren't checked
+    let data = permissions.enforce(block(5)).await?;
+    assert_eq!(
+        cids(&data),
+        vec!["5-editor", "5-former", "5-unknown", "5-personal"]
+    );
+    let data = permissions.enforce(block(6)).await?;
+    assert_eq!(cids(&data), vec!["6-editor", "6-unknown", "6-personal"]);
+
+    let unauthorized = sqlx::query_as::<_, (String, Option<String>, String)>(
+        "SELECT cid, sender, created_at_block FROM unauthorized_edits WHERE space_id = $1 ORDER BY cid",
+    )
+    .bind(space_id)
+    .fetch_all(&pool)
+    .await?;
+    assert_eq!(
+        unauthorized,
+        vec![
+            (
+                "ipfs://permissions-5-stranger".to_string(),
+                Some(stranger.clone()),
+                "5".to_string()
+            ),
+            (
+                "ipfs://permissions-6-former".to_string(),
+                Some(checksum_address(&former)),
+                "6".to_string()
+            ),
+            (
+                "ipfs://permissions-6-stranger".to_string(),
+                Some(stranger.clone()),
+                "6".to_string()
+            ),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_consistency_checker() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    let (relation_id, space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let member = checksum_address(generate_unique_address("consistency_member"));
+
+    sqlx::query(
+        "INSERT INTO relations (id, entity_id, type_id, from_entity_id, to_entity_id, space_id)
+        VALUES ($1, $2, $3, $4, $5, $6)",
+    )
+    .bind(relation_id)
+    .bind(Uuid::new_v4())
+    .bind(Uuid::new_v4())
+    .bind(Uuid::new_v4())
+    .bind(Uuid::new_v4())
+    .bind(space_id)
+    .execute(&pool)
+    .await?;
+    sqlx::query("INSERT INTO members (address, space_id) VALUES ($1, $2)")
+        .bind(&member)
+        .bind(space_id)
+        .execute(&pool)
+        .await?;
+
+    let checker = ConsistencyChecker::new(pool.clone(), 1000);
+    let report = checker.run(false).await?;
+    let check = |kind| {
+        report
+            .checks
+            .iter()
+            .find(|check| check.kind == kind)
+            .unwrap()
+            .clone()
+    };
+    assert!(check(IssueKind::DanglingRelation)
+        .sample
+        .contains(&relation_id.to_string()));
+    assert!(check(IssueKind::UnknownMemberSpace)
+        .sample
+        .contains(&format!("{}/{}", space_id, member)));
+    assert!(!check(IssueKind::UnknownProposalSpace).fixable);
+    assert!(report.has_issues());
+
+    let fixed = checker.run(true).await?;
+    for check in &fixed.checks {
+        assert_eq!(check.fixed.is_some(), check.fixable);
+    }
+
+    let report = checker.run(false).await?;
+    for kind in [IssueKind::DanglingRelation, IssueKind::UnknownMemberSpace] {
+        let check = report
+            .checks
+            .iter()
+            .find(|check| check.kind == kind)
+            .unwrap();
+        assert_eq!(check.count, 0);
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_block_replay_is_idempotent() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+
+    let indexer_id = format!("replay_test:{}", Uuid::new_v4());
+    let dao_address = generate_unique_address("replay_test");
+    let editor_address = generate_unique_address("replay_editor");
+    let editor = checksum_address(editor_address.clone());
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let entity = Uuid::new_v4();
+
+    let mut first = KgDataBuilder::new(1)
+        .edits(vec![PreprocessedEdit {
+            edit: Some(
+                EditBuilder::new(Uuid::new_v4())
+                    .author(Uuid::new_v4())
+                    .create_property(NAME_PROPERTY, PbDataType::Text)
+                    .update_entity(entity, [value(NAME_PROPERTY, "Replayed")])
+                    .build(),
+            ),
+            is_errored: false,
+            space_id,
+            cid: "ipfs://replay-1".to_string(),
+            sender: None,
+            event_index: 0,
+        }])
+        .spaces(vec![make_public_space(&dao_address)])
+        .build();
+    first.added_editors = vec![make_added_member(&dao_address, &editor_address)];
+    let second = KgDataBuilder::new(2)
+        .removed_editors(vec![make_removed_member(&dao_address, &editor_address)])
+        .build();
+    let third = KgDataBuilder::new(3)
+        .added_editors(vec![make_added_member(&dao_address, &editor_address)])
+        .build();
+
+    for block in [&first, &second, &third] {
+        assert!(
+            root_handler::run_once(
+                block,
+                &block.block,
+                &storage,
+                &properties_cache,
+                &indexer_id
+            )
+            .await?
+        );
+    }
+
+    // The ledger skips blocks that were already processed
+    assert!(
+        storage
+            .is_block_processed(&indexer_id, &first.block)
+            .await?
+    );
+    assert!(
+        !root_handler::run_once(
+            &second,
+            &second.block,
+            &storage,
+            &properties_cache,
+            &indexer_id
+        )
+        .await?
+    );
+    assert!(storage.get_editor(&editor, &space_id).await.is_ok());
+
+    // Without the ledger, replaying a block doesn't count it again
+    let rollups = storage
+        .get_space_activity(&space_id, RollupPeriod::Day)
+        .await?;
+    assert_eq!(rollups[0].edits, 1);
+    root_handler::run(&first, &first.block, &storage, &properties_cache).await?;
+    assert_eq!(
+        storage
+            .get_space_activity(&space_id, RollupPeriod::Day)
+            .await?,
+        rollups
+    );
+
+    // and replaying an older removal doesn't close the editor's later period
+    root_handler::run(&second, &second.block, &storage, &properties_cache).await?;
+    let history: Vec<(i64, Option<i64>)> = sqlx::query_as(
+        "SELECT added_at_block, removed_at_block FROM editor_history
+        WHERE space_id = $1 AND address = $2 ORDER BY added_at_block",
+    )
+    .bind(space_id)
+    .bind(&editor)
+    .fetch_all(storage.get_pool())
+    .await?;
+    assert_eq!(history, vec![(1, Some(2)), (3, None)]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_backup_and_restore() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+    let pool = storage.get_pool().clone();
+
+    let dao_address = generate_unique_address("backup_test");
+    let (kept, added) = (
+        generate_unique_address("backup_kept"),
+        generate_unique_address("backup_added"),
+    );
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (kept_address, added_address) = (checksum_address(&kept), checksum_address(&added));
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            KgDataBuilder::new(2)
+                .added_members(vec![make_added_member(&dao_address, &kept)])
+                .build(),
+        ])
+        .await?;
+
+    let space_backup = env::temp_dir().join(format!("gaia_backup_{}", Uuid::new_v4()));
+    let full_backup = env::temp_dir().join(format!("gaia_backup_{}", Uuid::new_v4()));
+    let manifest = backup::backup(&pool, &space_backup, Some(&[space_id])).await?;
+    assert_eq!(manifest.space_ids, Some(vec![space_id]));
+    assert!(manifest.tables.iter().all(|table| table.name != "entities"));
+    let members = manifest.tables.iter().find(|table| table.name == "members");
+    assert_eq!(members.map(|table| table.rows), Some(1));
+    backup::backup(&pool, &full_backup, None).await?;
+    assert!(backup::backup(&pool, &full_backup, None).await.is_err());
+
+    indexer
+        .run(&vec![KgDataBuilder::new(3)
+            .added_members(vec![make_added_member(&dao_address, &added)])
+            .removed_members(vec![make_removed_member(&dao_address, &kept)])
+            .build()])
+        .await?;
+    assert!(storage.get_member(&kept_address, &space_id).await.is_err());
+
+    // Restoring the space only replaces the space's rows
+    backup::restore(&pool, &space_backup).await?;
+    assert!(storage.get_member(&kept_address, &space_id).await.is_ok());
+    assert!(storage.get_member(&added_address, &space_id).await.is_err());
+
+    let other_space = Uuid::new_v4();
+    sqlx::query("INSERT INTO members (address, space_id) VALUES ($1, $2)")
+        .bind(&added_address)
+        .bind(other_space)
+        .execute(&pool)
+        .await?;
+
+    let manifest = backup::restore(&pool, &full_backup).await?;
+    assert!(manifest.space_ids.is_none());
+    assert!(storage
+        .get_member(&added_address, &other_space)
+        .await
+        .is_err());
+    for table in &manifest.tables {
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", table.name))
+            .fetch_one(&pool)
+            .await?;
+        if table.name != "meta" && table.name != "processed_blocks" {
+            assert_eq!(rows as u64, table.rows, "{}", table.name);
+        }
+    }
+
+    std::fs::remove_dir_all(&space_backup).ok();
+    std::fs::remove_dir_all(&full_backup).ok();
+
+    Ok(())
+}
+
+/// Connects to the database with only the given schema on the search path.
+async fn connect_to_schema(database_url: &str, schema: &str) -> sqlx::PgPool {
+    let separator = if database_url.contains('?') { '&' } else { '?' };
+    sqlx::PgPool::connect(&format!(
+        "{}{}options=-csearch_path%3D{}",
+        database_url, separator, schema
+    ))
+    .await
+    .expect("Failed to connect to the schema")
+}
+
+#[tokio::test]
+#[serial]
+async fn test_database_comparison() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    let ids: Vec<Uuid> = (0..5).map(|_| Uuid::new_v4()).collect();
+    for schema in ["gaia_compare_left", "gaia_compare_right"] {
+        sqlx::query(&format!("DROP SCHEMA IF EXISTS {} CASCADE", schema))
+            .execute(&pool)
+            .await?;
+        sqlx::query(&format!("CREATE SCHEMA {}", schema))
+            .execute(&pool)
+            .await?;
+        for table in ["relations", "meta"] {
+            sqlx::query(&format!(
+                "CREATE TABLE {}.{} (LIKE public.{} INCLUDING ALL)",
+                schema, table, table
+            ))
+            .execute(&pool)
+            .await?;
+        }
+        sqlx::query(&format!(
+            "INSERT INTO {}.meta (id, cursor, block_number) VALUES ('kg_indexer', 'cursor', '10')",
+            schema
+        ))
+        .execute(&pool)
+        .await?;
+        sqlx::query(&format!(
+            "INSERT INTO {}.relations (id, entity_id, type_id, from_entity_id, to_entity_id, space_id, position)
+            SELECT id, id, id, id, id, id, 'a0' FROM UNNEST($1::uuid[]) AS id",
+            schema
+        ))
+        .bind(&ids[..4])
+        .execute(&pool)
+        .await?;
+    }
+
+    // The right side is missing a relation, has one more, has one that
+    // moved and has a column the left side doesn't have
+    sqlx::query("DELETE FROM gaia_compare_right.relations WHERE id = $1")
+        .bind(ids[0])
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "INSERT INTO gaia_compare_right.relations (id, entity_id, type_id, from_entity_id, to_entity_id, space_id)
+        VALUES ($1, $1, $1, $1, $1, $1)",
+    )
+    .bind(ids[4])
+    .execute(&pool)
+    .await?;
+    sqlx::query("UPDATE gaia_compare_right.relations SET position = 'b0' WHERE id = $1")
+        .bind(ids[1])
+        .execute(&pool)
+        .await?;
+    sqlx::query("ALTER TABLE gaia_compare_right.relations ADD COLUMN created_at_block bigint")
+        .execute(&pool)
+        .await?;
+
+    let left = connect_to_schema(&database_url, "gaia_compare_left").await;
+    let right = connect_to_schema(&database_url, "gaia_compare_right").await;
+    let relations = ComparedTable::find("relations").unwrap();
+
+    let report = DatabaseComparison::new(left.clone(), right.clone(), 2, 10)
+        .run(&[relations])
+        .await?;
+    assert!(!report.is_identical());
+    assert_eq!(report.left_cursors, report.right_cursors);
+
+    let table = &report.tables[0];
+    assert_eq!((table.left_rows, table.right_rows), (4, 4));
+    assert_eq!(table.only_in_left_sample, vec![ids[0].to_string()]);
+    assert_eq!(table.only_in_right_sample, vec![ids[4].to_string()]);
+    assert_eq!(table.different_sample, vec![ids[1].to_string()]);
+    assert_eq!(table.skipped_columns, vec!["created_at_block".to_string()]);
+
+    let report = DatabaseComparison::new(left.clone(), left, 2, 10)
+        .run(&[relations])
+        .await?;
+    assert!(report.is_identical());
+
+    for schema in ["gaia_compare_left", "gaia_compare_right"] {
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cache_copy() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    for schema in ["gaia_cache_source", "gaia_cache_target"] {
+        sqlx::query(&format!("DROP SCHEMA IF EXISTS {} CASCADE", schema))
+            .execute(&pool)
+            .await?;
+        sqlx::query(&format!("CREATE SCHEMA {}", schema))
+            .execute(&pool)
+            .await?;
+        sqlx::query(&format!(
+            "CREATE TABLE {}.ipfs_cache (LIKE public.ipfs_cache INCLUDING ALL)",
+            schema
+        ))
+        .execute(&pool)
+        .await?;
+    }
+
+    let (space_id, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO gaia_cache_source.ipfs_cache (uri, json, is_errored, block, space, content_hash, gateway)
+        VALUES
+            ('ipfs://early', '{\"name\": \"early\"}', false, '1700000000', $1, 'hash', 'https://gateway/'),
+            ('ipfs://late', NULL, true, '1700000100', $1, NULL, NULL),
+            ('ipfs://other', '{}', false, '1700000100', $2, NULL, NULL),
+            ('ipfs://cached', '{}', false, '1700000100', $1, NULL, NULL)",
+    )
+    .bind(space_id)
+    .bind(other_space_id)
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "INSERT INTO gaia_cache_target.ipfs_cache (uri, json, block, space)
+        VALUES ('ipfs://cached', '{\"kept\": true}', '1', $1)",
+    )
+    .bind(space_id)
+    .execute(&pool)
+    .await?;
+
+    let source = connect_to_schema(&database_url, "gaia_cache_source").await;
+    let target = connect_to_schema(&database_url, "gaia_cache_target").await;
+
+    let report = CacheCopy::new(source.clone(), target.clone(), 1)
+        .run(&CacheSelection {
+            since: Some(1_700_000_050),
+            space_ids: vec![space_id],
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!((report.selected, report.copied, report.skipped), (2, 1, 1));
+
+    let report = CacheCopy::new(source, target.clone(), 10)
+        .run(&CacheSelection {
+            uris: vec!["ipfs://early".to_string()],
+            ..Default::default()
+        })
+        .await?;
+    assert_eq!(report.copied, 1);
+
+    let rows: Vec<(String, Option<serde_json::Value>, bool, Option<String>)> = sqlx::query_as(
+        "SELECT uri, json, is_errored, content_hash FROM ipfs_cache ORDER BY uri",
+    )
+    .fetch_all(&target)
+    .await?;
+    assert_eq!(
+        rows,
+        vec![
+            (
+                "ipfs://cached".to_string(),
+                Some(serde_json::json!({ "kept": true })),
+                false,
+                None
+            ),
+            (
+                "ipfs://early".to_string(),
+                Some(serde_json::json!({ "name": "early" })),
+                false,
+                Some("hash".to_string())
+            ),
+            ("ipfs://late".to_string(), None, true, None),
+        ]
+    );
+    let gateway: Option<String> =
+        sqlx::query_scalar("SELECT gateway FROM ipfs_cache WHERE uri = 'ipfs://early'")
+            .fetch_one(&target)
+            .await?;
+    assert_eq!(gateway.as_deref(), Some("https://gateway/"));
+
+    for schema in ["gaia_cache_source", "gaia_cache_target"] {
+        sqlx::query(&format!("DROP SCHEMA {} CASCADE", schema))
+            .execute(&pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cache_redecode() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    sqlx::query("DROP SCHEMA IF EXISTS gaia_cache_redecode CASCADE")
+        .execute(&pool)
+        .await?;
+    sqlx::query("CREATE SCHEMA gaia_cache_redecode")
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "CREATE TABLE gaia_cache_redecode.ipfs_cache (LIKE public.ipfs_cache INCLUDING ALL)",
+    )
+    .execute(&pool)
+    .await?;
+
+    let edit = EditBuilder::new(id("55555555-5555-5555-5555-555555555555"))
+        .name("Redecoded Edit")
+        .author(id("66666666-6666-6666-6666-666666666666"))
+        .build();
+    let bytes = prost::Message::encode_to_vec(&edit);
+    let space_id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO gaia_cache_redecode.ipfs_cache (uri, json, is_errored, block, space, content_hash, raw_bytes)
+        VALUES
+            ('ipfs://errored', 'null', true, '1', $1, NULL, $2),
+            ('ipfs://garbage', 'null', true, '1', $1, NULL, '\\xdeadbeef'),
+            ('ipfs://unfetched', 'null', true, '1', $1, NULL, NULL),
+            ('ipfs://decoded', '{}', false, '1', $1, $3, $2),
+            ('ipfs://stale', '{}', false, '1', $1, 'stale', $2)",
+    )
+    .bind(space_id)
+    .bind(&bytes)
+    .bind(wire::hash::edit_hash(&edit))
+    .execute(&pool)
+    .await?;
+
+    let cache = connect_to_schema(&database_url, "gaia_cache_redecode").await;
+
+    let report = CacheRedecoder::new(cache.clone(), 1).run(false).await?;
+    assert_eq!(
+        (report.checked, report.fixed, report.changed, report.failed),
+        (2, 1, 0, 1)
+    );
+
+    let report = CacheRedecoder::new(cache.clone(), 10).run(true).await?;
+    assert_eq!(
+        (report.checked, report.fixed, report.changed, report.failed),
+        (4, 0, 1, 1)
+    );
+
+    let rows: Vec<(String, bool, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT uri, is_errored, content_hash, encoding FROM ipfs_cache ORDER BY uri",
+    )
+    .fetch_all(&cache)
+    .await?;
+    let hash = Some(wire::hash::edit_hash(&edit));
+    let protobuf = Some("protobuf".to_string());
+    assert_eq!(
+        rows,
+        vec![
+            ("ipfs://decoded".to_string(), false, hash.clone(), None),
+            (
+                "ipfs://errored".to_string(),
+                false,
+                hash.clone(),
+                protobuf.clone()
+            ),
+            ("ipfs://garbage".to_string(), true, None, None),
+            ("ipfs://stale".to_string(), false, hash, protobuf),
+            ("ipfs://unfetched".to_string(), true, None, None),
+        ]
+    );
+    let json: serde_json::Value =
+        sqlx::query_scalar("SELECT json FROM ipfs_cache WHERE uri = 'ipfs://errored'")
+            .fetch_one(&cache)
+            .await?;
+    assert_eq!(
+        wire::deserialize::deserialize_from_json(json).ok(),
+        Some(edit)
+    );
+
+    sqlx::query("DROP SCHEMA gaia_cache_redecode CASCADE")
+        .execute(&pool)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_cache_gc() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    sqlx::query("DROP SCHEMA IF EXISTS gaia_cache_gc CASCADE")
+        .execute(&pool)
+        .await?;
+    sqlx::query("CREATE SCHEMA gaia_cache_gc")
+        .execute(&pool)
+        .await?;
+    for table in [
+        "ipfs_cache",
+        "ipfs_cache_archive",
+        "edits",
+        "unverified_edits",
+        "unauthorized_edits",
+        "proposal_previews",
+    ] {
+        sqlx::query(&format!(
+            "CREATE TABLE gaia_cache_gc.{} (LIKE public.{} INCLUDING ALL)",
+            table, table
+        ))
+        .execute(&pool)
+        .await?;
+    }
+
+    let (space_id, blocked_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO gaia_cache_gc.ipfs_cache (uri, json, is_errored, block, space, content_hash)
+        VALUES
+            ('ipfs://applied', '{}', false, '100', $1, 'applied'),
+            ('ipfs://duplicate', '{}', false, '100', $1, 'applied'),
+            ('ipfs://unverified', '{}', false, '100', $1, 'unverified'),
+            ('ipfs://proposed', '{}', false, '100', $1, 'proposed'),
+            ('ipfs://orphaned', '{\"name\": \"orphaned\"}', false, '100', $1, 'orphaned'),
+            ('ipfs://errored', 'null', true, '100', $1, NULL),
+            ('ipfs://unindexed', '{}', false, '300', $1, 'unindexed'),
+            ('ipfs://blocked', '{}', false, '300', $2, 'blocked')",
+    )
+    .bind(space_id)
+    .bind(blocked_space_id)
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "INSERT INTO gaia_cache_gc.edits (space_id, content_hash, cid, created_at, created_at_block)
+        VALUES ($1, 'applied', 'ipfs://applied', '200', '2')",
+    )
+    .bind(space_id)
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "INSERT INTO gaia_cache_gc.unverified_edits (space_id, cid, rejected, created_at, created_at_block)
+        VALUES ($1, 'ipfs://unverified', true, '100', '1')",
+    )
+    .bind(space_id)
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "INSERT INTO gaia_cache_gc.proposal_previews (space_id, proposal_id, content_uri, entity_ids, property_ids,
+            values_set, values_unset, relations_created, relations_updated, relations_deleted, entities_deleted,
+            created_at_block)
+        VALUES ($1, '1', 'ipfs://proposed', '{}', '{}', 0, 0, 0, 0, 0, 0, '1')",
+    )
+    .bind(space_id)
+    .execute(&pool)
+    .await?;
+
+    let cache = connect_to_schema(&database_url, "gaia_cache_gc").await;
+
+    let report = CacheCollector::new(cache.clone(), 10)
+        .run(CollectMode::DryRun, &[blocked_space_id])
+        .await?;
+    assert_eq!(
+        (report.orphaned, report.archived, report.deleted),
+        (2, 0, 0)
+    );
+
+    let report = CacheCollector::new(cache.clone(), 1)
+        .run(CollectMode::Archive, &[blocked_space_id])
+        .await?;
+    assert_eq!(
+        (report.orphaned, report.archived, report.deleted),
+        (2, 2, 0)
+    );
+
+    let uris: Vec<String> = sqlx::query_scalar("SELECT uri FROM ipfs_cache ORDER BY uri")
+        .fetch_all(&cache)
+        .await?;
+    assert_eq!(
+        uris,
+        vec![
+            "ipfs://applied",
+            "ipfs://duplicate",
+            "ipfs://errored",
+            "ipfs://proposed",
+            "ipfs://unindexed",
+            "ipfs://unverified",
+        ]
+    );
+    let archived: Vec<(String, Option<serde_json::Value>)> =
+        sqlx::query_as("SELECT uri, json FROM ipfs_cache_archive ORDER BY uri")
+            .fetch_all(&cache)
+            .await?;
+    assert_eq!(
+        archived,
+        vec![
+            ("ipfs://blocked".to_string(), Some(serde_json::json!({}))),
+            (
+                "ipfs://orphaned".to_string(),
+                Some(serde_json::json!({ "name": "orphaned" }))
+            ),
+        ]
+    );
+
+    // Once the indexer reaches its block, an entry nothing refers to is
+    // collected too
+    sqlx::query("UPDATE edits SET created_at = '300'")
+        .execute(&cache)
+        .await?;
+    let report = CacheCollector::new(cache.clone(), 10)
+        .run(CollectMode::Delete, &[])
+        .await?;
+    assert_eq!(
+        (report.orphaned, report.archived, report.deleted),
+        (1, 0, 1)
+    );
+    let archived: i64 = sqlx::query_scalar("SELECT count(*) FROM ipfs_cache_archive")
+        .fetch_one(&cache)
+        .await?;
+    assert_eq!(archived, 2);
+
+    sqlx::query("DROP SCHEMA gaia_cache_gc CASCADE")
+        .execute(&pool)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_value_partitions() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    sqlx::query("DROP SCHEMA IF EXISTS gaia_partitions CASCADE")
+        .execute(&pool)
+        .await?;
+    sqlx::query("CREATE SCHEMA gaia_partitions")
+        .execute(&pool)
+        .await?;
+    sqlx::query(
+        "CREATE TABLE gaia_partitions.values (LIKE public.values INCLUDING ALL) PARTITION BY HASH (space_id)",
+    )
+    .execute(&pool)
+    .await?;
+
+    let partitioned = PostgresStorage {
+        pool: connect_to_schema(&database_url, "gaia_partitions").await,
+    };
+    assert!(partitioned.partition_values(2).await?);
+
+    let space_ids: Vec<Uuid> = (0..20).map(|_| Uuid::new_v4()).collect();
+    sqlx::query(
+        "INSERT INTO values (id, property_id, entity_id, space_id, string)
+        SELECT space_id::text, $2, $2, space_id, 'value' FROM UNNEST($1::uuid[]) AS space_id",
+    )
+    .bind(&space_ids)
+    .bind(Uuid::new_v4())
+    .execute(&partitioned.pool)
+    .await?;
+
+    assert!(partitioned.partition_values(4).await?);
+    assert!(!partitioned.partition_values(4).await?);
+
+    let partitions = partitioned.value_partitions().await?;
+    assert_eq!(
+        partitions
+            .iter()
+            .map(|partition| (
+                partition.name.as_str(),
+                partition.modulus,
+                partition.remainder
+            ))
+            .collect::<Vec<_>>(),
+        vec![
+            ("values_4_0", 4, 0),
+            ("values_4_1", 4, 1),
+            ("values_4_2", 4, 2),
+            ("values_4_3", 4, 3),
+        ]
+    );
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM values")
+        .fetch_one(&partitioned.pool)
+        .await?;
+    assert_eq!(count, 20);
+
+    // A space's values are only read from its partition
+    let plan: Vec<String> =
+        sqlx::query_scalar("EXPLAIN (COSTS OFF) SELECT * FROM values WHERE space_id = $1")
+            .bind(space_ids[0])
+            .fetch_all(&partitioned.pool)
+            .await?;
+    assert_eq!(
+        plan.iter()
+            .filter(|line| line.contains("values_4_"))
+            .count(),
+        1
+    );
+
+    sqlx::query("DROP SCHEMA gaia_partitions CASCADE")
+        .execute(&pool)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_property_migration_is_guarded() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let test_storage = TestStorage::new(storage.clone());
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache.clone());
+
+    let dao_address = generate_unique_address("property_migration");
+    let public_space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let personal_space_id = Uuid::new_v4();
+    let (age, nickname, entity_id) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let setup = EditBuilder::new(Uuid::new_v4())
+        .create_property(age, PbDataType::Number)
+        .create_property(nickname, PbDataType::Text)
+        .update_entity(entity_id, [value(age, "42"), value(nickname, "Al")])
+        .build();
+    // Numbers convert to text, but "Al" isn't a number
+    let migrate = EditBuilder::new(Uuid::new_v4())
+        .update_property(age, PbDataType::Text)
+        .update_property(nickname, PbDataType::Number)
+        .update_entity(entity_id, [value(age, "forty-two")])
+        .build();
+    let ungoverned = EditBuilder::new(Uuid::new_v4())
+        .update_property(age, PbDataType::Number)
+        .build();
+
+    let edit = |edit: &wire::pb::grc20::Edit, space_id: Uuid, cid: &str| PreprocessedEdit {
+        edit: Some(edit.clone()),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .edits(vec![edit(&setup, public_space_id, "ipfs://setup")])
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            KgDataBuilder::new(2)
+                .edits(vec![edit(&migrate, public_space_id, "ipfs://migrate")])
+                .build(),
+            KgDataBuilder::new(3)
+                .edits(vec![edit(
+                    &ungoverned,
+                    personal_space_id,
+                    "ipfs://ungoverned",
+                )])
+                .build(),
+        ])
+        .await?;
+
+    // The edit's own values use the new data type
+    assert_eq!(
+        storage.get_property(&age.to_string()).await?.data_type,
+        DataType::String
+    );
+    assert_eq!(properties_cache.get(&age).unwrap(), DataType::String);
+    let value = storage
+        .get_value(&derive_value_id(&entity_id, &age, &public_space_id).to_string())
+        .await?;
+    assert_eq!(value.string, Some("forty-two".to_string()));
+    assert_eq!(value.number, None);
+
+    assert_eq!(
+        storage.get_property(&nickname.to_string()).await?.data_type,
+        DataType::String
+    );
+    assert_eq!(
+        storage
+            .get_value(&derive_value_id(&entity_id, &nickname, &public_space_id).to_string())
+            .await?
+            .string,
+        Some("Al".to_string())
+    );
+
+    let age_migrations = test_storage.get_property_migrations(&age).await?;
+    assert_eq!(age_migrations.len(), 2);
+    assert!(age_migrations[0].applied);
+    assert_eq!(age_migrations[0].converted_values, 1);
+    assert_eq!(age_migrations[0].from_type, "Number");
+    assert_eq!(age_migrations[0].to_type, "String");
+    assert!(!age_migrations[1].applied);
+    assert_eq!(age_migrations[1].space_id, personal_space_id);
+    assert_eq!(
+        age_migrations[1].reason.as_deref(),
+        Some("only public spaces can migrate properties")
+    );
+
+    let nickname_migrations = test_storage.get_property_migrations(&nickname).await?;
+    assert_eq!(nickname_migrations.len(), 1);
+    assert!(!nickname_migrations[0].applied);
+    assert_eq!(nickname_migrations[0].converted_values, 0);
+
+    Ok(())
+}
+
+/// A webhook endpoint answering every request with `status`. Returns its url
+/// and the headers and body of the requests it received.
+async fn serve_webhook(status: u16) -> (String, Arc<std::sync::Mutex<Vec<(String, String)>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let received = requests.clone();
+
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0u8; 4096];
+
+            // Reads until the body announced by the content length is in
+            let (head, body) = loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = head
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(str::to_string)
+                        })
+                        .map(|length| length.trim().parse::<usize>().unwrap())
+                        .unwrap_or(0);
+
+                    if body.len() >= length || read == 0 {
+                        break (head.to_lowercase(), body.to_string());
+                    }
+                }
+            };
+
+            received.lock().unwrap().push((head, body));
+            let response = format!(
+                "HTTP/1.1 {} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+                status
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+
+    (url, requests)
+}
+
+#[tokio::test]
+#[serial]
+async fn test_webhook_notifications() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("webhook_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+
+    let (ok_url, ok_requests) = serve_webhook(200).await;
+    let (failing_url, failing_requests) = serve_webhook(500).await;
+
+    let dispatcher = Arc::new(WebhookDispatcher::new(
+        storage.pool.clone(),
+        NotificationsConfig {
+            enabled: true,
+            max_attempts: 2,
+            retry_base_ms: 10,
+            timeout_secs: 5,
+        },
+        None,
+    )?);
+    let webhook = Webhook {
+        id: Uuid::new_v4(),
+        url: ok_url,
+        secret: "secret".to_string(),
+        space_id: Some(space_id),
+        event_types: vec![],
+    };
+    let failing_webhook = Webhook {
+        id: Uuid::new_v4(),
+        url: failing_url,
+        secret: "secret".to_string(),
+        space_id: Some(space_id),
+        event_types: vec![EventType::MemberAdded],
+    };
+    dispatcher.register(&webhook).await?;
+    dispatcher.register(&failing_webhook).await?;
+
+    let mut block = KgDataBuilder::new(2)
+        .edits(vec![PreprocessedEdit {
+            edit: Some(EditBuilder::new(Uuid::new_v4()).build()),
+            is_errored: false,
+            space_id,
+            cid: "ipfs://webhook".to_string(),
+            sender: None,
+            event_index: 0,
+        }])
+        .build();
+    block.added_members = vec![AddedMember {
+        dao_address: dao_address.clone(),
+        editor_address: generate_unique_address("webhook_member"),
+    }];
+    block.executed_proposals = vec![ExecutedProposal {
+        proposal_id: "1".to_string(),
+        plugin_address: format!("{}_governance_plugin", dao_address),
+        tx_hash: "0x01".to_string(),
+    }];
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            block.clone(),
+        ])
+        .await?;
+
+    let mut results = Vec::new();
+    for delivery in dispatcher.notify(&block).await? {
+        results.push(delivery.await?);
+    }
+
+    // Every event is delivered to the first webhook, only the new member to
+    // the failing one, which gives up after two attempts
+    assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+
+    let requests = ok_requests.lock().unwrap().clone();
+    let bodies: Vec<serde_json::Value> = requests
+        .iter()
+        .map(|(_, body)| serde_json::from_str(body).unwrap())
+        .collect();
+    let event_types: Vec<&str> = bodies
+        .iter()
+        .map(|body| body["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        event_types,
+        vec!["edit_published", "proposal_executed", "member_added"]
+    );
+    assert_eq!(bodies[1]["space_id"], space_id.to_string());
+    assert_eq!(bodies[0]["block_number"], 2);
+
+    for (head, body) in &requests {
+        let signature = format!(
+            "{}: {}",
+            SIGNATURE_HEADER.to_lowercase(),
+            sign("secret", body.as_bytes())
+        );
+        assert!(head.contains(&signature), "unsigned request: {}", head);
+    }
+
+    assert_eq!(failing_requests.lock().unwrap().len(), 2);
+
+    let (event_type, attempts): (String, i32) = sqlx::query_as(
+        "SELECT event_type, attempts FROM webhook_dead_letters WHERE webhook_id = $1",
+    )
+    .bind(failing_webhook.id)
+    .fetch_one(&storage.pool)
+    .await?;
+    assert_eq!(event_type, "member_added");
+    assert_eq!(attempts, 2);
+
+    sqlx::query("DELETE FROM webhooks WHERE id = ANY($1)")
+        .bind(vec![webhook.id, failing_webhook.id])
+        .execute(&storage.pool)
+        .await?;
+
+    Ok(())
+}
+
+/// A search index keeping its documents in memory.
+#[derive(Default)]
+struct MemorySearchIndex {
+    documents: std::sync::Mutex<std::collections::HashMap<Uuid, SearchDocument>>,
+}
+
+#[async_trait::async_trait]
+impl SearchIndex for MemorySearchIndex {
+    async fn upsert(&self, documents: &[SearchDocument]) -> Result<(), SearchError> {
+        let mut stored = self.documents.lock().unwrap();
+        for document in documents {
+            stored.insert(document.id, document.clone());
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, ids: &[Uuid]) -> Result<(), SearchError> {
+        let mut stored = self.documents.lock().unwrap();
+        for id in ids {
+            stored.remove(id);
+        }
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), SearchError> {
+        self.documents.lock().unwrap().clear();
+        Ok(())
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_search_sync() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("search_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (person, topic, person_type) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .create_property(DESCRIPTION_PROPERTY, PbDataType::Text)
+        .update_entity(person, [value(NAME_PROPERTY, "Alice")])
+        .update_entity(topic, [value(DESCRIPTION_PROPERTY, "Everything crypto")])
+        .create_relation(relation(
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            TYPES_PROPERTY,
+            person,
+            person_type,
+        ))
+        .build();
+    let delete = EditBuilder::new(Uuid::new_v4())
+        .unset_entity_values(person, [NAME_PROPERTY])
+        .delete_entity(topic)
+        .build();
+
+    let edit_item = |edit: Edit, cid: &str| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+    let edit_block = KgDataBuilder::new(2)
+        .edits(vec![edit_item(edit, "ipfs://search")])
+        .build();
+    let delete_block = KgDataBuilder::new(3)
+        .edits(vec![edit_item(delete, "ipfs://search-delete")])
+        .build();
+
+    let index = Arc::new(MemorySearchIndex::default());
+    let search = SearchSync::new(storage.pool.clone(), index.clone(), 1);
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            edit_block.clone(),
+        ])
+        .await?;
+    assert_eq!(search.sync_block(&edit_block).await?, 2);
+
+    assert_eq!(
+        index.documents.lock().unwrap().get(&person),
+        Some(&SearchDocument {
+            id: person,
+            name: Some("Alice".to_string()),
+            description: None,
+            types: vec![person_type],
+            space_ids: vec![space_id],
+        })
+    );
+    assert_eq!(
+        index.documents.lock().unwrap()[&topic]
+            .description
+            .as_deref(),
+        Some("Everything crypto")
+    );
+
+    // The person keeps its type, the deleted topic has nothing left to
+    // search it by
+    indexer.run(&vec![delete_block.clone()]).await?;
+    search.sync_block(&delete_block).await?;
+
+    let documents = index.documents.lock().unwrap();
+    assert_eq!(documents[&person].name, None);
+    assert_eq!(documents[&person].types, vec![person_type]);
+    assert!(!documents.contains_key(&topic));
+
+    Ok(())
+}
+
+/// A mirror keeping every written row in memory, like an unmerged
+/// `ReplacingMergeTree`.
+#[derive(Default)]
+struct MemoryMirror {
+    values: std::sync::Mutex<Vec<MirroredValue>>,
+    relations: std::sync::Mutex<Vec<MirroredRelation>>,
+    edits: std::sync::Mutex<Vec<MirroredEdit>>,
+}
+
+impl MemoryMirror {
+    /// The latest version of each value that isn't deleted.
+    fn live_values(&self) -> Vec<MirroredValue> {
+        let mut latest: std::collections::HashMap<String, MirroredValue> =
+            std::collections::HashMap::new();
+        for value in self.values.lock().unwrap().iter() {
+            if latest
+                .get(&value.id)
+                .is_none_or(|other| other.block_number <= value.block_number)
+            {
+                latest.insert(value.id.clone(), value.clone());
+            }
+        }
+        latest
+            .into_values()
+            .filter(|value| value.is_deleted == 0)
+            .collect()
+    }
+
+    fn live_relations(&self) -> Vec<MirroredRelation> {
+        let mut latest: std::collections::HashMap<Uuid, MirroredRelation> =
+            std::collections::HashMap::new();
+        for relation in self.relations.lock().unwrap().iter() {
+            if latest
+                .get(&relation.id)
+                .is_none_or(|other| other.block_number <= relation.block_number)
+            {
+                latest.insert(relation.id, relation.clone());
+            }
+        }
+        latest
+            .into_values()
+            .filter(|relation| relation.is_deleted == 0)
+            .collect()
+    }
+}
+
+#[async_trait::async_trait]
+impl MirrorWriter for MemoryMirror {
+    async fn create_tables(&self) -> Result<(), MirrorError> {
+        Ok(())
+    }
+
+    async fn write_values(&self, values: &[MirroredValue]) -> Result<(), MirrorError> {
+        self.values.lock().unwrap().extend_from_slice(values);
+        Ok(())
+    }
+
+    async fn write_relations(&self, relations: &[MirroredRelation]) -> Result<(), MirrorError> {
+        self.relations.lock().unwrap().extend_from_slice(relations);
+        Ok(())
+    }
+
+    async fn write_edits(&self, edits: &[MirroredEdit]) -> Result<(), MirrorError> {
+        self.edits.lock().unwrap().extend_from_slice(edits);
+        Ok(())
+    }
+
+    async fn values(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+    ) -> Result<Vec<MirroredValue>, MirrorError> {
+        Ok(self
+            .live_values()
+            .into_iter()
+            .filter(|value| value.space_id == space_id && entity_ids.contains(&value.entity_id))
+            .collect())
+    }
+
+    async fn relations(
+        &self,
+        space_id: Uuid,
+        entity_ids: &[Uuid],
+        relation_ids: &[Uuid],
+    ) -> Result<Vec<MirroredRelation>, MirrorError> {
+        Ok(self
+            .live_relations()
+            .into_iter()
+            .filter(|relation| {
+                relation.space_id == space_id
+                    && (entity_ids.contains(&relation.from_entity_id)
+                        || entity_ids.contains(&relation.to_entity_id)
+                        || relation_ids.contains(&relation.id))
+            })
+            .collect())
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_mirror_sync() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("mirror_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (person, topic, person_type) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    let (type_relation, topic_relation) = (Uuid::new_v4(), Uuid::new_v4());
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .create_property(DESCRIPTION_PROPERTY, PbDataType::Text)
+        .update_entity(
+            person,
+            [
+                value(NAME_PROPERTY, "Alice"),
+                value(DESCRIPTION_PROPERTY, "Researcher"),
+            ],
+        )
+        .update_entity(topic, [value(NAME_PROPERTY, "Crypto")])
+        .create_relation(relation(
+            type_relation,
+            Uuid::new_v4(),
+            TYPES_PROPERTY,
+            person,
+            person_type,
+        ))
+        .create_relation(relation(
+            topic_relation,
+            Uuid::new_v4(),
+            TYPES_PROPERTY,
+            person,
+            topic,
+        ))
+        .build();
+    let delete = EditBuilder::new(Uuid::new_v4())
+        .unset_entity_values(person, [DESCRIPTION_PROPERTY])
+        .delete_entity(topic)
+        .build();
+
+    let edit_item = |edit: Edit, cid: &str| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+    let edit_block = KgDataBuilder::new(2)
+        .edits(vec![edit_item(edit, "ipfs://mirror")])
+        .build();
+    let delete_block = KgDataBuilder::new(3)
+        .edits(vec![edit_item(delete, "ipfs://mirror-delete")])
+        .build();
+
+    let mirror = Arc::new(MemoryMirror::default());
+    let sync = MirrorSync::new(storage.pool.clone(), mirror.clone());
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            edit_block.clone(),
+        ])
+        .await?;
+    assert_eq!(sync.mirror_block(&edit_block).await?, 6);
+
+    let values = mirror.live_values();
+    assert_eq!(values.len(), 3);
+    assert!(values.iter().all(|value| value.block_number == 2));
+    assert!(values
+        .iter()
+        .any(|value| value.entity_id == person && value.string.as_deref() == Some("Alice")));
+    assert_eq!(mirror.live_relations().len(), 2);
+    assert_eq!(mirror.edits.lock().unwrap()[0].cid, "ipfs://mirror");
+
+    // The unset description, the deleted topic and the relation to it are
+    // written again as deleted
+    indexer.run(&vec![delete_block.clone()]).await?;
+    sync.mirror_block(&delete_block).await?;
+
+    let values = mirror.live_values();
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].string.as_deref(), Some("Alice"));
+    assert_eq!(
+        mirror
+            .live_relations()
+            .iter()
+            .map(|relation| relation.id)
+            .collect::<Vec<_>>(),
+        vec![type_relation]
+    );
+    assert_eq!(
+        mirror
+            .values
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|value| value.is_deleted == 1 && value.block_number == 3)
+            .count(),
+        2
+    );
+    assert_eq!(mirror.edits.lock().unwrap().len(), 2);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_deferred_sync() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("deferred_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let person = Uuid::new_v4();
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .update_entity(person, [value(NAME_PROPERTY, "Deferred")])
+        .build();
+    let edit_block = KgDataBuilder::new(2)
+        .edits(vec![PreprocessedEdit {
+            edit: Some(edit),
+            is_errored: false,
+            space_id,
+            cid: "ipfs://deferred".to_string(),
+            sender: None,
+            event_index: 0,
+        }])
+        .build();
+    let next_block = KgDataBuilder::new(3).build();
+
+    let index = Arc::new(MemorySearchIndex::default());
+    let search = SearchSync::new(storage.pool.clone(), index.clone(), 1);
+    let mirror = Arc::new(MemoryMirror::default());
+    let sync = MirrorSync::new(storage.pool.clone(), mirror.clone());
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            edit_block.clone(),
+        ])
+        .await?;
+    search.defer_block(&edit_block);
+    sync.defer_block(&edit_block);
+    assert!(index.documents.lock().unwrap().is_empty());
+    assert!(mirror.values.lock().unwrap().is_empty());
+
+    // The deferred changes are written with the next block
+    indexer.run(&vec![next_block.clone()]).await?;
+    assert_eq!(search.sync_block(&next_block).await?, 1);
+    assert_eq!(sync.mirror_block(&next_block).await?, 2);
+
+    assert_eq!(
+        index.documents.lock().unwrap()[&person].name.as_deref(),
+        Some("Deferred")
+    );
+    let values = mirror.live_values();
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].block_number, 3);
+    assert_eq!(mirror.edits.lock().unwrap()[0].block_number, 2);
+
+    // Nothing is left to write
+    assert_eq!(sync.mirror_block(&next_block).await?, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_change_notifications() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let mut listener = sqlx::postgres::PgListener::connect(&database_url).await?;
+    listener
+        .listen_all([SPACE_UPDATED_CHANNEL, PROPOSAL_UPDATED_CHANNEL])
+        .await?;
+
+    let dao_address = generate_unique_address("notify_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .update_entity(Uuid::new_v4(), [value(NAME_PROPERTY, "Notified")])
+        .build();
+    let mut block = KgDataBuilder::new(2)
+        .edits(vec![PreprocessedEdit {
+            edit: Some(edit),
+            is_errored: false,
+            space_id,
+            cid: "ipfs://notify".to_string(),
+            sender: None,
+            event_index: 0,
+        }])
+        .build();
+    block.added_members = vec![make_added_member(
+        &dao_address,
+        &generate_unique_address("notify_member"),
+    )];
+    block.created_proposals = vec![CreatedProposal {
+        proposal_id: "1".to_string(),
+        proposal_type: indexer::ProposalType::PublishEdit,
+        creator: generate_unique_address("notify_creator"),
+        dao_address: dao_address.clone(),
+        plugin_address: format!("{}_governance_plugin", dao_address),
+        preview: None,
+    }];
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            block,
+        ])
+        .await?;
+
+    // Other tests index at the same time, so only this space's
+    // notifications are kept
+    let mut changes = Vec::new();
+    let mut proposals = Vec::new();
+    while changes.len() < 3 || proposals.is_empty() {
+        let notification = tokio::time::timeout(Duration::from_secs(5), listener.recv())
+            .await
+            .expect("timed out waiting for notifications")?;
+        if notification.channel() == SPACE_UPDATED_CHANNEL {
+            let payload: SpaceUpdated = serde_json::from_str(notification.payload()).unwrap();
+            if payload.space_id == space_id {
+                changes.push(payload.change);
+            }
+        } else {
+            let payload: ProposalUpdated = serde_json::from_str(notification.payload()).unwrap();
+            if payload.space_id == space_id {
+                proposals.push(payload);
+            }
+        }
+    }
+
+    changes.sort_by_key(|change| change.as_str());
+    assert_eq!(
+        changes,
+        vec![
+            SpaceChange::Created,
+            SpaceChange::Edit,
+            SpaceChange::Members
+        ]
+    );
+    assert_eq!(
+        proposals,
+        vec![ProposalUpdated {
+            space_id,
+            proposal_id: "1".to_string(),
+            status: ProposalStatus::Created,
+        }]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_activity_rollups() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("activity_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let author = Uuid::new_v4();
+    let name_property = Uuid::new_v4();
+
+    let edit_item = |entity: Uuid, cid: &str| PreprocessedEdit {
+        edit: Some(
+            EditBuilder::new(Uuid::new_v4())
+                .author(author)
+                .create_property(name_property, PbDataType::Text)
+                .update_entity(entity, [value(name_property, cid)])
+                .build(),
+        ),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+    let proposals = |block: &mut KgData| {
+        block.created_proposals = vec![CreatedProposal {
+            proposal_id: "1".to_string(),
+            proposal_type: indexer::ProposalType::PublishEdit,
+            creator: generate_unique_address("activity_creator"),
+            dao_address: dao_address.clone(),
+            plugin_address: format!("{}_governance_plugin", dao_address),
+            preview: None,
+        }];
+        block.executed_proposals = vec![ExecutedProposal {
+            proposal_id: "1".to_string(),
+            plugin_address: format!("{}_governance_plugin", dao_address),
+            tx_hash: "0x01".to_string(),
+        }];
+    };
+
+    let mut first = KgDataBuilder::new(2)
+        .edits(vec![
+            edit_item(Uuid::new_v4(), "ipfs://activity-1"),
+            edit_item(Uuid::new_v4(), "ipfs://activity-2"),
+        ])
+        .build();
+    proposals(&mut first);
+    // The same proposal seen again, like every shard sees executed proposals
+    let mut second = KgDataBuilder::new(3)
+        .edits(vec![edit_item(Uuid::new_v4(), "ipfs://activity-3")])
+        .build();
+    proposals(&mut second);
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            first,
+            second,
+        ])
+        .await?;
+
+    // Every block of the test shares a timestamp
+    for period in RollupPeriod::ALL {
+        assert_eq!(
+            storage.get_space_activity(&space_id, period).await?,
+            vec![ActivityRollup {
+                period_start: period.start(1234567890),
+                edits: 3,
+                active_editors: 1,
+                proposals_created: 1,
+                proposals_executed: 1,
+                // The edited entities and the property's entity
+                new_entities: 4,
+            }]
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_proposal_previews() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("preview_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let entity_id = Uuid::new_v4();
+
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .update_entity(entity_id, [value(NAME_PROPERTY, "Proposed")])
+        .delete_relation(Uuid::new_v4())
+        .build();
+    let proposal = |proposal_id: &str, preview: Option<ProposalPreview>| CreatedProposal {
+        proposal_id: proposal_id.to_string(),
+        proposal_type: indexer::ProposalType::PublishEdit,
+        creator: generate_unique_address("preview_creator"),
+        dao_address: dao_address.clone(),
+        plugin_address: format!("{}_governance_plugin", dao_address),
+        preview,
+    };
+    let mut block = KgDataBuilder::new(2).build();
+    block.created_proposals = vec![
+        proposal(
+            "1",
+            Some(ProposalPreview::from_edit("ipfs://proposed", &edit)),
+        ),
+        // Proposals whose content wasn't cached aren't previewed
+        proposal("2", None),
+    ];
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .build(),
+            block,
+        ])
+        .await?;
+
+    let previews: Vec<(String, String, Vec<Uuid>, i32)> = sqlx::query_as(
+        r#"
+            SELECT proposal_id, content_uri, entity_ids, relations_deleted
+            FROM proposal_previews
+            WHERE space_id = $1
+            "#,
+    )
+    .bind(space_id)
+    .fetch_all(storage.get_pool())
+    .await
+    .map_err(StorageError::from)?;
+
+    assert_eq!(
+        previews,
+        vec![(
+            "1".to_string(),
+            "ipfs://proposed".to_string(),
+            vec![entity_id],
+            1
+        )]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_proposal_snapshots() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("snapshot_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let editor = generate_unique_address("snapshot_editor");
+    let member = generate_unique_address("snapshot_member");
+    let late_editor = generate_unique_address("snapshot_late_editor");
+    let proposal = CreatedProposal {
+        proposal_id: "1".to_string(),
+        proposal_type: indexer::ProposalType::AddEditor,
+        creator: editor.clone(),
+        dao_address: dao_address.clone(),
+        plugin_address: format!("{}_governance_plugin", dao_address),
+        preview: None,
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .added_editor(&dao_address, &editor)
+                .build(),
+            // Members added in the proposal's block are part of its electorate
+            KgDataBuilder::new(2)
+                .added_member(&dao_address, &member)
+                .created_proposal(proposal.clone())
+                .build(),
+            KgDataBuilder::new(3)
+                .added_editor(&dao_address, &late_editor)
+                .build(),
+        ])
+        .await?;
+
+    let snapshot_query = r#"
+        SELECT role, lower(address), created_at_block
+        FROM proposal_snapshots
+        WHERE space_id = $1 AND proposal_id = '1'
+        ORDER BY role
+        "#;
+    let snapshot: Vec<(String, String, String)> = sqlx::query_as(snapshot_query)
+        .bind(space_id)
+        .fetch_all(storage.get_pool())
+        .await
+        .map_err(StorageError::from)?;
+    let expected = vec![
+        ("editor".to_string(), editor.to_lowercase(), "2".to_string()),
+        ("member".to_string(), member.to_lowercase(), "2".to_string()),
+    ];
+    assert_eq!(snapshot, expected);
+
+    // Processing the proposal's block again keeps the snapshot it was
+    // created with
+    indexer
+        .run(&vec![KgDataBuilder::new(2)
+            .created_proposal(proposal)
+            .build()])
+        .await?;
+    let snapshot: Vec<(String, String, String)> = sqlx::query_as(snapshot_query)
+        .bind(space_id)
+        .fetch_all(storage.get_pool())
+        .await
+        .map_err(StorageError::from)?;
+    assert_eq!(snapshot, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_proposal_cancellations() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("cancel_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let plugin_address = generate_unique_address("cancel_plugin");
+    let creator = generate_unique_address("cancel_creator");
+    let vetoer = generate_unique_address("cancel_vetoer");
+    let proposal = |proposal_id: &str| CreatedProposal {
+        proposal_id: proposal_id.to_string(),
+        proposal_type: indexer::ProposalType::AddMember,
+        creator: creator.clone(),
+        dao_address: dao_address.clone(),
+        plugin_address: plugin_address.clone(),
+        preview: None,
+    };
+    let executed = |proposal_id: &str| ExecutedProposal {
+        proposal_id: proposal_id.to_string(),
+        plugin_address: plugin_address.clone(),
+        tx_hash: format!("0x{}", proposal_id),
+    };
+    let canceled = |proposal_id: &str, canceled_by: &str| CanceledProposal {
+        proposal_id: proposal_id.to_string(),
+        plugin_address: plugin_address.clone(),
+        canceled_by: canceled_by.to_string(),
+    };
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .created_proposal(proposal("1"))
+                .created_proposal(proposal("2"))
+                .created_proposal(proposal("3"))
+                .created_proposal(proposal("4"))
+                .build(),
+            // Withdrawn by its creator, vetoed by an editor, and executed
+            KgDataBuilder::new(2)
+                .canceled_proposal(canceled("1", &creator))
+                .canceled_proposal(canceled("2", &vetoer))
+                .executed_proposal(executed("3"))
+                .build(),
+            // Settled proposals don't change status again
+            KgDataBuilder::new(3)
+                .executed_proposal(executed("1"))
+                .canceled_proposal(canceled("3", &vetoer))
+                .build(),
+        ])
+        .await?;
+
+    let proposals: Vec<(String, String, Option<String>, Option<String>)> = sqlx::query_as(
+        r#"
+        SELECT status, proposal_id, lower(canceled_by), coalesce(canceled_at_block, executed_at_block)
+        FROM proposals
+        WHERE space_id = $1
+        ORDER BY proposal_id
+        "#,
+    )
+    .bind(space_id)
+    .fetch_all(storage.get_pool())
+    .await
+    .map_err(StorageError::from)?;
+    let row =
+        |status: &str, proposal_id: &str, canceled_by: Option<&String>, block: Option<&str>| {
+            (
+                status.to_string(),
+                proposal_id.to_string(),
+                canceled_by.map(|address| address.to_lowercase()),
+                block.map(str::to_string),
+            )
+        };
+    assert_eq!(
+        proposals,
+        vec![
+            row("canceled", "1", Some(&creator), Some("2")),
+            row("canceled", "2", Some(&vetoer), Some("2")),
+            row("executed", "3", None, Some("2")),
+            row("created", "4", None, None),
+        ]
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_proposal_execution_details() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("execution_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let plugin_address = generate_unique_address("execution_plugin");
+    let tx_hash = format!("0x{}", "ab".repeat(32));
+
+    indexer
+        .run(&vec![
+            KgDataBuilder::new(1)
+                .spaces(vec![make_public_space(&dao_address)])
+                .created_proposal(CreatedProposal {
+                    proposal_id: "1".to_string(),
+                    proposal_type: indexer::ProposalType::AddEditor,
+                    creator: generate_unique_address("execution_creator"),
+                    dao_address: dao_address.clone(),
+                    plugin_address: plugin_address.clone(),
+                    preview: None,
+                })
+                .build(),
+            KgDataBuilder::new(2)
+                .timestamp("1700000000")
+                .executed_proposal(ExecutedProposal {
+                    proposal_id: "1".to_string(),
+                    plugin_address: plugin_address.clone(),
+                    tx_hash: tx_hash.clone(),
+                })
+                .build(),
+        ])
+        .await?;
+
+    let execution: (String, String, String, String) = sqlx::query_as(
+        r#"
+        SELECT status, executed_at, executed_at_block, executed_tx_hash
+        FROM proposals
+        WHERE space_id = $1 AND proposal_id = '1'
+        "#,
+    )
+    .bind(space_id)
+    .fetch_one(storage.get_pool())
+    .await
+    .map_err(StorageError::from)?;
+    assert_eq!(
+        execution,
+        (
+            "executed".to_string(),
+            "1700000000".to_string(),
+            "2".to_string(),
+            tx_hash
+        )
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_space_membership_counts() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("counts_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (alice, bob, carol) = (
+        generate_unique_address("counts_alice"),
+        generate_unique_address("counts_bob"),
+        generate_unique_address("counts_carol"),
+    );
+
+    let counts = || async {
+        sqlx::query_as::<_, (i32, i32)>(
+            "SELECT member_count, editor_count FROM spaces WHERE id = $1",
+        )
+        .bind(space_id)
+        .fetch_one(storage.get_pool())
+        .await
+        .map_err(StorageError::from)
+    };
+
+    let blocks = vec![
+        KgDataBuilder::new(1)
+            .spaces(vec![make_public_space(&dao_address)])
+            .added_member(&dao_address, &alice)
+            .added_member(&dao_address, &bob)
+            .added_editor(&dao_address, &alice)
+            .build(),
+        // Adding a member twice or removing one that isn't a member doesn't
+        // change the counts
+        KgDataBuilder::new(2)
+            .added_member(&dao_address, &bob)
+            .added_member(&dao_address, &carol)
+            .added_editor(&dao_address, &bob)
+            .removed_member(&dao_address, &alice)
+            .removed_editor(&dao_address, &carol)
+            .build(),
+    ];
+    indexer.run(&blocks).await?;
+    assert_eq!(counts().await?, (2, 2));
+
+    // Replayed blocks don't count their changes again
+    indexer.run(&blocks).await?;
+    assert_eq!(counts().await?, (2, 2));
+
+    indexer
+        .run(&vec![KgDataBuilder::new(3)
+            .removed_editor(&dao_address, &alice)
+            .removed_member(&dao_address, &bob)
+            .build()])
+        .await?;
+    assert_eq!(counts().await?, (1, 1));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_personal_admin_changes() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("admin_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let first_plugin = format!("{}_personal_plugin", dao_address);
+    let second_plugin = generate_unique_address("admin_plugin");
+    let (alice, bob) = (
+        generate_unique_address("admin_alice"),
+        generate_unique_address("admin_bob"),
+    );
+
+    let personal_address = || async {
+        sqlx::query_scalar::<_, Option<String>>("SELECT personal_address FROM spaces WHERE id = $1")
+            .bind(space_id)
+            .fetch_one(storage.get_pool())
+            .await
+            .map_err(StorageError::from)
+    };
+    let history = || async {
+        sqlx::query_as::<_, (String, Option<String>, i64, Option<i64>)>(
+            "SELECT personal_address, admin_address, added_at_block, replaced_at_block
+             FROM personal_admin_history WHERE space_id = $1 ORDER BY added_at_block",
+        )
+        .bind(space_id)
+        .fetch_all(storage.get_pool())
+        .await
+        .map_err(StorageError::from)
+    };
+
+    let blocks = vec![
+        KgDataBuilder::new(1)
+            .spaces(vec![make_personal_space(&dao_address)])
+            .personal_admin_change(&dao_address, &first_plugin, Some(&alice))
+            .build(),
+        KgDataBuilder::new(2)
+            .personal_admin_change(&dao_address, &second_plugin, Some(&bob))
+            .build(),
+    ];
+    indexer.run(&blocks).await?;
+    assert_eq!(
+        personal_address().await?,
+        Some(checksum_address(&second_plugin))
+    );
+
+    let expected = vec![
+        (
+            checksum_address(&first_plugin),
+            Some(checksum_address(&alice)),
+            1,
+            Some(2),
+        ),
+        (
+            checksum_address(&second_plugin),
+            Some(checksum_address(&bob)),
+            2,
+            None,
+        ),
+    ];
+    assert_eq!(history().await?, expected);
+
+    // Replaying the first block doesn't bring the first admin back
+    indexer.run(&blocks[..1].to_vec()).await?;
+    assert_eq!(
+        personal_address().await?,
+        Some(checksum_address(&second_plugin))
+    );
+    assert_eq!(history().await?, expected);
+
+    // Changes to public spaces are ignored
+    let public_dao = generate_unique_address("admin_test_public");
+    indexer
+        .run(&vec![KgDataBuilder::new(3)
+            .spaces(vec![make_public_space(&public_dao)])
+            .personal_admin_change(&public_dao, &second_plugin, Some(&bob))
+            .build()])
+        .await?;
+    let public_history: i64 =
+        sqlx::query_scalar("SELECT count(*) FROM personal_admin_history WHERE space_id = $1")
+            .bind(Network::default().space_id(&checksum_address(&public_dao)))
+            .fetch_one(storage.get_pool())
+            .await
+            .map_err(StorageError::from)?;
+    assert_eq!(public_history, 0);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_governance_plugin_changes() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("plugins_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let first_voting = format!("{}_governance_plugin", dao_address);
+    let membership = format!("{}_membership_plugin", dao_address);
+    let second_voting = generate_unique_address("plugins_voting");
+
+    let plugins = || async {
+        sqlx::query_as::<_, (Option<String>, Option<String>)>(
+            "SELECT main_voting_address, membership_address FROM spaces WHERE id = $1",
+        )
+        .bind(space_id)
+        .fetch_one(storage.get_pool())
+        .await
+        .map_err(StorageError::from)
+    };
+    let history = || async {
+        sqlx::query_as::<_, (String, String, i64, Option<i64>)>(
+            "SELECT plugin_type, plugin_address, added_at_block, replaced_at_block
+             FROM space_plugins_history WHERE space_id = $1
+             ORDER BY added_at_block, plugin_type",
+        )
+        .bind(space_id)
+        .fetch_all(storage.get_pool())
+        .await
+        .map_err(StorageError::from)
+    };
+
+    let blocks = vec![
+        KgDataBuilder::new(1)
+            .spaces(vec![make_public_space(&dao_address)])
+            .governance_plugin_change(&dao_address, &first_voting, &membership)
+            .build(),
+        // Only the voting plugin is upgraded
+        KgDataBuilder::new(2)
+            .governance_plugin_change(&dao_address, &second_voting, &membership)
+            .build(),
+    ];
+    indexer.run(&blocks).await?;
+
+    let current = (
+        Some(checksum_address(&second_voting)),
+        Some(checksum_address(&membership)),
+    );
+    assert_eq!(plugins().await?, current);
+    let expected = vec![
+        (
+            "membership".to_string(),
+            checksum_address(&membership),
+            1,
+            None,
+        ),
+        (
+            "voting".to_string(),
+            checksum_address(&first_voting),
+            1,
+            Some(2),
+        ),
+        (
+            "voting".to_string(),
+            checksum_address(&second_voting),
+            2,
+            None,
+        ),
+    ];
+    assert_eq!(history().await?, expected);
+
+    // Replaying the first block doesn't bring the first plugin back
+    indexer.run(&blocks[..1].to_vec()).await?;
+    assert_eq!(plugins().await?, current);
+    assert_eq!(history().await?, expected);
+
+    // A proposal of the replaced plugin is still counted for the space
+    let proposal_id = Uuid::new_v4().to_string();
+    let mut block = KgDataBuilder::new(3).build();
+    block.executed_proposals = vec![ExecutedProposal {
+        proposal_id: proposal_id.clone(),
+        plugin_address: first_voting.clone(),
+        tx_hash: "0x01".to_string(),
+    }];
+    indexer.run(&vec![block]).await?;
+
+    let counted: Vec<Uuid> =
+        sqlx::query_scalar("SELECT space_id FROM counted_proposals WHERE proposal_id = $1")
+            .bind(&proposal_id)
+            .fetch_all(storage.get_pool())
+            .await
+            .map_err(StorageError::from)?;
+    assert_eq!(counted, vec![space_id]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_space_provenance() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let dao_address = generate_unique_address("provenance_test");
+    let creator = generate_unique_address("provenance_creator");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let space = CreatedSpace::Public(PublicSpace {
+        creator: Some(creator.clone()),
+        tx_hash: Some("0xabc".to_string()),
+        ..match make_public_space(&dao_address) {
+            CreatedSpace::Public(space) => space,
+            CreatedSpace::Personal(_) => unreachable!(),
+        }
+    });
+
+    let provenance = || async {
+        sqlx::query_as::<_, (Option<String>, Option<String>, Option<String>, Option<String>)>(
+            "SELECT creator, created_tx_hash, created_at, created_at_block FROM spaces WHERE id = $1",
+        )
+        .bind(space_id)
+        .fetch_one(storage.get_pool())
+        .await
+        .map_err(StorageError::from)
+    };
+    let expected = (
+        Some(checksum_address(&creator)),
+        Some("0xabc".to_string()),
+        Some("1700000000".to_string()),
+        Some("5".to_string()),
+    );
+
+    indexer
+        .run(&vec![KgDataBuilder::new(5)
+            .timestamp("1700000000")
+            .spaces(vec![space.clone()])
+            .build()])
+        .await?;
+    assert_eq!(provenance().await?, expected);
+
+    // A space created again keeps the block it was first created in
+    indexer
+        .run(&vec![KgDataBuilder::new(7).spaces(vec![space]).build()])
+        .await?;
+    assert_eq!(provenance().await?, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_edit_provenance() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let space_id = Uuid::new_v4();
+    let (edit_id, from_id, to_id, relation_id) = (
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+        Uuid::new_v4(),
+    );
+
+    let edit = EditBuilder::new(edit_id)
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .update_entity(from_id, [value(NAME_PROPERTY, "Traced")])
+        .create_relation(relation(
+            relation_id,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            from_id,
+            to_id,
+        ))
+        .build();
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![PreprocessedEdit {
+                edit: Some(edit),
+                is_errored: false,
+                space_id,
+                cid: "ipfs://provenance".to_string(),
+                sender: None,
+                event_index: 0,
+            }])
+            .build()])
+        .await?;
+
+    let entities = storage
+        .get_entities_with_values(&[from_id], &space_id)
+        .await?;
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].values.len(), 1);
+    assert_eq!(entities[0].values[0].edit_id, Some(edit_id));
+    assert_eq!(entities[0].relations.len(), 1);
+    assert_eq!(entities[0].relations[0].edit_id, Some(edit_id));
+
+    let relations = storage
+        .get_outgoing_relations(&from_id, &space_id, &PageRequest::default())
+        .await?
+        .items;
+    assert_eq!(relations[0].edit_id, Some(edit_id));
+
+    // Both trace back to the CID the edit was published under
+    let cids: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT e.cid FROM values v
+        JOIN edits e ON e.edit_id = v.edit_id AND e.space_id = v.space_id
+        WHERE v.entity_id = $1
+        UNION ALL
+        SELECT e.cid FROM relations r
+        JOIN edits e ON e.edit_id = r.edit_id AND e.space_id = r.space_id
+        WHERE r.id = $2
+        "#,
+    )
+    .bind(from_id)
+    .bind(relation_id)
+    .fetch_all(storage.get_pool())
+    .await
+    .map_err(StorageError::from)?;
+    assert_eq!(cids, vec!["ipfs://provenance", "ipfs://provenance"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_block_properties_keep_first_data_type() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache.clone());
+
+    // Two spaces create the same property in one block, with different types
+    let property_id = Uuid::new_v4();
+    let (first_space, second_space) = (Uuid::new_v4(), Uuid::new_v4());
+    let (first_entity, second_entity) = (Uuid::new_v4(), Uuid::new_v4());
+    let preprocessed =
+        |space_id: Uuid, data_type: PbDataType, entity: Uuid, raw: &str| PreprocessedEdit {
+            edit: Some(
+                EditBuilder::new(Uuid::new_v4())
+                    .create_property(property_id, data_type)
+                    .update_entity(entity, [value(property_id, raw)])
+                    .build(),
+            ),
+            is_errored: false,
+            space_id,
+            cid: format!("ipfs://{}", entity),
+            sender: None,
+            event_index: 0,
+        };
+
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![
+                preprocessed(first_space, PbDataType::Number, first_entity, "42"),
+                preprocessed(second_space, PbDataType::Text, second_entity, "forty-two"),
+            ])
+            .build()])
+        .await?;
+
+    let data_type: String = sqlx::query_scalar("SELECT type::text FROM properties WHERE id = $1")
+        .bind(property_id)
+        .fetch_one(storage.get_pool())
+        .await
+        .map_err(StorageError::from)?;
+    assert_eq!(data_type, "Number");
+    assert_eq!(
+        properties_cache.get(&property_id).unwrap(),
+        DataType::Number
+    );
+
+    // The second space's value is validated against the first data type
+    let first = storage
+        .get_entities_with_values(&[first_entity], &first_space)
+        .await?;
+    assert_eq!(first[0].values.len(), 1);
+    let second = storage
+        .get_entities_with_values(&[second_entity], &second_space)
+        .await?;
+    assert!(second.iter().all(|entity| entity.values.is_empty()));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_edits_apply_in_event_order() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let (space_id, entity_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let preprocessed = |name: &str, event_index: usize| PreprocessedEdit {
+        edit: Some(
+            EditBuilder::new(Uuid::new_v4())
+                .create_property(NAME_PROPERTY, PbDataType::Text)
+                .update_entity(entity_id, [value(NAME_PROPERTY, name)])
+                .build(),
+        ),
+        is_errored: false,
+        space_id,
+        cid: format!("ipfs://{}", name),
+        sender: None,
+        event_index,
+    };
+
+    // The edits arrive out of order, as when their fetches finish out of order
+    indexer
+        .run(&vec![KgDataBuilder::new(1)
+            .edits(vec![
+                preprocessed("Third", 2),
+                preprocessed("First", 0),
+                preprocessed("Second", 1),
+            ])
+            .build()])
+        .await?;
+
+    let entities = storage
+        .get_entities_with_values(&[entity_id], &space_id)
+        .await?;
+    assert_eq!(entities[0].values.len(), 1);
+    assert_eq!(entities[0].values[0].string.as_deref(), Some("Third"));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_ops_journal() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let properties_cache = Arc::new(PropertiesCache::new());
+    let indexer = TestIndexer::new(storage.clone(), properties_cache);
+
+    let space_id = Uuid::new_v4();
+    let (entity, relation_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let edit_item = |edit, cid: &str| PreprocessedEdit {
+        edit: Some(edit),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+
+    let first = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .update_entity(entity, [value(NAME_PROPERTY, "Journaled")])
+        .build();
+    let second = EditBuilder::new(Uuid::new_v4())
+        .create_relation(relation(
+            relation_id,
+            Uuid::new_v4(),
+            Uuid::new_v4(),
+            entity,
+            entity,
+        ))
+        .delete_entity(entity)
+        .build();
+
+    let blocks = vec![
+        KgDataBuilder::new(1)
+            .edits(vec![edit_item(first.clone(), "ipfs://journal-1")])
+            .build(),
+        // Content that was already applied to the space isn't journaled again
+        KgDataBuilder::new(2)
+            .edits(vec![
+                edit_item(second, "ipfs://journal-2"),
+                edit_item(first, "ipfs://journal-1-again"),
+            ])
+            .build(),
+    ];
+    indexer.run(&blocks).await?;
+    // Replaying the blocks doesn't journal their ops twice
+    indexer.run(&blocks).await?;
+
+    let mut ops = Vec::new();
+    let mut page = Some(PageRequest::first(3));
+    while let Some(request) = page {
+        let result = storage.list_ops_journal(Some(&space_id), &request).await?;
+        page = request.next(&result);
+        ops.extend(result.items);
+    }
+
+    let journaled: Vec<(i64, &str, Uuid)> = ops
+        .iter()
+        .map(|op| (op.block_number, op.op_kind.as_str(), op.target_id))
+        .collect();
+    assert_eq!(
+        journaled,
+        vec![
+            (1, "create_property", NAME_PROPERTY),
+            (1, "update_entity", entity),
+            (2, "create_relation", relation_id),
+            (2, "delete_entity", entity),
+        ]
+    );
+    assert!(ops.windows(2).all(|w| w[0].sequence < w[1].sequence));
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_id_scheme_check_and_mapping() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let pool = storage.get_pool().clone();
+
+    sqlx::query("DROP SCHEMA IF EXISTS gaia_id_scheme CASCADE")
+        .execute(&pool)
+        .await?;
+    sqlx::query("CREATE SCHEMA gaia_id_scheme")
+        .execute(&pool)
+        .await?;
+    for table in ["id_scheme", "id_mappings", "spaces", "values"] {
+        sqlx::query(&format!(
+            "CREATE TABLE gaia_id_scheme.{} (LIKE public.{} INCLUDING ALL)",
+            table, table
+        ))
+        .execute(&pool)
+        .await?;
+    }
+
+    // A space indexed before the scheme was recorded
+    let dao_address = "0x1234567890123456789012345678901234567890";
+    let space_id = IdScheme::V1.space_id(GEO, dao_address);
+    let (entity_id, property_id, orphaned_space_id) =
+        (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    sqlx::query(
+        "INSERT INTO gaia_id_scheme.spaces (id, type, dao_address, space_address, network)
+        VALUES ($1, 'Public', $2, $2, $3)",
+    )
+    .bind(space_id)
+    .bind(dao_address)
+    .bind(GEO)
+    .execute(&pool)
+    .await?;
+    let value_id = IdScheme::V1.value_id(&entity_id, &property_id, &space_id);
+    sqlx::query(
+        "INSERT INTO gaia_id_scheme.values (id, property_id, entity_id, space_id, string)
+        VALUES ($1, $2, $3, $4, 'mapped'), ($5, $2, $3, $6, 'orphaned')",
+    )
+    .bind(value_id.to_string())
+    .bind(property_id)
+    .bind(entity_id)
+    .bind(space_id)
+    .bind(Uuid::new_v4().to_string())
+    .bind(orphaned_space_id)
+    .execute(&pool)
+    .await?;
+
+    let schema_url = format!("{}?options=-csearch_path%3Dgaia_id_scheme", database_url);
+    let scoped = PostgresStorage::new(&schema_url).await?;
+
+    // The scheme the spaces were indexed with is recorded, not the
+    // configured one
+    let result = scoped.check_id_scheme(IdScheme::V2).await;
+    assert!(matches!(
+        result,
+        Err(StorageError::IdSchemeMismatch { ref recorded, configured: IdScheme::V2 })
+            if recorded == "v1"
+    ));
+    scoped.check_id_scheme(IdScheme::V1).await?;
+    let recorded: Vec<String> = sqlx::query_scalar("SELECT scheme FROM id_scheme")
+        .fetch_all(scoped.get_pool())
+        .await?;
+    assert_eq!(recorded, vec!["v1"]);
+
+    let mapper = IdMapper::new(scoped.get_pool().clone(), 1);
+    let report = mapper.run(IdScheme::V2).await?;
+    assert_eq!(
+        (report.spaces, report.values, report.skipped_values),
+        (1, 1, 1)
+    );
+    // Mapping again replaces the earlier mappings
+    mapper.run(IdScheme::V2).await?;
+
+    let new_space_id = IdScheme::V2.space_id(GEO, dao_address);
+    let mappings: Vec<(String, String, Uuid)> = sqlx::query_as(
+        "SELECT kind, old_id, new_id FROM id_mappings WHERE scheme = 'v2' ORDER BY kind",
+    )
+    .fetch_all(scoped.get_pool())
+    .await?;
+    assert_eq!(
+        mappings,
+        vec![
+            ("space".to_string(), space_id.to_string(), new_space_id),
+            (
+                "value".to_string(),
+                value_id.to_string(),
+                IdScheme::V2.value_id(&entity_id, &property_id, &new_space_id)
+            ),
+        ]
+    );
+
+    sqlx::query("DROP SCHEMA gaia_id_scheme CASCADE")
+        .execute(&pool)
+        .await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_recording_storage_captures_handler_calls() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    // Nothing is written to the database, the handlers only begin and
+    // commit their transactions on it
+    let pool = sqlx::PgPool::connect(&database_url).await?;
+    let storage = Arc::new(RecordingStorage::new(pool));
+    let properties_cache = Arc::new(PropertiesCache::new());
+
+    let dao_address = generate_unique_address("recording_test");
+    let space_id = Network::default().space_id(&checksum_address(&dao_address));
+    let (entity, other_space_id) = (Uuid::new_v4(), Uuid::new_v4());
+    let edit = EditBuilder::new(Uuid::new_v4())
+        .create_property(NAME_PROPERTY, PbDataType::Text)
+        .update_entity(entity, [value(NAME_PROPERTY, "Recorded")])
+        // A relation into a space that isn't indexed
+        .create_relation(Relation {
+            to_space: Some(other_space_id.as_bytes().to_vec()),
+            ..relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                TYPES_PROPERTY,
+                entity,
+                SPACE_TYPE,
+            )
+        })
+        .build();
+    let preprocessed = |cid: &str| PreprocessedEdit {
+        edit: Some(edit.clone()),
+        is_errored: false,
+        space_id,
+        cid: cid.to_string(),
+        sender: None,
+        event_index: 0,
+    };
+    let data = KgDataBuilder::new(1)
+        .edits(vec![
+            preprocessed("ipfs://recorded"),
+            // The same content under another CID is squashed
+            preprocessed("ipfs://recorded-again"),
+        ])
+        .spaces(vec![make_public_space(&dao_address)])
+        .build();
+
+    assert!(
+        root_handler::run_once(&data, &data.block, &storage, &properties_cache, "recording")
+            .await?
+    );
+
+    let names = storage.call_names();
+    assert_eq!(names.first(), Some(&"is_block_processed"));
+    assert_eq!(names.last(), Some(&"record_processed_block"));
+
+    let edit_calls: Vec<&str> = names
+        .iter()
+        .copied()
+        .filter(|name| {
+            !matches!(
+                *name,
+                "is_block_processed"
+                    | "record_processed_block"
+                    | "insert_spaces"
+                    | "append_ops_journal"
+                    | "record_activity"
+            )
+        })
+        .collect();
+    assert_eq!(
+        edit_calls,
+        vec![
+            // The block's properties are written once, before its edits
+            "insert_properties",
+            "record_edit",
+            "delete_entities",
+            "lock_entities",
+            "insert_entities",
+            "record_edited_entities",
+            "insert_values",
+            "delete_values",
+            "find_unknown_spaces",
+            "insert_relations",
+            "update_relations",
+            "unset_relation_fields",
+            "delete_relations",
+            "update_property_metadata",
+            "update_space_metadata",
+            // The second copy stops once its content is found recorded
+            "record_edit",
+        ]
+    );
+
+    // The journal and activity are written once the edits are committed
+    let position = |name: &str| names.iter().position(|n| *n == name).unwrap();
+    assert!(position("append_ops_journal") > position("update_space_metadata"));
+    assert!(position("append_ops_journal") < position("record_activity"));
+
+    let calls = storage.calls();
+    let cids: Vec<&str> = calls
+        .iter()
+        .filter_map(|call| match call {
+            StorageCall::RecordEdit { cid, .. } => Some(cid.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(cids, vec!["ipfs://recorded", "ipfs://recorded-again"]);
+    let unknown = calls.iter().find_map(|call| match call {
+        StorageCall::FindUnknownSpaces(space_ids) => Some(space_ids.clone()),
+        _ => None,
+    });
+    assert_eq!(unknown, Some(vec![other_space_id]));
+
+    // A processed block is skipped
+    storage.clear_calls();
+    assert!(
+        !root_handler::run_once(&data, &data.block, &storage, &properties_cache, "recording")
+            .await?
+    );
+    assert_eq!(storage.call_names(), vec!["is_block_processed"]);
+
+    Ok(())
+}
+
+#[tokio::test]
+#[serial]
+async fn test_scenarios() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let test_storage = TestStorage::new(storage.clone());
+
+    let scenarios = Scenario::load_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/scenarios"))
+        .expect("scenarios should load");
+    assert!(!scenarios.is_empty());
+
+    for scenario in scenarios {
+        // Edits whose content was applied before are skipped, so the
+        // scenarios' edits have to be new on every run
+        test_storage.clear_table("edits").await?;
+        let indexer = TestIndexer::new(storage.clone(), Arc::new(PropertiesCache::new()));
+        indexer
+            .run(&scenario.blocks().expect("scenario blocks should build"))
+            .await?;
+
+        let failures = scenario.verify(&storage).await?;
+        assert!(
+            failures.is_empty(),
+            "scenario '{}' failed: {:?}",
+            scenario.name,
+            failures
+        );
+
+        let snapshot = scenario.snapshot(&storage).await?;
+        let changes = scenario
+            .check_snapshot(&snapshot)
+            .expect("snapshot should be readable, set UPDATE_SNAPSHOTS=1 to write it");
+        assert!(
+            changes.is_empty(),
+            "scenario '{}' doesn't match its snapshot, set UPDATE_SNAPSHOTS=1 if the changes are intended:\n{}",
+            scenario.name,
+            changes.join("\n")
+        );
+    }
+
+    Ok(())
+}
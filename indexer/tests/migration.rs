@@ -13,6 +13,7 @@ use indexer::{
     storage::postgres::PostgresStorage,
     CreatedSpace, KgData, PersonalSpace, PublicSpace,
 };
+use indexer_utils::network_ids::Network;
 
 struct TestIndexer {
     storage: Arc<PostgresStorage>,
@@ -65,6 +66,8 @@ async fn main() -> Result<(), IndexingError> {
         edit: Some(root_space_edit.clone().unwrap()),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let crypto_space_preprocessed_edit = PreprocessedEdit {
@@ -72,6 +75,8 @@ async fn main() -> Result<(), IndexingError> {
         edit: Some(crypto_space_edit.clone().unwrap()),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let crypto_events_space_preprocessed_edit = PreprocessedEdit {
@@ -79,6 +84,8 @@ async fn main() -> Result<(), IndexingError> {
         edit: Some(crypto_events_space_edit.clone().unwrap()),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let regions_space_preprocessed_edit = PreprocessedEdit {
@@ -86,6 +93,8 @@ async fn main() -> Result<(), IndexingError> {
         edit: Some(regions_space_edit.clone().unwrap()),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let crypto_news_preprocessed_edit = PreprocessedEdit {
@@ -93,6 +102,8 @@ async fn main() -> Result<(), IndexingError> {
         edit: Some(crypto_news_space_edit.clone().unwrap()),
         is_errored: false,
         cid: "".to_string(),
+        sender: None,
+        event_index: 0,
     };
 
     let block_1 = BlockMetadata {
@@ -112,30 +123,40 @@ async fn main() -> Result<(), IndexingError> {
         space_address: "0xABCDEF1234567890123456789012345678901234".to_string(),
         membership_plugin: "0x1111111111111111111111111111111111111111".to_string(),
         governance_plugin: "0x3333333333333333333333333333333333333333".to_string(),
+        creator: None,
+        tx_hash: None,
     });
 
     let crypto_space = CreatedSpace::Personal(PersonalSpace {
         dao_address: "0x0987654321098765432109876543210987654321".to_string(),
         space_address: "0xFEDCBA0987654321098765432109876543210987".to_string(),
         personal_plugin: "0x2222222222222222222222222222222222222222".to_string(),
+        creator: None,
+        tx_hash: None,
     });
 
     let crypto_events_space = CreatedSpace::Personal(PersonalSpace {
         dao_address: "0x098765432109876543210987654321098765432f".to_string(),
         space_address: "0xFEDCBA0987654321098765432109876543210987".to_string(),
         personal_plugin: "0x2222222222222222222222222222222222222222".to_string(),
+        creator: None,
+        tx_hash: None,
     });
 
     let regions_space = CreatedSpace::Personal(PersonalSpace {
         dao_address: "0x098765432109876543210987654321098765432e".to_string(),
         space_address: "0xFEDCBA0987654321098765432109876543210987".to_string(),
         personal_plugin: "0x2222222222222222222222222222222222222222".to_string(),
+        creator: None,
+        tx_hash: None,
     });
 
     let crypto_news_space = CreatedSpace::Personal(PersonalSpace {
         dao_address: "0x098765432109876543210987654321098765432g".to_string(),
         space_address: "0xFEDCBA0987654321098765432109876543210987".to_string(),
         personal_plugin: "0x2222222222222222222222222222222222222222".to_string(),
+        creator: None,
+        tx_hash: None,
     });
 
     let indexer = TestIndexer::new(storage.clone(), properties_cache.clone());
@@ -153,6 +174,13 @@ async fn main() -> Result<(), IndexingError> {
                 removed_members: vec![],
                 added_subspaces: vec![],
                 removed_subspaces: vec![],
+                personal_admin_changes: vec![],
+                governance_plugin_changes: vec![],
+                created_proposals: vec![],
+                executed_proposals: vec![],
+                canceled_proposals: vec![],
+                network: Network::default(),
+                unowned_properties: vec![],
             },
             KgData {
                 block: block_2,
@@ -168,6 +196,13 @@ async fn main() -> Result<(), IndexingError> {
                 removed_members: vec![],
                 added_subspaces: vec![],
                 removed_subspaces: vec![],
+                personal_admin_changes: vec![],
+                governance_plugin_changes: vec![],
+                created_proposals: vec![],
+                executed_proposals: vec![],
+                canceled_proposals: vec![],
+                network: Network::default(),
+                unowned_properties: vec![],
             },
         ])
         .await?;
@@ -0,0 +1,150 @@
+use indexer::{
+    error::IndexingError,
+    storage::postgres::{PostgresStorage, MIGRATOR},
+};
+use sqlx::PgPool;
+use uuid::Uuid;
+
+#[sqlx::test(migrations = false)]
+async fn test_embedded_migrations_set_up_a_fresh_database(
+    pool: PgPool,
+) -> Result<(), IndexingError> {
+    let storage = PostgresStorage { pool };
+
+    storage.migrate().await?;
+    // Applied migrations are skipped on the next startup
+    storage.migrate().await?;
+
+    let applied: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM _sqlx_migrations WHERE success")
+        .fetch_one(&storage.pool)
+        .await?;
+    assert_eq!(applied as usize, MIGRATOR.iter().count());
+
+    // The schema matches the latest migration
+    sqlx::query("SELECT network, chain_id FROM spaces")
+        .fetch_all(&storage.pool)
+        .await?;
+    sqlx::query("SELECT space_id, content_hash FROM edits")
+        .fetch_all(&storage.pool)
+        .await?;
+
+    Ok(())
+}
+
+/// The API reads as `gaia_reader`, which must either be denied every table
+/// keyed by space or only be shown the spaces its viewer can read.
+#[sqlx::test(migrations = false)]
+async fn test_reader_role_hides_private_spaces_in_every_table(
+    pool: PgPool,
+) -> Result<(), IndexingError> {
+    let storage = PostgresStorage { pool };
+    storage.migrate().await?;
+
+    let (public_space, private_space) = (Uuid::new_v4(), Uuid::new_v4());
+    let member = "0x000000000000000000000000000000000000bEEF";
+    sqlx::query(
+        "INSERT INTO spaces (id, type, dao_address, space_address, is_private) VALUES
+        ($1, 'Public', '0x0000000000000000000000000000000000000a01', '0x0000000000000000000000000000000000000a02', false),
+        ($2, 'Personal', '0x0000000000000000000000000000000000000a11', '0x0000000000000000000000000000000000000a12', true)",
+    )
+    .bind(public_space)
+    .bind(private_space)
+    .execute(&storage.pool)
+    .await?;
+    sqlx::query("INSERT INTO members (address, space_id) VALUES ($1, $2)")
+        .bind(member)
+        .bind(private_space)
+        .execute(&storage.pool)
+        .await?;
+    sqlx::query("INSERT INTO editors (address, space_id) VALUES ($1, $2)")
+        .bind(member)
+        .bind(private_space)
+        .execute(&storage.pool)
+        .await?;
+    sqlx::query("INSERT INTO subspaces (parent_space_id, child_space_id) VALUES ($1, $2)")
+        .bind(public_space)
+        .bind(private_space)
+        .execute(&storage.pool)
+        .await?;
+
+    // Tables the role can read are filtered by row level security
+    let unfiltered: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT DISTINCT c.relname::text FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid
+        WHERE n.nspname = 'public' AND c.relkind IN ('r', 'p')
+            AND a.attnum > 0 AND NOT a.attisdropped AND a.atttypid = 'uuid'::regtype
+            AND (a.attname LIKE '%space_id' OR a.attname IN ('space', 'space_pov'))
+            AND has_table_privilege('gaia_reader', c.oid, 'SELECT')
+            AND NOT c.relrowsecurity
+        "#,
+    )
+    .fetch_all(&storage.pool)
+    .await?;
+    assert_eq!(unfiltered, Vec::<String>::new());
+
+    let columns: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT c.relname::text, a.attname::text FROM pg_class c
+        JOIN pg_namespace n ON n.oid = c.relnamespace
+        JOIN pg_attribute a ON a.attrelid = c.oid
+        WHERE n.nspname = 'public' AND c.relkind IN ('r', 'p')
+            AND a.attnum > 0 AND NOT a.attisdropped AND a.atttypid = 'uuid'::regtype
+            AND (a.attname LIKE '%space_id' OR a.attname IN ('space', 'space_pov')
+                OR (c.relname = 'spaces' AND a.attname = 'id'))
+        ORDER BY 1, 2
+        "#,
+    )
+    .fetch_all(&storage.pool)
+    .await?;
+    assert!(columns.len() > 20, "found {} space columns", columns.len());
+
+    let read_as = |viewer: &'static str, table: String, column: String| {
+        let pool = storage.pool.clone();
+        async move {
+            let mut tx = pool.begin().await?;
+            sqlx::query(
+                "SELECT set_config('role', 'gaia_reader', true), set_config('gaia.viewer', $1, true)",
+            )
+            .bind(viewer)
+            .execute(&mut *tx)
+            .await?;
+            let count: Result<i64, sqlx::Error> = sqlx::query_scalar(&format!(
+                r#"SELECT count(*) FROM "{}" WHERE "{}" = $1"#,
+                table, column
+            ))
+            .bind(private_space)
+            .fetch_one(&mut *tx)
+            .await;
+            tx.rollback().await?;
+
+            match count {
+                Ok(count) => Ok(Some(count)),
+                // Tables the role isn't granted can't be read at all
+                Err(sqlx::Error::Database(error)) if error.code().as_deref() == Some("42501") => {
+                    Ok(None)
+                }
+                Err(error) => Err(error),
+            }
+        }
+    };
+
+    for (table, column) in columns {
+        let count = read_as("", table.clone(), column.clone()).await?;
+        assert!(
+            matches!(count, None | Some(0)),
+            "{}.{} shows a private space to anonymous viewers",
+            table,
+            column
+        );
+    }
+
+    // The space's members still see it
+    for (table, column) in [("members", "space_id"), ("subspaces", "child_space_id")] {
+        let count = read_as(member, table.to_string(), column.to_string()).await?;
+        assert_eq!(count, Some(1), "{}.{}", table, column);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,135 @@
+use std::{env, sync::Arc};
+
+use dotenv::dotenv;
+use indexer::{
+    block_handler::root_handler,
+    cache::{postgres::PostgresCache, properties_cache::PropertiesCache},
+    error::IndexingError,
+    preprocess,
+    storage::postgres::PostgresStorage,
+    test_utils::TestStorage,
+    KgData,
+};
+use indexer_utils::{
+    config::{DatabaseConfig, LimitsConfig},
+    decoded::geo_output_block,
+    network_ids::Network,
+};
+use serial_test::serial;
+use stream::{
+    pb::sf::substreams::rpc::v2::BlockScopedData, test_source::replay_preprocessed,
+    PreprocessedSink,
+};
+use uuid::Uuid;
+use wire::pb::{
+    chain::{EditPublished, GeoOutput},
+    grc20::{op::Payload, Edit, Entity, Op, Value},
+};
+
+/// Mirrors the production `KgIndexer` so fixtures run through the same
+/// preprocess and handler steps as blocks coming from a substreams provider.
+struct ReplayIndexer {
+    storage: Arc<PostgresStorage>,
+    ipfs_cache: Arc<PostgresCache>,
+    properties_cache: Arc<PropertiesCache>,
+}
+
+impl PreprocessedSink<KgData> for ReplayIndexer {
+    type Error = IndexingError;
+
+    async fn preprocess_block_scoped_data(
+        &self,
+        block_data: &BlockScopedData,
+    ) -> Result<KgData, Self::Error> {
+        preprocess::preprocess_block_scoped_data(
+            block_data,
+            &self.ipfs_cache,
+            &Network::default(),
+            &LimitsConfig::default(),
+        )
+        .await
+    }
+
+    async fn process_block_scoped_data(
+        &self,
+        _block_data: &BlockScopedData,
+        decoded_data: KgData,
+    ) -> Result<(), Self::Error> {
+        root_handler::run(
+            &decoded_data,
+            &decoded_data.block,
+            &self.storage,
+            &self.properties_cache,
+        )
+        .await
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn test_replay_fixture_through_indexer() -> Result<(), IndexingError> {
+    dotenv().ok();
+    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL not set");
+    let storage = Arc::new(PostgresStorage::new(&database_url).await?);
+    let ipfs_cache = Arc::new(PostgresCache::new(&database_url, &DatabaseConfig::default()).await?);
+    let test_storage = TestStorage::new(storage.clone());
+
+    let entity_id = Uuid::new_v4();
+    let space_id = Uuid::new_v4();
+    let content_uri = format!("ipfs://replay-{}", Uuid::new_v4());
+
+    let edit = Edit {
+        id: Uuid::new_v4().as_bytes().to_vec(),
+        name: String::from("Replay"),
+        ops: vec![Op {
+            payload: Some(Payload::UpdateEntity(Entity {
+                id: entity_id.as_bytes().to_vec(),
+                values: vec![Value {
+                    property: Uuid::new_v4().as_bytes().to_vec(),
+                    value: String::from("Replayed entity"),
+                    options: None,
+                }],
+            })),
+        }],
+        authors: vec![],
+        language: None,
+    };
+
+    sqlx::query(
+        "INSERT INTO ipfs_cache (uri, json, block, space, is_errored) VALUES ($1, $2, $3, $4, false)",
+    )
+    .bind(&content_uri)
+    .bind(serde_json::to_value(&edit).unwrap())
+    .bind("1700000000")
+    .bind(space_id)
+    .execute(ipfs_cache.get_pool())
+    .await
+    .map_err(|e| IndexingError::StorageError(e.into()))?;
+
+    let geo = GeoOutput {
+        edits_published: vec![EditPublished {
+            content_uri,
+            plugin_address: String::from("0x0000000000000000000000000000000000000001"),
+            dao_address: String::from("0x0000000000000000000000000000000000000002"),
+            sender: String::new(),
+        }],
+        ..Default::default()
+    };
+
+    let blocks = vec![geo_output_block(1, 1_700_000_000, &geo)];
+
+    let indexer = ReplayIndexer {
+        storage: storage.clone(),
+        ipfs_cache,
+        properties_cache: Arc::new(PropertiesCache::new()),
+    };
+
+    replay_preprocessed(&indexer, &blocks)
+        .await
+        .expect("fixtures should replay through the indexer");
+
+    let entity = test_storage.get_entity_by_id(&entity_id).await?;
+    assert!(entity.is_some(), "replayed entity should be indexed");
+
+    Ok(())
+}
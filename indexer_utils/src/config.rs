@@ -0,0 +1,1964 @@
+use std::{env, fs, path::Path, time::Duration};
+
+use serde::Deserialize;
+use sqlx::{
+    postgres::{PgConnectOptions, PgPoolOptions},
+    PgPool,
+};
+use thiserror::Error;
+
+use crate::{
+    id::IdScheme,
+    network_ids::{Network, GEO},
+};
+
+/// The first block of the Geo chain that contains knowledge graph events.
+pub const DEFAULT_START_BLOCK: i64 = 67162;
+pub const DEFAULT_MAX_CONNECTIONS: u32 = 20;
+/// The time to wait for a pooled connection, matching the sqlx default.
+pub const DEFAULT_ACQUIRE_TIMEOUT_SECS: u64 = 30;
+pub const DEFAULT_IPFS_CONCURRENCY: usize = 20;
+pub const DEFAULT_IPFS_MAX_RETRIES: usize = 5;
+pub const DEFAULT_IPFS_RETRY_MAX_DELAY_MS: u64 = 5000;
+pub const DEFAULT_PROPERTIES_REFRESH_SECS: u64 = 300;
+pub const DEFAULT_SHARD_LEASE_RETRY_SECS: u64 = 5;
+pub const DEFAULT_WEBHOOK_MAX_ATTEMPTS: u32 = 5;
+pub const DEFAULT_WEBHOOK_RETRY_BASE_MS: u64 = 500;
+pub const DEFAULT_WEBHOOK_TIMEOUT_SECS: u64 = 10;
+pub const DEFAULT_SEARCH_INDEX: &str = "entities";
+pub const DEFAULT_SEARCH_BATCH_SIZE: usize = 1000;
+pub const DEFAULT_ARCHIVE_REGION: &str = "us-east-1";
+pub const DEFAULT_ARCHIVE_COMPRESSION_LEVEL: i32 = 3;
+pub const DEFAULT_MIRROR_DATABASE: &str = "gaia";
+pub const DEFAULT_ACTIONS_BATCH_MAX_ACTIONS: usize = 1000;
+pub const DEFAULT_ACTIONS_VOTE_CACHE_SIZE: usize = 0;
+
+const MAX_APPLICATION_NAME_LENGTH: usize = 63;
+const MAX_VALUE_PARTITIONS: u32 = 1024;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("Failed to read config file {path}: {source}")]
+    Read {
+        path: String,
+        source: std::io::Error,
+    },
+
+    #[error("Failed to parse config file {path}: {source}")]
+    Parse {
+        path: String,
+        source: toml::de::Error,
+    },
+
+    #[error("{key} is not set. Set it in the config file or with {env}")]
+    Missing {
+        key: &'static str,
+        env: &'static str,
+    },
+
+    #[error("{key} is invalid: {reason}. Fix it in the config file or with {env}")]
+    Invalid {
+        key: &'static str,
+        env: &'static str,
+        reason: String,
+    },
+}
+
+/// Settings shared by the cache, the knowledge graph indexer and the actions
+/// indexer.
+///
+/// Settings are read from the TOML file named by `CONFIG_FILE`, if set, and
+/// then overridden by environment variables, so deployments configured
+/// purely through the environment keep working. Each binary requires only
+/// the settings it uses, through accessors like [`Config::database_url`].
+///
+/// ```toml
+/// [database]
+/// url = "postgresql://localhost:5432/gaia"
+/// max_connections = 20
+/// acquire_timeout_secs = 30
+/// statement_timeout_ms = 60000
+/// application_name = "gaia-indexer"
+/// run_migrations = false
+/// value_partitions = 16
+///
+/// [substreams]
+/// endpoint = "https://substreams.example.com"
+/// start_block = 67162
+///
+/// [ipfs]
+/// gateway = "https://ipfs.example.com/ipfs/"
+/// concurrency = 20
+/// max_retries = 5
+/// retry_max_delay_ms = 5000
+/// rate_limit_per_sec = 50
+///
+/// [properties_cache]
+/// refresh_interval_secs = 300
+/// max_age_secs = 3600
+/// redis_url = "redis://localhost:6379"
+///
+/// [sharding]
+/// count = 4
+/// index = 0
+/// lease_retry_secs = 5
+///
+/// [network]
+/// id = "KJjKetFsGVSbw9qFpRzRSy"
+/// chain_id = 19411
+/// id_scheme = "v1"
+///
+/// [notifications]
+/// enabled = true
+/// max_attempts = 5
+/// retry_base_ms = 500
+/// timeout_secs = 10
+///
+/// [search]
+/// url = "http://localhost:7700"
+/// engine = "meilisearch"
+/// index = "entities"
+/// api_key = "master-key"
+/// batch_size = 1000
+/// reindex = false
+///
+/// [verification]
+/// authors = "flag"
+/// strict_editors = true
+///
+/// [moderation]
+/// blocked_cids = ["ipfs://bafkreib..."]
+/// blocked_entity_ids = ["5871e8f7b719420fb7b5a3e5b4b6e0f1"]
+///
+/// [limits]
+/// max_ops_per_edit = 100000
+/// max_edit_bytes = 50000000
+/// max_values_per_entity = 1000
+///
+/// [archive]
+/// url = "s3://gaia-archive/mainnet"
+/// endpoint = "https://s3.us-east-1.amazonaws.com"
+/// region = "us-east-1"
+/// access_key_id = "AKIA..."
+/// secret_access_key = "..."
+/// compression_level = 3
+/// replay = false
+///
+/// [mirror]
+/// url = "http://localhost:8123"
+/// database = "gaia"
+/// user = "default"
+/// password = "..."
+///
+/// [drift]
+/// alert_secs = 600
+/// throttle_secs = 3600
+///
+/// [actions]
+/// batch_max_actions = 1000
+/// batch_window_ms = 500
+/// vote_cache_size = 10000
+/// denied_senders = ["0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"]
+/// max_votes_per_hour = 100
+/// min_sender_age_secs = 86400
+/// ```
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub database: DatabaseConfig,
+    pub substreams: SubstreamsConfig,
+    pub ipfs: IpfsConfig,
+    pub properties_cache: PropertiesCacheConfig,
+    pub sharding: ShardingConfig,
+    pub network: NetworkConfig,
+    pub notifications: NotificationsConfig,
+    pub search: SearchConfig,
+    pub verification: VerificationConfig,
+    pub moderation: ModerationConfig,
+    pub limits: LimitsConfig,
+    pub archive: ArchiveConfig,
+    pub mirror: MirrorConfig,
+    pub drift: DriftConfig,
+    pub actions: ActionsConfig,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+    /// Aborts statements that run longer than this. Unset keeps the server's
+    /// `statement_timeout`.
+    pub statement_timeout_ms: Option<u64>,
+    /// Reported in `pg_stat_activity` so connections can be told apart.
+    pub application_name: Option<String>,
+    /// Whether the indexers apply the embedded schema migrations on startup.
+    pub run_migrations: bool,
+    /// The number of hash partitions of the values table. The knowledge
+    /// graph indexer repartitions the table on startup if it has a different
+    /// number. Unset keeps the partitions the table has.
+    pub value_partitions: Option<u32>,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        DatabaseConfig {
+            url: None,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            acquire_timeout_secs: DEFAULT_ACQUIRE_TIMEOUT_SECS,
+            statement_timeout_ms: None,
+            application_name: None,
+            run_migrations: false,
+            value_partitions: None,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    pub fn pool_options(&self) -> PgPoolOptions {
+        PgPoolOptions::new()
+            .max_connections(self.max_connections)
+            .acquire_timeout(Duration::from_secs(self.acquire_timeout_secs))
+    }
+
+    /// The options of every connection to `database_url`, with the
+    /// statement timeout and application name applied.
+    pub fn connect_options(&self, database_url: &str) -> Result<PgConnectOptions, sqlx::Error> {
+        let mut options: PgConnectOptions = database_url.parse()?;
+
+        if let Some(timeout) = self.statement_timeout_ms {
+            options = options.options([("statement_timeout", timeout.to_string())]);
+        }
+        if let Some(application_name) = &self.application_name {
+            options = options.application_name(application_name);
+        }
+
+        Ok(options)
+    }
+
+    /// Opens a connection pool to `database_url` with these settings.
+    pub async fn connect(&self, database_url: &str) -> Result<PgPool, sqlx::Error> {
+        self.pool_options()
+            .connect_with(self.connect_options(database_url)?)
+            .await
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SubstreamsConfig {
+    /// One endpoint, or several separated by commas to fail over between.
+    pub endpoint: Option<String>,
+    pub api_token: Option<String>,
+    pub start_block: i64,
+}
+
+impl Default for SubstreamsConfig {
+    fn default() -> Self {
+        SubstreamsConfig {
+            endpoint: None,
+            api_token: None,
+            start_block: DEFAULT_START_BLOCK,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct IpfsConfig {
+    pub gateway: Option<String>,
+    /// The number of IPFS fetches the cache runs at once.
+    pub concurrency: usize,
+    /// How many times the cache retries a fetch that failed with a transient
+    /// error, like a timeout or a gateway error, before caching the edit as
+    /// errored. 0 disables retries.
+    pub max_retries: usize,
+    /// The longest the cache waits between retries of a fetch.
+    pub retry_max_delay_ms: u64,
+    /// The most requests a second the cache sends to the gateway. Unset
+    /// doesn't limit them.
+    pub rate_limit_per_sec: Option<u32>,
+}
+
+impl Default for IpfsConfig {
+    fn default() -> Self {
+        IpfsConfig {
+            gateway: None,
+            concurrency: DEFAULT_IPFS_CONCURRENCY,
+            max_retries: DEFAULT_IPFS_MAX_RETRIES,
+            retry_max_delay_ms: DEFAULT_IPFS_RETRY_MAX_DELAY_MS,
+            rate_limit_per_sec: None,
+        }
+    }
+}
+
+impl IpfsConfig {
+    pub fn retry_max_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_max_delay_ms)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct PropertiesCacheConfig {
+    /// How often the indexer reloads property data types from the database.
+    /// 0 disables the refresh.
+    pub refresh_interval_secs: u64,
+    /// How long cached data types are trusted. The first refresh after that
+    /// replaces the cached data types that differ from the database. Unset
+    /// by default, so cached data types are only replaced once invalidated.
+    pub max_age_secs: Option<u64>,
+    /// Shares property data types between indexer instances through Redis
+    /// when set.
+    pub redis_url: Option<String>,
+}
+
+impl Default for PropertiesCacheConfig {
+    fn default() -> Self {
+        PropertiesCacheConfig {
+            refresh_interval_secs: DEFAULT_PROPERTIES_REFRESH_SECS,
+            max_age_secs: None,
+            redis_url: None,
+        }
+    }
+}
+
+impl PropertiesCacheConfig {
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        match self.refresh_interval_secs {
+            0 => None,
+            seconds => Some(Duration::from_secs(seconds)),
+        }
+    }
+
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age_secs.map(Duration::from_secs)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShardingConfig {
+    /// The number of shards spaces are split between. 1 disables sharding.
+    pub count: u32,
+    /// The shard this instance indexes, from 0 to `count - 1`.
+    pub index: u32,
+    /// How often a standby instance tries to take over its shard, and how
+    /// often the active instance checks it still holds it.
+    pub lease_retry_secs: u64,
+}
+
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        ShardingConfig {
+            count: 1,
+            index: 0,
+            lease_retry_secs: DEFAULT_SHARD_LEASE_RETRY_SECS,
+        }
+    }
+}
+
+impl ShardingConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.count > 1
+    }
+
+    pub fn lease_retry_interval(&self) -> Duration {
+        Duration::from_secs(self.lease_retry_secs)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkConfig {
+    pub id: Option<String>,
+    pub chain_id: Option<u64>,
+    /// The scheme space and value ids are derived with. A database's ids
+    /// are all derived with one scheme, so changing it means indexing into
+    /// a new database.
+    pub id_scheme: IdScheme,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct NotificationsConfig {
+    /// Whether the indexer posts the events of each block to the registered
+    /// webhooks.
+    pub enabled: bool,
+    /// How many times a webhook is tried before the notification is
+    /// dead-lettered.
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled for every retry after it.
+    pub retry_base_ms: u64,
+    pub timeout_secs: u64,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        NotificationsConfig {
+            enabled: false,
+            max_attempts: DEFAULT_WEBHOOK_MAX_ATTEMPTS,
+            retry_base_ms: DEFAULT_WEBHOOK_RETRY_BASE_MS,
+            timeout_secs: DEFAULT_WEBHOOK_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl NotificationsConfig {
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchEngine {
+    #[default]
+    Meilisearch,
+    Elasticsearch,
+}
+
+impl std::str::FromStr for SearchEngine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "meilisearch" => Ok(SearchEngine::Meilisearch),
+            "elasticsearch" => Ok(SearchEngine::Elasticsearch),
+            _ => Err(format!(
+                "expected meilisearch or elasticsearch, got '{}'",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    /// Mirrors entities into the search engine at this URL when set.
+    pub url: Option<String>,
+    pub engine: SearchEngine,
+    /// The index entities are written to.
+    pub index: String,
+    pub api_key: Option<String>,
+    /// The number of documents written per request.
+    pub batch_size: usize,
+    /// Whether the indexer rebuilds the whole index on startup before
+    /// syncing new blocks.
+    pub reindex: bool,
+}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            url: None,
+            engine: SearchEngine::default(),
+            index: DEFAULT_SEARCH_INDEX.to_string(),
+            api_key: None,
+            batch_size: DEFAULT_SEARCH_BATCH_SIZE,
+            reindex: false,
+        }
+    }
+}
+
+/// What the indexer does with edits it can't verify were published by one
+/// of their authors or an editor of their space.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AuthorVerification {
+    /// Edits aren't verified.
+    #[default]
+    Off,
+    /// Unverified edits are recorded and still applied.
+    Flag,
+    /// Unverified edits are recorded and not applied.
+    Reject,
+}
+
+impl std::str::FromStr for AuthorVerification {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "off" => Ok(AuthorVerification::Off),
+            "flag" => Ok(AuthorVerification::Flag),
+            "reject" => Ok(AuthorVerification::Reject),
+            _ => Err(format!("expected off, flag or reject, got '{}'", s)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerificationConfig {
+    pub authors: AuthorVerification,
+    /// Whether edits to public spaces that weren't published by an editor
+    /// of the space at their block are recorded instead of applied.
+    pub strict_editors: bool,
+}
+
+/// Content the indexer refuses to apply, on top of the edits of blocklisted
+/// DAOs, which are dropped while decoding.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ModerationConfig {
+    /// Edits published with these content URIs aren't applied. The
+    /// `ipfs://` prefix is optional.
+    pub blocked_cids: Vec<String>,
+    /// Ops writing to these entities are dropped from every edit.
+    pub blocked_entity_ids: Vec<String>,
+}
+
+/// Limits on the size of edits. Edits over a limit are marked as errored
+/// instead of applied, so one pathological edit can't stall a block. Every
+/// limit is off unless set.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    pub max_ops_per_edit: Option<usize>,
+    /// The size of the edit once decoded and re-encoded as protobuf.
+    pub max_edit_bytes: Option<usize>,
+    /// The number of values an edit sets on a single entity, across all of
+    /// its ops.
+    pub max_values_per_entity: Option<usize>,
+}
+
+/// Where the raw output of every block is archived, so the database can be
+/// rebuilt without streaming the blocks again.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ArchiveConfig {
+    /// A directory, or an `s3://bucket/prefix` URL. Blocks are archived
+    /// when set.
+    pub url: Option<String>,
+    /// The S3 compatible endpoint, defaulting to AWS in `region`.
+    pub endpoint: Option<String>,
+    pub region: String,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    /// The zstd level blocks are compressed with, from 1 to 22.
+    pub compression_level: i32,
+    /// Whether the indexer replays the archived blocks after its cursor on
+    /// startup instead of streaming, and stops once they're processed.
+    pub replay: bool,
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        ArchiveConfig {
+            url: None,
+            endpoint: None,
+            region: DEFAULT_ARCHIVE_REGION.to_string(),
+            access_key_id: None,
+            secret_access_key: None,
+            compression_level: DEFAULT_ARCHIVE_COMPRESSION_LEVEL,
+            replay: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct MirrorConfig {
+    /// Mirrors values, relations and edits into the ClickHouse server at
+    /// this HTTP URL when set.
+    pub url: Option<String>,
+    /// The ClickHouse database the tables are created in.
+    pub database: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+}
+
+impl Default for MirrorConfig {
+    fn default() -> Self {
+        MirrorConfig {
+            url: None,
+            database: DEFAULT_MIRROR_DATABASE.to_string(),
+            user: None,
+            password: None,
+        }
+    }
+}
+
+/// Thresholds on how far the indexer is behind the chain head, measured
+/// from the timestamp of the block it's processing. Both are off unless set.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct DriftConfig {
+    /// Logs a warning when the drift rises above this many seconds, and
+    /// again once it falls back under it.
+    pub alert_secs: Option<u64>,
+    /// Defers the search sync and the mirror while the drift is above this
+    /// many seconds, so blocks are written faster until the indexer catches
+    /// up.
+    pub throttle_secs: Option<u64>,
+}
+
+impl DriftConfig {
+    pub fn alert_after(&self) -> Option<Duration> {
+        self.alert_secs.map(Duration::from_secs)
+    }
+
+    pub fn throttle_after(&self) -> Option<Duration> {
+        self.throttle_secs.map(Duration::from_secs)
+    }
+}
+
+/// How the actions indexer batches blocks before writing them, and which
+/// votes it holds back as spam. Every filter is off unless set.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(default, deny_unknown_fields)]
+pub struct ActionsConfig {
+    /// Writes a batch once it holds at least this many actions.
+    pub batch_max_actions: usize,
+    /// Accumulates the actions of consecutive blocks for up to this many
+    /// milliseconds and writes them in one transaction. Every block is
+    /// written on its own unless set.
+    pub batch_window_ms: Option<u64>,
+    /// How many stored user votes, and separately vote counts, to keep in
+    /// memory for counting new votes. Nothing is cached if it's 0, the
+    /// default, as the cache assumes nothing else writes votes.
+    pub vote_cache_size: usize,
+    /// Actions sent by these addresses are dropped.
+    pub denied_senders: Vec<String>,
+    /// Votes a sender sends over this many within an hour are flagged.
+    pub max_votes_per_hour: Option<usize>,
+    /// Votes are flagged until this many seconds after their sender's first
+    /// action.
+    pub min_sender_age_secs: Option<u64>,
+}
+
+impl Default for ActionsConfig {
+    fn default() -> Self {
+        ActionsConfig {
+            batch_max_actions: DEFAULT_ACTIONS_BATCH_MAX_ACTIONS,
+            batch_window_ms: None,
+            vote_cache_size: DEFAULT_ACTIONS_VOTE_CACHE_SIZE,
+            denied_senders: Vec::new(),
+            max_votes_per_hour: None,
+            min_sender_age_secs: None,
+        }
+    }
+}
+
+impl ActionsConfig {
+    pub fn batch_window(&self) -> Option<Duration> {
+        self.batch_window_ms.map(Duration::from_millis)
+    }
+
+    pub fn min_sender_age(&self) -> Option<Duration> {
+        self.min_sender_age_secs.map(Duration::from_secs)
+    }
+}
+
+impl ArchiveConfig {
+    /// The access key id and secret the S3 archive is signed with.
+    pub fn credentials(&self) -> Result<(&str, &str), ConfigError> {
+        Ok((
+            self.access_key_id
+                .as_deref()
+                .ok_or(ARCHIVE_ACCESS_KEY_ID.missing())?,
+            self.secret_access_key
+                .as_deref()
+                .ok_or(ARCHIVE_SECRET_ACCESS_KEY.missing())?,
+        ))
+    }
+}
+
+impl Config {
+    /// Loads the config file named by `CONFIG_FILE`, applies environment
+    /// overrides and validates the result.
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut config = match env::var("CONFIG_FILE") {
+            Ok(path) => Config::from_file(&path)?,
+            Err(_) => Config::default(),
+        };
+
+        config.apply_overrides(|name| env::var(name).ok())?;
+        config.validate()?;
+
+        Ok(config)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, ConfigError> {
+        let path = path.as_ref().display().to_string();
+        let contents = fs::read_to_string(&path).map_err(|source| ConfigError::Read {
+            path: path.clone(),
+            source,
+        })?;
+
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse { path, source })
+    }
+
+    /// Overrides settings with the environment variables `lookup` returns a
+    /// value for. Empty variables are ignored.
+    pub fn apply_overrides(
+        &mut self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<(), ConfigError> {
+        let lookup = |name: &str| lookup(name).filter(|value| !value.is_empty());
+
+        if let Some(url) = lookup("DATABASE_URL") {
+            self.database.url = Some(url);
+        }
+        if let Some(value) = lookup("DATABASE_MAX_CONNECTIONS") {
+            self.database.max_connections = parse_override(DATABASE_MAX_CONNECTIONS, &value)?;
+        }
+        if let Some(value) = lookup("DATABASE_ACQUIRE_TIMEOUT_SECS") {
+            self.database.acquire_timeout_secs = parse_override(DATABASE_ACQUIRE_TIMEOUT, &value)?;
+        }
+        if let Some(value) = lookup("DATABASE_STATEMENT_TIMEOUT_MS") {
+            self.database.statement_timeout_ms =
+                Some(parse_override(DATABASE_STATEMENT_TIMEOUT, &value)?);
+        }
+        if let Some(application_name) = lookup("DATABASE_APPLICATION_NAME") {
+            self.database.application_name = Some(application_name);
+        }
+        if let Some(value) = lookup("RUN_MIGRATIONS") {
+            self.database.run_migrations = parse_flag(RUN_MIGRATIONS, &value)?;
+        }
+        if let Some(value) = lookup("DATABASE_VALUE_PARTITIONS") {
+            self.database.value_partitions =
+                Some(parse_override(DATABASE_VALUE_PARTITIONS, &value)?);
+        }
+        if let Some(endpoint) = lookup("SUBSTREAMS_ENDPOINT") {
+            self.substreams.endpoint = Some(endpoint);
+        }
+        if let Some(api_token) = lookup("SUBSTREAMS_API_TOKEN") {
+            self.substreams.api_token = Some(api_token);
+        }
+        if let Some(value) = lookup("START_BLOCK") {
+            self.substreams.start_block = parse_override(START_BLOCK, &value)?;
+        }
+        if let Some(gateway) = lookup("IPFS_GATEWAY") {
+            self.ipfs.gateway = Some(gateway);
+        }
+        if let Some(value) = lookup("IPFS_CONCURRENCY") {
+            self.ipfs.concurrency = parse_override(IPFS_CONCURRENCY, &value)?;
+        }
+        if let Some(value) = lookup("IPFS_MAX_RETRIES") {
+            self.ipfs.max_retries = parse_override(IPFS_MAX_RETRIES, &value)?;
+        }
+        if let Some(value) = lookup("IPFS_RETRY_MAX_DELAY_MS") {
+            self.ipfs.retry_max_delay_ms = parse_override(IPFS_RETRY_MAX_DELAY, &value)?;
+        }
+        if let Some(value) = lookup("IPFS_RATE_LIMIT_PER_SEC") {
+            self.ipfs.rate_limit_per_sec = Some(parse_override(IPFS_RATE_LIMIT, &value)?);
+        }
+        if let Some(value) = lookup("PROPERTIES_CACHE_REFRESH_SECS") {
+            self.properties_cache.refresh_interval_secs =
+                parse_override(PROPERTIES_CACHE_REFRESH, &value)?;
+        }
+        if let Some(value) = lookup("PROPERTIES_CACHE_MAX_AGE_SECS") {
+            self.properties_cache.max_age_secs =
+                Some(parse_override(PROPERTIES_CACHE_MAX_AGE, &value)?);
+        }
+        if let Some(redis_url) = lookup("PROPERTIES_CACHE_REDIS_URL") {
+            self.properties_cache.redis_url = Some(redis_url);
+        }
+        if let Some(value) = lookup("SHARD_COUNT") {
+            self.sharding.count = parse_override(SHARD_COUNT, &value)?;
+        }
+        if let Some(value) = lookup("SHARD_INDEX") {
+            self.sharding.index = parse_override(SHARD_INDEX, &value)?;
+        }
+        if let Some(value) = lookup("SHARD_LEASE_RETRY_SECS") {
+            self.sharding.lease_retry_secs = parse_override(SHARD_LEASE_RETRY, &value)?;
+        }
+        if let Some(id) = lookup("NETWORK_ID") {
+            self.network.id = Some(id);
+        }
+        if let Some(value) = lookup("CHAIN_ID") {
+            self.network.chain_id = Some(parse_override(CHAIN_ID, &value)?);
+        }
+        if let Some(value) = lookup("ID_SCHEME") {
+            self.network.id_scheme = value
+                .parse()
+                .map_err(|reason: String| ID_SCHEME.invalid(reason))?;
+        }
+        if let Some(value) = lookup("NOTIFICATIONS_ENABLED") {
+            self.notifications.enabled = parse_flag(NOTIFICATIONS_ENABLED, &value)?;
+        }
+        if let Some(value) = lookup("WEBHOOK_MAX_ATTEMPTS") {
+            self.notifications.max_attempts = parse_override(WEBHOOK_MAX_ATTEMPTS, &value)?;
+        }
+        if let Some(value) = lookup("WEBHOOK_RETRY_BASE_MS") {
+            self.notifications.retry_base_ms = parse_override(WEBHOOK_RETRY_BASE, &value)?;
+        }
+        if let Some(value) = lookup("WEBHOOK_TIMEOUT_SECS") {
+            self.notifications.timeout_secs = parse_override(WEBHOOK_TIMEOUT, &value)?;
+        }
+        if let Some(url) = lookup("SEARCH_URL") {
+            self.search.url = Some(url);
+        }
+        if let Some(value) = lookup("SEARCH_ENGINE") {
+            self.search.engine = value
+                .parse()
+                .map_err(|reason: String| SEARCH_ENGINE.invalid(reason))?;
+        }
+        if let Some(index) = lookup("SEARCH_INDEX") {
+            self.search.index = index;
+        }
+        if let Some(api_key) = lookup("SEARCH_API_KEY") {
+            self.search.api_key = Some(api_key);
+        }
+        if let Some(value) = lookup("SEARCH_BATCH_SIZE") {
+            self.search.batch_size = parse_override(SEARCH_BATCH_SIZE, &value)?;
+        }
+        if let Some(value) = lookup("SEARCH_REINDEX") {
+            self.search.reindex = parse_flag(SEARCH_REINDEX, &value)?;
+        }
+        if let Some(value) = lookup("AUTHOR_VERIFICATION") {
+            self.verification.authors = value
+                .parse()
+                .map_err(|reason: String| AUTHOR_VERIFICATION.invalid(reason))?;
+        }
+        if let Some(value) = lookup("STRICT_EDITOR_PERMISSIONS") {
+            self.verification.strict_editors = parse_flag(STRICT_EDITOR_PERMISSIONS, &value)?;
+        }
+        if let Some(value) = lookup("BLOCKED_CIDS") {
+            self.moderation.blocked_cids = parse_list(&value);
+        }
+        if let Some(value) = lookup("BLOCKED_ENTITY_IDS") {
+            self.moderation.blocked_entity_ids = parse_list(&value);
+        }
+        if let Some(value) = lookup("MAX_OPS_PER_EDIT") {
+            self.limits.max_ops_per_edit = Some(parse_override(MAX_OPS_PER_EDIT, &value)?);
+        }
+        if let Some(value) = lookup("MAX_EDIT_BYTES") {
+            self.limits.max_edit_bytes = Some(parse_override(MAX_EDIT_BYTES, &value)?);
+        }
+        if let Some(value) = lookup("MAX_VALUES_PER_ENTITY") {
+            self.limits.max_values_per_entity =
+                Some(parse_override(MAX_VALUES_PER_ENTITY, &value)?);
+        }
+        if let Some(url) = lookup("ARCHIVE_URL") {
+            self.archive.url = Some(url);
+        }
+        if let Some(endpoint) = lookup("ARCHIVE_ENDPOINT") {
+            self.archive.endpoint = Some(endpoint);
+        }
+        if let Some(region) = lookup("ARCHIVE_REGION") {
+            self.archive.region = region;
+        }
+        if let Some(access_key_id) = lookup("ARCHIVE_ACCESS_KEY_ID") {
+            self.archive.access_key_id = Some(access_key_id);
+        }
+        if let Some(secret_access_key) = lookup("ARCHIVE_SECRET_ACCESS_KEY") {
+            self.archive.secret_access_key = Some(secret_access_key);
+        }
+        if let Some(value) = lookup("ARCHIVE_COMPRESSION_LEVEL") {
+            self.archive.compression_level = parse_override(ARCHIVE_COMPRESSION_LEVEL, &value)?;
+        }
+        if let Some(value) = lookup("ARCHIVE_REPLAY") {
+            self.archive.replay = parse_flag(ARCHIVE_REPLAY, &value)?;
+        }
+        if let Some(url) = lookup("MIRROR_URL") {
+            self.mirror.url = Some(url);
+        }
+        if let Some(database) = lookup("MIRROR_DATABASE") {
+            self.mirror.database = database;
+        }
+        if let Some(user) = lookup("MIRROR_USER") {
+            self.mirror.user = Some(user);
+        }
+        if let Some(password) = lookup("MIRROR_PASSWORD") {
+            self.mirror.password = Some(password);
+        }
+        if let Some(value) = lookup("DRIFT_ALERT_SECS") {
+            self.drift.alert_secs = Some(parse_override(DRIFT_ALERT_SECS, &value)?);
+        }
+        if let Some(value) = lookup("DRIFT_THROTTLE_SECS") {
+            self.drift.throttle_secs = Some(parse_override(DRIFT_THROTTLE_SECS, &value)?);
+        }
+        if let Some(value) = lookup("ACTIONS_BATCH_MAX_ACTIONS") {
+            self.actions.batch_max_actions = parse_override(ACTIONS_BATCH_MAX_ACTIONS, &value)?;
+        }
+        if let Some(value) = lookup("ACTIONS_BATCH_WINDOW_MS") {
+            self.actions.batch_window_ms = Some(parse_override(ACTIONS_BATCH_WINDOW_MS, &value)?);
+        }
+        if let Some(value) = lookup("ACTIONS_VOTE_CACHE_SIZE") {
+            self.actions.vote_cache_size = parse_override(ACTIONS_VOTE_CACHE_SIZE, &value)?;
+        }
+        if let Some(value) = lookup("ACTIONS_DENIED_SENDERS") {
+            self.actions.denied_senders = parse_list(&value);
+        }
+        if let Some(value) = lookup("ACTIONS_MAX_VOTES_PER_HOUR") {
+            self.actions.max_votes_per_hour = Some(parse_override(ACTIONS_MAX_VOTES_PER_HOUR, &value)?);
+        }
+        if let Some(value) = lookup("ACTIONS_MIN_SENDER_AGE_SECS") {
+            self.actions.min_sender_age_secs = Some(parse_override(ACTIONS_MIN_SENDER_AGE_SECS, &value)?);
+        }
+
+        Ok(())
+    }
+
+    /// Checks every setting that is present. Missing required settings are
+    /// reported by their accessors instead, as each binary needs a different
+    /// subset.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if let Some(url) = self
+            .database
+            .url
+            .as_deref()
+            .filter(|url| !is_postgres_url(url))
+        {
+            return Err(DATABASE_URL.invalid(format!(
+                "expected a postgres:// or postgresql:// connection string, got '{}'",
+                url
+            )));
+        }
+        if self.database.max_connections == 0 {
+            return Err(DATABASE_MAX_CONNECTIONS.invalid("the pool needs at least 1 connection"));
+        }
+        if self.database.acquire_timeout_secs == 0 {
+            return Err(DATABASE_ACQUIRE_TIMEOUT.invalid("the timeout must be at least 1 second"));
+        }
+        if self.database.statement_timeout_ms == Some(0) {
+            return Err(DATABASE_STATEMENT_TIMEOUT.invalid(
+                "0 disables the timeout, leave it unset to use the server's timeout instead",
+            ));
+        }
+        if let Some(application_name) = self
+            .database
+            .application_name
+            .as_ref()
+            .filter(|name| name.len() > MAX_APPLICATION_NAME_LENGTH)
+        {
+            return Err(DATABASE_APPLICATION_NAME.invalid(format!(
+                "postgres truncates names longer than {} bytes, got '{}'",
+                MAX_APPLICATION_NAME_LENGTH, application_name
+            )));
+        }
+        if let Some(partitions) = self
+            .database
+            .value_partitions
+            .filter(|partitions| !(1..=MAX_VALUE_PARTITIONS).contains(partitions))
+        {
+            return Err(DATABASE_VALUE_PARTITIONS.invalid(format!(
+                "expected from 1 to {} partitions, got {}",
+                MAX_VALUE_PARTITIONS, partitions
+            )));
+        }
+        if let Some(endpoints) = &self.substreams.endpoint {
+            for endpoint in endpoints.split(',') {
+                validate_http_url(SUBSTREAMS_ENDPOINT, endpoint.trim())?;
+            }
+        }
+        if self.substreams.start_block < 0 {
+            return Err(START_BLOCK.invalid(format!(
+                "expected a block number of 0 or more, got {}",
+                self.substreams.start_block
+            )));
+        }
+        if let Some(gateway) = &self.ipfs.gateway {
+            validate_http_url(IPFS_GATEWAY, gateway)?;
+        }
+        if self.ipfs.concurrency == 0 {
+            return Err(IPFS_CONCURRENCY.invalid("at least 1 fetch must be allowed at a time"));
+        }
+        if self.ipfs.rate_limit_per_sec == Some(0) {
+            return Err(IPFS_RATE_LIMIT.invalid(
+                "at least 1 request a second must be allowed, leave it unset to disable it",
+            ));
+        }
+        if self.properties_cache.max_age_secs == Some(0) {
+            return Err(PROPERTIES_CACHE_MAX_AGE.invalid(
+                "cached data types must be kept for at least 1 second, leave it unset to disable it",
+            ));
+        }
+        if self.properties_cache.max_age_secs.is_some()
+            && self.properties_cache.refresh_interval().is_none()
+        {
+            return Err(PROPERTIES_CACHE_MAX_AGE.invalid(
+                "the max age is only checked on refresh, so the refresh must be enabled",
+            ));
+        }
+        if let Some(redis_url) = self
+            .properties_cache
+            .redis_url
+            .as_ref()
+            .filter(|url| !(url.starts_with("redis://") || url.starts_with("rediss://")))
+        {
+            return Err(PROPERTIES_CACHE_REDIS_URL.invalid(format!(
+                "expected a redis:// or rediss:// URL, got '{}'",
+                redis_url
+            )));
+        }
+        if self.sharding.count == 0 {
+            return Err(SHARD_COUNT.invalid("there must be at least 1 shard"));
+        }
+        if self.sharding.index >= self.sharding.count {
+            return Err(SHARD_INDEX.invalid(format!(
+                "expected a shard from 0 to {}, got {}",
+                self.sharding.count - 1,
+                self.sharding.index
+            )));
+        }
+        if self.sharding.lease_retry_secs == 0 {
+            return Err(SHARD_LEASE_RETRY.invalid("the interval must be at least 1 second"));
+        }
+        if self.network.id.as_ref().is_some_and(|id| id.is_empty()) {
+            return Err(NETWORK_ID.invalid("the network id can't be empty"));
+        }
+        if self.notifications.max_attempts == 0 {
+            return Err(WEBHOOK_MAX_ATTEMPTS.invalid("a webhook must be tried at least once"));
+        }
+        if self.notifications.timeout_secs == 0 {
+            return Err(WEBHOOK_TIMEOUT.invalid("the timeout must be at least 1 second"));
+        }
+        if let Some(url) = &self.search.url {
+            validate_http_url(SEARCH_URL, url)?;
+        }
+        if self.search.index.is_empty() {
+            return Err(SEARCH_INDEX.invalid("the index name can't be empty"));
+        }
+        if self.search.batch_size == 0 {
+            return Err(SEARCH_BATCH_SIZE.invalid("at least 1 document must be written at a time"));
+        }
+        if let Some(id) = self
+            .moderation
+            .blocked_entity_ids
+            .iter()
+            .find(|id| uuid::Uuid::parse_str(id).is_err())
+        {
+            return Err(BLOCKED_ENTITY_IDS.invalid(format!("expected entity ids, got '{}'", id)));
+        }
+        for (setting, limit) in [
+            (MAX_OPS_PER_EDIT, self.limits.max_ops_per_edit),
+            (MAX_EDIT_BYTES, self.limits.max_edit_bytes),
+            (MAX_VALUES_PER_ENTITY, self.limits.max_values_per_entity),
+        ] {
+            if limit == Some(0) {
+                return Err(setting.invalid(
+                    "0 would mark every edit as errored, leave it unset to disable the limit",
+                ));
+            }
+        }
+        if let Some(endpoint) = &self.archive.endpoint {
+            validate_http_url(ARCHIVE_ENDPOINT, endpoint)?;
+        }
+        if self.archive.url.as_ref().is_some_and(|url| url.is_empty()) {
+            return Err(ARCHIVE_URL.invalid("the archive URL can't be empty"));
+        }
+        if self.archive.region.is_empty() {
+            return Err(ARCHIVE_REGION.invalid("the region can't be empty"));
+        }
+        if !(1..=22).contains(&self.archive.compression_level) {
+            return Err(ARCHIVE_COMPRESSION_LEVEL.invalid(format!(
+                "expected a level from 1 to 22, got {}",
+                self.archive.compression_level
+            )));
+        }
+        if self.archive.replay && self.archive.url.is_none() {
+            return Err(ARCHIVE_REPLAY.invalid("there's no archive to replay without archive.url"));
+        }
+        if let Some(url) = &self.mirror.url {
+            validate_http_url(MIRROR_URL, url)?;
+        }
+        // The database name is written into the mirror's statements
+        if self.mirror.database.is_empty()
+            || !self
+                .mirror
+                .database
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            return Err(MIRROR_DATABASE.invalid(format!(
+                "expected letters, digits and underscores, got '{}'",
+                self.mirror.database
+            )));
+        }
+
+        for (setting, threshold) in [
+            (DRIFT_ALERT_SECS, self.drift.alert_secs),
+            (DRIFT_THROTTLE_SECS, self.drift.throttle_secs),
+        ] {
+            if threshold == Some(0) {
+                return Err(
+                    setting.invalid("0 would apply to every block, leave it unset to disable it")
+                );
+            }
+        }
+
+        if self.actions.batch_max_actions == 0 {
+            return Err(ACTIONS_BATCH_MAX_ACTIONS.invalid("a batch needs at least 1 action"));
+        }
+        if self.actions.batch_window_ms == Some(0) {
+            return Err(ACTIONS_BATCH_WINDOW_MS.invalid(
+                "0 would write every block on its own, leave it unset to disable batching",
+            ));
+        }
+        if let Some(sender) = self.actions.denied_senders.iter().find(|sender| !is_address(sender)) {
+            return Err(ACTIONS_DENIED_SENDERS.invalid(format!(
+                "expected 0x-prefixed 20 byte hex addresses, got '{}'",
+                sender
+            )));
+        }
+        if self.actions.max_votes_per_hour == Some(0) {
+            return Err(ACTIONS_MAX_VOTES_PER_HOUR.invalid(
+                "0 would flag every vote, leave it unset to disable the limit",
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn database_url(&self) -> Result<&str, ConfigError> {
+        self.database.url.as_deref().ok_or(DATABASE_URL.missing())
+    }
+
+    pub fn substreams_endpoint(&self) -> Result<&str, ConfigError> {
+        self.substreams
+            .endpoint
+            .as_deref()
+            .ok_or(SUBSTREAMS_ENDPOINT.missing())
+    }
+
+    pub fn substreams_api_token(&self) -> Result<&str, ConfigError> {
+        self.substreams
+            .api_token
+            .as_deref()
+            .ok_or(SUBSTREAMS_API_TOKEN.missing())
+    }
+
+    pub fn ipfs_gateway(&self) -> Result<&str, ConfigError> {
+        self.ipfs.gateway.as_deref().ok_or(IPFS_GATEWAY.missing())
+    }
+
+    /// The network to index, defaulting to the GEO network.
+    pub fn network(&self) -> Network {
+        Network::new(
+            self.network.id.as_deref().unwrap_or(GEO),
+            self.network.chain_id,
+        )
+    }
+}
+
+/// A setting's key in the config file and the environment variable that
+/// overrides it, used to point error messages at both.
+struct Setting {
+    key: &'static str,
+    env: &'static str,
+}
+
+impl Setting {
+    fn missing(&self) -> ConfigError {
+        ConfigError::Missing {
+            key: self.key,
+            env: self.env,
+        }
+    }
+
+    fn invalid(&self, reason: impl Into<String>) -> ConfigError {
+        ConfigError::Invalid {
+            key: self.key,
+            env: self.env,
+            reason: reason.into(),
+        }
+    }
+}
+
+const DATABASE_URL: Setting = Setting {
+    key: "database.url",
+    env: "DATABASE_URL",
+};
+const DATABASE_MAX_CONNECTIONS: Setting = Setting {
+    key: "database.max_connections",
+    env: "DATABASE_MAX_CONNECTIONS",
+};
+const DATABASE_ACQUIRE_TIMEOUT: Setting = Setting {
+    key: "database.acquire_timeout_secs",
+    env: "DATABASE_ACQUIRE_TIMEOUT_SECS",
+};
+const DATABASE_STATEMENT_TIMEOUT: Setting = Setting {
+    key: "database.statement_timeout_ms",
+    env: "DATABASE_STATEMENT_TIMEOUT_MS",
+};
+const DATABASE_APPLICATION_NAME: Setting = Setting {
+    key: "database.application_name",
+    env: "DATABASE_APPLICATION_NAME",
+};
+const RUN_MIGRATIONS: Setting = Setting {
+    key: "database.run_migrations",
+    env: "RUN_MIGRATIONS",
+};
+const DATABASE_VALUE_PARTITIONS: Setting = Setting {
+    key: "database.value_partitions",
+    env: "DATABASE_VALUE_PARTITIONS",
+};
+const SUBSTREAMS_ENDPOINT: Setting = Setting {
+    key: "substreams.endpoint",
+    env: "SUBSTREAMS_ENDPOINT",
+};
+const SUBSTREAMS_API_TOKEN: Setting = Setting {
+    key: "substreams.api_token",
+    env: "SUBSTREAMS_API_TOKEN",
+};
+const START_BLOCK: Setting = Setting {
+    key: "substreams.start_block",
+    env: "START_BLOCK",
+};
+const IPFS_GATEWAY: Setting = Setting {
+    key: "ipfs.gateway",
+    env: "IPFS_GATEWAY",
+};
+const IPFS_CONCURRENCY: Setting = Setting {
+    key: "ipfs.concurrency",
+    env: "IPFS_CONCURRENCY",
+};
+const IPFS_MAX_RETRIES: Setting = Setting {
+    key: "ipfs.max_retries",
+    env: "IPFS_MAX_RETRIES",
+};
+const IPFS_RETRY_MAX_DELAY: Setting = Setting {
+    key: "ipfs.retry_max_delay_ms",
+    env: "IPFS_RETRY_MAX_DELAY_MS",
+};
+const IPFS_RATE_LIMIT: Setting = Setting {
+    key: "ipfs.rate_limit_per_sec",
+    env: "IPFS_RATE_LIMIT_PER_SEC",
+};
+const PROPERTIES_CACHE_REFRESH: Setting = Setting {
+    key: "properties_cache.refresh_interval_secs",
+    env: "PROPERTIES_CACHE_REFRESH_SECS",
+};
+const PROPERTIES_CACHE_MAX_AGE: Setting = Setting {
+    key: "properties_cache.max_age_secs",
+    env: "PROPERTIES_CACHE_MAX_AGE_SECS",
+};
+const PROPERTIES_CACHE_REDIS_URL: Setting = Setting {
+    key: "properties_cache.redis_url",
+    env: "PROPERTIES_CACHE_REDIS_URL",
+};
+const SHARD_COUNT: Setting = Setting {
+    key: "sharding.count",
+    env: "SHARD_COUNT",
+};
+const SHARD_INDEX: Setting = Setting {
+    key: "sharding.index",
+    env: "SHARD_INDEX",
+};
+const SHARD_LEASE_RETRY: Setting = Setting {
+    key: "sharding.lease_retry_secs",
+    env: "SHARD_LEASE_RETRY_SECS",
+};
+const NETWORK_ID: Setting = Setting {
+    key: "network.id",
+    env: "NETWORK_ID",
+};
+const CHAIN_ID: Setting = Setting {
+    key: "network.chain_id",
+    env: "CHAIN_ID",
+};
+const ID_SCHEME: Setting = Setting {
+    key: "network.id_scheme",
+    env: "ID_SCHEME",
+};
+const NOTIFICATIONS_ENABLED: Setting = Setting {
+    key: "notifications.enabled",
+    env: "NOTIFICATIONS_ENABLED",
+};
+const WEBHOOK_MAX_ATTEMPTS: Setting = Setting {
+    key: "notifications.max_attempts",
+    env: "WEBHOOK_MAX_ATTEMPTS",
+};
+const WEBHOOK_RETRY_BASE: Setting = Setting {
+    key: "notifications.retry_base_ms",
+    env: "WEBHOOK_RETRY_BASE_MS",
+};
+const WEBHOOK_TIMEOUT: Setting = Setting {
+    key: "notifications.timeout_secs",
+    env: "WEBHOOK_TIMEOUT_SECS",
+};
+const SEARCH_URL: Setting = Setting {
+    key: "search.url",
+    env: "SEARCH_URL",
+};
+const SEARCH_ENGINE: Setting = Setting {
+    key: "search.engine",
+    env: "SEARCH_ENGINE",
+};
+const SEARCH_INDEX: Setting = Setting {
+    key: "search.index",
+    env: "SEARCH_INDEX",
+};
+const SEARCH_BATCH_SIZE: Setting = Setting {
+    key: "search.batch_size",
+    env: "SEARCH_BATCH_SIZE",
+};
+const SEARCH_REINDEX: Setting = Setting {
+    key: "search.reindex",
+    env: "SEARCH_REINDEX",
+};
+const AUTHOR_VERIFICATION: Setting = Setting {
+    key: "verification.authors",
+    env: "AUTHOR_VERIFICATION",
+};
+const STRICT_EDITOR_PERMISSIONS: Setting = Setting {
+    key: "verification.strict_editors",
+    env: "STRICT_EDITOR_PERMISSIONS",
+};
+const BLOCKED_ENTITY_IDS: Setting = Setting {
+    key: "moderation.blocked_entity_ids",
+    env: "BLOCKED_ENTITY_IDS",
+};
+const MAX_OPS_PER_EDIT: Setting = Setting {
+    key: "limits.max_ops_per_edit",
+    env: "MAX_OPS_PER_EDIT",
+};
+const MAX_EDIT_BYTES: Setting = Setting {
+    key: "limits.max_edit_bytes",
+    env: "MAX_EDIT_BYTES",
+};
+const MAX_VALUES_PER_ENTITY: Setting = Setting {
+    key: "limits.max_values_per_entity",
+    env: "MAX_VALUES_PER_ENTITY",
+};
+const ARCHIVE_URL: Setting = Setting {
+    key: "archive.url",
+    env: "ARCHIVE_URL",
+};
+const ARCHIVE_ENDPOINT: Setting = Setting {
+    key: "archive.endpoint",
+    env: "ARCHIVE_ENDPOINT",
+};
+const ARCHIVE_REGION: Setting = Setting {
+    key: "archive.region",
+    env: "ARCHIVE_REGION",
+};
+const ARCHIVE_ACCESS_KEY_ID: Setting = Setting {
+    key: "archive.access_key_id",
+    env: "ARCHIVE_ACCESS_KEY_ID",
+};
+const ARCHIVE_SECRET_ACCESS_KEY: Setting = Setting {
+    key: "archive.secret_access_key",
+    env: "ARCHIVE_SECRET_ACCESS_KEY",
+};
+const ARCHIVE_COMPRESSION_LEVEL: Setting = Setting {
+    key: "archive.compression_level",
+    env: "ARCHIVE_COMPRESSION_LEVEL",
+};
+const ARCHIVE_REPLAY: Setting = Setting {
+    key: "archive.replay",
+    env: "ARCHIVE_REPLAY",
+};
+const MIRROR_URL: Setting = Setting {
+    key: "mirror.url",
+    env: "MIRROR_URL",
+};
+const MIRROR_DATABASE: Setting = Setting {
+    key: "mirror.database",
+    env: "MIRROR_DATABASE",
+};
+const DRIFT_ALERT_SECS: Setting = Setting {
+    key: "drift.alert_secs",
+    env: "DRIFT_ALERT_SECS",
+};
+const DRIFT_THROTTLE_SECS: Setting = Setting {
+    key: "drift.throttle_secs",
+    env: "DRIFT_THROTTLE_SECS",
+};
+const ACTIONS_BATCH_MAX_ACTIONS: Setting = Setting {
+    key: "actions.batch_max_actions",
+    env: "ACTIONS_BATCH_MAX_ACTIONS",
+};
+const ACTIONS_BATCH_WINDOW_MS: Setting = Setting {
+    key: "actions.batch_window_ms",
+    env: "ACTIONS_BATCH_WINDOW_MS",
+};
+const ACTIONS_VOTE_CACHE_SIZE: Setting = Setting {
+    key: "actions.vote_cache_size",
+    env: "ACTIONS_VOTE_CACHE_SIZE",
+};
+const ACTIONS_DENIED_SENDERS: Setting = Setting {
+    key: "actions.denied_senders",
+    env: "ACTIONS_DENIED_SENDERS",
+};
+const ACTIONS_MAX_VOTES_PER_HOUR: Setting = Setting {
+    key: "actions.max_votes_per_hour",
+    env: "ACTIONS_MAX_VOTES_PER_HOUR",
+};
+const ACTIONS_MIN_SENDER_AGE_SECS: Setting = Setting {
+    key: "actions.min_sender_age_secs",
+    env: "ACTIONS_MIN_SENDER_AGE_SECS",
+};
+
+fn parse_override<T: std::str::FromStr>(setting: Setting, value: &str) -> Result<T, ConfigError> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| setting.invalid(format!("expected a number, got '{}'", value)))
+}
+
+fn parse_flag(setting: Setting, value: &str) -> Result<bool, ConfigError> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(setting.invalid(format!("expected true or false, got '{}'", value))),
+    }
+}
+
+/// Splits a comma separated list, ignoring empty items.
+fn parse_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn is_address(value: &str) -> bool {
+    value
+        .strip_prefix("0x")
+        .is_some_and(|hex| hex.len() == 40 && hex.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_postgres_url(url: &str) -> bool {
+    url.starts_with("postgres://") || url.starts_with("postgresql://")
+}
+
+fn validate_http_url(setting: Setting, url: &str) -> Result<(), ConfigError> {
+    let host = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"));
+
+    match host {
+        Some(host) if !host.is_empty() => Ok(()),
+        _ => Err(setting.invalid(format!(
+            "expected an http:// or https:// URL, got '{}'",
+            url
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn overrides(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn test_parses_toml_with_defaults() {
+        let config: Config = toml::from_str(
+            r#"
+            [database]
+            url = "postgresql://localhost:5432/gaia"
+
+            [ipfs]
+            concurrency = 8
+
+            [network]
+            chain_id = 19411
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.database_url().unwrap(),
+            "postgresql://localhost:5432/gaia"
+        );
+        assert_eq!(config.database.max_connections, DEFAULT_MAX_CONNECTIONS);
+        assert_eq!(config.substreams.start_block, DEFAULT_START_BLOCK);
+        assert_eq!(config.ipfs.concurrency, 8);
+        assert_eq!(config.ipfs.max_retries, DEFAULT_IPFS_MAX_RETRIES);
+        assert_eq!(
+            config.properties_cache.refresh_interval(),
+            Some(Duration::from_secs(DEFAULT_PROPERTIES_REFRESH_SECS))
+        );
+        assert!(!config.sharding.is_enabled());
+        assert_eq!(config.network(), Network::new(GEO, Some(19411)));
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rejects_unknown_keys() {
+        assert!(toml::from_str::<Config>("[database]\nmax_connection = 5").is_err());
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [substreams]
+            endpoint = "https://file.example.com"
+            start_block = 10
+            "#,
+        )
+        .unwrap();
+
+        config
+            .apply_overrides(overrides(&[
+                ("SUBSTREAMS_ENDPOINT", "https://env.example.com"),
+                ("START_BLOCK", "20"),
+                ("RUN_MIGRATIONS", "true"),
+                ("DATABASE_VALUE_PARTITIONS", "32"),
+                ("IPFS_GATEWAY", ""),
+            ]))
+            .unwrap();
+
+        assert_eq!(
+            config.substreams_endpoint().unwrap(),
+            "https://env.example.com"
+        );
+        assert_eq!(config.substreams.start_block, 20);
+        assert!(config.database.run_migrations);
+        assert_eq!(config.database.value_partitions, Some(32));
+        assert!(matches!(
+            config.ipfs_gateway(),
+            Err(ConfigError::Missing {
+                env: "IPFS_GATEWAY",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_connect_options() {
+        let database = DatabaseConfig {
+            statement_timeout_ms: Some(5000),
+            application_name: Some("gaia-indexer".to_string()),
+            ..Default::default()
+        };
+
+        let options = database
+            .connect_options("postgresql://localhost:5432/gaia")
+            .unwrap();
+
+        assert_eq!(options.get_application_name(), Some("gaia-indexer"));
+        assert_eq!(options.get_options(), Some("-c statement_timeout=5000"));
+        assert_eq!(
+            DatabaseConfig::default()
+                .connect_options("postgresql://localhost:5432/gaia")
+                .unwrap()
+                .get_options(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_invalid_settings_are_reported() {
+        let mut config = Config::default();
+        let error = config
+            .apply_overrides(overrides(&[("IPFS_CONCURRENCY", "many")]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "ipfs.concurrency is invalid: expected a number, got 'many'. Fix it in the config file or with IPFS_CONCURRENCY"
+        );
+
+        let mut config = Config::default();
+        config.substreams.endpoint = Some("https://a.example.com,b.example.com".to_string());
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid {
+                key: "substreams.endpoint",
+                ..
+            })
+        ));
+
+        let mut config = Config::default();
+        config.database.url = Some("mysql://localhost".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.database.max_connections = 0;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.substreams.start_block = -1;
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.database.statement_timeout_ms = Some(0);
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.database.value_partitions = Some(0);
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config.properties_cache.redis_url = Some("localhost:6379".to_string());
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[("PROPERTIES_CACHE_MAX_AGE_SECS", "0")]))
+            .unwrap();
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[
+                ("PROPERTIES_CACHE_MAX_AGE_SECS", "3600"),
+                ("PROPERTIES_CACHE_REFRESH_SECS", "0"),
+            ]))
+            .unwrap();
+        assert_eq!(
+            config.properties_cache.max_age(),
+            Some(Duration::from_secs(3600))
+        );
+        assert!(config.validate().is_err());
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[("SHARD_COUNT", "4"), ("SHARD_INDEX", "4")]))
+            .unwrap();
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "sharding.index is invalid: expected a shard from 0 to 3, got 4. Fix it in the config file or with SHARD_INDEX"
+        );
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[
+                ("NOTIFICATIONS_ENABLED", "true"),
+                ("WEBHOOK_MAX_ATTEMPTS", "0"),
+            ]))
+            .unwrap();
+        assert!(config.notifications.enabled);
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::Invalid {
+                key: "notifications.max_attempts",
+                ..
+            })
+        ));
+
+        let mut config = Config::default();
+        let error = config
+            .apply_overrides(overrides(&[("SEARCH_ENGINE", "solr")]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "search.engine is invalid: expected meilisearch or elasticsearch, got 'solr'. Fix it in the config file or with SEARCH_ENGINE"
+        );
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[("ID_SCHEME", "v2")]))
+            .unwrap();
+        assert_eq!(config.network.id_scheme, IdScheme::V2);
+        let error = config
+            .apply_overrides(overrides(&[("ID_SCHEME", "sha256")]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "network.id_scheme is invalid: expected v1 or v2, got 'sha256'. Fix it in the config file or with ID_SCHEME"
+        );
+
+        let mut config = Config::default();
+        config.search.url = Some("localhost:7700".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_search_settings() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [search]
+            url = "http://localhost:9200"
+            engine = "elasticsearch"
+            "#,
+        )
+        .unwrap();
+
+        config
+            .apply_overrides(overrides(&[
+                ("SEARCH_INDEX", "gaia"),
+                ("SEARCH_REINDEX", "true"),
+            ]))
+            .unwrap();
+
+        assert_eq!(config.search.engine, SearchEngine::Elasticsearch);
+        assert_eq!(config.search.index, "gaia");
+        assert_eq!(config.search.batch_size, DEFAULT_SEARCH_BATCH_SIZE);
+        assert!(config.search.reindex);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_author_verification_settings() {
+        let config: Config = toml::from_str("[verification]\nauthors = \"flag\"").unwrap();
+        assert_eq!(config.verification.authors, AuthorVerification::Flag);
+        assert_eq!(
+            Config::default().verification.authors,
+            AuthorVerification::Off
+        );
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[("AUTHOR_VERIFICATION", "Reject")]))
+            .unwrap();
+        assert_eq!(config.verification.authors, AuthorVerification::Reject);
+
+        let error = Config::default()
+            .apply_overrides(overrides(&[("AUTHOR_VERIFICATION", "strict")]))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "verification.authors is invalid: expected off, flag or reject, got 'strict'. Fix it in the config file or with AUTHOR_VERIFICATION"
+        );
+    }
+
+    #[test]
+    fn test_moderation_settings() {
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[
+                ("BLOCKED_CIDS", "ipfs://a, b,"),
+                (
+                    "BLOCKED_ENTITY_IDS",
+                    "5871e8f7b719420fb7b5a3e5b4b6e0f1,not-an-id",
+                ),
+            ]))
+            .unwrap();
+        assert_eq!(config.moderation.blocked_cids, vec!["ipfs://a", "b"]);
+
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "moderation.blocked_entity_ids is invalid: expected entity ids, got 'not-an-id'. Fix it in the config file or with BLOCKED_ENTITY_IDS"
+        );
+
+        config.moderation.blocked_entity_ids.pop();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_limits_settings() {
+        let config: Config = toml::from_str("[limits]\nmax_ops_per_edit = 10").unwrap();
+        assert_eq!(config.limits.max_ops_per_edit, Some(10));
+        assert_eq!(config.limits.max_edit_bytes, None);
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[
+                ("MAX_EDIT_BYTES", "1000"),
+                ("MAX_VALUES_PER_ENTITY", "0"),
+            ]))
+            .unwrap();
+        assert_eq!(config.limits.max_edit_bytes, Some(1000));
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "limits.max_values_per_entity is invalid: 0 would mark every edit as errored, leave it unset to disable the limit. Fix it in the config file or with MAX_VALUES_PER_ENTITY"
+        );
+    }
+
+    #[test]
+    fn test_strict_editors_setting() {
+        let config: Config = toml::from_str("[verification]\nstrict_editors = true").unwrap();
+        assert!(config.verification.strict_editors);
+        assert!(!Config::default().verification.strict_editors);
+
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[("STRICT_EDITOR_PERMISSIONS", "1")]))
+            .unwrap();
+        assert!(config.verification.strict_editors);
+    }
+    #[test]
+    fn test_archive_settings() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [archive]
+            url = "s3://gaia-archive/mainnet"
+            endpoint = "http://localhost:9000"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.archive.region, DEFAULT_ARCHIVE_REGION);
+        assert_eq!(
+            config.archive.credentials().unwrap_err().to_string(),
+            "archive.access_key_id is not set. Set it in the config file or with ARCHIVE_ACCESS_KEY_ID"
+        );
+
+        config
+            .apply_overrides(overrides(&[
+                ("ARCHIVE_ACCESS_KEY_ID", "key"),
+                ("ARCHIVE_SECRET_ACCESS_KEY", "secret"),
+                ("ARCHIVE_REPLAY", "true"),
+            ]))
+            .unwrap();
+        assert_eq!(config.archive.credentials().unwrap(), ("key", "secret"));
+        assert!(config.archive.replay);
+        assert!(config.validate().is_ok());
+
+        config.archive.compression_level = 0;
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "archive.compression_level is invalid: expected a level from 1 to 22, got 0. Fix it in the config file or with ARCHIVE_COMPRESSION_LEVEL"
+        );
+
+        let mut config = Config::default();
+        config.archive.replay = true;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_mirror_settings() {
+        let mut config: Config = toml::from_str(
+            r#"
+            [mirror]
+            url = "http://localhost:8123"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.mirror.database, DEFAULT_MIRROR_DATABASE);
+
+        config
+            .apply_overrides(overrides(&[
+                ("MIRROR_DATABASE", "gaia_mainnet"),
+                ("MIRROR_USER", "indexer"),
+            ]))
+            .unwrap();
+        assert_eq!(config.mirror.database, "gaia_mainnet");
+        assert_eq!(config.mirror.user.as_deref(), Some("indexer"));
+        assert!(config.validate().is_ok());
+
+        config.mirror.database = "gaia; DROP TABLE values".to_string();
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "mirror.database is invalid: expected letters, digits and underscores, got 'gaia; DROP TABLE values'. Fix it in the config file or with MIRROR_DATABASE"
+        );
+
+        let mut config = Config::default();
+        config.mirror.url = Some("localhost:8123".to_string());
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_drift_settings() {
+        let mut config: Config = toml::from_str("[drift]\nalert_secs = 600").unwrap();
+        assert_eq!(config.drift.alert_after(), Some(Duration::from_secs(600)));
+        assert_eq!(config.drift.throttle_after(), None);
+
+        config
+            .apply_overrides(overrides(&[("DRIFT_THROTTLE_SECS", "3600")]))
+            .unwrap();
+        assert_eq!(
+            config.drift.throttle_after(),
+            Some(Duration::from_secs(3600))
+        );
+        assert!(config.validate().is_ok());
+
+        config.drift.throttle_secs = Some(0);
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "drift.throttle_secs is invalid: 0 would apply to every block, leave it unset to disable it. Fix it in the config file or with DRIFT_THROTTLE_SECS"
+        );
+    }
+
+    #[test]
+    fn test_actions_batch_settings() {
+        let mut config = Config::default();
+        assert_eq!(config.actions.batch_window(), None);
+        assert_eq!(config.actions.vote_cache_size, 0);
+
+        config
+            .apply_overrides(overrides(&[
+                ("ACTIONS_BATCH_MAX_ACTIONS", "250"),
+                ("ACTIONS_BATCH_WINDOW_MS", "500"),
+                ("ACTIONS_VOTE_CACHE_SIZE", "10000"),
+            ]))
+            .unwrap();
+        assert_eq!(config.actions.batch_max_actions, 250);
+        assert_eq!(config.actions.vote_cache_size, 10_000);
+        assert_eq!(
+            config.actions.batch_window(),
+            Some(Duration::from_millis(500))
+        );
+        assert!(config.validate().is_ok());
+
+        config.actions.batch_window_ms = Some(0);
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "actions.batch_window_ms is invalid: 0 would write every block on its own, leave it unset to disable batching. Fix it in the config file or with ACTIONS_BATCH_WINDOW_MS"
+        );
+
+        config.actions.batch_window_ms = None;
+        config.actions.batch_max_actions = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_actions_filter_settings() {
+        let mut config = Config::default();
+        assert_eq!(config.actions.min_sender_age(), None);
+
+        config
+            .apply_overrides(overrides(&[
+                (
+                    "ACTIONS_DENIED_SENDERS",
+                    "0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045, ",
+                ),
+                ("ACTIONS_MAX_VOTES_PER_HOUR", "100"),
+                ("ACTIONS_MIN_SENDER_AGE_SECS", "86400"),
+            ]))
+            .unwrap();
+        assert_eq!(
+            config.actions.denied_senders,
+            vec!["0xd8dA6BF26964aF9D7eEd9e03E53415D37aA96045"]
+        );
+        assert_eq!(config.actions.max_votes_per_hour, Some(100));
+        assert_eq!(
+            config.actions.min_sender_age(),
+            Some(Duration::from_secs(86400))
+        );
+        assert!(config.validate().is_ok());
+
+        config.actions.denied_senders = vec!["d8dA6BF26964aF9D7eEd9e03E53415D37aA96045".to_string()];
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "actions.denied_senders is invalid: expected 0x-prefixed 20 byte hex addresses, got 'd8dA6BF26964aF9D7eEd9e03E53415D37aA96045'. Fix it in the config file or with ACTIONS_DENIED_SENDERS"
+        );
+
+        config.actions.denied_senders.clear();
+        config.actions.max_votes_per_hour = Some(0);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_ipfs_fetch_settings() {
+        let mut config = Config::default();
+        config
+            .apply_overrides(overrides(&[("IPFS_MAX_RETRIES", "0")]))
+            .unwrap();
+        assert_eq!(config.ipfs.max_retries, 0);
+        assert!(config.validate().is_ok());
+
+        assert_eq!(
+            config
+                .apply_overrides(overrides(&[("IPFS_MAX_RETRIES", "-1")]))
+                .unwrap_err()
+                .to_string(),
+            "ipfs.max_retries is invalid: expected a number, got '-1'. Fix it in the config file or with IPFS_MAX_RETRIES"
+        );
+
+        config
+            .apply_overrides(overrides(&[
+                ("IPFS_RETRY_MAX_DELAY_MS", "250"),
+                ("IPFS_RATE_LIMIT_PER_SEC", "10"),
+            ]))
+            .unwrap();
+        assert_eq!(config.ipfs.retry_max_delay(), Duration::from_millis(250));
+        assert_eq!(config.ipfs.rate_limit_per_sec, Some(10));
+        assert!(config.validate().is_ok());
+
+        config.ipfs.rate_limit_per_sec = Some(0);
+        assert_eq!(
+            config.validate().unwrap_err().to_string(),
+            "ipfs.rate_limit_per_sec is invalid: at least 1 request a second must be allowed, leave it unset to disable it. Fix it in the config file or with IPFS_RATE_LIMIT_PER_SEC"
+        );
+    }
+}
@@ -0,0 +1,154 @@
+use prost::Message;
+use stream::{
+    pb::sf::substreams::rpc::v2::BlockScopedData,
+    test_source::fixture_block,
+    utils::{self, BlockMetadata},
+};
+use thiserror::Error;
+use wire::pb::chain::GeoOutput;
+
+use crate::get_blocklist;
+
+pub const GEO_OUTPUT_MODULE: &str = "geo_out";
+pub const GEO_OUTPUT_TYPE_URL: &str = "type.googleapis.com/chain.GeoOutput";
+
+#[derive(Error, Debug)]
+pub enum DecodeError {
+    #[error("Block {0} has no module output")]
+    MissingOutput(String),
+
+    #[error("Block {0} has no clock")]
+    MissingClock(String),
+
+    #[error("Failed to decode GeoOutput: {0}")]
+    Protobuf(#[from] prost::DecodeError),
+}
+
+/// A `GeoOutput` decoded from a substreams block along with the block it was
+/// emitted in.
+///
+/// Edits published by blocklisted DAOs are removed from `output` during
+/// decoding, so every sink sees the same filtered set of edits.
+#[derive(Clone, Debug)]
+pub struct DecodedBlock {
+    pub block: BlockMetadata,
+    pub output: GeoOutput,
+    pub payload_bytes: usize,
+    pub blocklisted_edits: usize,
+}
+
+/// Decodes the `GeoOutput` of a block and filters out blocklisted edits.
+pub fn decode_block(block_data: &BlockScopedData) -> Result<DecodedBlock, DecodeError> {
+    let output = block_data
+        .output
+        .as_ref()
+        .and_then(|output| output.map_output.as_ref())
+        .ok_or_else(|| DecodeError::MissingOutput(block_data.cursor.clone()))?;
+
+    if block_data
+        .clock
+        .as_ref()
+        .is_none_or(|clock| clock.timestamp.is_none())
+    {
+        return Err(DecodeError::MissingClock(block_data.cursor.clone()));
+    }
+
+    let mut geo = GeoOutput::decode(output.value.as_slice())?;
+
+    let total_edits = geo.edits_published.len();
+    geo.edits_published.retain(|edit| {
+        !get_blocklist()
+            .dao_addresses
+            .contains(&edit.dao_address.as_str())
+    });
+
+    Ok(DecodedBlock {
+        block: utils::block_metadata(block_data),
+        blocklisted_edits: total_edits - geo.edits_published.len(),
+        payload_bytes: output.value.len(),
+        output: geo,
+    })
+}
+
+/// Wraps an encoded `GeoOutput` in a `BlockScopedData` as emitted by the
+/// `geo_out` module, so tests can feed decoded data to any sink.
+pub fn geo_output_block(
+    block_number: u64,
+    timestamp_seconds: i64,
+    output: &GeoOutput,
+) -> BlockScopedData {
+    fixture_block(
+        block_number,
+        timestamp_seconds,
+        GEO_OUTPUT_MODULE,
+        GEO_OUTPUT_TYPE_URL,
+        output.encode_to_vec(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wire::pb::chain::EditPublished;
+
+    fn edit(dao_address: &str) -> EditPublished {
+        EditPublished {
+            content_uri: format!("ipfs://{}", dao_address),
+            plugin_address: "0xplugin".to_string(),
+            dao_address: dao_address.to_string(),
+            sender: String::new(),
+        }
+    }
+
+    #[test]
+    fn test_decode_round_trip() {
+        let geo = GeoOutput {
+            edits_published: vec![edit("0x1")],
+            ..Default::default()
+        };
+
+        let decoded = decode_block(&geo_output_block(42, 1_700_000_000, &geo)).unwrap();
+
+        assert_eq!(decoded.block.block_number, 42);
+        assert_eq!(decoded.block.timestamp, "1700000000");
+        assert_eq!(decoded.output, geo);
+        assert_eq!(decoded.blocklisted_edits, 0);
+        assert_eq!(decoded.payload_bytes, geo.encoded_len());
+    }
+
+    #[test]
+    fn test_decode_filters_blocklisted_edits() {
+        let blocklisted = get_blocklist().dao_addresses[0];
+        let geo = GeoOutput {
+            edits_published: vec![edit(blocklisted), edit("0x1")],
+            ..Default::default()
+        };
+
+        let decoded = decode_block(&geo_output_block(1, 1_700_000_000, &geo)).unwrap();
+
+        assert_eq!(decoded.output.edits_published, vec![edit("0x1")]);
+        assert_eq!(decoded.blocklisted_edits, 1);
+    }
+
+    #[test]
+    fn test_decode_missing_output() {
+        let mut block = geo_output_block(1, 1_700_000_000, &GeoOutput::default());
+        block.output = None;
+
+        assert!(matches!(
+            decode_block(&block),
+            Err(DecodeError::MissingOutput(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_missing_clock() {
+        let mut block = geo_output_block(1, 1_700_000_000, &GeoOutput::default());
+        block.clock = None;
+
+        assert!(matches!(
+            decode_block(&block),
+            Err(DecodeError::MissingClock(_))
+        ));
+    }
+}
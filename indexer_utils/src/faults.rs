@@ -0,0 +1,59 @@
+//! Randomized fault injection for tests of how the indexers cope with
+//! failing dependencies. Faults are drawn from a seeded generator, so a run
+//! that goes wrong can be reproduced from its seed.
+
+use std::sync::{
+    Mutex,
+    atomic::{AtomicUsize, Ordering},
+};
+
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+pub struct FaultInjector {
+    rng: Mutex<StdRng>,
+    injected: AtomicUsize,
+}
+
+impl FaultInjector {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+            injected: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether to inject a fault that happens at `rate`, from 0 for never
+    /// to 1 for always.
+    pub fn roll(&self, rate: f64) -> bool {
+        let fault = self.rng.lock().unwrap().random_bool(rate.clamp(0.0, 1.0));
+        if fault {
+            self.injected.fetch_add(1, Ordering::Relaxed);
+        }
+        fault
+    }
+
+    /// The number of faults injected so far.
+    pub fn injected(&self) -> usize {
+        self.injected.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rates_and_seeds() {
+        let injector = FaultInjector::new(7);
+        assert!((0..100).all(|_| !injector.roll(0.0)));
+        assert!((0..100).all(|_| injector.roll(1.0)));
+        assert_eq!(injector.injected(), 100);
+
+        let rolls = |seed| {
+            let injector = FaultInjector::new(seed);
+            (0..64).map(|_| injector.roll(0.5)).collect::<Vec<_>>()
+        };
+        assert_eq!(rolls(7), rolls(7));
+        assert_ne!(rolls(7), rolls(8));
+    }
+}
@@ -1,70 +1,137 @@
+use std::{fmt, str::FromStr, sync::OnceLock};
+
 use md5::{Digest, Md5};
-use uuid::{Builder, Uuid};
+use serde::{Deserialize, Serialize};
+use uuid::{Builder, Uuid, uuid};
+pub use wire::base58::{decode_base58_to_uuid, encode_uuid_to_base58};
 
 use crate::checksum_address;
 
-pub fn create_id_from_unique_string(text: impl Into<String>) -> String {
-    let mut hasher = Md5::new();
-    hasher.update(text.into());
-    let hashed: [u8; 16] = hasher.finalize().into();
+/// The namespace of the UUIDv5 ids derived with `IdScheme::V2`, which is
+/// the UUIDv5 of `gaia` in the DNS namespace.
+pub const GAIA_NAMESPACE: Uuid = uuid!("52f6c49e-383f-518e-acdc-a929e0878eba");
 
-    let uuid = Builder::from_random_bytes(hashed).into_uuid();
-    encode_uuid_to_base58(&uuid.to_string())
+/// The algorithm ids are derived with. Ids derived with different schemes
+/// never match, so a deployment derives every id with the same scheme and
+/// records it in its database.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum IdScheme {
+    /// The MD5 of the input, with the UUID version and variant bits set.
+    #[default]
+    V1,
+    /// The UUIDv5 (SHA-1) of the input in the `GAIA_NAMESPACE`.
+    V2,
 }
 
-pub fn derive_space_id(network: &str, dao_address: &str) -> Uuid {
-    let mut hasher = Md5::new();
-    hasher.update(format!("{}:{}", network, checksum_address(dao_address)));
-    let hashed: [u8; 16] = hasher.finalize().into();
+impl IdScheme {
+    pub const ALL: [IdScheme; 2] = [IdScheme::V1, IdScheme::V2];
 
-    Builder::from_random_bytes(hashed).into_uuid()
-}
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IdScheme::V1 => "v1",
+            IdScheme::V2 => "v2",
+        }
+    }
 
-const BASE58_ALLOWED_CHARS: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    pub fn derive(&self, input: &str) -> Uuid {
+        match self {
+            IdScheme::V1 => {
+                let mut hasher = Md5::new();
+                hasher.update(input);
+                let hashed: [u8; 16] = hasher.finalize().into();
 
-pub fn encode_uuid_to_base58(val: &str) -> String {
-    let val = val.replace("-", "");
+                Builder::from_random_bytes(hashed).into_uuid()
+            }
+            IdScheme::V2 => Uuid::new_v5(&GAIA_NAMESPACE, input.as_bytes()),
+        }
+    }
 
-    let hex = match u128::from_str_radix(&val, 16) {
-        Ok(num) => num,
-        Err(_) => return String::new(),
-    };
-    let mut remainder = hex;
-    let mut result = Vec::new();
+    pub fn space_id(&self, network: &str, dao_address: &str) -> Uuid {
+        self.derive(&format!("{}:{}", network, checksum_address(dao_address)))
+    }
 
-    while remainder > 0 {
-        let mod_val = remainder % 58;
-        if let Some(&base58_char) = BASE58_ALLOWED_CHARS.get(mod_val as usize) {
-            result.push(base58_char as char);
-        }
-        remainder /= 58;
+    pub fn value_id(&self, entity_id: &Uuid, property_id: &Uuid, space_id: &Uuid) -> Uuid {
+        self.derive(&format!("value:{}:{}:{}", entity_id, property_id, space_id))
+    }
+
+    pub fn version_id(&self, entity_id: &Uuid, edit_id: &Uuid) -> Uuid {
+        self.derive(&format!("version:{}:{}", entity_id, edit_id))
+    }
+
+    pub fn edit_scoped_id(&self, edit_id: &Uuid, id: &Uuid) -> Uuid {
+        self.derive(&format!("edit:{}:{}", edit_id, id))
     }
+}
 
-    result.reverse();
-    result.iter().collect()
+impl fmt::Display for IdScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
 }
 
-pub fn decode_base58_to_uuid(encoded: &str) -> Result<String, &'static str> {
-    let mut decoded: u128 = 0;
+impl FromStr for IdScheme {
+    type Err = String;
 
-    for char in encoded.chars() {
-        let index = BASE58_ALLOWED_CHARS.iter().position(|&c| c == char as u8);
-        if let Some(index) = index {
-            decoded = decoded * 58 + index as u128;
-        } else {
-            return Err("Invalid Base58 character");
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "v1" => Ok(IdScheme::V1),
+            "v2" => Ok(IdScheme::V2),
+            _ => Err(format!("expected v1 or v2, got '{}'", s)),
         }
     }
+}
 
-    let hex_str = format!("{:032x}", decoded);
-    Ok(format!(
-        "{}-{}-{}-{}-{}",
-        &hex_str[0..8],
-        &hex_str[8..12],
-        &hex_str[12..16],
-        &hex_str[16..20],
-        &hex_str[20..32]
-    ))
+static SCHEME: OnceLock<IdScheme> = OnceLock::new();
+
+/// Selects the scheme the `derive_*` functions use for the rest of the
+/// process. Only the first selection takes effect, so the selected scheme
+/// is returned.
+pub fn select_scheme(scheme: IdScheme) -> IdScheme {
+    *SCHEME.get_or_init(|| scheme)
+}
+
+/// The scheme the `derive_*` functions use, `V1` unless another one was
+/// selected.
+pub fn scheme() -> IdScheme {
+    SCHEME.get().copied().unwrap_or_default()
+}
+
+pub fn create_id_from_unique_string(text: impl Into<String>) -> String {
+    let mut hasher = Md5::new();
+    hasher.update(text.into());
+    let hashed: [u8; 16] = hasher.finalize().into();
+
+    let uuid = Builder::from_random_bytes(hashed).into_uuid();
+    encode_uuid_to_base58(&uuid.to_string())
+}
+
+/// Derives the id of a DAO's space on a network from
+/// `{network}:{dao_address}`, with the address checksummed, using the
+/// selected scheme.
+pub fn derive_space_id(network: &str, dao_address: &str) -> Uuid {
+    scheme().space_id(network, dao_address)
+}
+
+/// Derives the id of the value an entity has for a property in a space, from
+/// `value:{entity_id}:{property_id}:{space_id}` with all ids in hyphenated
+/// form, using the selected scheme.
+pub fn derive_value_id(entity_id: &Uuid, property_id: &Uuid, space_id: &Uuid) -> Uuid {
+    scheme().value_id(entity_id, property_id, space_id)
+}
+
+/// Derives the id of the version of an entity created by an edit, from
+/// `version:{entity_id}:{edit_id}` with both ids in hyphenated form, using
+/// the selected scheme.
+pub fn derive_version_id(entity_id: &Uuid, edit_id: &Uuid) -> Uuid {
+    scheme().version_id(entity_id, edit_id)
+}
+
+/// Derives an id for something that only exists within a single edit, like
+/// an entity's changes in that edit, from `edit:{edit_id}:{id}` with both ids
+/// in hyphenated form, using the selected scheme.
+pub fn derive_edit_scoped_id(edit_id: &Uuid, id: &Uuid) -> Uuid {
+    scheme().edit_scoped_id(edit_id, id)
 }
 
 #[derive(Clone, Debug)]
@@ -114,4 +181,180 @@ mod tests {
         let decoded = decode_base58_to_uuid(&encoded).unwrap();
         assert_eq!(uuid, decoded);
     }
+
+    const ENTITY_ID: &str = "1cc6995f-6cc2-4c7a-9592-1466bf95f6be";
+    const EDIT_ID: &str = "08c4f093-7858-4b7c-9b94-b82e448abcff";
+    const PROPERTY_ID: &str = "a126ca53-0c8e-48d5-b888-82c734c38935";
+    const SPACE_ID: &str = "f92aa4e1-c092-4259-b7ff-efa2a89c1660";
+
+    #[test]
+    fn test_derive_space_id() {
+        assert_eq!(
+            derive_space_id(
+                crate::network_ids::GEO,
+                "0x0000000000000000000000000000000000000001"
+            )
+            .to_string(),
+            "f92aa4e1-c092-4259-b7ff-efa2a89c1660",
+        )
+    }
+
+    #[test]
+    fn test_derive_value_id() {
+        let entity_id = Uuid::parse_str(ENTITY_ID).unwrap();
+        let property_id = Uuid::parse_str(PROPERTY_ID).unwrap();
+        let space_id = Uuid::parse_str(SPACE_ID).unwrap();
+
+        assert_eq!(
+            derive_value_id(&entity_id, &property_id, &space_id).to_string(),
+            "f1428272-fb47-438f-a0e5-b2698f048147",
+        )
+    }
+
+    #[test]
+    fn test_derive_version_id() {
+        let entity_id = Uuid::parse_str(ENTITY_ID).unwrap();
+        let edit_id = Uuid::parse_str(EDIT_ID).unwrap();
+
+        assert_eq!(
+            derive_version_id(&entity_id, &edit_id).to_string(),
+            "a0d07b40-b830-4b1a-bdc7-235bb2c5ec74",
+        )
+    }
+
+    #[test]
+    fn test_derive_edit_scoped_id() {
+        let entity_id = Uuid::parse_str(ENTITY_ID).unwrap();
+        let edit_id = Uuid::parse_str(EDIT_ID).unwrap();
+
+        assert_eq!(
+            derive_edit_scoped_id(&edit_id, &entity_id).to_string(),
+            "c96fd5e1-c1d1-4e59-af88-8d764e06a034",
+        );
+        assert_ne!(
+            derive_edit_scoped_id(&edit_id, &entity_id),
+            derive_version_id(&entity_id, &edit_id)
+        );
+    }
+
+    /// The vectors other implementations check their id derivations against.
+    const ID_VECTORS: &str = include_str!("../fixtures/id_vectors.json");
+
+    #[derive(serde::Deserialize)]
+    struct IdVectors {
+        space_id: Vec<SpaceIdVector>,
+        value_id: Vec<ValueIdVector>,
+        version_id: Vec<VersionIdVector>,
+        edit_scoped_id: Vec<EditScopedIdVector>,
+        base58: Vec<Base58Vector>,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct SpaceIdVector {
+        network: String,
+        dao_address: String,
+        input: String,
+        expected: Uuid,
+        expected_v2: Uuid,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct ValueIdVector {
+        entity_id: Uuid,
+        property_id: Uuid,
+        space_id: Uuid,
+        input: String,
+        expected: Uuid,
+        expected_v2: Uuid,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct VersionIdVector {
+        entity_id: Uuid,
+        edit_id: Uuid,
+        input: String,
+        expected: Uuid,
+        expected_v2: Uuid,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct EditScopedIdVector {
+        edit_id: Uuid,
+        id: Uuid,
+        input: String,
+        expected: Uuid,
+        expected_v2: Uuid,
+    }
+
+    #[derive(serde::Deserialize)]
+    struct Base58Vector {
+        uuid: String,
+        expected: String,
+    }
+
+    #[test]
+    fn test_id_vectors() {
+        let vectors: IdVectors = serde_json::from_str(ID_VECTORS).unwrap();
+
+        for vector in &vectors.space_id {
+            assert_eq!(IdScheme::V1.derive(&vector.input), vector.expected);
+            assert_eq!(IdScheme::V2.derive(&vector.input), vector.expected_v2);
+            assert_eq!(
+                derive_space_id(&vector.network, &vector.dao_address),
+                vector.expected,
+                "space id of {} on {}",
+                vector.dao_address,
+                vector.network
+            );
+            assert_eq!(
+                IdScheme::V2.space_id(&vector.network, &vector.dao_address),
+                vector.expected_v2
+            );
+        }
+        for vector in &vectors.value_id {
+            assert_eq!(IdScheme::V1.derive(&vector.input), vector.expected);
+            assert_eq!(IdScheme::V2.derive(&vector.input), vector.expected_v2);
+            assert_eq!(
+                derive_value_id(&vector.entity_id, &vector.property_id, &vector.space_id),
+                vector.expected
+            );
+            assert_eq!(
+                IdScheme::V2.value_id(&vector.entity_id, &vector.property_id, &vector.space_id),
+                vector.expected_v2
+            );
+        }
+        for vector in &vectors.version_id {
+            assert_eq!(IdScheme::V1.derive(&vector.input), vector.expected);
+            assert_eq!(IdScheme::V2.derive(&vector.input), vector.expected_v2);
+            assert_eq!(
+                derive_version_id(&vector.entity_id, &vector.edit_id),
+                vector.expected
+            );
+        }
+        for vector in &vectors.edit_scoped_id {
+            assert_eq!(IdScheme::V1.derive(&vector.input), vector.expected);
+            assert_eq!(IdScheme::V2.derive(&vector.input), vector.expected_v2);
+            assert_eq!(
+                derive_edit_scoped_id(&vector.edit_id, &vector.id),
+                vector.expected
+            );
+        }
+        for vector in &vectors.base58 {
+            assert_eq!(encode_uuid_to_base58(&vector.uuid), vector.expected);
+            assert_eq!(
+                decode_base58_to_uuid(&vector.expected).unwrap(),
+                vector.uuid
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_id_scheme() {
+        assert_eq!("v1".parse(), Ok(IdScheme::V1));
+        assert_eq!(" V2 ".parse(), Ok(IdScheme::V2));
+        assert!("md5".parse::<IdScheme>().is_err());
+        assert_eq!(IdScheme::V2.to_string(), "v2");
+        // Nothing in the tests selects a scheme
+        assert_eq!(scheme(), IdScheme::V1);
+    }
 }
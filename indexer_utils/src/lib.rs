@@ -1,9 +1,50 @@
+pub mod config;
+pub mod decoded;
+#[cfg(feature = "faults")]
+pub mod faults;
 pub mod graph_uri;
 pub mod id;
 pub mod network_ids;
+pub mod system_ids;
 
 use sha3::{Digest, Keccak256};
+use thiserror::Error;
 
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("Invalid address '{0}': expected 0x followed by 40 hex characters")]
+    Malformed(String),
+
+    #[error("Invalid address '{address}': mixed case doesn't match the EIP-55 checksum {expected}")]
+    Checksum { address: String, expected: String },
+}
+
+/// Checks that an address is `0x` followed by 40 hex characters. Addresses in
+/// mixed case must match their EIP-55 checksum, while all lowercase or all
+/// uppercase addresses aren't checksummed and are accepted as they are.
+pub fn validate_address(address: &str) -> Result<(), AddressError> {
+    let hex = address
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40 && hex.bytes().all(|byte| byte.is_ascii_hexdigit()))
+        .ok_or_else(|| AddressError::Malformed(address.to_string()))?;
+
+    let has_lower = hex.bytes().any(|byte| byte.is_ascii_lowercase());
+    let has_upper = hex.bytes().any(|byte| byte.is_ascii_uppercase());
+    if has_lower && has_upper {
+        let expected = checksum_address(address);
+        if expected != address {
+            return Err(AddressError::Checksum {
+                address: address.to_string(),
+                expected,
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the EIP-55 checksummed form of an address. The address isn't
+/// validated, so check it with `validate_address` first.
 pub fn checksum_address(address: impl Into<String>) -> String {
     let input_address = address.into().to_lowercase().replace("0x", "");
 
@@ -47,6 +88,40 @@ pub fn get_blocklist() -> SpacesBlocklist<'static> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_validate_address() {
+        for address in [
+            "0x7E3DFCf5E438bAb9d1C6F1A4542c916432Fb9FEB",
+            "0x7e3dfcf5e438bab9d1c6f1a4542c916432fb9feb",
+            "0x7E3DFCF5E438BAB9D1C6F1A4542C916432FB9FEB",
+        ] {
+            assert_eq!(validate_address(address), Ok(()));
+        }
+
+        for address in [
+            "",
+            "dao1",
+            "7e3dfcf5e438bab9d1c6f1a4542c916432fb9feb",
+            "0x7e3dfcf5e438bab9d1c6f1a4542c916432fb9fe",
+            "0x7e3dfcf5e438bab9d1c6f1a4542c916432fb9febb",
+            "0x7e3dfcf5e438bab9d1c6f1a4542c916432fb9feg",
+            "0x7e3dfcf5e438bab9d1c6f1a4542c916432fb9fé",
+        ] {
+            assert_eq!(
+                validate_address(address),
+                Err(AddressError::Malformed(address.to_string()))
+            );
+        }
+
+        assert_eq!(
+            validate_address("0x7e3DFCf5E438bAb9d1C6F1A4542c916432Fb9FEB"),
+            Err(AddressError::Checksum {
+                address: "0x7e3DFCf5E438bAb9d1C6F1A4542c916432Fb9FEB".to_string(),
+                expected: "0x7E3DFCf5E438bAb9d1C6F1A4542c916432Fb9FEB".to_string(),
+            })
+        );
+    }
+
     #[test]
     fn test_checksum_address() {
         assert_eq!(
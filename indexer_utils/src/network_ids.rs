@@ -1,3 +1,73 @@
+use uuid::Uuid;
+
+use crate::id::derive_space_id;
+
 pub const POLYGON: &str = "ReFDxuVQ674gHd2kFjsFL";
 pub const ETHEREUM: &str = "JnWfsCw2gqoPWFT1NXmFWW";
 pub const GEO: &str = "KJjKetFsGVSbw9qFpRzRSy";
+
+/// The network a deployment indexes.
+///
+/// Space ids are derived from the network id, so the same DAO address on two
+/// networks maps to two different spaces and several networks can be indexed
+/// into the same database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Network {
+    pub id: String,
+    pub chain_id: Option<u64>,
+}
+
+impl Network {
+    pub fn new(id: impl Into<String>, chain_id: Option<u64>) -> Self {
+        Network {
+            id: id.into(),
+            chain_id,
+        }
+    }
+
+    pub fn space_id(&self, dao_address: &str) -> Uuid {
+        derive_space_id(&self.id, dao_address)
+    }
+
+    /// Scopes a sink's cursor id to the network so several networks can
+    /// share a cursor table. GEO keeps the unscoped id so existing cursors
+    /// are still found.
+    pub fn cursor_id(&self, cursor_id: &str) -> String {
+        if self.id == GEO {
+            cursor_id.to_string()
+        } else {
+            format!("{}:{}", cursor_id, self.id)
+        }
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        Network::new(GEO, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAO_ADDRESS: &str = "0x0000000000000000000000000000000000000001";
+
+    #[test]
+    fn test_space_ids_differ_per_network() {
+        let geo = Network::default();
+        let other = Network::new(POLYGON, Some(137));
+
+        assert_eq!(geo.space_id(DAO_ADDRESS), derive_space_id(GEO, DAO_ADDRESS));
+        assert_ne!(geo.space_id(DAO_ADDRESS), other.space_id(DAO_ADDRESS));
+    }
+
+    #[test]
+    fn test_cursor_id() {
+        assert_eq!(Network::default().cursor_id("kg_indexer"), "kg_indexer");
+        assert_eq!(
+            Network::new(POLYGON, None).cursor_id("kg_indexer"),
+            format!("kg_indexer:{}", POLYGON)
+        );
+    }
+}
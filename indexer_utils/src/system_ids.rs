@@ -0,0 +1,33 @@
+//! Ids of the system properties the indexer reads from the knowledge graph.
+
+use uuid::{Uuid, uuid};
+
+/// The name of an entity, as a text value.
+pub const NAME_PROPERTY: Uuid = uuid!("a126ca53-0c8e-48d5-b888-82c734c38935");
+
+/// The description of an entity, as a text value.
+pub const DESCRIPTION_PROPERTY: Uuid = uuid!("9b1f76ff-9711-404c-861e-59dc3fa7d037");
+
+/// The relation from a property to the type its values render as, e.g. URL
+/// or image.
+pub const RENDERABLE_TYPE_PROPERTY: Uuid = uuid!("2316bbe1-c76f-4635-83f2-3e03b4f1fe46");
+
+/// The relation from an entity to each of its types.
+pub const TYPES_PROPERTY: Uuid = uuid!("8f151ba4-de20-4e3c-9cb4-99ddf96f48f1");
+
+/// The type of types. Entities with a types relation to it are types.
+pub const SCHEMA_TYPE: Uuid = uuid!("e7d737c5-3676-4c60-9fa1-6aa64a8c90ad");
+
+/// The relation from a type to each property its entities are expected to
+/// have.
+pub const PROPERTIES_PROPERTY: Uuid = uuid!("01412f83-8189-4ab1-8365-65c7fd358cc1");
+
+/// The type of a space's own entity, which carries the space's name,
+/// description and cover.
+pub const SPACE_TYPE: Uuid = uuid!("362c1dbd-dc64-44bb-a3c4-652f38a642d7");
+
+/// The relation from an entity to its cover image.
+pub const COVER_PROPERTY: Uuid = uuid!("34f53507-2e6b-42c5-a844-43981a77cfa2");
+
+/// The URL of an image entity, as a text value.
+pub const IMAGE_URL_PROPERTY: Uuid = uuid!("8a743832-c094-4a62-b665-0c3cc2f9c7bc");
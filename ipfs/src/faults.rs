@@ -0,0 +1,116 @@
+use indexer_utils::faults::FaultInjector;
+
+/// The rates at which an [`IpfsClient`](crate::IpfsClient) fails its
+/// fetches, from 0 for never to 1 for always.
+#[derive(Clone, Debug, Default)]
+pub struct IpfsFaults {
+    /// Fetches that time out without reaching the gateway. Timeouts are
+    /// transient errors.
+    pub timeout_rate: f64,
+    /// Fetches whose content is cut short, missing its second half, as when
+    /// the gateway closes the connection early. The content fails to decode
+    /// unless it happens to end on a field boundary.
+    pub truncate_rate: f64,
+    pub seed: u64,
+}
+
+pub(crate) struct Faults {
+    config: IpfsFaults,
+    injector: FaultInjector,
+}
+
+impl Faults {
+    pub(crate) fn new(config: IpfsFaults) -> Self {
+        Faults {
+            injector: FaultInjector::new(config.seed),
+            config,
+        }
+    }
+
+    pub(crate) fn timeout(&self) -> bool {
+        self.injector.roll(self.config.timeout_rate)
+    }
+
+    pub(crate) fn truncate(&self, bytes: &mut Vec<u8>) {
+        if self.injector.roll(self.config.truncate_rate) {
+            bytes.truncate(bytes.len() / 2);
+        }
+    }
+
+    pub(crate) fn injected(&self) -> usize {
+        self.injector.injected()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+
+    use prost::Message;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+    use wire::pb::grc20::Edit;
+
+    use super::*;
+    use crate::{IpfsClient, IpfsError};
+
+    /// Serves `body` to a single request and returns the gateway URL.
+    async fn serve_once(body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = [0; 1024];
+            let _ = socket.read(&mut request).await.unwrap();
+            let head = format!(
+                "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                body.len()
+            );
+            socket.write_all(head.as_bytes()).await.unwrap();
+            socket.write_all(&body).await.unwrap();
+        });
+
+        format!("http://{}/ipfs/", address)
+    }
+
+    #[tokio::test]
+    async fn test_timeouts_are_transient() {
+        // Nothing listens on the discard port, timeouts never reach it
+        let client = IpfsClient::new("http://127.0.0.1:9/ipfs/").with_faults(IpfsFaults {
+            timeout_rate: 1.0,
+            ..IpfsFaults::default()
+        });
+
+        let fetched = client.fetch("ipfs://cid").await;
+
+        assert!(
+            matches!(&fetched.edit, Err(IpfsError::Io(error)) if error.kind() == ErrorKind::TimedOut)
+        );
+        assert!(fetched.edit.unwrap_err().is_transient());
+        assert_eq!(fetched.bytes, None);
+        assert_eq!(client.injected_faults(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_truncated_content_fails_to_decode() {
+        let edit = Edit {
+            name: "x".repeat(100),
+            ..Edit::default()
+        };
+        let content = edit.encode_to_vec();
+        let client = IpfsClient::new(&serve_once(content.clone()).await).with_faults(IpfsFaults {
+            truncate_rate: 1.0,
+            ..IpfsFaults::default()
+        });
+
+        let fetched = client.fetch("ipfs://cid").await;
+
+        assert!(!fetched.edit.unwrap_err().is_transient());
+        assert_eq!(fetched.bytes, Some(content[..content.len() / 2].to_vec()));
+        assert_eq!(fetched.metadata.content_length, Some(content.len() / 2));
+        assert_eq!(client.injected_faults(), 1);
+    }
+}
@@ -1,6 +1,15 @@
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "faults")]
+mod faults;
+mod rate_limit;
+#[cfg(feature = "faults")]
+pub use faults::IpfsFaults;
+pub use rate_limit::RateLimiter;
+
 use reqwest::Client as ReqwestClient;
 use wire::{
-    deserialize::{deserialize, DeserializeError},
+    deserialize::{deserialize_with_format, DeserializeError, PayloadFormat},
     pb::grc20::Edit,
 };
 
@@ -18,11 +27,55 @@ pub enum IpfsError {
     DeserializeError(#[from] DeserializeError),
 }
 
+impl IpfsError {
+    /// Whether the error may go away if the fetch is tried again, like a
+    /// timeout or an error from the gateway, rather than content that can't
+    /// be decoded.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            IpfsError::Reqwest(error) => match error.status() {
+                Some(status) => {
+                    status.is_server_error() || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+                }
+                None => !error.is_decode() && !error.is_builder(),
+            },
+            IpfsError::Io(_) => true,
+            IpfsError::Prost(_) | IpfsError::CidError(_) | IpfsError::DeserializeError(_) => false,
+        }
+    }
+}
+
 type Result<T> = std::result::Result<T, IpfsError>;
 
+/// How an edit was fetched, recorded whether or not it could be decoded.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FetchMetadata {
+    /// The gateway URL the content was requested from.
+    pub gateway: String,
+    /// The size of the content as fetched, unset if the request failed.
+    pub content_length: Option<usize>,
+    /// Unset if the content couldn't be decoded.
+    pub format: Option<PayloadFormat>,
+    /// The time taken to fetch the content, without decoding it.
+    pub duration: Duration,
+}
+
+/// The outcome of fetching an edit.
+#[derive(Debug)]
+pub struct FetchedEdit {
+    pub edit: Result<Edit>,
+    /// The content as fetched, before it's decompressed, unset if the
+    /// request failed.
+    pub bytes: Option<Vec<u8>>,
+    pub metadata: FetchMetadata,
+}
+
 pub struct IpfsClient {
     url: String,
     client: ReqwestClient,
+    rate_limiter: Option<RateLimiter>,
+    #[cfg(feature = "faults")]
+    faults: Option<faults::Faults>,
 }
 
 impl IpfsClient {
@@ -30,10 +83,39 @@ impl IpfsClient {
         IpfsClient {
             url: url.to_string(),
             client: ReqwestClient::new(),
+            rate_limiter: None,
+            #[cfg(feature = "faults")]
+            faults: None,
         }
     }
 
+    /// Limits the requests sent to the gateway to `per_sec` a second.
+    pub fn with_rate_limit(mut self, per_sec: u32) -> Self {
+        self.rate_limiter = Some(RateLimiter::new(per_sec));
+        self
+    }
+
+    /// Fails fetches at the given rates, for testing how callers cope with
+    /// an unreliable gateway.
+    #[cfg(feature = "faults")]
+    pub fn with_faults(mut self, faults: IpfsFaults) -> Self {
+        self.faults = Some(faults::Faults::new(faults));
+        self
+    }
+
+    /// The number of faults injected into fetches so far.
+    #[cfg(feature = "faults")]
+    pub fn injected_faults(&self) -> usize {
+        self.faults.as_ref().map_or(0, faults::Faults::injected)
+    }
+
     pub async fn get(&self, hash: &str) -> Result<Edit> {
+        self.fetch(hash).await.edit
+    }
+
+    /// Fetches and decodes an edit like [`IpfsClient::get`], and returns the
+    /// fetched content and how it was fetched along with the result.
+    pub async fn fetch(&self, hash: &str) -> FetchedEdit {
         // @TODO: Error handle
         let cid = if let Some((_, maybe_cid)) = hash.split_once("://") {
             maybe_cid
@@ -41,16 +123,68 @@ impl IpfsClient {
             ""
         };
 
-        // @TODO: Should retry this fetch
-        let bytes = self.get_bytes(cid).await?;
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire().await;
+        }
+
+        let started = Instant::now();
+        let bytes = self.fetch_bytes(cid).await;
+        let mut metadata = FetchMetadata {
+            gateway: self.url.clone(),
+            content_length: None,
+            format: None,
+            duration: started.elapsed(),
+        };
+
+        let bytes = match bytes {
+            Ok(bytes) => bytes,
+            Err(error) => {
+                return FetchedEdit {
+                    edit: Err(error),
+                    bytes: None,
+                    metadata,
+                }
+            }
+        };
+        metadata.content_length = Some(bytes.len());
+
+        let edit = match deserialize_with_format(&bytes) {
+            Ok((edit, format)) => {
+                metadata.format = Some(format);
+                Ok(edit)
+            }
+            Err(error) => Err(error.into()),
+        };
+
+        FetchedEdit {
+            edit,
+            bytes: Some(bytes),
+            metadata,
+        }
+    }
+
+    #[cfg(not(feature = "faults"))]
+    async fn fetch_bytes(&self, cid: &str) -> Result<Vec<u8>> {
+        self.get_bytes(cid).await
+    }
+
+    #[cfg(feature = "faults")]
+    async fn fetch_bytes(&self, cid: &str) -> Result<Vec<u8>> {
+        let Some(faults) = &self.faults else {
+            return self.get_bytes(cid).await;
+        };
+        if faults.timeout() {
+            return Err(std::io::Error::from(std::io::ErrorKind::TimedOut).into());
+        }
 
-        let data = deserialize(&bytes)?;
-        return Ok(data);
+        let mut bytes = self.get_bytes(cid).await?;
+        faults.truncate(&mut bytes);
+        Ok(bytes)
     }
 
     pub async fn get_bytes(&self, hash: &str) -> Result<Vec<u8>> {
         let url = format!("{}{}", self.url, hash);
-        let res = self.client.get(&url).send().await?;
+        let res = self.client.get(&url).send().await?.error_for_status()?;
         let bytes = res.bytes().await?;
         Ok(bytes.to_vec())
     }
@@ -0,0 +1,56 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Paces requests to a gateway so they start at most `per_sec` times a
+/// second. Requests over the limit wait for their turn rather than failing.
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub fn new(per_sec: u32) -> Self {
+        RateLimiter {
+            interval: Duration::from_secs(1) / per_sec.max(1),
+            next: Mutex::new(None),
+        }
+    }
+
+    /// Waits until a request can be sent, and returns how long it waited.
+    pub async fn acquire(&self) -> Duration {
+        let now = Instant::now();
+        let slot = self.reserve(now);
+        tokio::time::sleep_until(slot.into()).await;
+        slot.saturating_duration_since(now)
+    }
+
+    /// Reserves the earliest free slot at or after `now`.
+    fn reserve(&self, now: Instant) -> Instant {
+        let mut next = self.next.lock().unwrap();
+        let slot = next.map_or(now, |next| next.max(now));
+        *next = Some(slot + self.interval);
+        slot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spaces_out_requests() {
+        let limiter = RateLimiter::new(4);
+        let now = Instant::now();
+
+        assert_eq!(limiter.reserve(now), now);
+        assert_eq!(limiter.reserve(now), now + Duration::from_millis(250));
+        assert_eq!(limiter.reserve(now), now + Duration::from_millis(500));
+
+        // Unused slots aren't saved up for later bursts
+        let later = now + Duration::from_secs(10);
+        assert_eq!(limiter.reserve(later), later);
+        assert_eq!(limiter.reserve(later), later + Duration::from_millis(250));
+    }
+}
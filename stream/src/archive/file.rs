@@ -0,0 +1,54 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use async_trait::async_trait;
+
+use super::{ArchiveError, ArchiveStore};
+
+/// Stores each key as a file in a directory.
+pub struct DirectoryStore {
+    dir: PathBuf,
+}
+
+impl DirectoryStore {
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, ArchiveError> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        Ok(DirectoryStore { dir })
+    }
+}
+
+#[async_trait]
+impl ArchiveStore for DirectoryStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ArchiveError> {
+        // Written next to the file and renamed over it, so a crash never
+        // leaves a truncated block behind.
+        let partial = self.dir.join(format!("{}.partial", key));
+        fs::write(&partial, bytes)?;
+        fs::rename(&partial, self.dir.join(key))?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ArchiveError> {
+        match fs::read(self.dir.join(key)) {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn list(&self) -> Result<Vec<String>, ArchiveError> {
+        let mut keys = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            if let Some(name) = entry?.file_name().to_str() {
+                keys.push(name.to_string());
+            }
+        }
+
+        Ok(keys)
+    }
+}
@@ -0,0 +1,65 @@
+//! Stores for archived blocks, in a directory or an S3 compatible bucket.
+//!
+//! Blocks are archived under keys that sort in block order, so the recorder
+//! and the indexer's archive can read each other's blocks.
+
+use async_trait::async_trait;
+use thiserror::Error;
+
+pub mod file;
+pub mod s3;
+
+const BLOCK_SUFFIX: &str = ".pb.zst";
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Archive error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Archive error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Archive store responded with status {status}: {body}")]
+    Status { status: u16, body: String },
+
+    #[error("Archive error: {0}")]
+    Decode(#[from] prost::DecodeError),
+
+    #[error("Block {0} isn't archived")]
+    NotFound(u64),
+}
+
+/// Where archived blocks are stored, by key.
+#[async_trait]
+pub trait ArchiveStore: Send + Sync {
+    /// Stores the bytes, replacing any stored under the same key.
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<(), ArchiveError>;
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>, ArchiveError>;
+
+    /// Returns every stored key, in any order.
+    async fn list(&self) -> Result<Vec<String>, ArchiveError>;
+}
+
+/// The key a block is archived under, which sorts in block order.
+pub fn block_key(block_number: u64) -> String {
+    format!("{:012}{}", block_number, BLOCK_SUFFIX)
+}
+
+/// The number of the block archived under `key`, or `None` if the key isn't
+/// a block's.
+pub fn parse_block_key(key: &str) -> Option<u64> {
+    key.strip_suffix(BLOCK_SUFFIX)?.parse().ok()
+}
+
+async fn check_response(response: reqwest::Response) -> Result<reqwest::Response, ArchiveError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    Err(ArchiveError::Status {
+        status: status.as_u16(),
+        body: response.text().await.unwrap_or_default(),
+    })
+}
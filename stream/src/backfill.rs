@@ -0,0 +1,156 @@
+use anyhow::Error;
+use futures03::StreamExt;
+use std::{env, sync::Arc};
+
+use crate::{
+    pb::sf::substreams::v1::Modules,
+    substreams::SubstreamsEndpoint,
+    substreams_stream::{BlockResponse, StreamOptions, SubstreamsStream},
+};
+
+/// Settings for a parallel historical backfill.
+///
+/// The range `[start_block, end_block)` is split into segments of
+/// `segment_size` blocks. Up to `workers` segments are streamed over separate
+/// substreams connections at once, and completed segments are handed to the
+/// sink strictly in block order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BackfillConfig {
+    pub end_block: u64,
+    pub workers: usize,
+    pub segment_size: u64,
+}
+
+impl BackfillConfig {
+    /// Reads `BACKFILL_END_BLOCK`, `BACKFILL_WORKERS` and `BACKFILL_SEGMENT_SIZE`
+    /// from the environment. Returns `None` when no end block is configured,
+    /// which disables the backfill.
+    pub fn from_env() -> Option<Self> {
+        let end_block = env::var("BACKFILL_END_BLOCK").ok()?.parse::<u64>().ok()?;
+
+        let workers = env::var("BACKFILL_WORKERS")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(4)
+            .max(1);
+
+        let segment_size = env::var("BACKFILL_SEGMENT_SIZE")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+            .unwrap_or(100_000)
+            .max(1);
+
+        Some(BackfillConfig {
+            end_block,
+            workers,
+            segment_size,
+        })
+    }
+}
+
+/// Splits `[start_block, end_block)` into contiguous `(start, stop)` segments.
+pub(crate) fn segments(start_block: i64, end_block: u64, segment_size: u64) -> Vec<(i64, u64)> {
+    let mut segments = Vec::new();
+    let mut start = start_block.max(0) as u64;
+
+    while start < end_block {
+        let stop = (start + segment_size).min(end_block);
+        segments.push((start as i64, stop));
+        start = stop;
+    }
+
+    segments
+}
+
+/// Streams a single segment to completion and buffers its responses.
+async fn fetch_segment(
+    endpoint: Arc<SubstreamsEndpoint>,
+    modules: Option<Modules>,
+    module_name: String,
+    start_block: i64,
+    stop_block: u64,
+    production_mode: bool,
+) -> Result<Vec<BlockResponse>, Error> {
+    let mut stream = SubstreamsStream::with_options(
+        endpoint,
+        None,
+        modules,
+        module_name,
+        start_block,
+        stop_block,
+        StreamOptions {
+            production_mode,
+            ..StreamOptions::default()
+        },
+    );
+
+    let mut responses = Vec::new();
+    while let Some(response) = stream.next().await {
+        responses.push(response?);
+    }
+
+    println!(
+        "Backfill segment [{}, {}) fetched ({} responses)",
+        start_block,
+        stop_block,
+        responses.len()
+    );
+
+    Ok(responses)
+}
+
+/// Fetches every segment of the backfill range in parallel and yields the
+/// buffered responses of each segment in block order.
+///
+/// Segment fetches are spawned as separate tasks so connections keep streaming
+/// while the consumer is busy processing an earlier segment.
+pub fn backfill_segments(
+    endpoint: Arc<SubstreamsEndpoint>,
+    modules: Option<Modules>,
+    module_name: String,
+    start_block: i64,
+    config: &BackfillConfig,
+    production_mode: bool,
+) -> impl futures03::Stream<Item = Result<Vec<BlockResponse>, Error>> + Send {
+    futures03::stream::iter(segments(start_block, config.end_block, config.segment_size))
+        .map(move |(start, stop)| {
+            tokio::spawn(fetch_segment(
+                endpoint.clone(),
+                modules.clone(),
+                module_name.clone(),
+                start,
+                stop,
+                production_mode,
+            ))
+        })
+        .buffered(config.workers)
+        .map(|result| result?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments_cover_range() {
+        assert_eq!(
+            segments(0, 250, 100),
+            vec![(0, 100), (100, 200), (200, 250)]
+        );
+    }
+
+    #[test]
+    fn test_segments_exact_multiple() {
+        assert_eq!(segments(100, 300, 100), vec![(100, 200), (200, 300)]);
+    }
+
+    #[test]
+    fn test_segments_empty_when_start_past_end() {
+        assert!(segments(500, 300, 100).is_empty());
+    }
+
+    #[test]
+    fn test_segments_negative_start_clamped() {
+        assert_eq!(segments(-10, 50, 100), vec![(0, 50)]);
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use sqlx::{Postgres, Row};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum CursorStoreError {
+    #[error("Cursor store error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    #[error("Cursor store error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Persists substreams cursors so a sink can resume where it left off.
+///
+/// Sinks opt in by holding a store and delegating `persist_cursor` and
+/// `load_persisted_cursor` to it, keyed by an id unique to the sink.
+pub trait CursorStore: Send + Sync {
+    fn load(
+        &self,
+        id: &str,
+    ) -> impl Future<Output = Result<Option<String>, CursorStoreError>> + Send;
+
+    fn persist(
+        &self,
+        id: &str,
+        cursor: &str,
+        block: u64,
+    ) -> impl Future<Output = Result<(), CursorStoreError>> + Send;
+}
+
+/// Stores cursors in the `meta` table shared by the indexing services.
+#[derive(Clone, Debug)]
+pub struct PostgresCursorStore {
+    pool: sqlx::Pool<Postgres>,
+}
+
+impl PostgresCursorStore {
+    pub fn new(pool: sqlx::Pool<Postgres>) -> Self {
+        PostgresCursorStore { pool }
+    }
+}
+
+impl CursorStore for PostgresCursorStore {
+    async fn load(&self, id: &str) -> Result<Option<String>, CursorStoreError> {
+        let row = sqlx::query("SELECT cursor FROM meta WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("cursor")))
+    }
+
+    async fn persist(&self, id: &str, cursor: &str, block: u64) -> Result<(), CursorStoreError> {
+        sqlx::query(
+            "INSERT INTO meta (id, cursor, block_number) VALUES ($1, $2, $3) ON CONFLICT (id) DO UPDATE SET cursor = $2, block_number = $3",
+        )
+        .bind(id)
+        .bind(cursor)
+        .bind(block.to_string())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Stores each cursor in `<dir>/<id>.cursor`. Writes go through a temporary
+/// file and a rename so a crash never leaves a truncated cursor behind.
+#[derive(Clone, Debug)]
+pub struct FileCursorStore {
+    dir: PathBuf,
+}
+
+impl FileCursorStore {
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, CursorStoreError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(FileCursorStore { dir })
+    }
+
+    fn path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.cursor", id))
+    }
+}
+
+impl CursorStore for FileCursorStore {
+    async fn load(&self, id: &str) -> Result<Option<String>, CursorStoreError> {
+        match tokio::fs::read_to_string(self.path(id)).await {
+            Ok(contents) => Ok(contents.lines().next().map(|cursor| cursor.to_string())),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    async fn persist(&self, id: &str, cursor: &str, block: u64) -> Result<(), CursorStoreError> {
+        let path = self.path(id);
+        let tmp_path = path.with_extension("cursor.tmp");
+
+        tokio::fs::write(&tmp_path, format!("{}\n{}\n", cursor, block)).await?;
+        tokio::fs::rename(&tmp_path, &path).await?;
+
+        Ok(())
+    }
+}
+
+/// Keeps cursors in memory. Useful for tests and one-off runs that should
+/// always start from the configured start block.
+#[derive(Debug, Default)]
+pub struct InMemoryCursorStore {
+    cursors: Mutex<HashMap<String, (String, u64)>>,
+}
+
+impl InMemoryCursorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the block number stored alongside the cursor for `id`.
+    pub fn block(&self, id: &str) -> Option<u64> {
+        self.cursors
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|(_, block)| *block)
+    }
+}
+
+impl CursorStore for InMemoryCursorStore {
+    async fn load(&self, id: &str) -> Result<Option<String>, CursorStoreError> {
+        Ok(self
+            .cursors
+            .lock()
+            .unwrap()
+            .get(id)
+            .map(|(cursor, _)| cursor.clone()))
+    }
+
+    async fn persist(&self, id: &str, cursor: &str, block: u64) -> Result<(), CursorStoreError> {
+        self.cursors
+            .lock()
+            .unwrap()
+            .insert(id.to_string(), (cursor.to_string(), block));
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_memory_store_round_trip() {
+        let store = InMemoryCursorStore::new();
+
+        assert_eq!(store.load("sink").await.unwrap(), None);
+
+        store.persist("sink", "cursor-1", 1).await.unwrap();
+        store.persist("sink", "cursor-2", 2).await.unwrap();
+
+        assert_eq!(
+            store.load("sink").await.unwrap(),
+            Some("cursor-2".to_string())
+        );
+        assert_eq!(store.block("sink"), Some(2));
+        assert_eq!(store.load("other").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_round_trip() {
+        let dir = std::env::temp_dir().join(format!("stream-cursor-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = FileCursorStore::new(&dir).unwrap();
+
+        assert_eq!(store.load("sink").await.unwrap(), None);
+
+        store.persist("sink", "cursor-1", 1).await.unwrap();
+        store.persist("sink", "cursor-2", 2).await.unwrap();
+
+        assert_eq!(
+            store.load("sink").await.unwrap(),
+            Some("cursor-2".to_string())
+        );
+        assert!(!dir.join("sink.cursor.tmp").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
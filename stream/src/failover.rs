@@ -0,0 +1,91 @@
+use anyhow::{Error, format_err};
+
+/// Ordered list of substreams endpoints the sink runners fail over between.
+///
+/// Endpoints are configured as a comma-separated list, e.g.
+/// `SUBSTREAMS_ENDPOINT="https://a.example:443,https://b.example:443"`. The
+/// runner starts on the first endpoint and moves to the next one, wrapping
+/// around, whenever the active endpoint errors or stalls.
+#[derive(Clone, Debug)]
+pub struct EndpointList {
+    urls: Vec<String>,
+    current: usize,
+}
+
+impl EndpointList {
+    pub fn parse(value: &str) -> Result<Self, Error> {
+        let urls: Vec<String> = value
+            .split(',')
+            .map(|url| url.trim())
+            .filter(|url| !url.is_empty())
+            .map(|url| url.to_string())
+            .collect();
+
+        if urls.is_empty() {
+            return Err(format_err!("no substreams endpoint configured"));
+        }
+
+        Ok(EndpointList { urls, current: 0 })
+    }
+
+    pub fn current(&self) -> &str {
+        &self.urls[self.current]
+    }
+
+    pub fn len(&self) -> usize {
+        self.urls.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+
+    /// Moves to the next endpoint in the list and returns it.
+    pub fn advance(&mut self) -> &str {
+        self.current = (self.current + 1) % self.urls.len();
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_single_endpoint() {
+        let endpoints = EndpointList::parse("https://a.example:443").unwrap();
+
+        assert_eq!(endpoints.len(), 1);
+        assert_eq!(endpoints.current(), "https://a.example:443");
+    }
+
+    #[test]
+    fn test_parse_trims_and_skips_empty_entries() {
+        let endpoints =
+            EndpointList::parse(" https://a.example:443 ,, https://b.example:443,").unwrap();
+
+        assert_eq!(endpoints.len(), 2);
+        assert_eq!(endpoints.current(), "https://a.example:443");
+    }
+
+    #[test]
+    fn test_parse_rejects_empty_value() {
+        assert!(EndpointList::parse(" , ").is_err());
+    }
+
+    #[test]
+    fn test_advance_wraps_around() {
+        let mut endpoints =
+            EndpointList::parse("https://a.example:443,https://b.example:443").unwrap();
+
+        assert_eq!(endpoints.advance(), "https://b.example:443");
+        assert_eq!(endpoints.advance(), "https://a.example:443");
+    }
+
+    #[test]
+    fn test_advance_single_endpoint_stays() {
+        let mut endpoints = EndpointList::parse("https://a.example:443").unwrap();
+
+        assert_eq!(endpoints.advance(), "https://a.example:443");
+    }
+}
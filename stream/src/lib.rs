@@ -1,7 +1,15 @@
+pub mod archive;
+pub mod backfill;
+pub mod cursor;
+pub mod failover;
+pub mod modules;
 pub mod pb;
+pub mod reconnect;
+pub mod recorder;
 pub mod sink;
 pub mod substreams;
 pub mod substreams_stream;
+pub mod test_source;
 
 pub use sink::{PreprocessedSink, Sink};
 pub mod utils;
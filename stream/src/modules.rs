@@ -0,0 +1,246 @@
+use futures03::future::BoxFuture;
+use std::collections::HashMap;
+use thiserror::Error;
+
+use crate::{
+    Sink,
+    cursor::{CursorStore, CursorStoreError},
+    pb::sf::substreams::rpc::v2::BlockScopedData,
+};
+
+pub type ModuleHandlerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Handles the decoded output of a single substreams module.
+pub trait ModuleHandler: Send + Sync {
+    fn handle<'a>(
+        &'a self,
+        block_data: &'a BlockScopedData,
+        output: &'a prost_types::Any,
+    ) -> BoxFuture<'a, Result<(), ModuleHandlerError>>;
+}
+
+#[derive(Error, Debug)]
+pub enum ModuleRouterError {
+    #[error("Module {module} handler error: {source}")]
+    Handler {
+        module: String,
+        source: ModuleHandlerError,
+    },
+
+    #[error("Module router error: {0}")]
+    Cursor(#[from] CursorStoreError),
+}
+
+/// Consumes several substreams modules over one connection and dispatches
+/// each module's output to its own handler.
+///
+/// The stream is requested for a single output module. When handlers are
+/// registered for other modules the router switches the connection to
+/// development mode, in which substreams also sends the outputs of the output
+/// module's ancestors in `debug_map_outputs`. Those modules therefore need to
+/// be inputs, direct or transitive, of the requested output module.
+pub struct ModuleRouter<C: CursorStore> {
+    handlers: HashMap<String, Box<dyn ModuleHandler>>,
+    cursor_id: String,
+    cursor_store: C,
+}
+
+impl<C: CursorStore> ModuleRouter<C> {
+    pub fn new(cursor_id: &str, cursor_store: C) -> Self {
+        ModuleRouter {
+            handlers: HashMap::new(),
+            cursor_id: cursor_id.to_string(),
+            cursor_store,
+        }
+    }
+
+    pub fn register(mut self, module_name: &str, handler: impl ModuleHandler + 'static) -> Self {
+        self.handlers
+            .insert(module_name.to_string(), Box::new(handler));
+        self
+    }
+
+    /// Runs the handler of every module present in the block, the output
+    /// module first followed by the debug outputs in the order received.
+    /// Outputs of modules without a handler are ignored.
+    pub async fn dispatch(&self, block_data: &BlockScopedData) -> Result<(), ModuleRouterError> {
+        let outputs = block_data
+            .output
+            .iter()
+            .chain(block_data.debug_map_outputs.iter());
+
+        for module_output in outputs {
+            let (Some(handler), Some(output)) = (
+                self.handlers.get(&module_output.name),
+                module_output.map_output.as_ref(),
+            ) else {
+                continue;
+            };
+
+            handler.handle(block_data, output).await.map_err(|source| {
+                ModuleRouterError::Handler {
+                    module: module_output.name.clone(),
+                    source,
+                }
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<C: CursorStore> Sink<()> for ModuleRouter<C> {
+    type Error = ModuleRouterError;
+
+    fn production_mode(&self) -> bool {
+        self.handlers.len() <= 1
+    }
+
+    async fn process_block_scoped_data(
+        &self,
+        block_data: &BlockScopedData,
+    ) -> Result<(), Self::Error> {
+        self.dispatch(block_data).await
+    }
+
+    async fn persist_cursor(&self, cursor: String, block: u64) -> Result<(), Self::Error> {
+        Ok(self
+            .cursor_store
+            .persist(&self.cursor_id, &cursor, block)
+            .await?)
+    }
+
+    async fn load_persisted_cursor(&self) -> Result<Option<String>, Self::Error> {
+        Ok(self.cursor_store.load(&self.cursor_id).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cursor::InMemoryCursorStore, pb::sf::substreams::rpc::v2::MapModuleOutput,
+        test_source::fixture_block,
+    };
+    use std::sync::{Arc, Mutex};
+
+    type Calls = Arc<Mutex<Vec<(String, Vec<u8>)>>>;
+
+    struct RecordingHandler {
+        calls: Calls,
+        label: &'static str,
+    }
+
+    impl ModuleHandler for RecordingHandler {
+        fn handle<'a>(
+            &'a self,
+            _block_data: &'a BlockScopedData,
+            output: &'a prost_types::Any,
+        ) -> BoxFuture<'a, Result<(), ModuleHandlerError>> {
+            Box::pin(async move {
+                self.calls
+                    .lock()
+                    .unwrap()
+                    .push((self.label.to_string(), output.value.clone()));
+                Ok(())
+            })
+        }
+    }
+
+    struct FailingHandler;
+
+    impl ModuleHandler for FailingHandler {
+        fn handle<'a>(
+            &'a self,
+            _block_data: &'a BlockScopedData,
+            _output: &'a prost_types::Any,
+        ) -> BoxFuture<'a, Result<(), ModuleHandlerError>> {
+            Box::pin(async { Err("boom".into()) })
+        }
+    }
+
+    fn block_with_debug_outputs() -> BlockScopedData {
+        let mut block = fixture_block(1, 1_700_000_000, "geo_out", "type", vec![1]);
+        block.debug_map_outputs = vec![
+            MapModuleOutput {
+                name: "actions_out".to_string(),
+                map_output: Some(prost_types::Any {
+                    type_url: "type".to_string(),
+                    value: vec![2],
+                }),
+                debug_info: None,
+            },
+            MapModuleOutput {
+                name: "unhandled_out".to_string(),
+                map_output: Some(prost_types::Any {
+                    type_url: "type".to_string(),
+                    value: vec![3],
+                }),
+                debug_info: None,
+            },
+        ];
+        block
+    }
+
+    #[tokio::test]
+    async fn test_dispatches_each_module_to_its_handler() {
+        let calls: Calls = Arc::new(Mutex::new(vec![]));
+        let router = ModuleRouter::new("router", InMemoryCursorStore::new())
+            .register(
+                "geo_out",
+                RecordingHandler {
+                    calls: calls.clone(),
+                    label: "geo",
+                },
+            )
+            .register(
+                "actions_out",
+                RecordingHandler {
+                    calls: calls.clone(),
+                    label: "actions",
+                },
+            );
+
+        router.dispatch(&block_with_debug_outputs()).await.unwrap();
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec![
+                ("geo".to_string(), vec![1]),
+                ("actions".to_string(), vec![2])
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_production_mode_only_for_single_module() {
+        let calls: Calls = Arc::new(Mutex::new(vec![]));
+        let router = ModuleRouter::new("router", InMemoryCursorStore::new()).register(
+            "geo_out",
+            RecordingHandler {
+                calls: calls.clone(),
+                label: "geo",
+            },
+        );
+        assert!(router.production_mode());
+
+        let router = router.register("actions_out", FailingHandler);
+        assert!(!router.production_mode());
+    }
+
+    #[tokio::test]
+    async fn test_handler_error_names_module() {
+        let router = ModuleRouter::new("router", InMemoryCursorStore::new())
+            .register("actions_out", FailingHandler);
+
+        let error = router
+            .dispatch(&block_with_debug_outputs())
+            .await
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            ModuleRouterError::Handler { ref module, .. } if module == "actions_out"
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+use std::{
+    env,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+
+static RECONNECT_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// Total number of times a sink runner has reconnected to a substreams
+/// endpoint since the process started.
+pub fn reconnect_count() -> u64 {
+    RECONNECT_COUNT.load(Ordering::Relaxed)
+}
+
+/// Controls how `Sink::run` and `PreprocessedSink::run` recover when the
+/// substreams connection terminates with an error.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectConfig {
+    /// Maximum number of consecutive reconnect attempts before the runner
+    /// gives up and returns the error. `None` retries forever.
+    pub max_retries: Option<u32>,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Treat the endpoint as lagging and fail over when no block arrives
+    /// within this window. `None` disables the check, which is the default as
+    /// the first block of a large backfill can take a long time to arrive.
+    pub stall_timeout: Option<Duration>,
+}
+
+impl Default for ReconnectConfig {
+    fn default() -> Self {
+        ReconnectConfig {
+            max_retries: Some(10),
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(45),
+            stall_timeout: None,
+        }
+    }
+}
+
+impl ReconnectConfig {
+    /// Reads `SUBSTREAMS_MAX_RETRIES` and `SUBSTREAMS_STALL_TIMEOUT_SECS` from
+    /// the environment. The max retries value is either a number of attempts or
+    /// `unlimited`. Falls back to the defaults when the variables are unset or
+    /// can't be parsed.
+    pub fn from_env() -> Self {
+        let mut config = ReconnectConfig::default();
+
+        if let Ok(value) = env::var("SUBSTREAMS_MAX_RETRIES") {
+            if value.eq_ignore_ascii_case("unlimited") {
+                config.max_retries = None;
+            } else if let Ok(max_retries) = value.parse::<u32>() {
+                config.max_retries = Some(max_retries);
+            }
+        }
+
+        if let Some(seconds) = env::var("SUBSTREAMS_STALL_TIMEOUT_SECS")
+            .ok()
+            .and_then(|value| value.parse::<u64>().ok())
+        {
+            config.stall_timeout = Some(Duration::from_secs(seconds));
+        }
+
+        config
+    }
+}
+
+/// Tracks consecutive reconnect attempts and computes the exponential backoff
+/// between them. Call `reset` once the stream makes progress again.
+pub(crate) struct Reconnector {
+    config: ReconnectConfig,
+    attempts: u32,
+}
+
+impl Reconnector {
+    pub(crate) fn new(config: ReconnectConfig) -> Self {
+        Reconnector {
+            config,
+            attempts: 0,
+        }
+    }
+
+    pub(crate) fn stall_timeout(&self) -> Option<Duration> {
+        self.config.stall_timeout
+    }
+
+    pub(crate) fn attempts(&self) -> u32 {
+        self.attempts
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.attempts = 0;
+    }
+
+    /// Returns how long to wait before the next attempt, or `None` when the
+    /// configured number of retries has been exhausted.
+    pub(crate) fn next_delay(&mut self) -> Option<Duration> {
+        if self
+            .config
+            .max_retries
+            .is_some_and(|max_retries| self.attempts >= max_retries)
+        {
+            return None;
+        }
+
+        let delay = self
+            .config
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(self.attempts))
+            .min(self.config.max_backoff);
+
+        self.attempts += 1;
+        RECONNECT_COUNT.fetch_add(1, Ordering::Relaxed);
+
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_retries: Option<u32>) -> ReconnectConfig {
+        ReconnectConfig {
+            max_retries,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            stall_timeout: None,
+        }
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let mut reconnector = Reconnector::new(config(None));
+
+        let delays: Vec<u128> = (0..5)
+            .map(|_| reconnector.next_delay().unwrap().as_millis())
+            .collect();
+
+        assert_eq!(delays, vec![100, 200, 400, 500, 500]);
+    }
+
+    #[test]
+    fn test_stops_after_max_retries() {
+        let mut reconnector = Reconnector::new(config(Some(2)));
+
+        assert!(reconnector.next_delay().is_some());
+        assert!(reconnector.next_delay().is_some());
+        assert!(reconnector.next_delay().is_none());
+        assert_eq!(reconnector.attempts(), 2);
+    }
+
+    #[test]
+    fn test_reset_restarts_backoff() {
+        let mut reconnector = Reconnector::new(config(Some(2)));
+
+        reconnector.next_delay();
+        reconnector.next_delay();
+        reconnector.reset();
+
+        assert_eq!(reconnector.next_delay(), Some(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn test_reconnects_are_counted() {
+        let before = reconnect_count();
+        let mut reconnector = Reconnector::new(config(None));
+
+        reconnector.next_delay();
+
+        assert!(reconnect_count() > before);
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::{Context, Error};
+use prost::Message;
+use std::{env, sync::Arc};
+
+use crate::{
+    archive::{ArchiveStore, block_key, file::DirectoryStore, parse_block_key, s3::S3Store},
+    pb::sf::substreams::rpc::v2::BlockScopedData,
+};
+
+const COMPRESSION_LEVEL: i32 = 3;
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Archives every `BlockScopedData` received by the sink runners so indexing
+/// bugs can be reproduced deterministically by replaying the archive.
+///
+/// Each block is protobuf-encoded, zstd-compressed and stored under a key
+/// named after the block number, in a directory or an S3 compatible bucket.
+/// Undo signals are not archived.
+#[derive(Clone)]
+pub struct BlockRecorder {
+    store: Arc<dyn ArchiveStore>,
+}
+
+impl BlockRecorder {
+    pub fn new(store: Arc<dyn ArchiveStore>) -> Self {
+        BlockRecorder { store }
+    }
+
+    /// Enables recording when `BLOCK_ARCHIVE_DIR` is set, to a directory or
+    /// an `s3://bucket/prefix` URL. S3 recordings are signed with
+    /// `ARCHIVE_ACCESS_KEY_ID` and `ARCHIVE_SECRET_ACCESS_KEY`, and sent to
+    /// `ARCHIVE_ENDPOINT` or AWS in `ARCHIVE_REGION`, like the indexer's
+    /// archive.
+    pub fn from_env() -> Result<Option<Self>, Error> {
+        match env::var("BLOCK_ARCHIVE_DIR") {
+            Ok(location) if !location.is_empty() => {
+                Ok(Some(BlockRecorder::new(open_store(&location)?)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Stores a block and returns the key it was stored under.
+    pub async fn record(&self, block: &BlockScopedData) -> Result<String, Error> {
+        let block_number = block.clock.as_ref().map(|clock| clock.number).unwrap_or(0);
+        let key = block_key(block_number);
+
+        let compressed = zstd::encode_all(block.encode_to_vec().as_slice(), COMPRESSION_LEVEL)
+            .context("compress block")?;
+
+        self.store
+            .put(&key, compressed)
+            .await
+            .with_context(|| format!("write block archive '{}'", key))?;
+
+        Ok(key)
+    }
+}
+
+/// Opens the directory or `s3://bucket/prefix` URL at `location`, reading
+/// the S3 settings from the environment.
+pub fn open_store(location: &str) -> Result<Arc<dyn ArchiveStore>, Error> {
+    let Some(bucket_location) = location.strip_prefix("s3://") else {
+        let dir = location.strip_prefix("file://").unwrap_or(location);
+        let store = DirectoryStore::new(dir)
+            .with_context(|| format!("create block archive directory '{}'", dir))?;
+        return Ok(Arc::new(store));
+    };
+
+    let setting = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    let (bucket, prefix) = bucket_location
+        .split_once('/')
+        .unwrap_or((bucket_location, ""));
+    let access_key_id = setting("ARCHIVE_ACCESS_KEY_ID")
+        .context("ARCHIVE_ACCESS_KEY_ID must be set to record blocks to S3")?;
+    let secret_access_key = setting("ARCHIVE_SECRET_ACCESS_KEY")
+        .context("ARCHIVE_SECRET_ACCESS_KEY must be set to record blocks to S3")?;
+
+    let store = S3Store::new(
+        setting("ARCHIVE_ENDPOINT").as_deref(),
+        setting("ARCHIVE_REGION")
+            .as_deref()
+            .unwrap_or(DEFAULT_REGION),
+        bucket,
+        prefix,
+        &access_key_id,
+        &secret_access_key,
+    )?;
+
+    Ok(Arc::new(store))
+}
+
+/// Loads every archived block in the store, ordered by block number.
+pub async fn load_archive(store: &dyn ArchiveStore) -> Result<Vec<BlockScopedData>, Error> {
+    let mut blocks: Vec<(u64, String)> = store
+        .list()
+        .await
+        .context("list block archive")?
+        .into_iter()
+        .filter_map(|key| parse_block_key(&key).map(|number| (number, key)))
+        .collect();
+
+    blocks.sort();
+
+    let mut decoded = Vec::with_capacity(blocks.len());
+    for (_, key) in blocks {
+        let compressed = store
+            .get(&key)
+            .await
+            .with_context(|| format!("read block archive '{}'", key))?
+            .with_context(|| format!("block archive '{}' was removed", key))?;
+        let bytes = zstd::decode_all(compressed.as_slice())
+            .with_context(|| format!("decompress block archive '{}'", key))?;
+        decoded.push(
+            BlockScopedData::decode(bytes.as_slice())
+                .with_context(|| format!("decode block archive '{}'", key))?,
+        );
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_source::fixture_block;
+
+    #[tokio::test]
+    async fn test_record_and_load_archive() {
+        let dir = std::env::temp_dir().join(format!("stream-recorder-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let store = open_store(dir.to_str().unwrap()).unwrap();
+        let recorder = BlockRecorder::new(store.clone());
+
+        let second = fixture_block(20, 1_700_000_020, "geo_out", "type", vec![9; 1024]);
+        let first = fixture_block(10, 1_700_000_010, "geo_out", "type", vec![7; 1024]);
+
+        recorder.record(&second).await.unwrap();
+        let key = recorder.record(&first).await.unwrap();
+
+        assert!(std::fs::metadata(dir.join(&key)).unwrap().len() < 1024);
+
+        let blocks = load_archive(store.as_ref()).await.unwrap();
+        assert_eq!(blocks, vec![first, second]);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
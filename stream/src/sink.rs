@@ -5,15 +5,20 @@ use prost::Message;
 use regex::Regex;
 use semver::Version;
 
-use std::{env, process::exit, sync::Arc};
+use std::{env, sync::Arc, time::Duration};
+use tokio::time::{sleep, timeout};
 
 use crate::{
+    backfill::{BackfillConfig, backfill_segments},
+    failover::EndpointList,
     pb::sf::substreams::{
         rpc::v2::{BlockScopedData, BlockUndoSignal},
         v1::Package,
     },
+    reconnect::{ReconnectConfig, Reconnector, reconnect_count},
+    recorder::BlockRecorder,
     substreams::SubstreamsEndpoint,
-    substreams_stream::{BlockResponse, SubstreamsStream},
+    substreams_stream::{BlockResponse, StreamOptions, SubstreamsStream},
 };
 
 pub trait PreprocessedSink<P: Send>: Send + Sync {
@@ -67,6 +72,26 @@ pub trait PreprocessedSink<P: Send>: Send + Sync {
         async { Ok(None) }
     }
 
+    /// Whether `run` requests the stream in production mode. Sinks consuming
+    /// more than one module per connection need development mode.
+    fn production_mode(&self) -> bool {
+        true
+    }
+
+    /// Reconnect behaviour used by `run` when the stream terminates with an
+    /// error. Reads `SUBSTREAMS_MAX_RETRIES` by default.
+    fn reconnect_config(&self) -> ReconnectConfig {
+        ReconnectConfig::from_env()
+    }
+
+    /// The token used to authenticate with the substreams endpoint. Reads
+    /// `SUBSTREAMS_API_TOKEN` by default.
+    fn api_token(&self) -> Option<String> {
+        env::var("SUBSTREAMS_API_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+    }
+
     fn run(
         &self,
         endpoint_url: &str,
@@ -76,59 +101,99 @@ pub trait PreprocessedSink<P: Send>: Send + Sync {
         end_block: u64,
     ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send {
         async move {
-            let token_env = env::var("SUBSTREAMS_API_TOKEN").unwrap_or("".to_string());
-            let mut token: Option<String> = None;
-            if !token_env.is_empty() {
-                token = Some(token_env);
-            }
+            let token = self.api_token();
 
-            let cursor: Option<String> = self.load_persisted_cursor().await?;
+            let mut cursor: Option<String> = self.load_persisted_cursor().await?;
 
             println!("Processing block {}", spkg_file);
 
             let package = read_package(spkg_file).await.unwrap();
+            let recorder = BlockRecorder::from_env()?;
 
-            let endpoint = Arc::new(SubstreamsEndpoint::new(&endpoint_url, token).await?);
-
-            let mut stream = SubstreamsStream::new(
-                endpoint.clone(),
-                cursor,
-                package.modules.clone(),
-                module_name.to_string(),
-                start_block,
-                end_block,
-            );
+            let mut endpoints = EndpointList::parse(endpoint_url)?;
+            let mut reconnector = Reconnector::new(self.reconnect_config());
 
             loop {
-                match stream.next().await {
-                    None => {
-                        println!("Stream consumed");
-                        break;
-                    }
-                    Some(Ok(BlockResponse::New(data))) => {
-                        let decoded_data = self.preprocess_block_scoped_data(&data).await?;
-                        self.process_block_scoped_data(&data, decoded_data).await?;
-                        self.persist_cursor(data.cursor, data.clock.unwrap().number)
-                            .await?;
+                let err = match SubstreamsEndpoint::new(endpoints.current(), token.clone()).await {
+                    Ok(endpoint) => {
+                        // Connection errors are surfaced straight away so the runner can
+                        // fail over to the next endpoint with the latest cursor.
+                        let mut stream = SubstreamsStream::with_options(
+                            Arc::new(endpoint),
+                            cursor.clone(),
+                            package.modules.clone(),
+                            module_name.to_string(),
+                            start_block,
+                            end_block,
+                            StreamOptions {
+                                max_retries: Some(0),
+                                production_mode: self.production_mode(),
+                            },
+                        );
+
+                        loop {
+                            match next_response(&mut stream, reconnector.stall_timeout()).await {
+                                None => {
+                                    println!("Stream consumed");
+                                    return Ok(());
+                                }
+                                Some(Ok(BlockResponse::New(data))) => {
+                                    if let Some(recorder) = &recorder {
+                                        recorder.record(&data).await?;
+                                    }
+                                    let decoded_data =
+                                        self.preprocess_block_scoped_data(&data).await?;
+                                    self.process_block_scoped_data(&data, decoded_data).await?;
+                                    let block_cursor = data.cursor.clone();
+                                    self.persist_cursor(
+                                        block_cursor.clone(),
+                                        data.clock.unwrap().number,
+                                    )
+                                    .await?;
+                                    cursor = Some(block_cursor);
+                                    reconnector.reset();
+                                }
+                                Some(Ok(BlockResponse::Undo(undo_signal))) => {
+                                    self.process_block_undo_signal(&undo_signal)?;
+                                    let block_cursor = undo_signal.last_valid_cursor.clone();
+                                    self.persist_cursor(
+                                        block_cursor.clone(),
+                                        undo_signal.last_valid_block.unwrap().number,
+                                    )
+                                    .await?;
+                                    cursor = Some(block_cursor);
+                                    reconnector.reset();
+                                }
+                                Some(Err(err)) => break err,
+                            }
+                        }
                     }
-                    Some(Ok(BlockResponse::Undo(undo_signal))) => {
-                        self.process_block_undo_signal(&undo_signal)?;
-                        self.persist_cursor(
-                            undo_signal.last_valid_cursor,
-                            undo_signal.last_valid_block.unwrap().number,
-                        )
-                        .await?;
+                    Err(err) => err,
+                };
+
+                match reconnector.next_delay() {
+                    Some(delay) => {
+                        let failed_endpoint = endpoints.current().to_string();
+                        println!(
+                            "Stream terminated with error on {}, reconnecting to {} in {:?} (attempt {}, total reconnects {}): {:?}",
+                            failed_endpoint,
+                            endpoints.advance(),
+                            delay,
+                            reconnector.attempts(),
+                            reconnect_count(),
+                            err
+                        );
+                        sleep(delay).await;
                     }
-                    Some(Err(err)) => {
-                        println!();
-                        println!("Stream terminated with error");
-                        println!("{:?}", err);
-                        exit(1);
+                    None => {
+                        println!(
+                            "Stream terminated with error after {} reconnect attempts",
+                            reconnector.attempts()
+                        );
+                        return Err(err);
                     }
                 }
             }
-
-            Ok(())
         }
     }
 }
@@ -178,71 +243,216 @@ pub trait Sink<T: Send>: Send + Sync {
         async { Ok(None) }
     }
 
-    fn run(
+    /// Syncs the historical range `[start_block, config.end_block)` over several
+    /// parallel connections, feeding blocks to the sink in order. Skipped when
+    /// a cursor has already been persisted, in which case `run` resumes from it.
+    fn backfill(
         &self,
         endpoint_url: &str,
         spkg_file: &str,
         module_name: &str,
         start_block: i64,
-        end_block: u64,
+        config: BackfillConfig,
     ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send {
         async move {
-            let token_env = env::var("SUBSTREAMS_API_TOKEN").unwrap_or("".to_string());
-            let mut token: Option<String> = None;
-            if !token_env.is_empty() {
-                token = Some(token_env);
+            if self.load_persisted_cursor().await?.is_some() {
+                println!("Persisted cursor found, skipping backfill");
+                return Ok(());
             }
 
-            let cursor: Option<String> = self.load_persisted_cursor().await?;
+            let token = self.api_token();
 
-            println!("Processing block {}", spkg_file);
+            println!(
+                "Backfilling blocks {} to {} ({} workers, {} blocks per segment)",
+                start_block, config.end_block, config.workers, config.segment_size
+            );
 
             let package = read_package(spkg_file).await.unwrap();
+            let recorder = BlockRecorder::from_env()?;
+            let endpoints = EndpointList::parse(endpoint_url)?;
+            let endpoint = Arc::new(SubstreamsEndpoint::new(endpoints.current(), token).await?);
 
-            let endpoint = Arc::new(SubstreamsEndpoint::new(&endpoint_url, token).await?);
-
-            let mut stream = SubstreamsStream::new(
-                endpoint.clone(),
-                cursor,
+            let mut segments = Box::pin(backfill_segments(
+                endpoint,
                 package.modules.clone(),
                 module_name.to_string(),
                 start_block,
-                end_block,
-            );
+                &config,
+                self.production_mode(),
+            ));
+
+            while let Some(segment) = segments.next().await {
+                for response in segment? {
+                    match response {
+                        BlockResponse::New(data) => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record(&data).await?;
+                            }
+                            self.process_block_scoped_data(&data).await?;
+                            self.persist_cursor(data.cursor, data.clock.unwrap().number)
+                                .await?;
+                        }
+                        BlockResponse::Undo(undo_signal) => {
+                            self.process_block_undo_signal(&undo_signal)?;
+                            self.persist_cursor(
+                                undo_signal.last_valid_cursor,
+                                undo_signal.last_valid_block.unwrap().number,
+                            )
+                            .await?;
+                        }
+                    }
+                }
+            }
+
+            println!("Backfill complete");
+
+            Ok(())
+        }
+    }
+
+    /// Whether `run` requests the stream in production mode. Sinks consuming
+    /// more than one module per connection need development mode.
+    fn production_mode(&self) -> bool {
+        true
+    }
+
+    /// Reconnect behaviour used by `run` when the stream terminates with an
+    /// error. Reads `SUBSTREAMS_MAX_RETRIES` by default.
+    fn reconnect_config(&self) -> ReconnectConfig {
+        ReconnectConfig::from_env()
+    }
+
+    /// The token used to authenticate with the substreams endpoint. Reads
+    /// `SUBSTREAMS_API_TOKEN` by default.
+    fn api_token(&self) -> Option<String> {
+        env::var("SUBSTREAMS_API_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty())
+    }
+
+    fn run(
+        &self,
+        endpoint_url: &str,
+        spkg_file: &str,
+        module_name: &str,
+        start_block: i64,
+        end_block: u64,
+    ) -> impl std::future::Future<Output = Result<(), anyhow::Error>> + Send {
+        async move {
+            let token = self.api_token();
+
+            let mut cursor: Option<String> = self.load_persisted_cursor().await?;
+
+            println!("Processing block {}", spkg_file);
+
+            let package = read_package(spkg_file).await.unwrap();
+            let recorder = BlockRecorder::from_env()?;
+
+            let mut endpoints = EndpointList::parse(endpoint_url)?;
+            let mut reconnector = Reconnector::new(self.reconnect_config());
 
             loop {
-                match stream.next().await {
-                    None => {
-                        println!("Stream consumed");
-                        break;
+                let err = match SubstreamsEndpoint::new(endpoints.current(), token.clone()).await {
+                    Ok(endpoint) => {
+                        // Connection errors are surfaced straight away so the runner can
+                        // fail over to the next endpoint with the latest cursor.
+                        let mut stream = SubstreamsStream::with_options(
+                            Arc::new(endpoint),
+                            cursor.clone(),
+                            package.modules.clone(),
+                            module_name.to_string(),
+                            start_block,
+                            end_block,
+                            StreamOptions {
+                                max_retries: Some(0),
+                                production_mode: self.production_mode(),
+                            },
+                        );
+
+                        loop {
+                            match next_response(&mut stream, reconnector.stall_timeout()).await {
+                                None => {
+                                    println!("Stream consumed");
+                                    return Ok(());
+                                }
+                                Some(Ok(BlockResponse::New(data))) => {
+                                    if let Some(recorder) = &recorder {
+                                        recorder.record(&data).await?;
+                                    }
+                                    self.process_block_scoped_data(&data).await?;
+                                    let block_cursor = data.cursor.clone();
+                                    self.persist_cursor(
+                                        block_cursor.clone(),
+                                        data.clock.unwrap().number,
+                                    )
+                                    .await?;
+                                    cursor = Some(block_cursor);
+                                    reconnector.reset();
+                                }
+                                Some(Ok(BlockResponse::Undo(undo_signal))) => {
+                                    self.process_block_undo_signal(&undo_signal)?;
+                                    let block_cursor = undo_signal.last_valid_cursor.clone();
+                                    self.persist_cursor(
+                                        block_cursor.clone(),
+                                        undo_signal.last_valid_block.unwrap().number,
+                                    )
+                                    .await?;
+                                    cursor = Some(block_cursor);
+                                    reconnector.reset();
+                                }
+                                Some(Err(err)) => break err,
+                            }
+                        }
                     }
-                    Some(Ok(BlockResponse::New(data))) => {
-                        self.process_block_scoped_data(&data).await?;
-                        self.persist_cursor(data.cursor, data.clock.unwrap().number)
-                            .await?;
-                    }
-                    Some(Ok(BlockResponse::Undo(undo_signal))) => {
-                        self.process_block_undo_signal(&undo_signal)?;
-                        self.persist_cursor(
-                            undo_signal.last_valid_cursor,
-                            undo_signal.last_valid_block.unwrap().number,
-                        )
-                        .await?;
+                    Err(err) => err,
+                };
+
+                match reconnector.next_delay() {
+                    Some(delay) => {
+                        let failed_endpoint = endpoints.current().to_string();
+                        println!(
+                            "Stream terminated with error on {}, reconnecting to {} in {:?} (attempt {}, total reconnects {}): {:?}",
+                            failed_endpoint,
+                            endpoints.advance(),
+                            delay,
+                            reconnector.attempts(),
+                            reconnect_count(),
+                            err
+                        );
+                        sleep(delay).await;
                     }
-                    Some(Err(err)) => {
-                        println!();
-                        println!("Stream terminated with error");
-                        println!("{:?}", err);
-                        exit(1);
+                    None => {
+                        println!(
+                            "Stream terminated with error after {} reconnect attempts",
+                            reconnector.attempts()
+                        );
+                        return Err(err);
                     }
                 }
             }
-
-            Ok(())
         }
     }
 }
 
+/// Waits for the next message from the stream. When a stall timeout is set and
+/// nothing arrives in time the endpoint is treated as lagging and an error is
+/// returned so the runner fails over.
+async fn next_response(
+    stream: &mut SubstreamsStream,
+    stall_timeout: Option<Duration>,
+) -> Option<Result<BlockResponse, anyhow::Error>> {
+    match stall_timeout {
+        Some(duration) => match timeout(duration, stream.next()).await {
+            Ok(response) => response,
+            Err(_) => Some(Err(format_err!(
+                "no block received within {:?}, endpoint is lagging",
+                duration
+            ))),
+        },
+        None => stream.next().await,
+    }
+}
+
 lazy_static! {
     static ref MODULE_NAME_REGEXP: Regex = Regex::new(r"^([a-zA-Z][a-zA-Z0-9_-]{0,63})$").unwrap();
 }
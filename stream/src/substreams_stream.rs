@@ -22,6 +22,27 @@ pub enum BlockResponse {
     Undo(BlockUndoSignal),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct StreamOptions {
+    /// Once this many consecutive reconnects have failed the last error is
+    /// yielded instead of retrying forever. This lets callers handle the
+    /// failure themselves, e.g. by failing over to another endpoint.
+    pub max_retries: Option<u32>,
+    /// Development mode (`false`) additionally sends the outputs of the output
+    /// module's ancestors in `debug_map_outputs`, at the cost of parallel
+    /// processing.
+    pub production_mode: bool,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        StreamOptions {
+            max_retries: None,
+            production_mode: true,
+        }
+    }
+}
+
 pub struct SubstreamsStream {
     stream: Pin<Box<dyn Stream<Item = Result<BlockResponse, Error>> + Send>>,
 }
@@ -34,6 +55,26 @@ impl SubstreamsStream {
         output_module_name: String,
         start_block: i64,
         end_block: u64,
+    ) -> Self {
+        Self::with_options(
+            endpoint,
+            cursor,
+            modules,
+            output_module_name,
+            start_block,
+            end_block,
+            StreamOptions::default(),
+        )
+    }
+
+    pub fn with_options(
+        endpoint: Arc<SubstreamsEndpoint>,
+        cursor: Option<String>,
+        modules: Option<Modules>,
+        output_module_name: String,
+        start_block: i64,
+        end_block: u64,
+        options: StreamOptions,
     ) -> Self {
         SubstreamsStream {
             stream: Box::pin(stream_blocks(
@@ -43,6 +84,7 @@ impl SubstreamsStream {
                 output_module_name,
                 start_block,
                 end_block,
+                options,
             )),
         }
     }
@@ -56,9 +98,12 @@ fn stream_blocks(
     output_module_name: String,
     start_block_num: i64,
     stop_block_num: u64,
+    options: StreamOptions,
 ) -> impl Stream<Item = Result<BlockResponse, Error>> {
     let mut latest_cursor = cursor.unwrap_or_else(|| "".to_string());
     let mut backoff = ExponentialBackoff::from_millis(500).max_delay(Duration::from_secs(45));
+    let mut retries: u32 = 0;
+    let max_retries = options.max_retries;
     let mut last_progress_report = Instant::now();
 
     try_stream! {
@@ -77,11 +122,10 @@ fn stream_blocks(
                 final_blocks_only: false,
                 modules: modules.clone(),
                 output_module: output_module_name.clone(),
-                // There is usually no good reason for you to consume the stream development mode (so switching `true`
-                // to `false`). If you do switch it, be aware that more than one output module will be send back to you,
-                // and the current code in `process_block_scoped_data` (within your 'main.rs' file) expects a single
-                // module.
-                production_mode: true,
+                // Production mode is the default. Development mode is only requested when a sink
+                // consumes more than one module over this connection (see `ModuleRouter`), as the
+                // outputs of the other modules are then sent back in `debug_map_outputs`.
+                production_mode: options.production_mode,
                 debug_initial_store_snapshot_for_modules: vec![],
                 noop_mode: false,
 
@@ -97,6 +141,7 @@ fn stream_blocks(
                             BlockProcessedResult::BlockScopedData(block_scoped_data) => {
                                 // Reset backoff because we got a good value from the stream
                                 backoff = ExponentialBackoff::from_millis(500).max_delay(Duration::from_secs(45));
+                                retries = 0;
 
                                 let cursor = block_scoped_data.cursor.clone();
                                 yield BlockResponse::New(block_scoped_data);
@@ -106,6 +151,7 @@ fn stream_blocks(
                             BlockProcessedResult::BlockUndoSignal(block_undo_signal) => {
                                 // Reset backoff because we got a good value from the stream
                                 backoff = ExponentialBackoff::from_millis(500).max_delay(Duration::from_secs(45));
+                                retries = 0;
 
                                 let cursor = block_undo_signal.last_valid_cursor.clone();
                                 yield BlockResponse::Undo(block_undo_signal);
@@ -121,6 +167,10 @@ fn stream_blocks(
                                 }
 
                                 println!("Received tonic error {:#}", status);
+                                if max_retries.is_some_and(|max_retries| retries >= max_retries) {
+                                    return Err(anyhow::Error::new(status.clone()))?;
+                                }
+
                                 encountered_error = true;
                                 break;
                             },
@@ -138,9 +188,14 @@ fn stream_blocks(
                     // having connection errors.
 
                     println!("Unable to connect to endpoint: {:#}", e);
+                    if max_retries.is_some_and(|max_retries| retries >= max_retries) {
+                        return Err(e)?;
+                    }
                 }
             }
 
+            retries += 1;
+
             // If we reach this point, we must wait a bit before retrying
             if let Some(duration) = backoff.next() {
                 sleep(duration).await
@@ -1,5 +1,5 @@
 use crate::pb::sf::substreams::rpc::v2::BlockScopedData;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 
 pub fn output(block_data: &BlockScopedData) -> &prost_types::Any {
     return block_data
@@ -29,13 +29,19 @@ pub fn block_metadata(block_data: &BlockScopedData) -> BlockMetadata {
     };
 }
 
-pub fn format_drift(block_metadata: &BlockMetadata) -> String {
+/// How far the block is behind the current time, which is how far the
+/// indexer is behind the chain head while it processes the block.
+pub fn drift(block_metadata: &BlockMetadata) -> Duration {
     let now = Utc::now();
     let block_timestamp_seconds: i64 = block_metadata.timestamp.parse().unwrap_or(0);
     let block_datetime = DateTime::from_timestamp(block_timestamp_seconds, 0)
         .unwrap_or_else(|| Utc::now());
-    
-    let drift = now - block_datetime;
+
+    now - block_datetime
+}
+
+pub fn format_drift(block_metadata: &BlockMetadata) -> String {
+    let drift = drift(block_metadata);
     let days = drift.num_days();
     let hours = drift.num_hours() % 24;
     let minutes = drift.num_minutes() % 60;
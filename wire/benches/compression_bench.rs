@@ -1,6 +1,7 @@
 use criterion::{Criterion, black_box, criterion_group, criterion_main};
 use std::fs;
-use wire::compression::decompress_bytes;
+use std::io::Read;
+use wire::compression::{DEFAULT_MAX_DECOMPRESSED_SIZE, decoder, decompress_bytes};
 use wire::deserialize::deserialize;
 
 fn bench_decompress_ops_json(c: &mut Criterion) {
@@ -107,6 +108,55 @@ fn bench_with_memory_allocation(c: &mut Criterion) {
     });
 }
 
+fn bench_streaming_decompress(c: &mut Criterion) {
+    let compressed_data =
+        fs::read("data/ops.json.zst").expect("Failed to read ops.json.zst file for benchmarking");
+
+    let mut group = c.benchmark_group("streaming_decompress");
+
+    group.bench_function("decompress_bytes", |b| {
+        b.iter(|| {
+            let result = decompress_bytes(black_box(&compressed_data));
+            black_box(result.expect("Decompression should succeed"))
+        })
+    });
+
+    // Drains the decoder through a fixed buffer, as a streaming parser would,
+    // without allocating the whole decompressed payload
+    group.bench_function("streaming_fixed_buffer", |b| {
+        let mut buffer = [0u8; 16 * 1024];
+        b.iter(|| {
+            let mut reader = decoder(
+                black_box(compressed_data.as_slice()),
+                DEFAULT_MAX_DECOMPRESSED_SIZE,
+            )
+            .expect("Decoder should initialize");
+            while reader
+                .read(&mut buffer)
+                .expect("Decompression should succeed")
+                > 0
+            {}
+            black_box(reader.bytes_read())
+        })
+    });
+
+    group.bench_function("streaming_read_to_end", |b| {
+        b.iter(|| {
+            let mut decompressed = Vec::new();
+            decoder(
+                black_box(compressed_data.as_slice()),
+                DEFAULT_MAX_DECOMPRESSED_SIZE,
+            )
+            .expect("Decoder should initialize")
+            .read_to_end(&mut decompressed)
+            .expect("Decompression should succeed");
+            black_box(decompressed)
+        })
+    });
+
+    group.finish();
+}
+
 fn bench_deserialize_proto(c: &mut Criterion) {
     // Load and decompress the proto data once
     let compressed_proto_data =
@@ -173,6 +223,7 @@ criterion_group!(
     bench_decompress_empty_data,
     bench_decompress_repeated_calls,
     bench_with_memory_allocation,
+    bench_streaming_decompress,
     bench_deserialize_proto,
     bench_decompress_and_deserialize_proto,
     bench_deserialize_proto_repeated,
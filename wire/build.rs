@@ -15,7 +15,10 @@ fn main() -> Result<()> {
 
     // // Generate one file per proto
     // config
-    //     .type_attribute(".", "#[derive(serde::Serialize, serde::Deserialize)]")
+    //     .type_attribute(
+    //         ".",
+    //         "#[cfg_attr(feature = \"serde\", derive(serde::Serialize, serde::Deserialize))]",
+    //     )
     //     .compile_protos(&protos, proto_include)
     //     .expect("Failed to compile protos");
 
@@ -0,0 +1,192 @@
+//! Proptest strategies for well-formed grc20 edits.
+//!
+//! The ops of a generated edit draw their ids from a small pool, so they keep
+//! setting, unsetting and deleting the same entities and relations the way
+//! real edits do, which is what exercises op squashing. Every property an
+//! edit sets values for is created at the start of the edit, and its values
+//! are valid for its data type.
+//!
+//! ```
+//! use proptest::{strategy::Strategy, test_runner::TestRunner};
+//! use wire::{arbitrary::edit, validate_edit};
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&edit(), |edit| {
+//!         assert!(validate_edit(&edit).is_empty());
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use proptest::{collection::vec, prelude::*, sample::select};
+use uuid::Uuid;
+
+use crate::{
+    builder::{EditBuilder, relation, relation_update, value},
+    pb::grc20::{DataType, Edit, Entity, Op, RelationUpdate, UnsetEntityValues, op::Payload},
+};
+
+/// How many entities, properties and relations an edit's ops are drawn from.
+pub const POOL_SIZE: usize = 5;
+
+/// The ids the ops of an edit are drawn from.
+#[derive(Clone, Debug)]
+pub struct IdPool {
+    pub entities: Vec<Uuid>,
+    pub properties: Vec<(Uuid, DataType)>,
+    pub relations: Vec<Uuid>,
+}
+
+impl IdPool {
+    /// The data type of a property of the pool.
+    pub fn data_type(&self, property: Uuid) -> Option<DataType> {
+        self.properties
+            .iter()
+            .find(|(id, _)| *id == property)
+            .map(|(_, data_type)| *data_type)
+    }
+}
+
+pub fn uuid() -> impl Strategy<Value = Uuid> {
+    any::<[u8; 16]>().prop_map(Uuid::from_bytes)
+}
+
+pub fn data_type() -> impl Strategy<Value = DataType> {
+    select(vec![
+        DataType::Text,
+        DataType::Number,
+        DataType::Checkbox,
+        DataType::Time,
+        DataType::Point,
+        DataType::Relation,
+    ])
+}
+
+pub fn id_pool() -> impl Strategy<Value = IdPool> {
+    (
+        vec(uuid(), POOL_SIZE),
+        vec((uuid(), data_type()), POOL_SIZE),
+        vec(uuid(), POOL_SIZE),
+    )
+        .prop_map(|(entities, properties, relations)| IdPool {
+            entities,
+            properties,
+            relations,
+        })
+}
+
+/// A value that's valid for the data type, as it's written in an edit.
+pub fn raw_value(data_type: DataType) -> BoxedStrategy<String> {
+    match data_type {
+        DataType::Text => "\\PC{0,24}".boxed(),
+        DataType::Number => prop_oneof![
+            any::<i64>().prop_map(|number| number.to_string()),
+            (-1e9..1e9f64).prop_map(|number| number.to_string()),
+        ]
+        .boxed(),
+        DataType::Checkbox => select(vec!["0", "1"]).prop_map(String::from).boxed(),
+        DataType::Time => (1970..2100u32, 1..=12u32, 1..=28u32, 0..24u32, 0..60u32)
+            .prop_map(|(year, month, day, hour, minute)| {
+                format!(
+                    "{:04}-{:02}-{:02}T{:02}:{:02}:00Z",
+                    year, month, day, hour, minute
+                )
+            })
+            .boxed(),
+        DataType::Point => (-180.0..180.0f64, -90.0..90.0f64)
+            .prop_map(|(x, y)| format!("{},{}", x, y))
+            .boxed(),
+        DataType::Relation => uuid().prop_map(|id| id.to_string()).boxed(),
+    }
+}
+
+/// An op on the entities, properties and relations of the pool. Relation
+/// types are drawn from the pool's properties.
+pub fn op(pool: &IdPool) -> BoxedStrategy<Op> {
+    let entity = select(pool.entities.clone());
+    let property = select(pool.properties.clone());
+    let relation_id = select(pool.relations.clone());
+
+    let set_value = property.clone().prop_flat_map(|(property, data_type)| {
+        raw_value(data_type).prop_map(move |raw| value(property, raw))
+    });
+    let update_entity = (entity.clone(), vec(set_value, 1..4)).prop_map(|(entity, values)| {
+        Payload::UpdateEntity(Entity {
+            id: id_bytes(entity),
+            values,
+        })
+    });
+    let unset_entity_values =
+        (entity.clone(), vec(property.clone(), 1..3)).prop_map(|(entity, properties)| {
+            Payload::UnsetEntityValues(UnsetEntityValues {
+                id: id_bytes(entity),
+                properties: properties.into_iter().map(|(id, _)| id_bytes(id)).collect(),
+            })
+        });
+    let delete_entity = entity
+        .clone()
+        .prop_map(|entity| Payload::DeleteEntity(id_bytes(entity)));
+    let create_relation = (
+        relation_id.clone(),
+        entity.clone(),
+        property,
+        entity.clone(),
+        entity,
+    )
+        .prop_map(|(id, entity, (relation_type, _), from, to)| {
+            Payload::CreateRelation(relation(id, entity, relation_type, from, to))
+        });
+    let update_relation = (
+        relation_id.clone(),
+        proptest::option::of("[a-z]{1,4}"),
+        proptest::option::of(any::<bool>()),
+    )
+        .prop_map(|(id, position, verified)| {
+            Payload::UpdateRelation(RelationUpdate {
+                position,
+                verified,
+                ..relation_update(id)
+            })
+        });
+    let delete_relation = relation_id.prop_map(|id| Payload::DeleteRelation(id_bytes(id)));
+
+    prop_oneof![
+        4 => update_entity,
+        2 => unset_entity_values,
+        1 => delete_entity,
+        3 => create_relation,
+        1 => update_relation,
+        1 => delete_relation,
+    ]
+    .prop_map(|payload| Op {
+        payload: Some(payload),
+    })
+    .boxed()
+}
+
+/// An edit that creates the pool's properties and then applies up to
+/// `max_ops` ops drawn from the pool.
+pub fn edit_with(max_ops: usize) -> impl Strategy<Value = (IdPool, Edit)> {
+    (id_pool(), uuid()).prop_flat_map(move |(pool, edit_id)| {
+        vec(op(&pool), 0..=max_ops).prop_map(move |ops| {
+            let builder = pool
+                .properties
+                .iter()
+                .fold(EditBuilder::new(edit_id), |builder, (id, data_type)| {
+                    builder.create_property(*id, *data_type)
+                });
+            let edit = ops.into_iter().fold(builder, EditBuilder::op).build();
+            (pool.clone(), edit)
+        })
+    })
+}
+
+/// An edit of up to 24 ops, see [`edit_with`].
+pub fn edit() -> impl Strategy<Value = Edit> {
+    edit_with(24).prop_map(|(_, edit)| edit)
+}
+
+fn id_bytes(id: Uuid) -> Vec<u8> {
+    id.as_bytes().to_vec()
+}
@@ -0,0 +1,226 @@
+use uuid::Uuid;
+
+use crate::pb::grc20::{
+    DataType, Edit, Entity, Op, Property, Relation, RelationUpdate, UnsetEntityValues, Value,
+    op::Payload,
+};
+
+/// Builds a grc20 `Edit` op by op.
+///
+/// Ids are taken as `Uuid`s so every id in the resulting protobuf is a valid
+/// 16 byte UUID. Ops are emitted in the order they are added.
+///
+/// ```
+/// use uuid::Uuid;
+/// use wire::builder::{relation, value, EditBuilder};
+/// use wire::pb::grc20::DataType;
+///
+/// let (name, person, alice, bob) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+///
+/// let edit = EditBuilder::new(Uuid::new_v4())
+///     .name("Add people")
+///     .author(Uuid::new_v4())
+///     .create_property(name, DataType::Text)
+///     .update_entity(alice, [value(name, "Alice")])
+///     .create_relation(relation(Uuid::new_v4(), Uuid::new_v4(), person, alice, bob))
+///     .build();
+///
+/// assert_eq!(edit.ops.len(), 3);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct EditBuilder {
+    edit: Edit,
+}
+
+impl EditBuilder {
+    pub fn new(id: Uuid) -> Self {
+        EditBuilder {
+            edit: Edit {
+                id: id_bytes(id),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.edit.name = name.into();
+        self
+    }
+
+    pub fn author(mut self, author: Uuid) -> Self {
+        self.edit.authors.push(id_bytes(author));
+        self
+    }
+
+    pub fn language(mut self, language: Uuid) -> Self {
+        self.edit.language = Some(id_bytes(language));
+        self
+    }
+
+    pub fn update_entity(self, entity: Uuid, values: impl IntoIterator<Item = Value>) -> Self {
+        self.push(Payload::UpdateEntity(Entity {
+            id: id_bytes(entity),
+            values: values.into_iter().collect(),
+        }))
+    }
+
+    pub fn unset_entity_values(
+        self,
+        entity: Uuid,
+        properties: impl IntoIterator<Item = Uuid>,
+    ) -> Self {
+        self.push(Payload::UnsetEntityValues(UnsetEntityValues {
+            id: id_bytes(entity),
+            properties: properties.into_iter().map(id_bytes).collect(),
+        }))
+    }
+
+    pub fn create_property(self, property: Uuid, data_type: DataType) -> Self {
+        self.push(Payload::CreateProperty(Property {
+            id: id_bytes(property),
+            data_type: data_type as i32,
+        }))
+    }
+
+    pub fn create_relation(self, relation: Relation) -> Self {
+        self.push(Payload::CreateRelation(relation))
+    }
+
+    pub fn update_relation(self, update: RelationUpdate) -> Self {
+        self.push(Payload::UpdateRelation(update))
+    }
+
+    pub fn delete_relation(self, relation: Uuid) -> Self {
+        self.push(Payload::DeleteRelation(id_bytes(relation)))
+    }
+
+    pub fn delete_entity(self, entity: Uuid) -> Self {
+        self.push(Payload::DeleteEntity(id_bytes(entity)))
+    }
+
+    pub fn update_property(self, property: Uuid, data_type: DataType) -> Self {
+        self.push(Payload::UpdateProperty(Property {
+            id: id_bytes(property),
+            data_type: data_type as i32,
+        }))
+    }
+
+    /// Appends an already constructed op.
+    pub fn op(mut self, op: Op) -> Self {
+        self.edit.ops.push(op);
+        self
+    }
+
+    pub fn build(self) -> Edit {
+        self.edit
+    }
+
+    fn push(self, payload: Payload) -> Self {
+        self.op(Op {
+            payload: Some(payload),
+        })
+    }
+}
+
+/// A value without options for use with [`EditBuilder::update_entity`].
+pub fn value(property: Uuid, value: impl Into<String>) -> Value {
+    Value {
+        property: id_bytes(property),
+        value: value.into(),
+        options: None,
+    }
+}
+
+/// A relation between two entities with every optional field unset.
+pub fn relation(
+    id: Uuid,
+    entity: Uuid,
+    relation_type: Uuid,
+    from_entity: Uuid,
+    to_entity: Uuid,
+) -> Relation {
+    Relation {
+        id: id_bytes(id),
+        r#type: id_bytes(relation_type),
+        entity: id_bytes(entity),
+        from_entity: id_bytes(from_entity),
+        to_entity: id_bytes(to_entity),
+        ..Default::default()
+    }
+}
+
+/// A relation update that changes nothing, to be filled in with struct
+/// update syntax.
+pub fn relation_update(id: Uuid) -> RelationUpdate {
+    RelationUpdate {
+        id: id_bytes(id),
+        ..Default::default()
+    }
+}
+
+fn id_bytes(id: Uuid) -> Vec<u8> {
+    id.as_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builds_ops_in_order() {
+        let edit_id = Uuid::new_v4();
+        let author = Uuid::new_v4();
+        let entity = Uuid::new_v4();
+        let property = Uuid::new_v4();
+        let relation_id = Uuid::new_v4();
+
+        let edit = EditBuilder::new(edit_id)
+            .name("Edit")
+            .author(author)
+            .create_property(property, DataType::Number)
+            .update_entity(entity, [value(property, "1")])
+            .unset_entity_values(entity, [property])
+            .create_relation(relation(relation_id, entity, property, entity, entity))
+            .update_relation(RelationUpdate {
+                verified: Some(true),
+                ..relation_update(relation_id)
+            })
+            .delete_relation(relation_id)
+            .delete_entity(entity)
+            .update_property(property, DataType::Text)
+            .build();
+
+        assert_eq!(edit.id, edit_id.as_bytes().to_vec());
+        assert_eq!(edit.name, "Edit");
+        assert_eq!(edit.authors, vec![author.as_bytes().to_vec()]);
+
+        let payloads: Vec<_> = edit.ops.into_iter().map(|op| op.payload.unwrap()).collect();
+
+        assert_eq!(
+            payloads[0],
+            Payload::CreateProperty(Property {
+                id: property.as_bytes().to_vec(),
+                data_type: DataType::Number as i32,
+            })
+        );
+        assert!(matches!(&payloads[1], Payload::UpdateEntity(e) if e.values[0].value == "1"));
+        assert!(matches!(&payloads[2], Payload::UnsetEntityValues(u) if u.properties.len() == 1));
+        assert!(matches!(&payloads[3], Payload::CreateRelation(r) if r.position.is_none()));
+        assert!(matches!(&payloads[4], Payload::UpdateRelation(u) if u.verified == Some(true)));
+        assert_eq!(
+            payloads[5],
+            Payload::DeleteRelation(relation_id.as_bytes().to_vec())
+        );
+        assert_eq!(
+            payloads[6],
+            Payload::DeleteEntity(entity.as_bytes().to_vec())
+        );
+        assert_eq!(
+            payloads[7],
+            Payload::UpdateProperty(Property {
+                id: property.as_bytes().to_vec(),
+                data_type: DataType::Text as i32,
+            })
+        );
+    }
+}
@@ -1,10 +1,91 @@
-use std::io;
+use async_compression::tokio::bufread::ZstdDecoder;
+use std::{
+    io::{self, BufRead, Read},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Compression formats recognized in published payloads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Codec {
+    Zstd,
+    Gzip,
+    Brotli,
+    Uncompressed,
+}
+
+impl Codec {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Codec::Zstd => "zstd",
+            Codec::Gzip => "gzip",
+            Codec::Brotli => "brotli",
+            Codec::Uncompressed => "none",
+        }
+    }
+}
+
+/// Detects zstd and gzip payloads by their magic bytes.
+///
+/// Brotli streams have no magic number, so anything else is reported as
+/// `Uncompressed` and callers fall back to brotli when it fails to parse.
+pub fn detect_codec(data: &[u8]) -> Codec {
+    if data.starts_with(&ZSTD_MAGIC) {
+        Codec::Zstd
+    } else if data.starts_with(&GZIP_MAGIC) {
+        Codec::Gzip
+    } else {
+        Codec::Uncompressed
+    }
+}
+
+/// Decompresses `data` with the given codec, failing once the output grows
+/// past `max_output_size` bytes
+pub fn decompress_with(codec: Codec, data: &[u8], max_output_size: u64) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    match codec {
+        Codec::Zstd => {
+            decoder(data, max_output_size)?.read_to_end(&mut decompressed)?;
+        }
+        Codec::Gzip => {
+            SizeLimited::new(flate2::bufread::GzDecoder::new(data), max_output_size)
+                .read_to_end(&mut decompressed)?;
+        }
+        Codec::Brotli => {
+            SizeLimited::new(brotli::Decompressor::new(data, 4096), max_output_size)
+                .read_to_end(&mut decompressed)?;
+        }
+        Codec::Uncompressed => {
+            SizeLimited::new(data, max_output_size).read_to_end(&mut decompressed)?;
+        }
+    }
+    Ok(decompressed)
+}
+
+/// Default upper bound on the decompressed size of a single payload, so a
+/// small malicious payload can't expand without bound.
+pub const DEFAULT_MAX_DECOMPRESSED_SIZE: u64 = 256 * 1024 * 1024;
 
 /// Decompresses zstd-compressed data from a byte slice
 pub fn decompress_bytes(compressed_data: &[u8]) -> io::Result<Vec<u8>> {
     zstd::decode_all(compressed_data).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
 
+/// Decompresses zstd-compressed data from a byte slice, failing once the
+/// output grows past `max_output_size` bytes
+pub fn decompress_bytes_limited(
+    compressed_data: &[u8],
+    max_output_size: u64,
+) -> io::Result<Vec<u8>> {
+    let mut decompressed = Vec::new();
+    decoder(compressed_data, max_output_size)?.read_to_end(&mut decompressed)?;
+    Ok(decompressed)
+}
+
 /// Decompresses zstd-compressed data and converts it to a UTF-8 string
 pub fn decompress_to_string(compressed_data: &[u8]) -> io::Result<String> {
     let decompressed_bytes = decompress_bytes(compressed_data)?;
@@ -12,10 +93,113 @@ pub fn decompress_to_string(compressed_data: &[u8]) -> io::Result<String> {
         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Invalid UTF-8: {}", e)))
 }
 
+/// Returns a streaming zstd decoder over `reader` that yields at most
+/// `max_output_size` decompressed bytes.
+///
+/// Payloads can be decoded incrementally, e.g. straight into a protobuf or
+/// JSON parser, without materializing the whole decompressed buffer first.
+pub fn decoder<R: BufRead>(
+    reader: R,
+    max_output_size: u64,
+) -> io::Result<SizeLimited<zstd::stream::read::Decoder<'static, R>>> {
+    let decoder = zstd::stream::read::Decoder::with_buffer(reader)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(SizeLimited::new(decoder, max_output_size))
+}
+
+/// Async counterpart of [`decoder`] for tokio readers.
+pub fn async_decoder<R: AsyncBufRead + Unpin>(
+    reader: R,
+    max_output_size: u64,
+) -> SizeLimited<ZstdDecoder<R>> {
+    SizeLimited::new(ZstdDecoder::new(reader), max_output_size)
+}
+
+/// Reader adapter that errors with `InvalidData` once the wrapped reader
+/// produces more than `limit` bytes.
+pub struct SizeLimited<R> {
+    inner: R,
+    limit: u64,
+    read: u64,
+}
+
+impl<R> SizeLimited<R> {
+    pub fn new(inner: R, limit: u64) -> Self {
+        SizeLimited {
+            inner,
+            limit,
+            read: 0,
+        }
+    }
+
+    /// Number of bytes read so far.
+    pub fn bytes_read(&self) -> u64 {
+        self.read
+    }
+
+    fn track(&mut self, n: usize) -> io::Result<()> {
+        self.read += n as u64;
+        if self.read > self.limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Decompressed data exceeds the maximum size of {} bytes",
+                    self.limit
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for SizeLimited<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.track(n)?;
+        Ok(n)
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for SizeLimited<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        match Pin::new(&mut self.inner).poll_read(cx, buf) {
+            Poll::Ready(Ok(())) => {
+                let n = buf.filled().len() - filled;
+                let result = self.track(n);
+                if result.is_err() {
+                    buf.set_filled(filled);
+                }
+                Poll::Ready(result)
+            }
+            other => other,
+        }
+    }
+}
+
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
-    use std::fs;
+    use std::{fs, io::Write};
+
+    pub(crate) fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    pub(crate) fn brotli(data: &[u8]) -> Vec<u8> {
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+            writer.write_all(data).unwrap();
+        }
+        compressed
+    }
 
     #[test]
     fn test_decompress_ops_json_zst() {
@@ -68,4 +252,98 @@ mod tests {
             "Decompressed string should be valid JSON"
         );
     }
+
+    #[test]
+    fn test_streaming_decoder_matches_decompress_bytes() {
+        let compressed_data =
+            fs::read("data/ops.json.zst").expect("Failed to read ops.json.zst file");
+
+        let mut streamed = Vec::new();
+        decoder(compressed_data.as_slice(), DEFAULT_MAX_DECOMPRESSED_SIZE)
+            .expect("Failed to create decoder")
+            .read_to_end(&mut streamed)
+            .expect("Failed to stream ops.json.zst");
+
+        assert_eq!(
+            streamed,
+            zstd::decode_all(compressed_data.as_slice()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_decompress_bytes_limited_rejects_oversized_output() {
+        let compressed_data = zstd::encode_all(vec![0u8; 4096].as_slice(), 3).unwrap();
+
+        assert_eq!(
+            decompress_bytes_limited(&compressed_data, 4096)
+                .unwrap()
+                .len(),
+            4096
+        );
+
+        let error = decompress_bytes_limited(&compressed_data, 4095).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_detect_codec() {
+        let payload = b"payload".repeat(16);
+
+        assert_eq!(
+            detect_codec(&zstd::encode_all(payload.as_slice(), 3).unwrap()),
+            Codec::Zstd
+        );
+        assert_eq!(detect_codec(&gzip(&payload)), Codec::Gzip);
+        assert_eq!(detect_codec(&payload), Codec::Uncompressed);
+        assert_eq!(detect_codec(&[]), Codec::Uncompressed);
+    }
+
+    #[test]
+    fn test_decompress_with_each_codec() {
+        let payload = b"payload".repeat(16);
+
+        let cases = [
+            (
+                Codec::Zstd,
+                zstd::encode_all(payload.as_slice(), 3).unwrap(),
+            ),
+            (Codec::Gzip, gzip(&payload)),
+            (Codec::Brotli, brotli(&payload)),
+            (Codec::Uncompressed, payload.clone()),
+        ];
+
+        for (codec, compressed) in cases {
+            assert_eq!(
+                decompress_with(codec, &compressed, DEFAULT_MAX_DECOMPRESSED_SIZE).unwrap(),
+                payload,
+                "{:?}",
+                codec
+            );
+            assert!(
+                decompress_with(codec, &compressed, 8).is_err(),
+                "{:?}",
+                codec
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_decoder_rejects_oversized_output() {
+        use tokio::io::AsyncReadExt;
+
+        let compressed_data = zstd::encode_all(vec![0u8; 4096].as_slice(), 3).unwrap();
+
+        let mut decompressed = Vec::new();
+        async_decoder(compressed_data.as_slice(), 4096)
+            .read_to_end(&mut decompressed)
+            .await
+            .expect("Failed to stream within the limit");
+        assert_eq!(decompressed.len(), 4096);
+
+        let error = async_decoder(compressed_data.as_slice(), 1024)
+            .read_to_end(&mut Vec::new())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
 }
@@ -1,21 +1,177 @@
-use crate::pb::grc20::Edit;
+#[cfg(feature = "serde")]
+use crate::legacy::{LegacyEditError, decode_legacy_edit, is_json};
+use crate::{
+    compression::{Codec, DEFAULT_MAX_DECOMPRESSED_SIZE, decompress_with, detect_codec},
+    pb::grc20::Edit,
+};
 use prost::Message;
-use serde_json;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
 pub enum DeserializeError {
+    #[cfg(feature = "serde")]
     #[error("JSON deserialization error: {0}")]
     JsonDeserializeError(#[from] serde_json::Error),
 
     #[error("Protobuf deserialization error: {0}")]
     ProtobufDeserializeError(#[from] prost::DecodeError),
+
+    #[error("Decompression error: {0}")]
+    DecompressionError(#[from] std::io::Error),
+
+    #[cfg(feature = "serde")]
+    #[error("Legacy edit deserialization error: {0}")]
+    LegacyEditError(#[from] LegacyEditError),
+}
+
+/// How an edit payload is encoded once decompressed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Protobuf,
+    LegacyJson,
+}
+
+impl Encoding {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Encoding::Protobuf => "protobuf",
+            Encoding::LegacyJson => "json",
+        }
+    }
+}
+
+/// The compression and encoding an edit payload was decoded with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PayloadFormat {
+    pub codec: Codec,
+    pub encoding: Encoding,
 }
 
+/// Decodes an edit payload that is either a raw grc20 protobuf or legacy JSON
+/// edit, or one of those compressed with zstd, gzip or brotli. Legacy JSON
+/// edits are only decoded with the `serde` feature.
 pub fn deserialize(buf: &[u8]) -> Result<Edit, DeserializeError> {
-    Ok(Edit::decode(buf)?)
+    deserialize_with_format(buf).map(|(edit, _)| edit)
+}
+
+/// Decodes an edit payload like [`deserialize`], and returns the format it
+/// was detected to be in.
+pub fn deserialize_with_format(buf: &[u8]) -> Result<(Edit, PayloadFormat), DeserializeError> {
+    let (codec, (edit, encoding)) = match detect_codec(buf) {
+        Codec::Uncompressed => match decode_payload(buf) {
+            Ok(decoded) => (Codec::Uncompressed, decoded),
+            // Brotli has no magic bytes, so only try it once the payload
+            // fails to parse as is.
+            Err(error) => {
+                match decompress_with(Codec::Brotli, buf, DEFAULT_MAX_DECOMPRESSED_SIZE) {
+                    Ok(decompressed) => (Codec::Brotli, decode_payload(&decompressed)?),
+                    Err(_) => return Err(error),
+                }
+            }
+        },
+        codec => (
+            codec,
+            decode_payload(&decompress_with(codec, buf, DEFAULT_MAX_DECOMPRESSED_SIZE)?)?,
+        ),
+    };
+
+    Ok((edit, PayloadFormat { codec, encoding }))
+}
+
+fn decode_payload(buf: &[u8]) -> Result<(Edit, Encoding), DeserializeError> {
+    #[cfg(feature = "serde")]
+    if is_json(buf) {
+        return Ok((decode_legacy_edit(buf)?, Encoding::LegacyJson));
+    }
+
+    Ok((Edit::decode(buf)?, Encoding::Protobuf))
 }
 
+#[cfg(feature = "serde")]
 pub fn deserialize_from_json(json: serde_json::Value) -> Result<Edit, DeserializeError> {
     Ok(serde_json::from_value::<Edit>(json)?)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::{brotli, gzip};
+
+    #[test]
+    fn test_deserialize_detects_codec() {
+        let expected = Edit {
+            id: vec![1; 16],
+            name: "Compressed edit".repeat(8),
+            authors: vec![vec![2; 16]],
+            ..Default::default()
+        };
+        let raw = expected.encode_to_vec();
+
+        assert_eq!(deserialize(&raw).unwrap(), expected);
+        assert_eq!(
+            deserialize(&zstd::encode_all(raw.as_slice(), 3).unwrap()).unwrap(),
+            expected
+        );
+        assert_eq!(deserialize(&gzip(&raw)).unwrap(), expected);
+        assert_eq!(deserialize(&brotli(&raw)).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_deserialize_reports_format() {
+        let raw = Edit {
+            id: vec![1; 16],
+            name: "Compressed edit".repeat(8),
+            ..Default::default()
+        }
+        .encode_to_vec();
+
+        let (_, format) = deserialize_with_format(&gzip(&raw)).unwrap();
+        assert_eq!(
+            format,
+            PayloadFormat {
+                codec: Codec::Gzip,
+                encoding: Encoding::Protobuf
+            }
+        );
+        let (_, format) = deserialize_with_format(&brotli(&raw)).unwrap();
+        assert_eq!(format.codec, Codec::Brotli);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_legacy_json() {
+        let json = br#"{"data": [{"type": "UPDATE_ENTITY", "entity": {"id": "8a856ce9-9905-4221-9856-62ec43bdee82", "values": []}}]}"#;
+
+        assert_eq!(deserialize(json).unwrap().ops.len(), 1);
+        assert_eq!(deserialize(&gzip(json)).unwrap().ops.len(), 1);
+
+        let (_, format) = deserialize_with_format(br#"{"data": []}"#).unwrap();
+        assert_eq!(
+            (format.codec.as_str(), format.encoding.as_str()),
+            ("none", "json")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_edit_json_round_trip() {
+        let edit = Edit {
+            id: vec![1; 16],
+            name: "Json edit".to_string(),
+            ops: vec![crate::pb::grc20::Op {
+                payload: Some(crate::pb::grc20::op::Payload::DeleteRelation(vec![3; 16])),
+            }],
+            authors: vec![vec![2; 16]],
+            language: None,
+        };
+
+        let json = serde_json::to_value(&edit).unwrap();
+
+        assert_eq!(deserialize_from_json(json).unwrap(), edit);
+    }
+
+    #[test]
+    fn test_deserialize_invalid_payload() {
+        assert!(deserialize(&[0xff, 0xfe, 0xfd, 0xfc, 0xfb]).is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use prost::Message;
+use sha2::{Digest, Sha256};
+
+use crate::pb::grc20::Edit;
+
+/// Serializes an edit into its canonical byte form.
+///
+/// The canonical form is the protobuf encoding of the decoded edit. prost
+/// writes fields in tag order, omits default values and drops unknown fields,
+/// and the grc20 schema has no maps, so the same edit always encodes to the
+/// same bytes regardless of how the payload was compressed or whether it was
+/// published as protobuf or legacy JSON.
+pub fn canonical_bytes(edit: &Edit) -> Vec<u8> {
+    edit.encode_to_vec()
+}
+
+/// Returns the hex encoded SHA-256 of the edit's canonical bytes. Edits with
+/// the same content hash to the same value even when published under
+/// different CIDs.
+pub fn edit_hash(edit: &Edit) -> String {
+    Sha256::digest(canonical_bytes(edit))
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compression::tests::gzip;
+    use crate::deserialize::deserialize;
+
+    fn edit(name: &str) -> Edit {
+        Edit {
+            id: vec![1; 16],
+            name: name.to_string(),
+            authors: vec![vec![2; 16]],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_edit_hash_is_stable_across_encodings() {
+        let original = edit("Edit");
+        let raw = original.encode_to_vec();
+
+        let from_raw = deserialize(&raw).unwrap();
+        let from_gzip = deserialize(&gzip(&raw)).unwrap();
+
+        assert_eq!(edit_hash(&from_raw), edit_hash(&original));
+        assert_eq!(edit_hash(&from_gzip), edit_hash(&original));
+        assert_eq!(edit_hash(&original).len(), 64);
+    }
+
+    #[test]
+    fn test_edit_hash_differs_for_different_content() {
+        assert_ne!(edit_hash(&edit("Edit")), edit_hash(&edit("Other edit")));
+    }
+}
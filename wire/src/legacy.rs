@@ -0,0 +1,267 @@
+use serde::Deserialize;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::base58::decode_base58_to_uuid;
+use crate::pb::grc20::{DataType, Edit, Entity, Op, Property, Relation, Value, op::Payload};
+
+#[derive(Error, Debug)]
+pub enum LegacyEditError {
+    #[error("Legacy edit JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("Legacy edit has invalid id '{0}'")]
+    InvalidId(String),
+
+    #[error("Legacy edit has unknown data type '{0}'")]
+    InvalidDataType(String),
+}
+
+#[derive(Deserialize)]
+struct LegacyEdit {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    authors: Vec<String>,
+    #[serde(alias = "data")]
+    ops: Vec<LegacyOp>,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+enum LegacyOp {
+    UpdateEntity { entity: LegacyEntity },
+    CreateRelation { relation: LegacyRelation },
+    DeleteRelation { relation: LegacyId },
+    CreateProperty { property: LegacyProperty },
+    DeleteEntity { entity: LegacyId },
+}
+
+#[derive(Deserialize)]
+struct LegacyEntity {
+    id: String,
+    #[serde(default)]
+    values: Vec<LegacyValue>,
+}
+
+#[derive(Deserialize)]
+struct LegacyValue {
+    property: String,
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyRelation {
+    id: String,
+    #[serde(rename = "type")]
+    relation_type: String,
+    from_entity: String,
+    to_entity: String,
+    entity: String,
+    #[serde(default)]
+    position: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct LegacyId {
+    id: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyProperty {
+    id: String,
+    data_type: String,
+}
+
+/// Returns true when the payload looks like a JSON document rather than a
+/// grc20 protobuf.
+pub fn is_json(buf: &[u8]) -> bool {
+    buf.iter()
+        .find(|byte| !byte.is_ascii_whitespace())
+        .is_some_and(|byte| *byte == b'{')
+}
+
+/// Decodes an edit published in the JSON format used before edits were
+/// grc20 protobufs, mapping its ops onto `pb::grc20::Edit`.
+///
+/// Ids may be UUIDs or the base58 ids used by early edits.
+pub fn decode_legacy_edit(buf: &[u8]) -> Result<Edit, LegacyEditError> {
+    let legacy: LegacyEdit = serde_json::from_slice(buf)?;
+
+    Ok(Edit {
+        id: legacy
+            .id
+            .as_deref()
+            .map(id_bytes)
+            .transpose()?
+            .unwrap_or_default(),
+        name: legacy.name,
+        ops: legacy
+            .ops
+            .into_iter()
+            .map(map_op)
+            .collect::<Result<_, _>>()?,
+        authors: legacy
+            .authors
+            .iter()
+            .map(|author| id_bytes(author))
+            .collect::<Result<_, _>>()?,
+        language: None,
+    })
+}
+
+fn map_op(op: LegacyOp) -> Result<Op, LegacyEditError> {
+    let payload = match op {
+        LegacyOp::UpdateEntity { entity } => Payload::UpdateEntity(Entity {
+            id: id_bytes(&entity.id)?,
+            values: entity
+                .values
+                .into_iter()
+                .map(|value| {
+                    Ok(Value {
+                        property: id_bytes(&value.property)?,
+                        value: value.value,
+                        options: None,
+                    })
+                })
+                .collect::<Result<_, LegacyEditError>>()?,
+        }),
+        LegacyOp::CreateRelation { relation } => Payload::CreateRelation(Relation {
+            id: id_bytes(&relation.id)?,
+            r#type: id_bytes(&relation.relation_type)?,
+            from_entity: id_bytes(&relation.from_entity)?,
+            to_entity: id_bytes(&relation.to_entity)?,
+            entity: id_bytes(&relation.entity)?,
+            position: relation.position,
+            ..Default::default()
+        }),
+        LegacyOp::DeleteRelation { relation } => Payload::DeleteRelation(id_bytes(&relation.id)?),
+        LegacyOp::CreateProperty { property } => Payload::CreateProperty(Property {
+            id: id_bytes(&property.id)?,
+            data_type: DataType::from_str_name(&property.data_type)
+                .ok_or(LegacyEditError::InvalidDataType(property.data_type))?
+                as i32,
+        }),
+        LegacyOp::DeleteEntity { entity } => Payload::DeleteEntity(id_bytes(&entity.id)?),
+    };
+
+    Ok(Op {
+        payload: Some(payload),
+    })
+}
+
+fn id_bytes(id: &str) -> Result<Vec<u8>, LegacyEditError> {
+    Uuid::parse_str(id)
+        .ok()
+        .or_else(|| {
+            decode_base58_to_uuid(id)
+                .ok()
+                .and_then(|uuid| Uuid::parse_str(&uuid).ok())
+        })
+        .map(|uuid| uuid.as_bytes().to_vec())
+        .ok_or_else(|| LegacyEditError::InvalidId(id.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENTITY: &str = "8a856ce9-9905-4221-9856-62ec43bdee82";
+    const PROPERTY: &str = "a126ca53-0c8e-48d5-b888-82c734c38935";
+
+    fn uuid_bytes(id: &str) -> Vec<u8> {
+        Uuid::parse_str(id).unwrap().as_bytes().to_vec()
+    }
+
+    #[test]
+    fn test_is_json() {
+        assert!(is_json(b"  {\"data\": []}"));
+        assert!(!is_json(&[0x0a, 0x10]));
+        assert!(!is_json(b""));
+    }
+
+    #[test]
+    fn test_decode_legacy_ops() {
+        let json = format!(
+            r#"{{"data": [
+                {{"type": "UPDATE_ENTITY", "entity": {{"id": "{ENTITY}", "values": [{{"property": "{PROPERTY}", "value": "Coinbase"}}]}}}},
+                {{"type": "CREATE_RELATION", "relation": {{"id": "{ENTITY}", "entity": "{PROPERTY}", "fromEntity": "{ENTITY}", "toEntity": "{PROPERTY}", "type": "{PROPERTY}", "position": "a0"}}}},
+                {{"type": "DELETE_RELATION", "relation": {{"id": "{ENTITY}"}}}},
+                {{"type": "CREATE_PROPERTY", "property": {{"id": "{PROPERTY}", "dataType": "RELATION"}}}},
+                {{"type": "DELETE_ENTITY", "entity": {{"id": "{ENTITY}"}}}}
+            ]}}"#
+        );
+
+        let edit = decode_legacy_edit(json.as_bytes()).unwrap();
+
+        assert_eq!(edit.ops.len(), 5);
+        assert_eq!(
+            edit.ops[0].payload,
+            Some(Payload::UpdateEntity(Entity {
+                id: uuid_bytes(ENTITY),
+                values: vec![Value {
+                    property: uuid_bytes(PROPERTY),
+                    value: "Coinbase".to_string(),
+                    options: None,
+                }],
+            }))
+        );
+        assert!(matches!(
+            &edit.ops[1].payload,
+            Some(Payload::CreateRelation(relation))
+                if relation.from_entity == uuid_bytes(ENTITY)
+                    && relation.position.as_deref() == Some("a0")
+        ));
+        assert_eq!(
+            edit.ops[2].payload,
+            Some(Payload::DeleteRelation(uuid_bytes(ENTITY)))
+        );
+        assert_eq!(
+            edit.ops[3].payload,
+            Some(Payload::CreateProperty(Property {
+                id: uuid_bytes(PROPERTY),
+                data_type: DataType::Relation as i32,
+            }))
+        );
+        assert_eq!(
+            edit.ops[4].payload,
+            Some(Payload::DeleteEntity(uuid_bytes(ENTITY)))
+        );
+    }
+
+    #[test]
+    fn test_decode_legacy_base58_ids() {
+        let json = r#"{"name": "Legacy", "ops": [{"type": "UPDATE_ENTITY", "entity": {"id": "Q5YFEacgaHtXE9Kub9AEkA", "values": []}}]}"#;
+
+        let edit = decode_legacy_edit(json.as_bytes()).unwrap();
+
+        assert_eq!(edit.name, "Legacy");
+        assert!(matches!(
+            &edit.ops[0].payload,
+            Some(Payload::UpdateEntity(entity)) if entity.id.len() == 16
+        ));
+    }
+
+    #[test]
+    fn test_decode_legacy_invalid_id() {
+        let json = r#"{"data": [{"type": "UPDATE_ENTITY", "entity": {"id": "not-an-id!", "values": []}}]}"#;
+
+        assert!(matches!(
+            decode_legacy_edit(json.as_bytes()),
+            Err(LegacyEditError::InvalidId(_))
+        ));
+    }
+
+    #[test]
+    fn test_decode_legacy_ops_fixture() {
+        let json = std::fs::read("data/ops.json").expect("Failed to read ops.json file");
+
+        let edit = decode_legacy_edit(&json).expect("Failed to decode ops.json");
+
+        assert!(!edit.ops.is_empty());
+    }
+}
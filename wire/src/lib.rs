@@ -1,3 +1,13 @@
+#[cfg(feature = "proptest")]
+pub mod arbitrary;
+pub mod base58;
+pub mod builder;
 pub mod compression;
 pub mod deserialize;
+pub mod hash;
+#[cfg(feature = "serde")]
+pub mod legacy;
 pub mod pb;
+pub mod validate;
+
+pub use validate::validate_edit;
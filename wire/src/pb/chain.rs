@@ -5,7 +5,7 @@
 ///
 /// This is immediately useful when migrating from legacy spaces to the new DAO-based spaces,
 /// but it's generally applicable across any space.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SuccessorSpaceCreated {
     #[prost(string, tag = "1")]
@@ -15,7 +15,7 @@ pub struct SuccessorSpaceCreated {
     #[prost(string, tag = "3")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SuccessorSpacesCreated {
     #[prost(message, repeated, tag = "1")]
@@ -28,15 +28,19 @@ pub struct SuccessorSpacesCreated {
 ///
 /// This event maps creation of the Space plugin and associates the Space plugin contract
 /// address with the address of the DAO contract.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoSpaceCreated {
     #[prost(string, tag = "1")]
     pub dao_address: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub space_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub creator: ::prost::alloc::string::String,
+    #[prost(string, tag = "4")]
+    pub tx_hash: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoSpacesCreated {
     #[prost(message, repeated, tag = "1")]
@@ -53,7 +57,7 @@ pub struct GeoSpacesCreated {
 /// As of January 23, 2024 there are two governance plugins:
 /// 1. Voting plugin – This defines the voting and proposal rules and behaviors for a DAO
 /// 2. Member access plugin – This defines the membership rules and behaviors for a DAO
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoGovernancePluginCreated {
     #[prost(string, tag = "1")]
@@ -63,13 +67,13 @@ pub struct GeoGovernancePluginCreated {
     #[prost(string, tag = "3")]
     pub member_access_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoGovernancePluginsCreated {
     #[prost(message, repeated, tag = "1")]
     pub plugins: ::prost::alloc::vec::Vec<GeoGovernancePluginCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoPersonalSpaceAdminPluginCreated {
     #[prost(string, tag = "1")]
@@ -79,7 +83,7 @@ pub struct GeoPersonalSpaceAdminPluginCreated {
     #[prost(string, tag = "3")]
     pub initial_editor: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoPersonalSpaceAdminPluginsCreated {
     #[prost(message, repeated, tag = "1")]
@@ -101,7 +105,7 @@ pub struct GeoPersonalSpaceAdminPluginsCreated {
 ///     in the space vote on whether to accept the new editor.
 /// 2. They are added as a set of initial editors when first creating the space. This allows
 ///     space deployers to bootstrap a set of editors on space creation.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct InitialEditorAdded {
     /// The event emits an array of addresses. We only emit multiple addresses
@@ -114,7 +118,7 @@ pub struct InitialEditorAdded {
     #[prost(string, tag = "3")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct InitialEditorsAdded {
     #[prost(message, repeated, tag = "1")]
@@ -124,20 +128,42 @@ pub struct InitialEditorsAdded {
 /// space's main voting plugin. The DAO itself also emits the executed event,
 /// but the ABI/interface is different. We really only care about the one
 /// from our plugins.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ProposalExecuted {
     #[prost(string, tag = "1")]
     pub proposal_id: ::prost::alloc::string::String,
     #[prost(string, tag = "2")]
     pub plugin_address: ::prost::alloc::string::String,
+    #[prost(string, tag = "3")]
+    pub tx_hash: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ProposalsExecuted {
     #[prost(message, repeated, tag = "1")]
     pub executed_proposals: ::prost::alloc::vec::Vec<ProposalExecuted>,
 }
+/// Canceled proposals were withdrawn by their creator in a space's main
+/// voting plugin, or vetoed by an editor in its member access plugin. Like
+/// executed proposals they only name the plugin, not the DAO.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProposalCanceled {
+    #[prost(string, tag = "1")]
+    pub proposal_id: ::prost::alloc::string::String,
+    #[prost(string, tag = "2")]
+    pub plugin_address: ::prost::alloc::string::String,
+    /// The creator who withdrew the proposal, or the editor who vetoed it
+    #[prost(string, tag = "3")]
+    pub canceled_by: ::prost::alloc::string::String,
+}
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ProposalsCanceled {
+    #[prost(message, repeated, tag = "1")]
+    pub canceled_proposals: ::prost::alloc::vec::Vec<ProposalCanceled>,
+}
 /// *
 /// Processed Proposals represent content that has been approved by a DAO
 /// and executed onchain.
@@ -145,7 +171,7 @@ pub struct ProposalsExecuted {
 /// We use the content URI to represent the content that was approved. We
 /// only consume the `proposalId` in the content URI to map the processed
 /// data to an existing proposal onchain and in the sink.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EditPublished {
     #[prost(string, tag = "1")]
@@ -154,8 +180,11 @@ pub struct EditPublished {
     pub plugin_address: ::prost::alloc::string::String,
     #[prost(string, tag = "3")]
     pub dao_address: ::prost::alloc::string::String,
+    /// The sender of the transaction that published the edit
+    #[prost(string, tag = "4")]
+    pub sender: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EditsPublished {
     #[prost(message, repeated, tag = "1")]
@@ -166,7 +195,7 @@ pub struct EditsPublished {
 /// of the DAO-based space. This is useful to "link" Spaces together in a
 /// tree of spaces, allowing us to curate the graph of their knowledge and
 /// permissions.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubspaceAdded {
     /// Subspace dao address
@@ -181,13 +210,13 @@ pub struct SubspaceAdded {
     #[prost(string, tag = "4")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubspacesAdded {
     #[prost(message, repeated, tag = "1")]
     pub subspaces: ::prost::alloc::vec::Vec<SubspaceAdded>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubspaceRemoved {
     /// Subspace dao address
@@ -202,7 +231,7 @@ pub struct SubspaceRemoved {
     #[prost(string, tag = "4")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct SubspacesRemoved {
     #[prost(message, repeated, tag = "1")]
@@ -214,7 +243,7 @@ pub struct SubspacesRemoved {
 /// Currently we use a simple majority voting model, where a proposal requires 51% of the
 /// available votes in order to pass. Only editors are allowed to vote on proposals, but editors
 /// _and_ members can create them.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct VoteCast {
     #[prost(string, tag = "1")]
@@ -226,13 +255,13 @@ pub struct VoteCast {
     #[prost(string, tag = "4")]
     pub plugin_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct VotesCast {
     #[prost(message, repeated, tag = "1")]
     pub votes: ::prost::alloc::vec::Vec<VoteCast>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MemberAdded {
     #[prost(string, tag = "1")]
@@ -244,13 +273,13 @@ pub struct MemberAdded {
     #[prost(string, tag = "4")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MembersAdded {
     #[prost(message, repeated, tag = "1")]
     pub members: ::prost::alloc::vec::Vec<MemberAdded>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MemberRemoved {
     #[prost(string, tag = "1")]
@@ -262,13 +291,13 @@ pub struct MemberRemoved {
     #[prost(string, tag = "4")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct MembersRemoved {
     #[prost(message, repeated, tag = "1")]
     pub members: ::prost::alloc::vec::Vec<MemberRemoved>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EditorAdded {
     #[prost(string, tag = "1")]
@@ -280,13 +309,13 @@ pub struct EditorAdded {
     #[prost(string, tag = "4")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EditorsAdded {
     #[prost(message, repeated, tag = "1")]
     pub editors: ::prost::alloc::vec::Vec<EditorAdded>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EditorRemoved {
     #[prost(string, tag = "1")]
@@ -298,13 +327,13 @@ pub struct EditorRemoved {
     #[prost(string, tag = "4")]
     pub dao_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct EditorsRemoved {
     #[prost(message, repeated, tag = "1")]
     pub editors: ::prost::alloc::vec::Vec<EditorRemoved>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PublishEditProposalCreated {
     #[prost(string, tag = "1")]
@@ -322,13 +351,13 @@ pub struct PublishEditProposalCreated {
     #[prost(string, tag = "7")]
     pub plugin_address: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct PublishEditsProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub edits: ::prost::alloc::vec::Vec<PublishEditProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AddMemberProposalCreated {
     #[prost(string, tag = "1")]
@@ -348,13 +377,13 @@ pub struct AddMemberProposalCreated {
     #[prost(string, tag = "8")]
     pub change_type: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AddMemberProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub proposed_members: ::prost::alloc::vec::Vec<AddMemberProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RemoveMemberProposalCreated {
     #[prost(string, tag = "1")]
@@ -374,13 +403,13 @@ pub struct RemoveMemberProposalCreated {
     #[prost(string, tag = "8")]
     pub change_type: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RemoveMemberProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub proposed_members: ::prost::alloc::vec::Vec<RemoveMemberProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AddEditorProposalCreated {
     #[prost(string, tag = "1")]
@@ -400,13 +429,13 @@ pub struct AddEditorProposalCreated {
     #[prost(string, tag = "8")]
     pub change_type: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AddEditorProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub proposed_editors: ::prost::alloc::vec::Vec<AddEditorProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RemoveEditorProposalCreated {
     #[prost(string, tag = "1")]
@@ -426,13 +455,13 @@ pub struct RemoveEditorProposalCreated {
     #[prost(string, tag = "8")]
     pub change_type: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RemoveEditorProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub proposed_editors: ::prost::alloc::vec::Vec<RemoveEditorProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AddSubspaceProposalCreated {
     #[prost(string, tag = "1")]
@@ -452,13 +481,13 @@ pub struct AddSubspaceProposalCreated {
     #[prost(string, tag = "8")]
     pub change_type: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct AddSubspaceProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub proposed_subspaces: ::prost::alloc::vec::Vec<AddSubspaceProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RemoveSubspaceProposalCreated {
     #[prost(string, tag = "1")]
@@ -478,13 +507,13 @@ pub struct RemoveSubspaceProposalCreated {
     #[prost(string, tag = "8")]
     pub change_type: ::prost::alloc::string::String,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RemoveSubspaceProposalsCreated {
     #[prost(message, repeated, tag = "1")]
     pub proposed_subspaces: ::prost::alloc::vec::Vec<RemoveSubspaceProposalCreated>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct GeoOutput {
     #[prost(message, repeated, tag = "1")]
@@ -533,4 +562,6 @@ pub struct GeoOutput {
     pub proposed_removed_subspaces: ::prost::alloc::vec::Vec<
         RemoveSubspaceProposalCreated,
     >,
+    #[prost(message, repeated, tag = "22")]
+    pub canceled_proposals: ::prost::alloc::vec::Vec<ProposalCanceled>,
 }
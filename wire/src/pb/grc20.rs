@@ -1,5 +1,5 @@
 // This file is @generated by prost-build.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Edit {
     #[prost(bytes = "vec", tag = "1")]
@@ -13,7 +13,7 @@ pub struct Edit {
     #[prost(bytes = "vec", optional, tag = "5")]
     pub language: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ImportEdit {
     #[prost(bytes = "vec", tag = "1")]
@@ -35,14 +35,14 @@ pub struct ImportEdit {
     #[prost(bytes = "vec", tag = "9")]
     pub transaction_hash: ::prost::alloc::vec::Vec<u8>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Import {
     /// these strings are IPFS cids representing the import edit message
     #[prost(string, repeated, tag = "1")]
     pub edits: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct File {
     #[prost(string, tag = "1")]
@@ -52,7 +52,7 @@ pub struct File {
 }
 /// Nested message and enum types in `File`.
 pub mod file {
-    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Payload {
         #[prost(message, tag = "2")]
@@ -63,15 +63,15 @@ pub mod file {
         ArchiveSpace(::prost::alloc::vec::Vec<u8>),
     }
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Op {
-    #[prost(oneof = "op::Payload", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "op::Payload", tags = "1, 2, 3, 4, 5, 6, 7, 1000, 1001")]
     pub payload: ::core::option::Option<op::Payload>,
 }
 /// Nested message and enum types in `Op`.
 pub mod op {
-    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Payload {
         #[prost(message, tag = "1")]
@@ -88,9 +88,15 @@ pub mod op {
         UnsetEntityValues(super::UnsetEntityValues),
         #[prost(message, tag = "7")]
         UnsetRelationFields(super::UnsetRelationFields),
+        /// Ops from 1000 up are local to this indexer and aren't part of the
+        /// GRC-20 spec, so they don't take tags the spec may allocate.
+        #[prost(bytes, tag = "1000")]
+        DeleteEntity(::prost::alloc::vec::Vec<u8>),
+        #[prost(message, tag = "1001")]
+        UpdateProperty(super::Property),
     }
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Property {
     #[prost(bytes = "vec", tag = "1")]
@@ -98,7 +104,7 @@ pub struct Property {
     #[prost(enumeration = "DataType", tag = "2")]
     pub data_type: i32,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UnsetEntityValues {
     #[prost(bytes = "vec", tag = "1")]
@@ -106,7 +112,7 @@ pub struct UnsetEntityValues {
     #[prost(bytes = "vec", repeated, tag = "2")]
     pub properties: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Relation {
     #[prost(bytes = "vec", tag = "1")]
@@ -132,7 +138,7 @@ pub struct Relation {
     #[prost(bool, optional, tag = "11")]
     pub verified: ::core::option::Option<bool>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RelationUpdate {
     #[prost(bytes = "vec", tag = "1")]
@@ -150,7 +156,7 @@ pub struct RelationUpdate {
     #[prost(bool, optional, tag = "7")]
     pub verified: ::core::option::Option<bool>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UnsetRelationFields {
     #[prost(bytes = "vec", tag = "1")]
@@ -168,7 +174,7 @@ pub struct UnsetRelationFields {
     #[prost(bool, optional, tag = "7")]
     pub verified: ::core::option::Option<bool>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Entity {
     #[prost(bytes = "vec", tag = "1")]
@@ -176,7 +182,7 @@ pub struct Entity {
     #[prost(message, repeated, tag = "2")]
     pub values: ::prost::alloc::vec::Vec<Value>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Options {
     #[prost(oneof = "options::Value", tags = "1, 2")]
@@ -184,7 +190,7 @@ pub struct Options {
 }
 /// Nested message and enum types in `Options`.
 pub mod options {
-    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Value {
         #[prost(message, tag = "1")]
@@ -193,7 +199,7 @@ pub mod options {
         Number(super::NumberOptions),
     }
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Value {
     #[prost(bytes = "vec", tag = "1")]
@@ -203,19 +209,19 @@ pub struct Value {
     #[prost(message, optional, tag = "3")]
     pub options: ::core::option::Option<Options>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TextOptions {
     #[prost(bytes = "vec", optional, tag = "1")]
     pub language: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NumberOptions {
     #[prost(bytes = "vec", optional, tag = "1")]
     pub unit: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
 pub enum DataType {
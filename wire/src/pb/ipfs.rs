@@ -1,5 +1,5 @@
 // This file is @generated by prost-build.
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Edit {
     #[prost(bytes = "vec", tag = "1")]
@@ -13,7 +13,7 @@ pub struct Edit {
     #[prost(bytes = "vec", optional, tag = "5")]
     pub language: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct ImportEdit {
     #[prost(bytes = "vec", tag = "1")]
@@ -35,14 +35,14 @@ pub struct ImportEdit {
     #[prost(bytes = "vec", tag = "9")]
     pub transaction_hash: ::prost::alloc::vec::Vec<u8>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Import {
     /// these strings are IPFS cids representing the import edit message
     #[prost(string, repeated, tag = "1")]
     pub edits: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct File {
     #[prost(string, tag = "1")]
@@ -52,7 +52,7 @@ pub struct File {
 }
 /// Nested message and enum types in `File`.
 pub mod file {
-    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Payload {
         #[prost(message, tag = "2")]
@@ -63,7 +63,7 @@ pub mod file {
         ArchiveSpace(::prost::alloc::vec::Vec<u8>),
     }
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Op {
     #[prost(oneof = "op::Payload", tags = "1, 2, 3, 4, 5, 6, 7")]
@@ -71,7 +71,7 @@ pub struct Op {
 }
 /// Nested message and enum types in `Op`.
 pub mod op {
-    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Payload {
         #[prost(message, tag = "1")]
@@ -90,7 +90,7 @@ pub mod op {
         UnsetRelationFields(super::UnsetRelationFields),
     }
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UnsetEntityValues {
     #[prost(bytes = "vec", tag = "1")]
@@ -98,7 +98,7 @@ pub struct UnsetEntityValues {
     #[prost(bytes = "vec", repeated, tag = "2")]
     pub properties: ::prost::alloc::vec::Vec<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Relation {
     #[prost(bytes = "vec", tag = "1")]
@@ -124,7 +124,7 @@ pub struct Relation {
     #[prost(bool, optional, tag = "11")]
     pub verified: ::core::option::Option<bool>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RelationUpdate {
     #[prost(bytes = "vec", tag = "1")]
@@ -142,7 +142,7 @@ pub struct RelationUpdate {
     #[prost(bool, optional, tag = "7")]
     pub verified: ::core::option::Option<bool>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UnsetRelationFields {
     #[prost(bytes = "vec", tag = "1")]
@@ -160,7 +160,7 @@ pub struct UnsetRelationFields {
     #[prost(bool, optional, tag = "7")]
     pub verified: ::core::option::Option<bool>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Entity {
     #[prost(bytes = "vec", tag = "1")]
@@ -168,7 +168,7 @@ pub struct Entity {
     #[prost(message, repeated, tag = "2")]
     pub values: ::prost::alloc::vec::Vec<Value>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Options {
     #[prost(oneof = "options::Value", tags = "1, 2, 3")]
@@ -176,7 +176,7 @@ pub struct Options {
 }
 /// Nested message and enum types in `Options`.
 pub mod options {
-    #[derive(serde::Serialize, serde::Deserialize)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
     #[derive(Clone, PartialEq, ::prost::Oneof)]
     pub enum Value {
         #[prost(message, tag = "1")]
@@ -187,7 +187,7 @@ pub mod options {
         Time(super::TimeOptions),
     }
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Value {
     #[prost(bytes = "vec", tag = "1")]
@@ -197,13 +197,13 @@ pub struct Value {
     #[prost(message, optional, tag = "3")]
     pub options: ::core::option::Option<Options>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TextOptions {
     #[prost(bytes = "vec", optional, tag = "1")]
     pub language: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct NumberOptions {
     #[prost(string, optional, tag = "1")]
@@ -211,7 +211,7 @@ pub struct NumberOptions {
     #[prost(bytes = "vec", optional, tag = "2")]
     pub unit: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
-#[derive(serde::Serialize, serde::Deserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct TimeOptions {
     #[prost(string, optional, tag = "1")]
@@ -0,0 +1,284 @@
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::pb::grc20::{
+    DataType, Edit, Entity, Op, Property, Relation, RelationUpdate, Value, op::Payload, options,
+};
+
+const UUID_LENGTH: usize = 16;
+
+/// What is wrong with a single field of an edit.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    #[error("required field is empty")]
+    Missing,
+
+    #[error("expected a {UUID_LENGTH} byte UUID, got {0} bytes")]
+    InvalidUuid(usize),
+
+    #[error("unknown data type {0}")]
+    UnknownDataType(i32),
+
+    #[error("op has no payload")]
+    MissingPayload,
+}
+
+/// A problem found in an edit, located by the index of the op it was found
+/// in and the path of the offending field within that op.
+///
+/// Problems with the edit itself, like its authors, have no op index.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub op_index: Option<usize>,
+    pub field: String,
+    pub problem: Problem,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.op_index {
+            Some(index) => write!(f, "op {} {}: {}", index, self.field, self.problem),
+            None => write!(f, "edit {}: {}", self.field, self.problem),
+        }
+    }
+}
+
+/// Checks that an edit is well-formed before it is indexed.
+///
+/// Every id must be a 16 byte UUID, required ids must be present and
+/// property data types must be known. Values themselves are not validated
+/// here as that depends on the data type of their property.
+///
+/// Returns every problem found, in op order. An empty list means the edit is
+/// well-formed.
+pub fn validate_edit(edit: &Edit) -> Vec<Diagnostic> {
+    let mut diagnostics = Diagnostics {
+        op_index: None,
+        found: vec![],
+    };
+
+    if !edit.id.is_empty() {
+        diagnostics.uuid("id", &edit.id);
+    }
+    for (index, author) in edit.authors.iter().enumerate() {
+        diagnostics.uuid(&format!("authors[{}]", index), author);
+    }
+    diagnostics.optional_uuid("language", edit.language.as_ref());
+
+    for (index, op) in edit.ops.iter().enumerate() {
+        diagnostics.op_index = Some(index);
+        diagnostics.op(op);
+    }
+
+    diagnostics.found
+}
+
+struct Diagnostics {
+    op_index: Option<usize>,
+    found: Vec<Diagnostic>,
+}
+
+impl Diagnostics {
+    fn push(&mut self, field: &str, problem: Problem) {
+        self.found.push(Diagnostic {
+            op_index: self.op_index,
+            field: field.to_string(),
+            problem,
+        });
+    }
+
+    fn uuid(&mut self, field: &str, id: &[u8]) {
+        match id.len() {
+            UUID_LENGTH => {}
+            0 => self.push(field, Problem::Missing),
+            length => self.push(field, Problem::InvalidUuid(length)),
+        }
+    }
+
+    fn optional_uuid(&mut self, field: &str, id: Option<&Vec<u8>>) {
+        if let Some(id) = id {
+            self.uuid(field, id);
+        }
+    }
+
+    fn op(&mut self, op: &Op) {
+        match &op.payload {
+            None => self.push("payload", Problem::MissingPayload),
+            Some(Payload::UpdateEntity(entity)) => self.entity(entity),
+            Some(Payload::CreateRelation(relation)) => self.relation(relation),
+            Some(Payload::UpdateRelation(update)) => self.relation_update(update),
+            Some(Payload::DeleteRelation(id)) => self.uuid("delete_relation", id),
+            Some(Payload::CreateProperty(property)) => self.property("create_property", property),
+            Some(Payload::UnsetEntityValues(unset)) => {
+                self.uuid("unset_entity_values.id", &unset.id);
+                for (index, property) in unset.properties.iter().enumerate() {
+                    self.uuid(
+                        &format!("unset_entity_values.properties[{}]", index),
+                        property,
+                    );
+                }
+            }
+            Some(Payload::UnsetRelationFields(unset)) => {
+                self.uuid("unset_relation_fields.id", &unset.id)
+            }
+            Some(Payload::DeleteEntity(id)) => self.uuid("delete_entity", id),
+            Some(Payload::UpdateProperty(property)) => self.property("update_property", property),
+        }
+    }
+
+    fn property(&mut self, field: &str, property: &Property) {
+        self.uuid(&format!("{}.id", field), &property.id);
+        if DataType::try_from(property.data_type).is_err() {
+            self.push(
+                &format!("{}.data_type", field),
+                Problem::UnknownDataType(property.data_type),
+            );
+        }
+    }
+
+    fn entity(&mut self, entity: &Entity) {
+        self.uuid("update_entity.id", &entity.id);
+        for (index, value) in entity.values.iter().enumerate() {
+            self.value(&format!("update_entity.values[{}]", index), value);
+        }
+    }
+
+    fn value(&mut self, field: &str, value: &Value) {
+        self.uuid(&format!("{}.property", field), &value.property);
+
+        match value
+            .options
+            .as_ref()
+            .and_then(|options| options.value.as_ref())
+        {
+            Some(options::Value::Text(text)) => self.optional_uuid(
+                &format!("{}.options.text.language", field),
+                text.language.as_ref(),
+            ),
+            Some(options::Value::Number(number)) => self.optional_uuid(
+                &format!("{}.options.number.unit", field),
+                number.unit.as_ref(),
+            ),
+            None => {}
+        }
+    }
+
+    fn relation(&mut self, relation: &Relation) {
+        self.uuid("create_relation.id", &relation.id);
+        self.uuid("create_relation.type", &relation.r#type);
+        self.uuid("create_relation.entity", &relation.entity);
+        self.uuid("create_relation.from_entity", &relation.from_entity);
+        self.uuid("create_relation.to_entity", &relation.to_entity);
+        self.optional_uuid("create_relation.from_space", relation.from_space.as_ref());
+        self.optional_uuid(
+            "create_relation.from_version",
+            relation.from_version.as_ref(),
+        );
+        self.optional_uuid("create_relation.to_space", relation.to_space.as_ref());
+        self.optional_uuid("create_relation.to_version", relation.to_version.as_ref());
+    }
+
+    fn relation_update(&mut self, update: &RelationUpdate) {
+        self.uuid("update_relation.id", &update.id);
+        self.optional_uuid("update_relation.from_space", update.from_space.as_ref());
+        self.optional_uuid("update_relation.from_version", update.from_version.as_ref());
+        self.optional_uuid("update_relation.to_space", update.to_space.as_ref());
+        self.optional_uuid("update_relation.to_version", update.to_version.as_ref());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::{EditBuilder, relation, relation_update, value};
+    use uuid::Uuid;
+
+    fn diagnostic(op_index: Option<usize>, field: &str, problem: Problem) -> Diagnostic {
+        Diagnostic {
+            op_index,
+            field: field.to_string(),
+            problem,
+        }
+    }
+
+    #[test]
+    fn test_well_formed_edit() {
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .author(Uuid::new_v4())
+            .create_property(Uuid::new_v4(), DataType::Relation)
+            .update_entity(Uuid::new_v4(), [value(Uuid::new_v4(), "Alice")])
+            .create_relation(relation(
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+                Uuid::new_v4(),
+            ))
+            .update_relation(relation_update(Uuid::new_v4()))
+            .delete_relation(Uuid::new_v4())
+            .delete_entity(Uuid::new_v4())
+            .update_property(Uuid::new_v4(), DataType::Text)
+            .build();
+
+        assert_eq!(validate_edit(&edit), vec![]);
+    }
+
+    #[test]
+    fn test_reports_problems_per_op() {
+        let mut edit = EditBuilder::new(Uuid::new_v4())
+            .author(Uuid::new_v4())
+            .update_entity(Uuid::new_v4(), [value(Uuid::new_v4(), "Alice")])
+            .op(Op { payload: None })
+            .op(Op {
+                payload: Some(Payload::CreateProperty(Property {
+                    id: vec![1; 4],
+                    data_type: 42,
+                })),
+            })
+            .build();
+        edit.authors[0].truncate(8);
+        edit.ops[0].payload = Some(Payload::UpdateEntity(Entity {
+            id: vec![],
+            values: vec![],
+        }));
+
+        assert_eq!(
+            validate_edit(&edit),
+            vec![
+                diagnostic(None, "authors[0]", Problem::InvalidUuid(8)),
+                diagnostic(Some(0), "update_entity.id", Problem::Missing),
+                diagnostic(Some(1), "payload", Problem::MissingPayload),
+                diagnostic(Some(2), "create_property.id", Problem::InvalidUuid(4)),
+                diagnostic(
+                    Some(2),
+                    "create_property.data_type",
+                    Problem::UnknownDataType(42)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional_ids_are_checked_when_present() {
+        let edit = EditBuilder::new(Uuid::new_v4())
+            .update_relation(RelationUpdate {
+                to_space: Some(vec![1; 3]),
+                ..relation_update(Uuid::new_v4())
+            })
+            .build();
+
+        assert_eq!(
+            validate_edit(&edit),
+            vec![diagnostic(
+                Some(0),
+                "update_relation.to_space",
+                Problem::InvalidUuid(3)
+            )]
+        );
+        assert_eq!(
+            validate_edit(&edit)[0].to_string(),
+            "op 0 update_relation.to_space: expected a 16 byte UUID, got 3 bytes"
+        );
+    }
+}